@@ -0,0 +1,69 @@
+use std::vec::Vec;
+
+extern crate rand;
+
+use rand::thread_rng;
+
+#[macro_use]
+extern crate criterion;
+
+use criterion::Criterion;
+
+extern crate aeonflux;
+
+use aeonflux::issuer::Issuer;
+use aeonflux::parameters::SystemParameters;
+use aeonflux::user::CredentialRequestConstructor;
+
+extern crate curve25519_dalek;
+
+use curve25519_dalek::scalar::Scalar;
+
+mod precomputed_tables_benches {
+    use super::*;
+
+    fn issue_8_scalars(c: &mut Criterion, system_parameters: &SystemParameters, name: &str) {
+        let mut rng = thread_rng();
+        let issuer = Issuer::new(&system_parameters, &mut rng);
+        let scalars: Vec<Scalar> = (0..8).map(|_| Scalar::random(&mut rng)).collect();
+
+        c.bench_function(name, |b| {
+            b.iter(|| {
+                let mut request = CredentialRequestConstructor::new(&system_parameters);
+
+                for scalar in scalars.iter() {
+                    request.append_revealed_scalar(*scalar);
+                }
+                issuer.issue(request.finish(), &mut rng)
+            });
+        });
+    }
+
+    fn issuance_8_without_tables(c: &mut Criterion) {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 8).unwrap();
+
+        issue_8_scalars(c, &system_parameters, "Issuance with 8 scalar attributes without precomputed tables");
+    }
+
+    fn issuance_8_with_tables(c: &mut Criterion) {
+        let mut rng = thread_rng();
+        let mut system_parameters = SystemParameters::generate(&mut rng, 8).unwrap();
+
+        system_parameters.precompute_tables();
+
+        issue_8_scalars(c, &system_parameters, "Issuance with 8 scalar attributes with precomputed tables");
+    }
+
+    criterion_group! {
+        name = precomputed_tables_benches;
+        config = Criterion::default();
+        targets =
+            issuance_8_without_tables,
+            issuance_8_with_tables,
+    }
+}
+
+criterion_main!(
+    precomputed_tables_benches::precomputed_tables_benches,
+);
@@ -0,0 +1,26 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2020 The Brave Authors
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+fn main() {
+    // Generate the C header for the foreign function interface into the build directory, so that building the
+    // crate never writes into the source tree.  The header shipped in `include/` is generated separately with the
+    // `cbindgen` tool, as described in the `ffi` module.
+    #[cfg(feature = "ffi")]
+    {
+        let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+        let out_dir = std::env::var("OUT_DIR").unwrap();
+
+        println!("cargo:rerun-if-changed=src/ffi.rs");
+        println!("cargo:rerun-if-changed=cbindgen.toml");
+
+        cbindgen::generate(&crate_dir)
+            .expect("Unable to generate the C header")
+            .write_to_file(format!("{}/aeonflux.h", out_dir));
+    }
+}
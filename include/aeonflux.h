@@ -0,0 +1,176 @@
+#ifndef AEONFLUX_H
+#define AEONFLUX_H
+
+/* This file is generated by cbindgen from src/ffi.rs.  Do not edit it. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// The result of a fallible FFI call.
+typedef enum AeonfluxStatus {
+  // The call succeeded.
+  AEONFLUX_STATUS_OK = 0,
+  // A required pointer was null.
+  AEONFLUX_STATUS_NULL_POINTER = 1,
+  // Some input bytes could not be deserialised.
+  AEONFLUX_STATUS_MALFORMED = 2,
+  // Issuance failed, or a presentation did not verify.
+  AEONFLUX_STATUS_FAILURE = 3,
+} AeonfluxStatus;
+
+// An anonymous credential issuer/verifier.
+typedef struct AeonfluxIssuer AeonfluxIssuer;
+
+// The `SystemParameters` define the system-wide context in which the anonymous
+// credentials scheme and its proofs are constructed within.
+//
+// They are defined as \\( \( \mathbb{G}, q, G, G_{w}, G_{w'}, G_{x_{0}}, G_{x_{1}},
+// G_{y_{0}}, \ldots G_{y_{n}}, G_{m_{0}}, \ldots, G_{m_{n}}, G_V \) \\)
+//
+// where:
+//
+// * \\( \mathbb{G} \\) is a group with order \\( q \\), where
+//   `q` is a `k`-bit prime (`k = 255` in the case of using the Ristretto255
+//   group),
+// * `G*` generators of `\\( \mathbb{G} \\)`,
+// * `\\( \log_G(G*) \\)` is unknown, that is, all generators `G*` are chosen
+//   as a distinguished basepoint which is orthogonal to `g`.
+// * `n` is the [`NUMBER_OF_ATTRIBUTES`] in the message space.
+//
+// Additionally, for the [`symmetric`]-key verifiable encryption scheme, we
+// require three more generators chosen orthogonally,
+// \\( (G_a, G_a0, G_a1) \in \mathbb{G} \\), chosen as detailed above.
+typedef struct AeonfluxSystemParameters AeonfluxSystemParameters;
+
+// Some bytes returned to C, which must be released with
+// [`aeonflux_buffer_free`].
+typedef struct AeonfluxBuffer {
+  uint8_t *data;
+  size_t len;
+} AeonfluxBuffer;
+
+// Release a buffer returned by this library.
+//
+// # Safety
+//
+// The `buffer` must have been returned by this library, and not already
+// released.
+void aeonflux_buffer_free(struct AeonfluxBuffer buffer);
+
+// Generate new [`SystemParameters`] for credentials with
+// `number_of_attributes`.
+//
+// # Returns
+//
+// A handle to the system parameters, or null on failure.
+struct AeonfluxSystemParameters *aeonflux_system_parameters_generate(uint32_t number_of_attributes);
+
+// Deserialise [`SystemParameters`] from `len` bytes.
+//
+// # Returns
+//
+// A handle to the system parameters, or null if the bytes are malformed.
+//
+// # Safety
+//
+// The `bytes` must be valid for reads of `len` bytes.
+struct AeonfluxSystemParameters *aeonflux_system_parameters_from_bytes(const uint8_t *bytes,
+                                                                       size_t len);
+
+// Serialise the `system_parameters` into the buffer `out`.
+//
+// # Safety
+//
+// The `system_parameters` must be a live handle, and `out` valid for writes.
+enum AeonfluxStatus aeonflux_system_parameters_to_bytes(const struct AeonfluxSystemParameters *system_parameters,
+                                                        struct AeonfluxBuffer *out);
+
+// Release a [`SystemParameters`] handle.
+//
+// # Safety
+//
+// The `system_parameters` must be null, or a live handle which is not used
+// again.
+void aeonflux_system_parameters_free(struct AeonfluxSystemParameters *system_parameters);
+
+// Generate a new [`Issuer`], with a fresh key, under the
+// `system_parameters`.
+//
+// # Returns
+//
+// A handle to the issuer, or null if the `system_parameters` are null.
+//
+// # Safety
+//
+// The `system_parameters` must be null or a live handle.
+struct AeonfluxIssuer *aeonflux_issuer_generate(const struct AeonfluxSystemParameters *system_parameters);
+
+// Deserialise an [`Issuer`], including its secret key, from `len` bytes.
+//
+// # Returns
+//
+// A handle to the issuer, or null if the bytes are malformed.
+//
+// # Safety
+//
+// The `bytes` must be valid for reads of `len` bytes.
+struct AeonfluxIssuer *aeonflux_issuer_from_bytes(const uint8_t *bytes, size_t len);
+
+// Serialise the `issuer`, including its secret key, into the buffer `out`.
+//
+// # Safety
+//
+// The `issuer` must be a live handle, and `out` valid for writes.
+enum AeonfluxStatus aeonflux_issuer_to_bytes(const struct AeonfluxIssuer *issuer,
+                                             struct AeonfluxBuffer *out);
+
+// Serialise the `issuer`'s public parameters, to publish to its users, into
+// the buffer `out`.
+//
+// # Safety
+//
+// The `issuer` must be a live handle, and `out` valid for writes.
+enum AeonfluxStatus aeonflux_issuer_parameters_to_bytes(const struct AeonfluxIssuer *issuer,
+                                                        struct AeonfluxBuffer *out);
+
+// Release an [`Issuer`] handle, zeroing its secret key.
+//
+// # Safety
+//
+// The `issuer` must be null, or a live handle which is not used again.
+void aeonflux_issuer_free(struct AeonfluxIssuer *issuer);
+
+// Issue a credential for a serialised
+// [`CredentialRequest`](crate::user::CredentialRequest) of `len` bytes,
+// writing the serialised
+// [`CredentialIssuance`](crate::issuer::CredentialIssuance) into the
+// buffer `out`.
+//
+// # Safety
+//
+// The `issuer` must be a live handle, the `request` valid for reads of
+// `len` bytes, and `out` valid for writes.
+enum AeonfluxStatus aeonflux_issuer_issue(const struct AeonfluxIssuer *issuer,
+                                          const uint8_t *request,
+                                          size_t len,
+                                          struct AeonfluxBuffer *out);
+
+// Verify a serialised presentation of `len` bytes.
+//
+// # Returns
+//
+// [`AeonfluxStatus::Ok`] if the presentation is valid, otherwise
+// [`AeonfluxStatus::Failure`], or another status for malformed input.
+//
+// # Safety
+//
+// The `issuer` must be a live handle, and the `presentation` valid for
+// reads of `len` bytes.
+enum AeonfluxStatus aeonflux_issuer_verify(const struct AeonfluxIssuer *issuer,
+                                           const uint8_t *presentation,
+                                           size_t len);
+
+#endif /* AEONFLUX_H */
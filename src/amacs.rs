@@ -17,32 +17,215 @@
 //! only a vector of commitments to the AMAC.  This is the underlying primitive
 //! used for our anonymous credential scheme.
 
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::collections::BTreeMap;
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::collections::VecDeque;
 #[cfg(all(not(feature = "std"), feature = "alloc"))]
 use alloc::vec::Vec;
 #[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::collections::BTreeMap;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::collections::VecDeque;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
 use std::vec::Vec;
 
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "std")]
+use std::io::Read;
+#[cfg(feature = "std")]
+use std::io::Write;
+
+#[cfg(feature = "arbitrary")]
+use arbitrary::Arbitrary;
+#[cfg(feature = "arbitrary")]
+use arbitrary::Unstructured;
+
+use argon2::Argon2;
+
+use chacha20poly1305::aead::Aead;
+use chacha20poly1305::aead::KeyInit;
+use chacha20poly1305::aead::Payload;
+use chacha20poly1305::XChaCha20Poly1305;
+use chacha20poly1305::XNonce;
+
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_TABLE;
 use curve25519_dalek::ristretto::CompressedRistretto;
 use curve25519_dalek::ristretto::RistrettoPoint;
 use curve25519_dalek::scalar::Scalar;
 use curve25519_dalek::traits::Identity;
 use curve25519_dalek::traits::MultiscalarMul;
 
+use hmac::Hmac;
+use hmac::Mac;
+
 use rand_core::CryptoRng;
 use rand_core::RngCore;
 
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
 use serde::{self, Serialize, Deserialize, Serializer, Deserializer};
 use serde::de::Visitor;
 
+use sha2::Digest;
+use sha2::Sha512Trunc256;
+
+use subtle::Choice;
+use subtle::ConditionallySelectable;
+use subtle::ConstantTimeEq;
+
 use zeroize::Zeroize;
+use zeroize::Zeroizing;
 
+use zkp::Transcript;
+
+use crate::credential::AnonymousCredential;
+use crate::errors::CredentialError;
 use crate::errors::MacError;
+use crate::nizk::issuance::ProofOfIssuance;
+use crate::parameters::IssuerParameters;
 use crate::parameters::SystemParameters;
+use crate::parameters::MAX_NUMBER_OF_ATTRIBUTES;
+use crate::rng::CryptoRngCore;
+use crate::rng::random_nondegenerate_scalar;
 use crate::symmetric::Plaintext;
+use crate::wire;
+use crate::wire::Sink;
+use crate::wire::WireType;
 
 /// Determine the size of a [`SecretKey`], in bytes.
-pub(crate) fn sizeof_secret_key(number_of_attributes: u32) -> usize {
-    32 * (5 + number_of_attributes) as usize + 4
+///
+/// This is the length of the body of its serialised form, less the 32-byte
+/// identifier of any [`SystemParameters`] it is bound to.
+pub fn sizeof_secret_key(number_of_attributes: u32) -> usize {
+    32 * (5 + number_of_attributes as usize) + 4
+}
+
+/// The length, in bytes, of the random salt fed into the KDF for a
+/// [`SecretKey`] backup.
+const BACKUP_SALT_LENGTH: usize = 16;
+
+/// The length, in bytes, of the XChaCha20-Poly1305 nonce for a [`SecretKey`] backup.
+const BACKUP_NONCE_LENGTH: usize = 24;
+
+/// The length, in bytes, of the Poly1305 authentication tag for a [`SecretKey`] backup.
+const BACKUP_TAG_LENGTH: usize = 16;
+
+/// Domain separator bound into every [`SecretKey`] backup as associated data.
+const BACKUP_DOMAIN_SEPARATOR: &[u8] = b"aeonflux amacs::SecretKey backup v1";
+
+/// Domain separator bound into every sealed [`SecretKey`] as associated data,
+/// followed by the [`SealingKey`] mode.
+const SEAL_DOMAIN_SEPARATOR: &[u8] = b"aeonflux amacs::SecretKey sealed v1";
+
+/// The key with which a [`SecretKey`] is sealed by [`SecretKey::seal`].
+#[derive(Clone, Copy)]
+pub enum SealingKey<'a> {
+    /// A passphrase, from which a key is derived with Argon2id and a random
+    /// salt, as for [`SecretKey::to_backup`].
+    Passphrase(&'a [u8]),
+    /// A uniformly random 32-byte key, e.g. one held by a key management
+    /// service, which is used directly.
+    Key(&'a [u8; 32]),
+}
+
+impl SealingKey<'_> {
+    /// The byte identifying this kind of key in a sealed [`SecretKey`].
+    fn mode(&self) -> u8 {
+        match self {
+            SealingKey::Key(_)        => 0,
+            SealingKey::Passphrase(_) => 1,
+        }
+    }
+
+    /// The length, in bytes, of the salt stored for this kind of key.
+    fn salt_length(&self) -> usize {
+        match self {
+            SealingKey::Key(_)        => 0,
+            SealingKey::Passphrase(_) => BACKUP_SALT_LENGTH,
+        }
+    }
+
+    /// Derive the symmetric key with the `salt`.
+    fn derive(&self, salt: &[u8]) -> Result<[u8; 32], MacError> {
+        match self {
+            SealingKey::Key(key)               => Ok(**key),
+            SealingKey::Passphrase(passphrase) => derive_backup_key(passphrase, salt),
+        }
+    }
+}
+
+/// Derive a 32-byte symmetric key for a [`SecretKey`] backup from a
+/// `passphrase` and `salt` with Argon2id, using the default parameters of the
+/// `argon2` crate (19 MiB of memory, 2 iterations, 1 degree of parallelism).
+fn derive_backup_key(passphrase: &[u8], salt: &[u8]) -> Result<[u8; 32], MacError> {
+    let mut key = [0u8; 32];
+
+    match Argon2::default().hash_password_into(passphrase, salt, &mut key) {
+        Ok(_)  => Ok(key),
+        Err(_) => Err(MacError::BackupAuthenticationError),
+    }
+}
+
+/// Encrypt and authenticate a serialised secret key `plaintext`, along with
+/// the associated data `aad`, with XChaCha20-Poly1305 under a derived `key`.
+///
+/// # Returns
+///
+/// The bytes `nonce || ciphertext`, where the `ciphertext` includes the
+/// authentication tag.
+fn wrap_key_bytes<R>(key: &[u8; 32], plaintext: &[u8], aad: &[u8], csprng: &mut R) -> Vec<u8>
+where
+    R: RngCore + CryptoRng,
+{
+    let mut nonce = [0u8; BACKUP_NONCE_LENGTH];
+
+    csprng.fill_bytes(&mut nonce);
+
+    let cipher = XChaCha20Poly1305::new(key.into());
+    let payload = Payload { msg: plaintext, aad };
+
+    // Encryption only fails if the plaintext is longer than 256 GiB.
+    let ciphertext = cipher.encrypt(XNonce::from_slice(&nonce), payload).unwrap();
+
+    let mut bytes: Vec<u8> = Vec::with_capacity(BACKUP_NONCE_LENGTH + ciphertext.len());
+
+    bytes.extend(&nonce);
+    bytes.extend(ciphertext);
+    bytes
+}
+
+/// Authenticate and decrypt the `bytes` `nonce || ciphertext` created by
+/// [`wrap_key_bytes`] under the same `key` and associated data `aad`.
+///
+/// # Returns
+///
+/// An `Option` whose `Some` value is the plaintext, which the caller must
+/// zeroize, otherwise `None` if the `bytes` are too short, or were encrypted
+/// under another key or associated data.
+fn unwrap_key_bytes(key: &[u8; 32], bytes: &[u8], aad: &[u8]) -> Option<Vec<u8>> {
+    if bytes.len() < BACKUP_NONCE_LENGTH + BACKUP_TAG_LENGTH {
+        return None;
+    }
+
+    let cipher = XChaCha20Poly1305::new(key.into());
+    let payload = Payload { msg: &bytes[BACKUP_NONCE_LENGTH..], aad };
+
+    cipher.decrypt(XNonce::from_slice(&bytes[..BACKUP_NONCE_LENGTH]), payload).ok()
+}
+
+/// Decode a canonical scalar from 32 `bytes` by way of a `scratch` buffer,
+/// which is wiped before returning (whether or not decoding succeeded) so
+/// that no secret bytes linger in it when it is reused.
+fn decode_scalar(scratch: &mut Zeroizing<[u8; 32]>, bytes: &[u8]) -> Option<Scalar> {
+    scratch.copy_from_slice(bytes);
+
+    let scalar = Scalar::from_canonical_bytes(**scratch);
+
+    scratch.zeroize();
+    scalar
 }
 
 /// An AMAC secret key is \(( (w, w', x_0, x_1, \vec{y_{n}}, W ) \in \mathbb{Z}_q \))
@@ -56,6 +239,12 @@ pub struct SecretKey {
     pub(crate) x_1: Scalar,
     pub(crate) y: Vec<Scalar>,
     pub(crate) W: RistrettoPoint,
+    /// The [`SystemParameters::identifier`] of the parameters this key is
+    /// bound to, if any.
+    pub(crate) parameters_identifier: Option<[u8; 32]>,
+    /// Whether this key has been zeroized, in which case using it is a bug.
+    #[cfg(feature = "debug-poison")]
+    pub(crate) poisoned: bool,
 }
 
 // We can't derive this because generally in elliptic curve cryptography group
@@ -70,6 +259,11 @@ impl Zeroize for SecretKey {
         self.y.zeroize();
 
         self.W = RistrettoPoint::identity();
+
+        #[cfg(feature = "debug-poison")]
+        {
+            self.poisoned = true;
+        }
     }
 }
 
@@ -86,274 +280,3689 @@ impl SecretKey {
     ///
     /// The size of the secret key is linear in the size of the desired number
     /// of attributes for the anonymous credential.
+    ///
+    /// Secret scalars which are zero or one are re-sampled, since they would
+    /// make the MAC trivially forgeable.
     pub fn generate<R>(csprng: &mut R, system_parameters: &SystemParameters) -> SecretKey
     where
         R: RngCore + CryptoRng,
     {
-        let w:       Scalar = Scalar::random(csprng);
-        let w_prime: Scalar = Scalar::random(csprng);
-        let x_0:     Scalar = Scalar::random(csprng);
-        let x_1:     Scalar = Scalar::random(csprng);
+        let w:       Scalar = random_nondegenerate_scalar(csprng);
+        let w_prime: Scalar = random_nondegenerate_scalar(csprng);
+        let x_0:     Scalar = random_nondegenerate_scalar(csprng);
+        let x_1:     Scalar = random_nondegenerate_scalar(csprng);
 
         let mut y: Vec<Scalar> = Vec::with_capacity(system_parameters.NUMBER_OF_ATTRIBUTES as usize);
 
         for _ in 0..system_parameters.NUMBER_OF_ATTRIBUTES {
-            y.push(Scalar::random(csprng));
+            y.push(random_nondegenerate_scalar(csprng));
         }
 
-        let W: RistrettoPoint = &system_parameters.G_w * &w;
+        let W: RistrettoPoint = system_parameters.mul_G_w(&w);
 
-        SecretKey { w, w_prime, x_0, x_1, y, W }
+        SecretKey {
+            w, w_prime, x_0, x_1, y, W,
+            parameters_identifier: None,
+            #[cfg(feature = "debug-poison")]
+            poisoned: false,
+        }
     }
 
-    /// Serialise this AMAC secret key to a vector of bytes.
-    pub(crate) fn to_bytes(&self) -> Vec<u8> {
-        let mut bytes: Vec<u8> = Vec::with_capacity(sizeof_secret_key(self.y.len() as u32));
+    /// Panic, in debug builds with the `debug-poison` feature, if this key
+    /// has been zeroized and thus should no longer be used.
+    ///
+    /// Without the feature, this compiles to nothing.
+    #[inline(always)]
+    pub(crate) fn check_poison(&self) {
+        #[cfg(feature = "debug-poison")]
+        debug_assert!(!self.poisoned, "use of a zeroized SecretKey");
+    }
 
-        bytes.extend(&(self.y.len() as u32).to_le_bytes());
-        bytes.extend(self.w.as_bytes());
-        bytes.extend(self.w_prime.as_bytes());
-        bytes.extend(self.x_0.as_bytes());
-        bytes.extend(self.x_1.as_bytes());
+    /// Generate a secret key, as in [`SecretKey::generate`], with a
+    /// type-erased `csprng`.
+    pub fn generate_dyn(csprng: &mut dyn CryptoRngCore, system_parameters: &SystemParameters) -> SecretKey {
+        SecretKey::generate(&mut &mut *csprng, system_parameters)
+    }
 
-        for y in self.y.iter() {
-            bytes.extend(y.as_bytes());
+    /// Generate a secret key, as in [`SecretKey::generate`], which is bound
+    /// to the `system_parameters` by recording their
+    /// [`SystemParameters::identifier`].
+    ///
+    /// A bound key may still be used with [`Tag::new`] and [`Tag::verify`],
+    /// but [`Tag::new_strict`] and [`Tag::verify_strict`] will refuse to
+    /// use it with any other parameters.
+    pub fn generate_bound<R>(csprng: &mut R, system_parameters: &SystemParameters) -> SecretKey
+    where
+        R: RngCore + CryptoRng,
+    {
+        let mut secret_key = SecretKey::generate(csprng, system_parameters);
+
+        secret_key.parameters_identifier = Some(system_parameters.identifier());
+        secret_key
+    }
+
+    /// Check that this secret key is bound to the `system_parameters`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is empty, otherwise a
+    /// [`MacError::ParameterMismatch`] if the key is unbound or bound to
+    /// different parameters.
+    pub fn check_parameters(&self, system_parameters: &SystemParameters) -> Result<(), MacError> {
+        match self.parameters_identifier {
+            Some(identifier) if identifier == system_parameters.identifier() => Ok(()),
+            _ => Err(MacError::ParameterMismatch),
+        }
+    }
+
+    /// Extend this secret key to the `extended_system_parameters`, e.g. from
+    /// [`SystemParameters::extend`], by sampling a fresh \(( y_i \)) for each
+    /// additional attribute.
+    ///
+    /// Every existing secret scalar is kept, so a tag computed with this key
+    /// upon some attributes also verifies under the extended key for the same
+    /// attributes followed by zero scalars, since \(( G_m_i * 0 \)) is the
+    /// identity.  If this key is bound to its parameters, the extended key is
+    /// bound to the extended parameters.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the extended key, otherwise a
+    /// [`MacError::MessageLengthError`] if the `extended_system_parameters`
+    /// have fewer attributes than this key.
+    pub fn extend<R>(
+        &self,
+        csprng: &mut R,
+        extended_system_parameters: &SystemParameters,
+    ) -> Result<SecretKey, MacError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let number_of_attributes = extended_system_parameters.NUMBER_OF_ATTRIBUTES as usize;
+
+        self.check_poison();
+
+        if number_of_attributes < self.y.len() {
+            return Err(MacError::MessageLengthError{ length: self.y.len() });
+        }
+
+        let mut secret_key = self.clone();
+
+        while secret_key.y.len() < number_of_attributes {
+            secret_key.y.push(random_nondegenerate_scalar(csprng));
         }
+        if secret_key.parameters_identifier.is_some() {
+            secret_key.parameters_identifier = Some(extended_system_parameters.identifier());
+        }
+        Ok(secret_key)
+    }
+
+    /// Serialise this AMAC secret key in the versioned [`wire`] format,
+    /// whose body is as for `to_raw_bytes`.
+    ///
+    /// [`wire`]: crate::wire
+    pub(crate) fn to_bytes(&self) -> Vec<u8> {
+        let mut body = self.to_raw_bytes();
+        let bytes = wire::encode(WireType::SecretKey, &body);
 
-        bytes.extend(self.W.compress().as_bytes());
+        body.zeroize();
         bytes
     }
 
-    /// Attempt to deserialise this AMAC secret key from bytes.
+    /// Attempt to deserialise this AMAC secret key from bytes in either the
+    /// versioned [`wire`] format or the unversioned format of `to_raw_bytes`.
+    ///
+    /// [`wire`]: crate::wire
     pub(crate) fn from_bytes(bytes: &[u8]) -> Result<SecretKey, MacError> {
-        // We assume no one is going to create a key for less that one attributes.
-        if bytes.len() < sizeof_secret_key(1) {
-            return Err(MacError::KeypairDeserialisation);
+        match wire::decode(bytes, WireType::SecretKey)? {
+            (0, body) | (1, body) => SecretKey::from_raw_bytes(body),
+            _ => Err(MacError::UnsupportedEncoding),
         }
+    }
 
-        let mut index: usize = 0;
-        let mut chunk: [u8; 32] = [0u8; 32];
+    /// Stream this AMAC secret key to a `writer` in the versioned [`wire`]
+    /// format, without serialising it into memory first.
+    ///
+    /// # Warning
+    ///
+    /// The key is written in the clear.  To store it at rest, prefer an
+    /// encrypted backup.
+    ///
+    /// [`wire`]: crate::wire
+    #[cfg(feature = "std")]
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let length = sizeof_secret_key(self.y.len() as u32) + self.parameters_identifier.map_or(0, |_| 32);
 
-        let mut tmp = [0u8; 4];
+        wire::write(&mut writer, WireType::SecretKey, length, |sink| self.write_raw(sink))
+    }
 
-        tmp.copy_from_slice(&bytes[index..index+4]);
-        let number_of_attributes = u32::from_le_bytes(tmp); index +=  4; chunk.copy_from_slice(&bytes[index..index+32]);
-        let w       = Scalar::from_canonical_bytes(chunk)?; index += 32; chunk.copy_from_slice(&bytes[index..index+32]);
-        let w_prime = Scalar::from_canonical_bytes(chunk)?; index += 32; chunk.copy_from_slice(&bytes[index..index+32]);
-        let x_0     = Scalar::from_canonical_bytes(chunk)?; index += 32; chunk.copy_from_slice(&bytes[index..index+32]);
-        let x_1     = Scalar::from_canonical_bytes(chunk)?; index += 32; chunk.copy_from_slice(&bytes[index..index+32]);
+    /// Read an AMAC secret key written with [`SecretKey::write_to`] from a
+    /// `reader`, consuming exactly its encoding.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the secret key, otherwise an
+    /// `io::Error`, of kind `InvalidData` if it is malformed.
+    #[cfg(feature = "std")]
+    pub fn read_from<R: Read>(mut reader: R) -> io::Result<SecretKey> {
+        let mut bytes = wire::read(&mut reader, WireType::SecretKey)?;
+        let secret_key = SecretKey::from_bytes(&bytes);
 
-        let mut y: Vec<Scalar> = Vec::with_capacity(number_of_attributes as usize);
+        bytes.zeroize();
 
-        for _ in 0..number_of_attributes {
-            y.push(Scalar::from_canonical_bytes(chunk)?); index += 32;
-        }
+        secret_key.map_err(wire::invalid_data)
+    }
 
-        let W = CompressedRistretto::from_slice(&bytes[index..index+32]).decompress()?;
+    /// Serialise this AMAC secret key to a vector of bytes, without a
+    /// [`wire`] header.
+    ///
+    /// The bytes begin with the number of attributes as a little-endian
+    /// `u32`, which is always the number of \(( y_i \)).  Every constructor
+    /// of a `SecretKey` maintains the invariant that this equals the
+    /// `NUMBER_OF_ATTRIBUTES` of the [`SystemParameters`] the key was made
+    /// for, and so the serialised count is authoritative when the key is
+    /// deserialised.
+    ///
+    /// If the key is bound to some parameters, their 32-byte identifier
+    /// follows the rest of the key.
+    ///
+    /// [`wire`]: crate::wire
+    pub(crate) fn to_raw_bytes(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = Vec::with_capacity(sizeof_secret_key(self.y.len() as u32) + 32);
 
-        Ok(SecretKey{ w, w_prime, x_0, x_1, y, W })
+        self.write_raw(&mut bytes);
+        bytes
     }
-}
 
-impl_serde_with_to_bytes_and_from_bytes!(SecretKey, "A valid byte sequence representing an amacs::SecretKey");
+    /// Write this AMAC secret key to a [`Sink`], as for `to_raw_bytes`.
+    fn write_raw<S: Sink>(&self, bytes: &mut S) {
+        self.check_poison();
 
-/// Attributes may be either group elements \(( M_i \in \mathbb{G} \)) or
-/// scalars \(( m_j \in \mathbb{Z}_q \)), written as \(( M_j = G_m_j * m_j \))
-/// where \(( G_m_j \)) is taken from the [`SystemParameters`].
-///
-/// When a `Credential` is shown, its attributes may be either revealed or
-/// hidden from the credential issuer.  These represent all the valid attribute
-/// types.
-#[derive(Clone, Debug)]
-pub enum Attribute {
-    /// A scalar attribute which is revealed upon credential presentation.
-    PublicScalar(Scalar),
-    /// A scalar attribute which is hidden upon credential presentation.
-    SecretScalar(Scalar),
-    /// A group element attribute which is always revealed upon credential presentation.
-    PublicPoint(RistrettoPoint),
-    /// A group element attribute which can be hidden or revealed upon credential presentation.
-    EitherPoint(Plaintext),
-    /// A group element attribute which is hidden upon credential presentation.
-    SecretPoint(Plaintext),
-}
+        bytes.put(&(self.y.len() as u32).to_le_bytes());
+        bytes.put(self.w.as_bytes());
+        bytes.put(self.w_prime.as_bytes());
+        bytes.put(self.x_0.as_bytes());
+        bytes.put(self.x_1.as_bytes());
 
-// We can't derive this because generally in elliptic curve cryptography group
-// elements aren't used as secrets, thus curve25519-dalek doesn't impl Zeroize
-// for RistrettoPoint.
-impl Zeroize for Attribute {
-    fn zeroize(&mut self) {
-        match self {
-            Attribute::SecretScalar(x) => x.zeroize(),
-            Attribute::SecretPoint(x) => x.zeroize(),
-            _ => return,
+        for y in self.y.iter() {
+            bytes.put(y.as_bytes());
         }
-    }
-}
 
-/// Overwrite the secret attributes with zeroes (and the identity element)
-/// when it drops out of scope.
-impl Drop for Attribute {
-    fn drop(&mut self) {
-        self.zeroize();
+        bytes.put(self.W.compress().as_bytes());
+
+        if let Some(identifier) = &self.parameters_identifier {
+            bytes.put(identifier);
+        }
     }
-}
 
-/// These are the form of the attributes during credential presentation, when
-/// some may be be hidden either by commiting to them and proving them in
-/// zero-knowledge (as is the case for hidden scalar attributes) or by
-/// encrypting them and proving the ciphertext's validity in zero-knowledge (as
-/// is the case for the hidden group element attributes).
-#[derive(Clone, Debug)]
-pub enum EncryptedAttribute {
-    /// A scalar attribute which is revealed upon credential presentation.
-    PublicScalar(Scalar),
-    /// A scalar attribute which is hidden upon credential presentation.
-    SecretScalar,
-    /// A group element attribute which is revealed upon credential presentation.
-    PublicPoint(RistrettoPoint),
-    /// A group element attribute which is hidden upon credential presentation.
-    SecretPoint,
-}
+    /// Attempt to deserialise this AMAC secret key from bytes without a
+    /// [`wire`] header.
+    ///
+    /// [`wire`]: crate::wire
+    pub(crate) fn from_raw_bytes(bytes: &[u8]) -> Result<SecretKey, MacError> {
+        // We assume no one is going to create a key for less that one attributes.
+        if bytes.len() < sizeof_secret_key(1) {
+            return Err(MacError::MessageLengthError{ length: sizeof_secret_key(1) });
+        }
 
-/// Messages are computed from `Attribute`s by scalar multiplying the scalar
-/// portions by their respective generator in `SystemParameters.G_m`.
-#[derive(Debug)]
-pub(crate) struct Messages(pub(crate) Vec<RistrettoPoint>);
+        let mut index: usize = 0;
+        let mut scratch: Zeroizing<[u8; 32]> = Zeroizing::new([0u8; 32]);
 
-impl Messages {
-    pub(crate) fn from_attributes(
-        attributes: &Vec<Attribute>,
-        system_parameters: &SystemParameters
-    ) -> Messages
-    {
-        let mut messages: Vec<RistrettoPoint> = Vec::with_capacity(attributes.len());
+        let mut tmp = [0u8; 4];
 
-        for (i, attribute) in attributes.iter().enumerate() {
-            let M_i: RistrettoPoint = match attribute {
-                Attribute::PublicScalar(m) => m * system_parameters.G_m[i],
-                Attribute::SecretScalar(m) => m * system_parameters.G_m[i],
-                Attribute::PublicPoint(M)  => *M,
-                Attribute::EitherPoint(p)  => p.M1,
-                Attribute::SecretPoint(p)  => p.M1,
-            };
-            messages.push(M_i);
+        tmp.copy_from_slice(&bytes[index..index+4]);
+        let number_of_attributes = u32::from_le_bytes(tmp); index +=  4;
+
+        if number_of_attributes > MAX_NUMBER_OF_ATTRIBUTES {
+            return Err(MacError::MessageLengthError{ length: sizeof_secret_key(MAX_NUMBER_OF_ATTRIBUTES) });
         }
-        Messages(messages)
-    }
-}
 
-/// An algebraic message authentication code, \(( (t,U,V) \in \mathbb{Z}_q \times \mathbb{G} \times \mathbb{G} \)).
-#[derive(Clone, Debug)]
-pub(crate) struct Amac {
-    pub(crate) t: Scalar,
-    pub(crate) U: RistrettoPoint,
-    pub(crate) V: RistrettoPoint,
-}
+        let unbound_length = sizeof_secret_key(number_of_attributes);
 
-impl Amac {
-    /// Compute \(( V = W + (U (x_0 + x_1 t)) + \sigma{i=1}{n} M_i y_i \)).
-    fn compute_V(
-        system_parameters: &SystemParameters,
-        secret_key: &SecretKey,
-        attributes: &Vec<Attribute>,
-        t: &Scalar,
-        U: &RistrettoPoint,
-    ) -> RistrettoPoint
-    {
-        let messages: Messages = Messages::from_attributes(attributes, system_parameters);
+        if bytes.len() != unbound_length && bytes.len() != unbound_length + 32 {
+            return Err(MacError::MessageLengthError{ length: unbound_length });
+        }
+        let w       = decode_scalar(&mut scratch, &bytes[index..index+32]).ok_or(MacError::ScalarFormatError)?; index += 32;
+        let w_prime = decode_scalar(&mut scratch, &bytes[index..index+32]).ok_or(MacError::ScalarFormatError)?; index += 32;
+        let x_0     = decode_scalar(&mut scratch, &bytes[index..index+32]).ok_or(MacError::ScalarFormatError)?; index += 32;
+        let x_1     = decode_scalar(&mut scratch, &bytes[index..index+32]).ok_or(MacError::ScalarFormatError)?; index += 32;
 
-        // V = W + U * x_0 + U * x_1 * t
-        let mut V: RistrettoPoint = secret_key.W + (U * secret_key.x_0) + (U * (secret_key.x_1 * t));
+        let mut y: Vec<Scalar> = Vec::with_capacity(number_of_attributes as usize);
 
-        // V = W + U * x_0 + U * x_1 + U * t + \sigma{i=1}{n} M_i y_i
-        V += RistrettoPoint::multiscalar_mul(&secret_key.y[..], &messages.0[..]);
-        V
+        for _ in 0..number_of_attributes {
+            y.push(decode_scalar(&mut scratch, &bytes[index..index+32]).ok_or(MacError::ScalarFormatError)?); index += 32;
+        }
+
+        let W = CompressedRistretto::from_slice(&bytes[index..index+32]).decompress().ok_or(MacError::PointDecompressionError)?; index += 32;
+
+        let mut parameters_identifier: Option<[u8; 32]> = None;
+
+        if bytes.len() == unbound_length + 32 {
+            let mut identifier = [0u8; 32];
+
+            identifier.copy_from_slice(&bytes[index..index+32]);
+            parameters_identifier = Some(identifier);
+        }
+
+        Ok(SecretKey {
+            w, w_prime, x_0, x_1, y, W,
+            parameters_identifier,
+            #[cfg(feature = "debug-poison")]
+            poisoned: false,
+        })
     }
 
-    /// Compute an algebraic message authentication code with a secret key for a
-    /// vector of messages.
-    pub(crate) fn tag<R>(
-        csprng: &mut R,
+    /// Check that this AMAC secret key is internally consistent, e.g. after
+    /// loading it from storage.
+    ///
+    /// This checks that \(( W = G_w * w \)) and that there is one \(( y_i \))
+    /// per attribute, and then creates and verifies a MAC over a fixed vector
+    /// of dummy attributes.  (Note that the round trip alone cannot detect a
+    /// corrupted \(( W \)), since both tagging and verification use it.)
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is empty, otherwise a [`MacError`].
+    pub fn self_test<R>(
+        &self,
         system_parameters: &SystemParameters,
-        secret_key: &SecretKey,
-        attributes: &Vec<Attribute>,
-    ) -> Result<Amac, MacError>
+        csprng: &mut R,
+    ) -> Result<(), MacError>
     where
         R: RngCore + CryptoRng,
     {
-        if attributes.len() != system_parameters.NUMBER_OF_ATTRIBUTES as usize {
+        if self.y.len() != system_parameters.NUMBER_OF_ATTRIBUTES as usize {
             return Err(MacError::MessageLengthError{length: system_parameters.NUMBER_OF_ATTRIBUTES as usize});
         }
+        if self.W != system_parameters.mul_G_w(&self.w) {
+            return Err(MacError::SelfTestFailure);
+        }
 
-        let t: Scalar = Scalar::random(csprng);
-        let U: RistrettoPoint = RistrettoPoint::random(csprng);
-        let V: RistrettoPoint = Amac::compute_V(system_parameters, secret_key, attributes, &t, &U);
-
-        Ok(Amac { t, U, V })
-    }
+        let mut attributes: Vec<Attribute> = Vec::with_capacity(self.y.len());
 
-    /// Verify this algebraic MAC w.r.t. a secret key and vector of messages.
-    #[allow(unused)] // We never actually call this function as the AMAC is verified indirectly in a NIZK.
-    pub(crate) fn verify(
-        &self,
-        system_parameters: &SystemParameters,
-        secret_key: &SecretKey,
-        attributes: &Vec<Attribute>,
-    ) -> Result<(), MacError> {
-        if attributes.len() != system_parameters.NUMBER_OF_ATTRIBUTES as usize {
-            return Err(MacError::MessageLengthError{length: system_parameters.NUMBER_OF_ATTRIBUTES as usize});
+        for i in 0..self.y.len() {
+            attributes.push(Attribute::PublicScalar(Scalar::from(i as u64 + 1)));
         }
 
-        let V_prime = Amac::compute_V(system_parameters, secret_key, attributes, &self.t, &self.U);
+        let amac = Amac::tag(csprng, system_parameters, self, &attributes)?;
 
-        if self.V == V_prime {
-            return Ok(());
+        amac.verify(system_parameters, self, &attributes).or(Err(MacError::SelfTestFailure))
+    }
+
+    /// Check that this AMAC secret key survives serialisation, i.e. that
+    /// deserialising the output of `to_bytes` yields a key which serialises
+    /// to exactly the same bytes, e.g. before persisting a new key.
+    ///
+    /// The comparison is in constant time, and every intermediate
+    /// serialisation is zeroized.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is empty, otherwise a
+    /// [`MacError::SelfTestFailure`] if the keys differ, or a [`MacError`]
+    /// if the serialised key could not be deserialised at all.
+    pub fn validate_roundtrip(&self) -> Result<(), MacError> {
+        let bytes: Zeroizing<Vec<u8>> = Zeroizing::new(self.to_bytes());
+        let decoded: SecretKey = SecretKey::from_bytes(&bytes)?;
+        let reencoded: Zeroizing<Vec<u8>> = Zeroizing::new(decoded.to_bytes());
+
+        if bytes.len() != reencoded.len() || !bool::from(bytes.ct_eq(&reencoded[..])) {
+            return Err(MacError::SelfTestFailure);
+        }
+        Ok(())
+    }
+
+    /// Create an encrypted and authenticated backup of this AMAC secret key.
+    ///
+    /// A 32-byte key is derived from the `passphrase` and a random salt with
+    /// Argon2id, which is then used to encrypt the serialised secret key with
+    /// XChaCha20-Poly1305.
+    ///
+    /// # Returns
+    ///
+    /// The bytes `salt || nonce || ciphertext`, where the `ciphertext`
+    /// includes the authentication tag.
+    pub fn to_backup<R>(&self, passphrase: &[u8], csprng: &mut R) -> Vec<u8>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let mut salt = [0u8; BACKUP_SALT_LENGTH];
+
+        csprng.fill_bytes(&mut salt);
+
+        // The Argon2 defaults accept any passphrase and a 16-byte salt, so this can't fail.
+        let mut key = derive_backup_key(passphrase, &salt).unwrap();
+        let mut plaintext = self.to_raw_bytes();
+        let wrapped = wrap_key_bytes(&key, &plaintext[..], BACKUP_DOMAIN_SEPARATOR, csprng);
+
+        key.zeroize();
+        plaintext.zeroize();
+
+        let mut bytes: Vec<u8> = Vec::with_capacity(BACKUP_SALT_LENGTH + wrapped.len());
+
+        bytes.extend(&salt);
+        bytes.extend(wrapped);
+        bytes
+    }
+
+    /// Authenticate, decrypt, and deserialise an AMAC secret key backup
+    /// created with [`SecretKey::to_backup`].
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the restored `SecretKey`, otherwise a
+    /// [`CredentialError::BackupAuthenticationError`] if the passphrase is
+    /// wrong or the backup was tampered with.
+    pub fn from_backup(bytes: &[u8], passphrase: &[u8]) -> Result<SecretKey, CredentialError> {
+        if bytes.len() < BACKUP_SALT_LENGTH + BACKUP_NONCE_LENGTH + BACKUP_TAG_LENGTH {
+            return Err(CredentialError::BackupAuthenticationError);
+        }
+
+        let mut key = derive_backup_key(passphrase, &bytes[..BACKUP_SALT_LENGTH])?;
+        let unwrapped = unwrap_key_bytes(&key, &bytes[BACKUP_SALT_LENGTH..], BACKUP_DOMAIN_SEPARATOR);
+
+        key.zeroize();
+
+        let mut plaintext = match unwrapped {
+            Some(x) => x,
+            None    => return Err(CredentialError::BackupAuthenticationError),
+        };
+        let secret_key = SecretKey::from_raw_bytes(&plaintext[..]);
+
+        plaintext.zeroize();
+        Ok(secret_key?)
+    }
+
+    /// Seal this AMAC secret key for storage at rest, encrypting and
+    /// authenticating its versioned [`wire`] encoding with
+    /// XChaCha20-Poly1305 under the `key`, so that the raw key bytes are
+    /// never persisted.
+    ///
+    /// # Returns
+    ///
+    /// The bytes `mode || salt || nonce || ciphertext`, where the `mode` is
+    /// `0` for a [`SealingKey::Key`], for which the `salt` is empty, or `1`
+    /// for a [`SealingKey::Passphrase`], and the `ciphertext` includes the
+    /// authentication tag.
+    ///
+    /// [`wire`]: crate::wire
+    pub fn seal<R>(&self, key: SealingKey, csprng: &mut R) -> Vec<u8>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let mode = key.mode();
+        let mut salt = vec![0u8; key.salt_length()];
+
+        csprng.fill_bytes(&mut salt);
+
+        // The Argon2 defaults accept any passphrase and a 16-byte salt, so this can't fail.
+        let mut symmetric_key = key.derive(&salt).unwrap();
+        let mut plaintext = self.to_bytes();
+        let mut aad: Vec<u8> = SEAL_DOMAIN_SEPARATOR.to_vec();
+
+        aad.push(mode);
+
+        let wrapped = wrap_key_bytes(&symmetric_key, &plaintext[..], &aad[..], csprng);
+
+        symmetric_key.zeroize();
+        plaintext.zeroize();
+
+        let mut bytes: Vec<u8> = Vec::with_capacity(1 + salt.len() + wrapped.len());
+
+        bytes.push(mode);
+        bytes.extend(&salt);
+        bytes.extend(wrapped);
+        bytes
+    }
+
+    /// Authenticate, decrypt, and deserialise an AMAC secret key sealed with
+    /// [`SecretKey::seal`] under the same kind of `key`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the unsealed `SecretKey`, otherwise a
+    /// [`MacError::BackupAuthenticationError`] if the key is wrong or the
+    /// sealed key was tampered with.
+    pub fn unseal(bytes: &[u8], key: SealingKey) -> Result<SecretKey, MacError> {
+        let salt_length = key.salt_length();
+
+        if bytes.len() < 1 + salt_length + BACKUP_NONCE_LENGTH + BACKUP_TAG_LENGTH || bytes[0] != key.mode() {
+            return Err(MacError::BackupAuthenticationError);
+        }
+
+        let mut symmetric_key = key.derive(&bytes[1..1+salt_length])?;
+        let mut aad: Vec<u8> = SEAL_DOMAIN_SEPARATOR.to_vec();
+
+        aad.push(bytes[0]);
+
+        let unwrapped = unwrap_key_bytes(&symmetric_key, &bytes[1+salt_length..], &aad[..]);
+
+        symmetric_key.zeroize();
+
+        let mut plaintext = match unwrapped {
+            Some(x) => x,
+            None    => return Err(MacError::BackupAuthenticationError),
+        };
+        let secret_key = SecretKey::from_bytes(&plaintext[..]);
+
+        plaintext.zeroize();
+        secret_key
+    }
+}
+
+impl_serde_with_to_bytes_and_from_bytes!(SecretKey, "A valid byte sequence representing an amacs::SecretKey");
+
+/// One share of a [`SecretKey`] which has been split with
+/// [`SecretKey::split`].
+///
+/// Each secret scalar of the key is Shamir secret-shared over
+/// \(( \mathbb{Z}_q \)), and this share holds the evaluation of each of
+/// their polynomials at `index`.
+#[derive(Clone, Debug)]
+pub struct KeyShare {
+    /// The point, from \(( 1 \)) to the number of shares, at which the
+    /// polynomials were evaluated.
+    pub index: u8,
+    /// The number of shares required to reconstruct the key.
+    pub threshold: u8,
+    pub(crate) w: Scalar,
+    pub(crate) w_prime: Scalar,
+    pub(crate) x_0: Scalar,
+    pub(crate) x_1: Scalar,
+    pub(crate) y: Vec<Scalar>,
+}
+
+impl Zeroize for KeyShare {
+    fn zeroize(&mut self) {
+        self.w.zeroize();
+        self.w_prime.zeroize();
+        self.x_0.zeroize();
+        self.x_1.zeroize();
+        self.y.zeroize();
+    }
+}
+
+/// Overwrite the share with zeroes when it drops out of scope.
+impl Drop for KeyShare {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+/// Shamir secret-share a `secret` into `shares` evaluations of a random
+/// polynomial of degree `threshold - 1` at \(( 1, \ldots, shares \)).
+fn share_scalar<R>(secret: &Scalar, threshold: u8, shares: u8, csprng: &mut R) -> Vec<Scalar>
+where
+    R: RngCore + CryptoRng,
+{
+    let mut coefficients: Vec<Scalar> = Vec::with_capacity(threshold as usize);
+
+    coefficients.push(*secret);
+
+    for _ in 1..threshold {
+        coefficients.push(Scalar::random(csprng));
+    }
+
+    let evaluations = (1..=shares).map(|index| {
+        let x = Scalar::from(index as u64);
+
+        // Horner's method.
+        coefficients.iter().rev().fold(Scalar::zero(), |acc, c| acc * x + c)
+    }).collect();
+
+    coefficients.zeroize();
+    evaluations
+}
+
+/// Compute the Lagrange coefficient at zero for the share at `index`, among
+/// the shares at the distinct `indices`.
+pub(crate) fn lagrange_coefficient(index: u8, indices: &[u8]) -> Scalar {
+    let x_i = Scalar::from(index as u64);
+    let mut numerator = Scalar::one();
+    let mut denominator = Scalar::one();
+
+    for x_j in indices.iter().filter(|x_j| **x_j != index).map(|x_j| Scalar::from(*x_j as u64)) {
+        numerator *= x_j;
+        denominator *= x_j - x_i;
+    }
+    numerator * denominator.invert()
+}
+
+/// Interpolate at zero the polynomial whose evaluation at each share's
+/// `index` is given by `value`.
+fn interpolate_at_zero<F>(shares: &[KeyShare], value: F) -> Scalar
+where
+    F: Fn(&KeyShare) -> Scalar,
+{
+    let indices: Vec<u8> = shares.iter().map(|share| share.index).collect();
+    let mut secret = Scalar::zero();
+
+    for share_i in shares.iter() {
+        secret += value(share_i) * lagrange_coefficient(share_i.index, &indices);
+    }
+    secret
+}
+
+impl KeyShare {
+    /// Treat this share as a secret key in its own right, whose MACs and
+    /// public parameters are shares of those of the whole key, since both
+    /// are linear in the key.
+    pub(crate) fn to_secret_key(&self, system_parameters: &SystemParameters) -> SecretKey {
+        SecretKey {
+            w: self.w,
+            w_prime: self.w_prime,
+            x_0: self.x_0,
+            x_1: self.x_1,
+            y: self.y.clone(),
+            W: system_parameters.mul_G_w(&self.w),
+            parameters_identifier: None,
+            #[cfg(feature = "debug-poison")]
+            poisoned: false,
+        }
+    }
+}
+
+impl SecretKey {
+    /// Split this secret key into `shares` [`KeyShare`]s, any `threshold` of
+    /// which suffice to [`SecretKey::reconstruct`] it, while fewer reveal
+    /// nothing about it.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the shares, otherwise a
+    /// [`MacError::KeyShareError`] if the `threshold` is zero or greater than
+    /// the number of `shares`.
+    pub fn split<R>(
+        &self,
+        threshold: u8,
+        shares: u8,
+        csprng: &mut R,
+    ) -> Result<Vec<KeyShare>, MacError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        self.check_poison();
+
+        if threshold == 0 || threshold > shares {
+            return Err(MacError::KeyShareError);
+        }
+
+        let mut w = share_scalar(&self.w, threshold, shares, csprng);
+        let mut w_prime = share_scalar(&self.w_prime, threshold, shares, csprng);
+        let mut x_0 = share_scalar(&self.x_0, threshold, shares, csprng);
+        let mut x_1 = share_scalar(&self.x_1, threshold, shares, csprng);
+        let mut y: Vec<Vec<Scalar>> = self.y.iter().map(|y_i| share_scalar(y_i, threshold, shares, csprng)).collect();
+
+        let key_shares = (0..shares as usize).map(|i| KeyShare {
+            index: i as u8 + 1,
+            threshold,
+            w: w[i],
+            w_prime: w_prime[i],
+            x_0: x_0[i],
+            x_1: x_1[i],
+            y: y.iter().map(|y_i| y_i[i]).collect(),
+        }).collect();
+
+        w.zeroize();
+        w_prime.zeroize();
+        x_0.zeroize();
+        x_1.zeroize();
+        y.iter_mut().for_each(|y_i| y_i.zeroize());
+
+        Ok(key_shares)
+    }
+
+    /// Reconstruct a secret key from at least a threshold of the
+    /// [`KeyShare`]s produced by [`SecretKey::split`].
+    ///
+    /// # Warning
+    ///
+    /// The whole secret key is recombined in memory, so this should only be
+    /// done within a secure enclave or similarly protected environment.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the secret key, otherwise a
+    /// [`MacError::KeyShareError`] if there are fewer shares than their
+    /// threshold, or the shares are duplicated or inconsistent with each other
+    /// or with the `system_parameters`.
+    pub fn reconstruct(
+        shares: &[KeyShare],
+        system_parameters: &SystemParameters,
+    ) -> Result<SecretKey, MacError>
+    {
+        if shares.is_empty() || shares.len() < shares[0].threshold as usize {
+            return Err(MacError::KeyShareError);
+        }
+
+        for (i, share) in shares.iter().enumerate() {
+            if share.index == 0 ||
+               share.threshold != shares[0].threshold ||
+               share.y.len() != system_parameters.NUMBER_OF_ATTRIBUTES as usize ||
+               shares[..i].iter().any(|other| other.index == share.index)
+            {
+                return Err(MacError::KeyShareError);
+            }
+        }
+
+        let w       = interpolate_at_zero(shares, |share| share.w);
+        let w_prime = interpolate_at_zero(shares, |share| share.w_prime);
+        let x_0     = interpolate_at_zero(shares, |share| share.x_0);
+        let x_1     = interpolate_at_zero(shares, |share| share.x_1);
+        let y       = (0..system_parameters.NUMBER_OF_ATTRIBUTES as usize)
+            .map(|i| interpolate_at_zero(shares, |share| share.y[i]))
+            .collect();
+
+        let W: RistrettoPoint = system_parameters.mul_G_w(&w);
+
+        Ok(SecretKey {
+            w, w_prime, x_0, x_1, y, W,
+            parameters_identifier: None,
+            #[cfg(feature = "debug-poison")]
+            poisoned: false,
+        })
+    }
+}
+
+/// Sample an arbitrary scalar by reducing 64 bytes of fuzzer input.
+#[cfg(feature = "arbitrary")]
+pub(crate) fn arbitrary_scalar(u: &mut Unstructured) -> arbitrary::Result<Scalar> {
+    let bytes: [u8; 64] = u.arbitrary()?;
+
+    Ok(Scalar::from_bytes_mod_order_wide(&bytes))
+}
+
+/// Sample an arbitrary group element by mapping 64 bytes of fuzzer input to
+/// the group.
+#[cfg(feature = "arbitrary")]
+pub(crate) fn arbitrary_point(u: &mut Unstructured) -> arbitrary::Result<RistrettoPoint> {
+    let bytes: [u8; 64] = u.arbitrary()?;
+
+    Ok(RistrettoPoint::from_uniform_bytes(&bytes))
+}
+
+#[cfg(feature = "arbitrary")]
+impl SecretKey {
+    /// Generate an arbitrary secret key for some `system_parameters` from
+    /// fuzzer input.
+    ///
+    /// The key has one \(( y_i \)) per attribute and \(( W = G_w * w \)),
+    /// but unlike [`SecretKey::generate`] its scalars are not re-sampled if
+    /// they are degenerate.
+    pub fn arbitrary_for(
+        u: &mut Unstructured,
+        system_parameters: &SystemParameters,
+    ) -> arbitrary::Result<SecretKey>
+    {
+        let w:       Scalar = arbitrary_scalar(u)?;
+        let w_prime: Scalar = arbitrary_scalar(u)?;
+        let x_0:     Scalar = arbitrary_scalar(u)?;
+        let x_1:     Scalar = arbitrary_scalar(u)?;
+
+        let mut y: Vec<Scalar> = Vec::with_capacity(system_parameters.NUMBER_OF_ATTRIBUTES as usize);
+
+        for _ in 0..system_parameters.NUMBER_OF_ATTRIBUTES {
+            y.push(arbitrary_scalar(u)?);
+        }
+
+        let W: RistrettoPoint = system_parameters.mul_G_w(&w);
+
+        Ok(SecretKey {
+            w, w_prime, x_0, x_1, y, W,
+            parameters_identifier: None,
+            #[cfg(feature = "debug-poison")]
+            poisoned: false,
+        })
+    }
+}
+
+/// Generate an arbitrary secret key for some arbitrary [`SystemParameters`].
+/// Use [`SecretKey::arbitrary_for`] to generate a key for known parameters.
+#[cfg(feature = "arbitrary")]
+impl<'a> Arbitrary<'a> for SecretKey {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<SecretKey> {
+        let system_parameters = SystemParameters::arbitrary(u)?;
+
+        SecretKey::arbitrary_for(u, &system_parameters)
+    }
+}
+
+/// The operations of an issuer which require its secret key, so that the key
+/// may be kept outside of this process, e.g. in an HSM or a secure enclave.
+///
+/// Only the secret key operations go through the signer: the nonces
+/// \(( (t, U) \)) and the messages \(( M_i \)) of the attributes are
+/// computed locally.  [`SecretKey`] is the implementation for keys resident
+/// in memory, and an enclave holding a [`SecretKey`] may implement
+/// [`MacSigner::prove_issuance`] by returning the bytes of its proof, to be
+/// parsed with [`ProofOfIssuance::from_bytes`].
+pub trait MacSigner {
+    /// Compute \(( V = W + (U (x_0 + x_1 t)) + \sigma{i=1}{n} M_i y_i \))
+    /// upon the `messages` \(( M_i \)).
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is \(( V \)), otherwise a
+    /// [`MacError::MessageLengthError`] if there are the wrong number of
+    /// `messages`, or a [`MacError::SignerFailure`].
+    fn compute_V(
+        &self,
+        messages: &[RistrettoPoint],
+        t: &Scalar,
+        U: &RistrettoPoint,
+    ) -> Result<RistrettoPoint, MacError>;
+
+    /// Prove that the tag of a `credential` was computed with this key,
+    /// whose public parameters are the `issuer_parameters`, binding the
+    /// proof to the `transcript`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the proof, otherwise a
+    /// [`MacError::SignerFailure`].
+    fn prove_issuance(
+        &self,
+        system_parameters: &SystemParameters,
+        issuer_parameters: &IssuerParameters,
+        credential: &AnonymousCredential,
+        transcript: &mut Transcript,
+    ) -> Result<ProofOfIssuance, MacError>;
+}
+
+impl MacSigner for SecretKey {
+    fn compute_V(
+        &self,
+        messages: &[RistrettoPoint],
+        t: &Scalar,
+        U: &RistrettoPoint,
+    ) -> Result<RistrettoPoint, MacError>
+    {
+        self.check_poison();
+
+        if messages.len() != self.y.len() {
+            return Err(MacError::MessageLengthError{length: self.y.len()});
+        }
+
+        // V = W + U * x_0 + U * x_1 * t + \sigma{i=1}{n} M_i y_i
+        Ok(self.W + (U * self.x_0) + (U * (self.x_1 * t)) + RistrettoPoint::multiscalar_mul(&self.y[..], messages))
+    }
+
+    fn prove_issuance(
+        &self,
+        system_parameters: &SystemParameters,
+        issuer_parameters: &IssuerParameters,
+        credential: &AnonymousCredential,
+        transcript: &mut Transcript,
+    ) -> Result<ProofOfIssuance, MacError>
+    {
+        Ok(ProofOfIssuance::prove_with_key(system_parameters, issuer_parameters, self, credential, transcript))
+    }
+}
+
+/// Attributes may be either group elements \(( M_i \in \mathbb{G} \)) or
+/// scalars \(( m_j \in \mathbb{Z}_q \)), written as \(( M_j = G_m_j * m_j \))
+/// where \(( G_m_j \)) is taken from the [`SystemParameters`].
+///
+/// When a `Credential` is shown, its attributes may be either revealed or
+/// hidden from the credential issuer.  These represent all the valid attribute
+/// types.
+#[derive(Clone, Debug)]
+pub enum Attribute {
+    /// A scalar attribute which is revealed upon credential presentation.
+    PublicScalar(Scalar),
+    /// A scalar attribute which is hidden upon credential presentation.
+    SecretScalar(Scalar),
+    /// A group element attribute which is always revealed upon credential presentation.
+    PublicPoint(RistrettoPoint),
+    /// A group element attribute which can be hidden or revealed upon credential presentation.
+    EitherPoint(Plaintext),
+    /// A group element attribute which is hidden upon credential presentation.
+    SecretPoint(Plaintext),
+}
+
+// We can't derive this because generally in elliptic curve cryptography group
+// elements aren't used as secrets, thus curve25519-dalek doesn't impl Zeroize
+// for RistrettoPoint.
+impl Zeroize for Attribute {
+    fn zeroize(&mut self) {
+        match self {
+            Attribute::SecretScalar(x) => x.zeroize(),
+            Attribute::SecretPoint(x) => x.zeroize(),
+            _ => return,
+        }
+    }
+}
+
+/// Overwrite the secret attributes with zeroes (and the identity element)
+/// when it drops out of scope.
+impl Drop for Attribute {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl Attribute {
+    /// Create an expiry attribute for a credential which expires at the end
+    /// of the given `epoch`.
+    ///
+    /// This is an [`Attribute::PublicScalar`], which may be hidden upon
+    /// presentation and proven unexpired in zero-knowledge with
+    /// [`PresentationOptions::not_expired`](crate::credential::PresentationOptions::not_expired).
+    pub fn expiry(epoch: u64) -> Attribute {
+        Attribute::PublicScalar(Scalar::from(epoch))
+    }
+
+    /// Create a schema version attribute, recording the `version` of the
+    /// credential schema, i.e. the meaning of each attribute, under which a
+    /// credential was issued.
+    ///
+    /// A credential which was issued under an older, smaller schema and
+    /// later padded with [`AnonymousCredential::extend`](crate::credential::AnonymousCredential::extend)
+    /// keeps its version, so that verifiers can tell its padding apart from
+    /// attributes which were issued as zero.
+    pub fn schema_version(version: u32) -> Attribute {
+        Attribute::PublicScalar(Scalar::from(version))
+    }
+
+    /// Convert this attribute into its revealed form.
+    ///
+    /// Hidden scalars become [`Attribute::PublicScalar`]s with the same
+    /// underlying scalar, and group element attributes which carry a
+    /// [`Plaintext`] become [`Attribute::PublicPoint`]s of the encoded point.
+    /// Attributes which are already revealed are returned unchanged.
+    pub fn reveal(self) -> Attribute {
+        match &self {
+            Attribute::SecretScalar(m) => Attribute::PublicScalar(*m),
+            Attribute::EitherPoint(p)  => Attribute::PublicPoint(p.M1),
+            Attribute::SecretPoint(p)  => Attribute::PublicPoint(p.M1),
+            _ => self,
+        }
+    }
+
+    /// Convert this attribute into its hidden form.
+    ///
+    /// Revealed scalars become [`Attribute::SecretScalar`]s with the same
+    /// underlying scalar, and [`Attribute::EitherPoint`]s become
+    /// [`Attribute::SecretPoint`]s.  Attributes which are already hidden are
+    /// returned unchanged.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the hidden attribute, otherwise
+    /// [`MacError::UnhideableAttribute`] if this is an
+    /// [`Attribute::PublicPoint`], since its plaintext is unknown and thus it
+    /// cannot be encrypted.
+    pub fn hide(self) -> Result<Attribute, MacError> {
+        match &self {
+            Attribute::PublicScalar(m) => Ok(Attribute::SecretScalar(*m)),
+            Attribute::EitherPoint(p)  => Ok(Attribute::SecretPoint(p.clone())),
+            Attribute::PublicPoint(_)  => Err(MacError::UnhideableAttribute),
+            _ => Ok(self),
+        }
+    }
+
+    /// Present this attribute either revealed or hidden, in constant time.
+    ///
+    /// Both the revealed and the hidden [`EncryptedAttribute`] are computed,
+    /// and one is selected according to `reveal` without branching upon it,
+    /// so that the time taken does not depend on the disclosure decision.
+    /// [`Attribute::PublicPoint`]s cannot be hidden and are always revealed.
+    ///
+    /// # Note
+    ///
+    /// This only hides the *timing* of the selection.  The resulting
+    /// `EncryptedAttribute`, and thus its serialised form sent to the
+    /// verifier, necessarily reveals which attributes were disclosed.
+    ///
+    /// # Inputs
+    ///
+    /// * `reveal` is a `Choice` which is `1` if the attribute should be
+    ///   revealed and `0` if it should be hidden.
+    pub fn conditionally_reveal(&self, reveal: Choice) -> EncryptedAttribute {
+        let (revealed, hidden) = match self {
+            Attribute::PublicScalar(m) |
+            Attribute::SecretScalar(m) => (EncryptedAttribute::PublicScalar(*m), EncryptedAttribute::SecretScalar),
+            Attribute::PublicPoint(M)  => (EncryptedAttribute::PublicPoint(*M), EncryptedAttribute::PublicPoint(*M)),
+            Attribute::EitherPoint(p) |
+            Attribute::SecretPoint(p)  => (EncryptedAttribute::PublicPoint(p.M1), EncryptedAttribute::SecretPoint),
+        };
+
+        EncryptedAttribute::conditional_select(&hidden, &revealed, reveal)
+    }
+}
+
+/// The number of attribute slots consumed by a byte string of `length` bytes
+/// encoded with [`Attribute::public_string`] or [`Attribute::secret_string`].
+///
+/// Each slot holds a [`Plaintext`] of thirty bytes, and the string is
+/// prefixed with its length as a little-endian `u32`.
+pub fn sizeof_string_attribute(length: usize) -> usize {
+    (4 + length + 29) / 30
+}
+
+/// Length-prefix a byte `string` and chunk it into [`Plaintext`]s.
+fn string_to_plaintexts(string: &[u8]) -> Vec<Plaintext> {
+    let mut bytes: Vec<u8> = Vec::with_capacity(4 + string.len());
+
+    bytes.extend(&(string.len() as u32).to_le_bytes());
+    bytes.extend(string);
+
+    let plaintexts = Plaintext::from_slice(&bytes[..]);
+
+    bytes.zeroize();
+    plaintexts
+}
+
+impl Attribute {
+    /// Encode an arbitrary-length byte `string` into as many revealable
+    /// group element attributes as are needed to hold it, i.e.
+    /// [`sizeof_string_attribute`] of them.
+    ///
+    /// Each of the returned attributes is an [`Attribute::EitherPoint`], and
+    /// they must occupy consecutive slots of the credential.
+    ///
+    /// # Panics
+    ///
+    /// If the `string` is longer than `u32::MAX` bytes.
+    pub fn public_string(string: &[u8]) -> Vec<Attribute> {
+        assert!(string.len() <= u32::MAX as usize);
+
+        string_to_plaintexts(string).into_iter().map(Attribute::EitherPoint).collect()
+    }
+
+    /// Encode an arbitrary-length byte `string` into as many hidden group
+    /// element attributes as are needed to hold it, as in
+    /// [`Attribute::public_string`], except that each of the returned
+    /// attributes is an [`Attribute::SecretPoint`].
+    ///
+    /// # Panics
+    ///
+    /// If the `string` is longer than `u32::MAX` bytes.
+    pub fn secret_string(string: &[u8]) -> Vec<Attribute> {
+        assert!(string.len() <= u32::MAX as usize);
+
+        string_to_plaintexts(string).into_iter().map(Attribute::SecretPoint).collect()
+    }
+
+    /// Decode a byte string from the consecutive `attributes` produced by
+    /// [`Attribute::public_string`] or [`Attribute::secret_string`].
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the string, which is zeroized when
+    /// dropped, otherwise a [`MacError::AttributeSlotMismatch`] if any of the
+    /// attributes doesn't carry a [`Plaintext`], or a
+    /// [`MacError::MessageLengthError`] if the number of attributes doesn't
+    /// match the encoded length.
+    pub fn string_from_attributes(attributes: &[Attribute]) -> Result<Zeroizing<Vec<u8>>, MacError> {
+        let mut bytes: Zeroizing<Vec<u8>> = Zeroizing::new(Vec::with_capacity(30 * attributes.len()));
+
+        for (i, attribute) in attributes.iter().enumerate() {
+            match attribute {
+                Attribute::EitherPoint(p) |
+                Attribute::SecretPoint(p) => bytes.extend(&<[u8; 30]>::from(p)),
+                _ => return Err(MacError::AttributeSlotMismatch{ index: i }),
+            }
+        }
+
+        if bytes.len() < 4 {
+            return Err(MacError::MessageLengthError{ length: 1 });
+        }
+
+        let mut tmp = [0u8; 4];
+
+        tmp.copy_from_slice(&bytes[..4]);
+
+        let length = u32::from_le_bytes(tmp) as usize;
+
+        if sizeof_string_attribute(length) != attributes.len() {
+            return Err(MacError::MessageLengthError{ length: sizeof_string_attribute(length) });
+        }
+
+        Ok(Zeroizing::new(bytes[4..4 + length].to_vec()))
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> Arbitrary<'a> for Attribute {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Attribute> {
+        Ok(match u.int_in_range(0..=4u8)? {
+            0 => Attribute::PublicScalar(arbitrary_scalar(u)?),
+            1 => Attribute::SecretScalar(arbitrary_scalar(u)?),
+            2 => Attribute::PublicPoint(arbitrary_point(u)?),
+            3 => Attribute::EitherPoint((&u.arbitrary::<[u8; 30]>()?).into()),
+            _ => Attribute::SecretPoint((&u.arbitrary::<[u8; 30]>()?).into()),
+        })
+    }
+}
+
+/// These are the form of the attributes during credential presentation, when
+/// some may be be hidden either by commiting to them and proving them in
+/// zero-knowledge (as is the case for hidden scalar attributes) or by
+/// encrypting them and proving the ciphertext's validity in zero-knowledge (as
+/// is the case for the hidden group element attributes).
+#[derive(Clone, Copy, Debug)]
+pub enum EncryptedAttribute {
+    /// A scalar attribute which is revealed upon credential presentation.
+    PublicScalar(Scalar),
+    /// A scalar attribute which is hidden upon credential presentation.
+    SecretScalar,
+    /// A group element attribute which is revealed upon credential presentation.
+    PublicPoint(RistrettoPoint),
+    /// A group element attribute which is hidden upon credential presentation.
+    SecretPoint,
+}
+
+impl EncryptedAttribute {
+    /// The [`AttributeKind`] of this presented attribute.
+    pub fn kind(&self) -> AttributeKind {
+        match self {
+            EncryptedAttribute::PublicScalar(_) => AttributeKind::PublicScalar,
+            EncryptedAttribute::SecretScalar    => AttributeKind::SecretScalar,
+            EncryptedAttribute::PublicPoint(_)  => AttributeKind::PublicPoint,
+            EncryptedAttribute::SecretPoint     => AttributeKind::SecretPoint,
+        }
+    }
+
+    /// Determine whether this is a revealed scalar attribute whose value is
+    /// one of the `allowed` scalars, e.g. a permitted tier or role.
+    ///
+    /// The value is compared against every allowed scalar in constant time.
+    pub fn public_scalar_in(&self, allowed: &[Scalar]) -> bool {
+        match self {
+            EncryptedAttribute::PublicScalar(m) => {
+                allowed.iter().fold(Choice::from(0), |found, x| found | m.ct_eq(x)).into()
+            },
+            _ => false,
+        }
+    }
+
+    /// Split this attribute into a discriminant and its (possibly default)
+    /// scalar and point payloads, for selecting between attributes in
+    /// constant time.
+    fn to_parts(self) -> (u8, Scalar, RistrettoPoint) {
+        match self {
+            EncryptedAttribute::PublicScalar(m) => (0, m, RistrettoPoint::identity()),
+            EncryptedAttribute::SecretScalar    => (1, Scalar::zero(), RistrettoPoint::identity()),
+            EncryptedAttribute::PublicPoint(M)  => (2, Scalar::zero(), M),
+            EncryptedAttribute::SecretPoint     => (3, Scalar::zero(), RistrettoPoint::identity()),
+        }
+    }
+}
+
+/// Select between two presented attributes in constant time.
+///
+/// The discriminants and payloads are each selected without branching on the
+/// `choice`.  Rebuilding the enum from the selected discriminant is not
+/// constant time, but it only depends upon the *result*, which is revealed to
+/// the verifier anyway.
+impl ConditionallySelectable for EncryptedAttribute {
+    fn conditional_select(a: &Self, b: &Self, choice: Choice) -> Self {
+        let (a_tag, a_m, a_M) = a.to_parts();
+        let (b_tag, b_m, b_M) = b.to_parts();
+
+        let tag = u8::conditional_select(&a_tag, &b_tag, choice);
+        let m = Scalar::conditional_select(&a_m, &b_m, choice);
+        let M = RistrettoPoint::conditional_select(&a_M, &b_M, choice);
+
+        match tag {
+            0 => EncryptedAttribute::PublicScalar(m),
+            1 => EncryptedAttribute::SecretScalar,
+            2 => EncryptedAttribute::PublicPoint(M),
+            _ => EncryptedAttribute::SecretPoint,
+        }
+    }
+}
+
+/// The type of an attribute upon credential presentation: whether it is a
+/// scalar or a group element, and whether it is revealed or hidden.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum AttributeKind {
+    /// A scalar attribute which is revealed upon credential presentation.
+    PublicScalar,
+    /// A scalar attribute which is hidden upon credential presentation.
+    SecretScalar,
+    /// A group element attribute which is revealed upon credential presentation.
+    PublicPoint,
+    /// A group element attribute which is hidden upon credential presentation.
+    SecretPoint,
+    /// A group element attribute which may be either revealed or hidden upon
+    /// credential presentation, as for an [`Attribute::EitherPoint`].
+    EitherPoint,
+}
+
+/// The [`AttributeKind`] which an issuer expects in each attribute slot of a
+/// presented credential.
+///
+/// Since each attribute is bound by position to its generator
+/// \( G_{m_i} \), a verifier should check that every presented attribute is
+/// of the type expected for its slot, e.g. that a revealed group element has
+/// not been presented where a scalar was expected.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AttributeSchema(pub Vec<AttributeKind>);
+
+/// Check that the `presented` attributes of a credential match the
+/// [`AttributeKind`]s expected for each slot by a `schema`.
+///
+/// # Returns
+///
+/// A `Result` whose `Ok` value is empty, otherwise a
+/// [`MacError::MessageLengthError`] if the number of attributes differs from
+/// the `schema`, or a [`MacError::AttributeSlotMismatch`] with the index of the
+/// first attribute of an unexpected type.
+pub fn check_slot_binding(
+    presented: &[EncryptedAttribute],
+    schema: &AttributeSchema,
+) -> Result<(), MacError>
+{
+    if presented.len() != schema.0.len() {
+        return Err(MacError::MessageLengthError{ length: schema.0.len() });
+    }
+
+    for (i, (attribute, expected)) in presented.iter().zip(schema.0.iter()).enumerate() {
+        let matches = match expected {
+            AttributeKind::EitherPoint => matches!(attribute, EncryptedAttribute::PublicPoint(_) |
+                                                              EncryptedAttribute::SecretPoint),
+            _ => attribute.kind() == *expected,
+        };
+
+        if !matches {
+            return Err(MacError::AttributeSlotMismatch{ index: i });
+        }
+    }
+
+    Ok(())
+}
+
+/// Check that the revealed scalar attributes of a presented credential have
+/// allowed values, given by per-slot `constraints`.
+///
+/// A slot whose constraint is `None` may hold any attribute, while a slot with
+/// `Some` allowlist must hold a revealed scalar which is in the allowlist, as
+/// in [`EncryptedAttribute::public_scalar_in`].
+///
+/// # Returns
+///
+/// A `Result` whose `Ok` value is empty, otherwise a
+/// [`MacError::MessageLengthError`] if the number of attributes differs from
+/// the number of `constraints`, or a [`MacError::DisallowedAttribute`] with the
+/// index of the first attribute whose value is not allowed.
+pub fn check_disclosed_against(
+    presented: &[EncryptedAttribute],
+    constraints: &[Option<&[Scalar]>],
+) -> Result<(), MacError>
+{
+    if presented.len() != constraints.len() {
+        return Err(MacError::MessageLengthError{ length: constraints.len() });
+    }
+
+    for (i, (attribute, constraint)) in presented.iter().zip(constraints.iter()).enumerate() {
+        if let Some(allowed) = constraint {
+            if !attribute.public_scalar_in(allowed) {
+                return Err(MacError::DisallowedAttribute{ index: i });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+impl Attribute {
+    /// Compute the message \(( M_i \)) for this attribute in the `i`th slot.
+    pub(crate) fn message_point(&self, i: usize, system_parameters: &SystemParameters) -> RistrettoPoint {
+        match self {
+            Attribute::PublicScalar(m) => system_parameters.mul_G_m(i, m),
+            Attribute::SecretScalar(m) => system_parameters.mul_G_m(i, m),
+            Attribute::PublicPoint(M)  => *M,
+            Attribute::EitherPoint(p)  => p.M1,
+            Attribute::SecretPoint(p)  => p.M1,
+        }
+    }
+}
+
+/// A source of the attributes to be tagged or verified, which need not be
+/// collected into a single `Vec` beforehand.
+///
+/// This is implemented for slices and `Vec`s of [`Attribute`]s, and for
+/// cloneable iterators over borrowed attributes wrapped in an
+/// [`AttributeIterator`], e.g. one chaining some attributes held in a `Vec`
+/// with others borrowed from elsewhere.
+pub trait MessageSource {
+    /// The number of attributes.
+    fn len(&self) -> usize;
+
+    /// Whether there are no attributes.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Compute the message \(( M_i \)) for the `i`th attribute.
+    ///
+    /// # Panics
+    ///
+    /// If `i` is not less than [`MessageSource::len`].
+    fn message_point(&self, i: usize, system_parameters: &SystemParameters) -> RistrettoPoint;
+}
+
+impl MessageSource for [Attribute] {
+    fn len(&self) -> usize {
+        <[Attribute]>::len(self)
+    }
+
+    fn message_point(&self, i: usize, system_parameters: &SystemParameters) -> RistrettoPoint {
+        self[i].message_point(i, system_parameters)
+    }
+}
+
+impl MessageSource for Vec<Attribute> {
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+
+    fn message_point(&self, i: usize, system_parameters: &SystemParameters) -> RistrettoPoint {
+        self[i].message_point(i, system_parameters)
+    }
+}
+
+/// A [`MessageSource`] over an iterator of borrowed [`Attribute`]s.
+///
+/// The iterator is cloned and advanced to reach each attribute, so this is
+/// best suited to cheaply cloneable iterators, such as (chains of) slice
+/// iterators.
+#[derive(Clone, Debug)]
+pub struct AttributeIterator<I>(pub I);
+
+impl<'a, I> MessageSource for AttributeIterator<I>
+where
+    I: Iterator<Item = &'a Attribute> + Clone,
+{
+    fn len(&self) -> usize {
+        self.0.clone().count()
+    }
+
+    fn message_point(&self, i: usize, system_parameters: &SystemParameters) -> RistrettoPoint {
+        match self.0.clone().nth(i) {
+            Some(attribute) => attribute.message_point(i, system_parameters),
+            None => panic!("attribute index {} is out of bounds", i),
+        }
+    }
+}
+
+/// How to fill the unpopulated trailing attribute slots of a credential
+/// with optional attributes, for [`Tag::new_padded`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PadPolicy {
+    /// Fill each missing slot with an [`Attribute::PublicScalar`] of zero.
+    ZeroScalar,
+    /// Fill each missing slot with an [`Attribute::PublicPoint`] of the
+    /// identity element.
+    IdentityPoint,
+}
+
+/// Padding defaults to [`PadPolicy::ZeroScalar`].  Either policy yields the
+/// identity element as the message for a padded slot, so the choice only
+/// determines the attribute type expected in those slots upon presentation.
+impl Default for PadPolicy {
+    fn default() -> PadPolicy {
+        PadPolicy::ZeroScalar
+    }
+}
+
+impl PadPolicy {
+    /// The attribute with which this policy fills a missing slot.
+    pub fn attribute(&self) -> Attribute {
+        match self {
+            PadPolicy::ZeroScalar    => Attribute::PublicScalar(Scalar::zero()),
+            PadPolicy::IdentityPoint => Attribute::PublicPoint(RistrettoPoint::identity()),
+        }
+    }
+}
+
+/// A [`MessageSource`] of some `attributes` followed by padding, up to a
+/// total of `number_of_attributes`.
+struct PaddedAttributes<'a, S: MessageSource + ?Sized> {
+    attributes: &'a S,
+    number_of_attributes: usize,
+    pad: PadPolicy,
+}
+
+impl<'a, S: MessageSource + ?Sized> MessageSource for PaddedAttributes<'a, S> {
+    fn len(&self) -> usize {
+        self.number_of_attributes
+    }
+
+    fn message_point(&self, i: usize, system_parameters: &SystemParameters) -> RistrettoPoint {
+        if i < self.attributes.len() {
+            return self.attributes.message_point(i, system_parameters);
+        }
+        self.pad.attribute().message_point(i, system_parameters)
+    }
+}
+
+/// Messages are computed from `Attribute`s by scalar multiplying the scalar
+/// portions by their respective generator in `SystemParameters.G_m`, which
+/// with the `parallel` feature is done for each attribute in parallel.
+///
+/// The points are the source of truth; their compressed encodings are
+/// computed lazily, at most once, by [`Messages::compressed`].
+#[derive(Debug)]
+pub(crate) struct Messages(pub(crate) Vec<RistrettoPoint>, Option<Vec<CompressedRistretto>>);
+
+impl Messages {
+    pub(crate) fn from_attributes(
+        attributes: &[Attribute],
+        system_parameters: &SystemParameters
+    ) -> Messages
+    {
+        #[cfg(feature = "parallel")]
+        let messages: Vec<RistrettoPoint> = attributes.par_iter().enumerate()
+            .map(|(i, attribute)| attribute.message_point(i, system_parameters))
+            .collect();
+        #[cfg(not(feature = "parallel"))]
+        let messages: Vec<RistrettoPoint> = attributes.iter().enumerate()
+            .map(|(i, attribute)| attribute.message_point(i, system_parameters))
+            .collect();
+
+        Messages(messages, None)
+    }
+}
+
+impl Messages {
+    /// Get the compressed encodings of these message points, compressing
+    /// them on the first call and reusing the result afterwards.
+    pub(crate) fn compressed(&mut self) -> &[CompressedRistretto] {
+        if self.1.is_none() {
+            self.1 = Some(self.0.iter().map(|M_i| M_i.compress()).collect());
+        }
+        // This can't panic since we populated it above.
+        &self.1.as_ref().unwrap()[..]
+    }
+
+    /// Compute the `Messages` for some `attributes`, consulting and updating a
+    /// [`MessageCache`] so that each distinct scalar attribute is only
+    /// multiplied by its generator once.
+    pub(crate) fn from_attributes_cached(
+        attributes: &[Attribute],
+        system_parameters: &SystemParameters,
+        cache: &mut MessageCache,
+    ) -> Messages
+    {
+        let mut messages: Vec<RistrettoPoint> = Vec::with_capacity(attributes.len());
+
+        for (i, attribute) in attributes.iter().enumerate() {
+            let M_i: RistrettoPoint = match attribute {
+                Attribute::PublicScalar(m) => cache.get_or_compute(i, m, system_parameters),
+                Attribute::SecretScalar(m) => cache.get_or_compute(i, m, system_parameters),
+                Attribute::PublicPoint(M)  => *M,
+                Attribute::EitherPoint(p)  => p.M1,
+                Attribute::SecretPoint(p)  => p.M1,
+            };
+            messages.push(M_i);
+        }
+        Messages(messages, None)
+    }
+}
+
+/// A bounded memoisation table for the message points \( M_i = G_{m_i} * m_i \)
+/// of scalar attributes, keyed on the attribute's index and the bytes of its
+/// scalar.
+///
+/// A cache is bound to the [`SystemParameters`] with which it was created,
+/// since the same scalar has a different message point under different
+/// generators, and may only be used with those parameters.
+///
+/// Group element attributes are already points and are never cached.  Once
+/// the cache holds `capacity` entries, the oldest entry is evicted to make
+/// room for each new one.
+///
+/// # Warning
+///
+/// The cache stores the scalars of hidden attributes as keys and does not
+/// zeroize them when dropped, so it should be treated as being as sensitive as
+/// the attributes themselves.
+#[derive(Clone, Debug)]
+pub struct MessageCache {
+    parameters_identifier: [u8; 32],
+    capacity: usize,
+    points: BTreeMap<(usize, [u8; 32]), RistrettoPoint>,
+    insertion_order: VecDeque<(usize, [u8; 32])>,
+    computations: usize,
+}
+
+impl MessageCache {
+    /// Create a new, empty cache for the message points under the
+    /// `system_parameters`, which holds at most `capacity` of them.
+    pub fn new(system_parameters: &SystemParameters, capacity: usize) -> MessageCache {
+        MessageCache {
+            parameters_identifier: system_parameters.identifier(),
+            capacity,
+            points: BTreeMap::new(),
+            insertion_order: VecDeque::with_capacity(capacity),
+            computations: 0,
+        }
+    }
+
+    /// The number of message points currently cached.
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    /// Returns `true` if no message points are cached.
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// The number of scalar multiplications this cache has had to perform,
+    /// i.e. the number of cache misses.
+    pub fn computations(&self) -> usize {
+        self.computations
+    }
+
+    fn get_or_compute(
+        &mut self,
+        index: usize,
+        m: &Scalar,
+        system_parameters: &SystemParameters,
+    ) -> RistrettoPoint
+    {
+        let key = (index, m.to_bytes());
+
+        if let Some(M_i) = self.points.get(&key) {
+            return *M_i;
+        }
+
+        let M_i = system_parameters.mul_G_m(index, m);
+
+        self.computations += 1;
+
+        if self.capacity == 0 {
+            return M_i;
+        }
+        if self.points.len() >= self.capacity {
+            if let Some(oldest) = self.insertion_order.pop_front() {
+                self.points.remove(&oldest);
+            }
+        }
+        self.points.insert(key, M_i);
+        self.insertion_order.push_back(key);
+
+        M_i
+    }
+}
+
+/// An algebraic message authentication code, \(( (t,U,V) \in \mathbb{Z}_q \times \mathbb{G} \times \mathbb{G} \)).
+#[derive(Clone, Debug)]
+pub(crate) struct Amac {
+    pub(crate) t: Scalar,
+    pub(crate) U: RistrettoPoint,
+    pub(crate) V: RistrettoPoint,
+}
+
+impl Amac {
+    /// The length, in bytes, of a serialised algebraic MAC: \(( t \)) and
+    /// the compressed \(( U \)) and \(( V \)), 32 bytes each.
+    pub(crate) const SERIALIZED_LEN: usize = 96;
+
+    /// Serialise this algebraic MAC to [`Amac::SERIALIZED_LEN`] bytes.
+    pub(crate) fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = Vec::with_capacity(Amac::SERIALIZED_LEN);
+
+        bytes.extend(self.t.as_bytes());
+        bytes.extend(self.U.compress().as_bytes());
+        bytes.extend(self.V.compress().as_bytes());
+
+        debug_assert_eq!(bytes.len(), Amac::SERIALIZED_LEN);
+
+        bytes
+    }
+
+    /// Attempt to deserialise an algebraic MAC from exactly
+    /// [`Amac::SERIALIZED_LEN`] bytes.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the algebraic MAC, otherwise a
+    /// [`MacError::MessageLengthError`] if there are too few or too many
+    /// `bytes`, or another [`MacError`] if they are malformed.
+    pub(crate) fn from_bytes(bytes: &[u8]) -> Result<Amac, MacError> {
+        if bytes.len() != Amac::SERIALIZED_LEN {
+            return Err(MacError::MessageLengthError{ length: Amac::SERIALIZED_LEN });
+        }
+
+        let mut chunk = [0u8; 32];
+
+        chunk.copy_from_slice(&bytes[0..32]);
+        let t = match Scalar::from_canonical_bytes(chunk) {
+            Some(t) => t,
+            None    => return Err(MacError::ScalarFormatError),
+        };
+
+        chunk.copy_from_slice(&bytes[32..64]);
+        let U = match CompressedRistretto(chunk).decompress() {
+            Some(U) => U,
+            None    => return Err(MacError::PointDecompressionError),
+        };
+
+        chunk.copy_from_slice(&bytes[64..96]);
+        let V = match CompressedRistretto(chunk).decompress() {
+            Some(V) => V,
+            None    => return Err(MacError::PointDecompressionError),
+        };
+
+        Ok(Amac { t, U, V })
+    }
+
+    /// Compute \(( V = W + (U (x_0 + x_1 t)) + \sigma{i=1}{n} M_i y_i \)).
+    ///
+    /// This is the expensive half of [`Amac::verify`], and may be computed
+    /// separately from the comparison in [`Tag::verify_against`].  The
+    /// \(( M_i \)) of scalar attributes are computed with the precomputed
+    /// tables of the `system_parameters`, if they have been built.
+    pub(crate) fn compute_V<S>(
+        system_parameters: &SystemParameters,
+        secret_key: &SecretKey,
+        attributes: &S,
+        t: &Scalar,
+        U: &RistrettoPoint,
+    ) -> RistrettoPoint
+    where
+        S: MessageSource + ?Sized,
+    {
+        secret_key.check_poison();
+
+        // V = W + U * x_0 + U * x_1 * t
+        let mut V: RistrettoPoint = secret_key.W + (U * secret_key.x_0) + (U * (secret_key.x_1 * t));
+
+        // V = W + U * x_0 + U * x_1 + U * t + \sigma{i=1}{n} M_i y_i
+        V += Amac::sum_of_messages(system_parameters, secret_key, attributes);
+        V
+    }
+
+    /// Compute \(( \sigma{i=1}{n} M_i y_i \)).
+    fn sum_of_messages<S>(
+        system_parameters: &SystemParameters,
+        secret_key: &SecretKey,
+        attributes: &S,
+    ) -> RistrettoPoint
+    where
+        S: MessageSource + ?Sized,
+    {
+        let messages = (0..attributes.len()).map(|i| attributes.message_point(i, system_parameters));
+
+        RistrettoPoint::multiscalar_mul(&secret_key.y[..], messages)
+    }
+
+    /// Compute \(( V \)) as in [`Amac::compute_V`], from already computed `messages`.
+    fn compute_V_from_messages(
+        secret_key: &SecretKey,
+        messages: &Messages,
+        t: &Scalar,
+        U: &RistrettoPoint,
+    ) -> RistrettoPoint
+    {
+        secret_key.check_poison();
+
+        // V = W + U * x_0 + U * x_1 * t
+        let mut V: RistrettoPoint = secret_key.W + (U * secret_key.x_0) + (U * (secret_key.x_1 * t));
+
+        // V = W + U * x_0 + U * x_1 + U * t + \sigma{i=1}{n} M_i y_i
+        V += RistrettoPoint::multiscalar_mul(&secret_key.y[..], &messages.0[..]);
+        V
+    }
+
+    /// Compute an algebraic message authentication code with a secret key for a
+    /// vector of messages, taken from any [`MessageSource`].
+    pub(crate) fn tag<R, S>(
+        csprng: &mut R,
+        system_parameters: &SystemParameters,
+        secret_key: &SecretKey,
+        attributes: &S,
+    ) -> Result<Amac, MacError>
+    where
+        R: RngCore + CryptoRng,
+        S: MessageSource + ?Sized,
+    {
+        Amac::tag_with_signer(csprng, system_parameters, secret_key, attributes)
+    }
+
+    /// Compute an algebraic message authentication code, as in
+    /// [`Amac::tag`], with a secret key held by any [`MacSigner`].
+    pub(crate) fn tag_with_signer<R, S, K>(
+        csprng: &mut R,
+        system_parameters: &SystemParameters,
+        signer: &K,
+        attributes: &S,
+    ) -> Result<Amac, MacError>
+    where
+        R: RngCore + CryptoRng,
+        S: MessageSource + ?Sized,
+        K: MacSigner + ?Sized,
+    {
+        if attributes.len() != system_parameters.NUMBER_OF_ATTRIBUTES as usize {
+            return Err(MacError::MessageLengthError{length: system_parameters.NUMBER_OF_ATTRIBUTES as usize});
+        }
+
+        let messages: Vec<RistrettoPoint> = (0..attributes.len())
+            .map(|i| attributes.message_point(i, system_parameters))
+            .collect();
+
+        let t: Scalar = Scalar::random(csprng);
+        let U: RistrettoPoint = RistrettoPoint::random(csprng);
+        let V: RistrettoPoint = signer.compute_V(&messages, &t, &U)?;
+
+        Ok(Amac { t, U, V })
+    }
+
+    /// Compute many algebraic message authentication codes, each upon its
+    /// own attributes, with the same `secret_key`.
+    ///
+    /// Rather than hashing a fresh group element to each \(( U_j \)), as in
+    /// [`Amac::tag`], this samples only scalars \(( u_j \)) and computes
+    /// \(( U_j = B * u_j \)) with a precomputed basepoint table, so that
+    /// each \(( V_j \)) is
+    ///
+    /// \(( W + B * (x_0 + x_1 t_j) u_j + \sigma{i=1}{n} M_{ij} y_i \)),
+    ///
+    /// i.e. one fixed-base and one multiscalar multiplication.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the algebraic MACs, in the same order
+    /// as the `attributes`, otherwise a [`MacError::MessageLengthError`] if
+    /// any has the wrong number of attributes.
+    pub(crate) fn tag_batch<R, S>(
+        csprng: &mut R,
+        system_parameters: &SystemParameters,
+        secret_key: &SecretKey,
+        attributes: &[&S],
+    ) -> Result<Vec<Amac>, MacError>
+    where
+        R: RngCore + CryptoRng,
+        S: MessageSource + ?Sized,
+    {
+        secret_key.check_poison();
+
+        if attributes.iter().any(|a| a.len() != system_parameters.NUMBER_OF_ATTRIBUTES as usize) {
+            return Err(MacError::MessageLengthError{length: system_parameters.NUMBER_OF_ATTRIBUTES as usize});
+        }
+
+        let mut tags: Vec<Amac> = Vec::with_capacity(attributes.len());
+
+        for a in attributes.iter() {
+            let t: Scalar = Scalar::random(csprng);
+            let mut u: Scalar = Scalar::random(csprng);
+            let mut k: Scalar = (secret_key.x_0 + secret_key.x_1 * t) * u;
+
+            let U: RistrettoPoint = &RISTRETTO_BASEPOINT_TABLE * &u;
+            let V: RistrettoPoint = secret_key.W + (&RISTRETTO_BASEPOINT_TABLE * &k) +
+                Amac::sum_of_messages(system_parameters, secret_key, *a);
+
+            u.zeroize();
+            k.zeroize();
+            tags.push(Amac { t, U, V });
+        }
+
+        Ok(tags)
+    }
+
+    /// Verify this algebraic MAC w.r.t. a secret key and vector of messages,
+    /// taken from any [`MessageSource`].
+    #[allow(unused)] // We never actually call this function as the AMAC is verified indirectly in a NIZK.
+    pub(crate) fn verify<S>(
+        &self,
+        system_parameters: &SystemParameters,
+        secret_key: &SecretKey,
+        attributes: &S,
+    ) -> Result<(), MacError>
+    where
+        S: MessageSource + ?Sized,
+    {
+        if attributes.len() != system_parameters.NUMBER_OF_ATTRIBUTES as usize {
+            return Err(MacError::MessageLengthError{length: system_parameters.NUMBER_OF_ATTRIBUTES as usize});
+        }
+
+        if self.verify_ct(system_parameters, secret_key, attributes).into() {
+            return Ok(());
+        }
+        Err(MacError::AuthenticationError)
+    }
+
+    /// Verify this algebraic MAC as in [`Amac::verify`], in constant time.
+    ///
+    /// Every attribute contributes to \(( V' \)) with a single multiscalar
+    /// multiplication, and \(( V \)) is compared to it as compressed
+    /// encodings with [`ConstantTimeEq`], so the timing reveals neither
+    /// whether the MAC is valid nor which attribute caused a mismatch.
+    ///
+    /// The number of `attributes`, which is public, must already have been
+    /// checked against the `system_parameters`.
+    ///
+    /// # Returns
+    ///
+    /// A [`Choice`] which is `1` if the MAC is valid, and `0` otherwise.
+    pub(crate) fn verify_ct<S>(
+        &self,
+        system_parameters: &SystemParameters,
+        secret_key: &SecretKey,
+        attributes: &S,
+    ) -> Choice
+    where
+        S: MessageSource + ?Sized,
+    {
+        let V_prime = Amac::compute_V(system_parameters, secret_key, attributes, &self.t, &self.U);
+
+        self.V_ct_eq(&V_prime)
+    }
+
+    /// Compare \(( V \)) to some \(( V' \)) as compressed encodings, in
+    /// constant time.
+    fn V_ct_eq(&self, V_prime: &RistrettoPoint) -> Choice {
+        self.V.compress().ct_eq(&V_prime.compress())
+    }
+
+}
+
+/// Compare two algebraic MACs for exact equality in constant time.
+///
+/// Tags are not secret, but a gateway deduplicating them should not reveal
+/// through timing how much of a tag matched one it has already seen.  Note
+/// that this is exact equality of \(( (t, U, V) \)), and thus two tags on
+/// the same attributes are never equal.
+impl ConstantTimeEq for Amac {
+    fn ct_eq(&self, other: &Amac) -> Choice {
+        self.t.ct_eq(&other.t) &
+            self.U.compress().ct_eq(&other.U.compress()) &
+            self.V.compress().ct_eq(&other.V.compress())
+    }
+}
+
+impl PartialEq for Amac {
+    fn eq(&self, other: &Amac) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Eq for Amac {}
+
+/// An algebraic MAC upon some attributes, for protocols built directly upon
+/// the \(( \mathsf{MAC}_{GGM} \)) primitive rather than upon anonymous
+/// credentials.
+///
+/// This is an opaque wrapper, whose contents may only be created by tagging
+/// some attributes with a [`SecretKey`], or by deserialisation.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Tag(pub(crate) Amac);
+
+impl Tag {
+    /// The length, in bytes, of the body of a serialised tag.
+    pub const SERIALIZED_LEN: usize = Amac::SERIALIZED_LEN;
+
+    /// Compute a tag upon the `attributes` with the `secret_key`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the `Tag`, otherwise a
+    /// [`MacError::MessageLengthError`] if there are the wrong number of
+    /// attributes for the `system_parameters`.
+    pub fn new<R, S>(
+        csprng: &mut R,
+        system_parameters: &SystemParameters,
+        secret_key: &SecretKey,
+        attributes: &S,
+    ) -> Result<Tag, MacError>
+    where
+        R: RngCore + CryptoRng,
+        S: MessageSource + ?Sized,
+    {
+        Amac::tag(csprng, system_parameters, secret_key, attributes).map(Tag)
+    }
+
+    /// Compute a tag, as in [`Tag::new`], after checking that the
+    /// `secret_key` is bound to the `system_parameters`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the `Tag`, otherwise a
+    /// [`MacError::ParameterMismatch`] if the key is unbound or bound to
+    /// different parameters.
+    pub fn new_strict<R, S>(
+        csprng: &mut R,
+        system_parameters: &SystemParameters,
+        secret_key: &SecretKey,
+        attributes: &S,
+    ) -> Result<Tag, MacError>
+    where
+        R: RngCore + CryptoRng,
+        S: MessageSource + ?Sized,
+    {
+        secret_key.check_parameters(system_parameters)?;
+
+        Tag::new(csprng, system_parameters, secret_key, attributes)
+    }
+
+    /// Compute a tag, as in [`Tag::new`], on fewer `attributes` than the
+    /// `system_parameters` support, filling the remaining slots according to
+    /// the `pad` policy.
+    ///
+    /// Either policy results in the identity element as the message for each
+    /// padded slot, so they produce interchangeable tags, but the policy also
+    /// determines the type of attribute expected in those slots upon
+    /// presentation.  The tag must be verified with [`Tag::verify_padded`]
+    /// and the same `pad` policy, or with [`Tag::verify`] on the attributes
+    /// explicitly padded with [`PadPolicy::attribute`].
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the `Tag`, otherwise a
+    /// [`MacError::MessageLengthError`] if there are more `attributes` than
+    /// the `system_parameters` support.
+    pub fn new_padded<R, S>(
+        csprng: &mut R,
+        system_parameters: &SystemParameters,
+        secret_key: &SecretKey,
+        attributes: &S,
+        pad: PadPolicy,
+    ) -> Result<Tag, MacError>
+    where
+        R: RngCore + CryptoRng,
+        S: MessageSource + ?Sized,
+    {
+        let number_of_attributes = system_parameters.NUMBER_OF_ATTRIBUTES as usize;
+
+        if attributes.len() > number_of_attributes {
+            return Err(MacError::MessageLengthError{length: number_of_attributes});
+        }
+
+        let padded = PaddedAttributes { attributes, number_of_attributes, pad };
+
+        Tag::new(csprng, system_parameters, secret_key, &padded)
+    }
+
+    /// Verify this tag as in [`Tag::verify`], after checking that the
+    /// `secret_key` is bound to the `system_parameters`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is empty if the tag is valid, otherwise a
+    /// [`MacError::ParameterMismatch`] if the key is unbound or bound to
+    /// different parameters, or a [`MacError::AuthenticationError`].
+    pub fn verify_strict<S>(
+        &self,
+        system_parameters: &SystemParameters,
+        secret_key: &SecretKey,
+        attributes: &S,
+    ) -> Result<(), MacError>
+    where
+        S: MessageSource + ?Sized,
+    {
+        secret_key.check_parameters(system_parameters)?;
+
+        self.verify(system_parameters, secret_key, attributes)
+    }
+    /// Verify a tag made with [`Tag::new_padded`], with the same `pad`
+    /// policy.
+    pub fn verify_padded<S>(
+        &self,
+        system_parameters: &SystemParameters,
+        secret_key: &SecretKey,
+        attributes: &S,
+        pad: PadPolicy,
+    ) -> Result<(), MacError>
+    where
+        S: MessageSource + ?Sized,
+    {
+        let number_of_attributes = system_parameters.NUMBER_OF_ATTRIBUTES as usize;
+
+        if attributes.len() > number_of_attributes {
+            return Err(MacError::MessageLengthError{length: number_of_attributes});
+        }
+
+        let padded = PaddedAttributes { attributes, number_of_attributes, pad };
+
+        self.verify(system_parameters, secret_key, &padded)
+    }
+
+    /// Compute only the message term \(( \sigma{i=1}{n} M_i y_i \)) of
+    /// \(( V \)), as in [`Tag::compute_V`], e.g. in order to reuse it across
+    /// several related proofs.
+    ///
+    /// This requires the secret \(( y_i \)) of the issuer's `secret_key`, and
+    /// the result should be treated as secret.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the message term, otherwise a
+    /// [`MacError::MessageLengthError`] if the number of `attributes` is not
+    /// the number of attributes of the `system_parameters`.
+    pub fn message_commitment<S>(
+        system_parameters: &SystemParameters,
+        secret_key: &SecretKey,
+        attributes: &S,
+    ) -> Result<RistrettoPoint, MacError>
+    where
+        S: MessageSource + ?Sized,
+    {
+        if attributes.len() != system_parameters.NUMBER_OF_ATTRIBUTES as usize {
+            return Err(MacError::MessageLengthError{length: system_parameters.NUMBER_OF_ATTRIBUTES as usize});
+        }
+        secret_key.check_poison();
+
+        Ok(Amac::sum_of_messages(system_parameters, secret_key, attributes))
+    }
+
+    /// Verify this tag upon the `attributes` with the `secret_key`, in
+    /// constant time.
+    pub fn verify<S>(
+        &self,
+        system_parameters: &SystemParameters,
+        secret_key: &SecretKey,
+        attributes: &S,
+    ) -> Result<(), MacError>
+    where
+        S: MessageSource + ?Sized,
+    {
+        self.0.verify(system_parameters, secret_key, attributes)
+    }
+
+    /// Verify this tag as in [`Tag::verify`], additionally counting the
+    /// attributes involved, e.g. for throughput metrics.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the number of attributes verified,
+    /// otherwise a [`MacError`].
+    pub fn verify_counted<S>(
+        &self,
+        system_parameters: &SystemParameters,
+        secret_key: &SecretKey,
+        attributes: &S,
+    ) -> Result<usize, MacError>
+    where
+        S: MessageSource + ?Sized,
+    {
+        self.verify(system_parameters, secret_key, attributes).map(|_| attributes.len())
+    }
+
+    /// Compute the \(( V' \)) against which this tag upon the `attributes`
+    /// is verified, e.g. by one node of a sharded verifier, for another to
+    /// compare with [`Tag::verify_against`].
+    ///
+    /// This requires the `secret_key`, and so the node computing it must be
+    /// trusted with the key.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is \(( V' \)), otherwise a
+    /// [`MacError::MessageLengthError`] if there are the wrong number of
+    /// attributes for the `system_parameters`.
+    pub fn compute_V<S>(
+        &self,
+        system_parameters: &SystemParameters,
+        secret_key: &SecretKey,
+        attributes: &S,
+    ) -> Result<RistrettoPoint, MacError>
+    where
+        S: MessageSource + ?Sized,
+    {
+        if attributes.len() != system_parameters.NUMBER_OF_ATTRIBUTES as usize {
+            return Err(MacError::MessageLengthError{length: system_parameters.NUMBER_OF_ATTRIBUTES as usize});
+        }
+
+        Ok(Amac::compute_V(system_parameters, secret_key, attributes, &self.0.t, &self.0.U))
+    }
+
+    /// Verify this tag against a \(( V' \)) which was computed elsewhere
+    /// with [`Tag::compute_V`].
+    ///
+    /// # Warning
+    ///
+    /// This merely compares \(( V \)) to \(( V' \)) in constant time.  It
+    /// is only as trustworthy as whoever computed `V_prime`, who must hold the
+    /// issuer's [`SecretKey`] and therefore be trusted with it.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is empty if \(( V = V' \)), otherwise a
+    /// [`MacError::AuthenticationError`].
+    pub fn verify_against(&self, V_prime: &RistrettoPoint) -> Result<(), MacError> {
+        if self.0.V_ct_eq(V_prime).into() {
+            return Ok(());
+        }
+        Err(MacError::AuthenticationError)
+    }
+
+    /// A 16-byte identifier for this tag, e.g. for keying maps of issued
+    /// credentials, computed as a truncated hash of its serialised
+    /// \(( (t, U, V) \)).
+    ///
+    /// `Tag` deliberately doesn't implement `core::hash::Hash`, since a
+    /// general-purpose hasher should never be fed secret-bearing types; use
+    /// this instead.  The identifier is public and isn't a security boundary:
+    /// it is only as unique as the tag itself, and a rerandomised tag has a
+    /// different identifier.
+    pub fn tag_id(&self) -> [u8; 16] {
+        let mut hash = Sha512Trunc256::default();
+        let mut id = [0u8; 16];
+
+        hash.input(b"aeonflux Amac tag_id");
+        hash.input(self.0.to_bytes());
+        id.copy_from_slice(&hash.result()[..16]);
+        id
+    }
+
+    /// Serialise this tag in the versioned [`wire`] format, whose body is
+    /// \(( t \)) and the compressed \(( U \)) and \(( V \)), 32 bytes
+    /// each.
+    ///
+    /// [`wire`]: crate::wire
+    pub fn to_bytes(&self) -> Vec<u8> {
+        wire::encode(WireType::Tag, &self.0.to_bytes())
+    }
+
+    /// Deserialise a tag previously serialised with [`Tag::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Tag, MacError> {
+        match wire::decode(bytes, WireType::Tag)? {
+            (1, body) => Amac::from_bytes(body).map(Tag),
+            _ => Err(MacError::UnsupportedEncoding),
+        }
+    }
+}
+
+/// Some attributes along with a [`Tag`] upon them.
+#[derive(Clone, Debug)]
+pub struct TaggedCredential {
+    tag: Tag,
+    attributes: Vec<Attribute>,
+}
+
+impl TaggedCredential {
+    /// Tag the `attributes` with the `secret_key`, as in [`Tag::new`].
+    pub fn new<R>(
+        csprng: &mut R,
+        system_parameters: &SystemParameters,
+        secret_key: &SecretKey,
+        attributes: Vec<Attribute>,
+    ) -> Result<TaggedCredential, MacError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let tag = Tag::new(csprng, system_parameters, secret_key, &attributes)?;
+
+        Ok(TaggedCredential { tag, attributes })
+    }
+
+    /// The tag upon these attributes.
+    pub fn tag(&self) -> &Tag {
+        &self.tag
+    }
+
+    /// The tagged attributes.
+    pub fn attributes(&self) -> &[Attribute] {
+        &self.attributes[..]
+    }
+
+    /// Verify the tag upon these attributes with the `secret_key`.
+    pub fn verify(&self, system_parameters: &SystemParameters, secret_key: &SecretKey) -> Result<(), MacError> {
+        self.tag.verify(system_parameters, secret_key, &self.attributes[..])
+    }
+
+    /// Verify many tagged credentials, each made with the same `secret_key`.
+    ///
+    /// Rather than checking each \(( V_j \)) separately, this checks a
+    /// random linear combination of all of the verification equations,
+    ///
+    /// \(( \sigma{j} r_j (V_j - W - U_j (x_0 + x_1 t_j) - \sigma{i=1}{n} M_{ij} y_i) = 0 \)),
+    ///
+    /// with a single multiscalar multiplication.  An invalid tag passes only
+    /// with negligible probability over the choice of the \(( r_j \)).
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is empty if every tag is valid, otherwise
+    /// a [`MacError::MessageLengthError`] if any credential has the wrong
+    /// number of attributes, or a [`MacError::AuthenticationError`] if any
+    /// tag is invalid.  In the latter case, the invalid credentials may be
+    /// found by verifying each of them with [`TaggedCredential::verify`].
+    pub fn batch_verify<R>(
+        csprng: &mut R,
+        system_parameters: &SystemParameters,
+        secret_key: &SecretKey,
+        credentials: &[TaggedCredential],
+    ) -> Result<(), MacError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let number_of_attributes = system_parameters.NUMBER_OF_ATTRIBUTES as usize;
+
+        for credential in credentials.iter() {
+            if credential.attributes.len() != number_of_attributes {
+                return Err(MacError::MessageLengthError{length: number_of_attributes});
+            }
+        }
+        secret_key.check_poison();
+
+        // Computing the message points dominates, so with the `parallel`
+        // feature they are computed for each tag in parallel.
+        #[cfg(feature = "parallel")]
+        let messages: Vec<Messages> = credentials.par_iter()
+            .map(|credential| Messages::from_attributes(&credential.attributes, system_parameters))
+            .collect();
+        #[cfg(not(feature = "parallel"))]
+        let messages: Vec<Messages> = credentials.iter()
+            .map(|credential| Messages::from_attributes(&credential.attributes, system_parameters))
+            .collect();
+
+        let capacity = 1 + credentials.len() * (2 + number_of_attributes);
+        let mut scalars: Vec<Scalar> = Vec::with_capacity(capacity);
+        let mut points: Vec<RistrettoPoint> = Vec::with_capacity(capacity);
+        let mut sum_of_r = Scalar::zero();
+
+        for (credential, messages) in credentials.iter().zip(messages.iter()) {
+            let amac = &credential.tag.0;
+            let r = Scalar::random(csprng);
+
+            scalars.push(r);
+            points.push(amac.V);
+            scalars.push(-(r * (secret_key.x_0 + secret_key.x_1 * amac.t)));
+            points.push(amac.U);
+
+            for (i, M_i) in messages.0.iter().enumerate() {
+                scalars.push(-(r * secret_key.y[i]));
+                points.push(*M_i);
+            }
+            sum_of_r += r;
+        }
+        scalars.push(-sum_of_r);
+        points.push(secret_key.W);
+
+        let result = RistrettoPoint::multiscalar_mul(&scalars[..], &points[..]);
+
+        scalars.zeroize();
+
+        if result.compress().ct_eq(&RistrettoPoint::identity().compress()).into() {
+            return Ok(());
+        }
+        Err(MacError::AuthenticationError)
+    }
+
+    /// Verify many tagged credentials, each made with the same `secret_key`,
+    /// memoising the message points of scalar attributes in a
+    /// [`MessageCache`].
+    ///
+    /// This is beneficial when many of the credentials share some attributes,
+    /// as each shared scalar attribute is then only multiplied by its
+    /// generator once.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is empty if every tag in the batch was
+    /// valid, otherwise a [`MacError::ParameterMismatch`] if the `cache` was
+    /// created for other system parameters, or another [`MacError`].
+    pub fn batch_verify_with_cache(
+        system_parameters: &SystemParameters,
+        secret_key: &SecretKey,
+        credentials: &[TaggedCredential],
+        cache: &mut MessageCache,
+    ) -> Result<(), MacError>
+    {
+        if cache.parameters_identifier != system_parameters.identifier() {
+            return Err(MacError::ParameterMismatch);
+        }
+
+        let mut valid = Choice::from(1);
+
+        for credential in credentials.iter() {
+            if credential.attributes.len() != system_parameters.NUMBER_OF_ATTRIBUTES as usize {
+                return Err(MacError::MessageLengthError{length: system_parameters.NUMBER_OF_ATTRIBUTES as usize});
+            }
+
+            let amac = &credential.tag.0;
+            let messages = Messages::from_attributes_cached(&credential.attributes, system_parameters, cache);
+            let V_prime = Amac::compute_V_from_messages(secret_key, &messages, &amac.t, &amac.U);
+
+            valid &= amac.V_ct_eq(&V_prime);
+        }
+
+        if valid.into() {
+            return Ok(());
+        }
+        Err(MacError::AuthenticationError)
+    }
+
+    /// Serialise these attributes and their tag in the versioned [`wire`]
+    /// format, whose body is the attributes, i.e. a little-endian `u32`
+    /// number of attributes followed by a one byte type and 32 bytes of data
+    /// for each, and then the body of the serialised [`Tag`].
+    ///
+    /// [`wire`]: crate::wire
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut body = attributes_to_bytes(&self.attributes);
+
+        body.extend(self.tag.0.to_bytes());
+
+        let bytes = wire::encode(WireType::TaggedCredential, &body);
+
+        body.zeroize();
+        bytes
+    }
+
+    /// Write these attributes and their tag to a `writer` as one record of
+    /// the stream read by [`verify_stream`].
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is empty, otherwise an `io::Error` from
+    /// the `writer`.
+    #[cfg(feature = "std")]
+    pub fn write_record<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let mut record = to_tag_record(&self.attributes, &self.tag.0);
+        let result = writer.write_all(&record);
+
+        // The record may contain hidden attributes.
+        record.zeroize();
+        result
+    }
+
+    /// Deserialise a tagged credential previously serialised with
+    /// [`TaggedCredential::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<TaggedCredential, MacError> {
+        match wire::decode(bytes, WireType::TaggedCredential)? {
+            (1, body) => from_tag_record(body).map(|(attributes, amac)| TaggedCredential { tag: Tag(amac), attributes }),
+            _ => Err(MacError::UnsupportedEncoding),
+        }
+    }
+}
+
+/// Compute the message points \(( M_i \)) of some `attributes`, as they are
+/// tagged, i.e. \(( G_{m_i} * m_i \)) for a scalar attribute, and the group
+/// element itself otherwise.
+pub fn message_points(attributes: &[Attribute], system_parameters: &SystemParameters) -> Vec<RistrettoPoint> {
+    Messages::from_attributes(attributes, system_parameters).0
+}
+
+/// The length, in bytes, of a serialised attribute: a one byte attribute type
+/// followed by a scalar, a compressed point, or 30 bytes of plaintext padded
+/// with two zeroes.
+pub(crate) const ATTRIBUTE_RECORD_LENGTH: usize = 33;
+
+/// Determine the size of some serialised attributes, in bytes.
+pub(crate) fn sizeof_attributes(number_of_attributes: u32) -> usize {
+    4 + ATTRIBUTE_RECORD_LENGTH * number_of_attributes as usize
+}
+
+/// Determine the size of a tag record, excluding its length prefix, in bytes.
+pub(crate) fn sizeof_tag_record(number_of_attributes: u32) -> usize {
+    sizeof_attributes(number_of_attributes) + Amac::SERIALIZED_LEN
+}
+
+/// Serialise some `attributes` as a little-endian `u32` number of attributes
+/// followed by each attribute, as described for `ATTRIBUTE_RECORD_LENGTH`.
+pub(crate) fn attributes_to_bytes(attributes: &[Attribute]) -> Vec<u8> {
+    let mut bytes: Vec<u8> = Vec::with_capacity(sizeof_attributes(attributes.len() as u32));
+
+    write_attributes(attributes, &mut bytes);
+    bytes
+}
+
+/// Write some attributes to a [`Sink`], as for `attributes_to_bytes`.
+pub(crate) fn write_attributes<S: Sink>(attributes: &[Attribute], bytes: &mut S) {
+    bytes.put(&(attributes.len() as u32).to_le_bytes());
+
+    for attribute in attributes.iter() {
+        match attribute {
+            Attribute::PublicScalar(m) => { bytes.put(&[0]); bytes.put(m.as_bytes()); },
+            Attribute::SecretScalar(m) => { bytes.put(&[1]); bytes.put(m.as_bytes()); },
+            Attribute::PublicPoint(M)  => { bytes.put(&[2]); bytes.put(M.compress().as_bytes()); },
+            Attribute::EitherPoint(p)  => { bytes.put(&[3]); bytes.put(&<[u8; 30]>::from(p)); bytes.put(&[0u8; 2]); },
+            Attribute::SecretPoint(p)  => { bytes.put(&[4]); bytes.put(&<[u8; 30]>::from(p)); bytes.put(&[0u8; 2]); },
+        }
+    }
+}
+
+/// Attempt to deserialise some attributes serialised with
+/// `attributes_to_bytes`.
+pub(crate) fn attributes_from_bytes(bytes: &[u8]) -> Result<Vec<Attribute>, MacError> {
+    if bytes.len() < 4 {
+        return Err(MacError::MessageLengthError{ length: sizeof_attributes(0) });
+    }
+
+    let mut tmp = [0u8; 4];
+
+    tmp.copy_from_slice(&bytes[0..4]);
+
+    let number_of_attributes = u32::from_le_bytes(tmp);
+
+    if number_of_attributes > MAX_NUMBER_OF_ATTRIBUTES {
+        return Err(MacError::MessageLengthError{ length: sizeof_attributes(MAX_NUMBER_OF_ATTRIBUTES) });
+    }
+    if bytes.len() != sizeof_attributes(number_of_attributes) {
+        return Err(MacError::MessageLengthError{ length: sizeof_attributes(number_of_attributes) });
+    }
+
+    let mut attributes: Vec<Attribute> = Vec::with_capacity(number_of_attributes as usize);
+
+    for record in bytes[4..].chunks(ATTRIBUTE_RECORD_LENGTH) {
+        attributes.push(attribute_from_record(record)?);
+    }
+
+    Ok(attributes)
+}
+
+/// Attempt to deserialise a single attribute from an
+/// [`ATTRIBUTE_RECORD_LENGTH`]-byte record, as written by
+/// `attributes_to_bytes`.
+pub(crate) fn attribute_from_record(record: &[u8]) -> Result<Attribute, MacError> {
+    if record.len() != ATTRIBUTE_RECORD_LENGTH {
+        return Err(MacError::MessageLengthError{ length: ATTRIBUTE_RECORD_LENGTH });
+    }
+
+    let mut chunk = [0u8; 32];
+    let mut data = [0u8; 30];
+
+    chunk.copy_from_slice(&record[1..ATTRIBUTE_RECORD_LENGTH]);
+    data.copy_from_slice(&chunk[..30]);
+
+    let attribute = match record[0] {
+        0 => Scalar::from_canonical_bytes(chunk).map(Attribute::PublicScalar).ok_or(MacError::ScalarFormatError),
+        1 => Scalar::from_canonical_bytes(chunk).map(Attribute::SecretScalar).ok_or(MacError::ScalarFormatError),
+        2 => CompressedRistretto(chunk).decompress().map(Attribute::PublicPoint).ok_or(MacError::PointDecompressionError),
+        3 if chunk[30..] == [0, 0] => Ok(Attribute::EitherPoint((&data).into())),
+        4 if chunk[30..] == [0, 0] => Ok(Attribute::SecretPoint((&data).into())),
+        _ => Err(MacError::AttributeTypeError),
+    };
+
+    chunk.zeroize();
+    data.zeroize();
+
+    attribute
+}
+
+/// Serialise some `attributes` and their `amac` as a tag record, for
+/// [`verify_stream`].
+///
+/// A record is a little-endian `u32` length of the remainder of the record,
+/// the attributes as serialised by `attributes_to_bytes`, and finally the
+/// serialised [`Amac`].
+pub(crate) fn to_tag_record(attributes: &[Attribute], amac: &Amac) -> Vec<u8> {
+    let length = sizeof_tag_record(attributes.len() as u32);
+    let mut bytes: Vec<u8> = Vec::with_capacity(4 + length);
+
+    bytes.extend(&(length as u32).to_le_bytes());
+    bytes.extend(attributes_to_bytes(attributes));
+    bytes.extend(amac.to_bytes());
+    bytes
+}
+
+/// Attempt to deserialise a tag record, excluding its length prefix.
+pub(crate) fn from_tag_record(bytes: &[u8]) -> Result<(Vec<Attribute>, Amac), MacError> {
+    if bytes.len() < sizeof_tag_record(0) {
+        return Err(MacError::MessageLengthError{ length: sizeof_tag_record(0) });
+    }
+
+    let (attributes_bytes, amac_bytes) = bytes.split_at(bytes.len() - Amac::SERIALIZED_LEN);
+    let attributes = attributes_from_bytes(attributes_bytes)?;
+    let amac = Amac::from_bytes(amac_bytes)?;
+
+    Ok((attributes, amac))
+}
+
+/// The magic bytes at the start of a serialised [`CredentialStore`].
+const CREDENTIAL_STORE_MAGIC: &[u8; 4] = b"AFCS";
+
+/// The current version of the [`CredentialStore`] serialisation format.
+const CREDENTIAL_STORE_VERSION: u32 = 1;
+
+/// The length, in bytes, of the header of a serialised [`CredentialStore`]:
+/// the magic bytes, the version, the number of records, and the
+/// [`SystemParameters::identifier`].
+const CREDENTIAL_STORE_HEADER_LENGTH: usize = 4 + 4 + 4 + 32;
+
+/// Compute the checksum over a serialised [`CredentialStore`], excluding the
+/// checksum itself.
+fn credential_store_checksum(bytes: &[u8]) -> [u8; 32] {
+    let mut hash = Sha512Trunc256::default();
+    let mut checksum = [0u8; 32];
+
+    hash.input(b"aeonflux CredentialStore checksum");
+    hash.input(bytes);
+    checksum.copy_from_slice(&hash.result()[..]);
+    checksum
+}
+
+/// A database of issued [`TaggedCredential`]s, all for the same
+/// [`SystemParameters`], which may be persisted as a single file.
+///
+/// The serialised form is a header of the magic bytes `AFCS`, a
+/// little-endian `u32` version, a little-endian `u32` number of records, and
+/// the 32-byte [`SystemParameters::identifier`], followed by each record in
+/// the format of `to_tag_record`, and finally a 32-byte checksum over
+/// everything before it.  The checksum detects corruption, but it is not a
+/// MAC: anyone may recompute it.
+#[derive(Clone, Debug)]
+pub struct CredentialStore {
+    parameters_identifier: [u8; 32],
+    credentials: Vec<TaggedCredential>,
+}
+
+impl CredentialStore {
+    /// Create an empty store for credentials under the `system_parameters`.
+    pub fn new(system_parameters: &SystemParameters) -> CredentialStore {
+        CredentialStore {
+            parameters_identifier: system_parameters.identifier(),
+            credentials: Vec::new(),
+        }
+    }
+
+    /// Add a `credential` to the store.
+    pub fn push(&mut self, credential: TaggedCredential) {
+        self.credentials.push(credential);
+    }
+
+    /// The credentials in the store, in the order in which they were added.
+    pub fn credentials(&self) -> &[TaggedCredential] {
+        &self.credentials[..]
+    }
+
+    /// The number of credentials in the store.
+    pub fn len(&self) -> usize {
+        self.credentials.len()
+    }
+
+    /// Whether the store has no credentials.
+    pub fn is_empty(&self) -> bool {
+        self.credentials.is_empty()
+    }
+
+    /// Serialise this store to a vector of bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = Vec::with_capacity(CREDENTIAL_STORE_HEADER_LENGTH);
+
+        bytes.extend(CREDENTIAL_STORE_MAGIC);
+        bytes.extend(&CREDENTIAL_STORE_VERSION.to_le_bytes());
+        bytes.extend(&(self.credentials.len() as u32).to_le_bytes());
+        bytes.extend(&self.parameters_identifier);
+
+        for credential in self.credentials.iter() {
+            bytes.extend(to_tag_record(&credential.attributes, &credential.tag.0));
+        }
+
+        let checksum = credential_store_checksum(&bytes);
+
+        bytes.extend(&checksum);
+        bytes
+    }
+
+    /// Attempt to deserialise a store of credentials under the
+    /// `system_parameters` from bytes.
+    ///
+    /// The header and checksum are validated before any record is decoded.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the store, otherwise a
+    /// [`MacError::CorruptCredentialStore`] if the magic bytes, version, or
+    /// checksum are wrong or a record is truncated, a
+    /// [`MacError::ParameterMismatch`] if the store is for other parameters,
+    /// or another [`MacError`] if a record cannot be decoded.
+    pub fn from_bytes(
+        bytes: &[u8],
+        system_parameters: &SystemParameters,
+    ) -> Result<CredentialStore, MacError>
+    {
+        if bytes.len() < CREDENTIAL_STORE_HEADER_LENGTH + 32 {
+            return Err(MacError::CorruptCredentialStore);
+        }
+
+        let (body, checksum) = bytes.split_at(bytes.len() - 32);
+        let mut tmp = [0u8; 4];
+
+        if &body[0..4] != CREDENTIAL_STORE_MAGIC {
+            return Err(MacError::CorruptCredentialStore);
+        }
+
+        tmp.copy_from_slice(&body[4..8]);
+
+        if u32::from_le_bytes(tmp) != CREDENTIAL_STORE_VERSION {
+            return Err(MacError::CorruptCredentialStore);
+        }
+        if credential_store_checksum(body)[..] != checksum[..] {
+            return Err(MacError::CorruptCredentialStore);
+        }
+
+        let mut parameters_identifier = [0u8; 32];
+
+        parameters_identifier.copy_from_slice(&body[12..44]);
+
+        if parameters_identifier != system_parameters.identifier() {
+            return Err(MacError::ParameterMismatch);
+        }
+
+        tmp.copy_from_slice(&body[8..12]);
+
+        let number_of_records = u32::from_le_bytes(tmp);
+        let mut store = CredentialStore { parameters_identifier, credentials: Vec::new() };
+        let mut index: usize = CREDENTIAL_STORE_HEADER_LENGTH;
+
+        for _ in 0..number_of_records {
+            if body.len() < index + 4 {
+                return Err(MacError::CorruptCredentialStore);
+            }
+
+            tmp.copy_from_slice(&body[index..index+4]); index += 4;
+
+            let length = u32::from_le_bytes(tmp) as usize;
+
+            if body.len() < index + length {
+                return Err(MacError::CorruptCredentialStore);
+            }
+
+            let (attributes, amac) = from_tag_record(&body[index..index+length])?; index += length;
+
+            store.credentials.push(TaggedCredential { tag: Tag(amac), attributes });
+        }
+
+        if index != body.len() {
+            return Err(MacError::CorruptCredentialStore);
+        }
+        Ok(store)
+    }
+}
+
+/// Verify an algebraic MAC on some attributes, where every input is
+/// serialised, e.g. as received over FFI or from a message bus.
+///
+/// # Inputs
+///
+/// * The serialised [`SystemParameters`] and [`SecretKey`],
+/// * The `attributes_bytes`, as a little-endian `u32` number of attributes
+///   followed by each attribute as a one byte type (`0` for a
+///   [`Attribute::PublicScalar`], `1` for a [`Attribute::SecretScalar`], `2`
+///   for a [`Attribute::PublicPoint`], `3` for an [`Attribute::EitherPoint`],
+///   and `4` for an [`Attribute::SecretPoint`]) and 32 bytes of data,
+/// * The 96 serialised bytes of the algebraic MAC.
+///
+/// # Returns
+///
+/// A `Result` whose `Ok` value is empty if the algebraic MAC is valid,
+/// otherwise a [`CredentialError`].
+pub fn verify_bytes(
+    params_bytes: &[u8],
+    key_bytes: &[u8],
+    attributes_bytes: &[u8],
+    amac_bytes: &[u8],
+) -> Result<(), CredentialError>
+{
+    let system_parameters = SystemParameters::from_bytes(params_bytes)?;
+    let secret_key = SecretKey::from_bytes(key_bytes)?;
+    let mut attributes = attributes_from_bytes(attributes_bytes)?;
+    let amac = Amac::from_bytes(amac_bytes)?;
+    let result = amac.verify(&system_parameters, &secret_key, &attributes);
+
+    attributes.zeroize();
+
+    Ok(result?)
+}
+
+/// Read from `reader` until `buffer` is full or the end of the stream.
+///
+/// # Returns
+///
+/// The number of bytes read, otherwise an `io::Error`.
+#[cfg(feature = "std")]
+fn read_fully<R: Read>(reader: &mut R, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut filled: usize = 0;
+
+    while filled < buffer.len() {
+        match reader.read(&mut buffer[filled..]) {
+            Ok(0)  => break,
+            Ok(n)  => filled += n,
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+/// An iterator over the verification results of the tag records in a stream.
+#[cfg(feature = "std")]
+struct VerifyStream<'a, R: Read> {
+    reader: R,
+    system_parameters: &'a SystemParameters,
+    secret_key: &'a SecretKey,
+    done: bool,
+}
+
+#[cfg(feature = "std")]
+impl<'a, R: Read> VerifyStream<'a, R> {
+    /// Stop iterating, yielding a [`MacError::StreamReadError`].
+    fn fail(&mut self) -> Option<Result<(), MacError>> {
+        self.done = true;
+        Some(Err(MacError::StreamReadError))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a, R: Read> Iterator for VerifyStream<'a, R> {
+    type Item = Result<(), MacError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let mut tmp = [0u8; 4];
+
+        match read_fully(&mut self.reader, &mut tmp) {
+            Ok(0) => { self.done = true; return None; },
+            Ok(4) => (),
+            _     => return self.fail(),
+        }
+
+        let length = u32::from_le_bytes(tmp) as usize;
+        let expected = sizeof_tag_record(self.system_parameters.NUMBER_OF_ATTRIBUTES);
+
+        // Skip over records of the wrong size without buffering them.
+        if length != expected {
+            let mut record = (&mut self.reader).take(length as u64);
+
+            return match io::copy(&mut record, &mut io::sink()) {
+                Ok(n) if n == length as u64 => Some(Err(MacError::MessageLengthError{ length: expected })),
+                _ => self.fail(),
+            };
+        }
+
+        let mut record: Vec<u8> = vec![0u8; length];
+
+        if self.reader.read_exact(&mut record).is_err() {
+            return self.fail();
+        }
+
+        let result = match from_tag_record(&record) {
+            Ok((attributes, amac)) => amac.verify(self.system_parameters, self.secret_key, &attributes),
+            Err(error) => Err(error),
+        };
+
+        // The record may contain hidden attributes.
+        record.zeroize();
+
+        Some(result)
+    }
+}
+
+/// Verify a stream of tag records, as written by
+/// [`TaggedCredential::write_record`], one at a time without reading them all
+/// into memory.
+///
+/// Each record is a little-endian `u32` length followed by the number of
+/// attributes, the attributes, and the algebraic MAC upon them.
+///
+/// # Inputs
+///
+/// * A `reader` from which to read the records,
+/// * The `system_parameters` and `secret_key` with which the tags were made.
+///
+/// # Returns
+///
+/// An iterator yielding, for each record, a `Result` whose `Ok` value is
+/// empty if the tag was valid, otherwise a [`MacError`].  Records with the
+/// wrong number of attributes are skipped with a
+/// [`MacError::MessageLengthError`].  If reading from the stream fails, or it
+/// ends part way through a record, a [`MacError::StreamReadError`] is yielded
+/// and iteration stops.
+#[cfg(feature = "std")]
+pub fn verify_stream<'a, R: Read + 'a>(
+    reader: R,
+    system_parameters: &'a SystemParameters,
+    secret_key: &'a SecretKey,
+) -> impl Iterator<Item = Result<(), MacError>> + 'a
+{
+    VerifyStream { reader, system_parameters, secret_key, done: false }
+}
+
+/// Domain separator for deriving the key of an [`AmacEnvelope`] binding from a
+/// [`SecretKey`].
+const ENVELOPE_DOMAIN_SEPARATOR: &[u8] = b"aeonflux amacs::AmacEnvelope binding key v1";
+
+/// A [`Tag`] together with some associated data, such as an expiry or an
+/// audience, which is not one of its attributes.
+///
+/// The binding is HMAC-SHA512/256, under a key derived from the issuer's
+/// [`SecretKey`], over the serialised tag and the associated data, so that
+/// the associated data cannot be swapped between tags undetected.
+#[derive(Clone, Debug)]
+pub struct AmacEnvelope {
+    tag: Tag,
+    aad: Vec<u8>,
+    binding: [u8; 32],
+}
+
+impl AmacEnvelope {
+    /// Compute the HMAC binding some `aad` to an `amac`.
+    fn mac(secret_key: &SecretKey, amac: &Amac, aad: &[u8]) -> Hmac<Sha512Trunc256> {
+        let mut hash = Sha512Trunc256::default();
+
+        secret_key.check_poison();
+
+        // Only the secret scalars are hashed, so that binding the key to some
+        // system parameters doesn't change the envelopes it seals.
+        hash.input(ENVELOPE_DOMAIN_SEPARATOR);
+        hash.input((secret_key.y.len() as u32).to_le_bytes());
+        hash.input(secret_key.w.as_bytes());
+        hash.input(secret_key.w_prime.as_bytes());
+        hash.input(secret_key.x_0.as_bytes());
+        hash.input(secret_key.x_1.as_bytes());
+
+        for y in secret_key.y.iter() {
+            hash.input(y.as_bytes());
+        }
+
+        let mut key = hash.result();
+        // This can't fail, since HMAC accepts keys of any length.
+        let mut mac = Hmac::<Sha512Trunc256>::new_varkey(&key).unwrap();
+
+        key.zeroize();
+
+        // The serialised AMAC has a fixed length, so the aad needn't be
+        // length-prefixed.
+        mac.input(&amac.to_bytes());
+        mac.input(aad);
+        mac
+    }
+
+    /// Bind some associated data `aad` to a `tag`.
+    pub fn seal(secret_key: &SecretKey, tag: Tag, aad: &[u8]) -> AmacEnvelope {
+        let mut binding = [0u8; 32];
+
+        binding.copy_from_slice(&AmacEnvelope::mac(secret_key, &tag.0, aad).result().code());
+
+        AmacEnvelope { tag, aad: aad.to_vec(), binding }
+    }
+
+    /// The associated data, which should not be trusted until the envelope
+    /// has been opened.
+    pub fn aad(&self) -> &[u8] {
+        &self.aad[..]
+    }
+
+    /// Check that this envelope's associated data is bound to its tag.
+    ///
+    /// This does not verify the tag itself, which must still be done
+    /// w.r.t. its attributes.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the tag, otherwise a
+    /// [`MacError::AuthenticationError`] if either the tag or the associated
+    /// data were altered.
+    pub fn open(&self, secret_key: &SecretKey) -> Result<&Tag, MacError> {
+        match AmacEnvelope::mac(secret_key, &self.tag.0, &self.aad).verify(&self.binding) {
+            Ok(_)  => Ok(&self.tag),
+            Err(_) => Err(MacError::AuthenticationError),
+        }
+    }
+}
+
+/// Generate an arbitrary, almost certainly invalid, algebraic MAC.
+#[cfg(feature = "arbitrary")]
+impl<'a> Arbitrary<'a> for Amac {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Amac> {
+        Ok(Amac {
+            t: arbitrary_scalar(u)?,
+            U: arbitrary_point(u)?,
+            V: arbitrary_point(u)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::issuer::issue_with_signer;
+    use crate::user::CredentialRequestConstructor;
+
+    use rand::thread_rng;
+
+    #[test]
+    fn secret_key_generate() {
+        let mut rng = thread_rng();
+        let params = SystemParameters::generate(&mut rng, 2).unwrap();
+        let sk = SecretKey::generate(&mut rng, &params);
+
+        assert!(sk.w != Scalar::zero());
+    }
+
+    /// A broken RNG which produces zeroes for its first `zeroes` outputs.
+    struct StuckRng {
+        zeroes: usize,
+    }
+
+    impl RngCore for StuckRng {
+        fn next_u32(&mut self) -> u32 { rand_core::impls::next_u32_via_fill(self) }
+        fn next_u64(&mut self) -> u64 { rand_core::impls::next_u64_via_fill(self) }
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            if self.zeroes == 0 {
+                return thread_rng().fill_bytes(dest);
+            }
+            for byte in dest.iter_mut() {
+                *byte = 0;
+            }
+            self.zeroes -= 1;
+        }
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+            self.fill_bytes(dest);
+            Ok(())
+        }
+    }
+
+    impl CryptoRng for StuckRng {}
+
+    #[test]
+    fn secret_key_generate_stuck_rng() {
+        let mut rng = thread_rng();
+        let params = SystemParameters::generate(&mut rng, 2).unwrap();
+        let sk = SecretKey::generate(&mut StuckRng { zeroes: 8 }, &params);
+
+        for x in [sk.w, sk.w_prime, sk.x_0, sk.x_1].iter().chain(sk.y.iter()) {
+            assert!(*x != Scalar::zero());
+        }
+    }
+
+    #[cfg(feature = "debug-poison")]
+    #[test]
+    #[should_panic(expected = "use of a zeroized SecretKey")]
+    fn secret_key_poisoned_after_zeroize() {
+        let mut rng = thread_rng();
+        let params = SystemParameters::generate(&mut rng, 1).unwrap();
+        let mut sk = SecretKey::generate(&mut rng, &params);
+        let mut attributes = Vec::new();
+
+        attributes.push(Attribute::PublicScalar(Scalar::random(&mut rng)));
+
+        sk.zeroize();
+
+        let _ = Amac::tag(&mut rng, &params, &sk, &attributes);
+    }
+
+    #[test]
+    fn secret_key_generate_dyn() {
+        let mut rng = thread_rng();
+        let params = SystemParameters::generate(&mut rng, 2).unwrap();
+        let csprng: &mut dyn CryptoRngCore = &mut rng;
+        let sk = SecretKey::generate_dyn(csprng, &params);
+
+        assert_eq!(sk.y.len(), 2);
+        assert_eq!(sk.W, params.G_w * sk.w);
+    }
+
+    #[test]
+    fn secret_key_generate_nondegenerate() {
+        let mut rng = thread_rng();
+        let params = SystemParameters::generate(&mut rng, 2).unwrap();
+        let sk = SecretKey::generate(&mut rng, &params);
+
+        for x in [sk.w, sk.w_prime, sk.x_0, sk.x_1].iter().chain(sk.y.iter()) {
+            assert!(*x != Scalar::zero());
+            assert!(*x != Scalar::one());
+        }
+    }
+
+    #[test]
+    fn secret_key_self_test() {
+        let mut rng = thread_rng();
+        let params = SystemParameters::generate(&mut rng, 3).unwrap();
+        let mut sk = SecretKey::generate(&mut rng, &params);
+
+        assert!(sk.self_test(&params, &mut rng).is_ok());
+
+        sk.W = RistrettoPoint::random(&mut rng);
+
+        assert_eq!(sk.self_test(&params, &mut rng).unwrap_err(), MacError::SelfTestFailure);
+    }
+
+    #[test]
+    fn secret_key_split_and_reconstruct() {
+        let mut rng = thread_rng();
+        let params = SystemParameters::generate(&mut rng, 3).unwrap();
+        let sk = SecretKey::generate(&mut rng, &params);
+        let shares = sk.split(3, 5, &mut rng).unwrap();
+
+        assert_eq!(shares.len(), 5);
+
+        for subset in [[0, 1, 2], [0, 2, 4], [4, 3, 1], [1, 2, 3]].iter() {
+            let chosen: Vec<KeyShare> = subset.iter().map(|i| shares[*i].clone()).collect();
+            let reconstructed = SecretKey::reconstruct(&chosen, &params).unwrap();
+
+            assert_eq!(reconstructed.to_bytes(), sk.to_bytes());
         }
-        Err(MacError::AuthenticationError)
+
+        let too_few: Vec<KeyShare> = shares[..2].to_vec();
+
+        assert_eq!(SecretKey::reconstruct(&too_few, &params).unwrap_err(), MacError::KeyShareError);
+        assert!(interpolate_at_zero(&too_few, |share| share.w) != sk.w);
+
+        // The shares of a 3-attribute key must not reconstruct a key for
+        // parameters with any other number of attributes.
+        let other_params = SystemParameters::generate(&mut rng, 2).unwrap();
+
+        assert_eq!(SecretKey::reconstruct(&shares[..3], &other_params).unwrap_err(), MacError::KeyShareError);
+    }
+
+    #[test]
+    fn secret_key_split_bad_threshold() {
+        let mut rng = thread_rng();
+        let params = SystemParameters::generate(&mut rng, 2).unwrap();
+        let sk = SecretKey::generate(&mut rng, &params);
+
+        assert_eq!(sk.split(0, 3, &mut rng).unwrap_err(), MacError::KeyShareError);
+        assert_eq!(sk.split(4, 3, &mut rng).unwrap_err(), MacError::KeyShareError);
+    }
+
+    #[cfg(feature = "arbitrary")]
+    #[test]
+    fn arbitrary_secret_key_roundtrip() {
+        let mut rng = thread_rng();
+        let mut data = [0u8; 2048];
+
+        for _ in 0..32 {
+            rng.fill_bytes(&mut data);
+
+            let mut u = Unstructured::new(&data);
+            let params = SystemParameters::arbitrary(&mut u).unwrap();
+            let sk = SecretKey::arbitrary_for(&mut u, &params).unwrap();
+
+            assert_eq!(sk.y.len(), params.NUMBER_OF_ATTRIBUTES as usize);
+            assert_eq!(sk.W, params.G_w * sk.w);
+
+            let sk_prime = SecretKey::from_bytes(&sk.to_bytes()).unwrap();
+
+            assert_eq!(sk_prime.to_bytes(), sk.to_bytes());
+        }
+    }
+
+    #[test]
+    fn secret_key_serialised_number_of_attributes() {
+        let mut rng = thread_rng();
+        let params = SystemParameters::generate(&mut rng, 3).unwrap();
+        let sk = SecretKey::generate(&mut rng, &params);
+        let bytes = sk.to_raw_bytes();
+        let mut count = [0u8; 4];
+
+        count.copy_from_slice(&bytes[..4]);
+
+        assert_eq!(u32::from_le_bytes(count), 3);
+        assert_eq!(bytes.len(), sizeof_secret_key(3));
+        assert_eq!(SecretKey::from_bytes(&bytes).unwrap().y.len(), 3);
+    }
+
+    #[test]
+    fn secret_key_from_bytes_2_attributes() {
+        let mut rng = thread_rng();
+        let params = SystemParameters::generate(&mut rng, 2).unwrap();
+        let sk = SecretKey::generate(&mut rng, &params);
+        let bytes = sk.to_bytes();
+        let sk_prime = SecretKey::from_bytes(&bytes);
+
+        assert!(sk_prime.is_ok());
+    }
+
+    #[test]
+    fn secret_key_roundtrip_preserves_every_scalar() {
+        let mut rng = thread_rng();
+
+        for number_of_attributes in 1..=16 {
+            let params = SystemParameters::generate(&mut rng, number_of_attributes).unwrap();
+
+            for sk in [SecretKey::generate(&mut rng, &params), SecretKey::generate_bound(&mut rng, &params)].iter() {
+                let sk_prime = SecretKey::from_bytes(&sk.to_bytes()).unwrap();
+
+                assert!(sk.w == sk_prime.w && sk.w_prime == sk_prime.w_prime);
+                assert!(sk.x_0 == sk_prime.x_0 && sk.x_1 == sk_prime.x_1);
+                assert_eq!(sk.y, sk_prime.y);
+                assert_eq!(sk.W, sk_prime.W);
+                assert_eq!(sk.parameters_identifier, sk_prime.parameters_identifier);
+                assert!(sk.validate_roundtrip().is_ok());
+                assert!(sk_prime.self_test(&params, &mut rng).is_ok());
+            }
+        }
+    }
+
+    #[test]
+    fn secret_key_validate_roundtrip_detects_corruption() {
+        let mut rng = thread_rng();
+        let params = SystemParameters::generate(&mut rng, 3).unwrap();
+        let mut sk = SecretKey::generate(&mut rng, &params);
+
+        // An unreduced scalar serialises, but can never be deserialised.
+        sk.y[2] = Scalar::from_bits([0xff; 32]);
+
+        assert_eq!(sk.validate_roundtrip(), Err(MacError::ScalarFormatError));
+    }
+
+    #[test]
+    fn secret_key_from_bytes_rejects_malformed_input() {
+        let mut rng = thread_rng();
+        let params = SystemParameters::generate(&mut rng, 2).unwrap();
+        let sk = SecretKey::generate(&mut rng, &params);
+        let body = sk.to_raw_bytes();
+
+        for bytes in wire::malformations(WireType::SecretKey, &body, &mut rng) {
+            let _ = SecretKey::from_bytes(&bytes);
+        }
+
+        // No truncation of a key without a parameters identifier parses.
+        for length in 0..body.len() {
+            assert!(SecretKey::from_raw_bytes(&body[..length]).is_err());
+        }
+
+        // Huge attribute counts are rejected before any lengths are computed.
+        let mut bytes = body.clone();
+
+        bytes[..4].copy_from_slice(&u32::MAX.to_le_bytes());
+
+        assert_eq!(SecretKey::from_raw_bytes(&bytes).err(),
+                   Some(MacError::MessageLengthError{ length: sizeof_secret_key(MAX_NUMBER_OF_ATTRIBUTES) }));
+        assert_eq!(sizeof_secret_key(u32::MAX), 32 * (5 + u32::MAX as usize) + 4);
+    }
+
+    #[test]
+    fn tags_from_bytes_reject_malformed_input() {
+        let mut rng = thread_rng();
+        let params = SystemParameters::generate(&mut rng, 2).unwrap();
+        let sk = SecretKey::generate(&mut rng, &params);
+        let attributes = vec![Attribute::PublicScalar(Scalar::random(&mut rng)),
+                              Attribute::SecretPoint(Plaintext::from(&[7u8; 30]))];
+        let tagged = TaggedCredential::new(&mut rng, &params, &sk, attributes).unwrap();
+
+        for bytes in wire::malformations(WireType::Tag, &tagged.tag().0.to_bytes(), &mut rng) {
+            let _ = Tag::from_bytes(&bytes);
+        }
+
+        let encoded = tagged.to_bytes();
+        let (_, body) = wire::decode(&encoded, WireType::TaggedCredential).unwrap();
+
+        for bytes in wire::malformations(WireType::TaggedCredential, body, &mut rng) {
+            let _ = TaggedCredential::from_bytes(&bytes);
+        }
+    }
+
+    #[cfg(feature = "arbitrary")]
+    #[test]
+    fn arbitrary_secret_keys_roundtrip() {
+        let mut rng = thread_rng();
+        let mut data = [0u8; 4096];
+
+        for _ in 0..16 {
+            rng.fill_bytes(&mut data);
+
+            let mut u = Unstructured::new(&data);
+
+            if let Ok(sk) = SecretKey::arbitrary(&mut u) {
+                assert_eq!(SecretKey::from_bytes(&sk.to_bytes()).unwrap().to_bytes(), sk.to_bytes());
+            }
+        }
+    }
+
+    #[test]
+    fn secret_key_sizeof() {
+        let mut rng = thread_rng();
+        let params = SystemParameters::generate(&mut rng, 2).unwrap();
+        let sk = SecretKey::generate(&mut rng, &params);
+        let sizeof = sizeof_secret_key(2);
+        let serialised = sk.to_raw_bytes();
+
+        // We use 4 bytes for storing the number of attributes.
+        assert!(sizeof == serialised.len(), "{} != {}", sizeof, serialised.len());
+    }
+
+    #[test]
+    fn secret_key_backup_roundtrip() {
+        let mut rng = thread_rng();
+        let params = SystemParameters::generate(&mut rng, 1).unwrap();
+        let sk = SecretKey::generate(&mut rng, &params);
+        let backup = sk.to_backup(b"correct horse battery staple", &mut rng);
+        let sk_prime = SecretKey::from_backup(&backup, b"correct horse battery staple").unwrap();
+
+        assert_eq!(sk.to_bytes(), sk_prime.to_bytes());
+    }
+
+    #[test]
+    fn public_tag_wrappers() {
+        let mut rng = thread_rng();
+        let params = SystemParameters::generate(&mut rng, 2).unwrap();
+        let sk = SecretKey::generate(&mut rng, &params);
+        let attributes = vec![Attribute::PublicScalar(Scalar::random(&mut rng)),
+                              Attribute::PublicPoint(RistrettoPoint::random(&mut rng))];
+
+        let tagged = TaggedCredential::new(&mut rng, &params, &sk, attributes.clone()).unwrap();
+        let tag = Tag::from_bytes(&tagged.tag().to_bytes()).unwrap();
+        let tagged_prime = TaggedCredential::from_bytes(&tagged.to_bytes()).unwrap();
+
+        assert!(tag.verify(&params, &sk, &attributes).is_ok());
+        assert!(tagged_prime.verify(&params, &sk).is_ok());
+        assert_eq!(tagged_prime.tag(), tagged.tag());
+        assert_eq!(message_points(&attributes, &params)[1], tagged.attributes()[1].message_point(1, &params));
+        assert_eq!(sizeof_secret_key(2), sk.to_raw_bytes().len());
+
+        // A tag is not a credential, and vice versa.
+        assert!(Tag::from_bytes(&tagged.to_bytes()).is_err());
+        assert!(TaggedCredential::from_bytes(&tag.to_bytes()).is_err());
+    }
+
+    #[test]
+    fn secret_key_seal_roundtrip() {
+        let mut rng = thread_rng();
+        let params = SystemParameters::generate(&mut rng, 1).unwrap();
+        let sk = SecretKey::generate(&mut rng, &params);
+        let key = [7u8; 32];
+
+        let sealed = sk.seal(SealingKey::Key(&key), &mut rng);
+        let unsealed = SecretKey::unseal(&sealed, SealingKey::Key(&key)).unwrap();
+
+        assert_eq!(sk.to_bytes(), unsealed.to_bytes());
+        assert!(SecretKey::unseal(&sealed, SealingKey::Key(&[8u8; 32])).is_err());
+        assert!(SecretKey::unseal(&sealed, SealingKey::Passphrase(&key)).is_err());
+
+        let mut sealed = sk.seal(SealingKey::Passphrase(b"correct horse battery staple"), &mut rng);
+        let unsealed = SecretKey::unseal(&sealed, SealingKey::Passphrase(b"correct horse battery staple")).unwrap();
+
+        assert_eq!(sk.to_bytes(), unsealed.to_bytes());
+
+        sealed[1] ^= 0x01;
+
+        assert_eq!(SecretKey::unseal(&sealed, SealingKey::Passphrase(b"correct horse battery staple")).unwrap_err(),
+                   MacError::BackupAuthenticationError);
+    }
+
+    #[test]
+    fn secret_key_backup_tampered() {
+        let mut rng = thread_rng();
+        let params = SystemParameters::generate(&mut rng, 1).unwrap();
+        let sk = SecretKey::generate(&mut rng, &params);
+        let mut backup = sk.to_backup(b"correct horse battery staple", &mut rng);
+        let last = backup.len() - 1;
+
+        backup[last] ^= 0x01;
+
+        let sk_prime = SecretKey::from_backup(&backup, b"correct horse battery staple");
+
+        assert_eq!(sk_prime.unwrap_err(), CredentialError::BackupAuthenticationError);
+    }
+
+    #[test]
+    fn attribute_reveal() {
+        let mut rng = thread_rng();
+        let m = Scalar::random(&mut rng);
+        let P: Plaintext = (&[7u8; 30]).into();
+        let M1 = P.M1;
+
+        match Attribute::SecretScalar(m).reveal() {
+            Attribute::PublicScalar(x) => assert_eq!(x, m),
+            _ => panic!("SecretScalar did not reveal to a PublicScalar"),
+        }
+        match Attribute::SecretPoint(P.clone()).reveal() {
+            Attribute::PublicPoint(X) => assert_eq!(X, M1),
+            _ => panic!("SecretPoint did not reveal to a PublicPoint"),
+        }
+        match Attribute::EitherPoint(P).reveal() {
+            Attribute::PublicPoint(X) => assert_eq!(X, M1),
+            _ => panic!("EitherPoint did not reveal to a PublicPoint"),
+        }
+        match Attribute::PublicScalar(m).reveal() {
+            Attribute::PublicScalar(x) => assert_eq!(x, m),
+            _ => panic!("PublicScalar changed when revealed"),
+        }
+    }
+
+    #[test]
+    fn attribute_hide() {
+        let mut rng = thread_rng();
+        let m = Scalar::random(&mut rng);
+        let P: Plaintext = (&[7u8; 30]).into();
+
+        match Attribute::PublicScalar(m).hide().unwrap() {
+            Attribute::SecretScalar(x) => assert_eq!(x, m),
+            _ => panic!("PublicScalar did not hide to a SecretScalar"),
+        }
+        match &Attribute::EitherPoint(P.clone()).hide().unwrap() {
+            Attribute::SecretPoint(X) => assert_eq!(X, &P),
+            _ => panic!("EitherPoint did not hide to a SecretPoint"),
+        }
+        match Attribute::SecretScalar(m).hide().unwrap() {
+            Attribute::SecretScalar(x) => assert_eq!(x, m),
+            _ => panic!("SecretScalar changed when hidden"),
+        }
+    }
+
+    #[test]
+    fn attribute_hide_public_point() {
+        let mut rng = thread_rng();
+        let hidden = Attribute::PublicPoint(RistrettoPoint::random(&mut rng)).hide();
+
+        assert_eq!(hidden.unwrap_err(), MacError::UnhideableAttribute);
+    }
+
+    #[test]
+    fn tag_batch() {
+        let mut rng = thread_rng();
+        let params = SystemParameters::generate(&mut rng, 2).unwrap();
+        let sk = SecretKey::generate(&mut rng, &params);
+        let attributes: Vec<Vec<Attribute>> = (0..4).map(|_| vec![
+            Attribute::PublicScalar(Scalar::random(&mut rng)),
+            Attribute::PublicPoint(RistrettoPoint::random(&mut rng)),
+        ]).collect();
+        let borrowed: Vec<&[Attribute]> = attributes.iter().map(|a| &a[..]).collect();
+
+        let tags = Amac::tag_batch(&mut rng, &params, &sk, &borrowed).unwrap();
+
+        assert_eq!(tags.len(), 4);
+
+        for (tag, a) in tags.iter().zip(attributes.iter()) {
+            assert!(tag.verify(&params, &sk, a).is_ok());
+        }
+
+        let short: Vec<&[Attribute]> = vec![&attributes[0][..1]];
+
+        assert!(Amac::tag_batch(&mut rng, &params, &sk, &short).is_err());
+    }
+
+    #[cfg(feature = "debug-poison")]
+    #[test]
+    #[should_panic(expected = "use of a zeroized SecretKey")]
+    fn tag_batch_poisoned_after_zeroize() {
+        let mut rng = thread_rng();
+        let params = SystemParameters::generate(&mut rng, 1).unwrap();
+        let mut sk = SecretKey::generate(&mut rng, &params);
+        let attributes = vec![Attribute::PublicScalar(Scalar::random(&mut rng))];
+
+        sk.zeroize();
+
+        let _ = Amac::tag_batch(&mut rng, &params, &sk, &[&attributes[..]]);
+    }
+
+    #[test]
+    fn string_attributes_roundtrip() {
+        let email = b"an.unreasonably.long.address+with.a.tag@subdomain.example.com";
+        let attributes = Attribute::secret_string(&email[..]);
+
+        assert_eq!(attributes.len(), sizeof_string_attribute(email.len()));
+        assert_eq!(attributes.len(), 3);
+        assert_eq!(*Attribute::string_from_attributes(&attributes).unwrap(), email.to_vec());
+
+        let empty = Attribute::public_string(&[]);
+
+        assert_eq!(empty.len(), 1);
+        assert!(Attribute::string_from_attributes(&empty).unwrap().is_empty());
+        assert_eq!(Attribute::string_from_attributes(&attributes[..2]).unwrap_err(),
+                   MacError::MessageLengthError{ length: 3 });
+    }
+
+    #[test]
+    fn attribute_conditionally_reveal() {
+        let mut rng = thread_rng();
+        let m = Scalar::random(&mut rng);
+        let P: Plaintext = (&[7u8; 30]).into();
+
+        match Attribute::SecretScalar(m).conditionally_reveal(Choice::from(1)) {
+            EncryptedAttribute::PublicScalar(x) => assert_eq!(x, m),
+            _ => panic!("SecretScalar was not revealed"),
+        }
+        match Attribute::SecretScalar(m).conditionally_reveal(Choice::from(0)) {
+            EncryptedAttribute::SecretScalar => (),
+            _ => panic!("SecretScalar was not hidden"),
+        }
+        match Attribute::EitherPoint(P.clone()).conditionally_reveal(Choice::from(1)) {
+            EncryptedAttribute::PublicPoint(X) => assert_eq!(X, P.M1),
+            _ => panic!("EitherPoint was not revealed"),
+        }
+        match Attribute::EitherPoint(P.clone()).conditionally_reveal(Choice::from(0)) {
+            EncryptedAttribute::SecretPoint => (),
+            _ => panic!("EitherPoint was not hidden"),
+        }
+        match Attribute::PublicPoint(P.M1).conditionally_reveal(Choice::from(0)) {
+            EncryptedAttribute::PublicPoint(X) => assert_eq!(X, P.M1),
+            _ => panic!("PublicPoint was hidden"),
+        }
+    }
+
+    #[test]
+    fn tagged_credential_batch_verification_with_cache() {
+        let mut rng = thread_rng();
+        let params = SystemParameters::generate(&mut rng, 3).unwrap();
+        let sk = SecretKey::generate(&mut rng, &params);
+        let shared = Scalar::random(&mut rng);
+        let mut batch = Vec::new();
+
+        for _ in 0..4 {
+            let mut attributes = Vec::new();
+
+            attributes.push(Attribute::PublicScalar(shared));
+            attributes.push(Attribute::SecretScalar(Scalar::random(&mut rng)));
+            attributes.push(Attribute::PublicPoint(RistrettoPoint::random(&mut rng)));
+
+            batch.push(TaggedCredential::new(&mut rng, &params, &sk, attributes).unwrap());
+        }
+
+        let mut cache = MessageCache::new(&params, 16);
+
+        assert!(TaggedCredential::batch_verify_with_cache(&params, &sk, &batch, &mut cache).is_ok());
+
+        // M_0 is computed once and shared, and each M_1 is computed separately.
+        assert_eq!(cache.computations(), 1 + 4);
+        assert_eq!(cache.len(), 1 + 4);
+
+        batch[2].tag.0.V = RistrettoPoint::random(&mut rng);
+
+        assert!(TaggedCredential::batch_verify_with_cache(&params, &sk, &batch, &mut cache).is_err());
+    }
+
+    #[test]
+    fn message_cache_is_bound_to_parameters() {
+        let mut rng = thread_rng();
+        let params = SystemParameters::generate(&mut rng, 1).unwrap();
+        let other_params = SystemParameters::generate(&mut rng, 1).unwrap();
+        let sk = SecretKey::generate(&mut rng, &params);
+        let m = Scalar::random(&mut rng);
+        let mut cache = MessageCache::new(&other_params, 16);
+
+        let _ = cache.get_or_compute(0, &m, &other_params);
+
+        let credential = TaggedCredential::new(&mut rng, &params, &sk, vec![Attribute::SecretScalar(m)]).unwrap();
+
+        assert_eq!(TaggedCredential::batch_verify_with_cache(&params, &sk, &[credential], &mut cache).unwrap_err(),
+                   MacError::ParameterMismatch);
+    }
+
+    #[test]
+    fn message_cache_is_bounded() {
+        let mut rng = thread_rng();
+        let params = SystemParameters::generate(&mut rng, 1).unwrap();
+        let mut cache = MessageCache::new(&params, 2);
+
+        for _ in 0..5 {
+            let m = Scalar::random(&mut rng);
+
+            assert_eq!(cache.get_or_compute(0, &m, &params), m * params.G_m[0]);
+        }
+
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.computations(), 5);
+    }
+
+    #[test]
+    fn public_scalar_in() {
+        let allowed = [Scalar::from(1u64), Scalar::from(2u64), Scalar::from(3u64)];
+
+        assert!(EncryptedAttribute::PublicScalar(Scalar::from(2u64)).public_scalar_in(&allowed));
+        assert!(!EncryptedAttribute::PublicScalar(Scalar::from(4u64)).public_scalar_in(&allowed));
+        assert!(!EncryptedAttribute::SecretScalar.public_scalar_in(&allowed));
+        assert!(!EncryptedAttribute::PublicScalar(Scalar::from(2u64)).public_scalar_in(&[]));
+    }
+
+    #[test]
+    fn disclosed_attributes_against_allowlists() {
+        let tiers = [Scalar::from(1u64), Scalar::from(2u64)];
+        let roles = [Scalar::from(10u64)];
+        let constraints: [Option<&[Scalar]>; 3] = [Some(&tiers[..]), None, Some(&roles[..])];
+        let mut presented = vec![
+            EncryptedAttribute::PublicScalar(Scalar::from(2u64)),
+            EncryptedAttribute::SecretPoint,
+            EncryptedAttribute::PublicScalar(Scalar::from(10u64)),
+        ];
+
+        assert!(check_disclosed_against(&presented, &constraints).is_ok());
+
+        presented[2] = EncryptedAttribute::PublicScalar(Scalar::from(11u64));
+
+        assert_eq!(check_disclosed_against(&presented, &constraints).unwrap_err(),
+                   MacError::DisallowedAttribute{ index: 2 });
+
+        presented[0] = EncryptedAttribute::SecretScalar;
+
+        assert_eq!(check_disclosed_against(&presented, &constraints).unwrap_err(),
+                   MacError::DisallowedAttribute{ index: 0 });
+        assert_eq!(check_disclosed_against(&presented[..2], &constraints).unwrap_err(),
+                   MacError::MessageLengthError{ length: 3 });
+    }
+
+    #[test]
+    fn messages_compressed() {
+        let mut rng = thread_rng();
+        let params = SystemParameters::generate(&mut rng, 3).unwrap();
+        let mut attributes = Vec::new();
+
+        attributes.push(Attribute::PublicScalar(Scalar::random(&mut rng)));
+        attributes.push(Attribute::SecretScalar(Scalar::random(&mut rng)));
+        attributes.push(Attribute::PublicPoint(RistrettoPoint::random(&mut rng)));
+
+        let mut messages = Messages::from_attributes(&attributes, &params);
+
+        assert!(messages.1.is_none());
+
+        let first = messages.compressed().as_ptr();
+
+        for (M_i, compressed) in messages.0.clone().iter().zip(messages.compressed().iter()) {
+            assert_eq!(M_i.compress(), *compressed);
+        }
+
+        // The second call reuses the compressions from the first.
+        assert_eq!(first, messages.compressed().as_ptr());
+    }
+
+    #[test]
+    fn slot_binding() {
+        let mut rng = thread_rng();
+        let schema = AttributeSchema(vec![AttributeKind::PublicScalar,
+                                          AttributeKind::PublicPoint,
+                                          AttributeKind::SecretScalar]);
+        let scalar = EncryptedAttribute::PublicScalar(Scalar::random(&mut rng));
+        let point = EncryptedAttribute::PublicPoint(RistrettoPoint::random(&mut rng));
+
+        let presented = vec![scalar, point, EncryptedAttribute::SecretScalar];
+
+        assert!(check_slot_binding(&presented, &schema).is_ok());
+
+        let swapped = vec![point, scalar, EncryptedAttribute::SecretScalar];
+
+        assert_eq!(check_slot_binding(&swapped, &schema).unwrap_err(),
+                   MacError::AttributeSlotMismatch{ index: 0 });
+        assert_eq!(check_slot_binding(&swapped[..2], &schema).unwrap_err(),
+                   MacError::MessageLengthError{ length: 3 });
     }
-}
 
-#[cfg(test)]
-mod test {
-    use super::*;
+    #[test]
+    fn amac_verification_with_plaintext_attribute() {
+        let mut rng = thread_rng();
+        let params = SystemParameters::generate(&mut rng, 2).unwrap();
+        let sk = SecretKey::generate(&mut rng, &params);
+        let mut attributes = Vec::new();
 
-    use rand::thread_rng;
+        attributes.push(Attribute::SecretScalar(Scalar::random(&mut rng)));
+        attributes.push(Attribute::PublicScalar(Scalar::random(&mut rng)));
+
+        let amac = Amac::tag(&mut rng, &params, &sk, &attributes).unwrap();
+
+        assert!(amac.verify(&params, &sk, &attributes).is_ok());
+    }
 
     #[test]
-    fn secret_key_generate() {
+    fn amac_tag_from_iterator_verify_from_slice() {
+        let mut rng = thread_rng();
+        let params = SystemParameters::generate(&mut rng, 4).unwrap();
+        let sk = SecretKey::generate(&mut rng, &params);
+        let owned = [
+            Attribute::SecretScalar(Scalar::random(&mut rng)),
+            Attribute::PublicPoint(RistrettoPoint::random(&mut rng)),
+        ];
+        let borrowed = [
+            Attribute::PublicScalar(Scalar::random(&mut rng)),
+            Attribute::SecretPoint((&[5u8; 30]).into()),
+        ];
+        let source = AttributeIterator(owned.iter().chain(borrowed[..].iter()));
+
+        assert_eq!(MessageSource::len(&source), 4);
+
+        let amac = Amac::tag(&mut rng, &params, &sk, &source).unwrap();
+        let attributes: Vec<Attribute> = owned.iter().chain(borrowed.iter()).cloned().collect();
+
+        assert!(amac.verify(&params, &sk, &attributes[..]).is_ok());
+        assert!(amac.verify(&params, &sk, &attributes[1..]).is_err());
+    }
+
+    #[test]
+    fn tag_message_commitment() {
+        let mut rng = thread_rng();
+        let params = SystemParameters::generate(&mut rng, 3).unwrap();
+        let sk = SecretKey::generate(&mut rng, &params);
+        let mut attributes = Vec::new();
+
+        attributes.push(Attribute::SecretScalar(Scalar::random(&mut rng)));
+        attributes.push(Attribute::PublicPoint(RistrettoPoint::random(&mut rng)));
+        attributes.push(Attribute::EitherPoint((&[1u8; 30]).into()));
+
+        let tag = Tag::new(&mut rng, &params, &sk, &attributes).unwrap();
+        let commitment = Tag::message_commitment(&params, &sk, &attributes).unwrap();
+
+        assert_eq!(sk.W + tag.0.U * (sk.x_0 + sk.x_1 * tag.0.t) + commitment, tag.0.V);
+        assert!(Tag::message_commitment(&params, &sk, &attributes[..2]).is_err());
+    }
+
+    #[test]
+    fn tag_new_padded() {
+        let mut rng = thread_rng();
+        let params = SystemParameters::generate(&mut rng, 4).unwrap();
+        let sk = SecretKey::generate(&mut rng, &params);
+        let mut attributes = Vec::new();
+
+        attributes.push(Attribute::SecretScalar(Scalar::random(&mut rng)));
+        attributes.push(Attribute::PublicPoint(RistrettoPoint::random(&mut rng)));
+
+        let tag = Tag::new_padded(&mut rng, &params, &sk, &attributes, PadPolicy::ZeroScalar).unwrap();
+
+        assert!(tag.verify_padded(&params, &sk, &attributes, PadPolicy::ZeroScalar).is_ok());
+        assert!(tag.verify(&params, &sk, &attributes).is_err());
+
+        attributes.push(PadPolicy::ZeroScalar.attribute());
+        attributes.push(PadPolicy::ZeroScalar.attribute());
+
+        assert!(tag.verify(&params, &sk, &attributes).is_ok());
+
+        attributes.push(PadPolicy::ZeroScalar.attribute());
+
+        assert!(Tag::new_padded(&mut rng, &params, &sk, &attributes, PadPolicy::IdentityPoint).is_err());
+    }
+
+    #[test]
+    fn tag_verify_counted() {
+        let mut rng = thread_rng();
+        let params = SystemParameters::generate(&mut rng, 4).unwrap();
+        let sk = SecretKey::generate(&mut rng, &params);
+        let mut attributes = Vec::new();
+
+        attributes.push(Attribute::SecretScalar(Scalar::random(&mut rng)));
+        attributes.push(Attribute::PublicScalar(Scalar::random(&mut rng)));
+        attributes.push(Attribute::PublicPoint(RistrettoPoint::random(&mut rng)));
+        attributes.push(Attribute::SecretPoint((&[3u8; 30]).into()));
+
+        let mut tag = Tag::new(&mut rng, &params, &sk, &attributes).unwrap();
+
+        assert_eq!(tag.verify_counted(&params, &sk, &attributes), Ok(4));
+
+        tag.0.V = RistrettoPoint::random(&mut rng);
+
+        assert_eq!(tag.verify_counted(&params, &sk, &attributes).unwrap_err(),
+                   tag.verify(&params, &sk, &attributes).unwrap_err());
+    }
+
+    #[test]
+    fn amac_verify_ct() {
+        let mut rng = thread_rng();
+        let params = SystemParameters::generate(&mut rng, 3).unwrap();
+        let sk = SecretKey::generate(&mut rng, &params);
+        let mut attributes = Vec::new();
+
+        attributes.push(Attribute::PublicScalar(Scalar::random(&mut rng)));
+        attributes.push(Attribute::SecretScalar(Scalar::random(&mut rng)));
+        attributes.push(Attribute::PublicPoint(RistrettoPoint::random(&mut rng)));
+
+        let amac = Amac::tag(&mut rng, &params, &sk, &attributes).unwrap();
+
+        assert!(bool::from(amac.verify_ct(&params, &sk, &attributes)));
+
+        for i in 0..attributes.len() {
+            let mut modified = attributes.clone();
+
+            modified[i] = Attribute::PublicScalar(Scalar::random(&mut rng));
+
+            assert!(!bool::from(amac.verify_ct(&params, &sk, &modified)));
+            assert_eq!(amac.verify(&params, &sk, &modified).unwrap_err(), MacError::AuthenticationError);
+        }
+    }
+
+    #[test]
+    fn amac_serialized_length() {
         let mut rng = thread_rng();
         let params = SystemParameters::generate(&mut rng, 2).unwrap();
         let sk = SecretKey::generate(&mut rng, &params);
+        let mut attributes = Vec::new();
 
-        assert!(sk.w != Scalar::zero());
+        attributes.push(Attribute::SecretScalar(Scalar::random(&mut rng)));
+        attributes.push(Attribute::PublicScalar(Scalar::random(&mut rng)));
+
+        let amac = Amac::tag(&mut rng, &params, &sk, &attributes).unwrap();
+        let mut bytes = amac.to_bytes();
+
+        assert_eq!(bytes.len(), Amac::SERIALIZED_LEN);
+        assert_eq!(Amac::from_bytes(&bytes).unwrap(), amac);
+        assert_eq!(Amac::from_bytes(&bytes[..95]).unwrap_err(),
+                   MacError::MessageLengthError{ length: Amac::SERIALIZED_LEN });
+
+        bytes.push(0);
+
+        assert_eq!(Amac::from_bytes(&bytes).unwrap_err(),
+                   MacError::MessageLengthError{ length: Amac::SERIALIZED_LEN });
     }
 
     #[test]
-    fn secret_key_from_bytes_2_attributes() {
+    fn amac_ct_eq() {
         let mut rng = thread_rng();
         let params = SystemParameters::generate(&mut rng, 2).unwrap();
         let sk = SecretKey::generate(&mut rng, &params);
-        let bytes = sk.to_bytes();
-        let sk_prime = SecretKey::from_bytes(&bytes);
+        let mut attributes = Vec::new();
 
-        assert!(sk_prime.is_ok());
+        attributes.push(Attribute::SecretScalar(Scalar::random(&mut rng)));
+        attributes.push(Attribute::PublicScalar(Scalar::random(&mut rng)));
+
+        let amac = Amac::tag(&mut rng, &params, &sk, &attributes).unwrap();
+        let r = Scalar::random(&mut rng);
+        let rerandomised = Amac { t: amac.t, U: amac.U * r, V: amac.V * r };
+
+        assert!(bool::from(amac.ct_eq(&amac.clone())));
+        assert_eq!(amac, amac.clone());
+        assert!(!bool::from(amac.ct_eq(&rerandomised)));
+        assert!(amac != rerandomised);
     }
 
     #[test]
-    fn secret_key_sizeof() {
+    fn tag_tag_id() {
         let mut rng = thread_rng();
         let params = SystemParameters::generate(&mut rng, 2).unwrap();
         let sk = SecretKey::generate(&mut rng, &params);
-        let sizeof = sizeof_secret_key(2);
-        let serialised = sk.to_bytes();
+        let mut attributes = Vec::new();
 
-        // We use 4 bytes for storing the number of attributes.
-        assert!(sizeof == serialised.len(), "{} != {}", sizeof, serialised.len());
+        attributes.push(Attribute::SecretScalar(Scalar::random(&mut rng)));
+        attributes.push(Attribute::PublicScalar(Scalar::random(&mut rng)));
+
+        let tag = Tag::new(&mut rng, &params, &sk, &attributes).unwrap();
+        let r = Scalar::random(&mut rng);
+        let rerandomised = Tag(Amac { t: tag.0.t, U: tag.0.U * r, V: tag.0.V * r });
+
+        assert_eq!(tag.tag_id(), tag.clone().tag_id());
+        assert!(tag.tag_id() != rerandomised.tag_id());
     }
 
     #[test]
-    fn amac_verification_with_plaintext_attribute() {
+    fn tag_verify_against_precomputed_V() {
         let mut rng = thread_rng();
         let params = SystemParameters::generate(&mut rng, 2).unwrap();
         let sk = SecretKey::generate(&mut rng, &params);
@@ -362,9 +3971,271 @@ mod test {
         attributes.push(Attribute::SecretScalar(Scalar::random(&mut rng)));
         attributes.push(Attribute::PublicScalar(Scalar::random(&mut rng)));
 
+        let tag = Tag::new(&mut rng, &params, &sk, &attributes).unwrap();
+        let V_prime = tag.compute_V(&params, &sk, &attributes).unwrap();
+
+        assert!(tag.verify_against(&V_prime).is_ok());
+        assert!(tag.verify_against(&RistrettoPoint::random(&mut rng)).is_err());
+        assert!(tag.compute_V(&params, &sk, &attributes[..1]).is_err());
+    }
+
+    #[test]
+    fn amac_verify_stream() {
+        let mut rng = thread_rng();
+        let params = SystemParameters::generate(&mut rng, 3).unwrap();
+        let sk = SecretKey::generate(&mut rng, &params);
+        let mut stream: Vec<u8> = Vec::new();
+        let mut offsets: Vec<usize> = Vec::new();
+
+        for _ in 0..3 {
+            let mut attributes = Vec::new();
+
+            attributes.push(Attribute::SecretScalar(Scalar::random(&mut rng)));
+            attributes.push(Attribute::PublicPoint(RistrettoPoint::random(&mut rng)));
+            attributes.push(Attribute::SecretPoint((&[9u8; 30]).into()));
+
+            let credential = TaggedCredential::new(&mut rng, &params, &sk, attributes).unwrap();
+
+            offsets.push(stream.len());
+            credential.write_record(&mut stream).unwrap();
+        }
+
+        // Corrupt the hidden scalar attribute of the second record.
+        stream[offsets[1] + 9] ^= 1;
+
+        let results: Vec<Result<(), MacError>> = verify_stream(&stream[..], &params, &sk).collect();
+
+        assert_eq!(results.len(), 3);
+        assert!(results[0].is_ok());
+        assert_eq!(results[1], Err(MacError::AuthenticationError));
+        assert!(results[2].is_ok());
+    }
+
+    #[test]
+    fn amac_verify_stream_truncated() {
+        let mut rng = thread_rng();
+        let params = SystemParameters::generate(&mut rng, 1).unwrap();
+        let sk = SecretKey::generate(&mut rng, &params);
+        let mut attributes = Vec::new();
+
+        attributes.push(Attribute::PublicScalar(Scalar::random(&mut rng)));
+
+        let credential = TaggedCredential::new(&mut rng, &params, &sk, attributes).unwrap();
+        let mut stream: Vec<u8> = Vec::new();
+
+        credential.write_record(&mut stream).unwrap();
+        credential.write_record(&mut stream).unwrap();
+        stream.truncate(stream.len() - 1);
+
+        let results: Vec<Result<(), MacError>> = verify_stream(&stream[..], &params, &sk).collect();
+
+        assert_eq!(results, vec![Ok(()), Err(MacError::StreamReadError)]);
+    }
+
+    #[test]
+    fn tag_strict_rejects_mismatched_parameters() {
+        let mut rng = thread_rng();
+        let params = SystemParameters::generate(&mut rng, 2).unwrap();
+        let other = SystemParameters::generate(&mut rng, 2).unwrap();
+        let sk = SecretKey::generate_bound(&mut rng, &params);
+        let mut attributes = Vec::new();
+
+        attributes.push(Attribute::PublicScalar(Scalar::random(&mut rng)));
+        attributes.push(Attribute::SecretScalar(Scalar::random(&mut rng)));
+
+        let tag = Tag::new_strict(&mut rng, &params, &sk, &attributes).unwrap();
+
+        assert!(tag.verify_strict(&params, &sk, &attributes).is_ok());
+        assert_eq!(Tag::new_strict(&mut rng, &other, &sk, &attributes).unwrap_err(),
+                   MacError::ParameterMismatch);
+        assert_eq!(tag.verify_strict(&other, &sk, &attributes).unwrap_err(),
+                   MacError::ParameterMismatch);
+
+        let unbound = SecretKey::generate(&mut rng, &params);
+
+        assert_eq!(Tag::new_strict(&mut rng, &params, &unbound, &attributes).unwrap_err(),
+                   MacError::ParameterMismatch);
+
+        let deserialized = SecretKey::from_bytes(&sk.to_bytes()).unwrap();
+
+        assert!(deserialized.check_parameters(&params).is_ok());
+        assert!(SecretKey::from_bytes(&unbound.to_bytes()).unwrap().parameters_identifier.is_none());
+    }
+
+    #[test]
+    fn decode_scalar_wipes_scratch() {
+        let mut rng = thread_rng();
+        let mut scratch: Zeroizing<[u8; 32]> = Zeroizing::new([0u8; 32]);
+        let x = Scalar::random(&mut rng);
+
+        assert_eq!(decode_scalar(&mut scratch, x.as_bytes()), Some(x));
+        assert_eq!(*scratch, [0u8; 32]);
+
+        // Non-canonical scalars must also be wiped.
+        assert_eq!(decode_scalar(&mut scratch, &[0xffu8; 32]), None);
+        assert_eq!(*scratch, [0u8; 32]);
+    }
+
+    fn credential_store_with_records(params: &SystemParameters, sk: &SecretKey, n: usize) -> CredentialStore {
+        let mut rng = thread_rng();
+        let mut store = CredentialStore::new(params);
+
+        for _ in 0..n {
+            let mut attributes = Vec::new();
+
+            attributes.push(Attribute::SecretScalar(Scalar::random(&mut rng)));
+            attributes.push(Attribute::PublicPoint(RistrettoPoint::random(&mut rng)));
+
+            store.push(TaggedCredential::new(&mut rng, params, sk, attributes).unwrap());
+        }
+        store
+    }
+
+    #[test]
+    fn credential_store_round_trip() {
+        let mut rng = thread_rng();
+        let params = SystemParameters::generate(&mut rng, 2).unwrap();
+        let sk = SecretKey::generate(&mut rng, &params);
+        let store = credential_store_with_records(&params, &sk, 100);
+        let restored = CredentialStore::from_bytes(&store.to_bytes(), &params).unwrap();
+
+        assert_eq!(restored.len(), 100);
+
+        for (credential, original) in restored.credentials().iter().zip(store.credentials().iter()) {
+            assert_eq!(credential.tag(), original.tag());
+            assert!(credential.verify(&params, &sk).is_ok());
+        }
+
+        let other = SystemParameters::generate(&mut rng, 2).unwrap();
+
+        assert_eq!(CredentialStore::from_bytes(&store.to_bytes(), &other).err(),
+                   Some(MacError::ParameterMismatch));
+    }
+
+    #[test]
+    fn credential_store_corrupted_checksum() {
+        let mut rng = thread_rng();
+        let params = SystemParameters::generate(&mut rng, 2).unwrap();
+        let sk = SecretKey::generate(&mut rng, &params);
+        let mut bytes = credential_store_with_records(&params, &sk, 3).to_bytes();
+        let last = bytes.len() - 1;
+
+        bytes[last] ^= 1;
+
+        assert_eq!(CredentialStore::from_bytes(&bytes, &params).err(),
+                   Some(MacError::CorruptCredentialStore));
+    }
+
+    #[test]
+    fn tagged_credential_batch_verify() {
+        let mut rng = thread_rng();
+        let params = SystemParameters::generate(&mut rng, 3).unwrap();
+        let sk = SecretKey::generate(&mut rng, &params);
+        let mut credentials = Vec::new();
+
+        for _ in 0..10 {
+            let mut attributes = Vec::new();
+
+            attributes.push(Attribute::SecretScalar(Scalar::random(&mut rng)));
+            attributes.push(Attribute::PublicPoint(RistrettoPoint::random(&mut rng)));
+            attributes.push(Attribute::SecretPoint((&[9u8; 30]).into()));
+
+            credentials.push(TaggedCredential::new(&mut rng, &params, &sk, attributes).unwrap());
+        }
+
+        assert!(TaggedCredential::batch_verify(&mut rng, &params, &sk, &[]).is_ok());
+        assert!(TaggedCredential::batch_verify(&mut rng, &params, &sk, &credentials[..]).is_ok());
+
+        credentials[4].attributes[0] = Attribute::SecretScalar(Scalar::random(&mut rng));
+
+        assert_eq!(TaggedCredential::batch_verify(&mut rng, &params, &sk, &credentials[..]).unwrap_err(),
+                   MacError::AuthenticationError);
+        assert!(credentials[4].verify(&params, &sk).is_err());
+
+        credentials[4].attributes.pop();
+
+        assert_eq!(TaggedCredential::batch_verify(&mut rng, &params, &sk, &credentials[..]).unwrap_err(),
+                   MacError::MessageLengthError{ length: 3 });
+    }
+
+    #[test]
+    fn amac_verify_bytes() {
+        let mut rng = thread_rng();
+        let params = SystemParameters::generate(&mut rng, 1).unwrap();
+        let sk = SecretKey::generate(&mut rng, &params);
+        let mut attributes = Vec::new();
+
+        attributes.push(Attribute::SecretScalar(Scalar::random(&mut rng)));
+
         let amac = Amac::tag(&mut rng, &params, &sk, &attributes).unwrap();
+        let params_bytes = params.to_bytes();
+        let key_bytes = sk.to_bytes();
+        let mut attributes_bytes = attributes_to_bytes(&attributes);
+        let amac_bytes = amac.to_bytes();
 
-        assert!(amac.verify(&params, &sk, &attributes).is_ok());
+        assert!(verify_bytes(&params_bytes, &key_bytes, &attributes_bytes, &amac_bytes).is_ok());
+        assert_eq!(verify_bytes(&params_bytes, &key_bytes, &attributes_bytes, &amac_bytes[1..]).unwrap_err(),
+                   CredentialError::Mac(MacError::MessageLengthError{ length: Amac::SERIALIZED_LEN }));
+
+        attributes_bytes[5] ^= 1;
+
+        assert_eq!(verify_bytes(&params_bytes, &key_bytes, &attributes_bytes, &amac_bytes).unwrap_err(),
+                   CredentialError::Mac(MacError::AuthenticationError));
+    }
+
+    #[test]
+    fn attribute_from_record_rejects_malformed_records() {
+        let P: Plaintext = (&[7u8; 30]).into();
+        let mut record = attributes_to_bytes(&[Attribute::SecretPoint(P)])[4..].to_vec();
+
+        assert!(attribute_from_record(&record).is_ok());
+
+        record[32] = 1;
+
+        assert_eq!(attribute_from_record(&record).unwrap_err(), MacError::AttributeTypeError);
+
+        record[32] = 0;
+        record[0] = 5;
+
+        assert_eq!(attribute_from_record(&record).unwrap_err(), MacError::AttributeTypeError);
+    }
+
+    #[test]
+    fn amac_envelope() {
+        let mut rng = thread_rng();
+        let params = SystemParameters::generate(&mut rng, 2).unwrap();
+        let sk = SecretKey::generate(&mut rng, &params);
+        let mut attributes = Vec::new();
+
+        attributes.push(Attribute::SecretScalar(Scalar::random(&mut rng)));
+        attributes.push(Attribute::PublicScalar(Scalar::random(&mut rng)));
+
+        let tag = Tag::new(&mut rng, &params, &sk, &attributes).unwrap();
+        let mut envelope = AmacEnvelope::seal(&sk, tag, b"expires 2020-12-31");
+
+        assert_eq!(envelope.aad(), b"expires 2020-12-31");
+        assert!(envelope.open(&sk).unwrap().verify(&params, &sk, &attributes).is_ok());
+
+        envelope.aad = b"expires 2099-12-31".to_vec();
+
+        assert_eq!(envelope.open(&sk).unwrap_err(), MacError::AuthenticationError);
+    }
+
+    #[test]
+    fn amac_envelope_ignores_parameters_binding() {
+        let mut rng = thread_rng();
+        let params = SystemParameters::generate(&mut rng, 1).unwrap();
+        let sk = SecretKey::generate_bound(&mut rng, &params);
+        let mut unbound = sk.clone();
+
+        unbound.parameters_identifier = None;
+
+        let attributes = vec![Attribute::SecretScalar(Scalar::random(&mut rng))];
+        let tag = Tag::new(&mut rng, &params, &sk, &attributes).unwrap();
+        let envelope = AmacEnvelope::seal(&sk, tag.clone(), b"audience");
+
+        assert_eq!(envelope.binding, AmacEnvelope::seal(&unbound, tag, b"audience").binding);
+        assert!(envelope.open(&unbound).is_ok());
     }
 
     #[test]
@@ -391,4 +4262,55 @@ mod test {
 
         assert!(amac.verify(&params, &sk, &messages).is_ok());
     }
+
+    /// A signer which forwards to a key it holds elsewhere, as an HSM would,
+    /// or which has failed.
+    struct ExternalSigner(Option<SecretKey>);
+
+    impl MacSigner for ExternalSigner {
+        fn compute_V(&self, messages: &[RistrettoPoint], t: &Scalar, U: &RistrettoPoint) -> Result<RistrettoPoint, MacError> {
+            self.0.as_ref().ok_or(MacError::SignerFailure)?.compute_V(messages, t, U)
+        }
+
+        fn prove_issuance(
+            &self,
+            system_parameters: &SystemParameters,
+            issuer_parameters: &IssuerParameters,
+            credential: &AnonymousCredential,
+            transcript: &mut Transcript,
+        ) -> Result<ProofOfIssuance, MacError>
+        {
+            let proof = self.0.as_ref().ok_or(MacError::SignerFailure)?
+                .prove_issuance(system_parameters, issuer_parameters, credential, transcript)?;
+
+            ProofOfIssuance::from_bytes(&proof.to_bytes(), credential.attributes.len()).or(Err(MacError::SignerFailure))
+        }
+    }
+
+    #[test]
+    fn issue_with_external_signer() {
+        let mut rng = thread_rng();
+        let params = SystemParameters::generate(&mut rng, 2).unwrap();
+        let sk = SecretKey::generate(&mut rng, &params);
+        let issuer_parameters = IssuerParameters::generate(&params, &sk);
+        let M = RistrettoPoint::random(&mut rng);
+        let request = || {
+            let mut request = CredentialRequestConstructor::new(&params);
+
+            request.append_revealed_scalar(Scalar::one());
+            request.append_revealed_point(M);
+            request.finish()
+        };
+        let signer = ExternalSigner(Some(sk));
+        let mut transcript = Transcript::new(b"2019/1416 anonymous credential");
+        let issuance = issue_with_signer(&params, &issuer_parameters, &signer, request(), &mut transcript, &mut rng).unwrap();
+        let credential = issuance.verify(&params, &issuer_parameters).unwrap();
+
+        assert!(credential.amac.verify(&params, signer.0.as_ref().unwrap(), &credential.attributes).is_ok());
+
+        let mut transcript = Transcript::new(b"2019/1416 anonymous credential");
+        let failed = issue_with_signer(&params, &issuer_parameters, &ExternalSigner(None), request(), &mut transcript, &mut rng);
+
+        assert!(matches!(failed, Err(CredentialError::Mac(MacError::SignerFailure))));
+    }
 }
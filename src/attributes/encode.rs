@@ -0,0 +1,201 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2020 The Brave Authors
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Canonical, injective encodings of common types into scalars, for use as
+//! [`Attribute::PublicScalar`] or [`Attribute::SecretScalar`] attributes.
+//!
+//! Integers are encoded as numbers below \\(( 2^{64} \\)), such that their
+//! order is preserved, and they may be proven within a range with a
+//! [`RangeStatement`] whose bounds are encoded with [`range_bound_u64`] or
+//! [`range_bound_i64`].  An `i64` is offset by \\(( 2^{63} \\)), and a UNIX
+//! timestamp is encoded as an `i64` number of seconds, so an unsigned integer
+//! and a signed integer or timestamp of the same slot may share an encoding.
+//!
+//! UUIDs and short ASCII strings are instead encoded as numbers of at least
+//! \\(( 2^{240} \\)), whose second-most significant byte is a tag of their
+//! type, so that they never collide with an integer, or with each other.
+//!
+//! Each `decode_*` function inverts its `encode_*` function, and returns
+//! `None` for any scalar which is not the encoding of a value of its type.
+//!
+//! [`Attribute::PublicScalar`]: crate::amacs::Attribute::PublicScalar
+//! [`Attribute::SecretScalar`]: crate::amacs::Attribute::SecretScalar
+//! [`RangeStatement`]: crate::nizk::range::RangeStatement
+
+use curve25519_dalek::scalar::Scalar;
+
+/// The index of the type tag of a tagged encoding.
+const TAG_INDEX: usize = 30;
+
+/// The type tag of an encoded UUID.
+const TAG_UUID: u8 = 1;
+
+/// The type tag of an encoded ASCII string.
+const TAG_ASCII: u8 = 2;
+
+/// The maximum length, in bytes, of an ASCII string which may be encoded.
+pub const MAX_ASCII_LENGTH: usize = 29;
+
+/// Encode an unsigned integer as the scalar of the same value.
+///
+/// This is the same encoding as for an [`Attribute::expiry`](crate::amacs::Attribute::expiry).
+pub fn encode_u64(value: u64) -> Scalar {
+    Scalar::from(value)
+}
+
+/// Decode an unsigned integer encoded with [`encode_u64`].
+pub fn decode_u64(scalar: &Scalar) -> Option<u64> {
+    let bytes = scalar.as_bytes();
+
+    if bytes[8..].iter().any(|byte| *byte != 0) {
+        return None;
+    }
+
+    let mut tmp = [0u8; 8];
+
+    tmp.copy_from_slice(&bytes[..8]);
+
+    Some(u64::from_le_bytes(tmp))
+}
+
+/// The bound of a [`RangeStatement`](crate::nizk::range::RangeStatement)
+/// on an unsigned integer encoded with [`encode_u64`].
+pub fn range_bound_u64(value: u64) -> u64 {
+    value
+}
+
+/// The bound of a [`RangeStatement`](crate::nizk::range::RangeStatement)
+/// on a signed integer encoded with [`encode_i64`], i.e. the integer offset
+/// by \\(( 2^{63} \\)).
+pub fn range_bound_i64(value: i64) -> u64 {
+    (value as u64) ^ (1 << 63)
+}
+
+/// Encode a signed integer, offset by \\(( 2^{63} \\)) so that its order is
+/// preserved.
+pub fn encode_i64(value: i64) -> Scalar {
+    encode_u64(range_bound_i64(value))
+}
+
+/// Decode a signed integer encoded with [`encode_i64`].
+pub fn decode_i64(scalar: &Scalar) -> Option<i64> {
+    decode_u64(scalar).map(|offset| (offset ^ (1 << 63)) as i64)
+}
+
+/// Encode a UNIX timestamp, in seconds, as with [`encode_i64`].
+pub fn encode_timestamp(seconds: i64) -> Scalar {
+    encode_i64(seconds)
+}
+
+/// Decode a UNIX timestamp, in seconds, encoded with [`encode_timestamp`].
+pub fn decode_timestamp(scalar: &Scalar) -> Option<i64> {
+    decode_i64(scalar)
+}
+
+/// Encode the 16 bytes of a UUID.
+pub fn encode_uuid(uuid: &[u8; 16]) -> Scalar {
+    let mut bytes = [0u8; 32];
+
+    bytes[..16].copy_from_slice(uuid);
+    bytes[TAG_INDEX] = TAG_UUID;
+
+    Scalar::from_bits(bytes)
+}
+
+/// Decode the 16 bytes of a UUID encoded with [`encode_uuid`].
+pub fn decode_uuid(scalar: &Scalar) -> Option<[u8; 16]> {
+    let bytes = scalar.as_bytes();
+
+    if bytes[TAG_INDEX] != TAG_UUID || bytes[16..TAG_INDEX].iter().chain(&bytes[TAG_INDEX + 1..]).any(|byte| *byte != 0) {
+        return None;
+    }
+
+    let mut uuid = [0u8; 16];
+
+    uuid.copy_from_slice(&bytes[..16]);
+
+    Some(uuid)
+}
+
+/// Encode an ASCII `string` of at most [`MAX_ASCII_LENGTH`] bytes, along
+/// with its length.
+///
+/// # Returns
+///
+/// The encoding, or `None` if the string is too long or isn't ASCII.
+pub fn encode_ascii(string: &str) -> Option<Scalar> {
+    if string.len() > MAX_ASCII_LENGTH || !string.is_ascii() {
+        return None;
+    }
+
+    let mut bytes = [0u8; 32];
+
+    bytes[..string.len()].copy_from_slice(string.as_bytes());
+    bytes[MAX_ASCII_LENGTH] = string.len() as u8;
+    bytes[TAG_INDEX] = TAG_ASCII;
+
+    Some(Scalar::from_bits(bytes))
+}
+
+/// Decode an ASCII string encoded with [`encode_ascii`], into the buffer
+/// `string`.
+///
+/// # Returns
+///
+/// The decoded string, borrowed from the buffer.
+pub fn decode_ascii<'a>(scalar: &Scalar, string: &'a mut [u8; MAX_ASCII_LENGTH]) -> Option<&'a str> {
+    let bytes = scalar.as_bytes();
+    let length = bytes[MAX_ASCII_LENGTH] as usize;
+
+    if bytes[TAG_INDEX] != TAG_ASCII || bytes[31] != 0 || length > MAX_ASCII_LENGTH {
+        return None;
+    }
+    if bytes[length..MAX_ASCII_LENGTH].iter().any(|byte| *byte != 0) || !bytes[..length].is_ascii() {
+        return None;
+    }
+
+    string[..length].copy_from_slice(&bytes[..length]);
+
+    core::str::from_utf8(&string[..length]).ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn encodings_roundtrip_and_do_not_collide() {
+        let uuid = [0xa5u8; 16];
+        let mut buffer = [0u8; MAX_ASCII_LENGTH];
+
+        assert_eq!(decode_u64(&encode_u64(u64::MAX)), Some(u64::MAX));
+        assert_eq!(decode_i64(&encode_i64(-5)), Some(-5));
+        assert_eq!(decode_i64(&encode_i64(i64::MIN)), Some(i64::MIN));
+        assert_eq!(decode_timestamp(&encode_timestamp(1_600_000_000)), Some(1_600_000_000));
+        assert_eq!(decode_uuid(&encode_uuid(&uuid)), Some(uuid));
+        assert_eq!(decode_ascii(&encode_ascii("alice").unwrap(), &mut buffer), Some("alice"));
+        assert_eq!(decode_ascii(&encode_ascii("").unwrap(), &mut buffer), Some(""));
+
+        // Signed integers keep their order, for range proofs.
+        assert!(range_bound_i64(-1) < range_bound_i64(0));
+        assert_eq!(encode_i64(0), encode_u64(range_bound_i64(0)));
+
+        // Trailing NULs are part of the string, and tagged types are distinct.
+        assert_ne!(encode_ascii("a"), encode_ascii("a\0"));
+        assert_eq!(decode_u64(&encode_uuid(&uuid)), None);
+        assert_eq!(decode_uuid(&encode_u64(7)), None);
+        assert_eq!(decode_ascii(&encode_uuid(&uuid), &mut buffer), None);
+        assert_eq!(decode_uuid(&encode_ascii("a").unwrap()), None);
+
+        // Long or non-ASCII strings are unencodable.
+        assert!(encode_ascii(&"x".repeat(MAX_ASCII_LENGTH)).is_some());
+        assert!(encode_ascii(&"x".repeat(MAX_ASCII_LENGTH + 1)).is_none());
+        assert!(encode_ascii("café").is_none());
+    }
+}
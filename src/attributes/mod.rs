@@ -0,0 +1,13 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2020 The Brave Authors
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Helpers for constructing credential attributes from application data.
+
+pub mod encode;
+pub mod schema;
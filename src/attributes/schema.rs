@@ -0,0 +1,336 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2020 The Brave Authors
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Credential schemas which name each attribute.
+//!
+//! A [`Schema`] maps named fields, e.g. `"age"` or `"country"`, to attribute
+//! indices and the [`AttributeKind`] each is expected to have upon
+//! presentation.  Attribute vectors may then be built by name with an
+//! [`AttributesBuilder`], and checked against the schema upon issuance and
+//! presentation, rather than relying upon every party to agree on the index
+//! of each attribute.
+//!
+//! Schemas whose fields are known at compile time may instead be declared
+//! with the [`attribute_schema!`](crate::attribute_schema) macro, whose
+//! generated `named_schema` function returns the equivalent [`Schema`].
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+
+use crate::amacs::check_slot_binding;
+use crate::amacs::Attribute;
+use crate::amacs::AttributeKind;
+use crate::amacs::AttributeSchema;
+use crate::amacs::EncryptedAttribute;
+use crate::errors::MacError;
+use crate::nizk::presentation::ProofOfValidCredential;
+use crate::parameters::SystemParameters;
+use crate::symmetric::Plaintext;
+use crate::user::CredentialRequest;
+
+/// Whether an attribute `kind` is a scalar, rather than a group element.
+fn is_scalar(kind: AttributeKind) -> bool {
+    matches!(kind, AttributeKind::PublicScalar | AttributeKind::SecretScalar)
+}
+
+/// A credential schema, i.e. an ordered list of named attributes and the
+/// [`AttributeSchema`] of the [`AttributeKind`]s they are expected to have.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Schema {
+    names: Vec<&'static str>,
+    kinds: AttributeSchema,
+}
+
+impl Schema {
+    /// Create a schema of the named `fields`, in the order of the
+    /// attributes.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the schema, otherwise a
+    /// [`MacError::DuplicateAttribute`] if two fields have the same name.
+    pub fn new(fields: &[(&'static str, AttributeKind)]) -> Result<Schema, MacError> {
+        let mut names: Vec<&'static str> = Vec::with_capacity(fields.len());
+
+        for (name, _) in fields.iter() {
+            if names.contains(name) {
+                return Err(MacError::DuplicateAttribute);
+            }
+            names.push(name);
+        }
+
+        Ok(Schema { names, kinds: AttributeSchema(fields.iter().map(|(_, kind)| *kind).collect()) })
+    }
+
+    /// The number of attributes in this schema.
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Whether this schema has no attributes.
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// The index of the attribute called `name`, if there is one.
+    pub fn index(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|field| *field == name)
+    }
+
+    /// The kind of the attribute called `name`, if there is one.
+    pub fn kind(&self, name: &str) -> Option<AttributeKind> {
+        self.index(name).map(|i| self.kinds.0[i])
+    }
+
+    /// The expected kind of each attribute upon credential presentation, as
+    /// for [`check_slot_binding`].
+    pub fn attribute_schema(&self) -> &AttributeSchema {
+        &self.kinds
+    }
+
+    /// Begin building a vector of attributes for this schema by name.
+    pub fn builder(&self) -> AttributesBuilder<'_> {
+        AttributesBuilder { schema: self, attributes: vec![None; self.len()], error: None }
+    }
+
+    /// Check that some `attributes` to be issued under the
+    /// `system_parameters` are of this schema.
+    ///
+    /// Since the user may hide or reveal a scalar attribute before
+    /// presenting it, only whether each attribute is a scalar or a group
+    /// element is checked, along with the number of attributes.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is empty, otherwise a
+    /// [`MacError::MessageLengthError`] if the number of attributes differs
+    /// from the schema or the `system_parameters`, or a
+    /// [`MacError::AttributeSlotMismatch`] with the index of the first
+    /// attribute of an unexpected type.
+    pub fn check_issuance(
+        &self,
+        system_parameters: &SystemParameters,
+        attributes: &[Attribute],
+    ) -> Result<(), MacError>
+    {
+        if self.len() != system_parameters.NUMBER_OF_ATTRIBUTES as usize || attributes.len() != self.len() {
+            return Err(MacError::MessageLengthError{ length: self.len() });
+        }
+
+        for (i, (attribute, expected)) in attributes.iter().zip(self.kinds.0.iter()).enumerate() {
+            let scalar = matches!(attribute, Attribute::PublicScalar(_) | Attribute::SecretScalar(_));
+
+            if scalar != is_scalar(*expected) {
+                return Err(MacError::AttributeSlotMismatch{ index: i });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check that the attributes of a `presentation` have exactly the kinds
+    /// given by this schema, as in [`check_slot_binding`].
+    pub fn check_presentation(&self, presentation: &ProofOfValidCredential) -> Result<(), MacError> {
+        check_slot_binding(presentation.attributes(), &self.kinds)
+    }
+
+    /// The presented attribute called `name` of a `presentation`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the attribute, otherwise a
+    /// [`MacError::UnknownAttribute`] if this schema has no such field, or a
+    /// [`MacError::MessageLengthError`] if the presentation has too few
+    /// attributes.
+    pub fn presented<'a>(
+        &self,
+        presentation: &'a ProofOfValidCredential,
+        name: &str,
+    ) -> Result<&'a EncryptedAttribute, MacError>
+    {
+        let index = self.index(name).ok_or(MacError::UnknownAttribute)?;
+
+        presentation.attributes().get(index).ok_or(MacError::MessageLengthError{ length: self.len() })
+    }
+}
+
+/// A builder of the attributes of a [`Schema`], by name.
+///
+/// The first error, e.g. from setting an attribute which isn't in the
+/// schema, is returned upon [`AttributesBuilder::build`].
+#[derive(Clone, Debug)]
+pub struct AttributesBuilder<'a> {
+    schema: &'a Schema,
+    attributes: Vec<Option<Attribute>>,
+    error: Option<MacError>,
+}
+
+impl<'a> AttributesBuilder<'a> {
+    /// Set the attribute called `name` to the `attribute` produced by
+    /// `convert` from its kind, if the kind is acceptable.
+    fn set<F>(mut self, name: &str, convert: F) -> AttributesBuilder<'a>
+    where
+        F: FnOnce(AttributeKind) -> Option<Attribute>,
+    {
+        if self.error.is_some() {
+            return self;
+        }
+
+        let index = match self.schema.index(name) {
+            Some(index) => index,
+            None => {
+                self.error = Some(MacError::UnknownAttribute);
+                return self;
+            },
+        };
+
+        match convert(self.schema.kinds.0[index]) {
+            Some(attribute) => self.attributes[index] = Some(attribute),
+            None            => self.error = Some(MacError::AttributeSlotMismatch{ index }),
+        }
+        self
+    }
+
+    /// Set the scalar attribute called `name` to `value`.
+    pub fn scalar(self, name: &str, value: Scalar) -> AttributesBuilder<'a> {
+        self.set(name, |kind| match kind {
+            AttributeKind::PublicScalar => Some(Attribute::PublicScalar(value)),
+            AttributeKind::SecretScalar => Some(Attribute::SecretScalar(value)),
+            _ => None,
+        })
+    }
+
+    /// Set the revealed group element attribute called `name` to `value`.
+    pub fn point(self, name: &str, value: RistrettoPoint) -> AttributesBuilder<'a> {
+        self.set(name, |kind| match kind {
+            AttributeKind::PublicPoint => Some(Attribute::PublicPoint(value)),
+            _ => None,
+        })
+    }
+
+    /// Set the group element attribute called `name` to the encoding of a
+    /// `plaintext`, which may be hidden upon presentation if the schema
+    /// expects a [`AttributeKind::SecretPoint`] or an
+    /// [`AttributeKind::EitherPoint`].
+    pub fn plaintext(self, name: &str, plaintext: &Plaintext) -> AttributesBuilder<'a> {
+        self.set(name, |kind| match kind {
+            AttributeKind::PublicPoint |
+            AttributeKind::EitherPoint => Some(Attribute::EitherPoint(plaintext.clone())),
+            AttributeKind::SecretPoint => Some(Attribute::SecretPoint(plaintext.clone())),
+            _ => None,
+        })
+    }
+
+    /// Finish building the attributes, in the order of the schema.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the attributes, otherwise the first
+    /// error encountered whilst setting them, or a
+    /// [`MacError::AttributeSlotMismatch`] with the index of the first
+    /// attribute which was never set.
+    pub fn build(self) -> Result<Vec<Attribute>, MacError> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+
+        self.attributes.into_iter().enumerate()
+            .map(|(index, attribute)| attribute.ok_or(MacError::AttributeSlotMismatch{ index }))
+            .collect()
+    }
+
+    /// Finish building the attributes, as in [`AttributesBuilder::build`],
+    /// into a [`CredentialRequest`] to be issued under the
+    /// `system_parameters`.
+    pub fn request(self, system_parameters: &SystemParameters) -> Result<CredentialRequest, MacError> {
+        let schema = self.schema;
+        let attributes = self.build()?;
+
+        schema.check_issuance(system_parameters, &attributes)?;
+
+        Ok(CredentialRequest { attributes })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::errors::CredentialError;
+    use crate::issuer::Issuer;
+
+    use rand::thread_rng;
+
+    fn schema() -> Schema {
+        Schema::new(&[("age", AttributeKind::SecretScalar),
+                      ("country", AttributeKind::PublicScalar),
+                      ("key", AttributeKind::PublicPoint)]).unwrap()
+    }
+
+    #[test]
+    fn schema_builds_attributes_by_name() {
+        let mut rng = thread_rng();
+        let schema = schema();
+        let system_parameters = SystemParameters::generate(&mut rng, 3).unwrap();
+        let issuer = Issuer::new(&system_parameters, &mut rng);
+        let key = RistrettoPoint::random(&mut rng);
+
+        // Fields may be set in any order.
+        let request = schema.builder()
+            .point("key", key)
+            .scalar("country", Scalar::from(49u64))
+            .scalar("age", Scalar::from(42u64))
+            .request(&system_parameters).unwrap();
+
+        let issuance = issuer.issue_with_schema(request, &schema, &mut rng).unwrap();
+        let credential = issuance.verify(&system_parameters, &issuer.issuer_parameters).unwrap();
+        let presentation = credential.show(&system_parameters, &issuer.issuer_parameters, None, &mut rng).unwrap();
+
+        assert!(issuer.verify_with_schema(&presentation, &schema).is_ok());
+        assert!(matches!(schema.presented(&presentation, "country").unwrap(),
+                         EncryptedAttribute::PublicScalar(x) if *x == Scalar::from(49u64)));
+        assert!(matches!(schema.presented(&presentation, "age").unwrap(), EncryptedAttribute::SecretScalar));
+        assert_eq!(schema.presented(&presentation, "name").unwrap_err(), MacError::UnknownAttribute);
+
+        // The same credential with all attributes revealed doesn't match the schema.
+        let mut revealed = credential.clone();
+
+        revealed.reveal_attribute(0).unwrap();
+
+        let presentation = revealed.show(&system_parameters, &issuer.issuer_parameters, None, &mut rng).unwrap();
+
+        assert_eq!(issuer.verify_with_schema(&presentation, &schema).unwrap_err(),
+                   CredentialError::Mac(MacError::AttributeSlotMismatch{ index: 0 }));
+    }
+
+    #[test]
+    fn schema_builder_errors() {
+        let schema = schema();
+
+        assert_eq!(schema.index("country"), Some(1));
+        assert_eq!(schema.kind("key"), Some(AttributeKind::PublicPoint));
+        assert_eq!(schema.builder().scalar("name", Scalar::one()).build().unwrap_err(),
+                   MacError::UnknownAttribute);
+        assert_eq!(schema.builder().point("age", RistrettoPoint::default()).build().unwrap_err(),
+                   MacError::AttributeSlotMismatch{ index: 0 });
+        assert_eq!(schema.builder().scalar("age", Scalar::one()).point("key", RistrettoPoint::default()).build()
+                       .unwrap_err(),
+                   MacError::AttributeSlotMismatch{ index: 1 });
+        assert!(schema.check_issuance(&SystemParameters::generate(&mut thread_rng(), 4).unwrap(),
+                                      &[Attribute::PublicScalar(Scalar::one())]).is_err());
+        assert_eq!(Schema::new(&[("age", AttributeKind::SecretScalar), ("age", AttributeKind::PublicScalar)])
+                       .unwrap_err(),
+                   MacError::DuplicateAttribute);
+    }
+}
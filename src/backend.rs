@@ -0,0 +1,122 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2020 The Brave Authors
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Asynchronous issuer backends.
+//!
+//! An [`IssuerBackend`] abstracts where an issuer's secret key is kept and how
+//! it is used to issue credentials and verify presentations, so that servers
+//! may keep their keys in e.g. an HSM or a database, behind an `async`
+//! interface, without forking this crate.  [`MemoryBackend`] is the default
+//! implementation, which keeps an [`Issuer`] in memory.
+
+use std::boxed::Box;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+
+use crate::errors::CredentialError;
+use crate::issuer::CredentialIssuance;
+use crate::issuer::Issuer;
+use crate::nizk::presentation::ProofOfValidCredential;
+use crate::parameters::IssuerParameters;
+use crate::parameters::SystemParameters;
+use crate::rng::CryptoRngCore;
+use crate::user::CredentialRequest;
+
+/// The storage of an issuer's key, and the operations which require it.
+#[async_trait]
+pub trait IssuerBackend: Send + Sync {
+    /// The [`SystemParameters`] under which this backend issues credentials.
+    async fn system_parameters(&self) -> Result<SystemParameters, CredentialError>;
+
+    /// The public [`IssuerParameters`] of this backend's key.
+    async fn issuer_parameters(&self) -> Result<IssuerParameters, CredentialError>;
+
+    /// Issue a new anonymous credential, as in [`Issuer::issue`].
+    async fn issue(&self, request: CredentialRequest) -> Result<CredentialIssuance, CredentialError>;
+
+    /// Verify a user's presentation of an anonymous credential, as in
+    /// [`Issuer::verify`].
+    async fn verify(&self, presentation: &ProofOfValidCredential) -> Result<(), CredentialError>;
+}
+
+/// An [`IssuerBackend`] which keeps its [`Issuer`] in memory.
+pub struct MemoryBackend<R> {
+    issuer: Issuer,
+    csprng: Mutex<R>,
+}
+
+impl<R> MemoryBackend<R>
+where
+    R: CryptoRngCore + Send,
+{
+    /// Create a backend for the `issuer`, which issues credentials with
+    /// randomness from the `csprng`.
+    pub fn new(issuer: Issuer, csprng: R) -> MemoryBackend<R> {
+        MemoryBackend { issuer, csprng: Mutex::new(csprng) }
+    }
+}
+
+#[async_trait]
+impl<R> IssuerBackend for MemoryBackend<R>
+where
+    R: CryptoRngCore + Send,
+{
+    async fn system_parameters(&self) -> Result<SystemParameters, CredentialError> {
+        Ok(self.issuer.system_parameters.clone())
+    }
+
+    async fn issuer_parameters(&self) -> Result<IssuerParameters, CredentialError> {
+        Ok(self.issuer.issuer_parameters.clone())
+    }
+
+    async fn issue(&self, request: CredentialRequest) -> Result<CredentialIssuance, CredentialError> {
+        // A panic whilst the lock was held cannot have left the RNG in an
+        // unusable state, so a poisoned lock is recovered.
+        let mut csprng = self.csprng.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        self.issuer.issue(request, &mut *csprng)
+    }
+
+    async fn verify(&self, presentation: &ProofOfValidCredential) -> Result<(), CredentialError> {
+        self.issuer.verify(presentation)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::user::CredentialRequestConstructor;
+
+    use curve25519_dalek::scalar::Scalar;
+
+    use futures::executor::block_on;
+
+    use rand::rngs::OsRng;
+    use rand::thread_rng;
+
+    #[test]
+    fn memory_backend_issue_and_verify() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 2).unwrap();
+        let backend: Box<dyn IssuerBackend> = Box::new(MemoryBackend::new(Issuer::new(&system_parameters, &mut rng), OsRng));
+        let issuer_parameters = block_on(backend.issuer_parameters()).unwrap();
+        let mut request = CredentialRequestConstructor::new(&block_on(backend.system_parameters()).unwrap());
+
+        request.append_revealed_scalar(Scalar::random(&mut rng));
+        request.append_revealed_scalar(Scalar::random(&mut rng));
+
+        let issuance = block_on(backend.issue(request.finish())).unwrap();
+        let credential = issuance.verify(&system_parameters, &issuer_parameters).unwrap();
+        let presentation = credential.show(&system_parameters, &issuer_parameters, None, &mut rng).unwrap();
+
+        assert!(block_on(backend.verify(&presentation)).is_ok());
+    }
+}
@@ -14,25 +14,370 @@ use alloc::vec::Vec;
 #[cfg(all(not(feature = "alloc"), feature = "std"))]
 use std::vec::Vec;
 
+use core::convert::TryFrom;
+
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "std")]
+use std::io::Read;
+#[cfg(feature = "std")]
+use std::io::Write;
+
+use curve25519_dalek::scalar::Scalar;
+
 use rand_core::CryptoRng;
 use rand_core::RngCore;
 
+use sha2::Digest;
+use sha2::Sha512Trunc256;
+
+use zeroize::Zeroize;
+
+use zkp::Transcript;
+
+use crate::amacs::attribute_from_record;
+use crate::amacs::attributes_to_bytes;
+use crate::amacs::from_tag_record;
+#[cfg(feature = "std")]
+use crate::amacs::sizeof_attributes;
+use crate::amacs::sizeof_tag_record;
+#[cfg(feature = "std")]
+use crate::amacs::write_attributes;
 use crate::amacs::Amac;
 use crate::amacs::Attribute;
+use crate::amacs::ATTRIBUTE_RECORD_LENGTH;
+use crate::attributes::encode::decode_u64;
 use crate::errors::CredentialError;
 use crate::parameters::IssuerParameters;
 use crate::parameters::SystemParameters;
+use crate::parameters::MAX_NUMBER_OF_ATTRIBUTES;
+use crate::nizk::designated::VerifierPublicKey;
+use crate::nizk::escrow::AuditorPublicKey;
+use crate::nizk::escrow::EscrowStatement;
+use crate::nizk::interactive::InteractivePresentation;
+use crate::nizk::interactive::PresentationCommitment;
+use crate::nizk::membership::SetMembershipStatement;
+use crate::nizk::nullifier::NullifierStatement;
+use crate::nizk::presentation::PrecomputedPresentation;
 use crate::nizk::presentation::ProofOfValidCredential;
+use crate::nizk::presentation::Statements;
+use crate::nizk::pseudonym::PseudonymStatement;
+use crate::nizk::range::RangeStatement;
+use crate::nizk::relation::LinearStatement;
+use crate::policy::Policy;
 use crate::symmetric::Keypair as SymmetricKeypair;
+use crate::symmetric::MasterSecret;
+use crate::token;
+use crate::wire;
+#[cfg(feature = "std")]
+use crate::wire::Sink;
+use crate::wire::WireType;
+
+/// A presentation of an [`AnonymousCredential`], comprising rerandomised
+/// commitments to its algebraic MAC and a proof of their validity, to be
+/// checked with [`Issuer::verify_presentation`](crate::issuer::Issuer::verify_presentation).
+pub type Presentation = ProofOfValidCredential;
+
+/// Options controlling which attributes of an [`AnonymousCredential`] are
+/// revealed by [`AnonymousCredential::show_with_options`].
+///
+/// By default, every [`Attribute::EitherPoint`] is hidden, since hiding is
+/// the privacy-preserving choice, and so presenting such a credential
+/// requires a symmetric keypair.  Attributes which should nevertheless be
+/// revealed must be listed explicitly with [`PresentationOptions::reveal`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PresentationOptions {
+    /// Whether to hide [`Attribute::EitherPoint`]s which aren't explicitly
+    /// revealed.  Defaults to `true`.
+    pub hide_either_points: bool,
+    /// The indices of group element attributes to reveal, whether or not
+    /// they are currently hidden.  Defaults to none.
+    pub revealed: Vec<usize>,
+    /// Statements that hidden scalar attributes lie within ranges, to be
+    /// proven in zero-knowledge.  Defaults to none.
+    pub ranges: Vec<RangeStatement>,
+    /// Statements that hidden scalar attributes are members of public sets,
+    /// to be proven in zero-knowledge.  Defaults to none.
+    pub memberships: Vec<SetMembershipStatement>,
+    /// Requests to derive nullifiers from hidden scalar attributes.
+    /// Defaults to none.
+    pub nullifiers: Vec<NullifierStatement>,
+    /// Requests to derive pseudonyms from hidden scalar attributes.
+    /// Defaults to none.
+    pub pseudonyms: Vec<PseudonymStatement>,
+    /// Requests to escrow hidden group element attributes to auditors.
+    /// Defaults to none.
+    pub escrows: Vec<EscrowStatement>,
+    /// Linear relations among hidden scalar attributes, to be proven in
+    /// zero-knowledge.  Defaults to none.
+    pub relations: Vec<LinearStatement>,
+}
+
+impl Default for PresentationOptions {
+    fn default() -> PresentationOptions {
+        PresentationOptions {
+            hide_either_points: true,
+            revealed: Vec::new(),
+            ranges: Vec::new(),
+            memberships: Vec::new(),
+            nullifiers: Vec::new(),
+            pseudonyms: Vec::new(),
+            escrows: Vec::new(),
+            relations: Vec::new(),
+        }
+    }
+}
+
+impl PresentationOptions {
+    /// Explicitly reveal the group element attribute at `index`, overriding
+    /// `hide_either_points`.
+    pub fn reveal(mut self, index: usize) -> PresentationOptions {
+        self.revealed.push(index);
+        self
+    }
+
+    /// Prove that the hidden scalar attribute at `index` lies within the
+    /// inclusive range `[min, max]`, without revealing it.
+    ///
+    /// The statement must be chosen before presenting, since proving it
+    /// requires the opening of the presentation's commitment to the
+    /// attribute.  Verifiers obtain the proven statements with
+    /// [`ProofOfValidCredential::range_statements`].
+    pub fn with_range_statement(mut self, index: usize, min: u64, max: u64) -> PresentationOptions {
+        self.ranges.push(RangeStatement { index, min, max });
+        self
+    }
+
+    /// Prove that the hidden scalar attribute at `index` is at least `min`,
+    /// as with [`PresentationOptions::with_range_statement`].
+    pub fn at_least(self, index: usize, min: u64) -> PresentationOptions {
+        self.with_range_statement(index, min, u64::MAX)
+    }
+
+    /// Prove that the hidden scalar attributes satisfy the linear relation
+    /// given by the `statement`, e.g. [`LinearStatement::sum`], within the
+    /// presentation's proof of knowledge of the credential.
+    ///
+    /// Verifiers obtain the proven statements with
+    /// [`ProofOfValidCredential::linear_statements`].
+    pub fn with_linear_statement(mut self, statement: LinearStatement) -> PresentationOptions {
+        self.relations.push(statement);
+        self
+    }
+
+    /// Prove that the hidden expiry attribute at `index` is no earlier than
+    /// the `current_epoch`, without revealing it, for verification with
+    /// [`Issuer::verify_unexpired`](crate::issuer::Issuer::verify_unexpired).
+    pub fn not_expired(self, index: usize, current_epoch: u64) -> PresentationOptions {
+        self.with_range_statement(index, current_epoch, u64::MAX)
+    }
+
+    /// Prove that the hidden scalar attribute at `index` is equal to one of
+    /// the values in `set`, without revealing which.
+    ///
+    /// As with [`PresentationOptions::with_range_statement`], verifiers
+    /// obtain the proven statements with
+    /// [`ProofOfValidCredential::membership_statements`].
+    pub fn with_membership_statement(mut self, index: usize, set: Vec<Scalar>) -> PresentationOptions {
+        self.memberships.push(SetMembershipStatement { index, set });
+        self
+    }
+
+    /// Derive a [`Nullifier`](crate::nizk::nullifier::Nullifier) from the
+    /// hidden scalar attribute at `index` within the verifier-chosen
+    /// `context`, with which the verifier can detect this credential being
+    /// presented to it again in the same context.
+    ///
+    /// Verifiers obtain the nullifier with
+    /// [`ProofOfValidCredential::nullifier`].
+    pub fn with_nullifier(mut self, index: usize, context: &[u8]) -> PresentationOptions {
+        self.nullifiers.push(NullifierStatement { index, context: context.to_vec() });
+        self
+    }
+
+    /// Derive a [`Pseudonym`](crate::nizk::pseudonym::Pseudonym) from the
+    /// hidden scalar attribute at `index` within the `scope`, e.g. the
+    /// verifier's domain name, which identifies the holder of this credential
+    /// to the verifier each time it is presented in that scope, but is
+    /// unlinkable to its pseudonyms in other scopes.
+    ///
+    /// Verifiers obtain the pseudonym with
+    /// [`ProofOfValidCredential::pseudonym`].
+    pub fn with_pseudonym(mut self, index: usize, scope: &[u8]) -> PresentationOptions {
+        self.pseudonyms.push(PseudonymStatement { index, scope: scope.to_vec() });
+        self
+    }
+
+    /// Show a rate-limiting [`token`](crate::token), whose hidden seed is at
+    /// `seed_index` and whose hidden limit is at `limit_index`, consuming its
+    /// `counter` within the `scope`.
+    ///
+    /// This proves that the `counter` is below the limit, and derives the
+    /// token's serial number for the `counter` within the `scope`, which
+    /// verifiers obtain with [`ProofOfValidCredential::token_serial`].
+    pub fn with_token(self, seed_index: usize, limit_index: usize, scope: &[u8], counter: u64) -> PresentationOptions {
+        let context = token::serial_context(scope, counter);
+
+        self.with_range_statement(limit_index, counter.saturating_add(1), u64::MAX)
+            .with_nullifier(seed_index, &context)
+    }
+
+    /// Escrow the hidden group element attribute at `index` to the
+    /// `auditor`, additionally encrypting it under their key, so that they
+    /// may later decrypt it, e.g. for compliance purposes.  The issuer learns
+    /// nothing more about the attribute, and escrows of the same attribute
+    /// in different presentations are unlinkable.
+    ///
+    /// Verifiers obtain the ciphertext to forward to the auditor with
+    /// [`ProofOfValidCredential::audit_ciphertext`].
+    pub fn with_escrow(mut self, index: usize, auditor: AuditorPublicKey) -> PresentationOptions {
+        self.escrows.push(EscrowStatement { index, auditor });
+        self
+    }
+
+    /// Hide or reveal the group element `attributes` according to these
+    /// options.
+    pub(crate) fn apply(&self, attributes: &mut [Attribute]) {
+        for (i, attribute) in attributes.iter_mut().enumerate() {
+            let reveal = self.revealed.contains(&i);
+
+            *attribute = match attribute {
+                Attribute::EitherPoint(p) if self.hide_either_points && !reveal => Attribute::SecretPoint(p.clone()),
+                Attribute::SecretPoint(p) if reveal => Attribute::EitherPoint(p.clone()),
+                _ => continue,
+            };
+        }
+    }
+
+    /// The statements about hidden attributes requested by these options.
+    pub(crate) fn statements(&self) -> Statements<'_> {
+        Statements {
+            ranges: &self.ranges,
+            memberships: &self.memberships,
+            nullifiers: &self.nullifiers,
+            pseudonyms: &self.pseudonyms,
+            escrows: &self.escrows,
+            relations: &self.relations,
+        }
+    }
+}
 
 /// An anonymous credential.
 #[derive(Clone, Debug)]
 pub struct AnonymousCredential {
     pub(crate) amac: Amac,
     pub(crate) attributes: Vec<Attribute>,
+    /// The [`SystemParameters::identifier`] of the parameters this
+    /// credential was issued under.
+    pub(crate) parameters_fingerprint: [u8; 32],
 }
 
 impl AnonymousCredential {
+    /// The [`SystemParameters::identifier`] of the parameters this
+    /// credential was issued under.
+    pub fn parameters_fingerprint(&self) -> &[u8; 32] {
+        &self.parameters_fingerprint
+    }
+
+    /// A 32-byte identifier of this credential, i.e. a hash of its algebraic
+    /// MAC, which is unique to each issuance.
+    ///
+    /// This is intended for deriving a symmetric keypair for this credential
+    /// alone with [`SymmetricKeypair::derive_for_credential`], and must not
+    /// be revealed upon presentation, since the issuer also knows it.
+    pub fn id(&self) -> [u8; 32] {
+        let mut hash = Sha512Trunc256::default();
+        let mut id = [0u8; 32];
+
+        hash.input(b"aeonflux AnonymousCredential id");
+        hash.input(self.amac.to_bytes());
+        id.copy_from_slice(&hash.result()[..]);
+        id
+    }
+
+    /// Derive the symmetric keypair of this credential, issued under the
+    /// `system_parameters`, from a `master_secret`, as in
+    /// [`SymmetricKeypair::derive_for_credential`].
+    pub fn derive_keypair(
+        &self,
+        master_secret: &MasterSecret,
+        system_parameters: &SystemParameters,
+    ) -> SymmetricKeypair
+    {
+        SymmetricKeypair::derive_for_credential(master_secret, &self.id(), system_parameters)
+    }
+
+    /// Check that this credential was issued under the `system_parameters`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is empty, otherwise a
+    /// [`CredentialError::ParameterMismatch`] if the credential was issued
+    /// under other parameters.
+    pub fn check_parameters(&self, system_parameters: &SystemParameters) -> Result<(), CredentialError> {
+        if self.parameters_fingerprint != system_parameters.identifier() {
+            return Err(CredentialError::ParameterMismatch);
+        }
+        Ok(())
+    }
+
+    /// Pad this credential, issued under the `system_parameters`, with a
+    /// zero [`Attribute::PublicScalar`] for each attribute added by the
+    /// `extended_system_parameters`, so that it may be presented to an issuer
+    /// whose key was extended with [`SecretKey::extend`].
+    ///
+    /// The algebraic MAC is unchanged, since each additional message
+    /// \\(( G_m_i * 0 \\)) is the identity.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the extended credential, otherwise a
+    /// [`CredentialError::ParameterMismatch`] if the credential wasn't issued
+    /// under the `system_parameters` or they aren't extended by the
+    /// `extended_system_parameters`, or a
+    /// [`CredentialError::WrongNumberOfAttributes`].
+    ///
+    /// [`SecretKey::extend`]: crate::amacs::SecretKey::extend
+    pub fn extend(
+        &self,
+        system_parameters: &SystemParameters,
+        extended_system_parameters: &SystemParameters,
+    ) -> Result<AnonymousCredential, CredentialError>
+    {
+        self.check_parameters(system_parameters)?;
+
+        if !extended_system_parameters.extends(system_parameters) {
+            return Err(CredentialError::ParameterMismatch);
+        }
+        if self.attributes.len() != system_parameters.NUMBER_OF_ATTRIBUTES as usize {
+            return Err(CredentialError::WrongNumberOfAttributes);
+        }
+
+        let mut credential = self.clone();
+
+        credential.attributes.resize(extended_system_parameters.NUMBER_OF_ATTRIBUTES as usize,
+                                     Attribute::PublicScalar(Scalar::zero()));
+        credential.parameters_fingerprint = extended_system_parameters.identifier();
+
+        Ok(credential)
+    }
+
+    /// The schema version recorded, with [`Attribute::schema_version`], in
+    /// the scalar attribute at `index`.
+    ///
+    /// # Returns
+    ///
+    /// The version, or `None` if there is no such attribute or it isn't a
+    /// schema version.
+    pub fn schema_version(&self, index: usize) -> Option<u32> {
+        match self.attributes.get(index) {
+            Some(Attribute::PublicScalar(m)) | Some(Attribute::SecretScalar(m)) => {
+                decode_u64(m).and_then(|version| u32::try_from(version).ok())
+            },
+            _ => None,
+        }
+    }
+
     /// Present this credential to an issuer.
     pub fn show(
         &self,
@@ -45,6 +390,242 @@ impl AnonymousCredential {
         ProofOfValidCredential::prove(&system_parameters, &issuer_parameters, &self, keypair, &mut csprng)
     }
 
+    /// Present this credential to an issuer, as in
+    /// [`AnonymousCredential::show`], with a slightly larger proof which the
+    /// issuer may verify along with many others with [`Issuer::verify_batch`].
+    ///
+    /// [`Issuer::verify_batch`]: crate::issuer::Issuer::verify_batch
+    pub fn show_batchable(
+        &self,
+        system_parameters: &SystemParameters,
+        issuer_parameters: &IssuerParameters,
+        keypair: Option<&SymmetricKeypair>,
+        mut csprng: impl CryptoRng + RngCore,
+    ) -> Result<ProofOfValidCredential, CredentialError>
+    {
+        ProofOfValidCredential::prove_batchable(system_parameters, issuer_parameters, self, keypair, &mut csprng)
+    }
+
+    /// Present this credential to an issuer, as in
+    /// [`AnonymousCredential::show`], with a proof which is only convincing
+    /// to the holder of the secret key of the designated `verifier`, so that
+    /// the verifier cannot transfer it to third parties as evidence.
+    ///
+    /// The issuer must verify it with [`Issuer::verify_designated`].
+    ///
+    /// [`Issuer::verify_designated`]: crate::issuer::Issuer::verify_designated
+    pub fn show_designated(
+        &self,
+        system_parameters: &SystemParameters,
+        issuer_parameters: &IssuerParameters,
+        keypair: Option<&SymmetricKeypair>,
+        verifier: &VerifierPublicKey,
+        mut csprng: impl CryptoRng + RngCore,
+    ) -> Result<ProofOfValidCredential, CredentialError>
+    {
+        ProofOfValidCredential::prove_designated(system_parameters, issuer_parameters, self, keypair, verifier,
+                                                 &mut csprng)
+    }
+
+    /// Begin an interactive presentation of this credential to an issuer,
+    /// in which the issuer supplies the challenge, as in the
+    /// [`interactive`](crate::nizk::interactive) module.
+    ///
+    /// The [`PresentationCommitment`] is sent to the issuer, and the
+    /// [`InteractivePresentation`] is kept to answer the issuer's challenge
+    /// with [`InteractivePresentation::respond`].  The issuer must verify the
+    /// response with [`Issuer::verify_interactive`].
+    ///
+    /// [`Issuer::verify_interactive`]: crate::issuer::Issuer::verify_interactive
+    pub fn show_interactive(
+        &self,
+        system_parameters: &SystemParameters,
+        issuer_parameters: &IssuerParameters,
+        keypair: Option<&SymmetricKeypair>,
+        mut csprng: impl CryptoRng + RngCore,
+    ) -> Result<(InteractivePresentation, PresentationCommitment), CredentialError>
+    {
+        InteractivePresentation::commit(system_parameters, issuer_parameters, self, keypair, &mut csprng)
+    }
+
+    /// Present this credential to an issuer, binding the presentation to a
+    /// `nonce` chosen by the issuer, so that it cannot be replayed.
+    ///
+    /// The issuer must check it with [`Issuer::verify_with_nonce`] and the
+    /// same `nonce`.
+    ///
+    /// [`Issuer::verify_with_nonce`]: crate::issuer::Issuer::verify_with_nonce
+    pub fn show_with_nonce(
+        &self,
+        system_parameters: &SystemParameters,
+        issuer_parameters: &IssuerParameters,
+        keypair: Option<&SymmetricKeypair>,
+        nonce: &[u8],
+        mut csprng: impl CryptoRng + RngCore,
+    ) -> Result<ProofOfValidCredential, CredentialError>
+    {
+        ProofOfValidCredential::prove_with_nonce(system_parameters, issuer_parameters, self, keypair, nonce, &mut csprng)
+    }
+
+    /// Present this credential to an issuer, binding the presentation to the
+    /// caller's `transcript`, into which a higher-level protocol may have
+    /// already appended its context, e.g. a session identifier or the body
+    /// of the request which this presentation authorises.
+    ///
+    /// The issuer must check it with [`Issuer::verify_with_transcript`] and a
+    /// transcript in the same state, so that the presentation cannot be
+    /// replayed within another session or protocol.
+    ///
+    /// [`Issuer::verify_with_transcript`]: crate::issuer::Issuer::verify_with_transcript
+    pub fn show_with_transcript(
+        &self,
+        system_parameters: &SystemParameters,
+        issuer_parameters: &IssuerParameters,
+        keypair: Option<&SymmetricKeypair>,
+        transcript: &mut Transcript,
+        mut csprng: impl CryptoRng + RngCore,
+    ) -> Result<ProofOfValidCredential, CredentialError>
+    {
+        ProofOfValidCredential::prove_with_transcript(system_parameters, issuer_parameters, self, keypair,
+                                                      transcript, &mut csprng)
+    }
+
+    /// Present this credential to an issuer, hiding or revealing its group
+    /// element attributes according to the `options`.
+    ///
+    /// A `keypair` is required if any group element attributes end up
+    /// hidden, which is the case by default for [`Attribute::EitherPoint`]s.
+    pub fn show_with_options(
+        &self,
+        system_parameters: &SystemParameters,
+        issuer_parameters: &IssuerParameters,
+        keypair: Option<&SymmetricKeypair>,
+        options: &PresentationOptions,
+        mut csprng: impl CryptoRng + RngCore,
+    ) -> Result<ProofOfValidCredential, CredentialError>
+    {
+        let mut credential = self.clone();
+
+        options.apply(&mut credential.attributes);
+
+        ProofOfValidCredential::prove_with_statements(system_parameters, issuer_parameters, &credential, keypair,
+                                                      None, options.statements(), &mut csprng)
+    }
+
+    /// Present this credential to an issuer, revealing and hiding its
+    /// attributes, and proving predicates about the hidden ones, as declared
+    /// by the `policy`.
+    ///
+    /// A `keypair` is required if the policy hides any group element
+    /// attributes.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the presentation, otherwise a
+    /// [`CredentialError::WrongNumberOfAttributes`] or a
+    /// [`CredentialError::BadAttribute`] if the policy is inconsistent, as
+    /// for [`Policy::check`], a [`CredentialError::BadAttribute`] if it
+    /// would hide an [`Attribute::PublicPoint`], or another
+    /// [`CredentialError`] if a predicate doesn't hold.
+    pub fn show_with_policy(
+        &self,
+        system_parameters: &SystemParameters,
+        issuer_parameters: &IssuerParameters,
+        keypair: Option<&SymmetricKeypair>,
+        policy: &Policy,
+        mut csprng: impl CryptoRng + RngCore,
+    ) -> Result<ProofOfValidCredential, CredentialError>
+    {
+        let mut credential = self.clone();
+
+        policy.apply(&mut credential)?;
+
+        let statements = Statements {
+            ranges: &policy.ranges,
+            memberships: &policy.memberships,
+            ..Statements::default()
+        };
+
+        ProofOfValidCredential::prove_with_statements(system_parameters, issuer_parameters, &credential, keypair,
+                                                      policy.nonce.as_deref(), statements, &mut csprng)
+    }
+
+    /// Precompute a single presentation of this credential, issued under the
+    /// `system_parameters` and `issuer_parameters`, as in
+    /// [`ProofOfValidCredential::precompute`], so that a client may store many
+    /// unlinkable presentations while offline, and finalise each with
+    /// [`PrecomputedPresentation::finalize`] without the group arithmetic.
+    ///
+    /// The algebraic MAC itself cannot be rerandomised, since scaling
+    /// \\(( (U, V) \\)) by some \\(( a \\)) does not yield a valid tag
+    /// over the same attributes.  Nor does it need to be: each presentation
+    /// commits to the tag and attributes afresh, and so presentations are
+    /// already unlinkable.  It is those commitments, i.e. nearly all of the
+    /// group arithmetic of a presentation, which are instead computed here.
+    ///
+    /// Which attributes are hidden is fixed at this point, according to how
+    /// they are currently marked.
+    ///
+    /// # Warning
+    ///
+    /// Each [`PrecomputedPresentation`] must only be presented once, which
+    /// is why finalising it consumes it.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the [`PrecomputedPresentation`],
+    /// otherwise a [`CredentialError::ParameterMismatch`] if the credential
+    /// was issued under other parameters.
+    pub fn precompute_presentation(
+        &self,
+        system_parameters: &SystemParameters,
+        issuer_parameters: &IssuerParameters,
+        keypair: Option<&SymmetricKeypair>,
+        mut csprng: impl CryptoRng + RngCore,
+    ) -> Result<PrecomputedPresentation, CredentialError>
+    {
+        ProofOfValidCredential::precompute(system_parameters, issuer_parameters, self, keypair, &mut csprng)
+    }
+
+    /// Present this credential to an issuer, selectively disclosing its
+    /// attributes according to a `disclosure_mask`.
+    ///
+    /// # Inputs
+    ///
+    /// * A `disclosure_mask` with one entry per attribute, where `true`
+    ///   reveals the attribute and `false` hides it.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is a [`Presentation`], otherwise a
+    /// [`CredentialError::WrongNumberOfAttributes`] if the mask is of the
+    /// wrong length, or a [`CredentialError::BadAttribute`] if it asks to
+    /// hide an [`Attribute::PublicPoint`].
+    pub fn show_disclosing(
+        &self,
+        system_parameters: &SystemParameters,
+        issuer_parameters: &IssuerParameters,
+        keypair: Option<&SymmetricKeypair>,
+        disclosure_mask: &[bool],
+        mut csprng: impl CryptoRng + RngCore,
+    ) -> Result<Presentation, CredentialError>
+    {
+        if disclosure_mask.len() != self.attributes.len() {
+            return Err(CredentialError::WrongNumberOfAttributes);
+        }
+
+        let mut credential = self.clone();
+
+        for (i, disclose) in disclosure_mask.iter().enumerate() {
+            match disclose {
+                true  => credential.reveal_attribute(i),
+                false => credential.hide_attribute(i),
+            }.or(Err(CredentialError::BadAttribute))?;
+        }
+
+        ProofOfValidCredential::prove(system_parameters, issuer_parameters, &credential, keypair, &mut csprng)
+    }
+
     /// Change one of this credential's attributes to be revealed upon presentation.
     ///
     /// # Returns
@@ -95,4 +676,763 @@ impl AnonymousCredential {
 
         Ok(())
     }
+
+    /// Serialise this credential in the versioned [`wire`] format, whose
+    /// body is its attributes, i.e. a little-endian `u32` number of
+    /// attributes followed by a one byte type and 32 bytes of data for each,
+    /// then the 96 bytes of its algebraic MAC, and finally the 32-byte
+    /// [`SystemParameters::identifier`] of the parameters it was issued
+    /// under.
+    ///
+    /// # Warning
+    ///
+    /// The serialised credential contains its hidden attributes in the clear.
+    ///
+    /// [`wire`]: crate::wire
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut body = attributes_to_bytes(&self.attributes);
+
+        body.extend(self.amac.to_bytes());
+        body.extend(&self.parameters_fingerprint);
+
+        let bytes = wire::encode(WireType::Credential, &body);
+
+        body.zeroize();
+        bytes
+    }
+
+    /// Stream this credential to a `writer` in the versioned [`wire`]
+    /// format, as for [`AnonymousCredential::to_bytes`], without serialising
+    /// it into memory first.
+    ///
+    /// # Warning
+    ///
+    /// The serialised credential contains its hidden attributes in the clear.
+    ///
+    /// [`wire`]: crate::wire
+    #[cfg(feature = "std")]
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let length = sizeof_attributes(self.attributes.len() as u32) + Amac::SERIALIZED_LEN + 32;
+
+        wire::write(&mut writer, WireType::Credential, length, |sink| {
+            write_attributes(&self.attributes, sink);
+            sink.put(&self.amac.to_bytes());
+            sink.put(&self.parameters_fingerprint);
+        })
+    }
+
+    /// Read a credential written with [`AnonymousCredential::write_to`]
+    /// from a `reader`, consuming exactly its encoding.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the credential, otherwise an
+    /// `io::Error`, of kind `InvalidData` if it is malformed.
+    #[cfg(feature = "std")]
+    pub fn read_from<R: Read>(mut reader: R) -> io::Result<AnonymousCredential> {
+        let mut bytes = wire::read(&mut reader, WireType::Credential)?;
+        let credential = AnonymousCredential::from_bytes(&bytes);
+
+        bytes.zeroize();
+
+        credential.map_err(wire::invalid_data)
+    }
+
+    /// Deserialise a credential previously serialised with
+    /// [`AnonymousCredential::to_bytes`].
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the credential, otherwise a
+    /// [`CredentialError`] if the `bytes` are malformed.
+    pub fn from_bytes(bytes: &[u8]) -> Result<AnonymousCredential, CredentialError> {
+        CredentialRef::from_bytes(bytes)?.to_owned()
+    }
+}
+
+/// A serialised [`AnonymousCredential`], borrowed from a byte slice.
+///
+/// Parsing only checks the length and attribute types, and allocates
+/// nothing; each attribute is decoded and validated when it is accessed.
+#[derive(Clone, Copy, Debug)]
+pub struct CredentialRef<'a> {
+    body: &'a [u8],
+    number_of_attributes: usize,
+    parameters_fingerprint: &'a [u8],
+}
+
+impl<'a> CredentialRef<'a> {
+    /// Borrow a credential serialised with [`AnonymousCredential::to_bytes`].
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the borrowed credential, otherwise a
+    /// [`CredentialError::UnsupportedEncoding`] if the [`wire`] header is
+    /// invalid, a [`CredentialError::WrongNumberOfAttributes`] if there are
+    /// more than [`MAX_NUMBER_OF_ATTRIBUTES`] attributes, a
+    /// [`CredentialError::WrongNumberOfBytes`] if the `bytes` have the wrong
+    /// length for their number of attributes, or a
+    /// [`CredentialError::BadAttribute`] if an attribute has an unknown type.
+    ///
+    /// [`wire`]: crate::wire
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<CredentialRef<'a>, CredentialError> {
+        // Unversioned credentials don't record the parameters they were issued under.
+        let bytes = match wire::decode(bytes, WireType::Credential)? {
+            (1, body) => body,
+            _ => return Err(CredentialError::UnsupportedEncoding),
+        };
+
+        if bytes.len() < 4 {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
+
+        let mut tmp = [0u8; 4];
+
+        tmp.copy_from_slice(&bytes[0..4]);
+
+        let number_of_attributes = u32::from_le_bytes(tmp);
+
+        if number_of_attributes > MAX_NUMBER_OF_ATTRIBUTES {
+            return Err(CredentialError::WrongNumberOfAttributes);
+        }
+
+        let length = sizeof_tag_record(number_of_attributes) as u64;
+
+        if (bytes.len() as u64) != length + 32 {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
+
+        let credential = CredentialRef {
+            body: &bytes[..length as usize],
+            number_of_attributes: number_of_attributes as usize,
+            parameters_fingerprint: &bytes[length as usize..],
+        };
+
+        if credential.records().any(|record| record[0] > 4) {
+            return Err(CredentialError::BadAttribute);
+        }
+        Ok(credential)
+    }
+
+    /// The serialised attributes.
+    fn records(&self) -> impl Iterator<Item = &'a [u8]> {
+        self.body[4..4 + self.number_of_attributes * ATTRIBUTE_RECORD_LENGTH].chunks(ATTRIBUTE_RECORD_LENGTH)
+    }
+
+    /// The number of attributes on this credential.
+    pub fn number_of_attributes(&self) -> usize {
+        self.number_of_attributes
+    }
+
+    /// Decode the attribute at `index`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the attribute, otherwise a
+    /// [`CredentialError::WrongNumberOfAttributes`] if there is no such
+    /// attribute, or another [`CredentialError`] if it is malformed.
+    pub fn attribute(&self, index: usize) -> Result<Attribute, CredentialError> {
+        match self.records().nth(index) {
+            Some(record) => Ok(attribute_from_record(record)?),
+            None         => Err(CredentialError::WrongNumberOfAttributes),
+        }
+    }
+
+    /// Decode the whole credential.
+    pub fn to_owned(&self) -> Result<AnonymousCredential, CredentialError> {
+        let (attributes, amac) = from_tag_record(self.body)?;
+        let mut parameters_fingerprint = [0u8; 32];
+
+        parameters_fingerprint.copy_from_slice(self.parameters_fingerprint);
+
+        Ok(AnonymousCredential { amac, attributes, parameters_fingerprint })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[cfg(feature = "std")]
+    use crate::amacs::SecretKey;
+    use crate::amacs::EncryptedAttribute;
+    use crate::issuer::CredentialIssuance;
+    use crate::issuer::Issuer;
+    use crate::nizk::escrow::AuditorKeypair;
+    use crate::nizk::presentation::PresentationRef;
+    use crate::symmetric::Plaintext;
+    use crate::user::CredentialRequest;
+    use crate::user::CredentialRequestConstructor;
+    use crate::wire::HEADER_LENGTH;
+
+    use curve25519_dalek::ristretto::RistrettoPoint;
+
+    use rand::thread_rng;
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn stream_several_objects() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 1).unwrap();
+        let issuer = Issuer::new(&system_parameters, &mut rng);
+        let mut request = CredentialRequestConstructor::new(&system_parameters);
+
+        request.append_revealed_scalar(Scalar::random(&mut rng));
+
+        let issuance = issuer.issue(request.finish(), &mut rng).unwrap();
+        let mut credential = issuance.verify(&system_parameters, &issuer.issuer_parameters).unwrap();
+
+        credential.hide_attribute(0).unwrap();
+
+        let presentation = credential.show(&system_parameters, &issuer.issuer_parameters, None, &mut rng).unwrap();
+        let mut stream: Vec<u8> = Vec::new();
+
+        system_parameters.write_to(&mut stream).unwrap();
+        issuer.amacs_key.write_to(&mut stream).unwrap();
+        credential.write_to(&mut stream).unwrap();
+        presentation.write_to(&mut stream).unwrap();
+
+        // The streamed encodings are identical to those built in memory.
+        let mut expected = system_parameters.to_bytes();
+
+        expected.extend(issuer.amacs_key.to_bytes());
+        expected.extend(credential.to_bytes());
+        expected.extend(presentation.to_bytes().unwrap());
+
+        assert_eq!(stream, expected);
+
+        let mut reader = &stream[..];
+
+        assert_eq!(SystemParameters::read_from(&mut reader).unwrap(), system_parameters);
+        assert_eq!(SecretKey::read_from(&mut reader).unwrap().to_bytes(), issuer.amacs_key.to_bytes());
+        assert_eq!(AnonymousCredential::read_from(&mut reader).unwrap().to_bytes(), credential.to_bytes());
+        assert!(issuer.verify(&ProofOfValidCredential::read_from(&mut reader).unwrap()).is_ok());
+        assert!(reader.is_empty());
+
+        // Objects must be read in the order they were written.
+        assert_eq!(AnonymousCredential::read_from(&stream[..]).unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn show_disclosing() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 4).unwrap();
+        let issuer = Issuer::new(&system_parameters, &mut rng);
+        let mut request = CredentialRequestConstructor::new(&system_parameters);
+
+        request.append_revealed_scalar(Scalar::random(&mut rng));
+        request.append_revealed_scalar(Scalar::random(&mut rng));
+        request.append_revealed_point(RistrettoPoint::random(&mut rng));
+        request.append_revealed_scalar(Scalar::random(&mut rng));
+
+        let issuance = issuer.issue(request.finish(), &mut rng).unwrap();
+        let credential = issuance.verify(&system_parameters, &issuer.issuer_parameters).unwrap();
+        let presentation = credential.show_disclosing(&system_parameters, &issuer.issuer_parameters, None,
+                                                      &[false, true, true, false], &mut rng).unwrap();
+
+        assert!(issuer.verify_presentation(&presentation).is_ok());
+
+        // A public point cannot be hidden, and the mask must cover every attribute.
+        assert!(credential.show_disclosing(&system_parameters, &issuer.issuer_parameters, None,
+                                           &[true, true, false, true], &mut rng).is_err());
+        assert!(credential.show_disclosing(&system_parameters, &issuer.issuer_parameters, None,
+                                           &[true, true, true], &mut rng).is_err());
+    }
+
+    #[test]
+    fn show_with_range_statement() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 2).unwrap();
+        let issuer = Issuer::new(&system_parameters, &mut rng);
+        let mut request = CredentialRequestConstructor::new(&system_parameters);
+
+        request.append_revealed_scalar(Scalar::random(&mut rng));
+        request.append_revealed_scalar(Scalar::from(1987u64));
+
+        let issuance = issuer.issue(request.finish(), &mut rng).unwrap();
+        let mut credential = issuance.verify(&system_parameters, &issuer.issuer_parameters).unwrap();
+
+        credential.hide_attribute(1).unwrap();
+
+        let options = PresentationOptions::default().with_range_statement(1, 1900, 2002);
+        let presentation = credential.show_with_options(&system_parameters, &issuer.issuer_parameters, None,
+                                                        &options, &mut rng).unwrap();
+
+        assert!(issuer.verify(&presentation).is_ok());
+        assert_eq!(presentation.range_statements(), options.ranges);
+
+        // Out of range, or on a revealed attribute, no proof can be made.
+        let options = PresentationOptions::default().with_range_statement(1, 1990, 2002);
+
+        assert!(credential.show_with_options(&system_parameters, &issuer.issuer_parameters, None,
+                                             &options, &mut rng).is_err());
+
+        let options = PresentationOptions::default().with_range_statement(0, 0, u64::MAX);
+
+        assert!(credential.show_with_options(&system_parameters, &issuer.issuer_parameters, None,
+                                             &options, &mut rng).is_err());
+    }
+
+    #[test]
+    fn show_with_linear_statement() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 4).unwrap();
+        let issuer = Issuer::new(&system_parameters, &mut rng);
+        let mut request = CredentialRequestConstructor::new(&system_parameters);
+
+        request.append_revealed_scalar(Scalar::from(5u64));
+        request.append_revealed_scalar(Scalar::from(7u64));
+        request.append_revealed_scalar(Scalar::from(12u64));
+        request.append_revealed_scalar(Scalar::from(100u64));
+
+        let issuance = issuer.issue(request.finish(), &mut rng).unwrap();
+        let mut credential = issuance.verify(&system_parameters, &issuer.issuer_parameters).unwrap();
+
+        for i in 0..4 {
+            credential.hide_attribute(i).unwrap();
+        }
+
+        let options = PresentationOptions::default()
+            .with_linear_statement(LinearStatement::sum(2, 0, 1))
+            .with_linear_statement(LinearStatement::new().plus(3, Scalar::from(2u64)).equals(Scalar::from(200u64)))
+            .at_least(3, 50);
+        let presentation = credential.show_with_options(&system_parameters, &issuer.issuer_parameters, None,
+                                                        &options, &mut rng).unwrap();
+
+        assert!(issuer.verify(&presentation).is_ok());
+        assert_eq!(presentation.linear_statements(), &options.relations[..]);
+
+        // A false relation cannot be proven.
+        let options = PresentationOptions::default().with_linear_statement(LinearStatement::sum(2, 0, 3));
+
+        assert!(credential.show_with_options(&system_parameters, &issuer.issuer_parameters, None,
+                                             &options, &mut rng).is_err());
+    }
+
+    #[test]
+    fn precomputed_presentations_present_unlinkably() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 2).unwrap();
+        let issuer = Issuer::new(&system_parameters, &mut rng);
+        let mut request = CredentialRequestConstructor::new(&system_parameters);
+
+        request.append_revealed_scalar(Scalar::from(5u64));
+        request.append_revealed_scalar(Scalar::from(7u64));
+
+        let issuance = issuer.issue(request.finish(), &mut rng).unwrap();
+        let mut credential = issuance.verify(&system_parameters, &issuer.issuer_parameters).unwrap();
+
+        credential.hide_attribute(1).unwrap();
+
+        let first = credential.precompute_presentation(&system_parameters, &issuer.issuer_parameters, None,
+                                                       &mut rng).unwrap();
+        let second = credential.precompute_presentation(&system_parameters, &issuer.issuer_parameters, None,
+                                                        &mut rng).unwrap();
+
+        // Hiding is fixed when the presentation is precomputed.
+        credential.reveal_attribute(1).unwrap();
+
+        let first = first.finalize(b"first nonce").unwrap();
+        let second = second.finalize(b"second nonce").unwrap();
+
+        assert!(issuer.verify_with_nonce(&first, b"first nonce").is_ok());
+        assert!(issuer.verify_with_nonce(&second, b"second nonce").is_ok());
+        assert!(matches!(first.attributes()[1], EncryptedAttribute::SecretScalar));
+        assert_ne!(first.C_y[1], second.C_y[1]);
+
+        // A presentation precomputed under other parameters is rejected.
+        let other = SystemParameters::generate(&mut rng, 2).unwrap();
+
+        assert!(credential.precompute_presentation(&other, &issuer.issuer_parameters, None, &mut rng).is_err());
+    }
+
+    #[test]
+    fn show_with_membership_statement() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 1).unwrap();
+        let issuer = Issuer::new(&system_parameters, &mut rng);
+        let mut request = CredentialRequestConstructor::new(&system_parameters);
+
+        request.append_revealed_scalar(Scalar::from(276u64));
+
+        let issuance = issuer.issue(request.finish(), &mut rng).unwrap();
+        let mut credential = issuance.verify(&system_parameters, &issuer.issuer_parameters).unwrap();
+
+        credential.hide_attribute(0).unwrap();
+
+        let set: Vec<Scalar> = [40u64, 56, 276].iter().map(|s| Scalar::from(*s)).collect();
+        let options = PresentationOptions::default().with_membership_statement(0, set);
+        let presentation = credential.show_with_options(&system_parameters, &issuer.issuer_parameters, None,
+                                                        &options, &mut rng).unwrap();
+
+        assert!(issuer.verify(&presentation).is_ok());
+        assert_eq!(presentation.membership_statements(), options.memberships);
+
+        let options = PresentationOptions::default().with_membership_statement(0, vec![Scalar::from(40u64)]);
+
+        assert!(credential.show_with_options(&system_parameters, &issuer.issuer_parameters, None,
+                                             &options, &mut rng).is_err());
+    }
+
+    #[test]
+    fn expiry() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 2).unwrap();
+        let issuer = Issuer::new(&system_parameters, &mut rng);
+        let mut request = CredentialRequestConstructor::new(&system_parameters);
+
+        request.append_revealed_scalar(Scalar::random(&mut rng));
+        request.append_expiry(u64::MAX);
+
+        // The issuer replaces the user's chosen expiry with its own.
+        let issuance = issuer.issue_with_expiry(request.finish(), 1, 2020, &mut rng).unwrap();
+        let mut credential = issuance.verify(&system_parameters, &issuer.issuer_parameters).unwrap();
+        let presentation = credential.show(&system_parameters, &issuer.issuer_parameters, None, &mut rng).unwrap();
+
+        assert!(issuer.verify_unexpired(&presentation, 1, 2020).is_ok());
+        assert_eq!(issuer.verify_unexpired(&presentation, 1, 2021).unwrap_err(), CredentialError::Expired);
+
+        credential.hide_attribute(1).unwrap();
+
+        let options = PresentationOptions::default().not_expired(1, 2019);
+        let presentation = credential.show_with_options(&system_parameters, &issuer.issuer_parameters, None,
+                                                        &options, &mut rng).unwrap();
+
+        assert!(issuer.verify_unexpired(&presentation, 1, 2019).is_ok());
+        assert_eq!(issuer.verify_unexpired(&presentation, 1, 2020).unwrap_err(), CredentialError::Expired);
+
+        let options = PresentationOptions::default().not_expired(1, 2021);
+
+        assert!(credential.show_with_options(&system_parameters, &issuer.issuer_parameters, None,
+                                             &options, &mut rng).is_err());
+    }
+
+    #[test]
+    fn show_with_nullifier() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 1).unwrap();
+        let issuer = Issuer::new(&system_parameters, &mut rng);
+        let mut request = CredentialRequestConstructor::new(&system_parameters);
+
+        request.append_revealed_scalar(Scalar::random(&mut rng));
+
+        let issuance = issuer.issue(request.finish(), &mut rng).unwrap();
+        let mut credential = issuance.verify(&system_parameters, &issuer.issuer_parameters).unwrap();
+
+        credential.hide_attribute(0).unwrap();
+
+        let options = PresentationOptions::default().with_nullifier(0, b"poll #1");
+        let presentation_1 = credential.show_with_options(&system_parameters, &issuer.issuer_parameters, None,
+                                                          &options, &mut rng).unwrap();
+        let presentation_2 = credential.show_with_options(&system_parameters, &issuer.issuer_parameters, None,
+                                                          &options, &mut rng).unwrap();
+
+        assert!(issuer.verify(&presentation_1).is_ok());
+        assert!(issuer.verify(&presentation_2).is_ok());
+        assert!(presentation_1.nullifier(0, b"poll #1").is_some());
+        assert!(presentation_1.nullifier(0, b"poll #2").is_none());
+        assert_eq!(presentation_1.nullifier(0, b"poll #1"), presentation_2.nullifier(0, b"poll #1"));
+    }
+
+    #[test]
+    fn show_with_pseudonym() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 1).unwrap();
+        let issuer = Issuer::new(&system_parameters, &mut rng);
+        let mut request = CredentialRequestConstructor::new(&system_parameters);
+
+        request.append_revealed_scalar(Scalar::random(&mut rng));
+
+        let issuance = issuer.issue(request.finish(), &mut rng).unwrap();
+        let mut credential = issuance.verify(&system_parameters, &issuer.issuer_parameters).unwrap();
+
+        credential.hide_attribute(0).unwrap();
+
+        let options = PresentationOptions::default().with_pseudonym(0, b"example.com");
+        let presentation_1 = credential.show_with_options(&system_parameters, &issuer.issuer_parameters, None,
+                                                          &options, &mut rng).unwrap();
+        let options = options.with_pseudonym(0, b"example.org");
+        let presentation_2 = credential.show_with_options(&system_parameters, &issuer.issuer_parameters, None,
+                                                          &options, &mut rng).unwrap();
+
+        assert!(issuer.verify(&presentation_1).is_ok());
+        assert!(issuer.verify(&presentation_2).is_ok());
+        assert!(presentation_1.pseudonym(0, b"example.org").is_none());
+        assert_eq!(presentation_1.pseudonym(0, b"example.com"), presentation_2.pseudonym(0, b"example.com"));
+        assert_ne!(presentation_2.pseudonym(0, b"example.com"), presentation_2.pseudonym(0, b"example.org"));
+    }
+
+    #[test]
+    fn show_with_escrow() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 2).unwrap();
+        let issuer = Issuer::new(&system_parameters, &mut rng);
+        let (keypair, _) = SymmetricKeypair::generate(&system_parameters, &mut rng);
+        let auditor = AuditorKeypair::generate(&system_parameters, &mut rng);
+        let mut request = CredentialRequestConstructor::new(&system_parameters);
+
+        request.append_revealed_scalar(Scalar::random(&mut rng));
+        let plaintexts = request.append_plaintext(&b"escrowed account number 123456".to_vec());
+
+        let issuance = issuer.issue(request.finish(), &mut rng).unwrap();
+        let credential = issuance.verify(&system_parameters, &issuer.issuer_parameters).unwrap();
+
+        let options = PresentationOptions::default().with_escrow(1, auditor.public);
+        let presentation = credential.show_with_options(&system_parameters, &issuer.issuer_parameters,
+                                                        Some(&keypair), &options, &mut rng).unwrap();
+
+        assert!(issuer.verify(&presentation).is_ok());
+        assert_eq!(presentation.escrow_statements(), options.escrows);
+
+        let ciphertext = presentation.audit_ciphertext(1, &auditor.public).unwrap();
+
+        assert_eq!(auditor.decrypt(&ciphertext), plaintexts[0].M1);
+
+        // Only hidden group element attributes may be escrowed.
+        let options = PresentationOptions::default().with_escrow(0, auditor.public);
+
+        assert!(credential.show_with_options(&system_parameters, &issuer.issuer_parameters,
+                                             Some(&keypair), &options, &mut rng).is_err());
+    }
+
+    #[test]
+    fn per_credential_keypairs() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 2).unwrap();
+        let issuer = Issuer::new(&system_parameters, &mut rng);
+        let (_, master_secret) = SymmetricKeypair::generate(&system_parameters, &mut rng);
+        let m = Scalar::random(&mut rng);
+        let request = || {
+            let mut request = CredentialRequestConstructor::new(&system_parameters);
+
+            request.append_revealed_scalar(m);
+            request.append_plaintext(&b"hidden account number 12345678".to_vec());
+            request.finish()
+        };
+        let first = issuer.issue(request(), &mut rng).unwrap();
+        let second = issuer.issue(request(), &mut rng).unwrap();
+        let (first, first_keypair) = first.verify_with_master_secret(&system_parameters, &issuer.issuer_parameters,
+                                                                     &master_secret).unwrap();
+        let (second, second_keypair) = second.verify_with_master_secret(&system_parameters, &issuer.issuer_parameters,
+                                                                        &master_secret).unwrap();
+
+        // The same attributes issued twice are encrypted under different keys.
+        assert!(first.id() != second.id());
+        assert!(first_keypair.public.pk != second_keypair.public.pk);
+        assert!(first.derive_keypair(&master_secret, &system_parameters).public.pk == first_keypair.public.pk);
+
+        let presentation = first.show(&system_parameters, &issuer.issuer_parameters,
+                                      Some(&first_keypair), &mut rng).unwrap();
+
+        assert!(issuer.verify(&presentation).is_ok());
+    }
+
+    #[test]
+    fn presentation_options_default_hides_either_points() {
+        let P: Plaintext = (&[7u8; 30]).into();
+        let mut attributes = vec![Attribute::EitherPoint(P.clone()), Attribute::EitherPoint(P)];
+
+        PresentationOptions::default().reveal(1).apply(&mut attributes);
+
+        match attributes[0] {
+            Attribute::SecretPoint(_) => (),
+            _ => panic!("EitherPoint was not hidden by default"),
+        }
+        match attributes[1] {
+            Attribute::EitherPoint(_) => (),
+            _ => panic!("explicitly revealed EitherPoint was hidden"),
+        }
+    }
+
+    #[test]
+    fn credential_ref_decodes_lazily() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 3).unwrap();
+        let issuer = Issuer::new(&system_parameters, &mut rng);
+        let mut request = CredentialRequestConstructor::new(&system_parameters);
+        let m = Scalar::random(&mut rng);
+
+        request.append_revealed_scalar(m);
+        request.append_revealed_point(RistrettoPoint::random(&mut rng));
+        request.append_revealed_scalar(Scalar::random(&mut rng));
+
+        let issuance = issuer.issue(request.finish(), &mut rng).unwrap();
+        let mut credential = issuance.verify(&system_parameters, &issuer.issuer_parameters).unwrap();
+
+        credential.hide_attribute(2).unwrap();
+
+        let mut bytes = credential.to_bytes();
+        let borrowed = CredentialRef::from_bytes(&bytes).unwrap();
+
+        assert_eq!(borrowed.number_of_attributes(), 3);
+        assert!(matches!(borrowed.attribute(0).unwrap(), Attribute::PublicScalar(x) if x == m));
+        assert!(borrowed.attribute(3).is_err());
+
+        let presentation = borrowed.to_owned().unwrap()
+            .show(&system_parameters, &issuer.issuer_parameters, None, &mut rng).unwrap();
+
+        assert!(issuer.verify(&presentation).is_ok());
+
+        // A corrupted attribute is only detected once it is decoded.
+        let attributes = HEADER_LENGTH + 4;
+
+        bytes[attributes + 33 + 1..attributes + 33 * 2].copy_from_slice(&[0xff; 32]);
+
+        let borrowed = CredentialRef::from_bytes(&bytes).unwrap();
+
+        assert!(borrowed.attribute(0).is_ok());
+        assert!(borrowed.attribute(1).is_err());
+        assert!(borrowed.to_owned().is_err());
+        assert!(CredentialRef::from_bytes(&bytes[1..]).is_err());
+        assert!(CredentialRef::from_bytes(&bytes[HEADER_LENGTH..]).is_err());
+    }
+
+    #[test]
+    fn request_and_issuance_serialisation_roundtrip() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 3).unwrap();
+        let issuer = Issuer::new(&system_parameters, &mut rng);
+        let mut request = CredentialRequestConstructor::new(&system_parameters);
+
+        request.append_revealed_scalar(Scalar::random(&mut rng));
+        request.append_revealed_point(RistrettoPoint::random(&mut rng));
+        request.append_expiry(1);
+
+        let request = CredentialRequest::from_bytes(&request.finish().to_bytes()).unwrap();
+        let bytes = issuer.issue(request, &mut rng).unwrap().to_bytes();
+        let issuance = CredentialIssuance::from_bytes(&bytes).unwrap();
+        let credential = issuance.verify(&system_parameters, &issuer.issuer_parameters).unwrap();
+        let presentation = credential.show(&system_parameters, &issuer.issuer_parameters, None, &mut rng).unwrap();
+
+        assert!(issuer.verify(&presentation).is_ok());
+        assert!(CredentialIssuance::from_bytes(&bytes[..bytes.len() - 32]).is_err());
+        assert!(CredentialRequest::from_bytes(&bytes).is_err());
+    }
+
+    #[test]
+    fn deserialisation_rejects_malformed_input() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 3).unwrap();
+        let issuer = Issuer::new(&system_parameters, &mut rng);
+        let mut request = CredentialRequestConstructor::new(&system_parameters);
+
+        request.append_revealed_scalar(Scalar::random(&mut rng));
+        request.append_revealed_scalar(Scalar::random(&mut rng));
+        request.append_revealed_point(RistrettoPoint::random(&mut rng));
+
+        let request = request.finish();
+        let request_bytes = request.to_bytes();
+        let issuance = issuer.issue(request, &mut rng).unwrap();
+        let issuance_bytes = issuance.to_bytes();
+        let mut credential = issuance.verify(&system_parameters, &issuer.issuer_parameters).unwrap();
+
+        credential.hide_attribute(1).unwrap();
+
+        let presentation = credential.show(&system_parameters, &issuer.issuer_parameters, None, &mut rng).unwrap();
+        let body = |bytes: Vec<u8>| bytes[HEADER_LENGTH..].to_vec();
+
+        for bytes in wire::malformations(WireType::Credential, &body(credential.to_bytes()), &mut rng) {
+            let _ = AnonymousCredential::from_bytes(&bytes);
+            let _ = CredentialRef::from_bytes(&bytes).map(|credential| credential.to_owned());
+        }
+        for bytes in wire::malformations(WireType::CredentialRequest, &body(request_bytes), &mut rng) {
+            let _ = CredentialRequest::from_bytes(&bytes);
+        }
+        for bytes in wire::malformations(WireType::CredentialIssuance, &body(issuance_bytes), &mut rng) {
+            let _ = CredentialIssuance::from_bytes(&bytes);
+        }
+        for bytes in wire::malformations(WireType::Presentation, &body(presentation.to_bytes().unwrap()), &mut rng) {
+            let _ = ProofOfValidCredential::from_bytes(&bytes);
+            let _ = PresentationRef::from_bytes(&bytes).map(|presentation| presentation.to_owned());
+        }
+        for bytes in wire::malformations(WireType::SecretKey, &issuer.to_bytes(), &mut rng) {
+            let _ = Issuer::from_bytes(&bytes);
+        }
+
+        let mut bytes = body(credential.to_bytes());
+
+        bytes[..4].copy_from_slice(&u32::MAX.to_le_bytes());
+
+        assert_eq!(AnonymousCredential::from_bytes(&wire::encode(WireType::Credential, &bytes)).unwrap_err(),
+                   CredentialError::WrongNumberOfAttributes);
+        assert_eq!(AnonymousCredential::from_bytes(&bytes).unwrap_err(), CredentialError::UnsupportedEncoding);
+    }
+
+    #[test]
+    fn extended_schema_accepts_old_credentials() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 2).unwrap();
+        let issuer = Issuer::new(&system_parameters, &mut rng);
+        let mut request = CredentialRequestConstructor::new(&system_parameters);
+
+        request.append_revealed_scalar(Scalar::random(&mut rng));
+        request.append_revealed_scalar(Scalar::random(&mut rng));
+
+        let issuance = issuer.issue_with_schema_version(request.finish(), 0, 1, &mut rng).unwrap();
+        let credential = issuance.verify(&system_parameters, &issuer.issuer_parameters).unwrap();
+
+        let extended_system_parameters = system_parameters.extend(&mut rng, 2).unwrap();
+        let extended_issuer = issuer.extend(&extended_system_parameters, &mut rng).unwrap();
+
+        // The old credential must be padded before it can be shown under the new schema.
+        assert!(matches!(credential.show(&extended_system_parameters, &extended_issuer.issuer_parameters,
+                                         None, &mut rng), Err(CredentialError::ParameterMismatch)));
+
+        let mut extended = credential.extend(&system_parameters, &extended_system_parameters).unwrap();
+
+        extended.hide_attribute(1).unwrap();
+        extended.hide_attribute(3).unwrap();
+
+        let presentation = extended.show(&extended_system_parameters, &extended_issuer.issuer_parameters,
+                                         None, &mut rng).unwrap();
+
+        assert!(extended_issuer.verify(&presentation).is_ok());
+        assert_eq!(extended.schema_version(0), Some(1));
+        assert_eq!(extended.attributes.len(), 4);
+        assert!(issuer.extend(&SystemParameters::generate(&mut rng, 4).unwrap(), &mut rng).is_err());
+        assert!(credential.extend(&extended_system_parameters, &system_parameters).is_err());
+
+        // New credentials are issued under the new schema as usual.
+        let mut request = CredentialRequestConstructor::new(&extended_system_parameters);
+
+        for _ in 0..4 {
+            request.append_revealed_scalar(Scalar::random(&mut rng));
+        }
+
+        let issuance = extended_issuer.issue_with_schema_version(request.finish(), 0, 2, &mut rng).unwrap();
+        let credential = issuance.verify(&extended_system_parameters, &extended_issuer.issuer_parameters).unwrap();
+        let presentation = credential.show(&extended_system_parameters, &extended_issuer.issuer_parameters,
+                                           None, &mut rng).unwrap();
+
+        assert!(extended_issuer.verify(&presentation).is_ok());
+        assert_eq!(credential.schema_version(0), Some(2));
+    }
+
+    #[test]
+    fn parameter_mismatch_is_detected() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 2).unwrap();
+        let other = SystemParameters::generate(&mut rng, 2).unwrap();
+        let issuer = Issuer::new(&system_parameters, &mut rng);
+        let mut request = CredentialRequestConstructor::new(&system_parameters);
+
+        request.append_revealed_scalar(Scalar::random(&mut rng));
+        request.append_revealed_scalar(Scalar::random(&mut rng));
+
+        let bytes = issuer.issue(request.finish(), &mut rng).unwrap().to_bytes();
+
+        assert_eq!(CredentialIssuance::from_bytes(&bytes).unwrap()
+                       .verify(&other, &issuer.issuer_parameters).unwrap_err(),
+                   CredentialError::ParameterMismatch);
+
+        let credential = CredentialIssuance::from_bytes(&bytes).unwrap()
+            .verify(&system_parameters, &issuer.issuer_parameters).unwrap();
+        let credential = AnonymousCredential::from_bytes(&credential.to_bytes()).unwrap();
+
+        assert_eq!(credential.parameters_fingerprint(), &system_parameters.identifier());
+        assert!(credential.check_parameters(&system_parameters).is_ok());
+        assert!(matches!(credential.show(&other, &issuer.issuer_parameters, None, &mut rng),
+                         Err(CredentialError::ParameterMismatch)));
+        assert!(credential.show(&system_parameters, &issuer.issuer_parameters, None, &mut rng).is_ok());
+    }
 }
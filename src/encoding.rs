@@ -8,7 +8,15 @@
 // - isis agora lovecruft <isis@patternsinthevoid.net>
 // - Henry de Valence <hdevalence@hdevalence.ca>
 
-//! Encoding/decoding byte sequences to and from the ristretto255 group.
+//! Encoding/decoding byte sequences to and from the ristretto255 group, and
+//! to and from hexadecimal strings for human-readable serialisation formats.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use core::fmt;
 
 use curve25519_dalek::ristretto::CompressedRistretto;
 use curve25519_dalek::ristretto::RistrettoPoint;
@@ -81,6 +89,41 @@ pub fn decode_from_group(point: &RistrettoPoint) -> ([u8; 30], usize) {
     (data, (compressed.as_bytes()[0] / 2) as usize + compressed.as_bytes()[31] as usize * 128usize)
 }
 
+/// A byte sequence which is displayed as lowercase hexadecimal.
+pub(crate) struct Hex<'a>(pub(crate) &'a [u8]);
+
+impl<'a> fmt::Display for Hex<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for byte in self.0.iter() {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+/// Decode a hexadecimal string, in either case, into bytes.
+///
+/// # Returns
+///
+/// The decoded bytes, or `None` if the string has an odd length or contains
+/// a character which is not a hexadecimal digit.
+pub(crate) fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    let digits = hex.as_bytes();
+
+    if digits.len() % 2 != 0 {
+        return None;
+    }
+
+    let nibble = |digit: u8| match digit {
+        b'0'..=b'9' => Some(digit - b'0'),
+        b'a'..=b'f' => Some(digit - b'a' + 10),
+        b'A'..=b'F' => Some(digit - b'A' + 10),
+        _           => None,
+    };
+
+    digits.chunks(2).map(|pair| Some(nibble(pair[0])? << 4 | nibble(pair[1])?)).collect()
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -101,4 +144,14 @@ mod test {
         assert_eq!(counter_a, counter_b);
         assert_eq!(decoded, data);
     }
+
+    #[test]
+    fn hex_roundtrip() {
+        let data = [0x00, 0x0f, 0xa5, 0xff];
+
+        assert_eq!(format!("{}", Hex(&data)), "000fa5ff");
+        assert_eq!(decode_hex("000fA5ff").unwrap(), data);
+        assert!(decode_hex("000").is_none());
+        assert!(decode_hex("0g").is_none());
+    }
 }
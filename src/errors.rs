@@ -10,39 +10,85 @@
 
 //! Errors which may occur during anonymous credential issuance and verification.
 
-#[cfg(feature = "std")]
-use std::convert::From;
-#[cfg(feature = "std")]
-use std::fmt;
-#[cfg(feature = "std")]
-use std::fmt::Display;
-#[cfg(feature = "std")]
-use std::option::NoneError;
-
-#[cfg(feature = "std")]
-use std::error::Error;
-
-#[cfg(not(feature = "std"))]
+// Formatting needs no allocation, so we always use `core::fmt` in order for
+// errors to be displayable in no_std environments.  Only the `Error` trait
+// requires std.
 use core::convert::From;
-#[cfg(not(feature = "std"))]
 use core::fmt;
-#[cfg(not(feature = "std"))]
 use core::fmt::Display;
-#[cfg(not(feature = "std"))]
-use core::option::NoneError;
 
+#[cfg(feature = "std")]
+use std::error::Error;
+
+#[cfg(any(feature = "alloc", feature = "std"))]
 use zkp::ProofError;
 
+/// Errors which may occur while creating, verifying, or (de)serialising
+/// algebraic MACs and their keys.
+///
+/// With the `defmt` feature, these may also be logged from embedded targets.
+/// No variant carries secret material.
+///
+/// More variants may be added in future, so matches upon these must have a
+/// wildcard arm.
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
-pub(crate) enum MacError {
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum MacError {
     KeypairDeserialisation,
     PointDecompressionError,
+    /// A scalar was not canonically encoded, i.e. was not reduced modulo the
+    /// group order.
+    ScalarFormatError,
     /// An error in the length of bytes handed to a constructor.
     ///
     /// To use this, pass the `length` in bytes which its constructor expects.
     MessageLengthError{ length: usize },
     /// The MAC could not be authenticated.
     AuthenticationError,
+    /// An encrypted secret key backup failed authentication, either because
+    /// the passphrase was wrong or the backup was corrupted.
+    BackupAuthenticationError,
+    /// A revealed group element attribute cannot be hidden, because its
+    /// plaintext is no longer known.
+    UnhideableAttribute,
+    /// A presented attribute's type did not match the type expected for its
+    /// slot.
+    ///
+    /// The `index` is that of the first mismatched attribute.
+    AttributeSlotMismatch{ index: usize },
+    /// A secret key failed its consistency self-test.
+    SelfTestFailure,
+    /// A record could not be read from a stream.
+    StreamReadError,
+    /// Secret key shares were too few, duplicated, or inconsistent, or the
+    /// sharing threshold was invalid.
+    KeyShareError,
+    /// A revealed attribute's value was not among those allowed for its slot.
+    ///
+    /// The `index` is that of the first disallowed attribute.
+    DisallowedAttribute{ index: usize },
+    /// A secret key was used with system parameters other than those to
+    /// which it is bound.
+    ParameterMismatch,
+    /// A serialised credential store had the wrong magic bytes or version,
+    /// failed its checksum, or was truncated.
+    CorruptCredentialStore,
+    /// A serialised object had an unknown wire format version, was of
+    /// another type, or had a body of the wrong length.
+    UnsupportedEncoding,
+    /// An external [`MacSigner`](crate::amacs::MacSigner), such as an HSM,
+    /// failed to perform an operation with its secret key.
+    SignerFailure,
+    /// No attribute of a [`Schema`](crate::attributes::schema::Schema) has
+    /// the given name.
+    UnknownAttribute,
+    /// Two attributes of a [`Schema`](crate::attributes::schema::Schema)
+    /// have the same name.
+    DuplicateAttribute,
+    /// A serialised attribute had an unknown type, or its plaintext was
+    /// followed by non-zero padding.
+    AttributeTypeError,
 }
 
 impl Display for MacError {
@@ -52,53 +98,106 @@ impl Display for MacError {
                 => write!(f, "Cannot deserialise keypair"),
             MacError::PointDecompressionError
                 => write!(f, "Cannot decompress Ristretto point"),
+            MacError::ScalarFormatError
+                => write!(f, "Cannot decode non-canonical scalar"),
             MacError::MessageLengthError{ length: l }
                 => write!(f, "Messages can only have up to {} attributes", l),
             MacError::AuthenticationError
                 => write!(f, "MAC could not be authenticated"),
+            MacError::BackupAuthenticationError
+                => write!(f, "Secret key backup could not be authenticated"),
+            MacError::UnhideableAttribute
+                => write!(f, "Public point attributes cannot be hidden"),
+            MacError::AttributeSlotMismatch{ index: i }
+                => write!(f, "Attribute {} does not have the type expected for its slot", i),
+            MacError::SelfTestFailure
+                => write!(f, "Secret key failed its consistency self-test"),
+            MacError::StreamReadError
+                => write!(f, "Could not read a record from the stream"),
+            MacError::KeyShareError
+                => write!(f, "Secret key shares are insufficient or inconsistent"),
+            MacError::DisallowedAttribute{ index: i }
+                => write!(f, "Attribute {} does not have an allowed value", i),
+            MacError::ParameterMismatch
+                => write!(f, "Secret key is bound to different system parameters"),
+            MacError::CorruptCredentialStore
+                => write!(f, "Credential store is malformed or corrupted"),
+            MacError::UnsupportedEncoding
+                => write!(f, "Unsupported or mismatched wire format"),
+            MacError::SignerFailure
+                => write!(f, "The external signer failed"),
+            MacError::UnknownAttribute
+                => write!(f, "No attribute has the given name"),
+            MacError::DuplicateAttribute
+                => write!(f, "Two attributes have the same name"),
+            MacError::AttributeTypeError
+                => write!(f, "Cannot deserialise attribute of unknown type"),
         }
     }
 }
 
-impl From<NoneError> for MacError {
-    fn from(_source: NoneError) -> MacError {
-        MacError::PointDecompressionError
-    }
-}
-
 #[cfg(feature = "std")]
 impl Error for MacError { }
 
+/// Errors which may occur while issuing, presenting, or verifying anonymous
+/// credentials.
+///
+/// With the `defmt` feature, these may also be logged from embedded targets.
+///
+/// More variants may be added in future, so matches upon these must have a
+/// wildcard arm.
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
 pub enum CredentialError {
+    BackupAuthenticationError,
     BadAttribute,
     CredentialIssuance,
-    MacCreation,
-    MacVerification,
+    Expired,
+    /// An operation upon an algebraic MAC or its keys failed, for the given
+    /// reason, which is also the `source()` of this error.  This replaces the
+    /// former `MacCreation` and `MacVerification` variants.
+    Mac(MacError),
     MissingData,
     NoSymmetricKey,
     NoIssuerKey,
     NoIssuerParameters,
     NoSystemParameters,
+    ParameterMismatch,
     PointDecompressionError,
+    /// A presentation hides or reveals attributes other than those declared
+    /// by a [`Policy`](crate::policy::Policy), or lacks one of its required
+    /// predicates.
+    PolicyViolation,
+    Revoked,
     ScalarFormatError,
     UndecryptableAttribute,
+    /// A message of an interactive protocol, such as an issuance session, was
+    /// replayed or arrived out of order.
+    UnexpectedMessage,
+    /// A presentation with range, set membership, nullifier, pseudonym, or
+    /// escrow proofs, or a designated-verifier proof, cannot yet be
+    /// serialised.
+    UnserialisableStatements,
+    UnsupportedEncoding,
     VerificationFailure,
     WrongNumberOfAttributes,
     WrongNumberOfBytes,
 }
 
-impl fmt::Display for CredentialError {
+impl Display for CredentialError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
+            CredentialError::BackupAuthenticationError
+                => write!(f, "Secret key backup could not be authenticated"),
             CredentialError::BadAttribute
                 => write!(f, "An attribute was unacceptable"),
             CredentialError::CredentialIssuance
                 => write!(f, "Failed to get a credential issued"),
-            CredentialError::MacCreation
-                => write!(f, "Could not create a MAC"),
-            CredentialError::MacVerification
-                => write!(f, "Could not verify a MAC"),
+            CredentialError::Expired
+                => write!(f, "The credential has expired"),
+            CredentialError::Mac(source)
+                => write!(f, "An algebraic MAC operation failed: {}", source),
             CredentialError::MissingData
                 => write!(f, "Some data, such as a key or zkproof, was missing"),
             CredentialError::NoSymmetricKey
@@ -109,12 +208,24 @@ impl fmt::Display for CredentialError {
                 => write!(f, "The issuer was not initialised properly and has no parameters"),
             CredentialError::NoSystemParameters
                 => write!(f, "The system parameters were not initialised"),
+            CredentialError::ParameterMismatch
+                => write!(f, "The credential was issued under different system parameters"),
             CredentialError::PointDecompressionError
                 => write!(f, "Cannot decompress Ristretto point"),
+            CredentialError::PolicyViolation
+                => write!(f, "The presentation does not satisfy the policy"),
+            CredentialError::Revoked
+                => write!(f, "The credential has been revoked"),
             CredentialError::ScalarFormatError
                 => write!(f, "Cannot use scalar with high-bit set"),
             CredentialError::UndecryptableAttribute
                 => write!(f, "A hidden group attribute could not be decrypted"),
+            CredentialError::UnexpectedMessage
+                => write!(f, "A protocol message was replayed or out of order"),
+            CredentialError::UnserialisableStatements
+                => write!(f, "The presentation's additional statements cannot be serialised"),
+            CredentialError::UnsupportedEncoding
+                => write!(f, "Unsupported or mismatched wire format"),
             CredentialError::VerificationFailure
                 => write!(f, "The proof could not be verified"),
             CredentialError::WrongNumberOfAttributes
@@ -125,32 +236,227 @@ impl fmt::Display for CredentialError {
     }
 }
 
-impl From<NoneError> for CredentialError {
-    fn from(_source: NoneError) -> CredentialError {
-        CredentialError::MissingData
-    }
-}
-
+/// Failures with an equivalent [`CredentialError`] are converted to it, and
+/// all others are wrapped in a [`CredentialError::Mac`], so that their cause
+/// is never lost.
 impl From<MacError> for CredentialError {
     fn from(source: MacError) -> CredentialError {
         match source {
-            MacError::KeypairDeserialisation
-                => CredentialError::NoIssuerKey,
             MacError::PointDecompressionError
-                => CredentialError::NoIssuerParameters,
-            MacError::MessageLengthError{ length: _ }
-                => CredentialError::MacCreation,
-            MacError::AuthenticationError
-                => CredentialError::MacVerification,
+                => CredentialError::PointDecompressionError,
+            MacError::ScalarFormatError
+                => CredentialError::ScalarFormatError,
+            MacError::BackupAuthenticationError
+                => CredentialError::BackupAuthenticationError,
+            MacError::ParameterMismatch
+                => CredentialError::ParameterMismatch,
+            MacError::UnsupportedEncoding
+                => CredentialError::UnsupportedEncoding,
+            _   => CredentialError::Mac(source),
         }
     }
 }
 
 #[cfg(feature = "std")]
-impl Error for CredentialError { }
+impl Error for CredentialError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CredentialError::Mac(source) => Some(source),
+            _ => None,
+        }
+    }
+}
 
+#[cfg(any(feature = "alloc", feature = "std"))]
 impl From<ProofError> for CredentialError {
     fn from(_source: ProofError) -> CredentialError {
         CredentialError::VerificationFailure
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use core::fmt::Write;
+    use core::str;
+
+    /// A fixed-size buffer for formatting errors without allocating.
+    struct Buffer {
+        bytes: [u8; 128],
+        length: usize,
+    }
+
+    impl Buffer {
+        fn new() -> Buffer {
+            Buffer { bytes: [0u8; 128], length: 0 }
+        }
+
+        fn as_str(&self) -> &str {
+            str::from_utf8(&self.bytes[..self.length]).unwrap()
+        }
+    }
+
+    impl Write for Buffer {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            let end = self.length + s.len();
+
+            if end > self.bytes.len() {
+                return Err(fmt::Error);
+            }
+            self.bytes[self.length..end].copy_from_slice(s.as_bytes());
+            self.length = end;
+
+            Ok(())
+        }
+    }
+
+    fn display<T: Display>(error: T) -> Buffer {
+        let mut buffer = Buffer::new();
+
+        write!(buffer, "{}", error).unwrap();
+        buffer
+    }
+
+    #[test]
+    fn mac_error_display() {
+        assert_eq!(display(MacError::KeypairDeserialisation).as_str(),
+                   "Cannot deserialise keypair");
+        assert_eq!(display(MacError::PointDecompressionError).as_str(),
+                   "Cannot decompress Ristretto point");
+        assert_eq!(display(MacError::ScalarFormatError).as_str(),
+                   "Cannot decode non-canonical scalar");
+        assert_eq!(display(MacError::MessageLengthError{ length: 4 }).as_str(),
+                   "Messages can only have up to 4 attributes");
+        assert_eq!(display(MacError::AuthenticationError).as_str(),
+                   "MAC could not be authenticated");
+        assert_eq!(display(MacError::BackupAuthenticationError).as_str(),
+                   "Secret key backup could not be authenticated");
+        assert_eq!(display(MacError::UnhideableAttribute).as_str(),
+                   "Public point attributes cannot be hidden");
+        assert_eq!(display(MacError::AttributeSlotMismatch{ index: 2 }).as_str(),
+                   "Attribute 2 does not have the type expected for its slot");
+        assert_eq!(display(MacError::SelfTestFailure).as_str(),
+                   "Secret key failed its consistency self-test");
+        assert_eq!(display(MacError::StreamReadError).as_str(),
+                   "Could not read a record from the stream");
+        assert_eq!(display(MacError::KeyShareError).as_str(),
+                   "Secret key shares are insufficient or inconsistent");
+        assert_eq!(display(MacError::DisallowedAttribute{ index: 1 }).as_str(),
+                   "Attribute 1 does not have an allowed value");
+        assert_eq!(display(MacError::ParameterMismatch).as_str(),
+                   "Secret key is bound to different system parameters");
+        assert_eq!(display(MacError::CorruptCredentialStore).as_str(),
+                   "Credential store is malformed or corrupted");
+        assert_eq!(display(MacError::UnsupportedEncoding).as_str(),
+                   "Unsupported or mismatched wire format");
+        assert_eq!(display(MacError::SignerFailure).as_str(),
+                   "The external signer failed");
+        assert_eq!(display(MacError::UnknownAttribute).as_str(),
+                   "No attribute has the given name");
+        assert_eq!(display(MacError::DuplicateAttribute).as_str(),
+                   "Two attributes have the same name");
+        assert_eq!(display(MacError::AttributeTypeError).as_str(),
+                   "Cannot deserialise attribute of unknown type");
+    }
+
+    #[cfg(feature = "defmt")]
+    fn assert_defmt_format<T: defmt::Format>(_error: T) {}
+
+    // Actually emitting defmt frames requires a global logger on the target,
+    // so on the host we can only check that every variant is formattable.
+    #[cfg(feature = "defmt")]
+    #[test]
+    fn errors_implement_defmt_format() {
+        assert_defmt_format(MacError::KeypairDeserialisation);
+        assert_defmt_format(MacError::PointDecompressionError);
+        assert_defmt_format(MacError::ScalarFormatError);
+        assert_defmt_format(MacError::MessageLengthError{ length: 4 });
+        assert_defmt_format(MacError::AuthenticationError);
+        assert_defmt_format(MacError::BackupAuthenticationError);
+        assert_defmt_format(MacError::UnhideableAttribute);
+        assert_defmt_format(MacError::AttributeSlotMismatch{ index: 2 });
+        assert_defmt_format(MacError::SelfTestFailure);
+        assert_defmt_format(MacError::StreamReadError);
+        assert_defmt_format(MacError::KeyShareError);
+        assert_defmt_format(MacError::DisallowedAttribute{ index: 1 });
+        assert_defmt_format(MacError::ParameterMismatch);
+        assert_defmt_format(MacError::CorruptCredentialStore);
+        assert_defmt_format(MacError::UnsupportedEncoding);
+        assert_defmt_format(MacError::SignerFailure);
+        assert_defmt_format(MacError::UnknownAttribute);
+        assert_defmt_format(MacError::DuplicateAttribute);
+        assert_defmt_format(MacError::AttributeTypeError);
+        assert_defmt_format(CredentialError::BadAttribute);
+        assert_defmt_format(CredentialError::Expired);
+        assert_defmt_format(CredentialError::Mac(MacError::AuthenticationError));
+        assert_defmt_format(CredentialError::ParameterMismatch);
+        assert_defmt_format(CredentialError::PolicyViolation);
+        assert_defmt_format(CredentialError::Revoked);
+        assert_defmt_format(CredentialError::WrongNumberOfBytes);
+    }
+
+    #[test]
+    fn credential_error_display() {
+        assert_eq!(display(CredentialError::BadAttribute).as_str(),
+                   "An attribute was unacceptable");
+        assert_eq!(display(CredentialError::CredentialIssuance).as_str(),
+                   "Failed to get a credential issued");
+        assert_eq!(display(CredentialError::Expired).as_str(),
+                   "The credential has expired");
+        assert_eq!(display(CredentialError::Mac(MacError::SignerFailure)).as_str(),
+                   "An algebraic MAC operation failed: The external signer failed");
+        assert_eq!(display(CredentialError::MissingData).as_str(),
+                   "Some data, such as a key or zkproof, was missing");
+        assert_eq!(display(CredentialError::NoSymmetricKey).as_str(),
+                   "Encrypted group element attributes require a symmetric key");
+        assert_eq!(display(CredentialError::NoIssuerKey).as_str(),
+                   "The issuer was not initialised properly and has no secret key");
+        assert_eq!(display(CredentialError::NoIssuerParameters).as_str(),
+                   "The issuer was not initialised properly and has no parameters");
+        assert_eq!(display(CredentialError::NoSystemParameters).as_str(),
+                   "The system parameters were not initialised");
+        assert_eq!(display(CredentialError::ParameterMismatch).as_str(),
+                   "The credential was issued under different system parameters");
+        assert_eq!(display(CredentialError::PointDecompressionError).as_str(),
+                   "Cannot decompress Ristretto point");
+        assert_eq!(display(CredentialError::PolicyViolation).as_str(),
+                   "The presentation does not satisfy the policy");
+        assert_eq!(display(CredentialError::Revoked).as_str(),
+                   "The credential has been revoked");
+        assert_eq!(display(CredentialError::ScalarFormatError).as_str(),
+                   "Cannot use scalar with high-bit set");
+        assert_eq!(display(CredentialError::UndecryptableAttribute).as_str(),
+                   "A hidden group attribute could not be decrypted");
+        assert_eq!(display(CredentialError::UnexpectedMessage).as_str(),
+                   "A protocol message was replayed or out of order");
+        assert_eq!(display(CredentialError::UnserialisableStatements).as_str(),
+                   "The presentation's additional statements cannot be serialised");
+        assert_eq!(display(CredentialError::UnsupportedEncoding).as_str(),
+                   "Unsupported or mismatched wire format");
+        assert_eq!(display(CredentialError::VerificationFailure).as_str(),
+                   "The proof could not be verified");
+        assert_eq!(display(CredentialError::WrongNumberOfAttributes).as_str(),
+                   "The credential did not have the correct number of attributes");
+        assert_eq!(display(CredentialError::WrongNumberOfBytes).as_str(),
+                   "The credential could not be deserialised because it was not a multiple of 32 bytes");
+    }
+
+    #[test]
+    fn mac_errors_convert_without_losing_their_cause() {
+        assert_eq!(CredentialError::from(MacError::PointDecompressionError), CredentialError::PointDecompressionError);
+        assert_eq!(CredentialError::from(MacError::ScalarFormatError), CredentialError::ScalarFormatError);
+        assert_eq!(CredentialError::from(MacError::MessageLengthError{ length: 96 }),
+                   CredentialError::Mac(MacError::MessageLengthError{ length: 96 }));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn credential_errors_chain_their_sources() {
+        let error = CredentialError::from(MacError::AuthenticationError);
+        let source = error.source().and_then(|source| source.downcast_ref::<MacError>());
+
+        assert_eq!(source, Some(&MacError::AuthenticationError));
+        assert!(CredentialError::VerificationFailure.source().is_none());
+    }
+}
@@ -0,0 +1,366 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2020 The Brave Authors
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! A C foreign function interface to the issuer's side of the protocol.
+//!
+//! [`SystemParameters`] and [`Issuer`]s are passed to C as opaque handles,
+//! which must be released with their `_free` function, while credential
+//! requests, issuances, and presentations are passed as their
+//! [`wire`](crate::wire) encodings.  Bytes returned to C are owned by an
+//! [`AeonfluxBuffer`], which must be released with [`aeonflux_buffer_free`].
+//!
+//! The header `include/aeonflux.h` is generated from this module with
+//! `cbindgen --config cbindgen.toml --output include/aeonflux.h`, and should
+//! be regenerated whenever this module changes.  (Building with the `ffi`
+//! feature also generates it, but only into the build's `OUT_DIR`, to check
+//! that it still generates.)  The static or dynamic library for linking into
+//! e.g. a Swift or Kotlin application may be built with
+//! `cargo rustc --release --features ffi --crate-type staticlib` (or
+//! `cdylib`).
+//!
+//! Randomness is taken from the operating system.
+
+use std::boxed::Box;
+use std::ptr;
+use std::slice;
+use std::vec::Vec;
+
+use rand_core::OsRng;
+
+use crate::issuer::Issuer;
+use crate::nizk::presentation::ProofOfValidCredential;
+use crate::parameters::SystemParameters;
+use crate::user::CredentialRequest;
+
+/// The result of a fallible FFI call.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AeonfluxStatus {
+    /// The call succeeded.
+    Ok = 0,
+    /// A required pointer was null.
+    NullPointer = 1,
+    /// Some input bytes could not be deserialised.
+    Malformed = 2,
+    /// Issuance failed, or a presentation did not verify.
+    Failure = 3,
+}
+
+/// Some bytes returned to C, which must be released with
+/// [`aeonflux_buffer_free`].
+#[repr(C)]
+#[derive(Debug)]
+pub struct AeonfluxBuffer {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+impl From<Vec<u8>> for AeonfluxBuffer {
+    fn from(bytes: Vec<u8>) -> AeonfluxBuffer {
+        let len = bytes.len();
+        let data = Box::into_raw(bytes.into_boxed_slice()) as *mut u8;
+
+        AeonfluxBuffer { data, len }
+    }
+}
+
+/// Borrow `len` bytes from C, if the pointer is non-null.
+unsafe fn borrow<'a>(bytes: *const u8, len: usize) -> Option<&'a [u8]> {
+    if bytes.is_null() {
+        return None;
+    }
+    Some(slice::from_raw_parts(bytes, len))
+}
+
+/// Return some `bytes` to C through the `out` pointer.
+unsafe fn give(bytes: Vec<u8>, out: *mut AeonfluxBuffer) -> AeonfluxStatus {
+    if out.is_null() {
+        return AeonfluxStatus::NullPointer;
+    }
+    *out = AeonfluxBuffer::from(bytes);
+
+    AeonfluxStatus::Ok
+}
+
+/// Release a buffer returned by this library.
+///
+/// # Safety
+///
+/// The `buffer` must have been returned by this library, and not already
+/// released.
+#[no_mangle]
+pub unsafe extern "C" fn aeonflux_buffer_free(buffer: AeonfluxBuffer) {
+    if !buffer.data.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(buffer.data, buffer.len)));
+    }
+}
+
+/// Generate new [`SystemParameters`] for credentials with
+/// `number_of_attributes`.
+///
+/// # Returns
+///
+/// A handle to the system parameters, or null on failure.
+#[no_mangle]
+pub extern "C" fn aeonflux_system_parameters_generate(number_of_attributes: u32) -> *mut SystemParameters {
+    match SystemParameters::generate(&mut OsRng, number_of_attributes) {
+        Ok(system_parameters) => Box::into_raw(Box::new(system_parameters)),
+        Err(_)                => ptr::null_mut(),
+    }
+}
+
+/// Deserialise [`SystemParameters`] from `len` bytes.
+///
+/// # Returns
+///
+/// A handle to the system parameters, or null if the bytes are malformed.
+///
+/// # Safety
+///
+/// The `bytes` must be valid for reads of `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn aeonflux_system_parameters_from_bytes(bytes: *const u8, len: usize) -> *mut SystemParameters {
+    match borrow(bytes, len).map(SystemParameters::from_bytes) {
+        Some(Ok(system_parameters)) => Box::into_raw(Box::new(system_parameters)),
+        _                           => ptr::null_mut(),
+    }
+}
+
+/// Serialise the `system_parameters` into the buffer `out`.
+///
+/// # Safety
+///
+/// The `system_parameters` must be a live handle, and `out` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn aeonflux_system_parameters_to_bytes(
+    system_parameters: *const SystemParameters,
+    out: *mut AeonfluxBuffer,
+) -> AeonfluxStatus
+{
+    match system_parameters.as_ref() {
+        Some(system_parameters) => give(system_parameters.to_bytes(), out),
+        None                    => AeonfluxStatus::NullPointer,
+    }
+}
+
+/// Release a [`SystemParameters`] handle.
+///
+/// # Safety
+///
+/// The `system_parameters` must be null, or a live handle which is not used
+/// again.
+#[no_mangle]
+pub unsafe extern "C" fn aeonflux_system_parameters_free(system_parameters: *mut SystemParameters) {
+    if !system_parameters.is_null() {
+        drop(Box::from_raw(system_parameters));
+    }
+}
+
+/// Generate a new [`Issuer`], with a fresh key, under the
+/// `system_parameters`.
+///
+/// # Returns
+///
+/// A handle to the issuer, or null if the `system_parameters` are null.
+///
+/// # Safety
+///
+/// The `system_parameters` must be null or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn aeonflux_issuer_generate(system_parameters: *const SystemParameters) -> *mut Issuer {
+    match system_parameters.as_ref() {
+        Some(system_parameters) => Box::into_raw(Box::new(Issuer::new(system_parameters, &mut OsRng))),
+        None                    => ptr::null_mut(),
+    }
+}
+
+/// Deserialise an [`Issuer`], including its secret key, from `len` bytes.
+///
+/// # Returns
+///
+/// A handle to the issuer, or null if the bytes are malformed.
+///
+/// # Safety
+///
+/// The `bytes` must be valid for reads of `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn aeonflux_issuer_from_bytes(bytes: *const u8, len: usize) -> *mut Issuer {
+    match borrow(bytes, len).map(Issuer::from_bytes) {
+        Some(Ok(issuer)) => Box::into_raw(Box::new(issuer)),
+        _                => ptr::null_mut(),
+    }
+}
+
+/// Serialise the `issuer`, including its secret key, into the buffer `out`.
+///
+/// # Safety
+///
+/// The `issuer` must be a live handle, and `out` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn aeonflux_issuer_to_bytes(issuer: *const Issuer, out: *mut AeonfluxBuffer) -> AeonfluxStatus {
+    match issuer.as_ref() {
+        Some(issuer) => give(issuer.to_bytes(), out),
+        None         => AeonfluxStatus::NullPointer,
+    }
+}
+
+/// Serialise the `issuer`'s public parameters, to publish to its users, into
+/// the buffer `out`.
+///
+/// # Safety
+///
+/// The `issuer` must be a live handle, and `out` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn aeonflux_issuer_parameters_to_bytes(
+    issuer: *const Issuer,
+    out: *mut AeonfluxBuffer,
+) -> AeonfluxStatus
+{
+    match issuer.as_ref() {
+        Some(issuer) => give(issuer.issuer_parameters.to_bytes(), out),
+        None         => AeonfluxStatus::NullPointer,
+    }
+}
+
+/// Release an [`Issuer`] handle, zeroing its secret key.
+///
+/// # Safety
+///
+/// The `issuer` must be null, or a live handle which is not used again.
+#[no_mangle]
+pub unsafe extern "C" fn aeonflux_issuer_free(issuer: *mut Issuer) {
+    if !issuer.is_null() {
+        drop(Box::from_raw(issuer));
+    }
+}
+
+/// Issue a credential for a serialised
+/// [`CredentialRequest`](crate::user::CredentialRequest) of `len` bytes,
+/// writing the serialised
+/// [`CredentialIssuance`](crate::issuer::CredentialIssuance) into the
+/// buffer `out`.
+///
+/// # Safety
+///
+/// The `issuer` must be a live handle, the `request` valid for reads of
+/// `len` bytes, and `out` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn aeonflux_issuer_issue(
+    issuer: *const Issuer,
+    request: *const u8,
+    len: usize,
+    out: *mut AeonfluxBuffer,
+) -> AeonfluxStatus
+{
+    let (issuer, request) = match (issuer.as_ref(), borrow(request, len)) {
+        (Some(issuer), Some(request)) => (issuer, request),
+        _ => return AeonfluxStatus::NullPointer,
+    };
+    let request = match CredentialRequest::from_bytes(request) {
+        Ok(request) => request,
+        Err(_)      => return AeonfluxStatus::Malformed,
+    };
+
+    match issuer.issue(request, &mut OsRng) {
+        Ok(issuance) => give(issuance.to_bytes(), out),
+        Err(_)       => AeonfluxStatus::Failure,
+    }
+}
+
+/// Verify a serialised presentation of `len` bytes.
+///
+/// # Returns
+///
+/// [`AeonfluxStatus::Ok`] if the presentation is valid, otherwise
+/// [`AeonfluxStatus::Failure`], or another status for malformed input.
+///
+/// # Safety
+///
+/// The `issuer` must be a live handle, and the `presentation` valid for
+/// reads of `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn aeonflux_issuer_verify(
+    issuer: *const Issuer,
+    presentation: *const u8,
+    len: usize,
+) -> AeonfluxStatus
+{
+    let (issuer, presentation) = match (issuer.as_ref(), borrow(presentation, len)) {
+        (Some(issuer), Some(presentation)) => (issuer, presentation),
+        _ => return AeonfluxStatus::NullPointer,
+    };
+
+    let presentation = match ProofOfValidCredential::from_bytes(presentation) {
+        Ok(presentation) => presentation,
+        Err(_)           => return AeonfluxStatus::Malformed,
+    };
+
+    match issuer.verify(&presentation) {
+        Ok(())  => AeonfluxStatus::Ok,
+        Err(_)  => AeonfluxStatus::Failure,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::issuer::CredentialIssuance;
+    use crate::user::CredentialRequestConstructor;
+
+    use curve25519_dalek::scalar::Scalar;
+
+    unsafe fn take(buffer: AeonfluxBuffer) -> Vec<u8> {
+        let bytes = slice::from_raw_parts(buffer.data, buffer.len).to_vec();
+
+        aeonflux_buffer_free(buffer);
+        bytes
+    }
+
+    #[test]
+    fn issue_and_verify_through_ffi() {
+        unsafe {
+            let mut out = AeonfluxBuffer { data: ptr::null_mut(), len: 0 };
+            let system_parameters = aeonflux_system_parameters_generate(2);
+            let generated = aeonflux_issuer_generate(system_parameters);
+
+            // The issuer survives serialisation.
+            assert_eq!(aeonflux_issuer_to_bytes(generated, &mut out), AeonfluxStatus::Ok);
+
+            let bytes = take(out);
+            let issuer = aeonflux_issuer_from_bytes(bytes.as_ptr(), bytes.len());
+
+            aeonflux_issuer_free(generated);
+            assert!(!issuer.is_null());
+
+            let mut request = CredentialRequestConstructor::new(&*system_parameters);
+
+            request.append_revealed_scalar(Scalar::one());
+            request.append_revealed_scalar(Scalar::one());
+
+            let request = request.finish().to_bytes();
+            let mut out = AeonfluxBuffer { data: ptr::null_mut(), len: 0 };
+
+            assert_eq!(aeonflux_issuer_issue(issuer, request.as_ptr(), request.len(), &mut out), AeonfluxStatus::Ok);
+            assert_eq!(aeonflux_issuer_issue(issuer, request.as_ptr(), 3, &mut out), AeonfluxStatus::Malformed);
+
+            let issuance = CredentialIssuance::from_bytes(&take(out)).unwrap();
+            let credential = issuance.verify(&*system_parameters, &(*issuer).issuer_parameters).unwrap();
+            let presentation = credential.show(&*system_parameters, &(*issuer).issuer_parameters, None, OsRng)
+                .unwrap().to_bytes().unwrap();
+
+            assert_eq!(aeonflux_issuer_verify(issuer, presentation.as_ptr(), presentation.len()), AeonfluxStatus::Ok);
+            assert_eq!(aeonflux_issuer_verify(issuer, ptr::null(), 0), AeonfluxStatus::NullPointer);
+            assert_eq!(aeonflux_issuer_verify(issuer, presentation.as_ptr(), 1), AeonfluxStatus::Malformed);
+
+            aeonflux_issuer_free(issuer);
+            aeonflux_system_parameters_free(system_parameters);
+        }
+    }
+}
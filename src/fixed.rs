@@ -0,0 +1,339 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2020 The Brave Authors
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Algebraic MACs upon a fixed number of scalar attributes, which never
+//! allocate.
+//!
+//! The [`SystemParameters`](crate::parameters::SystemParameters) and
+//! [`SecretKey`] store their per-attribute generators and scalars in `Vec`s,
+//! since the number of attributes is only known at runtime.  For embedded
+//! issuers and verifiers which know it at compile time, [`FixedGenerators`]
+//! and [`FixedSecretKey`] store them in arrays of length `N` instead, so that
+//! keys may be generated and tags computed and verified without a heap.
+//!
+//! The generators are derived exactly as in
+//! [`SystemParameters::generate_from_seed`](crate::parameters::SystemParameters::generate_from_seed),
+//! thus tags made with a [`FixedSecretKey`] are interoperable with those of
+//! the equivalent [`SecretKey`] for `N` secret scalar attributes.
+//!
+//! # Warning
+//!
+//! Only the algebraic MACs are heap-free.  Without either the `alloc` or
+//! `std` feature, this module and its errors are all that is built, and the
+//! rest of the crate (along with its NIZKs) is unavailable.
+
+#[cfg(any(feature = "alloc", feature = "std"))]
+use core::convert::TryFrom;
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use subtle::ConstantTimeEq;
+
+use sha2::Digest;
+use sha2::Sha512;
+
+use zeroize::Zeroize;
+
+#[cfg(any(feature = "alloc", feature = "std"))]
+use crate::amacs::SecretKey;
+use crate::errors::MacError;
+use crate::rng::random_nondegenerate_scalar;
+
+/// The domain separator of generators derived from a seed or domain, by
+/// [`SystemParameters::generate_from_seed`](crate::parameters::SystemParameters::generate_from_seed)
+/// and
+/// [`SystemParameters::generate_nums`](crate::parameters::SystemParameters::generate_nums).
+pub(crate) const DERIVATION_DOMAIN: &[u8] = b"aeonflux SystemParameters derivation";
+
+/// Deterministically derive a generator named `name` (with some `index`, for
+/// the generators which are vectors) from a `seed` by hashing to the group
+/// under a `domain` separator.
+///
+/// All inputs are length-prefixed, so that distinct inputs can never produce
+/// the same hash input.
+pub(crate) fn derive_generator(
+    domain: &[u8],
+    seed: &[u8],
+    name: &[u8],
+    index: u32,
+) -> RistrettoPoint
+{
+    let mut hash = Sha512::default();
+
+    hash.input(domain);
+    hash.input((seed.len() as u64).to_le_bytes());
+    hash.input(seed);
+    hash.input((name.len() as u64).to_le_bytes());
+    hash.input(name);
+    hash.input(index.to_le_bytes());
+
+    RistrettoPoint::from_hash(hash)
+}
+
+/// The generators needed to tag and verify algebraic MACs upon `N` scalar
+/// attributes.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct FixedGenerators<const N: usize> {
+    pub(crate) G_w: RistrettoPoint,
+    pub(crate) G_m: [RistrettoPoint; N],
+}
+
+impl<const N: usize> FixedGenerators<N> {
+    /// Deterministically derive the generators from a 32-byte `seed`, as in
+    /// [`SystemParameters::generate_from_seed`](crate::parameters::SystemParameters::generate_from_seed)
+    /// for `N` attributes.
+    pub fn from_seed(seed: &[u8; 32]) -> FixedGenerators<N> {
+        let mut G_m = [RistrettoPoint::identity(); N];
+
+        for (i, G_m_i) in G_m.iter_mut().enumerate() {
+            *G_m_i = derive_generator(DERIVATION_DOMAIN, &seed[..], b"G_m", i as u32);
+        }
+
+        FixedGenerators { G_w: derive_generator(DERIVATION_DOMAIN, &seed[..], b"G_w", 0), G_m }
+    }
+}
+
+/// An algebraic MAC secret key for `N` scalar attributes, as a [`SecretKey`]
+/// whose \\(( y_i \\)) are stored in an array.
+#[derive(Clone, Debug)]
+pub struct FixedSecretKey<const N: usize> {
+    pub(crate) w: Scalar,
+    pub(crate) w_prime: Scalar,
+    pub(crate) x_0: Scalar,
+    pub(crate) x_1: Scalar,
+    pub(crate) y: [Scalar; N],
+    pub(crate) W: RistrettoPoint,
+}
+
+impl<const N: usize> Zeroize for FixedSecretKey<N> {
+    fn zeroize(&mut self) {
+        self.w.zeroize();
+        self.w_prime.zeroize();
+        self.x_0.zeroize();
+        self.x_1.zeroize();
+        self.y.iter_mut().for_each(|y_i| y_i.zeroize());
+
+        self.W = RistrettoPoint::identity();
+    }
+}
+
+impl<const N: usize> Drop for FixedSecretKey<N> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+/// An algebraic MAC, \\(( (t, U, V) \\)), made with a [`FixedSecretKey`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct FixedTag {
+    pub(crate) t: Scalar,
+    pub(crate) U: RistrettoPoint,
+    pub(crate) V: RistrettoPoint,
+}
+
+impl FixedTag {
+    /// The length, in bytes, of a serialised tag.
+    pub const SERIALIZED_LEN: usize = 96;
+
+    /// Serialise this tag to [`FixedTag::SERIALIZED_LEN`] bytes.
+    pub fn to_bytes(&self) -> [u8; 96] {
+        let mut bytes = [0u8; 96];
+
+        bytes[0..32].copy_from_slice(self.t.as_bytes());
+        bytes[32..64].copy_from_slice(self.U.compress().as_bytes());
+        bytes[64..96].copy_from_slice(self.V.compress().as_bytes());
+        bytes
+    }
+
+    /// Deserialise a tag previously serialised with [`FixedTag::to_bytes`].
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the tag, otherwise a [`MacError`] if
+    /// the `bytes` are malformed.
+    pub fn from_bytes(bytes: &[u8; 96]) -> Result<FixedTag, MacError> {
+        let mut chunk = [0u8; 32];
+
+        chunk.copy_from_slice(&bytes[0..32]);
+        let t = match Scalar::from_canonical_bytes(chunk) {
+            Some(t) => t,
+            None    => return Err(MacError::ScalarFormatError),
+        };
+
+        chunk.copy_from_slice(&bytes[32..64]);
+        let U = match CompressedRistretto(chunk).decompress() {
+            Some(U) => U,
+            None    => return Err(MacError::PointDecompressionError),
+        };
+
+        chunk.copy_from_slice(&bytes[64..96]);
+        let V = match CompressedRistretto(chunk).decompress() {
+            Some(V) => V,
+            None    => return Err(MacError::PointDecompressionError),
+        };
+
+        Ok(FixedTag { t, U, V })
+    }
+}
+
+impl<const N: usize> FixedSecretKey<N> {
+    /// Given the `generators`, generate a secret key, as in
+    /// [`SecretKey::generate`].
+    pub fn generate<R>(csprng: &mut R, generators: &FixedGenerators<N>) -> FixedSecretKey<N>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let w:       Scalar = random_nondegenerate_scalar(csprng);
+        let w_prime: Scalar = random_nondegenerate_scalar(csprng);
+        let x_0:     Scalar = random_nondegenerate_scalar(csprng);
+        let x_1:     Scalar = random_nondegenerate_scalar(csprng);
+
+        let mut y = [Scalar::zero(); N];
+
+        for y_i in y.iter_mut() {
+            *y_i = random_nondegenerate_scalar(csprng);
+        }
+
+        let W: RistrettoPoint = generators.G_w * w;
+
+        FixedSecretKey { w, w_prime, x_0, x_1, y, W }
+    }
+
+    /// Compute \\(( V = W + (U (x_0 + x_1 t)) + \sigma{i=1}{n} M_i y_i \\)),
+    /// where \\(( M_i = G_m_i * m_i \\)).
+    ///
+    /// Without `alloc` there is no multiscalar multiplication, so each term
+    /// is computed separately.
+    fn compute_V(
+        &self,
+        generators: &FixedGenerators<N>,
+        messages: &[Scalar; N],
+        t: &Scalar,
+        U: &RistrettoPoint,
+    ) -> RistrettoPoint
+    {
+        let mut V: RistrettoPoint = self.W + (U * (self.x_0 + self.x_1 * t));
+
+        for ((G_m_i, m_i), y_i) in generators.G_m.iter().zip(messages.iter()).zip(self.y.iter()) {
+            V += G_m_i * (m_i * y_i);
+        }
+        V
+    }
+
+    /// Compute an algebraic MAC upon some scalar `messages`.
+    pub fn tag<R>(
+        &self,
+        csprng: &mut R,
+        generators: &FixedGenerators<N>,
+        messages: &[Scalar; N],
+    ) -> FixedTag
+    where
+        R: RngCore + CryptoRng,
+    {
+        let t: Scalar = Scalar::random(csprng);
+        let U: RistrettoPoint = RistrettoPoint::random(csprng);
+        let V: RistrettoPoint = self.compute_V(generators, messages, &t, &U);
+
+        FixedTag { t, U, V }
+    }
+
+    /// Verify an algebraic MAC upon some scalar `messages`, in constant time.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is empty if the `tag` is valid, otherwise
+    /// a [`MacError::AuthenticationError`].
+    pub fn verify(
+        &self,
+        generators: &FixedGenerators<N>,
+        tag: &FixedTag,
+        messages: &[Scalar; N],
+    ) -> Result<(), MacError>
+    {
+        let V_prime = self.compute_V(generators, messages, &tag.t, &tag.U);
+
+        if tag.V.compress().ct_eq(&V_prime.compress()).into() {
+            return Ok(());
+        }
+        Err(MacError::AuthenticationError)
+    }
+}
+
+#[cfg(any(feature = "alloc", feature = "std"))]
+impl<const N: usize> TryFrom<&SecretKey> for FixedSecretKey<N> {
+    type Error = MacError;
+
+    /// Convert a [`SecretKey`] for exactly `N` attributes.
+    fn try_from(secret_key: &SecretKey) -> Result<FixedSecretKey<N>, MacError> {
+        if secret_key.y.len() != N {
+            return Err(MacError::MessageLengthError{ length: N });
+        }
+
+        let mut y = [Scalar::zero(); N];
+
+        y.copy_from_slice(&secret_key.y[..]);
+
+        Ok(FixedSecretKey {
+            w: secret_key.w,
+            w_prime: secret_key.w_prime,
+            x_0: secret_key.x_0,
+            x_1: secret_key.x_1,
+            y,
+            W: secret_key.W,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[cfg(all(not(feature = "std"), feature = "alloc"))]
+    use alloc::vec::Vec;
+    #[cfg(all(not(feature = "alloc"), feature = "std"))]
+    use std::vec::Vec;
+
+    use crate::amacs::Amac;
+    use crate::amacs::Attribute;
+    use crate::parameters::SystemParameters;
+
+    use rand::thread_rng;
+
+    #[test]
+    fn fixed_tag_interoperates_with_amac() {
+        let mut rng = thread_rng();
+        let seed = [7u8; 32];
+        let generators = FixedGenerators::<3>::from_seed(&seed);
+        let system_parameters = SystemParameters::generate_from_seed(&seed, 3).unwrap();
+        let secret_key = SecretKey::generate(&mut rng, &system_parameters);
+        let fixed = FixedSecretKey::<3>::try_from(&secret_key).unwrap();
+        let messages = [Scalar::random(&mut rng), Scalar::random(&mut rng), Scalar::random(&mut rng)];
+
+        assert_eq!(&generators.G_m[..], &system_parameters.G_m[..]);
+
+        let tag = fixed.tag(&mut rng, &generators, &messages);
+        let tag = FixedTag::from_bytes(&tag.to_bytes()).unwrap();
+
+        assert!(fixed.verify(&generators, &tag, &messages).is_ok());
+        assert!(fixed.verify(&generators, &tag, &[messages[0], messages[2], messages[1]]).is_err());
+
+        // The tag verifies as an algebraic MAC upon secret scalar attributes.
+        let attributes: Vec<Attribute> = messages.iter().map(|m| Attribute::SecretScalar(*m)).collect();
+        let amac = Amac { t: tag.t, U: tag.U, V: tag.V };
+
+        assert!(amac.verify(&system_parameters, &secret_key, &attributes).is_ok());
+        assert!(FixedSecretKey::<2>::try_from(&secret_key).is_err());
+    }
+}
@@ -0,0 +1,455 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2020 The Brave Authors
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! CBOR envelopes of presentations and issuances, for carrying this crate's
+//! messages within CBOR-based protocols.
+//!
+//! An [`Envelope`] wraps a message in its [`wire`] format as a CBOR map
+//! (RFC 8949) whose keys are COSE header labels (RFC 9052), so that the
+//! message may be routed and matched to the right issuer without parsing it:
+//!
+//! | label    | COSE name    | value                                                |
+//! |----------|--------------|------------------------------------------------------|
+//! | `1`      | alg          | the text string [`ALGORITHM`]                        |
+//! | `3`      | content type | the text string of the message's [`ContentType`]     |
+//! | `4`      | kid          | the 32-byte [`key_id`] of the issuer's parameters    |
+//! | `-65537` | (private)    | the 32-byte fingerprint of the system parameters     |
+//! | `-65538` | (private)    | the message, as a byte string in the [`wire`] format |
+//!
+//! Envelopes are encoded deterministically, i.e. with the shortest
+//! encodings of lengths and labels, and the labels in the above order.  Only
+//! this form is accepted upon decoding.
+//!
+//! # Warning
+//!
+//! The headers are not authenticated.  The recipient must check them against
+//! the parameters it expects with [`Envelope::check`], and still verify the
+//! message itself.
+//!
+//! [`wire`]: crate::wire
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use sha2::Digest;
+use sha2::Sha512Trunc256;
+
+use crate::errors::CredentialError;
+use crate::issuer::CredentialIssuance;
+use crate::nizk::presentation::ProofOfValidCredential;
+use crate::parameters::IssuerParameters;
+use crate::parameters::SystemParameters;
+
+/// The value of the COSE `alg` header of every [`Envelope`].
+pub const ALGORITHM: &str = "aeonflux-2019/1416";
+
+/// The COSE header label of the algorithm.
+const LABEL_ALG: i64 = 1;
+
+/// The COSE header label of the content type.
+const LABEL_CONTENT_TYPE: i64 = 3;
+
+/// The COSE header label of the key identifier.
+const LABEL_KID: i64 = 4;
+
+/// The private-use header label of the system parameters fingerprint.
+const LABEL_PARAMETERS: i64 = -65537;
+
+/// The private-use label of the message.
+const LABEL_PAYLOAD: i64 = -65538;
+
+/// The CBOR major type of an unsigned integer.
+const MAJOR_UNSIGNED: u8 = 0;
+
+/// The CBOR major type of a negative integer.
+const MAJOR_NEGATIVE: u8 = 1;
+
+/// The CBOR major type of a byte string.
+const MAJOR_BYTES: u8 = 2;
+
+/// The CBOR major type of a text string.
+const MAJOR_TEXT: u8 = 3;
+
+/// The CBOR major type of a map.
+const MAJOR_MAP: u8 = 5;
+
+/// The type of message within an [`Envelope`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum ContentType {
+    /// A [`ProofOfValidCredential`].
+    Presentation,
+    /// A [`CredentialIssuance`].
+    Issuance,
+}
+
+impl ContentType {
+    /// The media type of this content type, as in the COSE `content type`
+    /// header.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContentType::Presentation => "application/aeonflux-presentation",
+            ContentType::Issuance     => "application/aeonflux-issuance",
+        }
+    }
+
+    /// The content type with the media type `name`, if there is one.
+    fn from_name(name: &[u8]) -> Option<ContentType> {
+        [ContentType::Presentation, ContentType::Issuance].iter()
+            .find(|content_type| content_type.as_str().as_bytes() == name)
+            .copied()
+    }
+}
+
+/// A 32-byte identifier of an issuer's key, i.e. a hash of its public
+/// `issuer_parameters`, as in the COSE `kid` header of an [`Envelope`].
+pub fn key_id(issuer_parameters: &IssuerParameters) -> [u8; 32] {
+    let mut hash = Sha512Trunc256::default();
+    let mut id = [0u8; 32];
+
+    hash.input(b"aeonflux IssuerParameters key id");
+    hash.input(issuer_parameters.to_bytes());
+    id.copy_from_slice(&hash.result()[..]);
+    id
+}
+
+/// A presentation or issuance, with headers identifying its type and the
+/// parameters under which it was made.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Envelope {
+    /// The type of the message.
+    pub content_type: ContentType,
+    /// The [`key_id`] of the issuer's parameters.
+    pub key_id: [u8; 32],
+    /// The [`SystemParameters::identifier`] of the system parameters.
+    pub parameters_fingerprint: [u8; 32],
+    /// The message, in the [`wire`](crate::wire) format.
+    pub payload: Vec<u8>,
+}
+
+impl Envelope {
+    /// Wrap a `presentation` of a credential issued under the
+    /// `system_parameters` and `issuer_parameters`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the envelope, otherwise a
+    /// [`CredentialError`] if the presentation cannot be serialised.
+    pub fn presentation(
+        presentation: &ProofOfValidCredential,
+        system_parameters: &SystemParameters,
+        issuer_parameters: &IssuerParameters,
+    ) -> Result<Envelope, CredentialError>
+    {
+        Ok(Envelope {
+            content_type: ContentType::Presentation,
+            key_id: key_id(issuer_parameters),
+            parameters_fingerprint: system_parameters.identifier(),
+            payload: presentation.to_bytes()?,
+        })
+    }
+
+    /// Wrap an `issuance` made under the `system_parameters` and
+    /// `issuer_parameters`.
+    pub fn issuance(
+        issuance: &CredentialIssuance,
+        system_parameters: &SystemParameters,
+        issuer_parameters: &IssuerParameters,
+    ) -> Envelope
+    {
+        Envelope {
+            content_type: ContentType::Issuance,
+            key_id: key_id(issuer_parameters),
+            parameters_fingerprint: system_parameters.identifier(),
+            payload: issuance.to_bytes(),
+        }
+    }
+
+    /// Check that this envelope's headers name the `system_parameters` and
+    /// `issuer_parameters` the recipient expects.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is empty, otherwise a
+    /// [`CredentialError::ParameterMismatch`].
+    pub fn check(
+        &self,
+        system_parameters: &SystemParameters,
+        issuer_parameters: &IssuerParameters,
+    ) -> Result<(), CredentialError>
+    {
+        if self.parameters_fingerprint != system_parameters.identifier() || self.key_id != key_id(issuer_parameters) {
+            return Err(CredentialError::ParameterMismatch);
+        }
+        Ok(())
+    }
+
+    /// Unwrap the presentation in this envelope.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the presentation, otherwise a
+    /// [`CredentialError::UnsupportedEncoding`] if this envelope holds
+    /// another type of message, or another [`CredentialError`] if the
+    /// presentation is malformed.
+    pub fn open_presentation(&self) -> Result<ProofOfValidCredential, CredentialError> {
+        match self.content_type {
+            ContentType::Presentation => ProofOfValidCredential::from_bytes(&self.payload),
+            _ => Err(CredentialError::UnsupportedEncoding),
+        }
+    }
+
+    /// Unwrap the issuance in this envelope, as for
+    /// [`Envelope::open_presentation`].
+    pub fn open_issuance(&self) -> Result<CredentialIssuance, CredentialError> {
+        match self.content_type {
+            ContentType::Issuance => CredentialIssuance::from_bytes(&self.payload),
+            _ => Err(CredentialError::UnsupportedEncoding),
+        }
+    }
+
+    /// Encode this envelope as a CBOR map, as described in the
+    /// [module documentation](self).
+    pub fn to_cbor(&self) -> Vec<u8> {
+        let content_type = self.content_type.as_str().as_bytes();
+        let mut bytes: Vec<u8> = Vec::with_capacity(128 + content_type.len() + self.payload.len());
+
+        write_head(&mut bytes, MAJOR_MAP, 5);
+
+        write_label(&mut bytes, LABEL_ALG);
+        write_string(&mut bytes, MAJOR_TEXT, ALGORITHM.as_bytes());
+        write_label(&mut bytes, LABEL_CONTENT_TYPE);
+        write_string(&mut bytes, MAJOR_TEXT, content_type);
+        write_label(&mut bytes, LABEL_KID);
+        write_string(&mut bytes, MAJOR_BYTES, &self.key_id);
+        write_label(&mut bytes, LABEL_PARAMETERS);
+        write_string(&mut bytes, MAJOR_BYTES, &self.parameters_fingerprint);
+        write_label(&mut bytes, LABEL_PAYLOAD);
+        write_string(&mut bytes, MAJOR_BYTES, &self.payload);
+
+        bytes
+    }
+
+    /// Decode an envelope encoded with [`Envelope::to_cbor`].
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the envelope, otherwise a
+    /// [`CredentialError::UnsupportedEncoding`] if the `bytes` are not a
+    /// deterministically-encoded envelope with the expected headers.
+    pub fn from_cbor(bytes: &[u8]) -> Result<Envelope, CredentialError> {
+        let mut reader = Reader { bytes, offset: 0 };
+
+        if reader.head()? != (MAJOR_MAP, 5) {
+            return Err(CredentialError::UnsupportedEncoding);
+        }
+
+        reader.label(LABEL_ALG)?;
+
+        if reader.string(MAJOR_TEXT)? != ALGORITHM.as_bytes() {
+            return Err(CredentialError::UnsupportedEncoding);
+        }
+
+        reader.label(LABEL_CONTENT_TYPE)?;
+
+        let content_type = ContentType::from_name(reader.string(MAJOR_TEXT)?).ok_or(CredentialError::UnsupportedEncoding)?;
+
+        reader.label(LABEL_KID)?;
+
+        let key_id = reader.fixed(MAJOR_BYTES)?;
+
+        reader.label(LABEL_PARAMETERS)?;
+
+        let parameters_fingerprint = reader.fixed(MAJOR_BYTES)?;
+
+        reader.label(LABEL_PAYLOAD)?;
+
+        let payload = reader.string(MAJOR_BYTES)?.to_vec();
+
+        if reader.offset != bytes.len() {
+            return Err(CredentialError::UnsupportedEncoding);
+        }
+
+        Ok(Envelope { content_type, key_id, parameters_fingerprint, payload })
+    }
+}
+
+/// Write the head of a CBOR data item of the `major` type, with the shortest
+/// encoding of its `value`.
+fn write_head(bytes: &mut Vec<u8>, major: u8, value: u64) {
+    let major = major << 5;
+
+    if value < 24 {
+        bytes.push(major | value as u8);
+    } else if value <= u8::MAX as u64 {
+        bytes.push(major | 24);
+        bytes.push(value as u8);
+    } else if value <= u16::MAX as u64 {
+        bytes.push(major | 25);
+        bytes.extend(&(value as u16).to_be_bytes());
+    } else if value <= u32::MAX as u64 {
+        bytes.push(major | 26);
+        bytes.extend(&(value as u32).to_be_bytes());
+    } else {
+        bytes.push(major | 27);
+        bytes.extend(&value.to_be_bytes());
+    }
+}
+
+/// Write an integer map `label`.
+fn write_label(bytes: &mut Vec<u8>, label: i64) {
+    match label < 0 {
+        true  => write_head(bytes, MAJOR_NEGATIVE, (-1 - label) as u64),
+        false => write_head(bytes, MAJOR_UNSIGNED, label as u64),
+    }
+}
+
+/// Write a byte or text string, of the `major` type.
+fn write_string(bytes: &mut Vec<u8>, major: u8, string: &[u8]) {
+    write_head(bytes, major, string.len() as u64);
+    bytes.extend(string);
+}
+
+/// A reader of the CBOR data items in an envelope.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    /// Take the next `length` bytes.
+    fn take(&mut self, length: usize) -> Result<&'a [u8], CredentialError> {
+        if self.bytes.len() - self.offset < length {
+            return Err(CredentialError::UnsupportedEncoding);
+        }
+
+        let taken = &self.bytes[self.offset..self.offset + length];
+
+        self.offset += length;
+
+        Ok(taken)
+    }
+
+    /// Read the head of a data item, as its major type and value, rejecting
+    /// indefinite lengths and any encoding which isn't the shortest.
+    fn head(&mut self) -> Result<(u8, u64), CredentialError> {
+        let initial = self.take(1)?[0];
+        let info = initial & 0x1f;
+
+        let (value, minimum) = match info {
+            0..=23 => return Ok((initial >> 5, info as u64)),
+            24 => (self.take(1)?[0] as u64, 24),
+            25 => (self.take(2)?.iter().fold(0, |value, byte| value << 8 | *byte as u64), u8::MAX as u64 + 1),
+            26 => (self.take(4)?.iter().fold(0, |value, byte| value << 8 | *byte as u64), u16::MAX as u64 + 1),
+            27 => (self.take(8)?.iter().fold(0, |value, byte| value << 8 | *byte as u64), u32::MAX as u64 + 1),
+            _  => return Err(CredentialError::UnsupportedEncoding),
+        };
+
+        if value < minimum {
+            return Err(CredentialError::UnsupportedEncoding);
+        }
+        Ok((initial >> 5, value))
+    }
+
+    /// Read an integer map label, which must be the `expected` one.
+    fn label(&mut self, expected: i64) -> Result<(), CredentialError> {
+        let label = match self.head()? {
+            (MAJOR_UNSIGNED, value) if value <= i64::MAX as u64 => value as i64,
+            (MAJOR_NEGATIVE, value) if value <= i64::MAX as u64 => -1 - value as i64,
+            _ => return Err(CredentialError::UnsupportedEncoding),
+        };
+
+        match label == expected {
+            true  => Ok(()),
+            false => Err(CredentialError::UnsupportedEncoding),
+        }
+    }
+
+    /// Read a byte or text string, of the `major` type.
+    fn string(&mut self, major: u8) -> Result<&'a [u8], CredentialError> {
+        match self.head()? {
+            (found, length) if found == major && length <= usize::MAX as u64 => self.take(length as usize),
+            _ => Err(CredentialError::UnsupportedEncoding),
+        }
+    }
+
+    /// Read a 32-byte string, of the `major` type.
+    fn fixed(&mut self, major: u8) -> Result<[u8; 32], CredentialError> {
+        let string = self.string(major)?;
+        let mut fixed = [0u8; 32];
+
+        if string.len() != 32 {
+            return Err(CredentialError::UnsupportedEncoding);
+        }
+        fixed.copy_from_slice(string);
+
+        Ok(fixed)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use curve25519_dalek::scalar::Scalar;
+
+    use crate::issuer::Issuer;
+    use crate::user::CredentialRequestConstructor;
+
+    use rand::thread_rng;
+
+    #[test]
+    fn envelopes_roundtrip() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 2).unwrap();
+        let issuer = Issuer::new(&system_parameters, &mut rng);
+        let other_issuer = Issuer::new(&system_parameters, &mut rng);
+        let mut request = CredentialRequestConstructor::new(&system_parameters);
+
+        request.append_revealed_scalar(Scalar::random(&mut rng));
+        request.append_revealed_scalar(Scalar::random(&mut rng));
+
+        let issuance = issuer.issue(request.finish(), &mut rng).unwrap();
+        let envelope = Envelope::issuance(&issuance, &system_parameters, &issuer.issuer_parameters);
+        let cbor = envelope.to_cbor();
+
+        // A map of five entries, beginning with the alg header.
+        assert_eq!(&cbor[..3], &[0xa5, 0x01, 0x72]);
+
+        let envelope = Envelope::from_cbor(&cbor).unwrap();
+
+        assert!(envelope.check(&system_parameters, &issuer.issuer_parameters).is_ok());
+        assert_eq!(envelope.check(&system_parameters, &other_issuer.issuer_parameters).unwrap_err(),
+                   CredentialError::ParameterMismatch);
+        assert!(envelope.open_presentation().is_err());
+
+        let credential = envelope.open_issuance().unwrap()
+            .verify(&system_parameters, &issuer.issuer_parameters).unwrap();
+        let presentation = credential.show(&system_parameters, &issuer.issuer_parameters, None, &mut rng).unwrap();
+        let envelope = Envelope::presentation(&presentation, &system_parameters, &issuer.issuer_parameters).unwrap();
+        let cbor = envelope.to_cbor();
+        let decoded = Envelope::from_cbor(&cbor).unwrap();
+
+        assert_eq!(decoded, envelope);
+        assert_eq!(decoded.content_type, ContentType::Presentation);
+        assert!(issuer.verify(&decoded.open_presentation().unwrap()).is_ok());
+
+        // Truncated, extended, or non-deterministic encodings are rejected.
+        let mut extended = cbor.clone();
+        let mut long_label = cbor.clone();
+
+        extended.push(0);
+        long_label.splice(1..2, [0x18, 0x01].iter().copied());
+
+        assert!(Envelope::from_cbor(&cbor[..cbor.len() - 1]).is_err());
+        assert!(Envelope::from_cbor(&extended).is_err());
+        assert!(Envelope::from_cbor(&long_label).is_err());
+    }
+}
@@ -23,17 +23,50 @@ use serde::de::Visitor;
 use serde::ser::Serialize;
 use serde::ser::Serializer;
 
+use zeroize::Zeroize;
+
+use zkp::Transcript;
+
+use crate::amacs::attributes_to_bytes;
+use crate::amacs::from_tag_record;
 use crate::amacs::sizeof_secret_key;
+use crate::amacs::sizeof_tag_record;
 use crate::amacs::Amac;
+use crate::amacs::Attribute;
+use crate::amacs::EncryptedAttribute;
+use crate::amacs::MacSigner;
 use crate::amacs::SecretKey;
+use crate::attributes::schema::Schema;
 use crate::credential::AnonymousCredential;
+use crate::credential::Presentation;
 use crate::errors::CredentialError;
+use crate::nizk::bundle::PresentationBundle;
+use crate::nizk::designated::VerifierPublicKey;
+use crate::nizk::interactive::PresentationChallenge;
+use crate::nizk::interactive::PresentationCommitment;
+use crate::nizk::interactive::PresentationResponse;
 use crate::nizk::issuance::ProofOfIssuance;
+use crate::nizk::issuance::Request as BlindRequest;
+use crate::nizk::issuance::Response as BlindResponse;
+use crate::nizk::keygen::ProofOfKeyGeneration;
+use crate::nizk::linked::Link;
+use crate::nizk::linked::LinkedPresentation;
 use crate::nizk::presentation::ProofOfValidCredential;
+use crate::nizk::translation::TranslationRequest;
+use crate::nizk::update::UpdateRequest;
 use crate::parameters::sizeof_system_parameters;
 use crate::parameters::IssuerParameters;
 use crate::parameters::SystemParameters;
+use crate::parameters::MAX_NUMBER_OF_ATTRIBUTES;
+use crate::policy::Policy;
+use crate::revocation::Accumulator;
+use crate::revocation::NonRevocationPresentation;
+use crate::rng::CryptoRngCore;
+use crate::symmetric::Keypair as SymmetricKeypair;
+use crate::symmetric::MasterSecret;
 use crate::user::CredentialRequest;
+use crate::wire;
+use crate::wire::WireType;
 
 /// An issued anonymous credential.
 ///
@@ -51,10 +84,139 @@ impl CredentialIssuance {
         issuer_parameters: &IssuerParameters,
     ) -> Result<AnonymousCredential, CredentialError>
     {
+        self.credential.check_parameters(system_parameters)?;
         self.proof
             .verify(system_parameters, issuer_parameters, &self.credential)
             .and(Ok(self.credential))
     }
+
+    /// Verify this issuance, as in [`CredentialIssuance::verify`], and
+    /// derive the symmetric keypair of the credential from the user's
+    /// `master_secret`, as in [`AnonymousCredential::derive_keypair`].
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the credential and its keypair,
+    /// otherwise a [`CredentialError`].
+    pub fn verify_with_master_secret(
+        self,
+        system_parameters: &SystemParameters,
+        issuer_parameters: &IssuerParameters,
+        master_secret: &MasterSecret,
+    ) -> Result<(AnonymousCredential, SymmetricKeypair), CredentialError>
+    {
+        let credential = self.verify(system_parameters, issuer_parameters)?;
+        let keypair = credential.derive_keypair(master_secret, system_parameters);
+
+        Ok((credential, keypair))
+    }
+
+    /// Verify this issuance, as in [`CredentialIssuance::verify`], when it
+    /// was issued with [`Issuer::issue_with_transcript`] for a `transcript`
+    /// in the same state as this one.
+    pub fn verify_with_transcript(
+        self,
+        system_parameters: &SystemParameters,
+        issuer_parameters: &IssuerParameters,
+        transcript: &mut Transcript,
+    ) -> Result<AnonymousCredential, CredentialError>
+    {
+        self.credential.check_parameters(system_parameters)?;
+        self.proof
+            .verify_with_transcript(system_parameters, issuer_parameters, &self.credential, transcript)
+            .and(Ok(self.credential))
+    }
+
+    /// Serialise this issuance, to send it to the user, as the credential's
+    /// tag record followed by the proof of issuance and the 32-byte
+    /// [`SystemParameters::identifier`] of the parameters it was issued under.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut body = attributes_to_bytes(&self.credential.attributes);
+
+        body.extend(self.credential.amac.to_bytes());
+        body.extend(self.proof.to_bytes());
+        body.extend(&self.credential.parameters_fingerprint);
+
+        let bytes = wire::encode(WireType::CredentialIssuance, &body);
+
+        body.zeroize();
+        bytes
+    }
+
+    /// Deserialise an issuance previously serialised with
+    /// [`CredentialIssuance::to_bytes`].
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the unverified issuance, otherwise a
+    /// [`CredentialError`] if the `bytes` are malformed.
+    pub fn from_bytes(bytes: &[u8]) -> Result<CredentialIssuance, CredentialError> {
+        // Unversioned issuances don't record the parameters they were issued under.
+        let body = match wire::decode(bytes, WireType::CredentialIssuance)? {
+            (1, body) => body,
+            _ => return Err(CredentialError::UnsupportedEncoding),
+        };
+
+        if body.len() < 4 {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
+
+        let mut tmp = [0u8; 4];
+
+        tmp.copy_from_slice(&body[0..4]);
+
+        let number_of_attributes = u32::from_le_bytes(tmp);
+
+        if number_of_attributes > MAX_NUMBER_OF_ATTRIBUTES {
+            return Err(CredentialError::WrongNumberOfAttributes);
+        }
+
+        let length = sizeof_tag_record(number_of_attributes);
+
+        if body.len() < length + 32 {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
+
+        let proof_end = body.len() - 32;
+        let (attributes, amac) = from_tag_record(&body[..length])?;
+        let proof = ProofOfIssuance::from_bytes(&body[length..proof_end], number_of_attributes as usize)?;
+        let mut parameters_fingerprint = [0u8; 32];
+
+        parameters_fingerprint.copy_from_slice(&body[proof_end..]);
+
+        Ok(CredentialIssuance { proof, credential: AnonymousCredential { amac, attributes, parameters_fingerprint } })
+    }
+}
+
+/// Issue a new anonymous credential, as in [`Issuer::issue_with_transcript`],
+/// with a secret key held by a `signer`, e.g. an HSM, rather than by an
+/// [`Issuer`].
+///
+/// # Returns
+///
+/// A `Result` whose `Ok` value is the issuance, otherwise a
+/// [`CredentialError`], including if the `signer` fails.
+pub fn issue_with_signer<K, C>(
+    system_parameters: &SystemParameters,
+    issuer_parameters: &IssuerParameters,
+    signer: &K,
+    request: CredentialRequest,
+    transcript: &mut Transcript,
+    csprng: &mut C,
+) -> Result<CredentialIssuance, CredentialError>
+where
+    K: MacSigner + ?Sized,
+    C: CryptoRng + RngCore,
+{
+    let amac = Amac::tag_with_signer(csprng, system_parameters, signer, &request.attributes)?;
+    let credential = AnonymousCredential {
+        amac,
+        attributes: request.attributes,
+        parameters_fingerprint: system_parameters.identifier(),
+    };
+    let proof = signer.prove_issuance(system_parameters, issuer_parameters, &credential, transcript)?;
+
+    Ok(CredentialIssuance { proof, credential })
 }
 
 /// An anonymous credential issuer/verifier.
@@ -92,6 +254,64 @@ impl Issuer {
         }
     }
 
+    /// Rotate this issuer's key, creating a new issuer with a fresh
+    /// [`SecretKey`] for the same [`SystemParameters`].
+    ///
+    /// This issuer should be kept, for as long as credentials issued under
+    /// its key are accepted, in order to translate them into credentials
+    /// under the new key with the new issuer's [`Issuer::translate`].
+    pub fn rotate_key<C>(&self, csprng: &mut C) -> Issuer
+    where
+        C: CryptoRng + RngCore,
+    {
+        Issuer::new(&self.system_parameters, csprng)
+    }
+
+    /// Extend this issuer to the `extended_system_parameters`, e.g. from
+    /// [`SystemParameters::extend`], with its key extended by
+    /// [`SecretKey::extend`].
+    ///
+    /// Credentials issued by this issuer remain valid under the extended
+    /// issuer once padded with [`AnonymousCredential::extend`], although
+    /// users must fetch the extended issuer's new [`IssuerParameters`].
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the extended issuer, otherwise a
+    /// [`CredentialError::ParameterMismatch`] if the
+    /// `extended_system_parameters` don't extend our own.
+    pub fn extend<C>(
+        &self,
+        extended_system_parameters: &SystemParameters,
+        csprng: &mut C,
+    ) -> Result<Issuer, CredentialError>
+    where
+        C: CryptoRng + RngCore,
+    {
+        if !extended_system_parameters.extends(&self.system_parameters) {
+            return Err(CredentialError::ParameterMismatch);
+        }
+
+        let amacs_key = self.amacs_key.extend(csprng, extended_system_parameters)?;
+        let issuer_parameters = IssuerParameters::generate(extended_system_parameters, &amacs_key);
+
+        Ok(Issuer {
+            system_parameters: extended_system_parameters.clone(),
+            issuer_parameters,
+            amacs_key,
+        })
+    }
+
+    /// Create a new anonymous credential issuer and verifier, as in
+    /// [`Issuer::new`], with a type-erased `csprng`.
+    pub fn new_dyn(
+        system_parameters: &SystemParameters,
+        csprng: &mut dyn CryptoRngCore,
+    ) -> Issuer
+    {
+        Issuer::new(system_parameters, &mut &mut *csprng)
+    }
+
     /// Issue a new anonymous credential on a set of `attributes` in an
     /// unblinded manner.
     ///
@@ -116,11 +336,231 @@ impl Issuer {
     where
         C: CryptoRng + RngCore,
     {
-        let amac = Amac::tag(csprng, &self.system_parameters, &self.amacs_key, &request.attributes)?;
-        let cred = AnonymousCredential { amac, attributes: request.attributes };
-        let proof = ProofOfIssuance::prove(&self, &cred);
+        let mut transcript = Transcript::new(b"2019/1416 anonymous credential");
+
+        self.issue_with_transcript(request, &mut transcript, csprng)
+    }
+
+    /// Issue a new anonymous credential, as in [`Issuer::issue`], whose
+    /// proof of issuance is bound to the caller's `transcript`, e.g. one into
+    /// which a higher-level protocol has already appended its session
+    /// identifier.
+    ///
+    /// The user must check it with
+    /// [`CredentialIssuance::verify_with_transcript`] and a transcript in
+    /// the same state.
+    pub fn issue_with_transcript<C>(
+        &self,
+        request: CredentialRequest,
+        transcript: &mut Transcript,
+        csprng: &mut C,
+    ) -> Result<CredentialIssuance, CredentialError>
+    where
+        C: CryptoRng + RngCore,
+    {
+        issue_with_signer(&self.system_parameters, &self.issuer_parameters, &self.amacs_key,
+                          request, transcript, csprng)
+    }
+
+    /// Translate a credential issued by the `old_issuer`, i.e. this issuer
+    /// before its key was rotated with [`Issuer::rotate_key`], into a new
+    /// credential with the same attributes under our key.
+    ///
+    /// Attributes hidden in the user's presentation of the old credential
+    /// remain hidden from us.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is a [`BlindResponse`] to the
+    /// translation's blind issuance request, otherwise a [`CredentialError`]
+    /// if the old credential's presentation doesn't verify under the
+    /// `old_issuer`'s key, or the request is not for the same attributes.
+    pub fn translate<C>(
+        &self,
+        old_issuer: &Issuer,
+        request: &TranslationRequest,
+        csprng: &mut C,
+    ) -> Result<BlindResponse, CredentialError>
+    where
+        C: CryptoRng + RngCore,
+    {
+        if old_issuer.system_parameters != self.system_parameters {
+            return Err(CredentialError::ParameterMismatch);
+        }
+
+        request.verify(old_issuer)?;
+
+        BlindResponse::prove(self, request.request(), csprng)
+    }
+
+    /// Issue a new anonymous credential, as in [`Issuer::issue`], which
+    /// expires at the end of the `expiry` epoch.
+    ///
+    /// The attribute at `index` of the `request` is overwritten with the
+    /// expiry, so that users cannot choose their own.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is a [`CredentialIssuance`], otherwise a
+    /// [`CredentialError::WrongNumberOfAttributes`] if the `request` has no
+    /// attribute at `index`.
+    pub fn issue_with_expiry<C>(
+        &self,
+        mut request: CredentialRequest,
+        index: usize,
+        expiry: u64,
+        csprng: &mut C,
+    ) -> Result<CredentialIssuance, CredentialError>
+    where
+        C: CryptoRng + RngCore,
+    {
+        match request.attributes.get_mut(index) {
+            Some(attribute) => *attribute = Attribute::expiry(expiry),
+            None => return Err(CredentialError::WrongNumberOfAttributes),
+        }
+
+        self.issue(request, csprng)
+    }
+
+    /// Issue a new anonymous credential, as in [`Issuer::issue`], recording
+    /// the schema `version` it is issued under.
+    ///
+    /// The attribute at `index` of the `request` is overwritten with an
+    /// [`Attribute::schema_version`], so that users cannot choose their own.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is a [`CredentialIssuance`], otherwise a
+    /// [`CredentialError::WrongNumberOfAttributes`] if the `request` has no
+    /// attribute at `index`.
+    pub fn issue_with_schema_version<C>(
+        &self,
+        mut request: CredentialRequest,
+        index: usize,
+        version: u32,
+        csprng: &mut C,
+    ) -> Result<CredentialIssuance, CredentialError>
+    where
+        C: CryptoRng + RngCore,
+    {
+        match request.attributes.get_mut(index) {
+            Some(attribute) => *attribute = Attribute::schema_version(version),
+            None => return Err(CredentialError::WrongNumberOfAttributes),
+        }
+
+        self.issue(request, csprng)
+    }
+
+    /// Issue many new anonymous credentials, as in [`Issuer::issue`], with
+    /// the algebraic MACs computed together by [`Amac::tag_batch`].
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is a [`CredentialIssuance`] for each of
+    /// the `requests`, in order, otherwise a [`CredentialError`] if any
+    /// request has the wrong number of attributes.
+    pub fn issue_batch<C>(
+        &self,
+        requests: Vec<CredentialRequest>,
+        csprng: &mut C,
+    ) -> Result<Vec<CredentialIssuance>, CredentialError>
+    where
+        C: CryptoRng + RngCore,
+    {
+        let attributes: Vec<&[Attribute]> = requests.iter().map(|request| &request.attributes[..]).collect();
+        let amacs = Amac::tag_batch(csprng, &self.system_parameters, &self.amacs_key, &attributes)?;
+
+        Ok(requests.into_iter().zip(amacs).map(|(request, amac)| {
+            let cred = AnonymousCredential {
+                amac,
+                attributes: request.attributes,
+                parameters_fingerprint: self.system_parameters.identifier(),
+            };
+            let proof = ProofOfIssuance::prove(self, &cred);
+
+            CredentialIssuance { proof, credential: cred }
+        }).collect())
+    }
+
+    /// Blindly issue a new anonymous credential, some of whose attributes
+    /// are hidden from us, as requested by a user's [`BlindRequest`].
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is a [`BlindResponse`], from which only the
+    /// user may obtain the credential, otherwise a [`CredentialError`] if the
+    /// user's proof of knowledge of their hidden attributes doesn't verify.
+    pub fn issue_blind<C>(
+        &self,
+        request: &BlindRequest,
+        csprng: &mut C,
+    ) -> Result<BlindResponse, CredentialError>
+    where
+        C: CryptoRng + RngCore,
+    {
+        BlindResponse::prove(self, request, csprng)
+    }
+
+    /// Blindly issue a new anonymous credential in exchange for a user's
+    /// presentation of an old one, with a hidden counter attribute
+    /// incremented, as requested by their [`UpdateRequest`].
+    ///
+    /// We learn neither the old nor the new value of the counter, nor any
+    /// other hidden scalar attribute, all of which are carried over to the
+    /// new credential along with the revealed attributes.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is a [`BlindResponse`], from which only the
+    /// user may obtain the new credential, otherwise a [`CredentialError`] if
+    /// the presentation or any of the user's proofs don't verify.
+    pub fn issue_update<C>(
+        &self,
+        request: &UpdateRequest,
+        csprng: &mut C,
+    ) -> Result<BlindResponse, CredentialError>
+    where
+        C: CryptoRng + RngCore,
+    {
+        self.verify(request.presentation())?;
+        request.verify(&self.system_parameters)?;
+
+        BlindResponse::prove(self, request.request(), csprng)
+    }
+
+    /// Issue a new anonymous credential, as in [`Issuer::issue`], after
+    /// checking that the `request` is of the given `schema`, as in
+    /// [`Schema::check_issuance`].
+    pub fn issue_with_schema<C>(
+        &self,
+        request: CredentialRequest,
+        schema: &Schema,
+        csprng: &mut C,
+    ) -> Result<CredentialIssuance, CredentialError>
+    where
+        C: CryptoRng + RngCore,
+    {
+        schema.check_issuance(&self.system_parameters, &request.attributes)?;
 
-        Ok(CredentialIssuance { proof: proof, credential: cred })
+        self.issue(request, csprng)
+    }
+
+    /// Issue a new anonymous credential, as in [`Issuer::issue`], with a
+    /// type-erased `csprng`.
+    pub fn issue_dyn(
+        &self,
+        request: CredentialRequest,
+        csprng: &mut dyn CryptoRngCore,
+    ) -> Result<CredentialIssuance, CredentialError>
+    {
+        self.issue(request, &mut &mut *csprng)
+    }
+
+    /// Prove that this issuer's public [`IssuerParameters`] were correctly
+    /// generated from its secret key, for clients to check with
+    /// [`ProofOfKeyGeneration::verify`] before requesting credentials.
+    pub fn prove_key_generation(&self) -> ProofOfKeyGeneration {
+        ProofOfKeyGeneration::prove(&self.system_parameters, &self.amacs_key, &self.issuer_parameters)
     }
 
     /// Verify a user's presentation of an anonymous credential.
@@ -145,15 +585,329 @@ impl Issuer {
     {
         presentation.verify(&self)
     }
+
+    /// Verify a user's [`Presentation`] of an anonymous credential, as
+    /// created by [`AnonymousCredential::show_disclosing`].
+    ///
+    /// This is equivalent to [`Issuer::verify`].
+    ///
+    /// [`AnonymousCredential::show_disclosing`]: crate::credential::AnonymousCredential::show_disclosing
+    pub fn verify_presentation(
+        &self,
+        presentation: &Presentation,
+    ) -> Result<(), CredentialError>
+    {
+        self.verify(presentation)
+    }
+
+    /// Verify many users' presentations of anonymous credentials at once, as
+    /// in [`Issuer::verify`].
+    ///
+    /// The proofs of those presentations created with
+    /// [`AnonymousCredential::show_batchable`] are checked together, which is
+    /// considerably faster than verifying each in turn.  Any other
+    /// presentations are verified individually.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is empty if *every* presentation is valid,
+    /// otherwise a `CredentialError`.  To learn which presentations are
+    /// invalid, verify them individually.
+    ///
+    /// [`AnonymousCredential::show_batchable`]: crate::credential::AnonymousCredential::show_batchable
+    pub fn verify_batch(
+        &self,
+        presentations: &[ProofOfValidCredential],
+    ) -> Result<(), CredentialError>
+    {
+        ProofOfValidCredential::verify_batch(self, presentations)
+    }
+
+    /// Verify a user's presentation of an anonymous credential, as created
+    /// by [`AnonymousCredential::show_designated`] for the designated
+    /// `verifier`, i.e. the public key of a keypair held by this issuer.
+    ///
+    /// Designated presentations are rejected by [`Issuer::verify`], and
+    /// presentations designated to other verifiers are rejected here.
+    ///
+    /// [`AnonymousCredential::show_designated`]: crate::credential::AnonymousCredential::show_designated
+    pub fn verify_designated(
+        &self,
+        presentation: &ProofOfValidCredential,
+        verifier: &VerifierPublicKey,
+    ) -> Result<(), CredentialError>
+    {
+        presentation.verify_designated(self, verifier)
+    }
+
+    /// Verify a user's interactive presentation of an anonymous credential,
+    /// as begun with [`AnonymousCredential::show_interactive`], i.e. their
+    /// `commitment` and their `response` to our `challenge`.
+    ///
+    /// The `challenge` must have been freshly generated with
+    /// [`PresentationChallenge::generate`] after receiving the `commitment`,
+    /// and must not be used for any other.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is empty, otherwise a `CredentialError`.
+    ///
+    /// [`AnonymousCredential::show_interactive`]: crate::credential::AnonymousCredential::show_interactive
+    pub fn verify_interactive(
+        &self,
+        commitment: &PresentationCommitment,
+        challenge: &PresentationChallenge,
+        response: &PresentationResponse,
+    ) -> Result<(), CredentialError>
+    {
+        commitment.verify(self, challenge, response)
+    }
+
+    /// Verify a user's presentation of an anonymous credential, as in
+    /// [`Issuer::verify`], after checking that its attributes have exactly
+    /// the kinds given by the `schema`, as in [`Schema::check_presentation`].
+    pub fn verify_with_schema(
+        &self,
+        presentation: &ProofOfValidCredential,
+        schema: &Schema,
+    ) -> Result<(), CredentialError>
+    {
+        schema.check_presentation(presentation)?;
+
+        self.verify(presentation)
+    }
+
+    /// Verify a user's presentation of an anonymous credential, as in
+    /// [`Issuer::verify`], and that its expiry attribute at `index` is no
+    /// earlier than the `current_epoch`.
+    ///
+    /// If the expiry is revealed it is compared directly, otherwise the
+    /// presentation must prove in zero-knowledge that it is not expired, as
+    /// created with [`PresentationOptions::not_expired`].
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is empty, otherwise a
+    /// [`CredentialError::Expired`] if the credential has or may have
+    /// expired, a [`CredentialError::BadAttribute`] if the attribute at
+    /// `index` is not an expiry, or another `CredentialError` if the
+    /// presentation doesn't verify.
+    ///
+    /// [`PresentationOptions::not_expired`]: crate::credential::PresentationOptions::not_expired
+    pub fn verify_unexpired(
+        &self,
+        presentation: &Presentation,
+        index: usize,
+        current_epoch: u64,
+    ) -> Result<(), CredentialError>
+    {
+        match presentation.attributes().get(index) {
+            Some(EncryptedAttribute::PublicScalar(expiry)) => {
+                let bytes = expiry.as_bytes();
+                let mut epoch = [0u8; 8];
+
+                if bytes[8..].iter().any(|byte| *byte != 0) {
+                    return Err(CredentialError::BadAttribute);
+                }
+                epoch.copy_from_slice(&bytes[..8]);
+
+                if u64::from_le_bytes(epoch) < current_epoch {
+                    return Err(CredentialError::Expired);
+                }
+            },
+            Some(EncryptedAttribute::SecretScalar) => {
+                let proven = presentation.range_statements().iter()
+                    .any(|statement| statement.index == index && statement.min >= current_epoch);
+
+                if !proven {
+                    return Err(CredentialError::Expired);
+                }
+            },
+            _ => return Err(CredentialError::BadAttribute),
+        }
+
+        self.verify(presentation)
+    }
+
+    /// Verify a user's presentation of an anonymous credential, as in
+    /// [`Issuer::verify`], which must be bound to a `nonce` previously sent
+    /// to the user, in order to prevent the replay of captured
+    /// presentations.
+    ///
+    /// # Inputs
+    ///
+    /// * A user's [`ProofOfValidCredential`], created with
+    ///   [`AnonymousCredential::show_with_nonce`].
+    /// * The `nonce`, which should be fresh for every presentation.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is empty, otherwise a `CredentialError`.
+    pub fn verify_with_nonce(
+        &self,
+        presentation: &ProofOfValidCredential,
+        nonce: &[u8],
+    ) -> Result<(), CredentialError>
+    {
+        presentation.verify_with_nonce(self, nonce)
+    }
+
+    /// Verify a user's presentation of an anonymous credential, as in
+    /// [`Issuer::verify`], which must reveal and hide exactly the attributes
+    /// declared by the `policy`, prove each of its predicates, and be bound
+    /// to its nonce, if any.
+    ///
+    /// # Inputs
+    ///
+    /// * A user's [`ProofOfValidCredential`], created with
+    ///   [`AnonymousCredential::show_with_policy`] and the same `policy`.
+    /// * The `policy`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is empty, otherwise a
+    /// [`CredentialError::PolicyViolation`] if the presentation doesn't
+    /// satisfy the policy, or another `CredentialError` if it doesn't
+    /// verify.
+    ///
+    /// [`AnonymousCredential::show_with_policy`]: crate::credential::AnonymousCredential::show_with_policy
+    pub fn verify_with_policy(
+        &self,
+        presentation: &ProofOfValidCredential,
+        policy: &Policy,
+    ) -> Result<(), CredentialError>
+    {
+        policy.check_presentation(presentation)?;
+
+        match &policy.nonce {
+            Some(nonce) => self.verify_with_nonce(presentation, nonce),
+            None        => self.verify(presentation),
+        }
+    }
+
+    /// Verify a user's presentation of an anonymous credential, as in
+    /// [`Issuer::verify`], which must be bound to a `transcript` in the same
+    /// state as that with which it was created.
+    ///
+    /// # Inputs
+    ///
+    /// * A user's [`ProofOfValidCredential`], created with
+    ///   [`AnonymousCredential::show_with_transcript`].
+    /// * The `transcript`, into which both parties should have appended the
+    ///   same context, e.g. a session identifier and the request body.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is empty, otherwise a `CredentialError`.
+    pub fn verify_with_transcript(
+        &self,
+        presentation: &ProofOfValidCredential,
+        transcript: &mut Transcript,
+    ) -> Result<(), CredentialError>
+    {
+        presentation.verify_with_transcript(self, transcript)
+    }
+
+    /// Verify a user's presentation of several anonymous credentials, some of
+    /// whose hidden attributes are claimed to be equal.
+    ///
+    /// # Inputs
+    ///
+    /// * A user's [`LinkedPresentation`].
+    /// * The `links` we require, each a pair of `(credential, attribute)`
+    ///   indices which must be proven equal.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is empty, otherwise a `CredentialError`.
+    /// The presentation is rejected unless it proves exactly the `links`, in
+    /// any order, and there is at least one.
+    pub fn verify_linked(
+        &self,
+        presentation: &LinkedPresentation,
+        links: &[Link],
+    ) -> Result<(), CredentialError>
+    {
+        presentation.verify(self, links)
+    }
+
+    /// Verify a user's bundle of presentations of several anonymous
+    /// credentials, possibly from different issuers, and that the one at
+    /// `index` was issued by us.
+    ///
+    /// # Inputs
+    ///
+    /// * A user's [`PresentationBundle`].
+    /// * The `index` of our credential within the bundle.
+    /// * The system and issuer parameters of every credential in the bundle,
+    ///   in order, including our own.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is empty, otherwise a `CredentialError`.
+    ///
+    /// # Warning
+    ///
+    /// This only checks the validity of our own credential.  The other
+    /// credentials' issuers must each verify theirs.
+    pub fn verify_bundle(
+        &self,
+        bundle: &PresentationBundle,
+        index: usize,
+        parameters: &[(&SystemParameters, &IssuerParameters)],
+    ) -> Result<(), CredentialError>
+    {
+        bundle.verify(self, index, parameters)
+    }
+
+    /// Verify a user's presentation of an anonymous credential, as in
+    /// [`Issuer::verify`], and that its revocation handle hasn't been
+    /// revoked from the `accumulator`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is empty, otherwise a
+    /// [`CredentialError::Revoked`] if the credential has been revoked, or
+    /// the user's witness is out of date, or another `CredentialError` if
+    /// the presentation doesn't verify.
+    pub fn verify_non_revoked(
+        &self,
+        accumulator: &Accumulator,
+        presentation: &NonRevocationPresentation,
+    ) -> Result<(), CredentialError>
+    {
+        self.verify(presentation.presentation())?;
+
+        accumulator.verify(&self.system_parameters, presentation)
+    }
 }
 
 impl Issuer {
     /// Create an [`Issuer`] from bytes.
     pub fn from_bytes(bytes: &[u8]) -> Result<Issuer, CredentialError> {
-        let system_parameters = SystemParameters::from_bytes(&bytes)?;
-        let offset = sizeof_system_parameters(system_parameters.NUMBER_OF_ATTRIBUTES);
+        if bytes.len() < 4 {
+            return Err(CredentialError::NoIssuerKey);
+        }
+
+        let mut tmp = [0u8; 4];
+
+        tmp.copy_from_slice(&bytes[0..4]);
+
+        let number_of_attributes = u32::from_le_bytes(tmp);
+
+        if number_of_attributes > MAX_NUMBER_OF_ATTRIBUTES {
+            return Err(CredentialError::WrongNumberOfAttributes);
+        }
+
+        let offset = sizeof_system_parameters(number_of_attributes);
+
+        if bytes.len() < offset + 64 {
+            return Err(CredentialError::NoIssuerKey);
+        }
+
+        let system_parameters = SystemParameters::from_raw_bytes(&bytes[..offset])?;
         let issuer_parameters = IssuerParameters::from_bytes(&bytes[offset..offset+64])?;
-        let amacs_key = SecretKey::from_bytes(&bytes[offset+64..])?;
+        let amacs_key = SecretKey::from_raw_bytes(&bytes[offset+64..])?;
 
         Ok(Issuer { system_parameters, issuer_parameters, amacs_key })
     }
@@ -166,9 +920,9 @@ impl Issuer {
             
         let mut bytes: Vec<u8> = Vec::with_capacity(size);
 
-        bytes.extend(self.system_parameters.to_bytes());
+        bytes.extend(self.system_parameters.to_raw_bytes());
         bytes.extend(self.issuer_parameters.to_bytes());
-        bytes.extend(self.amacs_key.to_bytes());
+        bytes.extend(self.amacs_key.to_raw_bytes());
 
         bytes
     }
@@ -21,39 +21,109 @@
 
 #![cfg_attr(feature = "nightly", doc(include = "../README.md"))]
 
-// TODO Get rid of the syntax that uses the nightly-only try_trait.
-#![feature(try_trait)]
 // We denote group elements with capital and scalars with lowercased names.
 #![allow(non_snake_case)]
 
 #[cfg(feature = "std")]
 #[macro_use]
 extern crate std;
-#[cfg(any(not(feature = "std"), feature = "alloc"))]
+#[cfg(feature = "alloc")]
 #[macro_use]
 extern crate alloc;
 
+#[cfg(feature = "arbitrary")]
+extern crate arbitrary;
+#[cfg(any(feature = "alloc", feature = "std"))]
+extern crate argon2;
+#[cfg(feature = "async")]
+extern crate async_trait;
+#[cfg(any(feature = "alloc", feature = "std"))]
+extern crate chacha20poly1305;
 extern crate curve25519_dalek;
+#[cfg(feature = "defmt")]
+extern crate defmt;
+extern crate hmac;
 #[cfg(test)]
 extern crate rand;
 extern crate rand_core;
+#[cfg(feature = "parallel")]
+extern crate rayon;
+#[cfg(feature = "schema")]
+extern crate schemars;
+#[cfg(any(feature = "alloc", feature = "std"))]
 extern crate serde;
+#[cfg(feature = "schema")]
+extern crate serde_json;
 extern crate sha2;
 extern crate subtle;
+#[cfg(feature = "wasm")]
+extern crate wasm_bindgen;
 extern crate zeroize;
+#[cfg(any(feature = "alloc", feature = "std"))]
 extern crate zkp;
 
 // The macros have to come first.
 #[macro_use]
 mod macros;
 
+// Without either `alloc` or `std`, only the heap-free algebraic MACs of
+// `fixed`, and the errors they return, are built.
+#[cfg(any(feature = "alloc", feature = "std"))]
 pub mod amacs;
+#[cfg(any(feature = "alloc", feature = "std"))]
+pub mod attributes;
+#[cfg(feature = "async")]
+pub mod backend;
+#[cfg(any(feature = "alloc", feature = "std"))]
 pub mod credential;
+#[cfg(any(feature = "alloc", feature = "std"))]
 pub mod encoding;
 pub mod errors;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod fixed;
+#[cfg(any(feature = "alloc", feature = "std"))]
+pub mod interop;
+#[cfg(any(feature = "alloc", feature = "std"))]
 pub mod issuer;
+#[cfg(any(feature = "alloc", feature = "std"))]
 pub mod nizk;
+#[cfg(any(feature = "alloc", feature = "std"))]
+pub mod oneshot;
+#[cfg(any(feature = "alloc", feature = "std"))]
 pub mod parameters;
+#[cfg(any(feature = "alloc", feature = "std"))]
+pub mod policy;
+#[cfg(any(feature = "alloc", feature = "std"))]
 pub mod prelude;
+#[cfg(any(feature = "alloc", feature = "std"))]
+pub mod revocation;
+pub mod rng;
+#[cfg(feature = "schema")]
+pub mod schema;
+#[cfg(any(feature = "alloc", feature = "std"))]
+pub mod session;
+#[cfg(any(feature = "alloc", feature = "std"))]
 pub mod symmetric;
+#[cfg(any(feature = "alloc", feature = "std"))]
+pub mod threshold;
+#[cfg(any(feature = "alloc", feature = "std"))]
+pub mod token;
+#[cfg(all(feature = "test-util", any(feature = "alloc", feature = "std")))]
+pub mod test_util;
+#[cfg(any(feature = "alloc", feature = "std"))]
 pub mod user;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(any(feature = "alloc", feature = "std"))]
+pub mod wire;
+
+// Re-exported for the exported macros, which mustn't assume that the crates
+// invoking them have `alloc` or `std` in scope.
+#[doc(hidden)]
+pub mod __private {
+    #[cfg(feature = "alloc")]
+    pub use alloc::vec::Vec;
+    #[cfg(all(feature = "std", not(feature = "alloc")))]
+    pub use std::vec::Vec;
+}
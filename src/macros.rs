@@ -8,13 +8,20 @@
 // Authors:
 // - isis agora lovecruft <isis@patternsinthevoid.net>
 
+/// Implement serde for a type with `to_bytes` and `from_bytes` methods, as a
+/// hexadecimal string for human-readable formats and a byte string otherwise.
+#[cfg(any(feature = "alloc", feature = "std"))]
 macro_rules! impl_serde_with_to_bytes_and_from_bytes {
     ($t:tt, $expecting:expr) => {
         impl Serialize for $t {
             fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
                 where S: Serializer
             {
-                serializer.serialize_bytes(&self.to_bytes()[..])
+                if serializer.is_human_readable() {
+                    serializer.collect_str(&$crate::encoding::Hex(&self.to_bytes()[..]))
+                } else {
+                    serializer.serialize_bytes(&self.to_bytes()[..])
+                }
             }
         }
 
@@ -43,9 +50,317 @@ macro_rules! impl_serde_with_to_bytes_and_from_bytes {
                             },
                         }
                     }
+
+                    fn visit_str<E>(self, v: &str) -> Result<$t, E>
+                        where E: serde::de::Error
+                    {
+                        match $crate::encoding::decode_hex(v) {
+                            Some(bytes) => self.visit_bytes(&bytes[..]),
+                            None        => Err(serde::de::Error::invalid_value(serde::de::Unexpected::Str(v), &self)),
+                        }
+                    }
+                }
+
+                if deserializer.is_human_readable() {
+                    deserializer.deserialize_str(AeonfluxVisitor)
+                } else {
+                    deserializer.deserialize_bytes(AeonfluxVisitor)
+                }
+            }
+        }
+    }
+}
+
+/// Describe the serialised form of a type whose serde implementation was made
+/// with `impl_serde_with_to_bytes_and_from_bytes`, which in JSON is a
+/// hexadecimal string.
+#[cfg(feature = "schema")]
+macro_rules! impl_json_schema_for_bytes {
+    ($t:tt, $description:expr) => {
+        impl ::schemars::JsonSchema for $t {
+            fn schema_name() -> ::std::string::String {
+                stringify!($t).into()
+            }
+
+            fn json_schema(gen: &mut ::schemars::gen::SchemaGenerator) -> ::schemars::schema::Schema {
+                let mut schema = <::std::string::String as ::schemars::JsonSchema>::json_schema(gen).into_object();
+
+                schema.string().pattern = Some("^([0-9a-fA-F]{2})*$".into());
+                schema.metadata().description = Some($description.into());
+                schema.into()
+            }
+        }
+    }
+}
+
+/// Declare a typed attribute schema for credentials.
+///
+/// Each field is given one of the attribute kinds `public_scalar`,
+/// `secret_scalar`, `public_point`, `either_point`, or `secret_point`, which
+/// determines both the field's type and the [`Attribute`] variant it is
+/// converted to.  The order of the fields is the order of the attributes on
+/// the credential, so issuers and verifiers sharing a schema can no longer
+/// disagree upon it.
+///
+/// # Example
+///
+/// ```rust
+/// # #[macro_use] extern crate aeonflux;
+/// # extern crate curve25519_dalek;
+/// # extern crate rand;
+/// # use curve25519_dalek::scalar::Scalar;
+/// # use aeonflux::parameters::SystemParameters;
+/// # use aeonflux::symmetric::Plaintext;
+/// attribute_schema! {
+///     pub struct Profile {
+///         age: public_scalar,
+///         avatar: secret_point,
+///     }
+/// }
+///
+/// # fn main() {
+/// # let mut rng = rand::thread_rng();
+/// let system_parameters = SystemParameters::generate(&mut rng, Profile::NUMBER_OF_ATTRIBUTES).unwrap();
+/// let profile = Profile { age: Scalar::from(42u64), avatar: Plaintext::from(b"This is a tsunami alert test..") };
+/// let attributes = profile.to_attributes(&system_parameters).unwrap();
+/// let parsed = Profile::from_attributes(&attributes).unwrap();
+///
+/// assert_eq!(parsed.age, profile.age);
+/// # }
+/// ```
+///
+/// [`Attribute`]: crate::amacs::Attribute
+#[macro_export]
+macro_rules! attribute_schema {
+    ($(#[$meta:meta])* $vis:vis struct $name:ident { $($field:ident : $kind:ident),+ $(,)? }) => {
+        $($crate::attribute_schema!(@check $kind);)+
+
+        $(#[$meta])*
+        #[derive(::core::clone::Clone, ::core::fmt::Debug)]
+        $vis struct $name {
+            $(pub $field: $crate::attribute_schema!(@type $kind),)+
+        }
+
+        // The fallback rules for unknown attribute kinds expand to unreachable
+        // code, so that only the error from the @check rule is reported.
+        #[allow(unreachable_code)]
+        impl $name {
+            /// The number of attributes in this schema.
+            pub const NUMBER_OF_ATTRIBUTES: u32 = 0 $(+ $crate::attribute_schema!(@one $field))+;
+
+            /// The expected kind of each attribute upon credential presentation.
+            pub fn schema() -> $crate::amacs::AttributeSchema {
+                $crate::amacs::AttributeSchema(::core::convert::From::from([
+                    $($crate::attribute_schema!(@presented $kind)),+
+                ]))
+            }
+
+            /// The names and expected kinds of the attributes of this schema.
+            pub fn named_schema() -> $crate::attributes::schema::Schema {
+                match $crate::attributes::schema::Schema::new(&[
+                    $((::core::stringify!($field), $crate::attribute_schema!(@presented $kind))),+
+                ]) {
+                    ::core::result::Result::Ok(schema) => schema,
+                    // The fields of a struct always have distinct names.
+                    ::core::result::Result::Err(_) => ::core::unreachable!(),
+                }
+            }
+
+            /// Convert this schema into a vector of attributes, checking that
+            /// the `system_parameters` support the number of attributes.
+            pub fn to_attributes(
+                &self,
+                system_parameters: &$crate::parameters::SystemParameters,
+            ) -> ::core::result::Result<$crate::__private::Vec<$crate::amacs::Attribute>, $crate::errors::MacError>
+            {
+                if system_parameters.NUMBER_OF_ATTRIBUTES != Self::NUMBER_OF_ATTRIBUTES {
+                    return ::core::result::Result::Err($crate::errors::MacError::MessageLengthError{
+                        length: system_parameters.NUMBER_OF_ATTRIBUTES as usize,
+                    });
+                }
+                ::core::result::Result::Ok(::core::convert::From::from([
+                    $($crate::attribute_schema!(@to $kind, self.$field)),+
+                ]))
+            }
+
+            /// Parse this schema from a vector of attributes, checking that
+            /// each attribute has the kind expected for its slot.
+            pub fn from_attributes(
+                attributes: &[$crate::amacs::Attribute],
+            ) -> ::core::result::Result<Self, $crate::errors::MacError>
+            {
+                if attributes.len() != Self::NUMBER_OF_ATTRIBUTES as usize {
+                    return ::core::result::Result::Err($crate::errors::MacError::MessageLengthError{
+                        length: Self::NUMBER_OF_ATTRIBUTES as usize,
+                    });
                 }
-                deserializer.deserialize_bytes(AeonfluxVisitor)
+
+                let mut _index: usize = 0;
+
+                $(
+                    let $field = $crate::attribute_schema!(@from $kind, &attributes[_index], _index);
+                    _index += 1;
+                )+
+
+                ::core::result::Result::Ok($name { $($field),+ })
             }
         }
+    };
+    (@check public_scalar) => {};
+    (@check secret_scalar) => {};
+    (@check public_point)  => {};
+    (@check either_point)  => {};
+    (@check secret_point)  => {};
+    (@check $other:ident)  => {
+        ::core::compile_error!(::core::concat!(
+            "unknown attribute kind `", ::core::stringify!($other), "`, expected one of `public_scalar`, \
+             `secret_scalar`, `public_point`, `either_point`, or `secret_point`"));
+    };
+    (@one $field:ident) => { 1u32 };
+    (@type public_scalar) => { $crate::prelude::Scalar };
+    (@type secret_scalar) => { $crate::prelude::Scalar };
+    (@type public_point)  => { $crate::prelude::RistrettoPoint };
+    (@type either_point)  => { $crate::symmetric::Plaintext };
+    (@type secret_point)  => { $crate::symmetric::Plaintext };
+    (@type $other:ident)  => { () };
+    (@presented public_scalar) => { $crate::amacs::AttributeKind::PublicScalar };
+    (@presented secret_scalar) => { $crate::amacs::AttributeKind::SecretScalar };
+    (@presented public_point)  => { $crate::amacs::AttributeKind::PublicPoint };
+    (@presented either_point)  => { $crate::amacs::AttributeKind::EitherPoint };
+    (@presented secret_point)  => { $crate::amacs::AttributeKind::SecretPoint };
+    (@presented $other:ident)  => { ::core::unreachable!() };
+    (@to public_scalar, $value:expr) => { $crate::amacs::Attribute::PublicScalar($value) };
+    (@to secret_scalar, $value:expr) => { $crate::amacs::Attribute::SecretScalar($value) };
+    (@to public_point,  $value:expr) => { $crate::amacs::Attribute::PublicPoint($value) };
+    (@to either_point,  $value:expr) => { $crate::amacs::Attribute::EitherPoint(::core::clone::Clone::clone(&$value)) };
+    (@to secret_point,  $value:expr) => { $crate::amacs::Attribute::SecretPoint(::core::clone::Clone::clone(&$value)) };
+    (@to $other:ident,  $value:expr) => { ::core::unreachable!() };
+    (@from public_scalar, $attribute:expr, $index:expr) => {
+        $crate::attribute_schema!(@match $attribute, $index, PublicScalar(x) => *x)
+    };
+    (@from secret_scalar, $attribute:expr, $index:expr) => {
+        $crate::attribute_schema!(@match $attribute, $index, SecretScalar(x) => *x)
+    };
+    (@from public_point, $attribute:expr, $index:expr) => {
+        $crate::attribute_schema!(@match $attribute, $index, PublicPoint(x) => *x)
+    };
+    (@from either_point, $attribute:expr, $index:expr) => {
+        $crate::attribute_schema!(@match $attribute, $index, EitherPoint(x) => ::core::clone::Clone::clone(x))
+    };
+    (@from secret_point, $attribute:expr, $index:expr) => {
+        $crate::attribute_schema!(@match $attribute, $index, SecretPoint(x) => ::core::clone::Clone::clone(x))
+    };
+    (@from $other:ident, $attribute:expr, $index:expr) => { ::core::unreachable!() };
+    (@match $attribute:expr, $index:expr, $variant:ident($x:ident) => $value:expr) => {
+        match $attribute {
+            $crate::amacs::Attribute::$variant($x) => $value,
+            _ => {
+                return ::core::result::Result::Err($crate::errors::MacError::AttributeSlotMismatch{ index: $index });
+            },
+        }
+    };
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(all(not(feature = "std"), feature = "alloc"))]
+    use alloc::vec::Vec;
+    #[cfg(all(not(feature = "alloc"), feature = "std"))]
+    use std::vec::Vec;
+
+    use crate::amacs::check_slot_binding;
+    use crate::amacs::Attribute;
+    use crate::amacs::EncryptedAttribute;
+    use crate::errors::MacError;
+    use crate::parameters::SystemParameters;
+    use crate::prelude::RistrettoPoint;
+    use crate::prelude::Scalar;
+    use crate::symmetric::Plaintext;
+
+    use rand::thread_rng;
+
+    attribute_schema! {
+        struct Profile {
+            age: public_scalar,
+            account: secret_scalar,
+            key: public_point,
+            email: either_point,
+            avatar: secret_point,
+        }
+    }
+
+    fn profile() -> Profile {
+        let mut rng = thread_rng();
+
+        Profile {
+            age: Scalar::from(42u64),
+            account: Scalar::random(&mut rng),
+            key: RistrettoPoint::random(&mut rng),
+            email: Plaintext::from(b"isis@patternsinthevoid.net...."),
+            avatar: Plaintext::from(b"This is a tsunami alert test.."),
+        }
+    }
+
+    #[test]
+    fn attribute_schema_roundtrip() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, Profile::NUMBER_OF_ATTRIBUTES).unwrap();
+        let profile = profile();
+        let attributes = profile.to_attributes(&system_parameters).unwrap();
+        let parsed = Profile::from_attributes(&attributes).unwrap();
+
+        assert_eq!(Profile::NUMBER_OF_ATTRIBUTES, 5);
+        assert_eq!(parsed.age, profile.age);
+        assert_eq!(parsed.account, profile.account);
+        assert_eq!(parsed.key, profile.key);
+        assert_eq!(parsed.email, profile.email);
+        assert_eq!(parsed.avatar, profile.avatar);
+    }
+
+    #[test]
+    fn attribute_schema_wrong_number_of_attributes() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 4).unwrap();
+
+        assert_eq!(profile().to_attributes(&system_parameters).unwrap_err(),
+                   MacError::MessageLengthError{ length: 4 });
+    }
+
+    #[test]
+    fn attribute_schema_wrong_attribute_kind() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, Profile::NUMBER_OF_ATTRIBUTES).unwrap();
+        let mut attributes: Vec<Attribute> = profile().to_attributes(&system_parameters).unwrap();
+
+        attributes.swap(0, 2);
+
+        assert_eq!(Profile::from_attributes(&attributes).unwrap_err(),
+                   MacError::AttributeSlotMismatch{ index: 0 });
+    }
+
+    #[test]
+    fn attribute_schema_presented() {
+        let mut rng = thread_rng();
+        let presented = vec![EncryptedAttribute::PublicScalar(Scalar::from(42u64)),
+                             EncryptedAttribute::SecretScalar,
+                             EncryptedAttribute::PublicPoint(RistrettoPoint::random(&mut rng)),
+                             EncryptedAttribute::PublicPoint(RistrettoPoint::random(&mut rng)),
+                             EncryptedAttribute::SecretPoint];
+
+        assert!(check_slot_binding(&presented, &Profile::schema()).is_ok());
+        assert_eq!(Profile::named_schema().attribute_schema(), &Profile::schema());
+        assert_eq!(Profile::named_schema().index("email"), Some(3));
+
+        // The either_point attribute may also be hidden, but no other may take its place.
+        let mut hidden = presented.clone();
+
+        hidden[3] = EncryptedAttribute::SecretPoint;
+
+        assert!(check_slot_binding(&hidden, &Profile::schema()).is_ok());
+
+        hidden[3] = EncryptedAttribute::SecretScalar;
+
+        assert_eq!(check_slot_binding(&hidden, &Profile::schema()).unwrap_err(),
+                   MacError::AttributeSlotMismatch{ index: 3 });
     }
 }
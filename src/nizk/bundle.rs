@@ -0,0 +1,569 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2020 The Brave Authors
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Presentations of several credentials, possibly from different issuers
+//! under different system parameters, in a single message.
+//!
+//! Every credential is proven valid as in a
+//! [`ProofOfValidCredential`](crate::nizk::presentation::ProofOfValidCredential),
+//! but all of the statements share a single transcript and thus a single
+//! Fiat-Shamir challenge, so that the proofs are bound to one another and
+//! cannot be separated and replayed individually.
+//!
+//! A hidden scalar attribute of each credential, e.g. a user id, may be
+//! bound to a single [`Pseudonym`] within the bundle's scope, as in
+//! [`ProofOfPseudonym`](crate::nizk::pseudonym::ProofOfPseudonym).  Every
+//! bound attribute shares one secret in the proof, which proves that they
+//! are all equal, and that the pseudonym was derived from them, without
+//! revealing them.
+//!
+//! Unlike a single presentation, the bundle carries the value \\(( Z = I * z
+//! \\)) for each credential, which would otherwise be recomputed by the
+//! verifier with its issuer's secret key.  Each issuer may therefore verify
+//! the whole proof, and check the \\(( Z \\)) of its own credentials, without
+//! the secret keys of the others.
+//!
+//! # Warning
+//!
+//! Since verification of a credential requires its issuer's secret key, an
+//! issuer which verifies a bundle with [`Issuer::verify_bundle`] only learns
+//! that *its own* credential is valid.  As for a
+//! [`FederatedPresentation`](crate::nizk::federated::FederatedPresentation),
+//! a service which requires every credential to be valid must learn, e.g.
+//! from the other issuers, that the bundle verifies for theirs too.
+//!
+//! [`Issuer::verify_bundle`]: crate::issuer::Issuer::verify_bundle
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use zeroize::Zeroize;
+
+use zkp::CompactProof;
+use zkp::Transcript;
+use zkp::toolbox::TranscriptProtocol;
+
+use crate::amacs::Attribute;
+use crate::amacs::AttributeKind;
+use crate::amacs::EncryptedAttribute;
+use crate::credential::AnonymousCredential;
+use crate::errors::CredentialError;
+use crate::issuer::Issuer;
+use crate::nizk::designated::LinearRelation;
+use crate::nizk::presentation::constrain_credential;
+use crate::nizk::presentation::encrypt_attributes;
+use crate::nizk::presentation::Commitments;
+use crate::nizk::presentation::PresentationPublics;
+use crate::nizk::pseudonym::Pseudonym;
+use crate::nizk::pseudonym::scope_basepoint;
+use crate::parameters::{IssuerParameters, SystemParameters};
+
+/// Feed the scope, the number of attributes of each credential, and the
+/// index of each attribute bound to the pseudonym into the `transcript`, so
+/// that the challenge is bound to the shape of the whole bundle.
+fn append_bundle(transcript: &mut Transcript, scope: &[u8], attributes: &[usize], bindings: &[Option<usize>]) {
+    transcript.append_message(b"credentials", &(attributes.len() as u32).to_le_bytes());
+    transcript.append_message(b"pseudonym scope", scope);
+
+    for (number_of_attributes, binding) in attributes.iter().zip(bindings.iter()) {
+        transcript.append_message(b"attributes", &(*number_of_attributes as u32).to_le_bytes());
+
+        if let Some(index) = binding {
+            transcript.append_message(b"pseudonym index", &(*index as u32).to_le_bytes());
+        }
+    }
+}
+
+/// Check that no credential has hidden group element attributes, and that
+/// every binding refers to a hidden scalar attribute.
+fn bundle_is_valid(
+    attributes: &[&[EncryptedAttribute]],
+    bindings: &[Option<usize>],
+) -> Result<(), CredentialError>
+{
+    for (encrypted_attributes, binding) in attributes.iter().zip(bindings.iter()) {
+        for attribute in encrypted_attributes.iter() {
+            if let EncryptedAttribute::SecretPoint = attribute {
+                return Err(CredentialError::BadAttribute);
+            }
+        }
+        if let Some(index) = binding {
+            match encrypted_attributes.get(*index) {
+                Some(EncryptedAttribute::SecretScalar) => continue,
+                _ => return Err(CredentialError::BadAttribute),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Create the transcript of a bundle, bound to its shape and to the
+/// parameters of every credential in it.
+fn bundle_transcript(
+    scope: &[u8],
+    parameters: &[(&SystemParameters, &IssuerParameters)],
+    attributes: &[&[EncryptedAttribute]],
+    bindings: &[Option<usize>],
+) -> Transcript
+{
+    let lengths: Vec<usize> = attributes.iter().map(|x| x.len()).collect();
+    let mut transcript = Transcript::new(b"2019/1416 anonymous credential");
+
+    append_bundle(&mut transcript, scope, &lengths[..], bindings);
+
+    for (system_parameters, _) in parameters.iter() {
+        transcript.append_message(b"parameters", &system_parameters.identifier());
+    }
+    transcript.domain_sep(b"2019/1416 presentation bundle proof");
+    transcript
+}
+
+/// The statement proven by a bundle: that of a presentation of each
+/// credential, as in [`constrain_credential`], where every bound attribute
+/// shares one secret \\(( m \\)), and the pseudonym \\(( P = H * m \\)) if
+/// there is one.
+///
+/// # Returns
+///
+/// The statement, and for each credential the indices within it of its
+/// secrets, in the order expected by [`constrain_credential`].
+fn bundle_relation(
+    scope: &[u8],
+    parameters: &[(&SystemParameters, &IssuerParameters)],
+    commitments: &[BundledCommitments],
+    bindings: &[Option<usize>],
+    P: Option<&RistrettoPoint>,
+) -> (LinearRelation, Vec<Vec<usize>>)
+{
+    let mut relation = LinearRelation::new(0);
+    let m_bound = P.map(|_| relation.allocate_scalar());
+    let mut secrets: Vec<Vec<usize>> = Vec::with_capacity(commitments.len());
+
+    for ((commitment, (system_parameters, issuer_parameters)), binding) in
+        commitments.iter().zip(parameters.iter()).zip(bindings.iter())
+    {
+        let kinds: Vec<AttributeKind> = commitment.encrypted_attributes.iter().map(|a| a.kind()).collect();
+        let mut secrets_k: Vec<usize> = (0..3).map(|_| relation.allocate_scalar()).collect();
+
+        for (i, kind) in kinds.iter().enumerate() {
+            if *kind == AttributeKind::SecretScalar {
+                secrets_k.push(match (binding, m_bound) {
+                    (Some(index), Some(m)) if *index == i => m,
+                    _ => relation.allocate_scalar(),
+                });
+            }
+        }
+
+        let publics = PresentationPublics {
+            I: &issuer_parameters.I,
+            Z: &commitment.Z,
+            C_x_0: &commitment.C_x_0,
+            C_x_1: &commitment.C_x_1,
+            C_y: &commitment.C_y,
+            kinds: &kinds,
+        };
+
+        constrain_credential(&mut relation, system_parameters, &publics, &secrets_k);
+        secrets.push(secrets_k);
+    }
+
+    // Constraint #4: P = H * m
+    if let (Some(m), Some(P)) = (m_bound, P) {
+        let H = relation.allocate_point(scope_basepoint(scope));
+        let P = relation.allocate_point(*P);
+
+        relation.constrain(P, vec![(m, H)]);
+    }
+
+    (relation, secrets)
+}
+
+/// One credential to be added to a [`PresentationBundle`], along with the
+/// parameters it was issued under.
+struct BundleEntry<'a> {
+    system_parameters: &'a SystemParameters,
+    issuer_parameters: &'a IssuerParameters,
+    credential: &'a AnonymousCredential,
+    binding: Option<usize>,
+}
+
+/// A builder for a [`PresentationBundle`] of several credentials, possibly
+/// from different issuers.
+///
+/// # Warning
+///
+/// Hidden group element attributes are not supported, and credentials with
+/// any [`Attribute::SecretPoint`]s cannot be presented this way.  As for a
+/// single credential, any [`Attribute::EitherPoint`]s are revealed.
+pub struct PresentationBundleBuilder<'a> {
+    scope: &'a [u8],
+    entries: Vec<BundleEntry<'a>>,
+}
+
+impl<'a> PresentationBundleBuilder<'a> {
+    /// Begin a bundle whose pseudonym, if any, is derived within a `scope`,
+    /// e.g. the verifier's domain name.
+    pub fn new(scope: &'a [u8]) -> PresentationBundleBuilder<'a> {
+        PresentationBundleBuilder {
+            scope,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Add a `credential`, issued under the `system_parameters` and
+    /// `issuer_parameters`, to the bundle.  If a `pseudonym_index` is given,
+    /// the hidden scalar attribute at that index is bound to the bundle's
+    /// pseudonym.
+    ///
+    /// # Returns
+    ///
+    /// The index of the `credential` within the bundle, for use with
+    /// [`Issuer::verify_bundle`].
+    ///
+    /// [`Issuer::verify_bundle`]: crate::issuer::Issuer::verify_bundle
+    pub fn add_credential(
+        &mut self,
+        system_parameters: &'a SystemParameters,
+        issuer_parameters: &'a IssuerParameters,
+        credential: &'a AnonymousCredential,
+        pseudonym_index: Option<usize>,
+    ) -> usize
+    {
+        self.entries.push(BundleEntry { system_parameters, issuer_parameters, credential, binding: pseudonym_index });
+        self.entries.len() - 1
+    }
+
+    /// Prove the validity of every credential, and the derivation of the
+    /// pseudonym from every bound attribute, with a single challenge.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the [`PresentationBundle`], otherwise a
+    /// [`CredentialError::WrongNumberOfAttributes`] if a credential doesn't
+    /// have one attribute per attribute of its [`SystemParameters`], or a
+    /// [`CredentialError::BadAttribute`] if a credential has a hidden group
+    /// element attribute, or the bound attributes aren't all equal hidden
+    /// scalar attributes.
+    pub fn finish<C>(self, csprng: &mut C) -> Result<PresentationBundle, CredentialError>
+    where
+        C: RngCore + CryptoRng,
+    {
+        PresentationBundle::prove(self.scope, &self.entries[..], csprng)
+    }
+}
+
+/// The commitments to one credential within a [`PresentationBundle`].
+pub struct BundledCommitments {
+    encrypted_attributes: Vec<EncryptedAttribute>,
+    C_x_0: RistrettoPoint,
+    C_x_1: RistrettoPoint,
+    C_V:   RistrettoPoint,
+    C_y: Vec<RistrettoPoint>,
+    Z:     RistrettoPoint,
+}
+
+/// A proof-of-knowledge of several valid credentials, possibly from
+/// different issuers, under a single challenge, created with a
+/// [`PresentationBundleBuilder`].
+pub struct PresentationBundle {
+    proof: CompactProof,
+    commitments: Vec<BundledCommitments>,
+    bindings: Vec<Option<usize>>,
+    scope: Vec<u8>,
+    P: Option<RistrettoPoint>,
+}
+
+impl PresentationBundle {
+    /// Create a [`PresentationBundle`].
+    fn prove<C>(
+        scope: &[u8],
+        entries: &[BundleEntry],
+        csprng: &mut C,
+    ) -> Result<PresentationBundle, CredentialError>
+    where
+        C: RngCore + CryptoRng,
+    {
+        let mut commitments: Vec<BundledCommitments> = Vec::with_capacity(entries.len());
+        let mut nonces: Vec<Scalar> = Vec::with_capacity(entries.len());
+
+        for entry in entries.iter() {
+            if entry.credential.attributes.iter().any(|a| matches!(a, Attribute::SecretPoint(_))) {
+                return Err(CredentialError::BadAttribute);
+            }
+
+            let committed = Commitments::new(entry.system_parameters, entry.issuer_parameters, entry.credential,
+                                             csprng)?;
+            let (encrypted_attributes, _) = encrypt_attributes(entry.system_parameters, entry.credential,
+                                                               None, &committed.z);
+
+            commitments.push(BundledCommitments {
+                encrypted_attributes,
+                C_x_0: committed.C_x_0,
+                C_x_1: committed.C_x_1,
+                C_V:   committed.C_V,
+                C_y:   committed.C_y.clone(),
+                Z:     committed.Z,
+            });
+            nonces.push(committed.z);
+        }
+
+        let bindings: Vec<Option<usize>> = entries.iter().map(|x| x.binding).collect();
+        let attributes: Vec<&[EncryptedAttribute]> = commitments.iter().map(|x| &x.encrypted_attributes[..]).collect();
+
+        bundle_is_valid(&attributes[..], &bindings[..])?;
+
+        // Every bound attribute must be equal, to share a single secret.
+        let mut bound: Option<Scalar> = None;
+
+        for entry in entries.iter() {
+            if let Some(index) = entry.binding {
+                match (&entry.credential.attributes[index], bound) {
+                    (Attribute::SecretScalar(m), None)               => bound = Some(*m),
+                    (Attribute::SecretScalar(m), Some(b)) if *m == b => continue,
+                    _ => return Err(CredentialError::BadAttribute),
+                }
+            }
+        }
+
+        let parameters: Vec<(&SystemParameters, &IssuerParameters)> = entries.iter()
+            .map(|entry| (entry.system_parameters, entry.issuer_parameters))
+            .collect();
+        let P = bound.map(|m| scope_basepoint(scope) * m);
+        let (relation, secrets) = bundle_relation(scope, &parameters, &commitments, &bindings, P.as_ref());
+
+        // The secrets, in the order in which the statement allocates them.
+        let mut witness: Vec<Scalar> = vec![Scalar::zero(); relation.number_of_scalars()];
+
+        for ((entry, z), secrets_k) in entries.iter().zip(nonces.iter()).zip(secrets.iter()) {
+            let hidden = entry.credential.attributes.iter().filter_map(|attribute| match attribute {
+                Attribute::SecretScalar(m) => Some(*m),
+                _ => None,
+            });
+            let values = vec![*z, (-entry.credential.amac.t * z).reduce(), entry.credential.amac.t];
+
+            for (index, value) in secrets_k.iter().zip(values.into_iter().chain(hidden)) {
+                witness[*index] = value;
+            }
+        }
+
+        let mut transcript = bundle_transcript(scope, &parameters, &attributes, &bindings);
+        let proof = relation.prove_compact(&mut transcript, &witness, csprng);
+
+        for secret in witness.iter_mut() {
+            secret.zeroize();
+        }
+
+        Ok(PresentationBundle {
+            proof,
+            commitments,
+            bindings,
+            scope: scope.to_vec(),
+            P,
+        })
+    }
+
+    /// The number of credentials in this bundle.
+    pub fn len(&self) -> usize {
+        self.commitments.len()
+    }
+
+    /// Whether this bundle contains no credentials.
+    pub fn is_empty(&self) -> bool {
+        self.commitments.is_empty()
+    }
+
+    /// The attributes of the `index`th credential as presented, i.e. with
+    /// hidden attributes elided.
+    pub fn attributes(&self, index: usize) -> Option<&[EncryptedAttribute]> {
+        self.commitments.get(index).map(|x| &x.encrypted_attributes[..])
+    }
+
+    /// The scope within which the pseudonym was derived.
+    pub fn scope(&self) -> &[u8] {
+        &self.scope[..]
+    }
+
+    /// The pseudonym to which the bound attributes were proven to
+    /// correspond, if any attributes were bound.
+    pub fn pseudonym(&self) -> Option<Pseudonym> {
+        self.P.map(|P| Pseudonym(P.compress().to_bytes()))
+    }
+
+    /// Verify a `PresentationBundle`, and the validity of the `index`th
+    /// credential, which must have been issued by the `issuer`.
+    ///
+    /// The `parameters` are the system and issuer parameters of every
+    /// credential in the bundle, in order.
+    pub(crate) fn verify(
+        &self,
+        issuer: &Issuer,
+        index: usize,
+        parameters: &[(&SystemParameters, &IssuerParameters)],
+    ) -> Result<(), CredentialError>
+    {
+        if parameters.len() != self.commitments.len() || self.bindings.len() != self.commitments.len() {
+            return Err(CredentialError::VerificationFailure);
+        }
+        match parameters.get(index) {
+            Some((system_parameters, issuer_parameters)) if *system_parameters == &issuer.system_parameters &&
+                                                            *issuer_parameters == &issuer.issuer_parameters => (),
+            _ => return Err(CredentialError::VerificationFailure),
+        }
+        for (commitment, (system_parameters, _)) in self.commitments.iter().zip(parameters.iter()) {
+            let NUMBER_OF_ATTRIBUTES = system_parameters.NUMBER_OF_ATTRIBUTES as usize;
+
+            if commitment.encrypted_attributes.len() != NUMBER_OF_ATTRIBUTES ||
+                commitment.C_y.len() != NUMBER_OF_ATTRIBUTES
+            {
+                return Err(CredentialError::WrongNumberOfAttributes);
+            }
+        }
+
+        let attributes: Vec<&[EncryptedAttribute]> = self.commitments.iter().map(|x| &x.encrypted_attributes[..]).collect();
+
+        bundle_is_valid(&attributes[..], &self.bindings[..])?;
+
+        // A pseudonym is given if and only if some attribute is bound to it.
+        if self.bindings.iter().any(|x| x.is_some()) != self.P.is_some() {
+            return Err(CredentialError::VerificationFailure);
+        }
+
+        // Recompute the prover's Z value for our credential, as for a
+        // single credential presentation, and check it against that given.
+        let commitment = &self.commitments[index];
+        let mut Z_ = commitment.C_V - issuer.amacs_key.W -
+            (commitment.C_x_0 * issuer.amacs_key.x_0) - (commitment.C_x_1 * issuer.amacs_key.x_1);
+
+        for (i, attribute) in commitment.encrypted_attributes.iter().enumerate() {
+            let x = match attribute {
+                EncryptedAttribute::PublicScalar(m_i) => commitment.C_y[i] + (issuer.system_parameters.G_m[i] * m_i),
+                EncryptedAttribute::SecretScalar      => commitment.C_y[i],
+                EncryptedAttribute::PublicPoint(M_i)  => commitment.C_y[i] + M_i,
+                EncryptedAttribute::SecretPoint       => commitment.C_y[i],
+            };
+            Z_ -= x * issuer.amacs_key.y[i];
+        }
+
+        if Z_ != commitment.Z {
+            return Err(CredentialError::VerificationFailure);
+        }
+
+        let (relation, _) = bundle_relation(&self.scope[..], parameters, &self.commitments, &self.bindings,
+                                            self.P.as_ref());
+        let mut transcript = bundle_transcript(&self.scope[..], parameters, &attributes, &self.bindings);
+
+        relation.verify_compact(&mut transcript, &self.proof)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+
+    use crate::user::CredentialRequestConstructor;
+
+    use rand::thread_rng;
+
+    #[test]
+    fn bundle_of_credentials_from_different_issuers() {
+        let mut rng = thread_rng();
+        let user_id = Scalar::random(&mut rng);
+        let first_system_parameters = SystemParameters::generate(&mut rng, 2).unwrap();
+        let second_system_parameters = SystemParameters::generate(&mut rng, 3).unwrap();
+        let first_issuer = Issuer::new(&first_system_parameters, &mut rng);
+        let second_issuer = Issuer::new(&second_system_parameters, &mut rng);
+
+        let mut request = CredentialRequestConstructor::new(&first_system_parameters);
+        request.append_revealed_scalar(user_id);
+        request.append_revealed_point(RistrettoPoint::random(&mut rng));
+
+        let issuance = first_issuer.issue(request.finish(), &mut rng).unwrap();
+        let mut first = issuance.verify(&first_system_parameters, &first_issuer.issuer_parameters).unwrap();
+
+        let mut request = CredentialRequestConstructor::new(&second_system_parameters);
+        request.append_revealed_scalar(Scalar::random(&mut rng));
+        request.append_revealed_scalar(Scalar::random(&mut rng));
+        request.append_revealed_scalar(user_id);
+
+        let issuance = second_issuer.issue(request.finish(), &mut rng).unwrap();
+        let mut second = issuance.verify(&second_system_parameters, &second_issuer.issuer_parameters).unwrap();
+
+        first.hide_attribute(0).unwrap();
+        second.hide_attribute(1).unwrap();
+        second.hide_attribute(2).unwrap();
+
+        let parameters = [(&first_system_parameters, &first_issuer.issuer_parameters),
+                          (&second_system_parameters, &second_issuer.issuer_parameters)];
+
+        let mut builder = PresentationBundleBuilder::new(b"example.com");
+        let a = builder.add_credential(&first_system_parameters, &first_issuer.issuer_parameters, &first, Some(0));
+        let b = builder.add_credential(&second_system_parameters, &second_issuer.issuer_parameters, &second, Some(2));
+        let mut bundle = builder.finish(&mut rng).unwrap();
+
+        assert_eq!(bundle.len(), 2);
+        assert!(matches!(bundle.attributes(b).unwrap()[0], EncryptedAttribute::PublicScalar(_)));
+        assert!(first_issuer.verify_bundle(&bundle, a, &parameters).is_ok());
+        assert!(second_issuer.verify_bundle(&bundle, b, &parameters).is_ok());
+
+        // Each issuer can only vouch for its own credential.
+        assert!(first_issuer.verify_bundle(&bundle, b, &parameters).is_err());
+        assert!(second_issuer.verify_bundle(&bundle, a, &parameters[..1]).is_err());
+
+        // The pseudonym is stable within the scope, but not across scopes.
+        let mut builder = PresentationBundleBuilder::new(b"example.com");
+        builder.add_credential(&second_system_parameters, &second_issuer.issuer_parameters, &second, Some(2));
+        let again = builder.finish(&mut rng).unwrap();
+
+        assert_eq!(bundle.pseudonym(), again.pseudonym());
+        assert!(second_issuer.verify_bundle(&again, 0, &parameters[1..]).is_ok());
+
+        let mut builder = PresentationBundleBuilder::new(b"example.org");
+        builder.add_credential(&second_system_parameters, &second_issuer.issuer_parameters, &second, Some(2));
+        let elsewhere = builder.finish(&mut rng).unwrap();
+
+        assert!(elsewhere.pseudonym().is_some());
+        assert_ne!(bundle.pseudonym(), elsewhere.pseudonym());
+
+        // Tampering with any credential invalidates the whole bundle, for
+        // every issuer.
+        bundle.commitments[b].C_y[0] += RISTRETTO_BASEPOINT_POINT;
+
+        assert!(first_issuer.verify_bundle(&bundle, a, &parameters).is_err());
+
+        bundle.commitments[b].C_y[0] -= RISTRETTO_BASEPOINT_POINT;
+        bundle.scope = b"example.org".to_vec();
+
+        assert!(first_issuer.verify_bundle(&bundle, a, &parameters).is_err());
+
+        // Unequal attributes cannot be bound to the same pseudonym.
+        let mut builder = PresentationBundleBuilder::new(b"example.com");
+
+        builder.add_credential(&first_system_parameters, &first_issuer.issuer_parameters, &first, Some(0));
+        builder.add_credential(&second_system_parameters, &second_issuer.issuer_parameters, &second, Some(1));
+
+        assert_eq!(builder.finish(&mut rng).err(), Some(CredentialError::BadAttribute));
+
+        // Nor can revealed attributes.
+        let mut builder = PresentationBundleBuilder::new(b"example.com");
+
+        builder.add_credential(&second_system_parameters, &second_issuer.issuer_parameters, &second, Some(0));
+
+        assert_eq!(builder.finish(&mut rng).err(), Some(CredentialError::BadAttribute));
+    }
+}
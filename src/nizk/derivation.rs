@@ -0,0 +1,184 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2020 The Brave Authors
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Deterministic group elements derived from hidden attributes.
+//!
+//! For a hidden scalar attribute \\(( m \\)) in slot \\(( i \\)), whose
+//! presentation commitment is \\(( C_y_i = G_y_i * z + G_m_i * m \\)), and
+//! some public bytes hashed to a group element \\(( H \\)), the derived
+//! element is \\(( T = H * m \\)).  The presenter proves that the same
+//! \\(( m \\)) is used in both.
+//!
+//! [`Nullifier`](crate::nizk::nullifier::Nullifier)s and
+//! [`Pseudonym`](crate::nizk::pseudonym::Pseudonym)s are both derived in this
+//! way, and differ only in the domain separators of their
+//! [`DerivationStatement`]s, so that the one is never the other.
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+
+use sha2::Digest;
+use sha2::Sha512;
+
+use zkp::CompactProof;
+use zkp::Transcript;
+use zkp::toolbox::SchnorrCS;
+use zkp::toolbox::prover::Prover;
+use zkp::toolbox::verifier::Verifier;
+
+use crate::errors::CredentialError;
+use crate::parameters::SystemParameters;
+
+/// A request to derive a group element from the hidden scalar attribute at
+/// some index, hashing some public bytes to its basepoint.
+pub trait DerivationStatement: Clone {
+    /// The domain separator under which the bytes are hashed to a basepoint.
+    const BASEPOINT_DOMAIN: &'static [u8];
+    /// The domain separator of the proofs of derivation.
+    const PROOF_DOMAIN: &'static [u8];
+
+    /// The index of the attribute, which must be hidden upon presentation.
+    fn index(&self) -> usize;
+
+    /// The bytes to hash to the basepoint.
+    fn bytes(&self) -> &[u8];
+}
+
+/// Hash some `bytes` to a group element under a `domain` separator.
+pub(crate) fn hash_to_basepoint(domain: &[u8], bytes: &[u8]) -> RistrettoPoint {
+    let mut hasher = Sha512::default();
+
+    hasher.input(domain);
+    hasher.input(bytes);
+
+    RistrettoPoint::from_hash(hasher)
+}
+
+/// Hash the bytes of a `statement` to its basepoint.
+fn basepoint<S: DerivationStatement>(statement: &S) -> RistrettoPoint {
+    hash_to_basepoint(S::BASEPOINT_DOMAIN, statement.bytes())
+}
+
+/// Bind a `statement` into a `transcript`.
+fn commit<S: DerivationStatement>(statement: &S, transcript: &mut Transcript) {
+    transcript.append_message(b"derivation index", &(statement.index() as u32).to_le_bytes());
+    transcript.append_message(b"derivation bytes", statement.bytes());
+}
+
+/// A non-interactive zero-knowledge proof that a group element was correctly
+/// derived from a hidden scalar attribute of a presented credential.
+#[derive(Clone)]
+pub struct ProofOfDerivation<S: DerivationStatement> {
+    statement: S,
+    T: RistrettoPoint,
+    proof: CompactProof,
+}
+
+impl<S: DerivationStatement> ProofOfDerivation<S> {
+    /// Create a [`ProofOfDerivation`] for the hidden scalar `m`, committed
+    /// to in slot `statement.index()` with the presentation nonce `z`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the proof, otherwise a
+    /// [`CredentialError::BadAttribute`] if the statement's index is out of
+    /// range.
+    pub(crate) fn prove(
+        system_parameters: &SystemParameters,
+        statement: &S,
+        m: &Scalar,
+        z: &Scalar,
+    ) -> Result<ProofOfDerivation<S>, CredentialError>
+    {
+        if statement.index() >= system_parameters.NUMBER_OF_ATTRIBUTES as usize {
+            return Err(CredentialError::BadAttribute);
+        }
+
+        let G_m_i = system_parameters.G_m[statement.index()];
+        let G_y_i = system_parameters.G_y[statement.index()];
+        let H_ = basepoint(statement);
+        let T_ = H_ * m;
+
+        let mut transcript = Transcript::new(b"2019/1416 anonymous credential");
+
+        commit(statement, &mut transcript);
+
+        let mut prover = Prover::new(S::PROOF_DOMAIN, &mut transcript);
+
+        // Feed the domain separators for the Camenisch-Stadler secrets into the protocol transcript.
+        let m_var = prover.allocate_scalar(b"m", *m);
+        let z_var = prover.allocate_scalar(b"z", *z);
+
+        // Feed in the domain separators and values for the publics into the transcript.
+        let (G_m, _) = prover.allocate_point(b"G_m", G_m_i);
+        let (G_y, _) = prover.allocate_point(b"G_y", G_y_i);
+        let (C_y, _) = prover.allocate_point(b"C_y", G_y_i * z + G_m_i * m);
+        let (H, _)   = prover.allocate_point(b"H", H_);
+        let (T, _)   = prover.allocate_point(b"T", T_);
+
+        // Constraint #1: C_y_i = G_m_i * m + G_y_i * z
+        prover.constrain(C_y, vec![(m_var, G_m), (z_var, G_y)]);
+
+        // Constraint #2: T = H * m
+        prover.constrain(T, vec![(m_var, H)]);
+
+        Ok(ProofOfDerivation { statement: statement.clone(), T: T_, proof: prover.prove_compact() })
+    }
+
+    /// The statement which this proof proves.
+    pub fn statement(&self) -> &S {
+        &self.statement
+    }
+
+    /// The derived group element, compressed.
+    pub(crate) fn derived(&self) -> [u8; 32] {
+        self.T.compress().to_bytes()
+    }
+
+    /// Verify this [`ProofOfDerivation`] against the presentation commitment
+    /// `C_y_i` to the attribute in slot `self.statement().index()`.
+    pub(crate) fn verify(
+        &self,
+        system_parameters: &SystemParameters,
+        C_y_i: &RistrettoPoint,
+    ) -> Result<(), CredentialError>
+    {
+        let statement = &self.statement;
+
+        if statement.index() >= system_parameters.NUMBER_OF_ATTRIBUTES as usize {
+            return Err(CredentialError::VerificationFailure);
+        }
+
+        let mut transcript = Transcript::new(b"2019/1416 anonymous credential");
+
+        commit(statement, &mut transcript);
+
+        let mut verifier = Verifier::new(S::PROOF_DOMAIN, &mut transcript);
+
+        // Feed the domain separators for the Camenisch-Stadler secrets into the protocol transcript.
+        let m = verifier.allocate_scalar(b"m");
+        let z = verifier.allocate_scalar(b"z");
+
+        // Feed in the domain separators and values for the publics into the transcript.
+        let G_m = verifier.allocate_point(b"G_m", system_parameters.G_m[statement.index()].compress())?;
+        let G_y = verifier.allocate_point(b"G_y", system_parameters.G_y[statement.index()].compress())?;
+        let C_y = verifier.allocate_point(b"C_y", C_y_i.compress())?;
+        let H   = verifier.allocate_point(b"H", basepoint(statement).compress())?;
+        let T   = verifier.allocate_point(b"T", CompressedRistretto(self.derived()))?;
+
+        // Constraint #1: C_y_i = G_m_i * m + G_y_i * z
+        verifier.constrain(C_y, vec![(m, G_m), (z, G_y)]);
+
+        // Constraint #2: T = H * m
+        verifier.constrain(T, vec![(m, H)]);
+
+        verifier.verify_compact(&self.proof).or(Err(CredentialError::VerificationFailure))
+    }
+}
@@ -0,0 +1,374 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2020 The Brave Authors
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Designated-verifier proofs, which are only convincing to the holder of a
+//! [`VerifierKeypair`].
+//!
+//! A designated-verifier proof of a statement is a proof of the disjunction
+//! "the statement holds, *or* I know the secret key \\(( x \\)) of the
+//! verifier's public key \\(( X = G * x \\))".  The prover, who doesn't know
+//! \\(( x \\)), must prove the statement, and so the designated verifier is
+//! convinced.  However, the designated verifier could have produced the
+//! proof themselves from their secret key, for any statement, and so the
+//! proof is no evidence of anything to a third party to whom the verifier
+//! transfers it.
+//!
+//! The disjunction is proven with the usual Cramer-Damgård-Schoenmakers
+//! technique: the prover simulates a proof of knowledge of \\(( x \\)) for a
+//! challenge \\(( c_X \\)) of their choice, and proves the statement for the
+//! challenge \\(( c - c_X \\)), where \\(( c \\)) is the Fiat-Shamir
+//! challenge over the commitments of both.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::MultiscalarMul;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use zeroize::Zeroize;
+
+use zkp::CompactProof;
+use zkp::Transcript;
+
+use crate::errors::CredentialError;
+
+/// A designated verifier's public key, \\(( X = G * x \\)).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct VerifierPublicKey(pub(crate) RistrettoPoint);
+
+/// A designated verifier's keypair.
+pub struct VerifierKeypair {
+    secret: Scalar,
+    /// The public portion of this keypair.
+    pub public: VerifierPublicKey,
+}
+
+/// Overwrite the verifier's secret key when it drops out of scope.
+impl Drop for VerifierKeypair {
+    fn drop(&mut self) {
+        self.secret.zeroize();
+    }
+}
+
+impl VerifierKeypair {
+    /// Generate a new designated verifier keypair.
+    pub fn generate<R>(csprng: &mut R) -> VerifierKeypair
+    where
+        R: RngCore + CryptoRng,
+    {
+        let secret = Scalar::random(csprng);
+        let public = VerifierPublicKey(RISTRETTO_BASEPOINT_POINT * secret);
+
+        VerifierKeypair { secret, public }
+    }
+}
+
+/// A statement of linear relations \\(( Q_j = \sum_k P_{j,k} * x_k \\))
+/// between public points and secret scalars, each given by its index.
+///
+/// Besides the designated-verifier proofs of this module, a statement may be
+/// proven with an ordinary compact proof, or interactively by answering a
+/// challenge to its [`commitments`](LinearRelation::commitments).
+pub(crate) struct LinearRelation {
+    points: Vec<RistrettoPoint>,
+    constraints: Vec<(usize, Vec<(usize, usize)>)>,
+    scalars: usize,
+}
+
+impl LinearRelation {
+    /// Create an empty statement about `scalars` secret scalars.
+    pub(crate) fn new(scalars: usize) -> LinearRelation {
+        LinearRelation { points: Vec::new(), constraints: Vec::new(), scalars }
+    }
+
+    /// Add another secret scalar to the statement, returning its index.
+    pub(crate) fn allocate_scalar(&mut self) -> usize {
+        self.scalars += 1;
+        self.scalars - 1
+    }
+
+    /// Add a public `point` to the statement, returning its index.
+    pub(crate) fn allocate_point(&mut self, point: RistrettoPoint) -> usize {
+        self.points.push(point);
+        self.points.len() - 1
+    }
+
+    /// Constrain the point `lhs` to be the sum of each `(scalar, point)`
+    /// product of the `rhs`.
+    pub(crate) fn constrain(&mut self, lhs: usize, rhs: Vec<(usize, usize)>) {
+        self.constraints.push((lhs, rhs));
+    }
+
+    /// The number of secret scalars in the statement.
+    pub(crate) fn number_of_scalars(&self) -> usize {
+        self.scalars
+    }
+
+    /// Compute \\(( \sum_k P_{j,k} * s_k - Q_j * c \\)) for each constraint.
+    pub(crate) fn commitments(&self, scalars: &[Scalar], challenge: &Scalar) -> Vec<RistrettoPoint> {
+        self.constraints.iter().map(|(lhs, rhs)| {
+            RistrettoPoint::multiscalar_mul(
+                rhs.iter().map(|(scalar, _)| scalars[*scalar]).chain(Some(-challenge)),
+                rhs.iter().map(|(_, point)| self.points[*point]).chain(Some(self.points[*lhs])),
+            )
+        }).collect()
+    }
+
+    /// Feed the points of the statement, and the `commitments` to it, into
+    /// the `transcript`.
+    fn append_to_transcript(&self, transcript: &mut Transcript, commitments: &[RistrettoPoint]) {
+        for point in self.points.iter() {
+            transcript.append_message(b"P", point.compress().as_bytes());
+        }
+        for commitment in commitments.iter() {
+            transcript.append_message(b"A", commitment.compress().as_bytes());
+        }
+    }
+
+    /// Derive the Fiat-Shamir challenge for the `commitments` to this
+    /// statement, continuing the `transcript`.
+    pub(crate) fn challenge(&self, transcript: &mut Transcript, commitments: &[RistrettoPoint]) -> Scalar {
+        let mut bytes = [0u8; 64];
+
+        self.append_to_transcript(transcript, commitments);
+        transcript.challenge_bytes(b"chal", &mut bytes);
+
+        Scalar::from_bytes_mod_order_wide(&bytes)
+    }
+
+    /// Prove this statement with the `witness`, its secret scalars in order,
+    /// continuing the `transcript`.
+    pub(crate) fn prove_compact<C>(
+        &self,
+        transcript: &mut Transcript,
+        witness: &[Scalar],
+        csprng: &mut C,
+    ) -> CompactProof
+    where
+        C: RngCore + CryptoRng,
+    {
+        let blindings: Vec<Scalar> = (0..self.scalars).map(|_| Scalar::random(csprng)).collect();
+        let commitments = self.commitments(&blindings, &Scalar::zero());
+        let challenge = self.challenge(transcript, &commitments);
+        let responses = blindings.iter().zip(witness.iter()).map(|(b, w)| b + challenge * w).collect();
+
+        CompactProof { challenge, responses }
+    }
+
+    /// Verify a compact `proof` of this statement, continuing the
+    /// `transcript`.
+    pub(crate) fn verify_compact(
+        &self,
+        transcript: &mut Transcript,
+        proof: &CompactProof,
+    ) -> Result<(), CredentialError>
+    {
+        if proof.responses.len() != self.scalars {
+            return Err(CredentialError::VerificationFailure);
+        }
+
+        let commitments = self.commitments(&proof.responses, &proof.challenge);
+
+        match self.challenge(transcript, &commitments) == proof.challenge {
+            true  => Ok(()),
+            false => Err(CredentialError::VerificationFailure),
+        }
+    }
+}
+
+/// Derive the challenge for the `commitments` to a `relation` and the
+/// commitment `A_X` to the verifier's secret key.
+fn challenge(
+    transcript: &mut Transcript,
+    relation: &LinearRelation,
+    commitments: &[RistrettoPoint],
+    verifier: &VerifierPublicKey,
+    A_X: &RistrettoPoint,
+) -> Scalar
+{
+    let mut bytes = [0u8; 64];
+
+    relation.append_to_transcript(transcript, commitments);
+    transcript.append_message(b"X", verifier.0.compress().as_bytes());
+    transcript.append_message(b"A_X", A_X.compress().as_bytes());
+    transcript.challenge_bytes(b"chal", &mut bytes);
+
+    Scalar::from_bytes_mod_order_wide(&bytes)
+}
+
+/// A proof of a [`LinearRelation`] which is only convincing to the holder of
+/// a [`VerifierKeypair`].
+#[derive(Clone)]
+pub(crate) struct DesignatedProof {
+    pub(crate) verifier: VerifierPublicKey,
+    challenge: Scalar,
+    responses: Vec<Scalar>,
+    verifier_challenge: Scalar,
+    verifier_response: Scalar,
+}
+
+impl DesignatedProof {
+    /// Prove the `relation` with the `witness`, its secret scalars in order,
+    /// designated to the `verifier`, continuing the `transcript`.
+    pub(crate) fn prove<C>(
+        transcript: &mut Transcript,
+        relation: &LinearRelation,
+        witness: &[Scalar],
+        verifier: &VerifierPublicKey,
+        csprng: &mut C,
+    ) -> DesignatedProof
+    where
+        C: RngCore + CryptoRng,
+    {
+        // Simulate the proof of knowledge of the verifier's secret key.
+        let verifier_challenge = Scalar::random(csprng);
+        let verifier_response = Scalar::random(csprng);
+        let A_X = RISTRETTO_BASEPOINT_POINT * verifier_response - verifier.0 * verifier_challenge;
+
+        // Commit to blindings of the witness, as in an ordinary proof.
+        let blindings: Vec<Scalar> = (0..relation.scalars).map(|_| Scalar::random(csprng)).collect();
+        let commitments = relation.commitments(&blindings, &Scalar::zero());
+
+        let challenge = challenge(transcript, relation, &commitments, verifier, &A_X) - verifier_challenge;
+        let responses = blindings.iter().zip(witness.iter()).map(|(b, w)| b + challenge * w).collect();
+
+        DesignatedProof { verifier: *verifier, challenge, responses, verifier_challenge, verifier_response }
+    }
+
+    /// Verify that this proof is of the `relation`, or of knowledge of the
+    /// secret key of the `verifier`, continuing the `transcript`.
+    pub(crate) fn verify(
+        &self,
+        transcript: &mut Transcript,
+        relation: &LinearRelation,
+        verifier: &VerifierPublicKey,
+    ) -> Result<(), CredentialError>
+    {
+        if self.verifier != *verifier || self.responses.len() != relation.scalars {
+            return Err(CredentialError::VerificationFailure);
+        }
+
+        let commitments = relation.commitments(&self.responses, &self.challenge);
+        let A_X = RISTRETTO_BASEPOINT_POINT * self.verifier_response - verifier.0 * self.verifier_challenge;
+
+        match challenge(transcript, relation, &commitments, verifier, &A_X) == self.challenge + self.verifier_challenge {
+            true  => Ok(()),
+            false => Err(CredentialError::VerificationFailure),
+        }
+    }
+
+    /// Forge a proof of any `relation` from the verifier's `keypair`, as the
+    /// designated verifier could.
+    #[cfg(test)]
+    pub(crate) fn forge<C>(
+        transcript: &mut Transcript,
+        relation: &LinearRelation,
+        keypair: &VerifierKeypair,
+        csprng: &mut C,
+    ) -> DesignatedProof
+    where
+        C: RngCore + CryptoRng,
+    {
+        // Simulate the proof of the relation.
+        let challenge_ = Scalar::random(csprng);
+        let responses: Vec<Scalar> = (0..relation.scalars).map(|_| Scalar::random(csprng)).collect();
+        let commitments = relation.commitments(&responses, &challenge_);
+
+        // Prove knowledge of the secret key.
+        let blinding = Scalar::random(csprng);
+        let A_X = RISTRETTO_BASEPOINT_POINT * blinding;
+        let verifier_challenge = challenge(transcript, relation, &commitments, &keypair.public, &A_X) - challenge_;
+
+        DesignatedProof {
+            verifier: keypair.public,
+            challenge: challenge_,
+            responses,
+            verifier_challenge,
+            verifier_response: blinding + verifier_challenge * keypair.secret,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    #[test]
+    fn designated_proofs_are_forgeable_by_the_verifier() {
+        let mut rng = thread_rng();
+        let verifier = VerifierKeypair::generate(&mut rng);
+        let other = VerifierKeypair::generate(&mut rng);
+        let x = Scalar::random(&mut rng);
+        let G = RistrettoPoint::random(&mut rng);
+
+        let mut relation = LinearRelation::new(1);
+        let G_ = relation.allocate_point(G);
+        let Q = relation.allocate_point(G * x);
+
+        relation.constrain(Q, vec![(0, G_)]);
+
+        let proof = DesignatedProof::prove(&mut Transcript::new(b"test"), &relation, &[x], &verifier.public, &mut rng);
+
+        assert!(proof.verify(&mut Transcript::new(b"test"), &relation, &verifier.public).is_ok());
+        assert!(proof.verify(&mut Transcript::new(b"test"), &relation, &other.public).is_err());
+        assert!(proof.verify(&mut Transcript::new(b"other"), &relation, &verifier.public).is_err());
+
+        // Without the witness, a proof is only possible with the verifier's secret key.
+        let mut false_relation = LinearRelation::new(1);
+        let G_ = false_relation.allocate_point(G);
+        let Q = false_relation.allocate_point(RistrettoPoint::random(&mut rng));
+
+        false_relation.constrain(Q, vec![(0, G_)]);
+
+        let forgery = DesignatedProof::forge(&mut Transcript::new(b"test"), &false_relation, &verifier, &mut rng);
+
+        assert!(forgery.verify(&mut Transcript::new(b"test"), &false_relation, &verifier.public).is_ok());
+        assert!(DesignatedProof::prove(&mut Transcript::new(b"test"), &false_relation, &[x], &verifier.public, &mut rng)
+                .verify(&mut Transcript::new(b"test"), &false_relation, &verifier.public).is_err());
+    }
+
+    #[test]
+    fn compact_proofs_of_linear_relations() {
+        let mut rng = thread_rng();
+        let (x, y) = (Scalar::random(&mut rng), Scalar::random(&mut rng));
+        let (G, H) = (RistrettoPoint::random(&mut rng), RistrettoPoint::random(&mut rng));
+
+        let mut relation = LinearRelation::new(2);
+        let G_ = relation.allocate_point(G);
+        let H_ = relation.allocate_point(H);
+        let P = relation.allocate_point(G * x + H * y);
+        let Q = relation.allocate_point(H * x);
+
+        relation.constrain(P, vec![(0, G_), (1, H_)]);
+        relation.constrain(Q, vec![(0, H_)]);
+
+        let mut proof = relation.prove_compact(&mut Transcript::new(b"test"), &[x, y], &mut rng);
+
+        assert!(relation.verify_compact(&mut Transcript::new(b"test"), &proof).is_ok());
+        assert!(relation.verify_compact(&mut Transcript::new(b"other"), &proof).is_err());
+
+        // A wrong witness yields a proof which doesn't verify.
+        let forgery = relation.prove_compact(&mut Transcript::new(b"test"), &[y, x], &mut rng);
+
+        assert!(relation.verify_compact(&mut Transcript::new(b"test"), &forgery).is_err());
+
+        proof.responses.pop();
+
+        assert!(relation.verify_compact(&mut Transcript::new(b"test"), &proof).is_err());
+    }
+}
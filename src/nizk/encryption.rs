@@ -9,6 +9,11 @@
 
 //! Non-interactive zero-knowledge proofs (NIZKs) of correct encryption under a given key.
 
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
 use curve25519_dalek::scalar::Scalar;
 use curve25519_dalek::ristretto::RistrettoPoint;
 
@@ -21,6 +26,8 @@ use zkp::toolbox::prover::Prover;
 use zkp::toolbox::verifier::Verifier;
 
 use crate::errors::CredentialError;
+use crate::nizk::presentation::point_from_slice;
+use crate::nizk::presentation::scalar_from_slice;
 use crate::parameters::SystemParameters;
 use crate::symmetric::Ciphertext;
 use crate::symmetric::Keypair as SymmetricKeypair;
@@ -34,13 +41,67 @@ pub struct ProofOfEncryption {
     public_key: SymmetricPublicKey,
     pub(crate) ciphertext: Ciphertext,
     index: u16,
-    C_y_1: RistrettoPoint,
+    pub(crate) C_y_1: RistrettoPoint,
     C_y_2: RistrettoPoint,
-    C_y_3: RistrettoPoint,
+    pub(crate) C_y_3: RistrettoPoint,
     C_y_2_prime: RistrettoPoint,
 }
 
 impl ProofOfEncryption {
+    /// The length, in bytes, of a serialised proof of encryption: seven
+    /// compressed points, the challenge, and one response for each of the
+    /// six secrets.
+    pub(crate) const SERIALIZED_LEN: usize = 32 * 14;
+
+    /// Serialise this proof, excluding its index, which is implied by its
+    /// position within a serialised presentation.
+    pub(crate) fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = Vec::with_capacity(ProofOfEncryption::SERIALIZED_LEN);
+
+        bytes.extend(self.public_key.pk.compress().as_bytes());
+        bytes.extend(self.ciphertext.E1.compress().as_bytes());
+        bytes.extend(self.ciphertext.E2.compress().as_bytes());
+        bytes.extend(self.C_y_1.compress().as_bytes());
+        bytes.extend(self.C_y_2.compress().as_bytes());
+        bytes.extend(self.C_y_3.compress().as_bytes());
+        bytes.extend(self.C_y_2_prime.compress().as_bytes());
+        bytes.extend(self.proof.challenge.as_bytes());
+
+        for response in self.proof.responses.iter() {
+            bytes.extend(response.as_bytes());
+        }
+
+        debug_assert_eq!(bytes.len(), ProofOfEncryption::SERIALIZED_LEN);
+
+        bytes
+    }
+
+    /// Deserialise a proof of encryption of the attribute at `index` from
+    /// exactly [`ProofOfEncryption::SERIALIZED_LEN`] bytes.
+    pub(crate) fn from_bytes(bytes: &[u8], index: u16) -> Result<ProofOfEncryption, CredentialError> {
+        if bytes.len() != ProofOfEncryption::SERIALIZED_LEN {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
+
+        let chunk = |i: usize| &bytes[32 * i..32 * (i + 1)];
+        let mut responses: Vec<Scalar> = Vec::with_capacity(6);
+
+        for i in 8..14 {
+            responses.push(scalar_from_slice(chunk(i))?);
+        }
+
+        Ok(ProofOfEncryption {
+            proof: CompactProof { challenge: scalar_from_slice(chunk(7))?, responses },
+            public_key: SymmetricPublicKey { pk: point_from_slice(chunk(0))? },
+            ciphertext: Ciphertext { E1: point_from_slice(chunk(1))?, E2: point_from_slice(chunk(2))? },
+            index,
+            C_y_1: point_from_slice(chunk(3))?,
+            C_y_2: point_from_slice(chunk(4))?,
+            C_y_3: point_from_slice(chunk(5))?,
+            C_y_2_prime: point_from_slice(chunk(6))?,
+        })
+    }
+
     /// Prove in zero-knowledge that a ciphertext is a verifiable encryption of
     /// a plaintext w.r.t. a valid commitment to a secret symmetric key.
     ///
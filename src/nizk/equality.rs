@@ -0,0 +1,276 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2020 The Brave Authors
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Proofs that a hidden attribute of one credential is equal to a hidden
+//! attribute of another credential held by the same user, e.g. that a
+//! payment credential and an age credential refer to the same account.
+//!
+//! The credentials may be issued by the same or different issuers, under the
+//! same or different system parameters.  The user presents each credential
+//! as usual, and proves that the hidden scalar attribute \\(( m \\))
+//! committed to in the first presentation,
+//! \\(( C_{y,i} = G_{y,i} * z + G_{m,i} * m \\)), is the same as that
+//! committed to in the second,
+//! \\(( C'_{y,j} = G'_{y,j} * z' + G'_{m,j} * m \\)).  Since the
+//! commitments are binding, and each is bound to its credential by its
+//! presentation, this links the two credentials without revealing \\(( m \\)).
+//!
+//! An [`EqualityProof`] is detached from the presentations, which may be
+//! sent, and verified by their issuers, separately.
+//!
+//! # Warning
+//!
+//! The link is only meaningful if *both* presentations are valid.
+
+use curve25519_dalek::scalar::Scalar;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use zkp::CompactProof;
+use zkp::Transcript;
+use zkp::toolbox::SchnorrCS;
+use zkp::toolbox::prover::Prover;
+use zkp::toolbox::verifier::Verifier;
+
+use crate::amacs::Attribute;
+use crate::amacs::EncryptedAttribute;
+use crate::credential::AnonymousCredential;
+use crate::errors::CredentialError;
+use crate::nizk::presentation::ProofOfValidCredential;
+use crate::parameters::{IssuerParameters, SystemParameters};
+use crate::symmetric::Keypair as SymmetricKeypair;
+
+/// A proof that the hidden scalar attribute at `first_index` of one
+/// presentation is equal to that at `second_index` of another.
+#[derive(Clone)]
+pub struct EqualityProof {
+    first_index: u16,
+    second_index: u16,
+    proof: CompactProof,
+}
+
+/// Get the hidden scalar attribute at `index` of a `credential`.
+fn hidden_scalar(credential: &AnonymousCredential, index: usize) -> Result<&Scalar, CredentialError> {
+    match credential.attributes.get(index) {
+        Some(Attribute::SecretScalar(m)) => Ok(m),
+        _ => Err(CredentialError::BadAttribute),
+    }
+}
+
+/// Check that the attribute at `index` of a `presentation` is a hidden
+/// scalar.
+fn is_hidden_scalar(presentation: &ProofOfValidCredential, index: usize) -> bool {
+    matches!(presentation.attributes().get(index), Some(EncryptedAttribute::SecretScalar)) &&
+        index < presentation.C_y.len()
+}
+
+/// A hidden scalar attribute of a credential, which is to be proven equal
+/// to that of another credential with an [`EqualityProof`].
+#[derive(Clone, Copy)]
+pub struct LinkedAttribute<'a> {
+    /// The system parameters under which the credential was issued.
+    pub system_parameters: &'a SystemParameters,
+    /// The parameters of the credential's issuer.
+    pub issuer_parameters: &'a IssuerParameters,
+    /// The credential.
+    pub credential: &'a AnonymousCredential,
+    /// The symmetric keypair, if the credential has hidden group element
+    /// attributes.
+    pub keypair: Option<&'a SymmetricKeypair>,
+    /// The index of the attribute, which must be a hidden scalar, i.e. it
+    /// must have been hidden with `credential.hide_attribute()` beforehand.
+    pub index: usize,
+}
+
+/// The presentations of the first and second credentials of an
+/// [`EqualityProof`], and the proof itself.
+pub type LinkedPresentations = (ProofOfValidCredential, ProofOfValidCredential, EqualityProof);
+
+impl EqualityProof {
+    /// Present the `first` and `second` credentials, and prove that their
+    /// linked attributes are equal.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the presentations of the first and
+    /// second credentials and the `EqualityProof`, otherwise a
+    /// [`CredentialError`].
+    pub fn prove<C>(
+        first: LinkedAttribute,
+        second: LinkedAttribute,
+        csprng: &mut C,
+    ) -> Result<LinkedPresentations, CredentialError>
+    where
+        C: RngCore + CryptoRng,
+    {
+        let first_system_parameters = first.system_parameters;
+        let second_system_parameters = second.system_parameters;
+        let first_index = first.index;
+        let second_index = second.index;
+        let m_ = hidden_scalar(first.credential, first_index)?;
+
+        if hidden_scalar(second.credential, second_index)? != m_ {
+            return Err(CredentialError::BadAttribute);
+        }
+
+        let (first_presentation, z_) = ProofOfValidCredential::prove_with_commitment_nonce(
+            first_system_parameters, first.issuer_parameters, first.credential, first.keypair, csprng)?;
+        let (second_presentation, z__) = ProofOfValidCredential::prove_with_commitment_nonce(
+            second_system_parameters, second.issuer_parameters, second.credential, second.keypair, csprng)?;
+
+        let mut transcript = Transcript::new(b"2019/1416 anonymous credential");
+        let mut prover = Prover::new(b"2019/1416 attribute equality proof", &mut transcript);
+
+        // Feed the domain separators for the Camenisch-Stadler secrets into the protocol transcript.
+        let m  = prover.allocate_scalar(b"m", *m_);
+        let z  = prover.allocate_scalar(b"z", z_);
+        let z_ = prover.allocate_scalar(b"z'", z__);
+
+        // Feed in the domain separators and values for the publics into the transcript.
+        let (G_y_i, _) = prover.allocate_point(b"G_y", first_system_parameters.G_y[first_index]);
+        let (G_m_i, _) = prover.allocate_point(b"G_m", first_system_parameters.G_m[first_index]);
+        let (C_y_i, _) = prover.allocate_point(b"C_y", first_presentation.C_y[first_index]);
+        let (G_y_j, _) = prover.allocate_point(b"G_y'", second_system_parameters.G_y[second_index]);
+        let (G_m_j, _) = prover.allocate_point(b"G_m'", second_system_parameters.G_m[second_index]);
+        let (C_y_j, _) = prover.allocate_point(b"C_y'", second_presentation.C_y[second_index]);
+
+        // Constraint #1: C_y_i = G_y_i * z + G_m_i * m
+        prover.constrain(C_y_i, vec![(z, G_y_i), (m, G_m_i)]);
+
+        // Constraint #2: C'_y_j = G'_y_j * z' + G'_m_j * m
+        prover.constrain(C_y_j, vec![(z_, G_y_j), (m, G_m_j)]);
+
+        let proof = EqualityProof {
+            first_index: first_index as u16,
+            second_index: second_index as u16,
+            proof: prover.prove_compact(),
+        };
+
+        Ok((first_presentation, second_presentation, proof))
+    }
+
+    /// The index of the linked attribute of the first presentation.
+    pub fn first_index(&self) -> usize {
+        self.first_index as usize
+    }
+
+    /// The index of the linked attribute of the second presentation.
+    pub fn second_index(&self) -> usize {
+        self.second_index as usize
+    }
+
+    /// Verify that the `first` and `second` presentations share a common
+    /// hidden attribute, given the system parameters under which each
+    /// credential was issued.
+    ///
+    /// This does *not* verify the presentations themselves, which must each
+    /// be verified by their issuer.
+    pub fn verify(
+        &self,
+        first_system_parameters: &SystemParameters,
+        first: &ProofOfValidCredential,
+        second_system_parameters: &SystemParameters,
+        second: &ProofOfValidCredential,
+    ) -> Result<(), CredentialError>
+    {
+        let first_index = self.first_index as usize;
+        let second_index = self.second_index as usize;
+
+        if !is_hidden_scalar(first, first_index) || !is_hidden_scalar(second, second_index) {
+            return Err(CredentialError::BadAttribute);
+        }
+        if first_index >= first_system_parameters.G_m.len() || second_index >= second_system_parameters.G_m.len() {
+            return Err(CredentialError::WrongNumberOfAttributes);
+        }
+
+        let mut transcript = Transcript::new(b"2019/1416 anonymous credential");
+        let mut verifier = Verifier::new(b"2019/1416 attribute equality proof", &mut transcript);
+
+        // Feed the domain separators for the Camenisch-Stadler secrets into the protocol transcript.
+        let m  = verifier.allocate_scalar(b"m");
+        let z  = verifier.allocate_scalar(b"z");
+        let z_ = verifier.allocate_scalar(b"z'");
+
+        // Feed in the domain separators and values for the publics into the transcript.
+        let G_y_i = verifier.allocate_point(b"G_y", first_system_parameters.G_y[first_index].compress())?;
+        let G_m_i = verifier.allocate_point(b"G_m", first_system_parameters.G_m[first_index].compress())?;
+        let C_y_i = verifier.allocate_point(b"C_y", first.C_y[first_index].compress())?;
+        let G_y_j = verifier.allocate_point(b"G_y'", second_system_parameters.G_y[second_index].compress())?;
+        let G_m_j = verifier.allocate_point(b"G_m'", second_system_parameters.G_m[second_index].compress())?;
+        let C_y_j = verifier.allocate_point(b"C_y'", second.C_y[second_index].compress())?;
+
+        // Constraint #1: C_y_i = G_y_i * z + G_m_i * m
+        verifier.constrain(C_y_i, vec![(z, G_y_i), (m, G_m_i)]);
+
+        // Constraint #2: C'_y_j = G'_y_j * z' + G'_m_j * m
+        verifier.constrain(C_y_j, vec![(z_, G_y_j), (m, G_m_j)]);
+
+        verifier.verify_compact(&self.proof).or(Err(CredentialError::VerificationFailure))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::issuer::Issuer;
+    use crate::user::CredentialRequestConstructor;
+
+    use rand::thread_rng;
+
+    #[test]
+    fn payment_and_age_credentials_share_an_account() {
+        let mut rng = thread_rng();
+        let account = Scalar::random(&mut rng);
+        let system_parameters = SystemParameters::generate(&mut rng, 2).unwrap();
+        let issuer = Issuer::new(&system_parameters, &mut rng);
+        let (keypair, _) = SymmetricKeypair::generate(&system_parameters, &mut rng);
+
+        // A payment credential, with a hidden group element attribute.
+        let mut request = CredentialRequestConstructor::new(&system_parameters);
+        request.append_revealed_scalar(account);
+        request.append_plaintext(&b"card number 4111111111111111..".to_vec());
+
+        let issuance = issuer.issue(request.finish(), &mut rng).unwrap();
+        let mut payment = issuance.verify(&system_parameters, &issuer.issuer_parameters).unwrap();
+
+        // An age credential from the same issuer.
+        let mut request = CredentialRequestConstructor::new(&system_parameters);
+        request.append_revealed_scalar(Scalar::from(21u64));
+        request.append_revealed_scalar(account);
+
+        let issuance = issuer.issue(request.finish(), &mut rng).unwrap();
+        let mut age = issuance.verify(&system_parameters, &issuer.issuer_parameters).unwrap();
+
+        payment.hide_attribute(0).unwrap();
+        payment.hide_attribute(1).unwrap();
+        age.hide_attribute(1).unwrap();
+
+        let linked_payment = LinkedAttribute {
+            system_parameters: &system_parameters,
+            issuer_parameters: &issuer.issuer_parameters,
+            credential: &payment,
+            keypair: Some(&keypair),
+            index: 0,
+        };
+        let linked_age = LinkedAttribute { credential: &age, keypair: None, index: 1, ..linked_payment };
+        let (first, second, proof) = EqualityProof::prove(linked_payment, linked_age, &mut rng).unwrap();
+
+        assert!(issuer.verify(&first).is_ok());
+        assert!(issuer.verify(&second).is_ok());
+        assert!(proof.verify(&system_parameters, &first, &system_parameters, &second).is_ok());
+        assert!(proof.verify(&system_parameters, &second, &system_parameters, &first).is_err());
+
+        // The proof is bound to the presentations it was made with.
+        let other = age.show(&system_parameters, &issuer.issuer_parameters, None, &mut rng).unwrap();
+
+        assert!(proof.verify(&system_parameters, &first, &system_parameters, &other).is_err());
+    }
+}
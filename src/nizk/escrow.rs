@@ -0,0 +1,289 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2020 The Brave Authors
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Escrow of hidden group element attributes to a designated auditor.
+//!
+//! A hidden group element attribute \\(( M_1 \\)) in slot \\(( i \\)) is
+//! verifiably encrypted under the user's symmetric key, as proven in its
+//! [`ProofOfEncryption`], against the commitments
+//! \\(( C_{y_1} = G_{y_1} * z + M_1 \\)) and
+//! \\(( C_{y_3} = G_{y_3} * z + G_m_i * m_3 \\)).  To escrow it, the user
+//! additionally ElGamal-encrypts it under an auditor's public key
+//! \\(( A = G * a \\)) as \\(( (E_1, E_2) = (G * r, A * r + M_1) \\)), and
+//! proves that
+//!
+//! \\(( C_{y_3} = G_{y_3} * z + G_m_i * m_3 \\)),
+//! \\(( E_1 = G * r \\)), and
+//! \\(( C_{y_1} - E_2 = G_{y_1} * z - A * r \\)),
+//!
+//! where \\(( C_{y_3} \\)) binds \\(( z \\)), and so both ciphertexts are of
+//! the same plaintext.  Since \\(( r \\)) is fresh for every presentation,
+//! the auditor's ciphertexts are unlinkable by the issuer, but the auditor
+//! may decrypt any of them.
+//!
+//! [`ProofOfEncryption`]: crate::nizk::encryption::ProofOfEncryption
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use zeroize::Zeroize;
+
+use zkp::CompactProof;
+use zkp::Transcript;
+use zkp::toolbox::SchnorrCS;
+use zkp::toolbox::prover::Prover;
+use zkp::toolbox::verifier::Verifier;
+
+use crate::errors::CredentialError;
+use crate::parameters::SystemParameters;
+use crate::symmetric::Plaintext;
+
+/// An auditor's public key, \\(( A = G * a \\)).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct AuditorPublicKey(pub(crate) RistrettoPoint);
+
+/// An auditor's keypair, with which it may decrypt escrowed attributes.
+pub struct AuditorKeypair {
+    secret: Scalar,
+    /// The public portion of this keypair.
+    pub public: AuditorPublicKey,
+}
+
+/// Overwrite the auditor's secret key when it drops out of scope.
+impl Drop for AuditorKeypair {
+    fn drop(&mut self) {
+        self.secret.zeroize();
+    }
+}
+
+/// An ElGamal encryption of a hidden group element attribute under an
+/// [`AuditorPublicKey`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct AuditCiphertext {
+    pub(crate) E_1: RistrettoPoint,
+    pub(crate) E_2: RistrettoPoint,
+}
+
+impl AuditorKeypair {
+    /// Generate a new auditor keypair for use with the `system_parameters`.
+    pub fn generate<R>(system_parameters: &SystemParameters, csprng: &mut R) -> AuditorKeypair
+    where
+        R: RngCore + CryptoRng,
+    {
+        let secret = Scalar::random(csprng);
+        let public = AuditorPublicKey(system_parameters.G * secret);
+
+        AuditorKeypair { secret, public }
+    }
+
+    /// Decrypt an escrowed attribute.
+    ///
+    /// # Returns
+    ///
+    /// The group element \\(( M_1 \\)) of the attribute's [`Plaintext`],
+    /// which for plaintexts made from bytes may be decoded with
+    /// `<[u8; 30]>::from(&Plaintext::from(&M_1))`.
+    pub fn decrypt(&self, ciphertext: &AuditCiphertext) -> RistrettoPoint {
+        ciphertext.E_2 - (ciphertext.E_1 * self.secret)
+    }
+}
+
+/// A request to escrow the hidden group element attribute at `index` to the
+/// `auditor`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct EscrowStatement {
+    /// The index of the attribute, which must be hidden upon presentation.
+    pub index: usize,
+    /// The auditor to whom the attribute is escrowed.
+    pub auditor: AuditorPublicKey,
+}
+
+impl EscrowStatement {
+    /// Bind this statement into a `transcript`.
+    fn commit(&self, transcript: &mut Transcript) {
+        transcript.append_message(b"escrow index", &(self.index as u32).to_le_bytes());
+    }
+}
+
+/// A non-interactive zero-knowledge proof that an [`AuditCiphertext`]
+/// encrypts the same hidden group element attribute as that verifiably
+/// encrypted under the user's symmetric key.
+#[derive(Clone)]
+pub struct ProofOfEscrow {
+    statement: EscrowStatement,
+    ciphertext: AuditCiphertext,
+    proof: CompactProof,
+}
+
+impl ProofOfEscrow {
+    /// Create a [`ProofOfEscrow`] for the `plaintext`, committed to in slot
+    /// `statement.index` with the presentation nonce `z`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the proof, otherwise a
+    /// [`CredentialError::BadAttribute`] if the statement's index is out of
+    /// range.
+    pub(crate) fn prove<C>(
+        system_parameters: &SystemParameters,
+        statement: &EscrowStatement,
+        plaintext: &Plaintext,
+        z: &Scalar,
+        csprng: &mut C,
+    ) -> Result<ProofOfEscrow, CredentialError>
+    where
+        C: RngCore + CryptoRng,
+    {
+        if statement.index >= system_parameters.NUMBER_OF_ATTRIBUTES as usize {
+            return Err(CredentialError::BadAttribute);
+        }
+
+        let mut r_ = Scalar::random(csprng);
+        let A_ = statement.auditor.0;
+        let ciphertext = AuditCiphertext {
+            E_1: system_parameters.G * r_,
+            E_2: (A_ * r_) + plaintext.M1,
+        };
+        let C_y_1_ = (system_parameters.G_y[0] * z) + plaintext.M1;
+        let C_y_3_ = (system_parameters.G_y[2] * z) + (system_parameters.G_m[statement.index] * plaintext.m3);
+
+        let mut transcript = Transcript::new(b"2019/1416 anonymous credential");
+
+        statement.commit(&mut transcript);
+
+        let mut prover = Prover::new(b"2019/1416 escrow proof", &mut transcript);
+
+        // Feed the domain separators for the Camenisch-Stadler secrets into the protocol transcript.
+        let z  = prover.allocate_scalar(b"z",  *z);
+        let m3 = prover.allocate_scalar(b"m3", plaintext.m3);
+        let r  = prover.allocate_scalar(b"r",  r_);
+
+        // Feed in the domain separators and values for the publics into the transcript.
+        let (G, _)               = prover.allocate_point(b"G",         system_parameters.G);
+        let (minus_A, _)         = prover.allocate_point(b"-A",        -A_);
+        let (G_y_1, _)           = prover.allocate_point(b"G_y_1",     system_parameters.G_y[0]);
+        let (G_y_3, _)           = prover.allocate_point(b"G_y_3",     system_parameters.G_y[2]);
+        let (G_m_3, _)           = prover.allocate_point(b"G_m_3",     system_parameters.G_m[statement.index]);
+        let (C_y_3, _)           = prover.allocate_point(b"C_y_3",     C_y_3_);
+        let (E_1, _)             = prover.allocate_point(b"E_1",       ciphertext.E_1);
+        let (C_y_1_minus_E_2, _) = prover.allocate_point(b"C_y_1-E_2", C_y_1_ - ciphertext.E_2);
+
+        // Constraint #1: The commitment to m3 binds the nonce z.
+        //                C_y_3 = G_y_3 * z + G_m_3 * m3
+        prover.constrain(C_y_3, vec![(z, G_y_3), (m3, G_m_3)]);
+
+        // Constraint #2: The ElGamal ciphertext is well formed and encrypts the committed plaintext.
+        //                E_1 = G * r
+        //                C_y_1 - E_2 = G_y_1 * z - A * r
+        prover.constrain(E_1, vec![(r, G)]);
+        prover.constrain(C_y_1_minus_E_2, vec![(z, G_y_1), (r, minus_A)]);
+
+        let proof = prover.prove_compact();
+
+        r_.zeroize();
+
+        Ok(ProofOfEscrow { statement: *statement, ciphertext, proof })
+    }
+
+    /// The statement which this proof proves.
+    pub fn statement(&self) -> &EscrowStatement {
+        &self.statement
+    }
+
+    /// The escrowed attribute, encrypted to the auditor.
+    pub fn ciphertext(&self) -> &AuditCiphertext {
+        &self.ciphertext
+    }
+
+    /// Verify this [`ProofOfEscrow`] against the commitments `C_y_1` and
+    /// `C_y_3` of the [`ProofOfEncryption`] for the attribute in slot
+    /// `self.statement().index`.
+    ///
+    /// [`ProofOfEncryption`]: crate::nizk::encryption::ProofOfEncryption
+    pub(crate) fn verify(
+        &self,
+        system_parameters: &SystemParameters,
+        C_y_1: &RistrettoPoint,
+        C_y_3: &RistrettoPoint,
+    ) -> Result<(), CredentialError>
+    {
+        let statement = &self.statement;
+
+        if statement.index >= system_parameters.NUMBER_OF_ATTRIBUTES as usize {
+            return Err(CredentialError::VerificationFailure);
+        }
+
+        let mut transcript = Transcript::new(b"2019/1416 anonymous credential");
+
+        statement.commit(&mut transcript);
+
+        let mut verifier = Verifier::new(b"2019/1416 escrow proof", &mut transcript);
+
+        // Feed the domain separators for the Camenisch-Stadler secrets into the protocol transcript.
+        let z  = verifier.allocate_scalar(b"z");
+        let m3 = verifier.allocate_scalar(b"m3");
+        let r  = verifier.allocate_scalar(b"r");
+
+        // Feed in the domain separators and values for the publics into the transcript.
+        let G               = verifier.allocate_point(b"G",         system_parameters.G.compress())?;
+        let minus_A         = verifier.allocate_point(b"-A",        (-statement.auditor.0).compress())?;
+        let G_y_1           = verifier.allocate_point(b"G_y_1",     system_parameters.G_y[0].compress())?;
+        let G_y_3           = verifier.allocate_point(b"G_y_3",     system_parameters.G_y[2].compress())?;
+        let G_m_3           = verifier.allocate_point(b"G_m_3",     system_parameters.G_m[statement.index].compress())?;
+        let C_y_3           = verifier.allocate_point(b"C_y_3",     C_y_3.compress())?;
+        let E_1             = verifier.allocate_point(b"E_1",       self.ciphertext.E_1.compress())?;
+        let C_y_1_minus_E_2 = verifier.allocate_point(b"C_y_1-E_2", (C_y_1 - self.ciphertext.E_2).compress())?;
+
+        // Constraint #1: The commitment to m3 binds the nonce z.
+        //                C_y_3 = G_y_3 * z + G_m_3 * m3
+        verifier.constrain(C_y_3, vec![(z, G_y_3), (m3, G_m_3)]);
+
+        // Constraint #2: The ElGamal ciphertext is well formed and encrypts the committed plaintext.
+        //                E_1 = G * r
+        //                C_y_1 - E_2 = G_y_1 * z - A * r
+        verifier.constrain(E_1, vec![(r, G)]);
+        verifier.constrain(C_y_1_minus_E_2, vec![(z, G_y_1), (r, minus_A)]);
+
+        verifier.verify_compact(&self.proof).or(Err(CredentialError::VerificationFailure))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    #[test]
+    fn escrow_proof() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 3).unwrap();
+        let auditor = AuditorKeypair::generate(&system_parameters, &mut rng);
+        let statement = EscrowStatement { index: 1, auditor: auditor.public };
+        let plaintext: Plaintext = b"This is a tsunami alert test..".into();
+        let z = Scalar::random(&mut rng);
+
+        let C_y_1 = (system_parameters.G_y[0] * z) + plaintext.M1;
+        let C_y_3 = (system_parameters.G_y[2] * z) + (system_parameters.G_m[1] * plaintext.m3);
+
+        let proof = ProofOfEscrow::prove(&system_parameters, &statement, &plaintext, &z, &mut rng).unwrap();
+
+        assert!(proof.verify(&system_parameters, &C_y_1, &C_y_3).is_ok());
+        assert_eq!(auditor.decrypt(proof.ciphertext()), plaintext.M1);
+
+        // The proof doesn't verify against a commitment to another plaintext.
+        let other: Plaintext = b"This is not a tsunami alert...".into();
+        let C_y_1 = (system_parameters.G_y[0] * z) + other.M1;
+
+        assert!(proof.verify(&system_parameters, &C_y_1, &C_y_3).is_err());
+    }
+}
@@ -0,0 +1,164 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2020 The Brave Authors
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Presentations of two credentials, possibly from different issuers under
+//! different system parameters, which share a common hidden attribute.
+//!
+//! This enables federated deployments, in which a service accepts a
+//! credential from its own issuer only if it is bound to the same hidden
+//! attribute, e.g. a user id or pseudonym, as a credential issued elsewhere.
+//!
+//! The user presents each credential as usual, along with an
+//! [`EqualityProof`] that the hidden scalar attribute committed to in the
+//! first presentation is the same as that committed to in the second.
+//!
+//! # Warning
+//!
+//! The link is only meaningful if *both* presentations are valid.  Since
+//! verification of a presentation requires its issuer's secret key, a service
+//! which verifies one presentation with [`Issuer::verify`] must learn, e.g.
+//! from the other issuer, that the other presentation verifies too.
+//!
+//! [`Issuer::verify`]: crate::issuer::Issuer::verify
+//! [`EqualityProof`]: crate::nizk::equality::EqualityProof
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use crate::errors::CredentialError;
+use crate::nizk::equality::EqualityProof;
+use crate::nizk::equality::LinkedAttribute;
+use crate::nizk::presentation::ProofOfValidCredential;
+use crate::parameters::SystemParameters;
+
+/// Presentations of two credentials, with a proof that the hidden scalar
+/// attribute at some index of the first is equal to that at some index of
+/// the second.
+pub struct FederatedPresentation {
+    first: ProofOfValidCredential,
+    second: ProofOfValidCredential,
+    equality: EqualityProof,
+}
+
+impl FederatedPresentation {
+    /// Create a [`FederatedPresentation`] of the `first` and `second`
+    /// credentials, whose linked attributes must be equal hidden scalars.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the `FederatedPresentation`, otherwise
+    /// a [`CredentialError`].
+    pub fn prove<C>(
+        first: LinkedAttribute,
+        second: LinkedAttribute,
+        csprng: &mut C,
+    ) -> Result<FederatedPresentation, CredentialError>
+    where
+        C: RngCore + CryptoRng,
+    {
+        let (first, second, equality) = EqualityProof::prove(first, second, csprng)?;
+
+        Ok(FederatedPresentation { first, second, equality })
+    }
+
+    /// The presentation of the first credential, to be verified by its issuer.
+    pub fn first(&self) -> &ProofOfValidCredential {
+        &self.first
+    }
+
+    /// The presentation of the second credential, to be verified by its issuer.
+    pub fn second(&self) -> &ProofOfValidCredential {
+        &self.second
+    }
+
+    /// The proof that the two presentations share a common hidden attribute.
+    pub fn equality(&self) -> &EqualityProof {
+        &self.equality
+    }
+
+    /// Verify that the two presentations share a common hidden attribute,
+    /// given the system parameters under which each credential was issued.
+    ///
+    /// This does *not* verify the presentations themselves, which must each
+    /// be verified by their issuer.
+    pub fn verify_link(
+        &self,
+        first_system_parameters: &SystemParameters,
+        second_system_parameters: &SystemParameters,
+    ) -> Result<(), CredentialError>
+    {
+        self.equality.verify(first_system_parameters, &self.first, second_system_parameters, &self.second)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use curve25519_dalek::scalar::Scalar;
+
+    use crate::issuer::Issuer;
+    use crate::user::CredentialRequestConstructor;
+
+    use rand::thread_rng;
+
+    #[test]
+    fn federated_credentials_share_hidden_attribute() {
+        let mut rng = thread_rng();
+        let user_id = Scalar::random(&mut rng);
+        let first_system_parameters = SystemParameters::generate(&mut rng, 2).unwrap();
+        let second_system_parameters = SystemParameters::generate(&mut rng, 3).unwrap();
+        let first_issuer = Issuer::new(&first_system_parameters, &mut rng);
+        let second_issuer = Issuer::new(&second_system_parameters, &mut rng);
+
+        let mut request = CredentialRequestConstructor::new(&first_system_parameters);
+        request.append_revealed_scalar(user_id);
+        request.append_revealed_scalar(Scalar::random(&mut rng));
+
+        let issuance = first_issuer.issue(request.finish(), &mut rng).unwrap();
+        let mut first = issuance.verify(&first_system_parameters, &first_issuer.issuer_parameters).unwrap();
+
+        let mut request = CredentialRequestConstructor::new(&second_system_parameters);
+        request.append_revealed_scalar(Scalar::random(&mut rng));
+        request.append_revealed_scalar(Scalar::random(&mut rng));
+        request.append_revealed_scalar(user_id);
+
+        let issuance = second_issuer.issue(request.finish(), &mut rng).unwrap();
+        let mut second = issuance.verify(&second_system_parameters, &second_issuer.issuer_parameters).unwrap();
+
+        first.hide_attribute(0).unwrap();
+        second.hide_attribute(1).unwrap();
+        second.hide_attribute(2).unwrap();
+
+        let linked_first = LinkedAttribute {
+            system_parameters: &first_system_parameters,
+            issuer_parameters: &first_issuer.issuer_parameters,
+            credential: &first,
+            keypair: None,
+            index: 0,
+        };
+        let linked_second = LinkedAttribute {
+            system_parameters: &second_system_parameters,
+            issuer_parameters: &second_issuer.issuer_parameters,
+            credential: &second,
+            keypair: None,
+            index: 2,
+        };
+        let presentation = FederatedPresentation::prove(linked_first, linked_second, &mut rng).unwrap();
+
+        assert!(first_issuer.verify(presentation.first()).is_ok());
+        assert!(second_issuer.verify(presentation.second()).is_ok());
+        assert!(presentation.verify_link(&first_system_parameters, &second_system_parameters).is_ok());
+        assert!(presentation.verify_link(&second_system_parameters, &first_system_parameters).is_err());
+
+        // Different hidden attributes cannot be linked.
+        assert!(FederatedPresentation::prove(linked_first, LinkedAttribute { index: 1, ..linked_second }, &mut rng)
+                .is_err());
+    }
+}
@@ -0,0 +1,382 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2020 The Brave Authors
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Interactive presentations, in which the verifier supplies the challenge.
+//!
+//! Rather than deriving the challenge of the proof of knowledge of a valid
+//! credential from a Fiat-Shamir transcript, the presentation is split into
+//! the three moves of the underlying sigma protocol:
+//!
+//! 1. The user commits to their credential and to blindings of its secrets
+//!    with [`AnonymousCredential::show_interactive`], keeping the
+//!    [`InteractivePresentation`] and sending the [`PresentationCommitment`]
+//!    to the issuer.
+//! 2. The issuer chooses a fresh random [`PresentationChallenge`] and sends
+//!    it to the user.
+//! 3. The user answers it with [`InteractivePresentation::respond`], and
+//!    sends the [`PresentationResponse`] to the issuer, who checks it with
+//!    [`Issuer::verify_interactive`].
+//!
+//! Since the challenge is chosen by the issuer only after it has seen the
+//! commitment, a presentation cannot have been computed ahead of time, and
+//! so is fresh without relying on nonces or timestamps.  Conversely, the
+//! transcript of an interactive presentation could have been simulated by
+//! anyone who knew the challenge beforehand, and so it convinces nobody but
+//! the issuer.
+//!
+//! # Warning
+//!
+//! The issuer must choose a new challenge for every commitment, and must not
+//! reveal it before receiving the commitment.  Likewise, answering two
+//! challenges for the same commitment reveals the user's secrets, which is
+//! why responding consumes the [`InteractivePresentation`].
+//!
+//! Presentations with additional statements, e.g. of ranges or nullifiers,
+//! cannot yet be made interactively.
+//!
+//! [`AnonymousCredential::show_interactive`]: crate::credential::AnonymousCredential::show_interactive
+//! [`Issuer::verify_interactive`]: crate::issuer::Issuer::verify_interactive
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use zeroize::Zeroize;
+
+use crate::amacs::Attribute;
+use crate::amacs::AttributeKind;
+use crate::amacs::EncryptedAttribute;
+use crate::credential::AnonymousCredential;
+use crate::errors::CredentialError;
+use crate::issuer::Issuer;
+use crate::nizk::designated::LinearRelation;
+use crate::nizk::encryption::ProofOfEncryption;
+use crate::nizk::presentation::attribute_kind;
+use crate::nizk::presentation::designated_relation;
+use crate::nizk::presentation::encrypt_attributes;
+use crate::nizk::presentation::Commitments;
+use crate::nizk::presentation::PresentationPublics;
+use crate::parameters::IssuerParameters;
+use crate::parameters::SystemParameters;
+use crate::symmetric::Keypair as SymmetricKeypair;
+
+/// The first message of an interactive presentation, sent from the user to
+/// the issuer: the commitments to the credential, as in a
+/// [`ProofOfValidCredential`](crate::nizk::presentation::ProofOfValidCredential),
+/// and to the blindings of its secrets.
+pub struct PresentationCommitment {
+    pub(crate) proofs_of_encryption: Vec<(u16, ProofOfEncryption)>,
+    pub(crate) encrypted_attributes: Vec<EncryptedAttribute>,
+    pub(crate) C_x_0: RistrettoPoint,
+    pub(crate) C_x_1: RistrettoPoint,
+    pub(crate) C_V:   RistrettoPoint,
+    pub(crate) C_y:   Vec<RistrettoPoint>,
+    pub(crate) blinding_commitments: Vec<RistrettoPoint>,
+}
+
+/// The second message of an interactive presentation, a random challenge
+/// chosen by the issuer.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PresentationChallenge(pub(crate) Scalar);
+
+impl PresentationChallenge {
+    /// Choose a fresh challenge, which must only be used for one
+    /// [`PresentationCommitment`].
+    pub fn generate<C>(csprng: &mut C) -> PresentationChallenge
+    where
+        C: RngCore + CryptoRng,
+    {
+        PresentationChallenge(Scalar::random(csprng))
+    }
+
+    /// Serialise this challenge.
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0.to_bytes()
+    }
+
+    /// Deserialise a challenge previously serialised with
+    /// [`PresentationChallenge::to_bytes`].
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the challenge, otherwise a
+    /// [`CredentialError::ScalarFormatError`] if the bytes aren't a
+    /// canonical scalar.
+    pub fn from_bytes(bytes: &[u8; 32]) -> Result<PresentationChallenge, CredentialError> {
+        match Scalar::from_canonical_bytes(*bytes) {
+            Some(challenge) => Ok(PresentationChallenge(challenge)),
+            None            => Err(CredentialError::ScalarFormatError),
+        }
+    }
+}
+
+/// The third message of an interactive presentation, sent from the user to
+/// the issuer: the responses to the issuer's [`PresentationChallenge`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PresentationResponse {
+    pub(crate) responses: Vec<Scalar>,
+}
+
+/// The user's state during an interactive presentation, between sending
+/// their [`PresentationCommitment`] and receiving the issuer's
+/// [`PresentationChallenge`].
+pub struct InteractivePresentation {
+    witness: Vec<Scalar>,
+    blindings: Vec<Scalar>,
+}
+
+/// Overwrite the secrets and blinding factors when they drop out of scope.
+impl Drop for InteractivePresentation {
+    fn drop(&mut self) {
+        for scalar in self.witness.iter_mut().chain(self.blindings.iter_mut()) {
+            scalar.zeroize();
+        }
+    }
+}
+
+impl InteractivePresentation {
+    /// Commit to a presentation of a `credential`, hiding its attributes as
+    /// they are currently marked.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the user's state, to be kept, and the
+    /// [`PresentationCommitment`], to be sent to the issuer, otherwise a
+    /// [`CredentialError::NoSymmetricKey`] if the credential has hidden group
+    /// element attributes and there is no `keypair`, or another
+    /// [`CredentialError`] if the credential wasn't issued under the
+    /// `system_parameters`.
+    pub(crate) fn commit<C>(
+        system_parameters: &SystemParameters,
+        issuer_parameters: &IssuerParameters,
+        credential: &AnonymousCredential,
+        keypair: Option<&SymmetricKeypair>,
+        csprng: &mut C,
+    ) -> Result<(InteractivePresentation, PresentationCommitment), CredentialError>
+    where
+        C: RngCore + CryptoRng,
+    {
+        InteractivePresentation::commit_to_relation(system_parameters, issuer_parameters, credential, keypair, csprng)
+            .map(|(state, commitment, _relation)| (state, commitment))
+    }
+
+    /// Commit to a presentation of a `credential`, as in
+    /// [`InteractivePresentation::commit`], additionally returning the
+    /// statement to which the commitment was made, so that a challenge may
+    /// be derived from it.
+    pub(crate) fn commit_to_relation<C>(
+        system_parameters: &SystemParameters,
+        issuer_parameters: &IssuerParameters,
+        credential: &AnonymousCredential,
+        keypair: Option<&SymmetricKeypair>,
+        csprng: &mut C,
+    ) -> Result<(InteractivePresentation, PresentationCommitment, LinearRelation), CredentialError>
+    where
+        C: RngCore + CryptoRng,
+    {
+        if keypair.is_none() && credential.attributes.iter().any(|a| matches!(a, Attribute::SecretPoint(_))) {
+            return Err(CredentialError::NoSymmetricKey);
+        }
+
+        let commitments = Commitments::new(system_parameters, issuer_parameters, credential, csprng)?;
+
+        // The secrets, in the order in which the statement allocates them.
+        let mut witness = vec![commitments.z, (-credential.amac.t * commitments.z).reduce(), credential.amac.t];
+
+        for attribute in credential.attributes.iter() {
+            if let Attribute::SecretScalar(m) = attribute {
+                witness.push(*m);
+            }
+        }
+
+        let kinds: Vec<AttributeKind> = credential.attributes.iter().map(attribute_kind).collect();
+        let publics = PresentationPublics {
+            I: &issuer_parameters.I,
+            Z: &commitments.Z,
+            C_x_0: &commitments.C_x_0,
+            C_x_1: &commitments.C_x_1,
+            C_y: &commitments.C_y,
+            kinds: &kinds,
+        };
+        let relation = designated_relation(system_parameters, &publics, &[]);
+
+        // Commit to blindings of the secrets, as in the first move of a sigma protocol.
+        let blindings: Vec<Scalar> = witness.iter().map(|_| Scalar::random(csprng)).collect();
+        let blinding_commitments = relation.commitments(&blindings, &Scalar::zero());
+
+        let (encrypted_attributes, proofs_of_encryption) = encrypt_attributes(system_parameters, credential,
+                                                                              keypair, &commitments.z);
+
+        Ok((InteractivePresentation { witness, blindings },
+            PresentationCommitment {
+                proofs_of_encryption,
+                encrypted_attributes,
+                C_x_0: commitments.C_x_0,
+                C_x_1: commitments.C_x_1,
+                C_V: commitments.C_V,
+                C_y: commitments.C_y.clone(),
+                blinding_commitments,
+            },
+            relation))
+    }
+
+    /// Answer the issuer's `challenge`, consuming this state so that no
+    /// other challenge may be answered for the same commitment.
+    pub fn respond(self, challenge: &PresentationChallenge) -> PresentationResponse {
+        let responses = self.witness.iter().zip(self.blindings.iter()).map(|(w, b)| b + challenge.0 * w).collect();
+
+        PresentationResponse { responses }
+    }
+}
+
+impl PresentationCommitment {
+    /// The attributes of the credential as presented, i.e. with hidden
+    /// attributes elided.
+    pub fn attributes(&self) -> &[EncryptedAttribute] {
+        &self.encrypted_attributes[..]
+    }
+
+    /// Verify the user's `response` to our `challenge` for this commitment.
+    pub(crate) fn verify(
+        &self,
+        issuer: &Issuer,
+        challenge: &PresentationChallenge,
+        response: &PresentationResponse,
+    ) -> Result<(), CredentialError>
+    {
+        let NUMBER_OF_ATTRIBUTES = issuer.system_parameters.NUMBER_OF_ATTRIBUTES as usize;
+
+        if self.encrypted_attributes.len() != NUMBER_OF_ATTRIBUTES || self.C_y.len() != NUMBER_OF_ATTRIBUTES {
+            return Err(CredentialError::WrongNumberOfAttributes);
+        }
+
+        // Recompute the prover's Z value, as for a non-interactive presentation.
+        let mut Z = self.C_V - issuer.amacs_key.W - (self.C_x_0 * issuer.amacs_key.x_0) - (self.C_x_1 * issuer.amacs_key.x_1);
+
+        for (i, attribute) in self.encrypted_attributes.iter().enumerate() {
+            let x = match attribute {
+                EncryptedAttribute::PublicScalar(m_i) => self.C_y[i] + (issuer.system_parameters.G_m[i] * m_i),
+                EncryptedAttribute::SecretScalar      => self.C_y[i],
+                EncryptedAttribute::PublicPoint(M_i)  => self.C_y[i] + M_i,
+                EncryptedAttribute::SecretPoint       => self.C_y[i],
+            };
+            Z -= x * issuer.amacs_key.y[i];
+        }
+
+        let kinds: Vec<AttributeKind> = self.encrypted_attributes.iter().map(|a| a.kind()).collect();
+        let publics = PresentationPublics {
+            I: &issuer.issuer_parameters.I,
+            Z: &Z,
+            C_x_0: &self.C_x_0,
+            C_x_1: &self.C_x_1,
+            C_y: &self.C_y,
+            kinds: &kinds,
+        };
+        let relation = designated_relation(&issuer.system_parameters, &publics, &[]);
+
+        if response.responses.len() != relation.number_of_scalars() {
+            return Err(CredentialError::VerificationFailure);
+        }
+
+        // Check that the responses open the blinding commitments under the challenge.
+        if relation.commitments(&response.responses, &challenge.0) != self.blinding_commitments {
+            return Err(CredentialError::VerificationFailure);
+        }
+
+        // Check the proofs of correct encryptions and fail if any cannot be verified.
+        for (_i, proof_of_encryption) in self.proofs_of_encryption.iter() {
+            proof_of_encryption.verify(&issuer.system_parameters)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::user::CredentialRequestConstructor;
+
+    use rand::thread_rng;
+
+    #[test]
+    fn interactive_presentation() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 3).unwrap();
+        let issuer = Issuer::new(&system_parameters, &mut rng);
+        let mut request = CredentialRequestConstructor::new(&system_parameters);
+
+        request.append_revealed_scalar(Scalar::random(&mut rng));
+        request.append_revealed_scalar(Scalar::random(&mut rng));
+        request.append_plaintext(&b"This is a tsunami alert test.."[..].to_vec());
+
+        let issuance = issuer.issue(request.finish(), &mut rng).unwrap();
+        let mut credential = issuance.verify(&system_parameters, &issuer.issuer_parameters).unwrap();
+        let (keypair, _) = SymmetricKeypair::generate(&system_parameters, &mut rng);
+
+        credential.hide_attribute(1).unwrap();
+        credential.hide_attribute(2).unwrap();
+
+        let (state, commitment) = credential.show_interactive(&system_parameters, &issuer.issuer_parameters,
+                                                              Some(&keypair), &mut rng).unwrap();
+        let challenge = PresentationChallenge::generate(&mut rng);
+        let response = state.respond(&challenge);
+
+        assert!(matches!(commitment.attributes()[1], EncryptedAttribute::SecretScalar));
+        assert!(issuer.verify_interactive(&commitment, &challenge, &response).is_ok());
+
+        // The response only answers the challenge for which it was made.
+        let other = PresentationChallenge::generate(&mut rng);
+
+        assert_eq!(issuer.verify_interactive(&commitment, &other, &response), Err(CredentialError::VerificationFailure));
+
+        // Challenges survive serialisation.
+        let bytes = challenge.to_bytes();
+
+        assert_eq!(PresentationChallenge::from_bytes(&bytes), Ok(challenge));
+        assert!(PresentationChallenge::from_bytes(&[0xff; 32]).is_err());
+
+        // Nor does it answer the challenge for another commitment.
+        let (state, fresh) = credential.show_interactive(&system_parameters, &issuer.issuer_parameters,
+                                                         Some(&keypair), &mut rng).unwrap();
+
+        assert!(issuer.verify_interactive(&fresh, &challenge, &response).is_err());
+        assert!(issuer.verify_interactive(&fresh, &challenge, &state.respond(&challenge)).is_ok());
+
+        // Malformed responses are rejected.
+        let mut truncated = response.clone();
+        truncated.responses.pop();
+
+        assert_eq!(issuer.verify_interactive(&commitment, &challenge, &truncated), Err(CredentialError::VerificationFailure));
+
+        let mut tampered = response.clone();
+        tampered.responses[0] += Scalar::one();
+
+        assert_eq!(issuer.verify_interactive(&commitment, &challenge, &tampered), Err(CredentialError::VerificationFailure));
+
+        // Hidden group element attributes require a symmetric keypair.
+        assert_eq!(credential.show_interactive(&system_parameters, &issuer.issuer_parameters, None, &mut rng).err(),
+                   Some(CredentialError::NoSymmetricKey));
+
+        // A credential from another issuer doesn't verify.
+        let other_issuer = Issuer::new(&system_parameters, &mut rng);
+        let (state, commitment) = credential.show_interactive(&system_parameters, &issuer.issuer_parameters,
+                                                              Some(&keypair), &mut rng).unwrap();
+
+        assert!(other_issuer.verify_interactive(&commitment, &challenge, &state.respond(&challenge)).is_err());
+    }
+}
@@ -14,8 +14,14 @@ use alloc::vec::Vec;
 #[cfg(all(not(feature = "alloc"), feature = "std"))]
 use std::vec::Vec;
 
+use curve25519_dalek::ristretto::RistrettoPoint;
 use curve25519_dalek::scalar::Scalar;
 
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use zeroize::Zeroize;
+
 use zkp::CompactProof;
 use zkp::Transcript;
 // XXX do we want/need batch proof verification?
@@ -24,10 +30,14 @@ use zkp::toolbox::SchnorrCS;
 use zkp::toolbox::prover::Prover;
 use zkp::toolbox::verifier::Verifier;
 
+use crate::amacs::Amac;
+use crate::amacs::Attribute;
 use crate::amacs::Messages;
+use crate::amacs::SecretKey;
 use crate::credential::AnonymousCredential;
 use crate::errors::CredentialError;
 use crate::issuer::Issuer;
+use crate::nizk::presentation::scalar_from_slice;
 use crate::parameters::{IssuerParameters, SystemParameters};
 
 /// A non-interactive zero-knowledge proof demonstrating knowledge of the
@@ -36,27 +46,84 @@ use crate::parameters::{IssuerParameters, SystemParameters};
 pub struct ProofOfIssuance(CompactProof);
 
 impl ProofOfIssuance {
+    /// Serialise this proof as its challenge followed by its responses.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = Vec::with_capacity(32 * (1 + self.0.responses.len()));
+
+        bytes.extend(self.0.challenge.as_bytes());
+
+        for response in self.0.responses.iter() {
+            bytes.extend(response.as_bytes());
+        }
+        bytes
+    }
+
+    /// Deserialise a proof for a credential with `number_of_attributes`,
+    /// i.e. with one response for each of \\(( w, w', x_0, x_1 \\)), each
+    /// \\(( y_i \\)), and the constant.
+    pub fn from_bytes(bytes: &[u8], number_of_attributes: usize) -> Result<ProofOfIssuance, CredentialError> {
+        if bytes.len() != 32 * (6 + number_of_attributes) {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
+
+        let challenge = scalar_from_slice(&bytes[0..32])?;
+        let responses = bytes[32..].chunks(32).map(scalar_from_slice).collect::<Result<Vec<Scalar>, _>>()?;
+
+        Ok(ProofOfIssuance(CompactProof { challenge, responses }))
+    }
+
     /// Create a [`ProofOfIssuance`].
     pub(crate) fn prove(
         issuer: &Issuer,
         credential: &AnonymousCredential,
     ) -> ProofOfIssuance
+    {
+        let mut transcript = Transcript::new(b"2019/1416 anonymous credential");
+
+        ProofOfIssuance::prove_with_transcript(issuer, credential, &mut transcript)
+    }
+
+    /// Create a [`ProofOfIssuance`], as in [`ProofOfIssuance::prove`], whose
+    /// Fiat-Shamir challenge is derived from the caller's `transcript`, and
+    /// thus bound to anything already appended to it.
+    pub(crate) fn prove_with_transcript(
+        issuer: &Issuer,
+        credential: &AnonymousCredential,
+        transcript: &mut Transcript,
+    ) -> ProofOfIssuance
+    {
+        ProofOfIssuance::prove_with_key(&issuer.system_parameters, &issuer.issuer_parameters,
+                                        &issuer.amacs_key, credential, transcript)
+    }
+
+    /// Create a [`ProofOfIssuance`], as in
+    /// [`ProofOfIssuance::prove_with_transcript`], with an issuer's
+    /// `secret_key` and public parameters.
+    pub(crate) fn prove_with_key(
+        system_parameters: &SystemParameters,
+        issuer_parameters: &IssuerParameters,
+        secret_key: &SecretKey,
+        credential: &AnonymousCredential,
+        transcript: &mut Transcript,
+    ) -> ProofOfIssuance
     {
         use zkp::toolbox::prover::PointVar;
         use zkp::toolbox::prover::ScalarVar;
 
-        let mut transcript = Transcript::new(b"2019/1416 anonymous credential");
-        let mut prover = Prover::new(b"2019/1416 issuance proof", &mut transcript);
+        // Bind the parameters the credential claims to be issued under into the transcript.
+        transcript.append_message(b"parameters", &credential.parameters_fingerprint);
+
+        let mut prover = Prover::new(b"2019/1416 issuance proof", transcript);
 
         // Commit the names of the Camenisch-Stadler secrets to the protocol transcript.
-        let w       = prover.allocate_scalar(b"w",   issuer.amacs_key.w);
-        let w_prime = prover.allocate_scalar(b"w'",  issuer.amacs_key.w_prime);
-        let x_0     = prover.allocate_scalar(b"x_0", issuer.amacs_key.x_0);
-        let x_1     = prover.allocate_scalar(b"x_1", issuer.amacs_key.x_1);
+        let w       = prover.allocate_scalar(b"w",   secret_key.w);
+        let w_prime = prover.allocate_scalar(b"w'",  secret_key.w_prime);
+        let x_0     = prover.allocate_scalar(b"x_0", secret_key.x_0);
+        let x_1     = prover.allocate_scalar(b"x_1", secret_key.x_1);
 
-        let mut y: Vec<ScalarVar> = Vec::with_capacity(issuer.system_parameters.NUMBER_OF_ATTRIBUTES as usize);
+        let mut y: Vec<ScalarVar> = Vec::with_capacity(system_parameters.NUMBER_OF_ATTRIBUTES as usize);
 
-        for (_i, y_i) in issuer.amacs_key.y.iter().enumerate() {
+        for (_i, y_i) in secret_key.y.iter().enumerate() {
             // XXX fix the zkp crate to take Strings
             //y.push(prover.allocate_scalar(format!("y_{}", _i), y_i));
             y.push(prover.allocate_scalar(b"y", *y_i));
@@ -68,15 +135,15 @@ impl ProofOfIssuance {
         let one = prover.allocate_scalar(b"1", Scalar::one());
 
         // Commit to the values and names of the Camenisch-Stadler publics.
-        let (G_V, _)       = prover.allocate_point(b"G_V",       issuer.system_parameters.G_V);
-        let (G_w, _)       = prover.allocate_point(b"G_w",       issuer.system_parameters.G_w);
-        let (G_w_prime, _) = prover.allocate_point(b"G_w_prime", issuer.system_parameters.G_w_prime);
-        let (neg_G_x_0, _) = prover.allocate_point(b"-G_x_0",   -issuer.system_parameters.G_x_0);
-        let (neg_G_x_1, _) = prover.allocate_point(b"-G_x_1",   -issuer.system_parameters.G_x_1);
+        let (G_V, _)       = prover.allocate_point(b"G_V",       system_parameters.G_V);
+        let (G_w, _)       = prover.allocate_point(b"G_w",       system_parameters.G_w);
+        let (G_w_prime, _) = prover.allocate_point(b"G_w_prime", system_parameters.G_w_prime);
+        let (neg_G_x_0, _) = prover.allocate_point(b"-G_x_0",   -system_parameters.G_x_0);
+        let (neg_G_x_1, _) = prover.allocate_point(b"-G_x_1",   -system_parameters.G_x_1);
 
-        let mut neg_G_y: Vec<PointVar> = Vec::with_capacity(issuer.system_parameters.NUMBER_OF_ATTRIBUTES as usize);
+        let mut neg_G_y: Vec<PointVar> = Vec::with_capacity(system_parameters.NUMBER_OF_ATTRIBUTES as usize);
 
-        for (_i, G_y_i) in issuer.system_parameters.G_y.iter().enumerate() {
+        for (_i, G_y_i) in system_parameters.G_y.iter().enumerate() {
             // XXX fix the zkp crate to take Strings
             //let (G_y_x, _) = prover.allocate_point(format!("G_y_{}", _i), G_y_i);
             let (neg_G_y_x, _) = prover.allocate_point(b"-G_y", -G_y_i);
@@ -84,15 +151,15 @@ impl ProofOfIssuance {
             neg_G_y.push(neg_G_y_x);
         }
 
-        let (C_W, _) = prover.allocate_point(b"C_W", issuer.issuer_parameters.C_W);
-        let (I, _)   = prover.allocate_point(b"I",   issuer.issuer_parameters.I);
+        let (C_W, _) = prover.allocate_point(b"C_W", issuer_parameters.C_W);
+        let (I, _)   = prover.allocate_point(b"I",   issuer_parameters.I);
         let (U, _)   = prover.allocate_point(b"U", credential.amac.U);
         let (V, _)   = prover.allocate_point(b"V", credential.amac.V);
         let (tU, _)  = prover.allocate_point(b"tU", credential.amac.t * credential.amac.U);
 
-        let mut M: Vec<PointVar> = Vec::with_capacity(issuer.system_parameters.NUMBER_OF_ATTRIBUTES as usize);
+        let mut M: Vec<PointVar> = Vec::with_capacity(system_parameters.NUMBER_OF_ATTRIBUTES as usize);
 
-        let messages: Messages = Messages::from_attributes(&credential.attributes, &issuer.system_parameters);
+        let messages: Messages = Messages::from_attributes(&credential.attributes, system_parameters);
 
         for (_i, M_i) in messages.0.iter().enumerate() {
             // XXX fix the zkp crate to take Strings
@@ -106,7 +173,7 @@ impl ProofOfIssuance {
         prover.constrain(C_W, vec![(w, G_w), (w_prime, G_w_prime)]);
 
         // Constraint #2: I = G_V - (G_x_0 * x_0 + G_x_1 * x_1 + G_y_1 * y_1 + ... + G_y_n * y_n)
-        let mut rhs: Vec<(ScalarVar, PointVar)> = Vec::with_capacity(3 + issuer.system_parameters.NUMBER_OF_ATTRIBUTES as usize);
+        let mut rhs: Vec<(ScalarVar, PointVar)> = Vec::with_capacity(3 + system_parameters.NUMBER_OF_ATTRIBUTES as usize);
 
         rhs.push((one, G_V));
         rhs.push((x_0, neg_G_x_0));
@@ -116,7 +183,7 @@ impl ProofOfIssuance {
         prover.constrain(I, rhs);
 
         // Constraint #3: V = G_w * w + U * x_0 + U * x_1 * t + \sigma{i=1}{n} M_i * y_i
-        let mut rhs: Vec<(ScalarVar, PointVar)> = Vec::with_capacity(3 + issuer.system_parameters.NUMBER_OF_ATTRIBUTES as usize);
+        let mut rhs: Vec<(ScalarVar, PointVar)> = Vec::with_capacity(3 + system_parameters.NUMBER_OF_ATTRIBUTES as usize);
 
         rhs.push((w, G_w));
         rhs.push((x_0, U));
@@ -135,12 +202,30 @@ impl ProofOfIssuance {
         issuer_parameters: &IssuerParameters,
         credential: &AnonymousCredential,
     ) -> Result<(), CredentialError>
+    {
+        let mut transcript = Transcript::new(b"2019/1416 anonymous credential");
+
+        self.verify_with_transcript(system_parameters, issuer_parameters, credential, &mut transcript)
+    }
+
+    /// Verify a [`ProofOfIssuance`] which was created with
+    /// [`ProofOfIssuance::prove_with_transcript`] for a `transcript` in the
+    /// same state as this one.
+    pub fn verify_with_transcript(
+        &self,
+        system_parameters: &SystemParameters,
+        issuer_parameters: &IssuerParameters,
+        credential: &AnonymousCredential,
+        transcript: &mut Transcript,
+    ) -> Result<(), CredentialError>
     {
         use zkp::toolbox::verifier::PointVar;
         use zkp::toolbox::verifier::ScalarVar;
 
-        let mut transcript = Transcript::new(b"2019/1416 anonymous credential");
-        let mut verifier = Verifier::new(b"2019/1416 issuance proof", &mut transcript);
+        // Bind the parameters the credential must be issued under into the transcript.
+        transcript.append_message(b"parameters", &system_parameters.identifier());
+
+        let mut verifier = Verifier::new(b"2019/1416 issuance proof", transcript);
 
         // Commit the names of the Camenisch-Stadler secrets to the protocol transcript.
         let w       = verifier.allocate_scalar(b"w");
@@ -181,12 +266,12 @@ impl ProofOfIssuance {
 
         let mut M: Vec<PointVar> = Vec::with_capacity(system_parameters.NUMBER_OF_ATTRIBUTES as usize);
 
-        let messages: Messages = Messages::from_attributes(&credential.attributes, system_parameters);
+        let mut messages: Messages = Messages::from_attributes(&credential.attributes, system_parameters);
 
-        for (_i, M_i) in messages.0.iter().enumerate() {
+        for (_i, M_i) in messages.compressed().iter().enumerate() {
             // XXX fix the zkp crate to take Strings
             //let (M_x, _) = verifier.allocate_point(format!("M_{}", _i), M_i);
-            let M_x = verifier.allocate_point(b"M", M_i.compress())?;
+            let M_x = verifier.allocate_point(b"M", *M_i)?;
 
             M.push(M_x);
         }
@@ -218,6 +303,554 @@ impl ProofOfIssuance {
     }
 }
 
+/// An attribute of a blind issuance [`Request`], which is either revealed to
+/// the issuer or ElGamal-encrypted under the user's blinding key.
+#[derive(Clone, Debug)]
+pub(crate) enum BlindedAttribute {
+    Revealed(Attribute),
+    Hidden{ E_1: RistrettoPoint, E_2: RistrettoPoint },
+}
+
+/// A request for the blind issuance of an [`AnonymousCredential`], some of
+/// whose attributes are hidden from the issuer.
+///
+/// Each hidden scalar attribute \(( m_i \)) is ElGamal-encrypted under the
+/// user's blinding key \(( D = G * d \)) as
+/// \(( (E_{1,i}, E_{2,i}) = (G * r_i, D * r_i + G_m_i * m_i) \)),
+/// and the request carries a proof of knowledge of \(( d \)) and of each
+/// \(( (r_i, m_i) \)).  The issuer then computes the algebraic MAC upon the
+/// encrypted attributes homomorphically, without learning them.
+pub struct Request {
+    pub(crate) D: RistrettoPoint,
+    pub(crate) attributes: Vec<BlindedAttribute>,
+    proof: CompactProof,
+}
+
+/// The user's secret state for a blind issuance [`Request`], with which to
+/// decrypt the issuer's [`Response`] into a credential.
+pub struct RequestState {
+    d: Scalar,
+    attributes: Vec<Attribute>,
+    /// The ElGamal encryption nonces for the hidden attributes, in order.
+    pub(crate) r: Vec<Scalar>,
+}
+
+/// Overwrite the blinding key and the hidden attributes when the state drops
+/// out of scope.
+impl Drop for RequestState {
+    fn drop(&mut self) {
+        self.d.zeroize();
+        self.r.zeroize();
+        self.attributes.zeroize();
+    }
+}
+
+/// An issuer's response to a blind issuance [`Request`].
+///
+/// This contains the nonces \(( (t, U) \)) of the algebraic MAC and an
+/// ElGamal encryption \(( (E_1, E_2) \)) of its \(( V \)) under the
+/// user's blinding key, along with a proof that it was computed correctly
+/// w.r.t. the issuer's public parameters.
+pub struct Response {
+    t: Scalar,
+    U: RistrettoPoint,
+    E_1: RistrettoPoint,
+    E_2: RistrettoPoint,
+    proof: CompactProof,
+}
+
+impl Request {
+    /// Create a blind issuance request for a credential with the given
+    /// `attributes`.
+    ///
+    /// Every [`Attribute::SecretScalar`] is hidden from the issuer, and every
+    /// other attribute is revealed to it.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the `Request` to send to the issuer and
+    /// the [`RequestState`] to keep for decrypting its [`Response`],
+    /// otherwise a [`CredentialError::WrongNumberOfAttributes`], or a
+    /// [`CredentialError::BadAttribute`] if any attribute is an
+    /// [`Attribute::SecretPoint`], as hidden group elements cannot yet be
+    /// blindly issued.
+    pub fn new<C>(
+        system_parameters: &SystemParameters,
+        attributes: Vec<Attribute>,
+        csprng: &mut C,
+    ) -> Result<(Request, RequestState), CredentialError>
+    where
+        C: RngCore + CryptoRng,
+    {
+        Request::new_inner(system_parameters, attributes, None, csprng)
+    }
+
+    /// Create a blind issuance request, as in [`Request::new`], whose proof
+    /// is bound to a `nonce` chosen by the issuer, e.g. the challenge of an
+    /// [`IssuerSession`], so that it cannot be replayed to another.
+    ///
+    /// [`IssuerSession`]: crate::session::IssuerSession
+    pub fn new_with_nonce<C>(
+        system_parameters: &SystemParameters,
+        attributes: Vec<Attribute>,
+        nonce: &[u8],
+        csprng: &mut C,
+    ) -> Result<(Request, RequestState), CredentialError>
+    where
+        C: RngCore + CryptoRng,
+    {
+        Request::new_inner(system_parameters, attributes, Some(nonce), csprng)
+    }
+
+    fn new_inner<C>(
+        system_parameters: &SystemParameters,
+        attributes: Vec<Attribute>,
+        nonce: Option<&[u8]>,
+        csprng: &mut C,
+    ) -> Result<(Request, RequestState), CredentialError>
+    where
+        C: RngCore + CryptoRng,
+    {
+        use zkp::toolbox::prover::PointVar;
+        use zkp::toolbox::prover::ScalarVar;
+
+        if attributes.len() != system_parameters.NUMBER_OF_ATTRIBUTES as usize {
+            return Err(CredentialError::WrongNumberOfAttributes);
+        }
+
+        let d: Scalar = Scalar::random(csprng);
+        let D: RistrettoPoint = system_parameters.G * d;
+
+        let mut blinded: Vec<BlindedAttribute> = Vec::with_capacity(attributes.len());
+        let mut hidden: Vec<(usize, Scalar, Scalar)> = Vec::new();
+
+        for (i, attribute) in attributes.iter().enumerate() {
+            match attribute {
+                Attribute::SecretScalar(m) => {
+                    let r: Scalar = Scalar::random(csprng);
+
+                    blinded.push(BlindedAttribute::Hidden {
+                        E_1: system_parameters.G * r,
+                        E_2: (D * r) + (system_parameters.G_m[i] * m),
+                    });
+                    hidden.push((i, r, *m));
+                },
+                Attribute::SecretPoint(_) => return Err(CredentialError::BadAttribute),
+                _ => blinded.push(BlindedAttribute::Revealed(attribute.clone())),
+            }
+        }
+
+        let mut transcript = Request::transcript(nonce);
+        let mut prover = Prover::new(b"2019/1416 blind issuance request", &mut transcript);
+
+        // Commit the names of the Camenisch-Stadler secrets to the protocol transcript.
+        let d_var = prover.allocate_scalar(b"d", d);
+
+        let mut secrets: Vec<(ScalarVar, ScalarVar)> = Vec::with_capacity(hidden.len());
+
+        for (_i, r, m) in hidden.iter() {
+            secrets.push((prover.allocate_scalar(b"r", *r), prover.allocate_scalar(b"m", *m)));
+        }
+
+        // Commit to the values and names of the Camenisch-Stadler publics.
+        let (G, _)     = prover.allocate_point(b"G", system_parameters.G);
+        let (D_var, _) = prover.allocate_point(b"D", D);
+
+        let mut publics: Vec<(PointVar, PointVar, PointVar)> = Vec::with_capacity(hidden.len());
+
+        for (i, _r, _m) in hidden.iter() {
+            if let BlindedAttribute::Hidden{ E_1, E_2 } = &blinded[*i] {
+                let (G_m_i, _) = prover.allocate_point(b"G_m", system_parameters.G_m[*i]);
+                let (E_1_i, _) = prover.allocate_point(b"E_1", *E_1);
+                let (E_2_i, _) = prover.allocate_point(b"E_2", *E_2);
+
+                publics.push((G_m_i, E_1_i, E_2_i));
+            }
+        }
+
+        // Constraint #1: D = G * d
+        prover.constrain(D_var, vec![(d_var, G)]);
+
+        // Constraint #2: E_1_i = G * r_i
+        //                E_2_i = D * r_i + G_m_i * m_i
+        for ((r, m), (G_m_i, E_1_i, E_2_i)) in secrets.iter().zip(publics.iter()) {
+            prover.constrain(*E_1_i, vec![(*r, G)]);
+            prover.constrain(*E_2_i, vec![(*r, D_var), (*m, *G_m_i)]);
+        }
+
+        let r: Vec<Scalar> = hidden.iter().map(|(_i, r, _m)| *r).collect();
+
+        for (_i, r, m) in hidden.iter_mut() {
+            r.zeroize();
+            m.zeroize();
+        }
+
+        let request = Request { D, attributes: blinded, proof: prover.prove_compact() };
+        let state = RequestState { d, attributes, r };
+
+        Ok((request, state))
+    }
+
+    /// Create a transcript for a request, bound to the issuer's `nonce` if
+    /// there is one.
+    fn transcript(nonce: Option<&[u8]>) -> Transcript {
+        let mut transcript = Transcript::new(b"2019/1416 anonymous credential");
+
+        if let Some(nonce) = nonce {
+            transcript.append_message(b"nonce", nonce);
+        }
+        transcript
+    }
+
+    /// Verify the proof of knowledge of the hidden attributes of this
+    /// `Request`.
+    pub(crate) fn verify(&self, system_parameters: &SystemParameters) -> Result<(), CredentialError> {
+        self.verify_inner(system_parameters, None)
+    }
+
+    /// Verify the proof of knowledge of the hidden attributes of this
+    /// `Request`, which must be bound to the `nonce`.
+    pub(crate) fn verify_with_nonce(
+        &self,
+        system_parameters: &SystemParameters,
+        nonce: &[u8],
+    ) -> Result<(), CredentialError>
+    {
+        self.verify_inner(system_parameters, Some(nonce))
+    }
+
+    fn verify_inner(&self, system_parameters: &SystemParameters, nonce: Option<&[u8]>) -> Result<(), CredentialError> {
+        use zkp::toolbox::verifier::PointVar;
+        use zkp::toolbox::verifier::ScalarVar;
+
+        if self.attributes.len() != system_parameters.NUMBER_OF_ATTRIBUTES as usize {
+            return Err(CredentialError::WrongNumberOfAttributes);
+        }
+
+        let mut transcript = Request::transcript(nonce);
+        let mut verifier = Verifier::new(b"2019/1416 blind issuance request", &mut transcript);
+
+        // Commit the names of the Camenisch-Stadler secrets to the protocol transcript.
+        let d = verifier.allocate_scalar(b"d");
+
+        let mut secrets: Vec<(ScalarVar, ScalarVar)> = Vec::new();
+
+        for attribute in self.attributes.iter() {
+            if let BlindedAttribute::Hidden{ .. } = attribute {
+                secrets.push((verifier.allocate_scalar(b"r"), verifier.allocate_scalar(b"m")));
+            }
+        }
+
+        // Commit to the values and names of the Camenisch-Stadler publics.
+        let G = verifier.allocate_point(b"G", system_parameters.G.compress())?;
+        let D = verifier.allocate_point(b"D", self.D.compress())?;
+
+        let mut publics: Vec<(PointVar, PointVar, PointVar)> = Vec::with_capacity(secrets.len());
+
+        for (i, attribute) in self.attributes.iter().enumerate() {
+            if let BlindedAttribute::Hidden{ E_1, E_2 } = attribute {
+                let G_m_i = verifier.allocate_point(b"G_m", system_parameters.G_m[i].compress())?;
+                let E_1_i = verifier.allocate_point(b"E_1", E_1.compress())?;
+                let E_2_i = verifier.allocate_point(b"E_2", E_2.compress())?;
+
+                publics.push((G_m_i, E_1_i, E_2_i));
+            }
+        }
+
+        // Constraint #1: D = G * d
+        verifier.constrain(D, vec![(d, G)]);
+
+        // Constraint #2: E_1_i = G * r_i
+        //                E_2_i = D * r_i + G_m_i * m_i
+        for ((r, m), (G_m_i, E_1_i, E_2_i)) in secrets.iter().zip(publics.iter()) {
+            verifier.constrain(*E_1_i, vec![(*r, G)]);
+            verifier.constrain(*E_2_i, vec![(*r, D), (*m, *G_m_i)]);
+        }
+
+        verifier.verify_compact(&self.proof).or(Err(CredentialError::VerificationFailure))
+    }
+}
+
+impl Response {
+    /// Compute an issuer's [`Response`] to a blind issuance [`Request`],
+    /// after verifying the request's proof.
+    pub(crate) fn prove<C>(
+        issuer: &Issuer,
+        request: &Request,
+        csprng: &mut C,
+    ) -> Result<Response, CredentialError>
+    where
+        C: RngCore + CryptoRng,
+    {
+        request.verify(&issuer.system_parameters)?;
+
+        Ok(Response::prove_inner(issuer, request, csprng))
+    }
+
+    /// Compute an issuer's [`Response`] to a blind issuance [`Request`],
+    /// after verifying that the request's proof is bound to the `nonce`.
+    pub(crate) fn prove_with_nonce<C>(
+        issuer: &Issuer,
+        request: &Request,
+        nonce: &[u8],
+        csprng: &mut C,
+    ) -> Result<Response, CredentialError>
+    where
+        C: RngCore + CryptoRng,
+    {
+        request.verify_with_nonce(&issuer.system_parameters, nonce)?;
+
+        Ok(Response::prove_inner(issuer, request, csprng))
+    }
+
+    fn prove_inner<C>(
+        issuer: &Issuer,
+        request: &Request,
+        csprng: &mut C,
+    ) -> Response
+    where
+        C: RngCore + CryptoRng,
+    {
+        use zkp::toolbox::prover::PointVar;
+        use zkp::toolbox::prover::ScalarVar;
+
+        let system_parameters = &issuer.system_parameters;
+        let key = &issuer.amacs_key;
+
+        let t: Scalar = Scalar::random(csprng);
+        let U: RistrettoPoint = RistrettoPoint::random(csprng);
+        let mut s: Scalar = Scalar::random(csprng);
+
+        // E_1 = G * s + \sigma{i \in hidden} E_1_i * y_i
+        // E_2 = W + U * x_0 + U * x_1 * t + D * s +
+        //       \sigma{i \in revealed} M_i * y_i + \sigma{i \in hidden} E_2_i * y_i
+        let mut E_1: RistrettoPoint = system_parameters.G * s;
+        let mut E_2: RistrettoPoint = key.W + (U * key.x_0) + (U * (key.x_1 * t)) + (request.D * s);
+
+        for (i, attribute) in request.attributes.iter().enumerate() {
+            match attribute {
+                BlindedAttribute::Revealed(a) => E_2 += a.message_point(i, system_parameters) * key.y[i],
+                BlindedAttribute::Hidden{ E_1: E_1_i, E_2: E_2_i } => {
+                    E_1 += E_1_i * key.y[i];
+                    E_2 += E_2_i * key.y[i];
+                },
+            }
+        }
+
+        let mut transcript = Transcript::new(b"2019/1416 anonymous credential");
+        let mut prover = Prover::new(b"2019/1416 blind issuance response", &mut transcript);
+
+        // Commit the names of the Camenisch-Stadler secrets to the protocol transcript.
+        let w       = prover.allocate_scalar(b"w",   key.w);
+        let w_prime = prover.allocate_scalar(b"w'",  key.w_prime);
+        let x_0     = prover.allocate_scalar(b"x_0", key.x_0);
+        let x_1     = prover.allocate_scalar(b"x_1", key.x_1);
+
+        let y: Vec<ScalarVar> = key.y.iter().map(|y_i| prover.allocate_scalar(b"y", *y_i)).collect();
+
+        let s_var = prover.allocate_scalar(b"s", s);
+
+        // Commit to the values and names of the Camenisch-Stadler publics.
+        let (G, _)           = prover.allocate_point(b"G",         system_parameters.G);
+        let (D, _)           = prover.allocate_point(b"D",         request.D);
+        let (G_w, _)         = prover.allocate_point(b"G_w",       system_parameters.G_w);
+        let (G_w_prime, _)   = prover.allocate_point(b"G_w_prime", system_parameters.G_w_prime);
+        let (G_x_0, _)       = prover.allocate_point(b"G_x_0",     system_parameters.G_x_0);
+        let (G_x_1, _)       = prover.allocate_point(b"G_x_1",     system_parameters.G_x_1);
+
+        let G_y: Vec<PointVar> = system_parameters.G_y[..y.len()].iter()
+            .map(|G_y_i| prover.allocate_point(b"G_y", *G_y_i).0).collect();
+
+        let (G_V_minus_I, _) = prover.allocate_point(b"G_V-I", system_parameters.G_V - issuer.issuer_parameters.I);
+        let (C_W, _)         = prover.allocate_point(b"C_W",   issuer.issuer_parameters.C_W);
+        let (U_var, _)       = prover.allocate_point(b"U",     U);
+        let (tU, _)          = prover.allocate_point(b"tU",    U * t);
+
+        let mut rhs_1: Vec<(ScalarVar, PointVar)> = Vec::with_capacity(1 + y.len());
+        let mut rhs_2: Vec<(ScalarVar, PointVar)> = Vec::with_capacity(4 + y.len());
+
+        rhs_1.push((s_var, G));
+        rhs_2.push((w, G_w));
+        rhs_2.push((x_0, U_var));
+        rhs_2.push((x_1, tU));
+        rhs_2.push((s_var, D));
+
+        for (i, attribute) in request.attributes.iter().enumerate() {
+            match attribute {
+                BlindedAttribute::Revealed(a) => {
+                    let (M_i, _) = prover.allocate_point(b"M", a.message_point(i, system_parameters));
+
+                    rhs_2.push((y[i], M_i));
+                },
+                BlindedAttribute::Hidden{ E_1: E_1_i, E_2: E_2_i } => {
+                    let (E_1_i, _) = prover.allocate_point(b"E_1", *E_1_i);
+                    let (E_2_i, _) = prover.allocate_point(b"E_2", *E_2_i);
+
+                    rhs_1.push((y[i], E_1_i));
+                    rhs_2.push((y[i], E_2_i));
+                },
+            }
+        }
+
+        let (E_1_var, _) = prover.allocate_point(b"E_1", E_1);
+        let (E_2_var, _) = prover.allocate_point(b"E_2", E_2);
+
+        // Constraint #1: C_W = G_w * w + G_w' * w'
+        prover.constrain(C_W, vec![(w, G_w), (w_prime, G_w_prime)]);
+
+        // Constraint #2: G_V - I = G_x_0 * x_0 + G_x_1 * x_1 + G_y_1 * y_1 + ... + G_y_n * y_n
+        let mut rhs: Vec<(ScalarVar, PointVar)> = Vec::with_capacity(2 + y.len());
+
+        rhs.push((x_0, G_x_0));
+        rhs.push((x_1, G_x_1));
+        rhs.extend(y.iter().copied().zip(G_y.iter().copied()));
+
+        prover.constrain(G_V_minus_I, rhs);
+
+        // Constraint #3: E_1 = G * s + \sigma{i \in hidden} E_1_i * y_i
+        prover.constrain(E_1_var, rhs_1);
+
+        // Constraint #4: E_2 = G_w * w + U * x_0 + U * x_1 * t + D * s +
+        //                      \sigma{i \in revealed} M_i * y_i + \sigma{i \in hidden} E_2_i * y_i
+        prover.constrain(E_2_var, rhs_2);
+
+        let proof = prover.prove_compact();
+
+        s.zeroize();
+
+        Response { t, U, E_1, E_2, proof }
+    }
+
+    /// Verify this [`Response`] to a blind issuance `request`.
+    pub(crate) fn verify(
+        &self,
+        system_parameters: &SystemParameters,
+        issuer_parameters: &IssuerParameters,
+        request: &Request,
+    ) -> Result<(), CredentialError>
+    {
+        use zkp::toolbox::verifier::PointVar;
+        use zkp::toolbox::verifier::ScalarVar;
+
+        let NUMBER_OF_ATTRIBUTES = system_parameters.NUMBER_OF_ATTRIBUTES as usize;
+
+        if request.attributes.len() != NUMBER_OF_ATTRIBUTES {
+            return Err(CredentialError::WrongNumberOfAttributes);
+        }
+
+        let mut transcript = Transcript::new(b"2019/1416 anonymous credential");
+        let mut verifier = Verifier::new(b"2019/1416 blind issuance response", &mut transcript);
+
+        // Commit the names of the Camenisch-Stadler secrets to the protocol transcript.
+        let w       = verifier.allocate_scalar(b"w");
+        let w_prime = verifier.allocate_scalar(b"w'");
+        let x_0     = verifier.allocate_scalar(b"x_0");
+        let x_1     = verifier.allocate_scalar(b"x_1");
+
+        let y: Vec<ScalarVar> = (0..NUMBER_OF_ATTRIBUTES).map(|_| verifier.allocate_scalar(b"y")).collect();
+
+        let s = verifier.allocate_scalar(b"s");
+
+        // Commit to the values and names of the Camenisch-Stadler publics.
+        let G         = verifier.allocate_point(b"G",         system_parameters.G.compress())?;
+        let D         = verifier.allocate_point(b"D",         request.D.compress())?;
+        let G_w       = verifier.allocate_point(b"G_w",       system_parameters.G_w.compress())?;
+        let G_w_prime = verifier.allocate_point(b"G_w_prime", system_parameters.G_w_prime.compress())?;
+        let G_x_0     = verifier.allocate_point(b"G_x_0",     system_parameters.G_x_0.compress())?;
+        let G_x_1     = verifier.allocate_point(b"G_x_1",     system_parameters.G_x_1.compress())?;
+
+        let mut G_y: Vec<PointVar> = Vec::with_capacity(NUMBER_OF_ATTRIBUTES);
+
+        for G_y_i in system_parameters.G_y[..NUMBER_OF_ATTRIBUTES].iter() {
+            G_y.push(verifier.allocate_point(b"G_y", G_y_i.compress())?);
+        }
+
+        let G_V_minus_I = verifier.allocate_point(b"G_V-I", (system_parameters.G_V - issuer_parameters.I).compress())?;
+        let C_W         = verifier.allocate_point(b"C_W",   issuer_parameters.C_W.compress())?;
+        let U           = verifier.allocate_point(b"U",     self.U.compress())?;
+        let tU          = verifier.allocate_point(b"tU",    (self.U * self.t).compress())?;
+
+        let mut rhs_1: Vec<(ScalarVar, PointVar)> = Vec::with_capacity(1 + NUMBER_OF_ATTRIBUTES);
+        let mut rhs_2: Vec<(ScalarVar, PointVar)> = Vec::with_capacity(4 + NUMBER_OF_ATTRIBUTES);
+
+        rhs_1.push((s, G));
+        rhs_2.push((w, G_w));
+        rhs_2.push((x_0, U));
+        rhs_2.push((x_1, tU));
+        rhs_2.push((s, D));
+
+        for (i, attribute) in request.attributes.iter().enumerate() {
+            match attribute {
+                BlindedAttribute::Revealed(a) => {
+                    let M_i = verifier.allocate_point(b"M", a.message_point(i, system_parameters).compress())?;
+
+                    rhs_2.push((y[i], M_i));
+                },
+                BlindedAttribute::Hidden{ E_1: E_1_i, E_2: E_2_i } => {
+                    let E_1_i = verifier.allocate_point(b"E_1", E_1_i.compress())?;
+                    let E_2_i = verifier.allocate_point(b"E_2", E_2_i.compress())?;
+
+                    rhs_1.push((y[i], E_1_i));
+                    rhs_2.push((y[i], E_2_i));
+                },
+            }
+        }
+
+        let E_1 = verifier.allocate_point(b"E_1", self.E_1.compress())?;
+        let E_2 = verifier.allocate_point(b"E_2", self.E_2.compress())?;
+
+        // Constraint #1: C_W = G_w * w + G_w' * w'
+        verifier.constrain(C_W, vec![(w, G_w), (w_prime, G_w_prime)]);
+
+        // Constraint #2: G_V - I = G_x_0 * x_0 + G_x_1 * x_1 + G_y_1 * y_1 + ... + G_y_n * y_n
+        let mut rhs: Vec<(ScalarVar, PointVar)> = Vec::with_capacity(2 + NUMBER_OF_ATTRIBUTES);
+
+        rhs.push((x_0, G_x_0));
+        rhs.push((x_1, G_x_1));
+        rhs.extend(y.iter().copied().zip(G_y.iter().copied()));
+
+        verifier.constrain(G_V_minus_I, rhs);
+
+        // Constraint #3: E_1 = G * s + \sigma{i \in hidden} E_1_i * y_i
+        verifier.constrain(E_1, rhs_1);
+
+        // Constraint #4: E_2 = G_w * w + U * x_0 + U * x_1 * t + D * s +
+        //                      \sigma{i \in revealed} M_i * y_i + \sigma{i \in hidden} E_2_i * y_i
+        verifier.constrain(E_2, rhs_2);
+
+        verifier.verify_compact(&self.proof).or(Err(CredentialError::VerificationFailure))
+    }
+}
+
+impl RequestState {
+    /// Verify the issuer's `response` to our `request`, and decrypt it into
+    /// an [`AnonymousCredential`] bearing all of our attributes, including
+    /// those which were hidden from the issuer.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the credential, otherwise a
+    /// [`CredentialError`] if the issuer's proof doesn't verify.
+    pub fn finish(
+        mut self,
+        system_parameters: &SystemParameters,
+        issuer_parameters: &IssuerParameters,
+        request: &Request,
+        response: &Response,
+    ) -> Result<AnonymousCredential, CredentialError>
+    {
+        response.verify(system_parameters, issuer_parameters, request)?;
+
+        // V = E_2 - E_1 * d
+        let V: RistrettoPoint = response.E_2 - (response.E_1 * self.d);
+        let amac = Amac { t: response.t, U: response.U, V };
+        let attributes = ::core::mem::take(&mut self.attributes);
+        let parameters_fingerprint = system_parameters.identifier();
+
+        Ok(AnonymousCredential { amac, attributes, parameters_fingerprint })
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -268,6 +901,37 @@ mod test {
         assert!(credential.is_ok());
     }
 
+    #[test]
+    fn blind_issuance_hides_attributes() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 4).unwrap();
+        let issuer = Issuer::new(&system_parameters, &mut rng);
+        let mut attributes = Vec::new();
+
+        attributes.push(Attribute::SecretScalar(Scalar::random(&mut rng)));
+        attributes.push(Attribute::PublicScalar(Scalar::random(&mut rng)));
+        attributes.push(Attribute::SecretScalar(Scalar::random(&mut rng)));
+        attributes.push(Attribute::PublicPoint(RistrettoPoint::random(&mut rng)));
+
+        let (request, state) = Request::new(&system_parameters, attributes.clone(), &mut rng).unwrap();
+        let response = issuer.issue_blind(&request, &mut rng).unwrap();
+        let credential = state.finish(&system_parameters, &issuer.issuer_parameters, &request, &response).unwrap();
+
+        assert!(credential.amac.verify(&system_parameters, &issuer.amacs_key, &attributes).is_ok());
+
+        let presentation = credential.show(&system_parameters, &issuer.issuer_parameters, None, &mut rng).unwrap();
+
+        assert!(issuer.verify(&presentation).is_ok());
+
+        // A response whose encrypted tag was tampered with is rejected.
+        let (request, state) = Request::new(&system_parameters, attributes, &mut rng).unwrap();
+        let mut response = issuer.issue_blind(&request, &mut rng).unwrap();
+
+        response.E_2 += system_parameters.G;
+
+        assert!(state.finish(&system_parameters, &issuer.issuer_parameters, &request, &response).is_err());
+    }
+
     /// An issuance proof with a plaintext equal to the identity element will fail.
     #[test]
     #[should_panic(expected = "assertion failed: credential.is_ok()")]
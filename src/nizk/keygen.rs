@@ -0,0 +1,187 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2020 The Brave Authors
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Non-interactive zero-knowledge proofs (NIZKs) of correct issuer key
+//! generation.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use zkp::CompactProof;
+use zkp::Transcript;
+use zkp::toolbox::SchnorrCS;
+use zkp::toolbox::prover::Prover;
+use zkp::toolbox::verifier::Verifier;
+
+use crate::amacs::SecretKey;
+use crate::errors::CredentialError;
+use crate::parameters::{IssuerParameters, SystemParameters};
+
+/// A non-interactive zero-knowledge proof demonstrating that some
+/// [`IssuerParameters`] were correctly computed from a [`SecretKey`] w.r.t.
+/// the published [`SystemParameters`], i.e. that the issuer knows
+/// \\(( (w, w', x_0, x_1, y_1, \ldots, y_n) \\)) such that
+///
+/// \\(( C_W = G_w * w + G_w' * w' \\)), and
+///
+/// \\(( G_V - I = G_x_0 * x_0 + G_x_1 * x_1 + \sigma{i=1}{n} G_y_i * y_i \\)).
+///
+/// Clients should check this before requesting credentials from an issuer.
+pub struct ProofOfKeyGeneration(CompactProof);
+
+impl ProofOfKeyGeneration {
+    /// Create a [`ProofOfKeyGeneration`] for the `issuer_parameters` derived
+    /// from the `secret_key`.
+    pub(crate) fn prove(
+        system_parameters: &SystemParameters,
+        secret_key: &SecretKey,
+        issuer_parameters: &IssuerParameters,
+    ) -> ProofOfKeyGeneration
+    {
+        use zkp::toolbox::prover::PointVar;
+        use zkp::toolbox::prover::ScalarVar;
+
+        let NUMBER_OF_ATTRIBUTES = system_parameters.NUMBER_OF_ATTRIBUTES as usize;
+
+        let mut transcript = Transcript::new(b"2019/1416 anonymous credential");
+        let mut prover = Prover::new(b"2019/1416 key generation proof", &mut transcript);
+
+        // Commit the names of the Camenisch-Stadler secrets to the protocol transcript.
+        let w       = prover.allocate_scalar(b"w",   secret_key.w);
+        let w_prime = prover.allocate_scalar(b"w'",  secret_key.w_prime);
+        let x_0     = prover.allocate_scalar(b"x_0", secret_key.x_0);
+        let x_1     = prover.allocate_scalar(b"x_1", secret_key.x_1);
+
+        let mut y: Vec<ScalarVar> = Vec::with_capacity(NUMBER_OF_ATTRIBUTES);
+
+        for y_i in secret_key.y.iter() {
+            // XXX fix the zkp crate to take Strings
+            y.push(prover.allocate_scalar(b"y", *y_i));
+        }
+
+        // Commit to the values and names of the Camenisch-Stadler publics.
+        let (G_w, _)       = prover.allocate_point(b"G_w",       system_parameters.G_w);
+        let (G_w_prime, _) = prover.allocate_point(b"G_w_prime", system_parameters.G_w_prime);
+        let (G_x_0, _)     = prover.allocate_point(b"G_x_0",     system_parameters.G_x_0);
+        let (G_x_1, _)     = prover.allocate_point(b"G_x_1",     system_parameters.G_x_1);
+
+        let mut G_y: Vec<PointVar> = Vec::with_capacity(NUMBER_OF_ATTRIBUTES);
+
+        for G_y_i in system_parameters.G_y[..NUMBER_OF_ATTRIBUTES].iter() {
+            // XXX fix the zkp crate to take Strings
+            G_y.push(prover.allocate_point(b"G_y", *G_y_i).0);
+        }
+
+        let (C_W, _)         = prover.allocate_point(b"C_W", issuer_parameters.C_W);
+        let (G_V_minus_I, _) = prover.allocate_point(b"G_V-I", system_parameters.G_V - issuer_parameters.I);
+
+        // Constraint #1: C_W = G_w * w + G_w' * w'
+        prover.constrain(C_W, vec![(w, G_w), (w_prime, G_w_prime)]);
+
+        // Constraint #2: G_V - I = G_x_0 * x_0 + G_x_1 * x_1 + G_y_1 * y_1 + ... + G_y_n * y_n
+        let mut rhs: Vec<(ScalarVar, PointVar)> = Vec::with_capacity(2 + NUMBER_OF_ATTRIBUTES);
+
+        rhs.push((x_0, G_x_0));
+        rhs.push((x_1, G_x_1));
+        rhs.extend(y.iter().copied().zip(G_y.iter().copied()));
+
+        prover.constrain(G_V_minus_I, rhs);
+
+        ProofOfKeyGeneration(prover.prove_compact())
+    }
+
+    /// Verify a [`ProofOfKeyGeneration`] for some `issuer_parameters`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is empty if the `issuer_parameters` were
+    /// correctly generated, otherwise a [`CredentialError`].
+    pub fn verify(
+        &self,
+        system_parameters: &SystemParameters,
+        issuer_parameters: &IssuerParameters,
+    ) -> Result<(), CredentialError>
+    {
+        use zkp::toolbox::verifier::PointVar;
+        use zkp::toolbox::verifier::ScalarVar;
+
+        let NUMBER_OF_ATTRIBUTES = system_parameters.NUMBER_OF_ATTRIBUTES as usize;
+
+        let mut transcript = Transcript::new(b"2019/1416 anonymous credential");
+        let mut verifier = Verifier::new(b"2019/1416 key generation proof", &mut transcript);
+
+        // Commit the names of the Camenisch-Stadler secrets to the protocol transcript.
+        let w       = verifier.allocate_scalar(b"w");
+        let w_prime = verifier.allocate_scalar(b"w'");
+        let x_0     = verifier.allocate_scalar(b"x_0");
+        let x_1     = verifier.allocate_scalar(b"x_1");
+
+        let mut y: Vec<ScalarVar> = Vec::with_capacity(NUMBER_OF_ATTRIBUTES);
+
+        for _ in 0..NUMBER_OF_ATTRIBUTES {
+            // XXX fix the zkp crate to take Strings
+            y.push(verifier.allocate_scalar(b"y"));
+        }
+
+        // Commit to the values and names of the Camenisch-Stadler publics.
+        let G_w       = verifier.allocate_point(b"G_w",       system_parameters.G_w.compress())?;
+        let G_w_prime = verifier.allocate_point(b"G_w_prime", system_parameters.G_w_prime.compress())?;
+        let G_x_0     = verifier.allocate_point(b"G_x_0",     system_parameters.G_x_0.compress())?;
+        let G_x_1     = verifier.allocate_point(b"G_x_1",     system_parameters.G_x_1.compress())?;
+
+        let mut G_y: Vec<PointVar> = Vec::with_capacity(NUMBER_OF_ATTRIBUTES);
+
+        for G_y_i in system_parameters.G_y[..NUMBER_OF_ATTRIBUTES].iter() {
+            // XXX fix the zkp crate to take Strings
+            G_y.push(verifier.allocate_point(b"G_y", G_y_i.compress())?);
+        }
+
+        let C_W         = verifier.allocate_point(b"C_W", issuer_parameters.C_W.compress())?;
+        let G_V_minus_I = verifier.allocate_point(b"G_V-I", (system_parameters.G_V - issuer_parameters.I).compress())?;
+
+        // Constraint #1: C_W = G_w * w + G_w' * w'
+        verifier.constrain(C_W, vec![(w, G_w), (w_prime, G_w_prime)]);
+
+        // Constraint #2: G_V - I = G_x_0 * x_0 + G_x_1 * x_1 + G_y_1 * y_1 + ... + G_y_n * y_n
+        let mut rhs: Vec<(ScalarVar, PointVar)> = Vec::with_capacity(2 + NUMBER_OF_ATTRIBUTES);
+
+        rhs.push((x_0, G_x_0));
+        rhs.push((x_1, G_x_1));
+        rhs.extend(y.iter().copied().zip(G_y.iter().copied()));
+
+        verifier.constrain(G_V_minus_I, rhs);
+
+        verifier.verify_compact(&self.0).or(Err(CredentialError::VerificationFailure))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+
+    use rand::thread_rng;
+
+    #[test]
+    fn key_generation_proof() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 4).unwrap();
+        let secret_key = SecretKey::generate(&mut rng, &system_parameters);
+        let (mut issuer_parameters, proof) = IssuerParameters::generate_with_proof(&system_parameters, &secret_key);
+
+        assert!(proof.verify(&system_parameters, &issuer_parameters).is_ok());
+
+        issuer_parameters.I += RISTRETTO_BASEPOINT_POINT;
+
+        assert!(proof.verify(&system_parameters, &issuer_parameters).is_err());
+    }
+}
@@ -0,0 +1,598 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2020 The Brave Authors
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Non-interactive zero-knowledge proofs (NIZKs) of the presentation of
+//! several credentials at once, with hidden scalar attributes proven equal
+//! across them.
+//!
+//! Every credential is proven valid exactly as in a
+//! [`ProofOfValidCredential`](crate::nizk::presentation::ProofOfValidCredential),
+//! but all of the statements share a single transcript and thus a single
+//! Fiat-Shamir challenge.  Linked attributes share one secret in the proof,
+//! which proves that they are equal without revealing them.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use zkp::CompactProof;
+use zkp::Transcript;
+use zkp::toolbox::SchnorrCS;
+use zkp::toolbox::prover::Prover;
+use zkp::toolbox::prover::PointVar as ProverPointVar;
+use zkp::toolbox::prover::ScalarVar as ProverScalarVar;
+use zkp::toolbox::verifier::Verifier;
+use zkp::toolbox::verifier::PointVar as VerifierPointVar;
+use zkp::toolbox::verifier::ScalarVar as VerifierScalarVar;
+
+use crate::amacs::Attribute;
+use crate::amacs::EncryptedAttribute;
+use crate::credential::AnonymousCredential;
+use crate::errors::CredentialError;
+use crate::issuer::Issuer;
+use crate::parameters::{IssuerParameters, SystemParameters};
+
+/// A link between two attribute slots, each given as the index of a
+/// credential within the presentation and the index of an attribute within
+/// that credential.
+pub type Link = ((usize, usize), (usize, usize));
+
+/// Feed the number of credentials and every link into the `transcript`, so
+/// that the challenge is bound to which attributes are claimed to be equal.
+fn append_links(transcript: &mut Transcript, number_of_credentials: usize, links: &[Link]) {
+    transcript.append_message(b"credentials", &(number_of_credentials as u32).to_le_bytes());
+
+    for ((credential_a, attribute_a), (credential_b, attribute_b)) in links.iter() {
+        let mut link = [0u8; 16];
+
+        link[0..4].copy_from_slice(&(*credential_a as u32).to_le_bytes());
+        link[4..8].copy_from_slice(&(*attribute_a as u32).to_le_bytes());
+        link[8..12].copy_from_slice(&(*credential_b as u32).to_le_bytes());
+        link[12..16].copy_from_slice(&(*attribute_b as u32).to_le_bytes());
+
+        transcript.append_message(b"link", &link);
+    }
+}
+
+/// Compute, for every attribute slot `credential * number_of_attributes +
+/// attribute`, the lowest slot to which it is (transitively) linked, so that
+/// all linked slots may share a single secret in the proof.
+fn link_classes(number_of_credentials: usize, number_of_attributes: usize, links: &[Link]) -> Vec<usize> {
+    let mut classes: Vec<usize> = (0..number_of_credentials * number_of_attributes).collect();
+    let mut changed = true;
+
+    while changed {
+        changed = false;
+
+        for ((credential_a, attribute_a), (credential_b, attribute_b)) in links.iter() {
+            let a = credential_a * number_of_attributes + attribute_a;
+            let b = credential_b * number_of_attributes + attribute_b;
+            let lowest = ::core::cmp::min(classes[a], classes[b]);
+
+            if classes[a] != lowest || classes[b] != lowest {
+                classes[a] = lowest;
+                classes[b] = lowest;
+                changed = true;
+            }
+        }
+    }
+    classes
+}
+
+/// Order the slots within each link, and the links themselves, so that
+/// equal sets of links compare equal.
+fn normalize_links(links: &[Link]) -> Vec<Link> {
+    let mut normalized: Vec<Link> = links.iter().map(|(a, b)| (*a.min(b), *a.max(b))).collect();
+
+    normalized.sort_unstable();
+    normalized.dedup();
+    normalized
+}
+
+/// Check that every link refers to a hidden scalar attribute of one of the
+/// presented credentials.
+fn links_are_valid(attributes: &[&[EncryptedAttribute]], links: &[Link]) -> bool {
+    let is_hidden_scalar = |(credential, attribute): (usize, usize)| {
+        matches!(attributes.get(credential).and_then(|x| x.get(attribute)), Some(EncryptedAttribute::SecretScalar))
+    };
+
+    links.iter().all(|(a, b)| is_hidden_scalar(*a) && is_hidden_scalar(*b))
+}
+
+/// A builder for a [`LinkedPresentation`] of several credentials, some of
+/// whose hidden scalar attributes are proven equal.
+///
+/// # Warning
+///
+/// Hidden group element attributes are not supported, and credentials with
+/// any [`Attribute::SecretPoint`]s cannot be presented this way.  As for a
+/// single credential, any [`Attribute::EitherPoint`]s are revealed.
+pub struct LinkedPresentationBuilder<'a> {
+    system_parameters: &'a SystemParameters,
+    issuer_parameters: &'a IssuerParameters,
+    credentials: Vec<&'a AnonymousCredential>,
+    links: Vec<Link>,
+}
+
+impl<'a> LinkedPresentationBuilder<'a> {
+    /// Begin a linked presentation of credentials from a single issuer.
+    pub fn new(
+        system_parameters: &'a SystemParameters,
+        issuer_parameters: &'a IssuerParameters,
+    ) -> LinkedPresentationBuilder<'a>
+    {
+        LinkedPresentationBuilder {
+            system_parameters,
+            issuer_parameters,
+            credentials: Vec::new(),
+            links: Vec::new(),
+        }
+    }
+
+    /// Add a `credential` to the presentation.
+    ///
+    /// # Returns
+    ///
+    /// The index of the `credential` within the presentation, for use with
+    /// [`LinkedPresentationBuilder::link`].
+    pub fn add_credential(&mut self, credential: &'a AnonymousCredential) -> usize {
+        self.credentials.push(credential);
+        self.credentials.len() - 1
+    }
+
+    /// Claim that two hidden scalar attributes, each given as the index of a
+    /// credential within the presentation and the index of an attribute
+    /// within that credential, are equal.
+    pub fn link(&mut self, first: (usize, usize), second: (usize, usize)) {
+        self.links.push((first, second));
+    }
+
+    /// Prove the validity of every credential, and the equality of every
+    /// pair of linked attributes, with a single challenge.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the [`LinkedPresentation`], otherwise a
+    /// [`CredentialError::WrongNumberOfAttributes`] if a credential doesn't
+    /// have one attribute per attribute of the [`SystemParameters`], or a
+    /// [`CredentialError::BadAttribute`] if a credential has a hidden group
+    /// element attribute, or a link refers to anything but two equal hidden
+    /// scalar attributes.
+    pub fn finish<C>(self, csprng: &mut C) -> Result<LinkedPresentation, CredentialError>
+    where
+        C: RngCore + CryptoRng,
+    {
+        LinkedPresentation::prove(self.system_parameters, self.issuer_parameters,
+                                  &self.credentials[..], &self.links[..], csprng)
+    }
+}
+
+/// The commitments to one credential within a [`LinkedPresentation`].
+pub struct LinkedCommitments {
+    encrypted_attributes: Vec<EncryptedAttribute>,
+    C_x_0: RistrettoPoint,
+    C_x_1: RistrettoPoint,
+    C_V:   RistrettoPoint,
+    C_y: Vec<RistrettoPoint>,
+}
+
+/// A proof-of-knowledge of several valid credentials, some of whose hidden
+/// scalar attributes are equal, created with a [`LinkedPresentationBuilder`].
+pub struct LinkedPresentation {
+    proof: CompactProof,
+    commitments: Vec<LinkedCommitments>,
+    links: Vec<Link>,
+}
+
+impl LinkedPresentation {
+    /// Create a [`LinkedPresentation`].
+    fn prove<C>(
+        system_parameters: &SystemParameters,
+        issuer_parameters: &IssuerParameters,
+        credentials: &[&AnonymousCredential],
+        links: &[Link],
+        csprng: &mut C,
+    ) -> Result<LinkedPresentation, CredentialError>
+    where
+        C: RngCore + CryptoRng,
+    {
+        let NUMBER_OF_ATTRIBUTES = system_parameters.NUMBER_OF_ATTRIBUTES as usize;
+
+        let mut commitments: Vec<LinkedCommitments> = Vec::with_capacity(credentials.len());
+        let mut nonces: Vec<(Scalar, Scalar, RistrettoPoint)> = Vec::with_capacity(credentials.len());
+
+        for credential in credentials.iter() {
+            if credential.attributes.len() != NUMBER_OF_ATTRIBUTES {
+                return Err(CredentialError::WrongNumberOfAttributes);
+            }
+
+            // Choose a nonce for the commitments.
+            let z_:   Scalar = Scalar::random(csprng);
+            let z_0_: Scalar = (-credential.amac.t * z_).reduce();
+
+            let mut C_y_: Vec<RistrettoPoint> = Vec::with_capacity(NUMBER_OF_ATTRIBUTES);
+            let mut encrypted_attributes: Vec<EncryptedAttribute> = Vec::with_capacity(NUMBER_OF_ATTRIBUTES);
+
+            for (i, attribute) in credential.attributes.iter().enumerate() {
+                match attribute {
+                    Attribute::PublicScalar(m) => {
+                        C_y_.push(system_parameters.G_y[i] * z_);
+                        encrypted_attributes.push(EncryptedAttribute::PublicScalar(*m));
+                    },
+                    Attribute::SecretScalar(m) => {
+                        C_y_.push(system_parameters.G_y[i] * z_ + system_parameters.G_m[i] * m);
+                        encrypted_attributes.push(EncryptedAttribute::SecretScalar);
+                    },
+                    Attribute::PublicPoint(M)  => {
+                        C_y_.push(system_parameters.G_y[i] * z_);
+                        encrypted_attributes.push(EncryptedAttribute::PublicPoint(*M));
+                    },
+                    Attribute::EitherPoint(p)  => {
+                        C_y_.push(system_parameters.G_y[i] * z_);
+                        encrypted_attributes.push(EncryptedAttribute::PublicPoint(p.M1));
+                    },
+                    Attribute::SecretPoint(_)  => return Err(CredentialError::BadAttribute),
+                }
+            }
+
+            commitments.push(LinkedCommitments {
+                encrypted_attributes,
+                C_x_0: (system_parameters.G_x_0 * z_) +  credential.amac.U,
+                C_x_1: (system_parameters.G_x_1 * z_) + (credential.amac.U * credential.amac.t),
+                C_V:   (system_parameters.G_V   * z_) +  credential.amac.V,
+                C_y: C_y_,
+            });
+            nonces.push((z_, z_0_, issuer_parameters.I * z_));
+        }
+
+        let attributes: Vec<&[EncryptedAttribute]> = commitments.iter().map(|x| &x.encrypted_attributes[..]).collect();
+
+        if !links_are_valid(&attributes[..], links) {
+            return Err(CredentialError::BadAttribute);
+        }
+        for ((credential_a, attribute_a), (credential_b, attribute_b)) in links.iter() {
+            match (&credentials[*credential_a].attributes[*attribute_a],
+                   &credentials[*credential_b].attributes[*attribute_b]) {
+                (Attribute::SecretScalar(m_a), Attribute::SecretScalar(m_b)) if m_a == m_b => continue,
+                _ => return Err(CredentialError::BadAttribute),
+            }
+        }
+
+        let classes = link_classes(credentials.len(), NUMBER_OF_ATTRIBUTES, links);
+
+        // Create a transcript, bound to the links, and prover.
+        let mut transcript = Transcript::new(b"2019/1416 anonymous credential");
+
+        append_links(&mut transcript, credentials.len(), links);
+
+        let mut prover = Prover::new(b"2019/1416 linked presentation proof", &mut transcript);
+
+        // Feed the domain separators for the Camenisch-Stadler secrets into the protocol transcript.
+        let mut secrets: Vec<(ProverScalarVar, ProverScalarVar, ProverScalarVar)> = Vec::with_capacity(credentials.len());
+
+        for ((z_, z_0_, _), credential) in nonces.iter().zip(credentials.iter()) {
+            let z   = prover.allocate_scalar(b"z", *z_);
+            let z_0 = prover.allocate_scalar(b"z_0", *z_0_);
+            let t   = prover.allocate_scalar(b"t", credential.amac.t);
+
+            secrets.push((z, z_0, t));
+        }
+
+        // Allocate one secret per class of linked hidden scalar attributes.
+        // Since links only join hidden scalars, the lowest slot of a class is
+        // always a hidden scalar, and is allocated before the rest.
+        let mut H_s: Vec<Option<ProverScalarVar>> = vec![None; classes.len()];
+
+        for (k, credential) in credentials.iter().enumerate() {
+            for (i, attribute) in credential.attributes.iter().enumerate() {
+                if let Attribute::SecretScalar(m) = attribute {
+                    let slot = k * NUMBER_OF_ATTRIBUTES + i;
+
+                    H_s[slot] = match H_s[classes[slot]] {
+                        Some(m_i) => Some(m_i),
+                        None      => Some(prover.allocate_scalar(b"m", *m)),
+                    };
+                }
+            }
+        }
+
+        // Feed in the domain separators and values for the publics into the transcript.
+        let (I, _)     = prover.allocate_point(b"I", issuer_parameters.I);
+        let (G_x_0, _) = prover.allocate_point(b"G_x_0", system_parameters.G_x_0);
+        let (G_x_1, _) = prover.allocate_point(b"G_x_1", system_parameters.G_x_1);
+
+        let G_y: Vec<ProverPointVar> = system_parameters.G_y[..NUMBER_OF_ATTRIBUTES].iter()
+            .map(|basepoint| prover.allocate_point(b"G_y", *basepoint).0).collect();
+        let G_m: Vec<ProverPointVar> = system_parameters.G_m.iter()
+            .map(|basepoint| prover.allocate_point(b"G_m", *basepoint).0).collect();
+
+        let mut C_x_0: Vec<ProverPointVar> = Vec::with_capacity(credentials.len());
+        let mut C_x_1: Vec<ProverPointVar> = Vec::with_capacity(credentials.len());
+        let mut C_y:   Vec<Vec<ProverPointVar>> = Vec::with_capacity(credentials.len());
+
+        for commitment in commitments.iter() {
+            C_x_1.push(prover.allocate_point(b"C_x_1", commitment.C_x_1).0);
+            C_x_0.push(prover.allocate_point(b"C_x_0", commitment.C_x_0).0);
+            C_y.push(commitment.C_y.iter().map(|C_y_i| prover.allocate_point(b"C_y", *C_y_i).0).collect());
+        }
+
+        let Z: Vec<ProverPointVar> = nonces.iter().map(|(_, _, Z_)| prover.allocate_point(b"Z", *Z_).0).collect();
+
+        for (k, (z, z_0, t)) in secrets.iter().enumerate() {
+            // Constraint #1: Z = I * z
+            prover.constrain(Z[k], vec![(*z, I)]);
+
+            // Constraint #2: C_x_1 = C_x_0 * t + G_x_0 * z_0 + G_x_1 * z
+            prover.constrain(C_x_1[k], vec![(*t, C_x_0[k]), (*z_0, G_x_0), (*z, G_x_1)]);
+
+            // Constraint #3: C_y_i = { G_y_i * z + G_m_i * m_i  if i is a hidden scalar attribute
+            //                        { G_y_i * z                if i is a revealed attribute
+            for (i, C_y_i) in C_y[k].iter().enumerate() {
+                match H_s[k * NUMBER_OF_ATTRIBUTES + i] {
+                    Some(m_i) => prover.constrain(*C_y_i, vec![(*z, G_y[i]), (m_i, G_m[i])]),
+                    None      => prover.constrain(*C_y_i, vec![(*z, G_y[i])]),
+                }
+            }
+        }
+
+        Ok(LinkedPresentation {
+            proof: prover.prove_compact(),
+            commitments,
+            links: links.to_vec(),
+        })
+    }
+
+    /// The number of credentials in this presentation.
+    pub fn len(&self) -> usize {
+        self.commitments.len()
+    }
+
+    /// Whether this presentation contains no credentials.
+    pub fn is_empty(&self) -> bool {
+        self.commitments.is_empty()
+    }
+
+    /// The attributes of the `index`th credential as presented, i.e. with
+    /// hidden attributes elided.
+    pub fn attributes(&self, index: usize) -> Option<&[EncryptedAttribute]> {
+        self.commitments.get(index).map(|x| &x.encrypted_attributes[..])
+    }
+
+    /// Verify a `LinkedPresentation` of exactly the expected `links`.
+    pub(crate) fn verify(
+        &self,
+        issuer: &Issuer,
+        links: &[Link],
+    ) -> Result<(), CredentialError>
+    {
+        let NUMBER_OF_ATTRIBUTES = issuer.system_parameters.NUMBER_OF_ATTRIBUTES as usize;
+
+        if links.is_empty() || normalize_links(links) != normalize_links(&self.links[..]) {
+            return Err(CredentialError::VerificationFailure);
+        }
+
+        for commitment in self.commitments.iter() {
+            if commitment.encrypted_attributes.len() != NUMBER_OF_ATTRIBUTES ||
+                commitment.C_y.len() != NUMBER_OF_ATTRIBUTES
+            {
+                return Err(CredentialError::WrongNumberOfAttributes);
+            }
+            for attribute in commitment.encrypted_attributes.iter() {
+                if let EncryptedAttribute::SecretPoint = attribute {
+                    return Err(CredentialError::BadAttribute);
+                }
+            }
+        }
+
+        let attributes: Vec<&[EncryptedAttribute]> = self.commitments.iter().map(|x| &x.encrypted_attributes[..]).collect();
+
+        if !links_are_valid(&attributes[..], &self.links[..]) {
+            return Err(CredentialError::BadAttribute);
+        }
+
+        let classes = link_classes(self.commitments.len(), NUMBER_OF_ATTRIBUTES, &self.links[..]);
+
+        // Recompute the prover's Z value for each credential, as for a
+        // single credential presentation.
+        let mut Z_: Vec<RistrettoPoint> = Vec::with_capacity(self.commitments.len());
+
+        for commitment in self.commitments.iter() {
+            let mut Z_k = commitment.C_V - issuer.amacs_key.W -
+                (commitment.C_x_0 * issuer.amacs_key.x_0) - (commitment.C_x_1 * issuer.amacs_key.x_1);
+
+            for (i, attribute) in commitment.encrypted_attributes.iter().enumerate() {
+                let x = match attribute {
+                    EncryptedAttribute::PublicScalar(m_i) => commitment.C_y[i] + (issuer.system_parameters.G_m[i] * m_i),
+                    EncryptedAttribute::SecretScalar      => commitment.C_y[i],
+                    EncryptedAttribute::PublicPoint(M_i)  => commitment.C_y[i] + M_i,
+                    EncryptedAttribute::SecretPoint       => commitment.C_y[i],
+                };
+                Z_k -= x * issuer.amacs_key.y[i];
+            }
+            Z_.push(Z_k);
+        }
+
+        // Create a transcript, bound to the links, and verifier.
+        let mut transcript = Transcript::new(b"2019/1416 anonymous credential");
+
+        append_links(&mut transcript, self.commitments.len(), &self.links[..]);
+
+        let mut verifier = Verifier::new(b"2019/1416 linked presentation proof", &mut transcript);
+
+        // Feed the domain separators for the Camenisch-Stadler secrets into the protocol transcript.
+        let mut secrets: Vec<(VerifierScalarVar, VerifierScalarVar, VerifierScalarVar)> = Vec::with_capacity(self.commitments.len());
+
+        for _ in self.commitments.iter() {
+            let z   = verifier.allocate_scalar(b"z");
+            let z_0 = verifier.allocate_scalar(b"z_0");
+            let t   = verifier.allocate_scalar(b"t");
+
+            secrets.push((z, z_0, t));
+        }
+
+        let mut H_s: Vec<Option<VerifierScalarVar>> = vec![None; classes.len()];
+
+        for (k, commitment) in self.commitments.iter().enumerate() {
+            for (i, attribute) in commitment.encrypted_attributes.iter().enumerate() {
+                if let EncryptedAttribute::SecretScalar = attribute {
+                    let slot = k * NUMBER_OF_ATTRIBUTES + i;
+
+                    H_s[slot] = match H_s[classes[slot]] {
+                        Some(m_i) => Some(m_i),
+                        None      => Some(verifier.allocate_scalar(b"m")),
+                    };
+                }
+            }
+        }
+
+        // Feed in the domain separators and values for the publics into the transcript.
+        let I     = verifier.allocate_point(b"I", issuer.issuer_parameters.I.compress())?;
+        let G_x_0 = verifier.allocate_point(b"G_x_0", issuer.system_parameters.G_x_0.compress())?;
+        let G_x_1 = verifier.allocate_point(b"G_x_1", issuer.system_parameters.G_x_1.compress())?;
+
+        let mut G_y: Vec<VerifierPointVar> = Vec::with_capacity(NUMBER_OF_ATTRIBUTES);
+        let mut G_m: Vec<VerifierPointVar> = Vec::with_capacity(NUMBER_OF_ATTRIBUTES);
+
+        for basepoint in issuer.system_parameters.G_y[..NUMBER_OF_ATTRIBUTES].iter() {
+            G_y.push(verifier.allocate_point(b"G_y", basepoint.compress())?);
+        }
+        for basepoint in issuer.system_parameters.G_m.iter() {
+            G_m.push(verifier.allocate_point(b"G_m", basepoint.compress())?);
+        }
+
+        let mut C_x_0: Vec<VerifierPointVar> = Vec::with_capacity(self.commitments.len());
+        let mut C_x_1: Vec<VerifierPointVar> = Vec::with_capacity(self.commitments.len());
+        let mut C_y:   Vec<Vec<VerifierPointVar>> = Vec::with_capacity(self.commitments.len());
+
+        for commitment in self.commitments.iter() {
+            let mut C_y_k: Vec<VerifierPointVar> = Vec::with_capacity(NUMBER_OF_ATTRIBUTES);
+
+            C_x_1.push(verifier.allocate_point(b"C_x_1", commitment.C_x_1.compress())?);
+            C_x_0.push(verifier.allocate_point(b"C_x_0", commitment.C_x_0.compress())?);
+
+            for C_y_i in commitment.C_y.iter() {
+                C_y_k.push(verifier.allocate_point(b"C_y", C_y_i.compress())?);
+            }
+            C_y.push(C_y_k);
+        }
+
+        let mut Z: Vec<VerifierPointVar> = Vec::with_capacity(self.commitments.len());
+
+        for Z_k in Z_.iter() {
+            Z.push(verifier.allocate_point(b"Z", Z_k.compress())?);
+        }
+
+        for (k, (z, z_0, t)) in secrets.iter().enumerate() {
+            // Constraint #1: Z = I * z
+            verifier.constrain(Z[k], vec![(*z, I)]);
+
+            // Constraint #2: C_x_1 = C_x_0 * t + G_x_0 * z_0 + G_x_1 * z
+            verifier.constrain(C_x_1[k], vec![(*t, C_x_0[k]), (*z_0, G_x_0), (*z, G_x_1)]);
+
+            // Constraint #3: C_y_i = { G_y_i * z + G_m_i * m_i  if i is a hidden scalar attribute
+            //                        { G_y_i * z                if i is a revealed attribute
+            for (i, C_y_i) in C_y[k].iter().enumerate() {
+                match H_s[k * NUMBER_OF_ATTRIBUTES + i] {
+                    Some(m_i) => verifier.constrain(*C_y_i, vec![(*z, G_y[i]), (m_i, G_m[i])]),
+                    None      => verifier.constrain(*C_y_i, vec![(*z, G_y[i])]),
+                }
+            }
+        }
+
+        verifier.verify_compact(&self.proof).or(Err(CredentialError::VerificationFailure))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+
+    use crate::user::CredentialRequestConstructor;
+
+    use rand::thread_rng;
+
+    #[test]
+    fn linked_presentation_shared_attribute() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 2).unwrap();
+        let issuer = Issuer::new(&system_parameters, &mut rng);
+        let shared = Scalar::random(&mut rng);
+
+        let mut request1 = CredentialRequestConstructor::new(&system_parameters);
+
+        request1.append_revealed_scalar(shared);
+        request1.append_revealed_point(RistrettoPoint::random(&mut rng));
+
+        let issuance1 = issuer.issue(request1.finish(), &mut rng).unwrap();
+        let mut credential1 = issuance1.verify(&system_parameters, &issuer.issuer_parameters).unwrap();
+
+        let mut request2 = CredentialRequestConstructor::new(&system_parameters);
+
+        request2.append_revealed_scalar(Scalar::random(&mut rng));
+        request2.append_revealed_scalar(shared);
+
+        let issuance2 = issuer.issue(request2.finish(), &mut rng).unwrap();
+        let mut credential2 = issuance2.verify(&system_parameters, &issuer.issuer_parameters).unwrap();
+
+        credential1.hide_attribute(0).unwrap();
+        credential2.hide_attribute(0).unwrap();
+        credential2.hide_attribute(1).unwrap();
+
+        let mut builder = LinkedPresentationBuilder::new(&system_parameters, &issuer.issuer_parameters);
+        let first = builder.add_credential(&credential1);
+        let second = builder.add_credential(&credential2);
+
+        builder.link((first, 0), (second, 1));
+
+        let mut presentation = builder.finish(&mut rng).unwrap();
+
+        assert_eq!(presentation.len(), 2);
+        assert!(issuer.verify_linked(&presentation, &[((first, 0), (second, 1))]).is_ok());
+        assert!(issuer.verify_linked(&presentation, &[((second, 1), (first, 0))]).is_ok());
+
+        // Only the expected links are accepted.
+        assert!(issuer.verify_linked(&presentation, &[]).is_err());
+        assert!(issuer.verify_linked(&presentation, &[((first, 0), (second, 0))]).is_err());
+        assert!(issuer.verify_linked(&presentation,
+                                     &[((first, 0), (second, 1)), ((first, 0), (second, 0))]).is_err());
+
+        // Nor is a presentation with no links at all.
+        let mut builder = LinkedPresentationBuilder::new(&system_parameters, &issuer.issuer_parameters);
+
+        builder.add_credential(&credential1);
+        builder.add_credential(&credential2);
+
+        let unlinked = builder.finish(&mut rng).unwrap();
+
+        assert!(issuer.verify_linked(&unlinked, &[((first, 0), (second, 1))]).is_err());
+        assert!(issuer.verify_linked(&unlinked, &[]).is_err());
+
+        presentation.commitments[second].C_y[1] += RISTRETTO_BASEPOINT_POINT;
+
+        assert!(issuer.verify_linked(&presentation, &[((first, 0), (second, 1))]).is_err());
+
+        // Unequal attributes cannot be linked.
+        let mut builder = LinkedPresentationBuilder::new(&system_parameters, &issuer.issuer_parameters);
+
+        builder.add_credential(&credential1);
+        builder.add_credential(&credential2);
+        builder.link((0, 0), (1, 0));
+
+        assert_eq!(builder.finish(&mut rng).err(), Some(CredentialError::BadAttribute));
+    }
+}
@@ -0,0 +1,277 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2020 The Brave Authors
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Non-interactive zero-knowledge proofs (NIZKs) that hidden scalar
+//! attributes are members of a public set.
+//!
+//! For a hidden scalar attribute \\(( m \\)) in slot \\(( i \\)), whose
+//! presentation commitment is \\(( C_y_i = G_y_i * z + G_m_i * m \\)), and a
+//! public set \\(( \{s_1, \ldots, s_k\} \\)), the prover shows that
+//! \\(( \prod{j} (m - s_j) = 0 \\)).  Starting from
+//! \\(( C_1 = C_y_i - G_m_i * s_1 \\)), a commitment to \\(( m - s_1 \\)),
+//! the prover sends commitments to each successive partial product,
+//!
+//! \\(( C_j = C_{j-1} * (m - s_j) + G_y_i * \tau_j \\)),
+//!
+//! and proves that \\(( C_j + C_{j-1} * s_j = C_{j-1} * m + G_y_i * \tau_j \\))
+//! for the same \\(( m \\)) as is committed to in \\(( C_y_i \\)).  Finally,
+//! the prover shows that \\(( C_k = G_y_i * \rho_k \\)), i.e. that the
+//! product is a commitment to zero.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use subtle::Choice;
+use subtle::ConstantTimeEq;
+
+use zeroize::Zeroize;
+
+use zkp::CompactProof;
+use zkp::Transcript;
+use zkp::toolbox::SchnorrCS;
+use zkp::toolbox::prover::Prover;
+use zkp::toolbox::verifier::Verifier;
+
+use crate::errors::CredentialError;
+use crate::parameters::SystemParameters;
+
+/// A public statement that the hidden scalar attribute at `index` is equal to
+/// one of the values in `set`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SetMembershipStatement {
+    /// The index of the attribute, which must be hidden upon presentation.
+    pub index: usize,
+    /// The allowed values.
+    pub set: Vec<Scalar>,
+}
+
+impl SetMembershipStatement {
+    /// Bind this statement into a `transcript`.
+    fn commit(&self, transcript: &mut Transcript) {
+        transcript.append_message(b"membership index", &(self.index as u32).to_le_bytes());
+        transcript.append_message(b"membership size", &(self.set.len() as u32).to_le_bytes());
+
+        for s_j in self.set.iter() {
+            transcript.append_message(b"membership value", s_j.as_bytes());
+        }
+    }
+
+    /// Check that this statement is well-formed w.r.t. the `system_parameters`.
+    fn is_valid(&self, system_parameters: &SystemParameters) -> bool {
+        !self.set.is_empty() && self.index < system_parameters.NUMBER_OF_ATTRIBUTES as usize
+    }
+}
+
+/// A non-interactive zero-knowledge proof that a hidden scalar attribute of
+/// a presented credential satisfies a [`SetMembershipStatement`], without
+/// revealing which of the set's values it is equal to.
+#[derive(Clone)]
+pub struct SetMembershipProof {
+    statement: SetMembershipStatement,
+    C: Vec<RistrettoPoint>,
+    proof: CompactProof,
+}
+
+impl SetMembershipProof {
+    /// Create a [`SetMembershipProof`] that the hidden scalar `m`, committed
+    /// to in slot `statement.index` with the presentation nonce `z`,
+    /// satisfies the `statement`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the proof, otherwise a
+    /// [`CredentialError::BadAttribute`] if the set is empty or `m` is not a
+    /// member of it.
+    pub(crate) fn prove<C>(
+        system_parameters: &SystemParameters,
+        statement: &SetMembershipStatement,
+        m: &Scalar,
+        z: &Scalar,
+        csprng: &mut C,
+    ) -> Result<SetMembershipProof, CredentialError>
+    where
+        C: RngCore + CryptoRng,
+    {
+        if !statement.is_valid(system_parameters) {
+            return Err(CredentialError::BadAttribute);
+        }
+
+        // Compare against every member, so as not to leak which one m is.
+        let is_member: bool = statement.set.iter().fold(Choice::from(0), |found, s_j| found | m.ct_eq(s_j)).into();
+
+        if !is_member {
+            return Err(CredentialError::BadAttribute);
+        }
+
+        let G_m_i = system_parameters.G_m[statement.index];
+        let G_y_i = system_parameters.G_y[statement.index];
+        let C_y_i = G_y_i * z + G_m_i * m;
+
+        // Compute the commitments to the partial products, along with their blinding factors.
+        let mut C_: Vec<RistrettoPoint> = Vec::with_capacity(statement.set.len());
+        let mut tau: Vec<Scalar> = Vec::with_capacity(statement.set.len() - 1);
+        let mut rho: Scalar = *z;
+
+        C_.push(C_y_i - G_m_i * statement.set[0]);
+
+        for s_j in statement.set[1..].iter() {
+            let tau_j = Scalar::random(csprng);
+            let C_j = C_[C_.len() - 1] * (m - s_j) + G_y_i * tau_j;
+
+            rho = rho * (m - s_j) + tau_j;
+            tau.push(tau_j);
+            C_.push(C_j);
+        }
+
+        let mut transcript = Transcript::new(b"2019/1416 anonymous credential");
+
+        statement.commit(&mut transcript);
+
+        let mut prover = Prover::new(b"2019/1416 set membership proof", &mut transcript);
+
+        // Feed the domain separators for the Camenisch-Stadler secrets into the protocol transcript.
+        let m_var   = prover.allocate_scalar(b"m", *m);
+        let z_var   = prover.allocate_scalar(b"z", *z);
+        let rho_var = prover.allocate_scalar(b"rho", rho);
+
+        // Feed in the domain separators and values for the publics into the transcript.
+        let (G_m, _) = prover.allocate_point(b"G_m", G_m_i);
+        let (G_y, _) = prover.allocate_point(b"G_y", G_y_i);
+        let (C_y, _) = prover.allocate_point(b"C_y", C_y_i);
+        let (C_1, _) = prover.allocate_point(b"C", C_[0]);
+
+        // Constraint #1: C_y_i = G_m_i * m + G_y_i * z
+        prover.constrain(C_y, vec![(m_var, G_m), (z_var, G_y)]);
+
+        // Constraint #2: C_j + C_{j-1} * s_j = C_{j-1} * m + G_y_i * tau_j
+        let mut C_previous = C_1;
+
+        for (j, tau_j) in tau.iter().enumerate() {
+            let tau_var  = prover.allocate_scalar(b"tau", *tau_j);
+            let (C_j, _) = prover.allocate_point(b"C", C_[j + 1]);
+            let (P_j, _) = prover.allocate_point(b"P", C_[j + 1] + C_[j] * statement.set[j + 1]);
+
+            prover.constrain(P_j, vec![(m_var, C_previous), (tau_var, G_y)]);
+            C_previous = C_j;
+        }
+
+        // Constraint #3: C_k = G_y_i * rho
+        prover.constrain(C_previous, vec![(rho_var, G_y)]);
+
+        let proof = prover.prove_compact();
+
+        rho.zeroize();
+        tau.zeroize();
+
+        Ok(SetMembershipProof { statement: statement.clone(), C: C_[1..].to_vec(), proof })
+    }
+
+    /// The statement which this proof proves.
+    pub fn statement(&self) -> &SetMembershipStatement {
+        &self.statement
+    }
+
+    /// Verify this [`SetMembershipProof`] against the presentation commitment
+    /// `C_y_i` to the attribute in slot `self.statement().index`.
+    pub(crate) fn verify(
+        &self,
+        system_parameters: &SystemParameters,
+        C_y_i: &RistrettoPoint,
+    ) -> Result<(), CredentialError>
+    {
+        let statement = &self.statement;
+
+        if !statement.is_valid(system_parameters) || self.C.len() != statement.set.len() - 1 {
+            return Err(CredentialError::VerificationFailure);
+        }
+
+        let G_m_i = system_parameters.G_m[statement.index];
+        let G_y_i = system_parameters.G_y[statement.index];
+
+        let mut C_: Vec<RistrettoPoint> = Vec::with_capacity(statement.set.len());
+
+        C_.push(C_y_i - G_m_i * statement.set[0]);
+        C_.extend(self.C.iter());
+
+        let mut transcript = Transcript::new(b"2019/1416 anonymous credential");
+
+        statement.commit(&mut transcript);
+
+        let mut verifier = Verifier::new(b"2019/1416 set membership proof", &mut transcript);
+
+        // Feed the domain separators for the Camenisch-Stadler secrets into the protocol transcript.
+        let m   = verifier.allocate_scalar(b"m");
+        let z   = verifier.allocate_scalar(b"z");
+        let rho = verifier.allocate_scalar(b"rho");
+
+        // Feed in the domain separators and values for the publics into the transcript.
+        let G_m = verifier.allocate_point(b"G_m", G_m_i.compress())?;
+        let G_y = verifier.allocate_point(b"G_y", G_y_i.compress())?;
+        let C_y = verifier.allocate_point(b"C_y", C_y_i.compress())?;
+        let C_1 = verifier.allocate_point(b"C", C_[0].compress())?;
+
+        // Constraint #1: C_y_i = G_m_i * m + G_y_i * z
+        verifier.constrain(C_y, vec![(m, G_m), (z, G_y)]);
+
+        // Constraint #2: C_j + C_{j-1} * s_j = C_{j-1} * m + G_y_i * tau_j
+        let mut C_previous = C_1;
+
+        for j in 0..self.C.len() {
+            let tau = verifier.allocate_scalar(b"tau");
+            let C_j = verifier.allocate_point(b"C", C_[j + 1].compress())?;
+            let P_j = verifier.allocate_point(b"P", (C_[j + 1] + C_[j] * statement.set[j + 1]).compress())?;
+
+            verifier.constrain(P_j, vec![(m, C_previous), (tau, G_y)]);
+            C_previous = C_j;
+        }
+
+        // Constraint #3: C_k = G_y_i * rho
+        verifier.constrain(C_previous, vec![(rho, G_y)]);
+
+        verifier.verify_compact(&self.proof).or(Err(CredentialError::VerificationFailure))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    #[test]
+    fn set_membership_proof() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 2).unwrap();
+        let set: Vec<Scalar> = [36u64, 40, 56, 250, 276].iter().map(|s| Scalar::from(*s)).collect();
+        let statement = SetMembershipStatement { index: 1, set };
+        let z = Scalar::random(&mut rng);
+
+        for m in statement.set.iter() {
+            let C_y_1 = system_parameters.G_y[1] * z + system_parameters.G_m[1] * m;
+            let proof = SetMembershipProof::prove(&system_parameters, &statement, m, &z, &mut rng).unwrap();
+
+            assert!(proof.verify(&system_parameters, &C_y_1).is_ok());
+
+            // The proof doesn't verify against a commitment to another value.
+            let C_y_1 = system_parameters.G_y[1] * z + system_parameters.G_m[1] * Scalar::from(41u64);
+
+            assert!(proof.verify(&system_parameters, &C_y_1).is_err());
+        }
+
+        assert!(SetMembershipProof::prove(&system_parameters, &statement, &Scalar::from(41u64), &z, &mut rng).is_err());
+    }
+}
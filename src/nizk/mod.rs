@@ -9,6 +9,23 @@
 
 //! Non-interactive zero-knowledge proofs (NIZKs).
 
+pub mod bundle;
+pub mod derivation;
+pub mod designated;
 pub mod encryption;
+pub mod equality;
+pub mod escrow;
+pub mod federated;
+pub mod interactive;
 pub mod issuance;
+pub mod keygen;
+pub mod linked;
+pub mod membership;
+pub mod nullifier;
+pub mod pedersen;
 pub mod presentation;
+pub mod pseudonym;
+pub mod range;
+pub mod relation;
+pub mod translation;
+pub mod update;
@@ -0,0 +1,115 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2020 The Brave Authors
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Nullifiers for detecting repeated presentations of a credential.
+//!
+//! For a hidden scalar attribute \\(( m \\)) in slot \\(( i \\)), whose
+//! presentation commitment is \\(( C_y_i = G_y_i * z + G_m_i * m \\)), and a
+//! verifier-chosen context string, the nullifier is
+//! \\(( N = H_{context} * m \\)), where \\(( H_{context} \\)) is the context
+//! hashed to a group element.  The presenter proves that the same
+//! \\(( m \\)) is used in both.
+//!
+//! Since the nullifier is deterministic, a verifier can detect a credential
+//! being shown twice in the same context, while nullifiers for different
+//! contexts are unlinkable under the decisional Diffie-Hellman assumption.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use crate::nizk::derivation::DerivationStatement;
+use crate::nizk::derivation::ProofOfDerivation;
+
+/// A deterministic tag for a hidden attribute within a context, which is the
+/// same each time a credential is presented in that context.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub struct Nullifier(pub(crate) [u8; 32]);
+
+impl Nullifier {
+    /// Serialise this nullifier, e.g. for storage in a verifier's database of
+    /// spent nullifiers.
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0
+    }
+
+    /// Deserialise a nullifier previously serialised with
+    /// [`Nullifier::to_bytes`].
+    pub fn from_bytes(bytes: &[u8; 32]) -> Nullifier {
+        Nullifier(*bytes)
+    }
+}
+
+/// A request to derive a [`Nullifier`] from the hidden scalar attribute at
+/// `index`, within a verifier-chosen `context`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct NullifierStatement {
+    /// The index of the attribute, which must be hidden upon presentation.
+    pub index: usize,
+    /// The context, e.g. the verifier's name and the current epoch.
+    pub context: Vec<u8>,
+}
+
+impl DerivationStatement for NullifierStatement {
+    const BASEPOINT_DOMAIN: &'static [u8] = b"aeonflux nullifier context";
+    const PROOF_DOMAIN: &'static [u8] = b"2019/1416 nullifier proof";
+
+    fn index(&self) -> usize {
+        self.index
+    }
+
+    fn bytes(&self) -> &[u8] {
+        &self.context[..]
+    }
+}
+
+/// A non-interactive zero-knowledge proof that a [`Nullifier`] was correctly
+/// derived from a hidden scalar attribute of a presented credential.
+pub type ProofOfNullifier = ProofOfDerivation<NullifierStatement>;
+
+impl ProofOfNullifier {
+    /// The nullifier.
+    pub fn nullifier(&self) -> Nullifier {
+        Nullifier(self.derived())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use curve25519_dalek::scalar::Scalar;
+
+    use crate::parameters::SystemParameters;
+
+    use rand::thread_rng;
+
+    #[test]
+    fn nullifier_is_deterministic_per_context() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 1).unwrap();
+        let m = Scalar::random(&mut rng);
+        let statement = NullifierStatement { index: 0, context: b"example.com 2020-11".to_vec() };
+        let other = NullifierStatement { index: 0, context: b"example.com 2020-12".to_vec() };
+
+        let z_1 = Scalar::random(&mut rng);
+        let z_2 = Scalar::random(&mut rng);
+        let proof_1 = ProofOfNullifier::prove(&system_parameters, &statement, &m, &z_1).unwrap();
+        let proof_2 = ProofOfNullifier::prove(&system_parameters, &statement, &m, &z_2).unwrap();
+        let proof_3 = ProofOfNullifier::prove(&system_parameters, &other, &m, &z_1).unwrap();
+
+        let C_y_0 = system_parameters.G_y[0] * z_1 + system_parameters.G_m[0] * m;
+
+        assert!(proof_1.verify(&system_parameters, &C_y_0).is_ok());
+        assert!(proof_2.verify(&system_parameters, &C_y_0).is_err());
+        assert_eq!(proof_1.nullifier(), proof_2.nullifier());
+        assert_ne!(proof_1.nullifier(), proof_3.nullifier());
+    }
+}
@@ -0,0 +1,325 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2020 The Brave Authors
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Pedersen commitments to scalar attributes, held outside of a credential.
+//!
+//! A [`Commitment`] to a scalar \\(( m \\)) for the attribute at index
+//! \\(( i \\)) is \\(( C = G_{y,i} * r + G_{m,i} * m \\)), for a secret
+//! blinding factor \\(( r \\)), i.e. it has the same form as the commitment
+//! \\(( C_{y,i} = G_{y,i} * z + G_{m,i} * m \\)) made to a hidden scalar
+//! attribute upon presentation.
+//!
+//! An application may hold such a commitment, e.g. in a ledger or another
+//! protocol, and its owner may prove knowledge of its opening with a
+//! [`ProofOfOpening`], or that it commits to the same scalar as a hidden
+//! attribute of their credential with a [`ConsistentPresentation`].
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use zkp::CompactProof;
+use zkp::Transcript;
+use zkp::toolbox::SchnorrCS;
+use zkp::toolbox::prover::Prover;
+use zkp::toolbox::verifier::Verifier;
+
+use crate::amacs::Attribute;
+use crate::amacs::EncryptedAttribute;
+use crate::credential::AnonymousCredential;
+use crate::errors::CredentialError;
+use crate::nizk::presentation::ProofOfValidCredential;
+use crate::parameters::{IssuerParameters, SystemParameters};
+use crate::symmetric::Keypair as SymmetricKeypair;
+
+/// A Pedersen commitment to a scalar attribute.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Commitment {
+    /// The commitment, \\(( C = G_{y,i} * r + G_{m,i} * m \\)).
+    pub C: RistrettoPoint,
+    /// The index \\(( i \\)) of the attribute whose generators were used.
+    pub index: u16,
+}
+
+/// Get the generators \\(( (G_{y,i}, G_{m,i}) \\)) of the attribute at `index`.
+fn generators(system_parameters: &SystemParameters, index: usize) -> Result<(RistrettoPoint, RistrettoPoint), CredentialError> {
+    match (system_parameters.G_y.get(index), system_parameters.G_m.get(index)) {
+        (Some(G_y), Some(G_m)) => Ok((*G_y, *G_m)),
+        _ => Err(CredentialError::WrongNumberOfAttributes),
+    }
+}
+
+impl Commitment {
+    /// Commit to the scalar `m`, as the attribute at `index`, with the
+    /// secret `blinding` factor.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the `Commitment`, otherwise a
+    /// [`CredentialError::WrongNumberOfAttributes`] if the
+    /// `system_parameters` have no attribute at `index`.
+    pub fn to_scalar_attribute(
+        system_parameters: &SystemParameters,
+        index: usize,
+        m: &Scalar,
+        blinding: &Scalar,
+    ) -> Result<Commitment, CredentialError>
+    {
+        let (G_y, G_m) = generators(system_parameters, index)?;
+
+        Ok(Commitment { C: G_y * blinding + G_m * m, index: index as u16 })
+    }
+
+    /// Check that this commitment opens to `m` with the `blinding` factor.
+    pub fn open(&self, system_parameters: &SystemParameters, m: &Scalar, blinding: &Scalar) -> bool {
+        match Commitment::to_scalar_attribute(system_parameters, self.index as usize, m, blinding) {
+            Ok(commitment) => commitment == *self,
+            Err(_) => false,
+        }
+    }
+}
+
+/// A proof of knowledge of the opening of a [`Commitment`].
+#[derive(Clone)]
+pub struct ProofOfOpening(CompactProof);
+
+impl ProofOfOpening {
+    /// Prove knowledge of the scalar `m` and `blinding` factor to which the
+    /// `commitment` opens.
+    pub fn prove(
+        system_parameters: &SystemParameters,
+        commitment: &Commitment,
+        m: &Scalar,
+        blinding: &Scalar,
+    ) -> Result<ProofOfOpening, CredentialError>
+    {
+        if !commitment.open(system_parameters, m, blinding) {
+            return Err(CredentialError::BadAttribute);
+        }
+
+        let (G_y_, G_m_) = generators(system_parameters, commitment.index as usize)?;
+
+        let mut transcript = Transcript::new(b"2019/1416 anonymous credential");
+        let mut prover = Prover::new(b"2019/1416 pedersen opening proof", &mut transcript);
+
+        // Feed the domain separators for the Camenisch-Stadler secrets into the protocol transcript.
+        let m = prover.allocate_scalar(b"m", *m);
+        let r = prover.allocate_scalar(b"r", *blinding);
+
+        // Feed in the domain separators and values for the publics into the transcript.
+        let (G_y, _) = prover.allocate_point(b"G_y", G_y_);
+        let (G_m, _) = prover.allocate_point(b"G_m", G_m_);
+        let (C, _)   = prover.allocate_point(b"C",   commitment.C);
+
+        // Constraint #1: C = G_y * r + G_m * m
+        prover.constrain(C, vec![(r, G_y), (m, G_m)]);
+
+        Ok(ProofOfOpening(prover.prove_compact()))
+    }
+
+    /// Verify that the prover knows the opening of the `commitment`.
+    pub fn verify(
+        &self,
+        system_parameters: &SystemParameters,
+        commitment: &Commitment,
+    ) -> Result<(), CredentialError>
+    {
+        let (G_y_, G_m_) = generators(system_parameters, commitment.index as usize)?;
+
+        let mut transcript = Transcript::new(b"2019/1416 anonymous credential");
+        let mut verifier = Verifier::new(b"2019/1416 pedersen opening proof", &mut transcript);
+
+        // Feed the domain separators for the Camenisch-Stadler secrets into the protocol transcript.
+        let m = verifier.allocate_scalar(b"m");
+        let r = verifier.allocate_scalar(b"r");
+
+        // Feed in the domain separators and values for the publics into the transcript.
+        let G_y = verifier.allocate_point(b"G_y", G_y_.compress())?;
+        let G_m = verifier.allocate_point(b"G_m", G_m_.compress())?;
+        let C   = verifier.allocate_point(b"C",   commitment.C.compress())?;
+
+        // Constraint #1: C = G_y * r + G_m * m
+        verifier.constrain(C, vec![(r, G_y), (m, G_m)]);
+
+        verifier.verify_compact(&self.0).or(Err(CredentialError::VerificationFailure))
+    }
+}
+
+/// A presentation of a credential, with a proof that the hidden scalar
+/// attribute at the index of a [`Commitment`] is the scalar committed to.
+pub struct ConsistentPresentation {
+    presentation: ProofOfValidCredential,
+    proof: CompactProof,
+}
+
+impl ConsistentPresentation {
+    /// Present a `credential`, and prove that its hidden scalar attribute at
+    /// the index of the `commitment` is the scalar to which the commitment
+    /// opens with the `blinding` factor.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the `ConsistentPresentation`,
+    /// otherwise a [`CredentialError::BadAttribute`] if the attribute isn't
+    /// a hidden scalar, or isn't the committed scalar.
+    pub fn prove<C>(
+        system_parameters: &SystemParameters,
+        issuer_parameters: &IssuerParameters,
+        credential: &AnonymousCredential,
+        keypair: Option<&SymmetricKeypair>,
+        commitment: &Commitment,
+        blinding: &Scalar,
+        csprng: &mut C,
+    ) -> Result<ConsistentPresentation, CredentialError>
+    where
+        C: RngCore + CryptoRng,
+    {
+        let index = commitment.index as usize;
+
+        let m_ = match credential.attributes.get(index) {
+            Some(Attribute::SecretScalar(m)) => m,
+            _ => return Err(CredentialError::BadAttribute),
+        };
+
+        if !commitment.open(system_parameters, m_, blinding) {
+            return Err(CredentialError::BadAttribute);
+        }
+
+        let (presentation, z_) = ProofOfValidCredential::prove_with_commitment_nonce(
+            system_parameters, issuer_parameters, credential, keypair, csprng)?;
+
+        let mut transcript = Transcript::new(b"2019/1416 anonymous credential");
+        let mut prover = Prover::new(b"2019/1416 pedersen consistency proof", &mut transcript);
+
+        // Feed the domain separators for the Camenisch-Stadler secrets into the protocol transcript.
+        let m = prover.allocate_scalar(b"m", *m_);
+        let r = prover.allocate_scalar(b"r", *blinding);
+        let z = prover.allocate_scalar(b"z", z_);
+
+        // Feed in the domain separators and values for the publics into the transcript.
+        let (G_y_i, _) = prover.allocate_point(b"G_y", system_parameters.G_y[index]);
+        let (G_m_i, _) = prover.allocate_point(b"G_m", system_parameters.G_m[index]);
+        let (C, _)     = prover.allocate_point(b"C",   commitment.C);
+        let (C_y_i, _) = prover.allocate_point(b"C_y", presentation.C_y[index]);
+
+        // Constraint #1: C = G_y_i * r + G_m_i * m
+        prover.constrain(C, vec![(r, G_y_i), (m, G_m_i)]);
+
+        // Constraint #2: C_y_i = G_y_i * z + G_m_i * m
+        prover.constrain(C_y_i, vec![(z, G_y_i), (m, G_m_i)]);
+
+        let proof = prover.prove_compact();
+
+        Ok(ConsistentPresentation { presentation, proof })
+    }
+
+    /// The presentation of the credential, to be verified by its issuer.
+    pub fn presentation(&self) -> &ProofOfValidCredential {
+        &self.presentation
+    }
+
+    /// Verify that the presented credential's hidden scalar attribute at the
+    /// index of the `commitment` is the committed scalar.
+    ///
+    /// This does *not* verify the presentation itself, which must be
+    /// verified by its issuer.
+    pub fn verify_consistency(
+        &self,
+        system_parameters: &SystemParameters,
+        commitment: &Commitment,
+    ) -> Result<(), CredentialError>
+    {
+        let index = commitment.index as usize;
+        let (G_y_i_, G_m_i_) = generators(system_parameters, index)?;
+
+        if !matches!(self.presentation.attributes().get(index), Some(EncryptedAttribute::SecretScalar)) ||
+            index >= self.presentation.C_y.len()
+        {
+            return Err(CredentialError::BadAttribute);
+        }
+
+        let mut transcript = Transcript::new(b"2019/1416 anonymous credential");
+        let mut verifier = Verifier::new(b"2019/1416 pedersen consistency proof", &mut transcript);
+
+        // Feed the domain separators for the Camenisch-Stadler secrets into the protocol transcript.
+        let m = verifier.allocate_scalar(b"m");
+        let r = verifier.allocate_scalar(b"r");
+        let z = verifier.allocate_scalar(b"z");
+
+        // Feed in the domain separators and values for the publics into the transcript.
+        let G_y_i = verifier.allocate_point(b"G_y", G_y_i_.compress())?;
+        let G_m_i = verifier.allocate_point(b"G_m", G_m_i_.compress())?;
+        let C     = verifier.allocate_point(b"C",   commitment.C.compress())?;
+        let C_y_i = verifier.allocate_point(b"C_y", self.presentation.C_y[index].compress())?;
+
+        // Constraint #1: C = G_y_i * r + G_m_i * m
+        verifier.constrain(C, vec![(r, G_y_i), (m, G_m_i)]);
+
+        // Constraint #2: C_y_i = G_y_i * z + G_m_i * m
+        verifier.constrain(C_y_i, vec![(z, G_y_i), (m, G_m_i)]);
+
+        verifier.verify_compact(&self.proof).or(Err(CredentialError::VerificationFailure))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::issuer::Issuer;
+    use crate::user::CredentialRequestConstructor;
+
+    use rand::thread_rng;
+
+    #[test]
+    fn commitments_open_and_are_consistent_with_credentials() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 2).unwrap();
+        let issuer = Issuer::new(&system_parameters, &mut rng);
+        let m = Scalar::random(&mut rng);
+        let blinding = Scalar::random(&mut rng);
+        let commitment = Commitment::to_scalar_attribute(&system_parameters, 1, &m, &blinding).unwrap();
+
+        assert!(commitment.open(&system_parameters, &m, &blinding));
+        assert!(!commitment.open(&system_parameters, &blinding, &m));
+        assert!(Commitment::to_scalar_attribute(&system_parameters, 2, &m, &blinding).is_err());
+
+        let proof = ProofOfOpening::prove(&system_parameters, &commitment, &m, &blinding).unwrap();
+
+        assert!(proof.verify(&system_parameters, &commitment).is_ok());
+        assert!(proof.verify(&system_parameters, &Commitment { index: 0, ..commitment }).is_err());
+        assert!(ProofOfOpening::prove(&system_parameters, &commitment, &m, &m).is_err());
+
+        let mut request = CredentialRequestConstructor::new(&system_parameters);
+        request.append_revealed_scalar(Scalar::random(&mut rng));
+        request.append_revealed_scalar(m);
+
+        let issuance = issuer.issue(request.finish(), &mut rng).unwrap();
+        let mut credential = issuance.verify(&system_parameters, &issuer.issuer_parameters).unwrap();
+
+        // The attribute must be hidden.
+        assert!(ConsistentPresentation::prove(&system_parameters, &issuer.issuer_parameters, &credential, None,
+                                              &commitment, &blinding, &mut rng).is_err());
+
+        credential.hide_attribute(1).unwrap();
+
+        let presentation = ConsistentPresentation::prove(&system_parameters, &issuer.issuer_parameters, &credential,
+                                                         None, &commitment, &blinding, &mut rng).unwrap();
+
+        assert!(issuer.verify(presentation.presentation()).is_ok());
+        assert!(presentation.verify_consistency(&system_parameters, &commitment).is_ok());
+
+        // A commitment to another scalar is inconsistent.
+        let other = Commitment::to_scalar_attribute(&system_parameters, 1, &blinding, &blinding).unwrap();
+
+        assert!(presentation.verify_consistency(&system_parameters, &other).is_err());
+    }
+}
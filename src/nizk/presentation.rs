@@ -9,9 +9,13 @@
 
 //! Non-interactive zero-knowledge proofs (NIZKs) of credential presentation.
 
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::boxed::Box;
 #[cfg(all(not(feature = "std"), feature = "alloc"))]
 use alloc::vec::Vec;
 #[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::boxed::Box;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
 use std::vec::Vec;
 
 #[cfg(all(not(feature = "std"), feature = "alloc"))]
@@ -22,17 +26,30 @@ use core::ops::Index;
 #[cfg(feature = "std")]
 use std::ops::Index;
 
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "std")]
+use std::io::Read;
+#[cfg(feature = "std")]
+use std::io::Write;
+
 use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::ristretto::CompressedRistretto;
 use curve25519_dalek::ristretto::RistrettoPoint;
 
 use rand_core::CryptoRng;
 use rand_core::RngCore;
 
+use zeroize::Zeroize;
+
+use zkp::BatchableProof;
 use zkp::CompactProof;
 use zkp::Transcript;
-// XXX do we want/need batch proof verification?
-// use zkp::toolbox::batch_verifier::BatchVerifier;
 use zkp::toolbox::SchnorrCS;
+use zkp::toolbox::TranscriptProtocol;
+use zkp::toolbox::batch_verifier::BatchVerifier;
+use zkp::toolbox::batch_verifier::PointVar as BatchPointVar;
+use zkp::toolbox::batch_verifier::ScalarVar as BatchScalarVar;
 use zkp::toolbox::prover::Prover;
 use zkp::toolbox::prover::PointVar as ProverPointVar;
 use zkp::toolbox::prover::ScalarVar as ProverScalarVar;
@@ -41,13 +58,40 @@ use zkp::toolbox::verifier::PointVar as VerifierPointVar;
 use zkp::toolbox::verifier::ScalarVar as VerifierScalarVar;
 
 use crate::amacs::Attribute;
+use crate::amacs::AttributeKind;
 use crate::amacs::EncryptedAttribute;
+use crate::amacs::ATTRIBUTE_RECORD_LENGTH;
 use crate::credential::AnonymousCredential;
 use crate::errors::CredentialError;
 use crate::issuer::Issuer;
+use crate::nizk::designated::DesignatedProof;
+use crate::nizk::designated::LinearRelation;
+use crate::nizk::designated::VerifierPublicKey;
 use crate::nizk::encryption::ProofOfEncryption;
-use crate::parameters::{IssuerParameters, SystemParameters};
+use crate::nizk::escrow::AuditCiphertext;
+use crate::nizk::escrow::AuditorPublicKey;
+use crate::nizk::escrow::EscrowStatement;
+use crate::nizk::escrow::ProofOfEscrow;
+use crate::nizk::interactive::InteractivePresentation;
+use crate::nizk::interactive::PresentationChallenge;
+use crate::nizk::interactive::PresentationCommitment;
+use crate::nizk::membership::SetMembershipProof;
+use crate::nizk::membership::SetMembershipStatement;
+use crate::nizk::nullifier::Nullifier;
+use crate::nizk::nullifier::NullifierStatement;
+use crate::nizk::nullifier::ProofOfNullifier;
+use crate::nizk::pseudonym::ProofOfPseudonym;
+use crate::nizk::pseudonym::Pseudonym;
+use crate::nizk::pseudonym::PseudonymStatement;
+use crate::nizk::range::ProofOfRange;
+use crate::nizk::range::RangeStatement;
+use crate::nizk::relation::LinearStatement;
+use crate::parameters::{IssuerParameters, SystemParameters, MAX_NUMBER_OF_ATTRIBUTES};
 use crate::symmetric::Keypair as SymmetricKeypair;
+use crate::token;
+use crate::wire;
+use crate::wire::Sink;
+use crate::wire::WireType;
 
 /// An incredibly shitty and inelegant hashmap-like structure to store/"index"
 /// hidden scalar attributes during construction of a [`ProofOfValidCredential`].
@@ -67,6 +111,16 @@ struct VerifierHiddenScalars(Vec<(usize, VerifierScalarVar)>);
 /// [`ProofOfValidCredential`].
 struct VerifierHiddenScalarBasepoints(Vec<(usize, VerifierPointVar)>);
 
+/// An incredibly shitty and inelegant hashmap-like structure to store/"index"
+/// hidden scalar attributes during batch verification of
+/// [`ProofOfValidCredential`]s.
+struct BatchHiddenScalars(Vec<(usize, BatchScalarVar)>);
+
+/// An incredibly shitty and inelegant hashmap-like structure to store/"index"
+/// corresponding hidden scalar basepoints during batch verification of
+/// [`ProofOfValidCredential`]s.
+struct BatchHiddenScalarBasepoints(Vec<(usize, BatchPointVar)>);
+
 macro_rules! construct_hidden_scalar_variant {
     ($scalar_type: ty, $basepoint_type: ty, $scalar_var: ty, $basepoint_var: ty) => {
         impl Index<usize> for $scalar_type {
@@ -111,19 +165,231 @@ macro_rules! construct_hidden_scalar_variant {
 
 construct_hidden_scalar_variant!(ProverHiddenScalars, ProverHiddenScalarBasepoints, ProverScalarVar, ProverPointVar);
 construct_hidden_scalar_variant!(VerifierHiddenScalars, VerifierHiddenScalarBasepoints, VerifierScalarVar, VerifierPointVar);
+construct_hidden_scalar_variant!(BatchHiddenScalars, BatchHiddenScalarBasepoints, BatchScalarVar, BatchPointVar);
+
+/// Decompress a point from a 32-byte slice.
+pub(crate) fn point_from_slice(bytes: &[u8]) -> Result<RistrettoPoint, CredentialError> {
+    let mut chunk = [0u8; 32];
+
+    chunk.copy_from_slice(bytes);
+
+    match CompressedRistretto(chunk).decompress() {
+        Some(point) => Ok(point),
+        None        => Err(CredentialError::PointDecompressionError),
+    }
+}
+
+/// Decode a canonical scalar from a 32-byte slice.
+pub(crate) fn scalar_from_slice(bytes: &[u8]) -> Result<Scalar, CredentialError> {
+    let mut chunk = [0u8; 32];
+
+    chunk.copy_from_slice(bytes);
+
+    match Scalar::from_canonical_bytes(chunk) {
+        Some(scalar) => Ok(scalar),
+        None         => Err(CredentialError::ScalarFormatError),
+    }
+}
+
+/// The proof of knowledge within a [`ProofOfValidCredential`].
+///
+/// Compact proofs are smaller, whereas batchable proofs send the prover's
+/// commitments rather than the challenge, so that many presentations may be
+/// verified at once with [`Issuer::verify_batch`].  Designated proofs are
+/// only convincing to the holder of a designated verifier's secret key, and
+/// are boxed since they are much larger than the others.
+enum PresentationProof {
+    Compact(CompactProof),
+    Batchable(BatchableProof),
+    Designated(Box<DesignatedProof>),
+}
+
+/// Which kind of [`PresentationProof`] to create.
+#[derive(Clone, Copy)]
+enum ProofMode<'a> {
+    Compact,
+    Batchable,
+    Designated(&'a VerifierPublicKey),
+}
+
+/// Presentations which prove the same statement, i.e. which have the same
+/// kinds of attributes and linear relations, and so may be verified together.
+type Batch<'a> = (Vec<AttributeKind>, &'a [LinearStatement], Vec<&'a ProofOfValidCredential>);
+
+/// A credential to be presented, along with the parameters it was issued
+/// under and the symmetric keypair for its hidden group element attributes.
+#[derive(Clone, Copy)]
+struct Presenter<'a> {
+    system_parameters: &'a SystemParameters,
+    issuer_parameters: &'a IssuerParameters,
+    credential: &'a AnonymousCredential,
+    keypair: Option<&'a SymmetricKeypair>,
+}
+
+/// The statements about the hidden attributes of a credential which a
+/// presentation proves alongside its knowledge of the credential.
+#[derive(Clone, Copy, Default)]
+pub(crate) struct Statements<'a> {
+    pub(crate) ranges: &'a [RangeStatement],
+    pub(crate) memberships: &'a [SetMembershipStatement],
+    pub(crate) nullifiers: &'a [NullifierStatement],
+    pub(crate) pseudonyms: &'a [PseudonymStatement],
+    pub(crate) escrows: &'a [EscrowStatement],
+    pub(crate) relations: &'a [LinearStatement],
+}
 
 /// A proof-of-knowledge of a valid `Credential` and its attributes,
 /// which may be either hidden or revealed.
 // XXX the commitments should be compressed
 pub struct ProofOfValidCredential {
-    proof: CompactProof,
+    proof: PresentationProof,
     proofs_of_encryption: Vec<(u16, ProofOfEncryption)>,
+    proofs_of_range: Vec<ProofOfRange>,
+    proofs_of_membership: Vec<SetMembershipProof>,
+    proofs_of_nullifier: Vec<ProofOfNullifier>,
+    proofs_of_pseudonym: Vec<ProofOfPseudonym>,
+    proofs_of_escrow: Vec<ProofOfEscrow>,
+    linear_statements: Vec<LinearStatement>,
     encrypted_attributes: Vec<EncryptedAttribute>,
     hidden_scalar_indices: Vec<u16>,
     C_x_0: RistrettoPoint,
     C_x_1: RistrettoPoint,
     C_V:   RistrettoPoint,
-    C_y: Vec<RistrettoPoint>,
+    pub(crate) C_y: Vec<RistrettoPoint>,
+}
+
+/// The rerandomised commitments to a credential's algebraic MAC and
+/// attributes for a single presentation, with their nonce \\(( z \\)).
+///
+/// Computing these is most of the group arithmetic of a presentation, so
+/// they may be precomputed, as in [`ProofOfValidCredential::precompute`].
+pub(crate) struct Commitments {
+    pub(crate) z: Scalar,
+    pub(crate) C_x_0: RistrettoPoint,
+    pub(crate) C_x_1: RistrettoPoint,
+    pub(crate) C_V:   RistrettoPoint,
+    pub(crate) C_y:   Vec<RistrettoPoint>,
+    pub(crate) Z:     RistrettoPoint,
+}
+
+/// Overwrite the nonce of the commitments when they drop out of scope.
+impl Drop for Commitments {
+    fn drop(&mut self) {
+        self.z.zeroize();
+    }
+}
+
+impl Commitments {
+    /// Commit to a `credential` with a fresh nonce, hiding its attributes as
+    /// they are currently marked.
+    pub(crate) fn new<C>(
+        system_parameters: &SystemParameters,
+        issuer_parameters: &IssuerParameters,
+        credential: &AnonymousCredential,
+        csprng: &mut C,
+    ) -> Result<Commitments, CredentialError>
+    where
+        C: RngCore + CryptoRng,
+    {
+        // A credential issued under other parameters would yield a presentation which never verifies.
+        credential.check_parameters(system_parameters)?;
+
+        // Choose a nonce for the commitments.
+        let z: Scalar = Scalar::random(csprng);
+
+        // Commit to the credential attributes.
+        let mut C_y: Vec<RistrettoPoint> = Vec::with_capacity(credential.attributes.len());
+
+        for (i, attribute) in credential.attributes.iter().enumerate() {
+            match attribute {
+                Attribute::PublicPoint(_)  => C_y.push(system_parameters.G_y[i] * z),
+                Attribute::EitherPoint(_)  => C_y.push(system_parameters.G_y[i] * z),
+                Attribute::SecretPoint(p)  => C_y.push(system_parameters.G_y[i] * z + p.M1),
+                Attribute::PublicScalar(_) => C_y.push(system_parameters.G_y[i] * z),
+                Attribute::SecretScalar(m) => C_y.push(system_parameters.G_y[i] * z + system_parameters.G_m[i] * *m),
+            };
+        }
+        let C_x_0: RistrettoPoint = (system_parameters.G_x_0 * z) +  credential.amac.U;
+        let C_x_1: RistrettoPoint = (system_parameters.G_x_1 * z) + (credential.amac.U * credential.amac.t);
+        let C_V:   RistrettoPoint = (system_parameters.G_V   * z) +  credential.amac.V;
+        let Z:     RistrettoPoint =  issuer_parameters.I     * z;
+
+        Ok(Commitments { z, C_x_0, C_x_1, C_V, C_y, Z })
+    }
+}
+
+/// A presentation of a credential which has been computed ahead of time
+/// with [`ProofOfValidCredential::precompute`], save for the Fiat-Shamir
+/// challenge of its proof of knowledge and the responses to it.
+///
+/// The commitments to the credential, the proofs of encryption of its
+/// hidden group element attributes, and the blinding factors of the proof
+/// of knowledge and the commitments to them, are all computed beforehand,
+/// as for an [`InteractivePresentation`], so that
+/// [`PrecomputedPresentation::finalize`] requires only hashing the verifier's
+/// context and the statement into the transcript and a scalar multiplication
+/// for each secret.
+///
+/// # Warning
+///
+/// Answering two challenges with the same blinding factors reveals the
+/// secrets, so a precomputed presentation must only be finalised once,
+/// which is why finalising it consumes it.
+pub struct PrecomputedPresentation {
+    state: InteractivePresentation,
+    commitment: PresentationCommitment,
+    relation: LinearRelation,
+    parameters_fingerprint: [u8; 32],
+}
+
+impl PrecomputedPresentation {
+    /// Finalise this presentation, binding it to a `context` chosen by the
+    /// verifier, e.g. a fresh nonce or challenge.
+    ///
+    /// The context is fed into the proof transcript exactly as the `nonce`
+    /// of [`AnonymousCredential::show_with_nonce`] is, and so the verifier
+    /// must check the presentation with [`Issuer::verify_with_nonce`] and
+    /// the same `context`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the [`ProofOfValidCredential`],
+    /// otherwise a [`CredentialError`].
+    ///
+    /// [`AnonymousCredential::show_with_nonce`]: crate::credential::AnonymousCredential::show_with_nonce
+    /// [`Issuer::verify_with_nonce`]: crate::issuer::Issuer::verify_with_nonce
+    pub fn finalize(self, context: &[u8]) -> Result<ProofOfValidCredential, CredentialError> {
+        let mut transcript = ProofOfValidCredential::transcript(Some(context));
+
+        transcript.append_message(b"parameters", &self.parameters_fingerprint);
+        transcript.domain_sep(b"2019/1416 presentation proof");
+
+        // Answer the Fiat-Shamir challenge, rather than a verifier's, as for a compact presentation.
+        let challenge = self.relation.challenge(&mut transcript, &self.commitment.blinding_commitments);
+        let response = self.state.respond(&PresentationChallenge(challenge));
+        let commitment = self.commitment;
+        let hidden_scalar_indices = commitment.encrypted_attributes.iter().enumerate()
+            .filter(|(_, attribute)| matches!(attribute, EncryptedAttribute::SecretScalar))
+            .map(|(i, _)| i as u16)
+            .collect();
+
+        Ok(ProofOfValidCredential {
+            proof: PresentationProof::Compact(CompactProof { challenge, responses: response.responses }),
+            proofs_of_encryption: commitment.proofs_of_encryption,
+            proofs_of_range: Vec::new(),
+            proofs_of_membership: Vec::new(),
+            proofs_of_nullifier: Vec::new(),
+            proofs_of_pseudonym: Vec::new(),
+            proofs_of_escrow: Vec::new(),
+            linear_statements: Vec::new(),
+            encrypted_attributes: commitment.encrypted_attributes,
+            hidden_scalar_indices,
+            C_x_0: commitment.C_x_0,
+            C_x_1: commitment.C_x_1,
+            C_V: commitment.C_V,
+            C_y: commitment.C_y,
+        })
+    }
 }
 
 impl ProofOfValidCredential {
@@ -146,6 +412,247 @@ impl ProofOfValidCredential {
     where
         C: RngCore + CryptoRng,
     {
+        let presenter = Presenter { system_parameters, issuer_parameters, credential, keypair };
+        let mut transcript = ProofOfValidCredential::transcript(None);
+
+        ProofOfValidCredential::prove_inner(presenter, &mut transcript, Statements::default(),
+                                            ProofMode::Compact, csprng)
+            .map(|(proof, _z)| proof)
+    }
+
+    /// Create a [`ProofOfValidCredential`], as in
+    /// [`ProofOfValidCredential::prove`], which is bound to a `nonce` chosen
+    /// by the verifier.
+    ///
+    /// The `nonce` is fed into the proof transcript, and thus the challenge,
+    /// so the proof only verifies under the same `nonce`, and a captured
+    /// presentation cannot be replayed to a verifier which issues fresh
+    /// nonces.  This is purely a matter of presentation freshness: the
+    /// issuer's tagging of credentials is unaffected.
+    pub(crate) fn prove_with_nonce<C>(
+        system_parameters: &SystemParameters,
+        issuer_parameters: &IssuerParameters,
+        credential: &AnonymousCredential,
+        keypair: Option<&SymmetricKeypair>,
+        nonce: &[u8],
+        csprng: &mut C,
+    ) -> Result<ProofOfValidCredential, CredentialError>
+    where
+        C: RngCore + CryptoRng,
+    {
+        let presenter = Presenter { system_parameters, issuer_parameters, credential, keypair };
+        let mut transcript = ProofOfValidCredential::transcript(Some(nonce));
+
+        ProofOfValidCredential::prove_inner(presenter, &mut transcript, Statements::default(),
+                                            ProofMode::Compact, csprng)
+            .map(|(proof, _z)| proof)
+    }
+
+    /// Create a [`ProofOfValidCredential`], as in
+    /// [`ProofOfValidCredential::prove`], whose Fiat-Shamir challenge is
+    /// derived from the caller's `transcript`.
+    ///
+    /// Anything already appended to the `transcript`, e.g. a session
+    /// identifier or the body of a higher-level protocol's request, is thus
+    /// bound into the proof, which only verifies against a transcript in the
+    /// same state.  The proof is then appended to the `transcript`, so both
+    /// sides' transcripts remain in sync for any subsequent proofs.
+    pub(crate) fn prove_with_transcript<C>(
+        system_parameters: &SystemParameters,
+        issuer_parameters: &IssuerParameters,
+        credential: &AnonymousCredential,
+        keypair: Option<&SymmetricKeypair>,
+        transcript: &mut Transcript,
+        csprng: &mut C,
+    ) -> Result<ProofOfValidCredential, CredentialError>
+    where
+        C: RngCore + CryptoRng,
+    {
+        let presenter = Presenter { system_parameters, issuer_parameters, credential, keypair };
+
+        ProofOfValidCredential::prove_inner(presenter, transcript, Statements::default(),
+                                            ProofMode::Compact, csprng)
+            .map(|(proof, _z)| proof)
+    }
+
+    /// Create a [`ProofOfValidCredential`], as in
+    /// [`ProofOfValidCredential::prove`], which is bound to the `nonce`, if
+    /// any, as in [`ProofOfValidCredential::prove_with_nonce`], and which
+    /// additionally proves the `statements` about the hidden attributes
+    /// within its proof of knowledge of the credential.
+    pub(crate) fn prove_with_statements<C>(
+        system_parameters: &SystemParameters,
+        issuer_parameters: &IssuerParameters,
+        credential: &AnonymousCredential,
+        keypair: Option<&SymmetricKeypair>,
+        nonce: Option<&[u8]>,
+        statements: Statements,
+        csprng: &mut C,
+    ) -> Result<ProofOfValidCredential, CredentialError>
+    where
+        C: RngCore + CryptoRng,
+    {
+        let presenter = Presenter { system_parameters, issuer_parameters, credential, keypair };
+        let mut transcript = ProofOfValidCredential::transcript(nonce);
+
+        ProofOfValidCredential::prove_inner(presenter, &mut transcript, statements,
+                                            ProofMode::Compact, csprng)
+            .map(|(proof, _z)| proof)
+    }
+
+    /// Create a [`ProofOfValidCredential`], as in
+    /// [`ProofOfValidCredential::prove`], additionally returning the nonce
+    /// \\(( z \\)) of its attribute commitments, with which further proofs
+    /// about the commitments may be made.
+    pub(crate) fn prove_with_commitment_nonce<C>(
+        system_parameters: &SystemParameters,
+        issuer_parameters: &IssuerParameters,
+        credential: &AnonymousCredential,
+        keypair: Option<&SymmetricKeypair>,
+        csprng: &mut C,
+    ) -> Result<(ProofOfValidCredential, Scalar), CredentialError>
+    where
+        C: RngCore + CryptoRng,
+    {
+        let presenter = Presenter { system_parameters, issuer_parameters, credential, keypair };
+        let mut transcript = ProofOfValidCredential::transcript(None);
+
+        ProofOfValidCredential::prove_inner(presenter, &mut transcript, Statements::default(),
+                                            ProofMode::Compact, csprng)
+    }
+
+    /// Create a [`ProofOfValidCredential`], as in
+    /// [`ProofOfValidCredential::prove`], whose proof is batchable, so that
+    /// it may be verified along with others with [`Issuer::verify_batch`].
+    ///
+    /// A batchable presentation is \\(( 32 \\)) bytes larger for each
+    /// attribute which isn't a hidden group element, plus another \\(( 32 \\))
+    /// bytes.
+    pub(crate) fn prove_batchable<C>(
+        system_parameters: &SystemParameters,
+        issuer_parameters: &IssuerParameters,
+        credential: &AnonymousCredential,
+        keypair: Option<&SymmetricKeypair>,
+        csprng: &mut C,
+    ) -> Result<ProofOfValidCredential, CredentialError>
+    where
+        C: RngCore + CryptoRng,
+    {
+        let presenter = Presenter { system_parameters, issuer_parameters, credential, keypair };
+        let mut transcript = ProofOfValidCredential::transcript(None);
+
+        ProofOfValidCredential::prove_inner(presenter, &mut transcript, Statements::default(),
+                                            ProofMode::Batchable, csprng)
+            .map(|(proof, _z)| proof)
+    }
+
+    /// Create a [`ProofOfValidCredential`], as in
+    /// [`ProofOfValidCredential::prove`], whose proof is only convincing to
+    /// the holder of the secret key of the designated `verifier`, as in the
+    /// [`designated`](crate::nizk::designated) module.
+    pub(crate) fn prove_designated<C>(
+        system_parameters: &SystemParameters,
+        issuer_parameters: &IssuerParameters,
+        credential: &AnonymousCredential,
+        keypair: Option<&SymmetricKeypair>,
+        verifier: &VerifierPublicKey,
+        csprng: &mut C,
+    ) -> Result<ProofOfValidCredential, CredentialError>
+    where
+        C: RngCore + CryptoRng,
+    {
+        let presenter = Presenter { system_parameters, issuer_parameters, credential, keypair };
+        let mut transcript = ProofOfValidCredential::transcript(None);
+
+        ProofOfValidCredential::prove_inner(presenter, &mut transcript, Statements::default(),
+                                            ProofMode::Designated(verifier), csprng)
+            .map(|(proof, _z)| proof)
+    }
+
+    /// Precompute a presentation of a `credential`, as in
+    /// [`ProofOfValidCredential::prove`], before the verifier's context is
+    /// known, e.g. in the background on a mobile device, so that it may be
+    /// finalised cheaply with [`PrecomputedPresentation::finalize`] once the
+    /// verifier has sent it.
+    ///
+    /// Which attributes are hidden is fixed at this point, according to how
+    /// they are currently marked.  Presentations with additional statements,
+    /// e.g. of ranges or nullifiers, cannot yet be precomputed.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the [`PrecomputedPresentation`],
+    /// otherwise a [`CredentialError`].
+    pub fn precompute<C>(
+        system_parameters: &SystemParameters,
+        issuer_parameters: &IssuerParameters,
+        credential: &AnonymousCredential,
+        keypair: Option<&SymmetricKeypair>,
+        csprng: &mut C,
+    ) -> Result<PrecomputedPresentation, CredentialError>
+    where
+        C: RngCore + CryptoRng,
+    {
+        // The commitments to the credential and to the blindings are those of an interactive presentation.
+        let (state, commitment, relation) = InteractivePresentation::commit_to_relation(system_parameters,
+                                                                                        issuer_parameters, credential,
+                                                                                        keypair, csprng)?;
+
+        Ok(PrecomputedPresentation {
+            state,
+            commitment,
+            relation,
+            parameters_fingerprint: credential.parameters_fingerprint,
+        })
+    }
+
+    /// Create a transcript for a presentation, bound to the verifier's
+    /// `nonce` if there is one.
+    fn transcript(nonce: Option<&[u8]>) -> Transcript {
+        let mut transcript = Transcript::new(b"2019/1416 anonymous credential");
+
+        if let Some(nonce) = nonce {
+            transcript.append_message(b"nonce", nonce);
+        }
+        transcript
+    }
+
+    fn prove_inner<C>(
+        presenter: Presenter,
+        transcript: &mut Transcript,
+        statements: Statements,
+        mode: ProofMode,
+        csprng: &mut C,
+    ) -> Result<(ProofOfValidCredential, Scalar), CredentialError>
+    where
+        C: RngCore + CryptoRng,
+    {
+        let commitments = Commitments::new(presenter.system_parameters, presenter.issuer_parameters,
+                                           presenter.credential, csprng)?;
+
+        ProofOfValidCredential::prove_committed(presenter, &commitments, transcript, statements, mode, csprng)
+    }
+
+    /// Create a [`ProofOfValidCredential`] for a `credential` from its
+    /// precomputed `commitments`, as in
+    /// [`ProofOfValidCredential::prove_inner`].
+    ///
+    /// The `commitments` must not be used for any other presentation, lest
+    /// the two be linkable.
+    fn prove_committed<C>(
+        presenter: Presenter,
+        commitments: &Commitments,
+        transcript: &mut Transcript,
+        statements: Statements,
+        mode: ProofMode,
+        csprng: &mut C,
+    ) -> Result<(ProofOfValidCredential, Scalar), CredentialError>
+    where
+        C: RngCore + CryptoRng,
+    {
+        let Presenter { system_parameters, issuer_parameters, credential, keypair } = presenter;
+        let Statements { ranges, memberships, nullifiers, pseudonyms, escrows, relations } = statements;
+
         // If a keypair was not supplied and we have encrypted group element attributes, bail early.
         if keypair.is_none() {
             for attribute in credential.attributes.iter() {
@@ -156,36 +663,190 @@ impl ProofOfValidCredential {
             }
         }
 
+        // Commitments for other attributes, or other parameters, would yield a presentation which never verifies.
+        if commitments.C_y.len() != credential.attributes.len() {
+            return Err(CredentialError::WrongNumberOfAttributes);
+        }
+
+        let z_:   Scalar = commitments.z;
+        let z_0_: Scalar = (-credential.amac.t * z_).reduce();
+
+        // The secrets, in the order in which the statement allocates them: z, z_0, t, and the hidden scalars.
+        let mut witness = vec![z_, z_0_, credential.amac.t];
+        // XXX assert number of attributes is less than 2^16-1
+        let mut hidden_scalar_indices: Vec<u16> = Vec::new();
+
+        for (i, attribute) in credential.attributes.iter().enumerate() {
+            if let Attribute::SecretScalar(m) = attribute {
+                witness.push(*m);
+                hidden_scalar_indices.push(i as u16);
+            }
+        }
+
+        // Bind the parameters the credential was issued under into the transcript.
+        transcript.append_message(b"parameters", &credential.parameters_fingerprint);
+
+        // Bind the linear relations to be proven into the transcript.
+        for relation in relations.iter() {
+            if !relation.is_satisfied_by(&credential.attributes) {
+                return Err(CredentialError::BadAttribute);
+            }
+            relation.commit(transcript);
+        }
+
+        let kinds: Vec<AttributeKind> = credential.attributes.iter().map(attribute_kind).collect();
+        let publics = PresentationPublics {
+            I: &issuer_parameters.I,
+            Z: &commitments.Z,
+            C_x_0: &commitments.C_x_0,
+            C_x_1: &commitments.C_x_1,
+            C_y: &commitments.C_y,
+            kinds: &kinds,
+        };
+        let relation = designated_relation(system_parameters, &publics, relations);
+
+        // Notes:
+        //
+        // 1. Prover recalculates Z', so it is not sent.
+        // 2. C_V, the commitment to the actual AMAC (recall that the t and U
+        //    values in the AMAC are nonces), is sent, but V is kept private to
+        //    provide anonymity, so we do not prove anything about it.
+        // 3; That z_0 actually equals -tz (mod \ell) is never proven, but this
+        //    should not matter as we prove knowledge of t and z, and constraint
+        //    #2 would never pass verification if either were other than the
+        //    values used to compute z_0.
+        let proof = match mode {
+            ProofMode::Compact => {
+                transcript.domain_sep(b"2019/1416 presentation proof");
+                PresentationProof::Compact(relation.prove_compact(transcript, &witness, csprng))
+            },
+            ProofMode::Batchable => {
+                PresentationProof::Batchable(ProofOfValidCredential::prove_batchable_statement(
+                    system_parameters, issuer_parameters, credential, commitments, relations, transcript))
+            },
+            ProofMode::Designated(verifier) => {
+                // The same statement is proven, but as a disjunction with knowledge of the verifier's secret key.
+                transcript.domain_sep(b"2019/1416 presentation proof");
+                let proof = DesignatedProof::prove(transcript, &relation, &witness, verifier, csprng);
+
+                PresentationProof::Designated(Box::new(proof))
+            },
+        };
+
+        // Construct proofs of correct encryptions for the hidden group attributes.
+        let (encrypted_attributes, proofs_of_encryption) = encrypt_attributes(system_parameters, credential,
+                                                                              keypair, &z_);
+
+        // Construct proofs that the requested hidden scalar attributes lie within their ranges.
+        let mut proofs_of_range: Vec<ProofOfRange> = Vec::with_capacity(ranges.len());
+
+        for statement in ranges.iter() {
+            match credential.attributes.get(statement.index) {
+                Some(Attribute::SecretScalar(m)) => {
+                    proofs_of_range.push(ProofOfRange::prove(system_parameters, statement, m, &z_, csprng)?);
+                },
+                _ => return Err(CredentialError::BadAttribute),
+            }
+        }
+
+        // Construct proofs that the requested hidden scalar attributes are members of their sets.
+        let mut proofs_of_membership: Vec<SetMembershipProof> = Vec::with_capacity(memberships.len());
+
+        for statement in memberships.iter() {
+            match credential.attributes.get(statement.index) {
+                Some(Attribute::SecretScalar(m)) => {
+                    proofs_of_membership.push(SetMembershipProof::prove(system_parameters, statement, m, &z_, csprng)?);
+                },
+                _ => return Err(CredentialError::BadAttribute),
+            }
+        }
+
+        // Construct proofs of correct derivation of the requested nullifiers.
+        let mut proofs_of_nullifier: Vec<ProofOfNullifier> = Vec::with_capacity(nullifiers.len());
+
+        for statement in nullifiers.iter() {
+            match credential.attributes.get(statement.index) {
+                Some(Attribute::SecretScalar(m)) => {
+                    proofs_of_nullifier.push(ProofOfNullifier::prove(system_parameters, statement, m, &z_)?);
+                },
+                _ => return Err(CredentialError::BadAttribute),
+            }
+        }
+
+        // Construct proofs of correct derivation of the requested pseudonyms.
+        let mut proofs_of_pseudonym: Vec<ProofOfPseudonym> = Vec::with_capacity(pseudonyms.len());
+
+        for statement in pseudonyms.iter() {
+            match credential.attributes.get(statement.index) {
+                Some(Attribute::SecretScalar(m)) => {
+                    proofs_of_pseudonym.push(ProofOfPseudonym::prove(system_parameters, statement, m, &z_)?);
+                },
+                _ => return Err(CredentialError::BadAttribute),
+            }
+        }
+
+        // Construct proofs that the requested hidden group element attributes are escrowed to auditors.
+        let mut proofs_of_escrow: Vec<ProofOfEscrow> = Vec::with_capacity(escrows.len());
+
+        for statement in escrows.iter() {
+            match credential.attributes.get(statement.index) {
+                Some(Attribute::SecretPoint(pt)) => {
+                    proofs_of_escrow.push(ProofOfEscrow::prove(system_parameters, statement, pt, &z_, csprng)?);
+                },
+                _ => return Err(CredentialError::BadAttribute),
+            }
+        }
+
+        Ok((ProofOfValidCredential {
+            proof,
+            proofs_of_encryption,
+            proofs_of_range,
+            proofs_of_membership,
+            proofs_of_nullifier,
+            proofs_of_pseudonym,
+            proofs_of_escrow,
+            linear_statements: relations.to_vec(),
+            encrypted_attributes,
+            hidden_scalar_indices,
+            C_x_0: commitments.C_x_0,
+            C_x_1: commitments.C_x_1,
+            C_V: commitments.C_V,
+            C_y: commitments.C_y.clone(),
+        }, z_))
+    }
+
+    /// Create a batchable proof of knowledge of the `credential` from its
+    /// `commitments`, and of the linear `relations` among its hidden scalar
+    /// attributes, continuing the `transcript`.
+    fn prove_batchable_statement(
+        system_parameters: &SystemParameters,
+        issuer_parameters: &IssuerParameters,
+        credential: &AnonymousCredential,
+        commitments: &Commitments,
+        relations: &[LinearStatement],
+        transcript: &mut Transcript,
+    ) -> BatchableProof
+    {
         let NUMBER_OF_ATTRIBUTES = system_parameters.NUMBER_OF_ATTRIBUTES as usize;
 
-        // Choose a nonce for the commitments.
-        let z_:   Scalar = Scalar::random(csprng);
+        let z_:   Scalar = commitments.z;
         let z_0_: Scalar = (-credential.amac.t * z_).reduce();
 
-        // Commit to the credential attributes, and store the hidden scalar attributes in H_s.
-        let mut C_y_: Vec<RistrettoPoint> = Vec::with_capacity(NUMBER_OF_ATTRIBUTES);
+        let C_y_:   &[RistrettoPoint] = &commitments.C_y;
+        let C_x_0_: RistrettoPoint    = commitments.C_x_0;
+        let C_x_1_: RistrettoPoint    = commitments.C_x_1;
+        let Z_:     RistrettoPoint    = commitments.Z;
+
+        // Store the hidden scalar attributes in H_s.
         let mut H_s_: Vec<(usize, RistrettoPoint, Scalar)> = Vec::new();
 
         for (i, attribute) in credential.attributes.iter().enumerate() {
-            match attribute {
-                Attribute::PublicPoint(_)  =>   C_y_.push(system_parameters.G_y[i] * z_),
-                Attribute::EitherPoint(_)  =>   C_y_.push(system_parameters.G_y[i] * z_),
-                Attribute::SecretPoint(p)  =>   C_y_.push(system_parameters.G_y[i] * z_ + p.M1),
-                Attribute::PublicScalar(_) =>   C_y_.push(system_parameters.G_y[i] * z_),
-                Attribute::SecretScalar(m) => {
-                    C_y_.push(system_parameters.G_y[i] * z_ + system_parameters.G_m[i] * *m);
-                    H_s_.push((i, system_parameters.G_m[i], *m));
-                },
-            };
+            if let Attribute::SecretScalar(m) = attribute {
+                H_s_.push((i, system_parameters.G_m[i], *m));
+            }
         }
-        let C_x_0_: RistrettoPoint = (system_parameters.G_x_0 * z_) +  credential.amac.U;
-        let C_x_1_: RistrettoPoint = (system_parameters.G_x_1 * z_) + (credential.amac.U * credential.amac.t);
-        let C_V_:   RistrettoPoint = (system_parameters.G_V   * z_) +  credential.amac.V;
-        let Z_:     RistrettoPoint =  issuer_parameters.I     * z_;
 
-        // Create a transcript and prover.
-        let mut transcript = Transcript::new(b"2019/1416 anonymous credential");
-        let mut prover = Prover::new(b"2019/1416 presentation proof", &mut transcript);
+        let mut prover = Prover::new(b"2019/1416 presentation proof", transcript);
 
         // Feed the domain separators for the Camenisch-Stadler secrets into the protocol transcript.
         let z   = prover.allocate_scalar(b"z", z_);
@@ -193,14 +854,11 @@ impl ProofOfValidCredential {
         let t   = prover.allocate_scalar(b"t", credential.amac.t);
 
         let mut H_s = ProverHiddenScalars(Vec::with_capacity(H_s_.len()));
-        let mut hidden_scalar_indices: Vec<u16> = Vec::new();
-        // XXX assert number of attributes is less than 2^16-1
 
         for (i, _basepoint, scalar) in H_s_.iter() {
             // XXX Fix zkp crate to take Strings
             //H_s.push(prover.allocate_scalar(format!(b"H_s_{}", i), scalar));
             H_s.push((*i, prover.allocate_scalar(b"m", *scalar)));
-            hidden_scalar_indices.push(*i as u16);
         }
 
         // Feed in the domain separators and values for the publics into the transcript.
@@ -271,53 +929,271 @@ impl ProofOfValidCredential {
                 _                          => prover.constrain(*C_y_i, vec![(z, G_y[i])]),
             }
         }
-        // Notes:
-        //
-        // 1. Prover recalculates Z', so it is not sent.
-        // 2. C_V, the commitment to the actual AMAC (recall that the t and U
-        //    values in the AMAC are nonces), is sent, but V is kept private to
-        //    provide anonymity, so we do not prove anything about it.
-        // 3; That z_0 actually equals -tz (mod \ell) is never proven, but this
-        //    should not matter as we prove knowledge of t and z, and constraint
-        //    #2 would never pass verification if either were other than the
-        //    values used to compute z_0.
-        let proof = prover.prove_compact();
 
-        // Construct proofs of correct encryptions for the hidden group attributes.
-        let mut proofs_of_encryption: Vec<(u16, ProofOfEncryption)> = Vec::new();
+        // Constraint #4: Prove the linear relations among hidden scalar attributes.
+        //                G * c + Z = \sum_k (G * a_k) * m_k + I * z
+        for relation in relations.iter() {
+            let (G_c_, G_a_) = relation.points(system_parameters);
+            let (G_c, _) = prover.allocate_point(b"G*c+Z", G_c_ + Z_);
+            let mut rhs: Vec<(ProverScalarVar, ProverPointVar)> = vec![(z, I)];
 
-        // Rebuild the attributes for our credential to send to the verifier.
-        let mut encrypted_attributes: Vec<EncryptedAttribute> = Vec::with_capacity(system_parameters.NUMBER_OF_ATTRIBUTES as usize);
+            for ((i, _), G_a_k) in relation.terms.iter().zip(G_a_.iter()) {
+                let (G_a_k, _) = prover.allocate_point(b"G*a", *G_a_k);
 
-        // XXX don't we also need DLEQ between the plaintext here and that in the commitments above?
-        for (i, attribute) in credential.attributes.iter().enumerate() {
+                rhs.push((H_s[*i], G_a_k));
+            }
+            prover.constrain(G_c, rhs);
+        }
+
+        prover.prove_batchable()
+    }
+
+    /// The credential's attributes as presented, i.e. with hidden attributes
+    /// elided.
+    ///
+    /// These may be checked against an [`AttributeSchema`] with
+    /// [`check_slot_binding`](crate::amacs::check_slot_binding).
+    ///
+    /// [`AttributeSchema`]: crate::amacs::AttributeSchema
+    pub fn attributes(&self) -> &[EncryptedAttribute] {
+        &self.encrypted_attributes[..]
+    }
+
+    /// The range statements proven about the credential's hidden scalar
+    /// attributes, which the verifier should check are those it requires.
+    pub fn range_statements(&self) -> Vec<RangeStatement> {
+        self.proofs_of_range.iter().map(|proof| *proof.statement()).collect()
+    }
+
+    /// The set membership statements proven about the credential's hidden
+    /// scalar attributes, which the verifier should check are those it
+    /// requires.
+    pub fn membership_statements(&self) -> Vec<SetMembershipStatement> {
+        self.proofs_of_membership.iter().map(|proof| proof.statement().clone()).collect()
+    }
+
+    /// The nullifier derived from the hidden attribute at `index` within the
+    /// `context`, if this presentation includes one.
+    ///
+    /// Verifiers which require a nullifier should reject presentations for
+    /// which this is `None`, and those whose nullifier they have already
+    /// seen.
+    pub fn nullifier(&self, index: usize, context: &[u8]) -> Option<Nullifier> {
+        self.proofs_of_nullifier.iter()
+            .find(|proof| proof.statement().index == index && proof.statement().context == context)
+            .map(|proof| proof.nullifier())
+    }
+
+    /// The counter and serial number of a show of a rate-limiting
+    /// [`token`](crate::token) within the `scope`, whose seed is the hidden
+    /// attribute at `seed_index` and whose limit is the hidden attribute at
+    /// `limit_index`, if this presentation includes one whose counter is
+    /// proven to be below the limit.
+    ///
+    /// Verifiers should reject presentations for which this is `None`, and
+    /// those whose serial number they have already seen.
+    pub fn token_serial(&self, seed_index: usize, limit_index: usize, scope: &[u8]) -> Option<(u64, Nullifier)> {
+        self.proofs_of_nullifier.iter()
+            .filter(|proof| proof.statement().index == seed_index)
+            .filter_map(|proof| match token::parse_serial_context(&proof.statement().context) {
+                Some((counter, context_scope)) if context_scope == scope => Some((counter, proof.nullifier())),
+                _ => None,
+            })
+            .find(|(counter, _)| self.proofs_of_range.iter()
+                  .any(|proof| proof.statement().index == limit_index && proof.statement().min > *counter))
+    }
+
+    /// The pseudonym derived from the hidden attribute at `index` within the
+    /// `scope`, if this presentation includes one.
+    ///
+    /// Verifiers which identify users by pseudonym should reject
+    /// presentations for which this is `None`, and should fix the `index` of
+    /// the attribute, e.g. a user id, from which it must be derived.
+    pub fn pseudonym(&self, index: usize, scope: &[u8]) -> Option<Pseudonym> {
+        self.proofs_of_pseudonym.iter()
+            .find(|proof| proof.statement().index == index && proof.statement().scope == scope)
+            .map(|proof| proof.pseudonym())
+    }
+
+    /// The linear relations proven among the credential's hidden scalar
+    /// attributes, which the verifier should check are those it requires.
+    pub fn linear_statements(&self) -> &[LinearStatement] {
+        &self.linear_statements[..]
+    }
+
+    /// The escrow statements proven about the credential's hidden group
+    /// element attributes, which the verifier should check are those it
+    /// requires.
+    pub fn escrow_statements(&self) -> Vec<EscrowStatement> {
+        self.proofs_of_escrow.iter().map(|proof| *proof.statement()).collect()
+    }
+
+    /// The hidden group element attribute at `index`, encrypted to the
+    /// `auditor`, if this presentation escrows it to them.
+    ///
+    /// The verifier should forward this to the auditor, who alone may
+    /// decrypt it.
+    pub fn audit_ciphertext(&self, index: usize, auditor: &AuditorPublicKey) -> Option<AuditCiphertext> {
+        self.proofs_of_escrow.iter()
+            .find(|proof| proof.statement().index == index && proof.statement().auditor == *auditor)
+            .map(|proof| *proof.ciphertext())
+    }
+
+    /// Serialise this presentation in the versioned [`wire`] format.
+    ///
+    /// The body is a little-endian `u32` number of attributes;
+    /// each attribute as presented, as a one byte type (`0` for a
+    /// [`EncryptedAttribute::PublicScalar`], `1` for a
+    /// [`EncryptedAttribute::SecretScalar`], `2` for a
+    /// [`EncryptedAttribute::PublicPoint`], and `4` for a
+    /// [`EncryptedAttribute::SecretPoint`]) and 32 bytes of data, which are
+    /// zeroes for hidden attributes; the commitments \\(( C_{x_0} \\)),
+    /// \\(( C_{x_1} \\)), \\(( C_V \\)), and each \\(( C_{y_i} \\));
+    /// the challenge and responses of the proof, or for a batchable proof its
+    /// commitments and responses; and finally a proof of encryption for each
+    /// hidden group element attribute, in order.  The number of commitments,
+    /// responses, and proofs of encryption are implied by the attributes, and
+    /// a batchable proof is always longer than a compact one, so no other
+    /// lengths are needed.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the serialised presentation, otherwise
+    /// a [`CredentialError::UnserialisableStatements`] if it includes range,
+    /// set membership, nullifier, pseudonym, or escrow proofs, or linear
+    /// relations, or its proof is designated to a verifier.
+    ///
+    /// [`wire`]: crate::wire
+    //
+    // XXX serialise the proofs of the additional statements
+    pub fn to_bytes(&self) -> Result<Vec<u8>, CredentialError> {
+        self.check_serialisable()?;
+
+        let mut bytes: Vec<u8> = Vec::with_capacity(self.sizeof_body());
+
+        self.write_body(&mut bytes);
+
+        Ok(wire::encode(WireType::Presentation, &bytes))
+    }
+
+    /// Stream this presentation to a `writer` in the versioned [`wire`]
+    /// format, as for [`ProofOfValidCredential::to_bytes`], without
+    /// serialising it into memory first.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is empty, otherwise an `io::Error`, of
+    /// kind `InvalidInput` if the presentation includes proofs which cannot
+    /// be serialised.
+    ///
+    /// [`wire`]: crate::wire
+    #[cfg(feature = "std")]
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        self.check_serialisable().map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+
+        wire::write(&mut writer, WireType::Presentation, self.sizeof_body(), |sink| self.write_body(sink))
+    }
+
+    /// Read a presentation written with [`ProofOfValidCredential::write_to`]
+    /// from a `reader`, consuming exactly its encoding.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the presentation, otherwise an
+    /// `io::Error`, of kind `InvalidData` if it is malformed.
+    #[cfg(feature = "std")]
+    pub fn read_from<R: Read>(mut reader: R) -> io::Result<ProofOfValidCredential> {
+        let bytes = wire::read(&mut reader, WireType::Presentation)?;
+
+        ProofOfValidCredential::from_bytes(&bytes).map_err(wire::invalid_data)
+    }
+
+    /// Check that this presentation has no proofs of additional statements,
+    /// nor a designated-verifier proof, which cannot yet be serialised.
+    fn check_serialisable(&self) -> Result<(), CredentialError> {
+        if !self.proofs_of_range.is_empty() || !self.proofs_of_membership.is_empty() ||
+           !self.proofs_of_nullifier.is_empty() || !self.proofs_of_pseudonym.is_empty() ||
+           !self.proofs_of_escrow.is_empty() || !self.linear_statements.is_empty() ||
+           self.designated_verifier().is_some()
+        {
+            return Err(CredentialError::UnserialisableStatements);
+        }
+        Ok(())
+    }
+
+    /// The size of the body of this presentation's serialised form, in bytes.
+    fn sizeof_body(&self) -> usize {
+        sizeof_presentation(self.encrypted_attributes.len(), self.hidden_scalar_indices.len(),
+                            self.proofs_of_encryption.len(), self.is_batchable())
+    }
+
+    /// Write the body of this presentation's serialised form to a [`Sink`].
+    fn write_body<S: Sink>(&self, bytes: &mut S) {
+        bytes.put(&(self.encrypted_attributes.len() as u32).to_le_bytes());
+
+        for attribute in self.encrypted_attributes.iter() {
             match attribute {
-                Attribute::PublicScalar(x) => encrypted_attributes.push(EncryptedAttribute::PublicScalar(*x)),
-                Attribute::SecretScalar(_) => encrypted_attributes.push(EncryptedAttribute::SecretScalar),
-                Attribute::PublicPoint(x)  => encrypted_attributes.push(EncryptedAttribute::PublicPoint(*x)),
-                Attribute::EitherPoint(x)  => encrypted_attributes.push(EncryptedAttribute::PublicPoint(x.M1)),
-                Attribute::SecretPoint(pt) => {
-                    // The .unwrap() here can never panic because we check above that the key isn't
-                    // None if we have encrypted group element attributes.
-                    let proof_of_encryption = ProofOfEncryption::prove(&system_parameters, &pt,
-                                                                       i as u16, &keypair.unwrap(), &z_);
-
-                    proofs_of_encryption.push((i as u16, proof_of_encryption));
-                    encrypted_attributes.push(EncryptedAttribute::SecretPoint);
-                },
+                EncryptedAttribute::PublicScalar(m) => { bytes.put(&[0]); bytes.put(m.as_bytes()); },
+                EncryptedAttribute::SecretScalar    => { bytes.put(&[1]); bytes.put(&[0u8; 32]); },
+                EncryptedAttribute::PublicPoint(M)  => { bytes.put(&[2]); bytes.put(M.compress().as_bytes()); },
+                EncryptedAttribute::SecretPoint     => { bytes.put(&[4]); bytes.put(&[0u8; 32]); },
             }
         }
 
-        Ok(ProofOfValidCredential {
-            proof: proof,
-            proofs_of_encryption: proofs_of_encryption,
-            encrypted_attributes: encrypted_attributes,
-            hidden_scalar_indices: hidden_scalar_indices,
-            C_x_0: C_x_0_,
-            C_x_1: C_x_1_,
-            C_V: C_V_,
-            C_y: C_y_,
-        })
+        bytes.put(self.C_x_0.compress().as_bytes());
+        bytes.put(self.C_x_1.compress().as_bytes());
+        bytes.put(self.C_V.compress().as_bytes());
+
+        for C_y_i in self.C_y.iter() {
+            bytes.put(C_y_i.compress().as_bytes());
+        }
+
+        let responses = match &self.proof {
+            PresentationProof::Compact(proof) => {
+                bytes.put(proof.challenge.as_bytes());
+                &proof.responses
+            },
+            PresentationProof::Batchable(proof) => {
+                for commitment in proof.commitments.iter() {
+                    bytes.put(commitment.as_bytes());
+                }
+                &proof.responses
+            },
+            PresentationProof::Designated(_) => unreachable!("designated proofs are checked to be unserialisable"),
+        };
+
+        for response in responses.iter() {
+            bytes.put(response.as_bytes());
+        }
+
+        for (_i, proof_of_encryption) in self.proofs_of_encryption.iter() {
+            bytes.put(&proof_of_encryption.to_bytes());
+        }
+    }
+
+    /// Deserialise a presentation previously serialised with
+    /// [`ProofOfValidCredential::to_bytes`].
+    ///
+    /// To inspect the presented attributes before decoding the whole
+    /// presentation, use a [`PresentationRef`] instead.
+    pub fn from_bytes(bytes: &[u8]) -> Result<ProofOfValidCredential, CredentialError> {
+        PresentationRef::from_bytes(bytes)?.to_owned()
+    }
+
+    /// Whether this presentation's proof is batchable, i.e. it was created
+    /// with [`AnonymousCredential::show_batchable`].
+    pub fn is_batchable(&self) -> bool {
+        matches!(self.proof, PresentationProof::Batchable(_))
+    }
+
+    /// The public key of the verifier to whom this presentation's proof is
+    /// designated, if it was created with
+    /// [`AnonymousCredential::show_designated`].
+    pub fn designated_verifier(&self) -> Option<&VerifierPublicKey> {
+        match &self.proof {
+            PresentationProof::Designated(proof) => Some(&proof.verifier),
+            _ => None,
+        }
     }
 
     /// Verify a `ProofOfValidCredential`.
@@ -326,34 +1202,270 @@ impl ProofOfValidCredential {
         issuer: &Issuer,
     ) -> Result<(), CredentialError>
     {
-        let NUMBER_OF_ATTRIBUTES = issuer.system_parameters.NUMBER_OF_ATTRIBUTES as usize;
+        self.verify_inner(issuer, &mut ProofOfValidCredential::transcript(None), None)
+    }
 
-        // Recompute the prover's Z value.
-        //
-        // Let \mathcal{H} denote the set of hidden attributes, both those which are group elements
-        // and those which are scalars.
-        //
-        // Let M_i be a revealed group element attribute, if so, and otherwise if a revealed scalar
-        // attribute, m_i, then let M_i be G_m_i * m_i.
-        //
-        // Z = C_V - (W + C_x0 * x0 + C_x1 * x1 +
-        //            \sigma_{i \in \mathcal{H}}{C_y_i * y_i} +
-        //            \sigma_{i \notin \mathcal{H}}{(C_y_i + M_i) * y_i})
-        let mut Z_ = self.C_V - issuer.amacs_key.W - (self.C_x_0 * issuer.amacs_key.x_0) - (self.C_x_1 * issuer.amacs_key.x_1);
+    /// Verify a `ProofOfValidCredential` which was created with
+    /// [`ProofOfValidCredential::prove_with_nonce`] for this `nonce`.
+    pub(crate) fn verify_with_nonce(
+        &self,
+        issuer: &Issuer,
+        nonce: &[u8],
+    ) -> Result<(), CredentialError>
+    {
+        self.verify_inner(issuer, &mut ProofOfValidCredential::transcript(Some(nonce)), None)
+    }
 
-        for (i, attribute) in self.encrypted_attributes.iter().enumerate() {
-            let x = match attribute {
-                EncryptedAttribute::PublicScalar(m_i) => self.C_y[i] + (issuer.system_parameters.G_m[i] * m_i),
-                EncryptedAttribute::SecretScalar      => self.C_y[i],
-                EncryptedAttribute::PublicPoint(M_i)  => self.C_y[i] + M_i,
-                EncryptedAttribute::SecretPoint       => self.C_y[i],
+    /// Verify a `ProofOfValidCredential` which was created with
+    /// [`ProofOfValidCredential::prove_with_transcript`] for a
+    /// `transcript` in the same state as this one.
+    pub(crate) fn verify_with_transcript(
+        &self,
+        issuer: &Issuer,
+        transcript: &mut Transcript,
+    ) -> Result<(), CredentialError>
+    {
+        self.verify_inner(issuer, transcript, None)
+    }
+
+    /// Verify a `ProofOfValidCredential` which was created with
+    /// [`ProofOfValidCredential::prove_designated`] for the `verifier`.
+    ///
+    /// Presentations whose proofs are not designated to the `verifier` are
+    /// rejected, as are designated presentations by every other method of
+    /// verification.
+    pub(crate) fn verify_designated(
+        &self,
+        issuer: &Issuer,
+        verifier: &VerifierPublicKey,
+    ) -> Result<(), CredentialError>
+    {
+        self.verify_inner(issuer, &mut ProofOfValidCredential::transcript(None), Some(verifier))
+    }
+
+    /// Verify many `presentations` at once.
+    ///
+    /// Batchable presentations with the same kinds of attributes and linear
+    /// relations prove the same statement, so their proofs are checked together with a single
+    /// multiscalar multiplication, whereas compact presentations are
+    /// verified individually.
+    pub(crate) fn verify_batch(
+        issuer: &Issuer,
+        presentations: &[ProofOfValidCredential],
+    ) -> Result<(), CredentialError>
+    {
+        let mut batches: Vec<Batch> = Vec::new();
+
+        for presentation in presentations.iter() {
+            if !presentation.is_batchable() {
+                presentation.verify(issuer)?;
+                continue;
+            }
+
+            let kinds: Vec<AttributeKind> = presentation.encrypted_attributes.iter().map(|a| a.kind()).collect();
+
+            let relations = presentation.linear_statements();
+
+            match batches.iter_mut().find(|(batch_kinds, batch_relations, _)| *batch_kinds == kinds &&
+                                          *batch_relations == relations) {
+                Some((_, _, batch)) => batch.push(presentation),
+                None                => batches.push((kinds, relations, vec![presentation])),
+            }
+        }
+
+        for (_kinds, _relations, batch) in batches.iter() {
+            ProofOfValidCredential::verify_batch_of_kind(issuer, batch)?;
+
+            for presentation in batch.iter() {
+                presentation.verify_statements(issuer)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Verify the batchable proofs of a `batch` of presentations which all
+    /// have the same kinds of attributes and linear relations, as in
+    /// [`ProofOfValidCredential::verify_inner`].
+    fn verify_batch_of_kind(
+        issuer: &Issuer,
+        batch: &[&ProofOfValidCredential],
+    ) -> Result<(), CredentialError>
+    {
+        let first = batch[0];
+        let instances = |f: &dyn Fn(&ProofOfValidCredential) -> RistrettoPoint| -> Vec<CompressedRistretto> {
+            batch.iter().map(|presentation| f(presentation).compress()).collect()
+        };
+
+        let mut proofs: Vec<BatchableProof> = Vec::with_capacity(batch.len());
+
+        for presentation in batch.iter() {
+            match &presentation.proof {
+                PresentationProof::Batchable(proof) => proofs.push(proof.clone()),
+                _                                   => return Err(CredentialError::VerificationFailure),
+            }
+        }
+
+        let mut transcripts: Vec<Transcript> = batch.iter().map(|_| ProofOfValidCredential::transcript(None)).collect();
+        let kinds: Vec<AttributeKind> = first.encrypted_attributes.iter().map(|a| a.kind()).collect();
+        let identifier = issuer.system_parameters.identifier();
+
+        // Bind our parameters into the transcripts.
+        for transcript in transcripts.iter_mut() {
+            transcript.append_message(b"parameters", &identifier);
+        }
+
+        // Bind the linear relations to be verified into the transcripts.
+        for relation in first.linear_statements.iter() {
+            if !relation.is_valid(&kinds) {
+                return Err(CredentialError::VerificationFailure);
+            }
+            for transcript in transcripts.iter_mut() {
+                relation.commit(transcript);
+            }
+        }
+
+        let mut verifier = BatchVerifier::new(b"2019/1416 presentation proof", batch.len(),
+                                              transcripts.iter_mut().collect())?;
+
+        // Feed the domain separators for the Camenisch-Stadler secrets into the protocol transcripts.
+        let z   = verifier.allocate_scalar(b"z");
+        let z_0 = verifier.allocate_scalar(b"z_0");
+        let t   = verifier.allocate_scalar(b"t");
+
+        let mut H_s = BatchHiddenScalars(Vec::new());
+
+        for i in first.hidden_scalar_indices.iter() {
+            H_s.push((*i as usize, verifier.allocate_scalar(b"m")));
+        }
+
+        // Feed in the domain separators and values for the publics into the transcripts.
+        let I     = verifier.allocate_static_point(b"I", issuer.issuer_parameters.I.compress())?;
+        let C_x_1 = verifier.allocate_instance_point(b"C_x_1", instances(&|p| p.C_x_1))?;
+        let C_x_0 = verifier.allocate_instance_point(b"C_x_0", instances(&|p| p.C_x_0))?;
+        let G_x_0 = verifier.allocate_static_point(b"G_x_0", issuer.system_parameters.G_x_0.compress())?;
+        let G_x_1 = verifier.allocate_static_point(b"G_x_1", issuer.system_parameters.G_x_1.compress())?;
+
+        let mut C_y: Vec<BatchPointVar> = Vec::with_capacity(first.C_y.len());
+        let mut G_y: Vec<BatchPointVar> = Vec::with_capacity(issuer.system_parameters.G_y.len());
+
+        for (i, attribute) in first.encrypted_attributes.iter().enumerate() {
+            match attribute {
+                EncryptedAttribute::SecretPoint => continue,
+                _ => C_y.push(verifier.allocate_instance_point(b"C_y", instances(&|p| p.C_y[i]))?),
             };
-            Z_ -= x * issuer.amacs_key.y[i];
         }
 
-        // Create a transcript and verifier.
-        let mut transcript = Transcript::new(b"2019/1416 anonymous credential");
-        let mut verifier = Verifier::new(b"2019/1416 presentation proof", &mut transcript);
+        for basepoint in issuer.system_parameters.G_y.iter() {
+            G_y.push(verifier.allocate_static_point(b"G_y", basepoint.compress())?);
+        }
+
+        let mut G_m = BatchHiddenScalarBasepoints(Vec::with_capacity(H_s.0.len()));
+
+        for (i, _) in H_s.0.iter() {
+            G_m.push((*i, verifier.allocate_static_point(b"G_m", issuer.system_parameters.G_m[*i].compress())?));
+        }
+
+        let Z = verifier.allocate_instance_point(b"Z", instances(&|p| p.recompute_Z(issuer)))?;
+
+        // Constraint #1: Z = I * z
+        verifier.constrain(Z, vec![(z, I)]);
+
+        // Constraint #2: C_x_1 = C_x_0 * t + G_x_0 * z_0 + G_x_1 * z
+        verifier.constrain(C_x_1, vec![(t, C_x_0), (z_0, G_x_0), (z, G_x_1)]);
+
+        // Constraint #3: C_y_i = { G_y_i * z + G_m_i * m_i          if i is a hidden scalar attribute
+        //                        { G_y_i * z                        if i is a revealed attribute
+        for (i, C_y_i) in C_y.iter().enumerate() {
+            match first.encrypted_attributes[i] {
+                EncryptedAttribute::SecretPoint  => continue,
+                EncryptedAttribute::SecretScalar => verifier.constrain(*C_y_i, vec![(z, G_y[i]), (H_s[i], G_m[i])]),
+                _                                => verifier.constrain(*C_y_i, vec![(z, G_y[i])]),
+            }
+        }
+
+        // Constraint #4: G * c + Z = \sum_k (G * a_k) * m_k + I * z
+        for relation in first.linear_statements.iter() {
+            let (G_c_, G_a_) = relation.points(&issuer.system_parameters);
+            let G_c = verifier.allocate_instance_point(b"G*c+Z", instances(&|p| G_c_ + p.recompute_Z(issuer)))?;
+            let mut rhs: Vec<(BatchScalarVar, BatchPointVar)> = vec![(z, I)];
+
+            for ((i, _), G_a_k) in relation.terms.iter().zip(G_a_.iter()) {
+                rhs.push((H_s[*i], verifier.allocate_static_point(b"G*a", G_a_k.compress())?));
+            }
+            verifier.constrain(G_c, rhs);
+        }
+
+        verifier.verify_batchable(&proofs).or(Err(CredentialError::VerificationFailure))
+    }
+
+    fn verify_inner(
+        &self,
+        issuer: &Issuer,
+        transcript: &mut Transcript,
+        designated: Option<&VerifierPublicKey>,
+    ) -> Result<(), CredentialError>
+    {
+        let Z_ = self.recompute_Z(issuer);
+        let kinds: Vec<AttributeKind> = self.encrypted_attributes.iter().map(|a| a.kind()).collect();
+
+        // Bind our parameters into the transcript.
+        transcript.append_message(b"parameters", &issuer.system_parameters.identifier());
+
+        // Bind the linear relations to be verified into the transcript.
+        for relation in self.linear_statements.iter() {
+            if !relation.is_valid(&kinds) {
+                return Err(CredentialError::VerificationFailure);
+            }
+            relation.commit(transcript);
+        }
+
+        if kinds.len() != self.C_y.len() || kinds.len() > issuer.system_parameters.G_y.len() {
+            return Err(CredentialError::WrongNumberOfAttributes);
+        }
+
+        let publics = PresentationPublics {
+            I: &issuer.issuer_parameters.I,
+            Z: &Z_,
+            C_x_0: &self.C_x_0,
+            C_x_1: &self.C_x_1,
+            C_y: &self.C_y,
+            kinds: &kinds,
+        };
+        let relation = designated_relation(&issuer.system_parameters, &publics, &self.linear_statements);
+
+        match (&self.proof, designated) {
+            (PresentationProof::Compact(proof), None) => {
+                transcript.domain_sep(b"2019/1416 presentation proof");
+                relation.verify_compact(transcript, proof)?;
+            },
+            (PresentationProof::Batchable(proof), None) => {
+                self.verify_batchable_statement(issuer, transcript, &Z_, proof)?;
+            },
+            (PresentationProof::Designated(proof), Some(designated)) => {
+                transcript.domain_sep(b"2019/1416 presentation proof");
+                proof.verify(transcript, &relation, designated)?;
+            },
+            _ => return Err(CredentialError::VerificationFailure),
+        }
+
+        self.verify_statements(issuer)
+    }
+
+    /// Verify the batchable `proof` of knowledge of the credential, as
+    /// created by [`ProofOfValidCredential::prove_batchable_statement`],
+    /// continuing the `transcript`.
+    fn verify_batchable_statement(
+        &self,
+        issuer: &Issuer,
+        transcript: &mut Transcript,
+        Z_: &RistrettoPoint,
+        proof: &BatchableProof,
+    ) -> Result<(), CredentialError>
+    {
+        let NUMBER_OF_ATTRIBUTES = issuer.system_parameters.NUMBER_OF_ATTRIBUTES as usize;
+
+        let mut verifier = Verifier::new(b"2019/1416 presentation proof", transcript);
 
         // Feed the domain separators for the Camenisch-Stadler secrets into the protocol transcript.
         let z   = verifier.allocate_scalar(b"z");
@@ -432,17 +1544,491 @@ impl ProofOfValidCredential {
             }
         }
 
-        verifier.verify_compact(&self.proof).or(Err(CredentialError::VerificationFailure))?;
+        // Constraint #4: Prove the linear relations among hidden scalar attributes.
+        //                G * c + Z = \sum_k (G * a_k) * m_k + I * z
+        for relation in self.linear_statements.iter() {
+            let (G_c_, G_a_) = relation.points(&issuer.system_parameters);
+            let G_c = verifier.allocate_point(b"G*c+Z", (G_c_ + Z_).compress())?;
+            let mut rhs: Vec<(VerifierScalarVar, VerifierPointVar)> = vec![(z, I)];
+
+            for ((i, _), G_a_k) in relation.terms.iter().zip(G_a_.iter()) {
+                rhs.push((H_s[*i], verifier.allocate_point(b"G*a", G_a_k.compress())?));
+            }
+            verifier.constrain(G_c, rhs);
+        }
+
+        verifier.verify_batchable(proof).or(Err(CredentialError::VerificationFailure))
+    }
+
+    /// Recompute the prover's \\(( Z \\)) value from the issuer's secret key.
+    fn recompute_Z(&self, issuer: &Issuer) -> RistrettoPoint {
+        // Let \mathcal{H} denote the set of hidden attributes, both those which are group elements
+        // and those which are scalars.
+        //
+        // Let M_i be a revealed group element attribute, if so, and otherwise if a revealed scalar
+        // attribute, m_i, then let M_i be G_m_i * m_i.
+        //
+        // Z = C_V - (W + C_x0 * x0 + C_x1 * x1 +
+        //            \sigma_{i \in \mathcal{H}}{C_y_i * y_i} +
+        //            \sigma_{i \notin \mathcal{H}}{(C_y_i + M_i) * y_i})
+        let mut Z_ = self.C_V - issuer.amacs_key.W - (self.C_x_0 * issuer.amacs_key.x_0) - (self.C_x_1 * issuer.amacs_key.x_1);
+
+        for (i, attribute) in self.encrypted_attributes.iter().enumerate() {
+            let x = match attribute {
+                EncryptedAttribute::PublicScalar(m_i) => self.C_y[i] + (issuer.system_parameters.G_m[i] * m_i),
+                EncryptedAttribute::SecretScalar      => self.C_y[i],
+                EncryptedAttribute::PublicPoint(M_i)  => self.C_y[i] + M_i,
+                EncryptedAttribute::SecretPoint       => self.C_y[i],
+            };
+            Z_ -= x * issuer.amacs_key.y[i];
+        }
+
+        Z_
+    }
 
+    /// Verify the proofs of encryption and of any additional statements,
+    /// after the proof of knowledge of the credential has been verified.
+    fn verify_statements(&self, issuer: &Issuer) -> Result<(), CredentialError> {
         // Check the proofs of correct encryptions and fail if any cannot be verified.
         for (_i, proof_of_encryption) in self.proofs_of_encryption.iter() {
             proof_of_encryption.verify(&issuer.system_parameters)?;
         }
 
+        // Check the proofs that hidden scalar attributes lie within ranges.
+        for proof_of_range in self.proofs_of_range.iter() {
+            let index = proof_of_range.statement().index;
+
+            match self.encrypted_attributes.get(index) {
+                Some(EncryptedAttribute::SecretScalar) => proof_of_range.verify(&issuer.system_parameters, &self.C_y[index])?,
+                _ => return Err(CredentialError::VerificationFailure),
+            }
+        }
+
+        // Check the proofs that hidden scalar attributes are members of sets.
+        for proof_of_membership in self.proofs_of_membership.iter() {
+            let index = proof_of_membership.statement().index;
+
+            match self.encrypted_attributes.get(index) {
+                Some(EncryptedAttribute::SecretScalar) => proof_of_membership.verify(&issuer.system_parameters, &self.C_y[index])?,
+                _ => return Err(CredentialError::VerificationFailure),
+            }
+        }
+
+        // Check the proofs that hidden group element attributes are escrowed to auditors.
+        for proof_of_escrow in self.proofs_of_escrow.iter() {
+            let index = proof_of_escrow.statement().index;
+            let proof_of_encryption = self.proofs_of_encryption.iter().find(|(i, _)| *i as usize == index);
+
+            match (self.encrypted_attributes.get(index), proof_of_encryption) {
+                (Some(EncryptedAttribute::SecretPoint), Some((_, encryption))) => {
+                    proof_of_escrow.verify(&issuer.system_parameters, &encryption.C_y_1, &encryption.C_y_3)?
+                },
+                _ => return Err(CredentialError::VerificationFailure),
+            }
+        }
+
+        // Check the proofs of correct derivation of nullifiers.
+        for proof_of_nullifier in self.proofs_of_nullifier.iter() {
+            let index = proof_of_nullifier.statement().index;
+
+            match self.encrypted_attributes.get(index) {
+                Some(EncryptedAttribute::SecretScalar) => proof_of_nullifier.verify(&issuer.system_parameters, &self.C_y[index])?,
+                _ => return Err(CredentialError::VerificationFailure),
+            }
+        }
+
+        // Check the proofs of correct derivation of pseudonyms.
+        for proof_of_pseudonym in self.proofs_of_pseudonym.iter() {
+            let index = proof_of_pseudonym.statement().index;
+
+            match self.encrypted_attributes.get(index) {
+                Some(EncryptedAttribute::SecretScalar) => proof_of_pseudonym.verify(&issuer.system_parameters, &self.C_y[index])?,
+                _ => return Err(CredentialError::VerificationFailure),
+            }
+        }
+
         Ok(())
     }
 }
 
+/// The kind of an `attribute` upon presentation, treating an
+/// [`Attribute::EitherPoint`] as revealed.
+pub(crate) fn attribute_kind(attribute: &Attribute) -> AttributeKind {
+    match attribute {
+        Attribute::PublicScalar(_) => AttributeKind::PublicScalar,
+        Attribute::SecretScalar(_) => AttributeKind::SecretScalar,
+        Attribute::PublicPoint(_)  => AttributeKind::PublicPoint,
+        Attribute::EitherPoint(_)  => AttributeKind::PublicPoint,
+        Attribute::SecretPoint(_)  => AttributeKind::SecretPoint,
+    }
+}
+
+/// Rebuild the attributes of a `credential` to send to the verifier, and
+/// construct proofs of correct encryption for its hidden group element
+/// attributes under the commitment nonce `z`.
+///
+/// # Panics
+///
+/// If there are hidden group element attributes and no `keypair`, which
+/// callers check beforehand.
+pub(crate) fn encrypt_attributes(
+    system_parameters: &SystemParameters,
+    credential: &AnonymousCredential,
+    keypair: Option<&SymmetricKeypair>,
+    z: &Scalar,
+) -> (Vec<EncryptedAttribute>, Vec<(u16, ProofOfEncryption)>)
+{
+    let mut proofs_of_encryption: Vec<(u16, ProofOfEncryption)> = Vec::new();
+    let mut encrypted_attributes: Vec<EncryptedAttribute> = Vec::with_capacity(credential.attributes.len());
+
+    // XXX don't we also need DLEQ between the plaintext here and that in the commitments above?
+    for (i, attribute) in credential.attributes.iter().enumerate() {
+        match attribute {
+            Attribute::PublicScalar(x) => encrypted_attributes.push(EncryptedAttribute::PublicScalar(*x)),
+            Attribute::SecretScalar(_) => encrypted_attributes.push(EncryptedAttribute::SecretScalar),
+            Attribute::PublicPoint(x)  => encrypted_attributes.push(EncryptedAttribute::PublicPoint(*x)),
+            Attribute::EitherPoint(x)  => encrypted_attributes.push(EncryptedAttribute::PublicPoint(x.M1)),
+            Attribute::SecretPoint(pt) => {
+                let proof_of_encryption = ProofOfEncryption::prove(system_parameters, pt, i as u16,
+                                                                   keypair.unwrap(), z);
+
+                proofs_of_encryption.push((i as u16, proof_of_encryption));
+                encrypted_attributes.push(EncryptedAttribute::SecretPoint);
+            },
+        }
+    }
+
+    (encrypted_attributes, proofs_of_encryption)
+}
+
+/// The public values of a presentation of one credential, about which its
+/// statement is made.
+pub(crate) struct PresentationPublics<'a> {
+    pub(crate) I:     &'a RistrettoPoint,
+    pub(crate) Z:     &'a RistrettoPoint,
+    pub(crate) C_x_0: &'a RistrettoPoint,
+    pub(crate) C_x_1: &'a RistrettoPoint,
+    pub(crate) C_y:   &'a [RistrettoPoint],
+    pub(crate) kinds: &'a [AttributeKind],
+}
+
+/// Constrain the `relation` to prove knowledge of a valid credential with the
+/// `publics`, i.e. constraints #1 to #3 of
+/// [`ProofOfValidCredential::prove_inner`].
+///
+/// The `secrets` are the indices within the `relation` of the scalars
+/// \\(( z \\)), \\(( z_0 \\)), \\(( t \\)), and then of each hidden scalar
+/// attribute, in order, so that secrets may be shared between credentials.
+///
+/// # Returns
+///
+/// The index within the `relation` of the point \\(( I \\)).
+pub(crate) fn constrain_credential(
+    relation: &mut LinearRelation,
+    system_parameters: &SystemParameters,
+    publics: &PresentationPublics,
+    secrets: &[usize],
+) -> usize
+{
+    let (z, z_0, t) = (secrets[0], secrets[1], secrets[2]);
+    let mut m = 3;
+
+    let I     = relation.allocate_point(*publics.I);
+    let Z     = relation.allocate_point(*publics.Z);
+    let C_x_0 = relation.allocate_point(*publics.C_x_0);
+    let C_x_1 = relation.allocate_point(*publics.C_x_1);
+    let G_x_0 = relation.allocate_point(system_parameters.G_x_0);
+    let G_x_1 = relation.allocate_point(system_parameters.G_x_1);
+
+    // Constraint #1: Z = I * z
+    relation.constrain(Z, vec![(z, I)]);
+
+    // Constraint #2: C_x_1 = C_x_0 * t + G_x_0 * z_0 + G_x_1 * z
+    relation.constrain(C_x_1, vec![(t, C_x_0), (z_0, G_x_0), (z, G_x_1)]);
+
+    // Constraint #3: C_y_i = G_y_i * z + G_m_i * m_i, or G_y_i * z if revealed.
+    for (i, kind) in publics.kinds.iter().enumerate() {
+        if *kind == AttributeKind::SecretPoint {
+            continue;
+        }
+
+        let C_y_i = relation.allocate_point(publics.C_y[i]);
+        let G_y_i = relation.allocate_point(system_parameters.G_y[i]);
+
+        match kind {
+            AttributeKind::SecretScalar => {
+                let G_m_i = relation.allocate_point(system_parameters.G_m[i]);
+
+                relation.constrain(C_y_i, vec![(z, G_y_i), (secrets[m], G_m_i)]);
+                m += 1;
+            },
+            _ => relation.constrain(C_y_i, vec![(z, G_y_i)]),
+        }
+    }
+
+    I
+}
+
+/// The statement proven by a compact, designated-verifier, precomputed, or
+/// interactive presentation, i.e. the same constraints as are proven by
+/// [`ProofOfValidCredential::prove_inner`], over the secrets \\(( z \\)),
+/// \\(( z_0 \\)), \\(( t \\)), and then each hidden scalar attribute, in
+/// order.
+pub(crate) fn designated_relation(
+    system_parameters: &SystemParameters,
+    publics: &PresentationPublics,
+    relations: &[LinearStatement],
+) -> LinearRelation
+{
+    let hidden_scalars = publics.kinds.iter().filter(|kind| **kind == AttributeKind::SecretScalar).count();
+    let secrets: Vec<usize> = (0..3 + hidden_scalars).collect();
+    let mut relation = LinearRelation::new(secrets.len());
+
+    let I = constrain_credential(&mut relation, system_parameters, publics, &secrets);
+
+    // The scalar of each hidden scalar attribute, by the index of the attribute.
+    let hidden: Vec<usize> = publics.kinds.iter().enumerate()
+        .filter(|(_, kind)| **kind == AttributeKind::SecretScalar)
+        .map(|(i, _)| i)
+        .collect();
+    let z = secrets[0];
+
+    // Constraint #4: G * c + Z = \sum_k (G * a_k) * m_k + I * z
+    //
+    // The relations are checked beforehand to be over hidden scalar attributes only.
+    for statement in relations.iter() {
+        let (G_c, G_a) = statement.points(system_parameters);
+        let G_c = relation.allocate_point(G_c + publics.Z);
+        let mut rhs: Vec<(usize, usize)> = vec![(z, I)];
+
+        for ((i, _), G_a_k) in statement.terms.iter().zip(G_a) {
+            if let Some(m_i) = hidden.iter().position(|j| j == i) {
+                rhs.push((secrets[3 + m_i], relation.allocate_point(G_a_k)));
+            }
+        }
+        relation.constrain(G_c, rhs);
+    }
+
+    relation
+}
+
+/// Determine the size of a serialised presentation with some number of
+/// attributes, of which `hidden_scalars` are hidden scalars and
+/// `hidden_points` are hidden group elements, in bytes.
+fn sizeof_presentation(attributes: usize, hidden_scalars: usize, hidden_points: usize, batchable: bool) -> usize {
+    4 + (ATTRIBUTE_RECORD_LENGTH + 32) * attributes + 32 * 3 +
+        32 * (sizeof_proof_prefix(attributes, hidden_points, batchable) + 3 + hidden_scalars) +
+        ProofOfEncryption::SERIALIZED_LEN * hidden_points
+}
+
+/// Determine the number of 32-byte chunks preceding the responses of a
+/// serialised proof, i.e. the challenge of a compact proof, or the
+/// commitments of a batchable one.
+///
+/// A batchable proof has a commitment for each of \\(( Z \\)) and
+/// \\(( C_{x_1} \\)), and for each attribute which isn't a hidden group
+/// element.
+fn sizeof_proof_prefix(attributes: usize, hidden_points: usize, batchable: bool) -> usize {
+    match batchable {
+        true  => 2 + attributes - hidden_points,
+        false => 1,
+    }
+}
+
+/// A serialised [`ProofOfValidCredential`], borrowed from a byte slice.
+///
+/// Parsing only checks the length and attribute types, and allocates
+/// nothing, so that a verifier handling many presentations may cheaply
+/// reject those whose attributes it does not accept.  Each attribute is
+/// decoded when it is accessed, and the commitments and proofs only upon
+/// conversion with [`PresentationRef::to_owned`], which is required for
+/// verification.
+#[derive(Clone, Copy, Debug)]
+pub struct PresentationRef<'a> {
+    body: &'a [u8],
+    number_of_attributes: usize,
+    hidden_scalars: usize,
+    hidden_points: usize,
+    batchable: bool,
+}
+
+impl<'a> PresentationRef<'a> {
+    /// Borrow a presentation serialised with
+    /// [`ProofOfValidCredential::to_bytes`].
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the borrowed presentation, otherwise a
+    /// [`CredentialError::UnsupportedEncoding`] if the [`wire`] header is
+    /// invalid, a [`CredentialError::WrongNumberOfBytes`] if the `bytes` have
+    /// the wrong length for their attributes, a
+    /// [`CredentialError::WrongNumberOfAttributes`] if there are more than
+    /// [`MAX_NUMBER_OF_ATTRIBUTES`] attributes, or a
+    /// [`CredentialError::BadAttribute`] if an attribute has an unknown type.
+    ///
+    /// [`wire`]: crate::wire
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<PresentationRef<'a>, CredentialError> {
+        let bytes = match wire::decode(bytes, WireType::Presentation)? {
+            (0, body) | (1, body) => body,
+            _ => return Err(CredentialError::UnsupportedEncoding),
+        };
+
+        if bytes.len() < 4 {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
+
+        let mut tmp = [0u8; 4];
+
+        tmp.copy_from_slice(&bytes[0..4]);
+
+        let number_of_attributes = u32::from_le_bytes(tmp) as usize;
+
+        if number_of_attributes > MAX_NUMBER_OF_ATTRIBUTES as usize {
+            return Err(CredentialError::WrongNumberOfAttributes);
+        }
+        if bytes.len() < 4 + ATTRIBUTE_RECORD_LENGTH * number_of_attributes {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
+
+        let mut presentation = PresentationRef {
+            body: bytes,
+            number_of_attributes,
+            hidden_scalars: 0,
+            hidden_points: 0,
+            batchable: false,
+        };
+
+        for record in presentation.records() {
+            match record[0] {
+                0 | 2 => continue,
+                1     => presentation.hidden_scalars += 1,
+                4     => presentation.hidden_points += 1,
+                _     => return Err(CredentialError::BadAttribute),
+            }
+        }
+
+        let size = |batchable| sizeof_presentation(number_of_attributes, presentation.hidden_scalars,
+                                                   presentation.hidden_points, batchable);
+
+        if bytes.len() == size(true) {
+            presentation.batchable = true;
+        } else if bytes.len() != size(false) {
+            return Err(CredentialError::WrongNumberOfBytes);
+        }
+        Ok(presentation)
+    }
+
+    /// The serialised attributes.
+    fn records(&self) -> impl Iterator<Item = &'a [u8]> {
+        self.body[4..4 + ATTRIBUTE_RECORD_LENGTH * self.number_of_attributes].chunks(ATTRIBUTE_RECORD_LENGTH)
+    }
+
+    /// The number of attributes on the presented credential.
+    pub fn number_of_attributes(&self) -> usize {
+        self.number_of_attributes
+    }
+
+    /// Decode the presented attribute at `index`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the attribute, otherwise a
+    /// [`CredentialError::WrongNumberOfAttributes`] if there is no such
+    /// attribute, or another [`CredentialError`] if it is malformed.
+    pub fn attribute(&self, index: usize) -> Result<EncryptedAttribute, CredentialError> {
+        let record = match self.records().nth(index) {
+            Some(record) => record,
+            None         => return Err(CredentialError::WrongNumberOfAttributes),
+        };
+
+        match record[0] {
+            0 => Ok(EncryptedAttribute::PublicScalar(scalar_from_slice(&record[1..])?)),
+            1 => Ok(EncryptedAttribute::SecretScalar),
+            2 => Ok(EncryptedAttribute::PublicPoint(point_from_slice(&record[1..])?)),
+            _ => Ok(EncryptedAttribute::SecretPoint),
+        }
+    }
+
+    /// Decode the whole presentation.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the presentation, otherwise a
+    /// [`CredentialError`] if any point or scalar is malformed.
+    pub fn to_owned(&self) -> Result<ProofOfValidCredential, CredentialError> {
+        let n = self.number_of_attributes;
+        let mut encrypted_attributes: Vec<EncryptedAttribute> = Vec::with_capacity(n);
+        let mut hidden_scalar_indices: Vec<u16> = Vec::with_capacity(self.hidden_scalars);
+
+        for i in 0..n {
+            let attribute = self.attribute(i)?;
+
+            if let EncryptedAttribute::SecretScalar = attribute {
+                hidden_scalar_indices.push(i as u16);
+            }
+            encrypted_attributes.push(attribute);
+        }
+
+        let commitments = 4 + ATTRIBUTE_RECORD_LENGTH * n;
+        let chunk = |i: usize| &self.body[commitments + 32 * i..commitments + 32 * (i + 1)];
+
+        let C_x_0 = point_from_slice(chunk(0))?;
+        let C_x_1 = point_from_slice(chunk(1))?;
+        let C_V   = point_from_slice(chunk(2))?;
+
+        let mut C_y: Vec<RistrettoPoint> = Vec::with_capacity(n);
+
+        for i in 0..n {
+            C_y.push(point_from_slice(chunk(3 + i))?);
+        }
+
+        // The proof has one response for each of z, z_0, t, and the hidden scalars.
+        let prefix = sizeof_proof_prefix(n, self.hidden_points, self.batchable);
+        let mut responses: Vec<Scalar> = Vec::with_capacity(3 + self.hidden_scalars);
+
+        for i in 0..3 + self.hidden_scalars {
+            responses.push(scalar_from_slice(chunk(3 + n + prefix + i))?);
+        }
+
+        let proof = match self.batchable {
+            true  => PresentationProof::Batchable(BatchableProof {
+                commitments: (0..prefix).map(|i| CompressedRistretto::from_slice(chunk(3 + n + i))).collect(),
+                responses,
+            }),
+            false => PresentationProof::Compact(CompactProof { challenge: scalar_from_slice(chunk(3 + n))?, responses }),
+        };
+
+        let mut proofs_of_encryption: Vec<(u16, ProofOfEncryption)> = Vec::new();
+        let mut offset = commitments + 32 * (6 + n + prefix + self.hidden_scalars);
+
+        for (i, attribute) in encrypted_attributes.iter().enumerate() {
+            if let EncryptedAttribute::SecretPoint = attribute {
+                let end = offset + ProofOfEncryption::SERIALIZED_LEN;
+
+                proofs_of_encryption.push((i as u16, ProofOfEncryption::from_bytes(&self.body[offset..end], i as u16)?));
+                offset = end;
+            }
+        }
+
+        Ok(ProofOfValidCredential {
+            proof,
+            proofs_of_encryption,
+            proofs_of_range: Vec::new(),
+            proofs_of_membership: Vec::new(),
+            proofs_of_nullifier: Vec::new(),
+            proofs_of_pseudonym: Vec::new(),
+            proofs_of_escrow: Vec::new(),
+            linear_statements: Vec::new(),
+            encrypted_attributes,
+            hidden_scalar_indices,
+            C_x_0,
+            C_x_1,
+            C_V,
+            C_y,
+        })
+    }
+}
+
 #[cfg(test)]
 mod test {
     #[cfg(all(not(feature = "std"), feature = "alloc"))]
@@ -452,6 +2038,7 @@ mod test {
 
     use super::*;
 
+    use crate::nizk::designated::VerifierKeypair;
     use crate::symmetric::Plaintext;
     use crate::user::CredentialRequestConstructor;
 
@@ -583,6 +2170,331 @@ mod test {
         assert!(verification.is_ok());
     }
 
+    #[test]
+    fn credential_proof_bound_to_nonce() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 2).unwrap();
+        let issuer = Issuer::new(&system_parameters, &mut rng);
+        let mut request = CredentialRequestConstructor::new(&system_parameters);
+
+        request.append_revealed_scalar(Scalar::random(&mut rng));
+        request.append_revealed_point(RistrettoPoint::random(&mut rng));
+
+        let credential_request = request.finish();
+        let issuance = issuer.issue(credential_request, &mut rng).unwrap();
+        let mut credential = issuance.verify(&system_parameters, &issuer.issuer_parameters).unwrap();
+
+        credential.hide_attribute(0).unwrap();
+
+        let presentation = credential.show_with_nonce(&system_parameters, &issuer.issuer_parameters,
+                                                      None, b"nonce A", &mut rng).unwrap();
+
+        assert!(issuer.verify_with_nonce(&presentation, b"nonce A").is_ok());
+        assert!(issuer.verify_with_nonce(&presentation, b"nonce B").is_err());
+        assert!(issuer.verify(&presentation).is_err());
+    }
+
+    #[test]
+    fn credential_proofs_bound_to_transcript() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 2).unwrap();
+        let issuer = Issuer::new(&system_parameters, &mut rng);
+        let mut request = CredentialRequestConstructor::new(&system_parameters);
+
+        request.append_revealed_scalar(Scalar::random(&mut rng));
+        request.append_revealed_point(RistrettoPoint::random(&mut rng));
+
+        let session = |id: &'static [u8]| {
+            let mut transcript = Transcript::new(b"example protocol");
+
+            transcript.append_message(b"session", id);
+            transcript
+        };
+
+        let issuance = issuer.issue_with_transcript(request.finish(), &mut session(b"1"), &mut rng).unwrap();
+        let mut credential = issuance.verify_with_transcript(&system_parameters, &issuer.issuer_parameters,
+                                                             &mut session(b"1")).unwrap();
+
+        credential.hide_attribute(0).unwrap();
+
+        let presentation = credential.show_with_transcript(&system_parameters, &issuer.issuer_parameters,
+                                                           None, &mut session(b"2"), &mut rng).unwrap();
+
+        assert!(issuer.verify_with_transcript(&presentation, &mut session(b"2")).is_ok());
+        assert!(issuer.verify_with_transcript(&presentation, &mut session(b"3")).is_err());
+        assert!(issuer.verify(&presentation).is_err());
+
+        // An issuance bound to one session does not verify in another.
+        let mut request = CredentialRequestConstructor::new(&system_parameters);
+
+        request.append_revealed_scalar(Scalar::random(&mut rng));
+        request.append_revealed_point(RistrettoPoint::random(&mut rng));
+
+        let issuance = issuer.issue_with_transcript(request.finish(), &mut session(b"1"), &mut rng).unwrap();
+
+        assert!(issuance.verify_with_transcript(&system_parameters, &issuer.issuer_parameters,
+                                                &mut session(b"2")).is_err());
+    }
+
+    #[test]
+    fn presentation_serialisation_roundtrip() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 4).unwrap();
+        let issuer = Issuer::new(&system_parameters, &mut rng);
+        let mut request = CredentialRequestConstructor::new(&system_parameters);
+        let message = String::from("This is a tsunami alert test..").into_bytes();
+        let m = Scalar::random(&mut rng);
+
+        request.append_revealed_scalar(m);
+        request.append_revealed_scalar(Scalar::random(&mut rng));
+        request.append_revealed_point(RistrettoPoint::random(&mut rng));
+        let _plaintext = request.append_plaintext(&message);
+
+        let issuance = issuer.issue(request.finish(), &mut rng).unwrap();
+        let mut credential = issuance.verify(&system_parameters, &issuer.issuer_parameters).unwrap();
+
+        credential.hide_attribute(1).unwrap();
+        credential.hide_attribute(3).unwrap();
+
+        let (keypair, _) = SymmetricKeypair::generate(&system_parameters, &mut rng);
+        let presentation = credential.show(&system_parameters, &issuer.issuer_parameters, Some(&keypair), &mut rng).unwrap();
+        let mut bytes = presentation.to_bytes().unwrap();
+        let borrowed = PresentationRef::from_bytes(&bytes).unwrap();
+
+        assert_eq!(borrowed.number_of_attributes(), 4);
+        assert!(matches!(borrowed.attribute(0).unwrap(), EncryptedAttribute::PublicScalar(x) if x == m));
+        assert!(matches!(borrowed.attribute(1).unwrap(), EncryptedAttribute::SecretScalar));
+        assert!(matches!(borrowed.attribute(3).unwrap(), EncryptedAttribute::SecretPoint));
+        assert!(issuer.verify(&borrowed.to_owned().unwrap()).is_ok());
+        assert!(issuer.verify(&ProofOfValidCredential::from_bytes(&bytes).unwrap()).is_ok());
+
+        // Truncated presentations are rejected before anything is decoded.
+        assert!(PresentationRef::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+
+        // A corrupted challenge is only detected once the presentation is decoded.
+        let challenge = crate::wire::HEADER_LENGTH + 4 + 33 * 4 + 32 * (3 + 4);
+
+        bytes[challenge..challenge + 32].copy_from_slice(&[0xff; 32]);
+
+        let borrowed = PresentationRef::from_bytes(&bytes).unwrap();
+
+        assert!(borrowed.attribute(0).is_ok());
+        assert!(borrowed.to_owned().is_err());
+
+        // Presentations with additional statements cannot yet be serialised.
+        let presentation = ProofOfValidCredential::prove_with_statements(
+            &system_parameters, &issuer.issuer_parameters, &credential, Some(&keypair), None,
+            Statements { nullifiers: &[NullifierStatement { index: 1, context: b"context".to_vec() }],
+                         ..Statements::default() }, &mut rng).unwrap();
+
+        assert_eq!(presentation.to_bytes().unwrap_err(), CredentialError::UnserialisableStatements);
+    }
+
+    #[test]
+    fn batchable_presentations_verify_in_batches() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 3).unwrap();
+        let issuer = Issuer::new(&system_parameters, &mut rng);
+        let other_issuer = Issuer::new(&system_parameters, &mut rng);
+        let (keypair, _) = SymmetricKeypair::generate(&system_parameters, &mut rng);
+        let mut presentations: Vec<ProofOfValidCredential> = Vec::new();
+
+        for i in 0..6 {
+            let mut request = CredentialRequestConstructor::new(&system_parameters);
+
+            request.append_revealed_scalar(Scalar::random(&mut rng));
+            request.append_revealed_scalar(Scalar::random(&mut rng));
+            let _plaintext = request.append_plaintext(&String::from("This is a tsunami alert test..").into_bytes());
+
+            let issuance = issuer.issue(request.finish(), &mut rng).unwrap();
+            let mut credential = issuance.verify(&system_parameters, &issuer.issuer_parameters).unwrap();
+
+            // Batch presentations with differing kinds of attributes, and a compact one.
+            if i % 2 == 0 {
+                credential.hide_attribute(1).unwrap();
+            }
+            let presentation = match i {
+                5 => credential.show(&system_parameters, &issuer.issuer_parameters, Some(&keypair), &mut rng),
+                _ => credential.show_batchable(&system_parameters, &issuer.issuer_parameters, Some(&keypair), &mut rng),
+            }.unwrap();
+
+            assert_eq!(presentation.is_batchable(), i != 5);
+            assert!(issuer.verify(&presentation).is_ok());
+
+            presentations.push(presentation);
+        }
+
+        assert!(issuer.verify_batch(&presentations).is_ok());
+        assert!(other_issuer.verify_batch(&presentations).is_err());
+
+        // Batchable presentations survive serialisation.
+        let bytes = presentations[0].to_bytes().unwrap();
+        let compact = presentations[5].to_bytes().unwrap();
+
+        assert!(bytes.len() > compact.len());
+        assert!(PresentationRef::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+
+        presentations[2] = ProofOfValidCredential::from_bytes(&bytes).unwrap();
+
+        assert!(presentations[2].is_batchable());
+        assert!(issuer.verify_batch(&presentations).is_ok());
+
+        // A single presentation for another issuer fails the whole batch.
+        let mut request = CredentialRequestConstructor::new(&system_parameters);
+
+        request.append_revealed_scalar(Scalar::random(&mut rng));
+        request.append_revealed_scalar(Scalar::random(&mut rng));
+        let _plaintext = request.append_plaintext(&String::from("This is a tsunami alert test..").into_bytes());
+
+        let issuance = other_issuer.issue(request.finish(), &mut rng).unwrap();
+        let mut credential = issuance.verify(&system_parameters, &other_issuer.issuer_parameters).unwrap();
+
+        credential.hide_attribute(1).unwrap();
+        presentations[4] = credential.show_batchable(&system_parameters, &other_issuer.issuer_parameters,
+                                                     Some(&keypair), &mut rng).unwrap();
+
+        assert!(issuer.verify_batch(&presentations[..4]).is_ok());
+        assert!(issuer.verify_batch(&presentations).is_err());
+    }
+
+    #[test]
+    fn linear_statements_are_bound_to_the_presentation_proof() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 3).unwrap();
+        let issuer = Issuer::new(&system_parameters, &mut rng);
+        let mut request = CredentialRequestConstructor::new(&system_parameters);
+
+        request.append_revealed_scalar(Scalar::from(3u64));
+        request.append_revealed_scalar(Scalar::from(4u64));
+        request.append_revealed_scalar(Scalar::from(7u64));
+
+        let issuance = issuer.issue(request.finish(), &mut rng).unwrap();
+        let mut credential = issuance.verify(&system_parameters, &issuer.issuer_parameters).unwrap();
+
+        credential.hide_attribute(0).unwrap();
+        credential.hide_attribute(2).unwrap();
+
+        let statement = LinearStatement::new().plus(2, Scalar::one()).minus(0).equals(Scalar::from(4u64));
+        let mut presentation = ProofOfValidCredential::prove_with_statements(
+            &system_parameters, &issuer.issuer_parameters, &credential, None, None,
+            Statements { relations: &[statement], ..Statements::default() }, &mut rng).unwrap();
+
+        assert!(issuer.verify(&presentation).is_ok());
+        assert_eq!(presentation.to_bytes().unwrap_err(), CredentialError::UnserialisableStatements);
+
+        // Altering the statement invalidates the proof.
+        presentation.linear_statements[0].constant = Scalar::from(5u64);
+
+        assert!(issuer.verify(&presentation).is_err());
+
+        // Relations may only be over hidden scalar attributes.
+        presentation.linear_statements[0] = LinearStatement::sum(2, 0, 1);
+
+        assert!(issuer.verify(&presentation).is_err());
+
+        // Relations are likewise proven by batchable and designated proofs.
+        let verifier = VerifierKeypair::generate(&mut rng);
+        let statement = LinearStatement::new().plus(0, Scalar::from(7u64)).plus(2, -Scalar::from(3u64));
+        let prove = |mode: ProofMode, rng: &mut _| {
+            let presenter = Presenter {
+                system_parameters: &system_parameters,
+                issuer_parameters: &issuer.issuer_parameters,
+                credential: &credential,
+                keypair: None,
+            };
+
+            ProofOfValidCredential::prove_inner(presenter, &mut ProofOfValidCredential::transcript(None),
+                                                Statements { relations: ::core::slice::from_ref(&statement), ..Statements::default() },
+                                                mode, rng).unwrap().0
+        };
+        let batchable = prove(ProofMode::Batchable, &mut rng);
+        let designated = prove(ProofMode::Designated(&verifier.public), &mut rng);
+
+        assert!(issuer.verify_batch(&[batchable, prove(ProofMode::Batchable, &mut rng)]).is_ok());
+        assert!(issuer.verify_designated(&designated, &verifier.public).is_ok());
+    }
+
+    #[test]
+    fn designated_presentations_verify_only_for_their_verifier() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 4).unwrap();
+        let issuer = Issuer::new(&system_parameters, &mut rng);
+        let verifier = VerifierKeypair::generate(&mut rng);
+        let other = VerifierKeypair::generate(&mut rng);
+        let (keypair, _) = SymmetricKeypair::generate(&system_parameters, &mut rng);
+        let mut request = CredentialRequestConstructor::new(&system_parameters);
+
+        request.append_plaintext(&b"This is a tsunami alert test..".to_vec());
+        request.append_revealed_scalar(Scalar::random(&mut rng));
+        request.append_revealed_scalar(Scalar::random(&mut rng));
+        request.append_revealed_point(RistrettoPoint::random(&mut rng));
+
+        let issuance = issuer.issue(request.finish(), &mut rng).unwrap();
+        let mut credential = issuance.verify(&system_parameters, &issuer.issuer_parameters).unwrap();
+
+        credential.hide_attribute(0).unwrap();
+        credential.hide_attribute(2).unwrap();
+
+        let presentation = credential.show_designated(&system_parameters, &issuer.issuer_parameters, Some(&keypair),
+                                                      &verifier.public, &mut rng).unwrap();
+
+        assert_eq!(presentation.designated_verifier(), Some(&verifier.public));
+        assert!(issuer.verify_designated(&presentation, &verifier.public).is_ok());
+        assert!(issuer.verify_designated(&presentation, &other.public).is_err());
+        assert!(issuer.verify(&presentation).is_err());
+        assert!(issuer.verify_batch(&[presentation]).is_err());
+
+        // Ordinary presentations aren't accepted as designated ones.
+        let presentation = credential.show(&system_parameters, &issuer.issuer_parameters, Some(&keypair), &mut rng).unwrap();
+
+        assert!(issuer.verify_designated(&presentation, &verifier.public).is_err());
+
+        // A designated presentation to another issuer doesn't verify.
+        let other_issuer = Issuer::new(&system_parameters, &mut rng);
+        let presentation = credential.show_designated(&system_parameters, &other_issuer.issuer_parameters,
+                                                      Some(&keypair), &verifier.public, &mut rng).unwrap();
+
+        assert!(issuer.verify_designated(&presentation, &verifier.public).is_err());
+        assert_eq!(presentation.to_bytes().unwrap_err(), CredentialError::UnserialisableStatements);
+    }
+
+    #[test]
+    fn precomputed_presentations_are_finalized_with_a_context() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 4).unwrap();
+        let issuer = Issuer::new(&system_parameters, &mut rng);
+        let (keypair, _) = SymmetricKeypair::generate(&system_parameters, &mut rng);
+        let mut request = CredentialRequestConstructor::new(&system_parameters);
+
+        request.append_revealed_scalar(Scalar::random(&mut rng));
+        request.append_revealed_scalar(Scalar::random(&mut rng));
+        request.append_plaintext(&b"This is a tsunami alert test..".to_vec());
+        request.append_revealed_point(RistrettoPoint::random(&mut rng));
+
+        let issuance = issuer.issue(request.finish(), &mut rng).unwrap();
+        let mut credential = issuance.verify(&system_parameters, &issuer.issuer_parameters).unwrap();
+
+        credential.hide_attribute(0).unwrap();
+        credential.hide_attribute(2).unwrap();
+
+        assert_eq!(ProofOfValidCredential::precompute(&system_parameters, &issuer.issuer_parameters, &credential,
+                                                      None, &mut rng).err(),
+                   Some(CredentialError::NoSymmetricKey));
+
+        let precomputed = ProofOfValidCredential::precompute(&system_parameters, &issuer.issuer_parameters,
+                                                             &credential, Some(&keypair), &mut rng).unwrap();
+        let presentation = precomputed.finalize(b"verifier challenge").unwrap();
+
+        assert!(issuer.verify_with_nonce(&presentation, b"verifier challenge").is_ok());
+        assert!(issuer.verify_with_nonce(&presentation, b"another challenge").is_err());
+        assert!(issuer.verify(&presentation).is_err());
+
+        // The finalised presentation is an ordinary one, and so serialisable.
+        let presentation = ProofOfValidCredential::from_bytes(&presentation.to_bytes().unwrap()).unwrap();
+
+        assert!(issuer.verify_with_nonce(&presentation, b"verifier challenge").is_ok());
+    }
+
     #[test]
     fn credential_proof_scalar_and_group_element_switch() {
         let mut rng = thread_rng();
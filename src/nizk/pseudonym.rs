@@ -0,0 +1,137 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2020 The Brave Authors
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Scope-specific pseudonyms derived from hidden attributes.
+//!
+//! For a hidden scalar attribute \\(( m \\)) in slot \\(( i \\)), e.g. a user
+//! id, whose presentation commitment is \\(( C_y_i = G_y_i * z + G_m_i * m \\)),
+//! and a scope, e.g. a site's domain name, the pseudonym is
+//! \\(( P = H_{scope} * m \\)), where \\(( H_{scope} \\)) is the scope hashed
+//! to a group element.  The presenter proves that the same \\(( m \\)) is
+//! used in both.
+//!
+//! A verifier thus obtains a stable identifier for the holder of a credential
+//! each time it is presented within its scope, while pseudonyms for
+//! different scopes are unlinkable under the decisional Diffie-Hellman
+//! assumption.  Unlike a [`Nullifier`](crate::nizk::nullifier::Nullifier),
+//! which is meant to be seen once, a pseudonym is meant to be seen many
+//! times, and the scopes of each are hashed under different domain
+//! separators so that the one is never the other.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+
+use crate::nizk::derivation::DerivationStatement;
+use crate::nizk::derivation::ProofOfDerivation;
+use crate::nizk::derivation::hash_to_basepoint;
+
+/// A deterministic identifier for the holder of a hidden attribute within a
+/// scope, which is the same each time a credential is presented in that
+/// scope.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub struct Pseudonym(pub(crate) [u8; 32]);
+
+impl Pseudonym {
+    /// Serialise this pseudonym, e.g. for use as a key in a verifier's
+    /// database of accounts.
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0
+    }
+
+    /// Deserialise a pseudonym previously serialised with
+    /// [`Pseudonym::to_bytes`].
+    pub fn from_bytes(bytes: &[u8; 32]) -> Pseudonym {
+        Pseudonym(*bytes)
+    }
+}
+
+/// A request to derive a [`Pseudonym`] from the hidden scalar attribute at
+/// `index`, within a `scope`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PseudonymStatement {
+    /// The index of the attribute, which must be hidden upon presentation.
+    pub index: usize,
+    /// The scope, e.g. the verifier's domain name.
+    pub scope: Vec<u8>,
+}
+
+impl DerivationStatement for PseudonymStatement {
+    const BASEPOINT_DOMAIN: &'static [u8] = b"aeonflux pseudonym scope";
+    const PROOF_DOMAIN: &'static [u8] = b"2019/1416 pseudonym proof";
+
+    fn index(&self) -> usize {
+        self.index
+    }
+
+    fn bytes(&self) -> &[u8] {
+        &self.scope[..]
+    }
+}
+
+/// Hash a pseudonym `scope` to a group element.
+pub(crate) fn scope_basepoint(scope: &[u8]) -> RistrettoPoint {
+    hash_to_basepoint(PseudonymStatement::BASEPOINT_DOMAIN, scope)
+}
+
+/// A non-interactive zero-knowledge proof that a [`Pseudonym`] was correctly
+/// derived from a hidden scalar attribute of a presented credential.
+pub type ProofOfPseudonym = ProofOfDerivation<PseudonymStatement>;
+
+impl ProofOfPseudonym {
+    /// The pseudonym.
+    pub fn pseudonym(&self) -> Pseudonym {
+        Pseudonym(self.derived())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use curve25519_dalek::scalar::Scalar;
+
+    use crate::nizk::nullifier::NullifierStatement;
+    use crate::nizk::nullifier::ProofOfNullifier;
+    use crate::parameters::SystemParameters;
+
+    use rand::thread_rng;
+
+    #[test]
+    fn pseudonym_is_stable_per_scope() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 1).unwrap();
+        let m = Scalar::random(&mut rng);
+        let statement = PseudonymStatement { index: 0, scope: b"example.com".to_vec() };
+        let other = PseudonymStatement { index: 0, scope: b"example.org".to_vec() };
+
+        let z_1 = Scalar::random(&mut rng);
+        let z_2 = Scalar::random(&mut rng);
+        let proof_1 = ProofOfPseudonym::prove(&system_parameters, &statement, &m, &z_1).unwrap();
+        let proof_2 = ProofOfPseudonym::prove(&system_parameters, &statement, &m, &z_2).unwrap();
+        let proof_3 = ProofOfPseudonym::prove(&system_parameters, &other, &m, &z_1).unwrap();
+
+        let C_y_0 = system_parameters.G_y[0] * z_1 + system_parameters.G_m[0] * m;
+
+        assert!(proof_1.verify(&system_parameters, &C_y_0).is_ok());
+        assert!(proof_2.verify(&system_parameters, &C_y_0).is_err());
+        assert_eq!(proof_1.pseudonym(), proof_2.pseudonym());
+        assert_ne!(proof_1.pseudonym(), proof_3.pseudonym());
+
+        // A pseudonym is never a nullifier within a context of the same bytes.
+        let nullifier = ProofOfNullifier::prove(&system_parameters,
+                                                &NullifierStatement { index: 0, context: b"example.com".to_vec() },
+                                                &m, &z_1).unwrap();
+
+        assert_ne!(proof_1.pseudonym().to_bytes(), nullifier.nullifier().to_bytes());
+    }
+}
@@ -0,0 +1,319 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2020 The Brave Authors
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Non-interactive zero-knowledge proofs (NIZKs) that hidden scalar
+//! attributes lie within a range.
+//!
+//! For a hidden scalar attribute \\(( m \\)) in slot \\(( i \\)), whose
+//! presentation commitment is \\(( C_y_i = G_y_i * z + G_m_i * m \\)), and a
+//! public range \\(( [min, max] \\)) with \\(( max - min < 2^n \\)), the
+//! prover decomposes both \\(( m - min \\)) and \\(( max - m \\)) into
+//! \\(( n \\)) bits and commits to each bit \\(( b_j \\)) as
+//! \\(( B_j = G_m_i * b_j + G_y_i * r_j \\)), choosing the blinding factors
+//! such that
+//!
+//! \\(( \sum_j B_j * 2^j = C_y_i - G_m_i * min \\)), and
+//!
+//! \\(( \sum_j D_j * 2^j = G_m_i * max - C_y_i \\)),
+//!
+//! which the verifier checks directly.  Each commitment is then proven to
+//! open to a bit, by proving knowledge of \\(( (b_j, r_j, r'_j) \\)) such that
+//! \\(( B_j = G_m_i * b_j + G_y_i * r_j \\)) and
+//! \\(( B_j = B_j * b_j + G_y_i * r'_j \\)), the latter of which implies
+//! \\(( b_j (1 - b_j) = 0 \\)).  Since both decompositions are less than
+//! \\(( 2^n \\)) and sum to \\(( max - min \\)), with no reduction modulo the
+//! group order, \\(( min \le m \le max \\)).
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use zeroize::Zeroize;
+
+use zkp::CompactProof;
+use zkp::Transcript;
+use zkp::toolbox::SchnorrCS;
+use zkp::toolbox::prover::Prover;
+use zkp::toolbox::verifier::Verifier;
+
+use crate::errors::CredentialError;
+use crate::parameters::SystemParameters;
+
+/// A public statement that the hidden scalar attribute at `index` lies
+/// within the inclusive range `[min, max]`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RangeStatement {
+    /// The index of the attribute, which must be hidden upon presentation.
+    pub index: usize,
+    /// The inclusive lower bound.
+    pub min: u64,
+    /// The inclusive upper bound.
+    pub max: u64,
+}
+
+impl RangeStatement {
+    /// The number of bits in each decomposition needed to prove this
+    /// statement.
+    fn bitsize(&self) -> usize {
+        match (self.max - self.min).leading_zeros() {
+            64 => 1,
+            n  => 64 - n as usize,
+        }
+    }
+
+    /// Bind this statement into a `transcript`.
+    fn commit(&self, transcript: &mut Transcript) {
+        transcript.append_message(b"range index", &(self.index as u32).to_le_bytes());
+        transcript.append_message(b"range min", &self.min.to_le_bytes());
+        transcript.append_message(b"range max", &self.max.to_le_bytes());
+    }
+}
+
+/// A non-interactive zero-knowledge proof that a hidden scalar attribute of
+/// a presented credential satisfies a [`RangeStatement`].
+#[derive(Clone)]
+pub struct ProofOfRange {
+    statement: RangeStatement,
+    B: Vec<RistrettoPoint>,
+    D: Vec<RistrettoPoint>,
+    proof: CompactProof,
+}
+
+/// Decompose `value` into its `n` least significant bits, or return `None` if
+/// it is not less than \\(( 2^n \\)).
+fn decompose(value: &Scalar, n: usize) -> Option<Vec<Scalar>> {
+    let mut bytes = value.to_bytes();
+    let bit = |bytes: &[u8; 32], j: usize| (bytes[j / 8] >> (j % 8)) & 1;
+    let bits = match (n..256).any(|j| bit(&bytes, j) == 1) {
+        true  => None,
+        false => Some((0..n).map(|j| Scalar::from(bit(&bytes, j) as u64)).collect()),
+    };
+
+    bytes.zeroize();
+    bits
+}
+
+/// Choose blinding factors for commitments to `n` bits, such that the
+/// commitments weighted by powers of two are blinded by `blinding`.
+fn blinding_factors<C>(blinding: &Scalar, n: usize, csprng: &mut C) -> Vec<Scalar>
+where
+    C: RngCore + CryptoRng,
+{
+    let mut r: Vec<Scalar> = (0..n).map(|_| Scalar::random(csprng)).collect();
+    let mut sum = Scalar::zero();
+
+    for (j, r_j) in r.iter().enumerate().skip(1) {
+        sum += Scalar::from(1u64 << j) * r_j;
+    }
+    r[0] = blinding - sum;
+    r
+}
+
+/// Compute \\(( \sum_j P_j * 2^j \\)).
+fn weighted_sum(points: &[RistrettoPoint]) -> RistrettoPoint {
+    points.iter().enumerate().fold(RistrettoPoint::identity(), |sum, (j, P_j)| sum + P_j * Scalar::from(1u64 << j))
+}
+
+impl ProofOfRange {
+    /// Create a [`ProofOfRange`] that the hidden scalar `m`, committed to in
+    /// slot `statement.index` with the presentation nonce `z`, satisfies the
+    /// `statement`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the proof, otherwise a
+    /// [`CredentialError::BadAttribute`] if the range is empty or `m` does
+    /// not lie within it.
+    pub(crate) fn prove<C>(
+        system_parameters: &SystemParameters,
+        statement: &RangeStatement,
+        m: &Scalar,
+        z: &Scalar,
+        csprng: &mut C,
+    ) -> Result<ProofOfRange, CredentialError>
+    where
+        C: RngCore + CryptoRng,
+    {
+        if statement.min > statement.max || statement.index >= system_parameters.NUMBER_OF_ATTRIBUTES as usize {
+            return Err(CredentialError::BadAttribute);
+        }
+
+        let n = statement.bitsize();
+        let G_m_i = system_parameters.G_m[statement.index];
+        let G_y_i = system_parameters.G_y[statement.index];
+
+        let mut lower = m - Scalar::from(statement.min);
+        let mut upper = Scalar::from(statement.max) - m;
+
+        let b = decompose(&lower, n);
+        let c = decompose(&upper, n);
+
+        lower.zeroize();
+        upper.zeroize();
+
+        let (mut b, mut c) = match (b, c) {
+            (Some(b), Some(c)) => (b, c),
+            _ => return Err(CredentialError::BadAttribute),
+        };
+
+        let mut r = blinding_factors(z, n, csprng);
+        let mut s = blinding_factors(&-z, n, csprng);
+
+        let B: Vec<RistrettoPoint> = b.iter().zip(r.iter()).map(|(b_j, r_j)| G_m_i * b_j + G_y_i * r_j).collect();
+        let D: Vec<RistrettoPoint> = c.iter().zip(s.iter()).map(|(c_j, s_j)| G_m_i * c_j + G_y_i * s_j).collect();
+
+        let mut transcript = Transcript::new(b"2019/1416 anonymous credential");
+
+        statement.commit(&mut transcript);
+
+        let mut prover = Prover::new(b"2019/1416 range proof", &mut transcript);
+
+        // Feed in the domain separators and values for the publics into the transcript.
+        let (G_m, _) = prover.allocate_point(b"G_m", G_m_i);
+        let (G_y, _) = prover.allocate_point(b"G_y", G_y_i);
+
+        for (bits, blindings, commitments) in [(&b, &r, &B), (&c, &s, &D)].iter() {
+            for ((b_j, r_j), B_j) in bits.iter().zip(blindings.iter()).zip(commitments.iter()) {
+                // Feed the domain separators for the Camenisch-Stadler secrets into the protocol transcript.
+                let bit             = prover.allocate_scalar(b"b", *b_j);
+                let blinding        = prover.allocate_scalar(b"r", *r_j);
+                let blinding_prime  = prover.allocate_scalar(b"r'", (Scalar::one() - b_j) * r_j);
+                let (commitment, _) = prover.allocate_point(b"B", *B_j);
+
+                // Constraint #1: B_j = G_m_i * b_j + G_y_i * r_j
+                prover.constrain(commitment, vec![(bit, G_m), (blinding, G_y)]);
+
+                // Constraint #2: B_j = B_j * b_j + G_y_i * r'_j
+                prover.constrain(commitment, vec![(bit, commitment), (blinding_prime, G_y)]);
+            }
+        }
+
+        let proof = prover.prove_compact();
+
+        b.zeroize();
+        c.zeroize();
+        r.zeroize();
+        s.zeroize();
+
+        Ok(ProofOfRange { statement: *statement, B, D, proof })
+    }
+
+    /// The statement which this proof proves.
+    pub fn statement(&self) -> &RangeStatement {
+        &self.statement
+    }
+
+    /// Verify this [`ProofOfRange`] against the presentation commitment
+    /// `C_y_i` to the attribute in slot `self.statement().index`.
+    pub(crate) fn verify(
+        &self,
+        system_parameters: &SystemParameters,
+        C_y_i: &RistrettoPoint,
+    ) -> Result<(), CredentialError>
+    {
+        let statement = &self.statement;
+
+        if statement.min > statement.max || statement.index >= system_parameters.NUMBER_OF_ATTRIBUTES as usize {
+            return Err(CredentialError::VerificationFailure);
+        }
+
+        let n = statement.bitsize();
+
+        if self.B.len() != n || self.D.len() != n {
+            return Err(CredentialError::VerificationFailure);
+        }
+
+        let G_m_i = system_parameters.G_m[statement.index];
+        let G_y_i = system_parameters.G_y[statement.index];
+
+        if weighted_sum(&self.B) != C_y_i - G_m_i * Scalar::from(statement.min) ||
+           weighted_sum(&self.D) != G_m_i * Scalar::from(statement.max) - C_y_i
+        {
+            return Err(CredentialError::VerificationFailure);
+        }
+
+        let mut transcript = Transcript::new(b"2019/1416 anonymous credential");
+
+        statement.commit(&mut transcript);
+
+        let mut verifier = Verifier::new(b"2019/1416 range proof", &mut transcript);
+
+        // Feed in the domain separators and values for the publics into the transcript.
+        let G_m = verifier.allocate_point(b"G_m", G_m_i.compress())?;
+        let G_y = verifier.allocate_point(b"G_y", G_y_i.compress())?;
+
+        for B_j in self.B.iter().chain(self.D.iter()) {
+            // Feed the domain separators for the Camenisch-Stadler secrets into the protocol transcript.
+            let bit            = verifier.allocate_scalar(b"b");
+            let blinding       = verifier.allocate_scalar(b"r");
+            let blinding_prime = verifier.allocate_scalar(b"r'");
+            let commitment     = verifier.allocate_point(b"B", B_j.compress())?;
+
+            // Constraint #1: B_j = G_m_i * b_j + G_y_i * r_j
+            verifier.constrain(commitment, vec![(bit, G_m), (blinding, G_y)]);
+
+            // Constraint #2: B_j = B_j * b_j + G_y_i * r'_j
+            verifier.constrain(commitment, vec![(bit, commitment), (blinding_prime, G_y)]);
+        }
+
+        verifier.verify_compact(&self.proof).or(Err(CredentialError::VerificationFailure))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    #[test]
+    fn range_proof() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 2).unwrap();
+        let statement = RangeStatement { index: 1, min: 18, max: 130 };
+        let m = Scalar::from(42u64);
+        let z = Scalar::random(&mut rng);
+        let C_y_1 = system_parameters.G_y[1] * z + system_parameters.G_m[1] * m;
+
+        let proof = ProofOfRange::prove(&system_parameters, &statement, &m, &z, &mut rng).unwrap();
+
+        assert!(proof.verify(&system_parameters, &C_y_1).is_ok());
+
+        // The proof doesn't verify against a commitment to another value.
+        let C_y_1 = system_parameters.G_y[1] * z + system_parameters.G_m[1] * Scalar::from(43u64);
+
+        assert!(proof.verify(&system_parameters, &C_y_1).is_err());
+    }
+
+    #[test]
+    fn range_proof_bounds() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 1).unwrap();
+        let statement = RangeStatement { index: 0, min: 18, max: 130 };
+        let z = Scalar::random(&mut rng);
+
+        for m in [18u64, 130].iter() {
+            let C_y_0 = system_parameters.G_y[0] * z + system_parameters.G_m[0] * Scalar::from(*m);
+            let proof = ProofOfRange::prove(&system_parameters, &statement, &Scalar::from(*m), &z, &mut rng).unwrap();
+
+            assert!(proof.verify(&system_parameters, &C_y_0).is_ok());
+        }
+        for m in [17u64, 131].iter() {
+            assert!(ProofOfRange::prove(&system_parameters, &statement, &Scalar::from(*m), &z, &mut rng).is_err());
+        }
+    }
+}
@@ -0,0 +1,133 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2020 The Brave Authors
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Statements of linear relations among hidden scalar attributes, proven
+//! within a presentation's proof of knowledge of its credential.
+//!
+//! A [`LinearStatement`] asserts that
+//! \\(( \sum_k a_k * m_{i_k} = c \\)) for public coefficients
+//! \\(( a_k \\)) and a public constant \\(( c \\)), e.g. that
+//! \\(( m_3 = m_1 + m_2 \\)).  It is proven by adding the constraint
+//!
+//! \\(( G * c + Z = \sum_k (G * a_k) * m_{i_k} + I * z \\))
+//!
+//! to the presentation proof, over the same secrets \\(( m_i \\)) which are
+//! proven to be committed to in each \\(( C_{y_i} \\)), and so in the same
+//! transcript.  Since \\(( Z = I * z \\)) is also proven, this implies the
+//! relation, and the offset by \\(( Z \\)) merely keeps the constraint's
+//! left-hand side from being the identity when \\(( c = 0 \\)).
+//!
+//! Inequalities such as \\(( m_1 \ge k \\)) are instead proven by bit
+//! decomposition, with a [`RangeStatement`].
+//!
+//! [`RangeStatement`]: crate::nizk::range::RangeStatement
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+
+use zkp::Transcript;
+
+use crate::amacs::Attribute;
+use crate::amacs::AttributeKind;
+use crate::parameters::SystemParameters;
+
+/// A public statement that a linear combination of hidden scalar attributes
+/// is equal to a constant.
+///
+/// Statements are built up term by term, e.g.
+/// `LinearStatement::new().plus(1, Scalar::one()).plus(2, Scalar::one()).minus(3)`
+/// states that \\(( m_1 + m_2 - m_3 = 0 \\)).
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct LinearStatement {
+    /// The index of each attribute in the combination, which must be hidden
+    /// upon presentation, and its coefficient.
+    pub terms: Vec<(usize, Scalar)>,
+    /// The constant to which the combination is equal.
+    pub constant: Scalar,
+}
+
+impl LinearStatement {
+    /// Begin a statement with no terms, equal to zero.
+    pub fn new() -> LinearStatement {
+        LinearStatement::default()
+    }
+
+    /// A statement that the hidden scalar attribute at `index` is the sum of
+    /// those at `first` and `second`.
+    pub fn sum(index: usize, first: usize, second: usize) -> LinearStatement {
+        LinearStatement::new().plus(first, Scalar::one()).plus(second, Scalar::one()).minus(index)
+    }
+
+    /// Add the hidden scalar attribute at `index`, multiplied by
+    /// `coefficient`, to the combination.
+    pub fn plus(mut self, index: usize, coefficient: Scalar) -> LinearStatement {
+        self.terms.push((index, coefficient));
+        self
+    }
+
+    /// Subtract the hidden scalar attribute at `index` from the combination.
+    pub fn minus(self, index: usize) -> LinearStatement {
+        self.plus(index, -Scalar::one())
+    }
+
+    /// Set the constant to which the combination is equal.
+    pub fn equals(mut self, constant: Scalar) -> LinearStatement {
+        self.constant = constant;
+        self
+    }
+
+    /// Check that this statement has terms, each of which has a non-zero
+    /// coefficient and is an attribute of one of the presented `kinds` which
+    /// is a hidden scalar.
+    pub(crate) fn is_valid(&self, kinds: &[AttributeKind]) -> bool {
+        !self.terms.is_empty() &&
+            self.terms.iter().all(|(i, a_i)| {
+                *a_i != Scalar::zero() && kinds.get(*i) == Some(&AttributeKind::SecretScalar)
+            })
+    }
+
+    /// Check that the `attributes` of a credential satisfy this statement,
+    /// and that it is valid for them.
+    pub(crate) fn is_satisfied_by(&self, attributes: &[Attribute]) -> bool {
+        let mut sum = -self.constant;
+
+        for (i, a_i) in self.terms.iter() {
+            match attributes.get(*i) {
+                Some(Attribute::SecretScalar(m_i)) if *a_i != Scalar::zero() => sum += a_i * m_i,
+                _ => return false,
+            }
+        }
+
+        !self.terms.is_empty() && sum == Scalar::zero()
+    }
+
+    /// Bind this statement into a `transcript`.
+    pub(crate) fn commit(&self, transcript: &mut Transcript) {
+        transcript.append_message(b"relation size", &(self.terms.len() as u32).to_le_bytes());
+
+        for (i, a_i) in self.terms.iter() {
+            transcript.append_message(b"relation index", &(*i as u32).to_le_bytes());
+            transcript.append_message(b"relation coefficient", a_i.as_bytes());
+        }
+        transcript.append_message(b"relation constant", self.constant.as_bytes());
+    }
+
+    /// The publics of this statement's constraint, \\(( G * c \\)) and each
+    /// \\(( G * a_k \\)), in order.
+    pub(crate) fn points(&self, system_parameters: &SystemParameters) -> (RistrettoPoint, Vec<RistrettoPoint>) {
+        let G_a: Vec<RistrettoPoint> = self.terms.iter().map(|(_, a_i)| system_parameters.G * a_i).collect();
+
+        (system_parameters.G * self.constant, G_a)
+    }
+}
@@ -0,0 +1,230 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2020 The Brave Authors
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Translation of credentials from an issuer's old key to its new key.
+//!
+//! The user presents their old credential, alongside a request for the
+//! blind issuance of a new credential with the same attributes, and proves
+//! that each hidden scalar attribute \\(( m_i \\)) committed to in the
+//! presentation, \\(( C_y_i = G_y_i * z + G_m_i * m_i \\)), is the same as
+//! that encrypted in the request,
+//! \\(( (E_{1,i}, E_{2,i}) = (G * r_i, D * r_i + G_m_i * m_i) \\)).  Revealed
+//! attributes are compared directly by the issuer.
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use zkp::CompactProof;
+use zkp::Transcript;
+use zkp::toolbox::SchnorrCS;
+use zkp::toolbox::prover::Prover;
+use zkp::toolbox::verifier::Verifier;
+
+use crate::amacs::Attribute;
+use crate::amacs::EncryptedAttribute;
+use crate::credential::AnonymousCredential;
+use crate::errors::CredentialError;
+use crate::issuer::Issuer;
+use crate::nizk::issuance::BlindedAttribute;
+use crate::nizk::issuance::Request;
+use crate::nizk::issuance::RequestState;
+use crate::nizk::presentation::ProofOfValidCredential;
+use crate::parameters::{IssuerParameters, SystemParameters};
+
+/// A request to translate a credential issued under an issuer's old key into
+/// a new credential, with the same attributes, under its new key.
+pub struct TranslationRequest {
+    presentation: ProofOfValidCredential,
+    request: Request,
+    proof: CompactProof,
+}
+
+impl TranslationRequest {
+    /// Create a [`TranslationRequest`] for the `credential`, which was issued
+    /// under the old `issuer_parameters`.
+    ///
+    /// Hidden group element attributes cannot be translated, since they
+    /// cannot be blindly issued.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the `TranslationRequest` to send to the
+    /// issuer and the [`RequestState`] with which to obtain the new
+    /// credential from its response, otherwise a [`CredentialError`].
+    pub fn new<C>(
+        system_parameters: &SystemParameters,
+        issuer_parameters: &IssuerParameters,
+        credential: &AnonymousCredential,
+        csprng: &mut C,
+    ) -> Result<(TranslationRequest, RequestState), CredentialError>
+    where
+        C: RngCore + CryptoRng,
+    {
+        let (request, state) = Request::new(system_parameters, credential.attributes.clone(), csprng)?;
+        let (presentation, z_) = ProofOfValidCredential::prove_with_commitment_nonce(system_parameters, issuer_parameters,
+                                                                                     credential, None, csprng)?;
+
+        let mut transcript = Transcript::new(b"2019/1416 anonymous credential");
+        let mut prover = Prover::new(b"2019/1416 translation proof", &mut transcript);
+
+        // Feed the domain separators for the Camenisch-Stadler secrets into the protocol transcript.
+        let z = prover.allocate_scalar(b"z", z_);
+
+        // Feed in the domain separators and values for the publics into the transcript.
+        let (G, _) = prover.allocate_point(b"G", system_parameters.G);
+        let (D, _) = prover.allocate_point(b"D", request.D);
+
+        let mut r = state.r.iter();
+
+        for (i, attribute) in credential.attributes.iter().enumerate() {
+            if let (Attribute::SecretScalar(m_i), BlindedAttribute::Hidden{ E_1, E_2 }) = (attribute, &request.attributes[i]) {
+                // The .unwrap() can never panic as the request has one nonce per hidden attribute.
+                let m   = prover.allocate_scalar(b"m", *m_i);
+                let r_i = prover.allocate_scalar(b"r", *r.next().unwrap());
+
+                let (G_y_i, _) = prover.allocate_point(b"G_y", system_parameters.G_y[i]);
+                let (G_m_i, _) = prover.allocate_point(b"G_m", system_parameters.G_m[i]);
+                let (C_y_i, _) = prover.allocate_point(b"C_y", presentation.C_y[i]);
+                let (E_1_i, _) = prover.allocate_point(b"E_1", *E_1);
+                let (E_2_i, _) = prover.allocate_point(b"E_2", *E_2);
+
+                // Constraint #1: C_y_i = G_y_i * z + G_m_i * m_i
+                prover.constrain(C_y_i, vec![(z, G_y_i), (m, G_m_i)]);
+
+                // Constraint #2: E_1_i = G * r_i
+                //                E_2_i = D * r_i + G_m_i * m_i
+                prover.constrain(E_1_i, vec![(r_i, G)]);
+                prover.constrain(E_2_i, vec![(r_i, D), (m, G_m_i)]);
+            }
+        }
+
+        let proof = prover.prove_compact();
+
+        Ok((TranslationRequest { presentation, request, proof }, state))
+    }
+
+    /// The request for the blind issuance of the new credential, with which
+    /// to finish it from the issuer's response with [`RequestState::finish`].
+    pub fn request(&self) -> &Request {
+        &self.request
+    }
+
+    /// Verify this [`TranslationRequest`], i.e. that the presentation of the
+    /// old credential verifies under the `old_issuer`'s key, and that the
+    /// request is for a new credential with the same attributes.
+    pub(crate) fn verify(&self, old_issuer: &Issuer) -> Result<(), CredentialError> {
+        let system_parameters = &old_issuer.system_parameters;
+        let presented = self.presentation.attributes();
+
+        self.presentation.verify(old_issuer)?;
+        self.request.verify(system_parameters)?;
+
+        if presented.len() != self.request.attributes.len() {
+            return Err(CredentialError::WrongNumberOfAttributes);
+        }
+
+        // Check that revealed attributes are the same, and hidden attributes are hidden.
+        for (i, (presented, requested)) in presented.iter().zip(self.request.attributes.iter()).enumerate() {
+            let M_i: RistrettoPoint = match presented {
+                EncryptedAttribute::PublicScalar(m_i) => system_parameters.G_m[i] * m_i,
+                EncryptedAttribute::PublicPoint(M_i)  => *M_i,
+                EncryptedAttribute::SecretScalar      => match requested {
+                    BlindedAttribute::Hidden{ .. } => continue,
+                    _ => return Err(CredentialError::BadAttribute),
+                },
+                EncryptedAttribute::SecretPoint       => return Err(CredentialError::BadAttribute),
+            };
+
+            match requested {
+                BlindedAttribute::Revealed(a) if a.message_point(i, system_parameters) == M_i => continue,
+                _ => return Err(CredentialError::BadAttribute),
+            }
+        }
+
+        let mut transcript = Transcript::new(b"2019/1416 anonymous credential");
+        let mut verifier = Verifier::new(b"2019/1416 translation proof", &mut transcript);
+
+        // Feed the domain separators for the Camenisch-Stadler secrets into the protocol transcript.
+        let z = verifier.allocate_scalar(b"z");
+
+        // Feed in the domain separators and values for the publics into the transcript.
+        let G = verifier.allocate_point(b"G", system_parameters.G.compress())?;
+        let D = verifier.allocate_point(b"D", self.request.D.compress())?;
+
+        for (i, requested) in self.request.attributes.iter().enumerate() {
+            if let BlindedAttribute::Hidden{ E_1, E_2 } = requested {
+                let m   = verifier.allocate_scalar(b"m");
+                let r_i = verifier.allocate_scalar(b"r");
+
+                let G_y_i = verifier.allocate_point(b"G_y", system_parameters.G_y[i].compress())?;
+                let G_m_i = verifier.allocate_point(b"G_m", system_parameters.G_m[i].compress())?;
+                let C_y_i = verifier.allocate_point(b"C_y", self.presentation.C_y[i].compress())?;
+                let E_1_i = verifier.allocate_point(b"E_1", E_1.compress())?;
+                let E_2_i = verifier.allocate_point(b"E_2", E_2.compress())?;
+
+                // Constraint #1: C_y_i = G_y_i * z + G_m_i * m_i
+                verifier.constrain(C_y_i, vec![(z, G_y_i), (m, G_m_i)]);
+
+                // Constraint #2: E_1_i = G * r_i
+                //                E_2_i = D * r_i + G_m_i * m_i
+                verifier.constrain(E_1_i, vec![(r_i, G)]);
+                verifier.constrain(E_2_i, vec![(r_i, D), (m, G_m_i)]);
+            }
+        }
+
+        verifier.verify_compact(&self.proof).or(Err(CredentialError::VerificationFailure))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::user::CredentialRequestConstructor;
+
+    use curve25519_dalek::scalar::Scalar;
+
+    use rand::thread_rng;
+
+    #[test]
+    fn translate_credential_to_rotated_key() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 3).unwrap();
+        let old_issuer = Issuer::new(&system_parameters, &mut rng);
+        let new_issuer = old_issuer.rotate_key(&mut rng);
+        let mut request = CredentialRequestConstructor::new(&system_parameters);
+
+        request.append_revealed_scalar(Scalar::random(&mut rng));
+        request.append_revealed_scalar(Scalar::random(&mut rng));
+        request.append_revealed_point(RistrettoPoint::random(&mut rng));
+
+        let issuance = old_issuer.issue(request.finish(), &mut rng).unwrap();
+        let mut credential = issuance.verify(&system_parameters, &old_issuer.issuer_parameters).unwrap();
+
+        credential.hide_attribute(1).unwrap();
+
+        let (translation, state) = TranslationRequest::new(&system_parameters, &old_issuer.issuer_parameters,
+                                                           &credential, &mut rng).unwrap();
+        let response = new_issuer.translate(&old_issuer, &translation, &mut rng).unwrap();
+        let translated = state.finish(&system_parameters, &new_issuer.issuer_parameters,
+                                      translation.request(), &response).unwrap();
+        let presentation = translated.show(&system_parameters, &new_issuer.issuer_parameters, None, &mut rng).unwrap();
+
+        assert!(new_issuer.verify(&presentation).is_ok());
+        assert!(old_issuer.verify(&presentation).is_err());
+
+        // Only the old issuer's credentials can be translated.
+        let (translation, _state) = TranslationRequest::new(&system_parameters, &new_issuer.issuer_parameters,
+                                                            &translated, &mut rng).unwrap();
+
+        assert!(new_issuer.translate(&old_issuer, &translation, &mut rng).is_err());
+    }
+}
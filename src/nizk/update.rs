@@ -0,0 +1,309 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2020 The Brave Authors
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Issuer-blind updates of a hidden counter attribute, e.g. for private
+//! loyalty or balance systems.
+//!
+//! The user presents a credential whose attribute at some index is a hidden
+//! scalar counter \\(( m \\)), and makes a blind issuance [`Request`] for a
+//! fresh credential with the same attributes, save that the counter is
+//! \\(( m + 1 \\)).  Each hidden scalar attribute \\(( m_j \\)) of the new
+//! credential is carried over from the old one by proving that the
+//! commitment \\(( C_{y,j} = G_{y,j} * z + G_{m,j} * m_j \\)) of the
+//! presentation and the encryption
+//! \\(( (E_{1,j}, E_{2,j}) = (G * r_j, D * r_j + G_{m,j} * (m_j + \delta_j)) \\))
+//! of the request hide the same \\(( m_j \\)), where \\(( \delta_j \\)) is
+//! \\(( 1 \\)) for the counter and \\(( 0 \\)) otherwise.  Revealed
+//! attributes are carried over in the clear.  The issuer thus learns neither
+//! the old nor the new value of the counter.
+//!
+//! # Warning
+//!
+//! The old credential remains valid after an update.  Systems in which it
+//! must not be presented again, e.g. when the counter is a balance which may
+//! also be spent, should require a [`nullifier`](crate::nizk::nullifier)
+//! from the presentation.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use zkp::CompactProof;
+use zkp::Transcript;
+use zkp::toolbox::SchnorrCS;
+use zkp::toolbox::prover::Prover;
+use zkp::toolbox::verifier::Verifier;
+
+use crate::amacs::Attribute;
+use crate::amacs::EncryptedAttribute;
+use crate::credential::AnonymousCredential;
+use crate::errors::CredentialError;
+use crate::nizk::issuance::BlindedAttribute;
+use crate::nizk::issuance::Request;
+use crate::nizk::issuance::RequestState;
+use crate::nizk::presentation::ProofOfValidCredential;
+use crate::parameters::{IssuerParameters, SystemParameters};
+
+/// A request to update the hidden counter attribute of a credential, as a
+/// presentation of the old credential, a blind issuance [`Request`] for the
+/// new one, and a proof linking the two.
+pub struct UpdateRequest {
+    presentation: ProofOfValidCredential,
+    request: Request,
+    index: u16,
+    proof: CompactProof,
+}
+
+impl UpdateRequest {
+    /// Present the `credential`, issued under the `system_parameters` and
+    /// `issuer_parameters`, and request a new credential from its issuer
+    /// whose hidden scalar attribute at `index` is incremented by one.
+    ///
+    /// The counter must be hidden with `credential.hide_attribute()`
+    /// beforehand, as must any other scalar attributes which should remain
+    /// hidden from the issuer.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the `UpdateRequest` to send to the
+    /// issuer and the [`RequestState`] with which to decrypt its response,
+    /// as in [`RequestState::finish`] with [`UpdateRequest::request`].
+    /// Otherwise, a [`CredentialError::BadAttribute`] if the counter isn't a
+    /// hidden scalar or the credential has any hidden group element
+    /// attributes, which cannot be blindly issued.
+    pub fn new<C>(
+        system_parameters: &SystemParameters,
+        issuer_parameters: &IssuerParameters,
+        credential: &AnonymousCredential,
+        index: usize,
+        csprng: &mut C,
+    ) -> Result<(UpdateRequest, RequestState), CredentialError>
+    where
+        C: RngCore + CryptoRng,
+    {
+        let mut attributes: Vec<Attribute> = credential.attributes.clone();
+
+        match attributes.get_mut(index) {
+            Some(Attribute::SecretScalar(m)) => *m += Scalar::one(),
+            _ => return Err(CredentialError::BadAttribute),
+        }
+
+        let (request, state) = Request::new(system_parameters, attributes, csprng)?;
+        let (presentation, z_) = ProofOfValidCredential::prove_with_commitment_nonce(
+            system_parameters, issuer_parameters, credential, None, csprng)?;
+
+        let mut transcript = Transcript::new(b"2019/1416 anonymous credential");
+        let mut prover = Prover::new(b"2019/1416 counter update proof", &mut transcript);
+
+        // Feed the domain separators for the Camenisch-Stadler secrets into the protocol transcript.
+        let z = prover.allocate_scalar(b"z", z_);
+
+        let mut secrets = Vec::with_capacity(state.r.len());
+        let mut hidden = state.r.iter();
+
+        for attribute in credential.attributes.iter() {
+            if let Attribute::SecretScalar(m) = attribute {
+                // The nonces are in the order of the hidden attributes.
+                let r = hidden.next().ok_or(CredentialError::BadAttribute)?;
+
+                secrets.push((prover.allocate_scalar(b"r", *r), prover.allocate_scalar(b"m", *m)));
+            }
+        }
+
+        // Feed in the domain separators and values for the publics into the transcript.
+        let (G, _) = prover.allocate_point(b"G", system_parameters.G);
+        let (D, _) = prover.allocate_point(b"D", request.D);
+
+        let mut secrets = secrets.into_iter();
+
+        for (j, attribute) in request.attributes.iter().enumerate() {
+            if let BlindedAttribute::Hidden{ E_1, E_2 } = attribute {
+                let (r, m) = secrets.next().ok_or(CredentialError::BadAttribute)?;
+                let delta = if j == index { system_parameters.G_m[j] } else { RistrettoPoint::identity() };
+
+                let (G_y_j, _) = prover.allocate_point(b"G_y",       system_parameters.G_y[j]);
+                let (G_m_j, _) = prover.allocate_point(b"G_m",       system_parameters.G_m[j]);
+                let (C_y_j, _) = prover.allocate_point(b"C_y",       presentation.C_y[j]);
+                let (E_1_j, _) = prover.allocate_point(b"E_1",       *E_1);
+                let (E_2_j, _) = prover.allocate_point(b"E_2-delta", E_2 - delta);
+
+                // Constraint #1: C_y_j = G_y_j * z + G_m_j * m_j
+                prover.constrain(C_y_j, vec![(z, G_y_j), (m, G_m_j)]);
+
+                // Constraint #2: E_1_j = G * r_j
+                prover.constrain(E_1_j, vec![(r, G)]);
+
+                // Constraint #3: E_2_j - G_m_j * delta_j = D * r_j + G_m_j * m_j
+                prover.constrain(E_2_j, vec![(r, D), (m, G_m_j)]);
+            }
+        }
+
+        let update = UpdateRequest { presentation, request, index: index as u16, proof: prover.prove_compact() };
+
+        Ok((update, state))
+    }
+
+    /// The index of the counter attribute to be incremented.
+    pub fn index(&self) -> usize {
+        self.index as usize
+    }
+
+    /// The presentation of the old credential, which the issuer must verify.
+    pub fn presentation(&self) -> &ProofOfValidCredential {
+        &self.presentation
+    }
+
+    /// The blind issuance request for the new credential.
+    pub fn request(&self) -> &Request {
+        &self.request
+    }
+
+    /// Verify that the new credential requested has the attributes of the
+    /// presented one, with the counter incremented.
+    ///
+    /// This does *not* verify the presentation itself, nor the proof of
+    /// knowledge of the hidden attributes of the request.
+    pub(crate) fn verify(&self, system_parameters: &SystemParameters) -> Result<(), CredentialError> {
+        let index = self.index as usize;
+        let presented = self.presentation.attributes();
+
+        if presented.len() != self.request.attributes.len() || presented.len() > self.presentation.C_y.len() {
+            return Err(CredentialError::WrongNumberOfAttributes);
+        }
+        if !matches!(presented.get(index), Some(EncryptedAttribute::SecretScalar)) {
+            return Err(CredentialError::BadAttribute);
+        }
+
+        // Every revealed attribute must be carried over unchanged, and every
+        // hidden scalar attribute must be hidden again.
+        for (j, (old, new)) in presented.iter().zip(self.request.attributes.iter()).enumerate() {
+            let M_j = match old {
+                EncryptedAttribute::PublicScalar(m) => system_parameters.G_m[j] * m,
+                EncryptedAttribute::PublicPoint(M)  => *M,
+                EncryptedAttribute::SecretScalar    => match new {
+                    BlindedAttribute::Hidden{ .. } => continue,
+                    _ => return Err(CredentialError::BadAttribute),
+                },
+                EncryptedAttribute::SecretPoint => return Err(CredentialError::BadAttribute),
+            };
+            match new {
+                BlindedAttribute::Revealed(a) if a.message_point(j, system_parameters) == M_j => (),
+                _ => return Err(CredentialError::BadAttribute),
+            }
+        }
+
+        let mut transcript = Transcript::new(b"2019/1416 anonymous credential");
+        let mut verifier = Verifier::new(b"2019/1416 counter update proof", &mut transcript);
+
+        // Feed the domain separators for the Camenisch-Stadler secrets into the protocol transcript.
+        let z = verifier.allocate_scalar(b"z");
+
+        let mut secrets = Vec::new();
+
+        for attribute in self.request.attributes.iter() {
+            if let BlindedAttribute::Hidden{ .. } = attribute {
+                secrets.push((verifier.allocate_scalar(b"r"), verifier.allocate_scalar(b"m")));
+            }
+        }
+
+        // Feed in the domain separators and values for the publics into the transcript.
+        let G = verifier.allocate_point(b"G", system_parameters.G.compress())?;
+        let D = verifier.allocate_point(b"D", self.request.D.compress())?;
+
+        let mut secrets = secrets.into_iter();
+
+        for (j, attribute) in self.request.attributes.iter().enumerate() {
+            if let BlindedAttribute::Hidden{ E_1, E_2 } = attribute {
+                let (r, m) = secrets.next().ok_or(CredentialError::BadAttribute)?;
+                let delta = if j == index { system_parameters.G_m[j] } else { RistrettoPoint::identity() };
+
+                let G_y_j = verifier.allocate_point(b"G_y",       system_parameters.G_y[j].compress())?;
+                let G_m_j = verifier.allocate_point(b"G_m",       system_parameters.G_m[j].compress())?;
+                let C_y_j = verifier.allocate_point(b"C_y",       self.presentation.C_y[j].compress())?;
+                let E_1_j = verifier.allocate_point(b"E_1",       E_1.compress())?;
+                let E_2_j = verifier.allocate_point(b"E_2-delta", (E_2 - delta).compress())?;
+
+                // Constraint #1: C_y_j = G_y_j * z + G_m_j * m_j
+                verifier.constrain(C_y_j, vec![(z, G_y_j), (m, G_m_j)]);
+
+                // Constraint #2: E_1_j = G * r_j
+                verifier.constrain(E_1_j, vec![(r, G)]);
+
+                // Constraint #3: E_2_j - G_m_j * delta_j = D * r_j + G_m_j * m_j
+                verifier.constrain(E_2_j, vec![(r, D), (m, G_m_j)]);
+            }
+        }
+
+        verifier.verify_compact(&self.proof).or(Err(CredentialError::VerificationFailure))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::issuer::Issuer;
+    use crate::user::CredentialRequestConstructor;
+
+    use rand::thread_rng;
+
+    #[test]
+    fn counters_are_incremented_blindly() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 3).unwrap();
+        let issuer = Issuer::new(&system_parameters, &mut rng);
+        let account = Scalar::random(&mut rng);
+
+        let mut request = CredentialRequestConstructor::new(&system_parameters);
+        request.append_revealed_scalar(Scalar::from(7u64));
+        request.append_revealed_scalar(account);
+        request.append_revealed_scalar(Scalar::from(42u64));
+
+        let issuance = issuer.issue(request.finish(), &mut rng).unwrap();
+        let mut credential = issuance.verify(&system_parameters, &issuer.issuer_parameters).unwrap();
+
+        credential.hide_attribute(0).unwrap();
+        credential.hide_attribute(1).unwrap();
+
+        for expected in 8u64..10 {
+            let (update, state) = UpdateRequest::new(&system_parameters, &issuer.issuer_parameters,
+                                                     &credential, 0, &mut rng).unwrap();
+            let response = issuer.issue_update(&update, &mut rng).unwrap();
+
+            credential = state.finish(&system_parameters, &issuer.issuer_parameters,
+                                      update.request(), &response).unwrap();
+
+            assert!(matches!(credential.attributes[0], Attribute::SecretScalar(m) if m == Scalar::from(expected)));
+            assert!(matches!(credential.attributes[1], Attribute::SecretScalar(m) if m == account));
+            assert!(matches!(credential.attributes[2], Attribute::PublicScalar(m) if m == Scalar::from(42u64)));
+        }
+
+        let presentation = credential.show(&system_parameters, &issuer.issuer_parameters, None, &mut rng).unwrap();
+
+        assert!(issuer.verify(&presentation).is_ok());
+
+        // The counter must be hidden.
+        assert!(UpdateRequest::new(&system_parameters, &issuer.issuer_parameters, &credential, 2, &mut rng).is_err());
+
+        // An update whose proof is for another counter is rejected.
+        let (mut update, _) = UpdateRequest::new(&system_parameters, &issuer.issuer_parameters,
+                                                 &credential, 0, &mut rng).unwrap();
+        update.index = 1;
+
+        assert!(issuer.issue_update(&update, &mut rng).is_err());
+    }
+}
@@ -0,0 +1,179 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2020 The Brave Authors
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! A one-shot interface to the common path of issuing, presenting, and
+//! verifying a credential.
+//!
+//! The [`issue`], [`receive`], [`show`], and [`verify`] functions, which
+//! are also exported by the [`prelude`](crate::prelude), hide the
+//! construction of requests, algebraic MACs, and proofs.  Which attributes a
+//! presentation reveals, the predicates it proves, and the nonce it is bound
+//! to, are described once by a [`Policy`] shared by the user and the issuer:
+//!
+//! 1. The issuer creates an [`Issuer`] and [`issue`]s a credential on some
+//!    attributes, all of which it sees.
+//! 2. The user [`receive`]s the credential, checking that it was issued
+//!    correctly under the issuer's public parameters.
+//! 3. The user [`show`]s the credential, revealing only those attributes
+//!    which the policy says to reveal.
+//! 4. The issuer [`verify`]s the presentation, which must satisfy the
+//!    policy.
+//!
+//! Blind issuance, hidden group element attributes, and additional
+//! statements such as nullifiers are not supported here, and require the
+//! lower-level interfaces of [`Issuer`] and [`AnonymousCredential`].
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use crate::amacs::Attribute;
+use crate::credential::AnonymousCredential;
+use crate::errors::CredentialError;
+use crate::issuer::CredentialIssuance;
+use crate::issuer::Issuer;
+use crate::nizk::presentation::ProofOfValidCredential;
+use crate::parameters::IssuerParameters;
+use crate::parameters::SystemParameters;
+use crate::policy::Policy;
+use crate::user::CredentialRequest;
+
+/// Issue a credential on some `attributes` with the `issuer`'s secret key.
+///
+/// # Returns
+///
+/// A `Result` whose `Ok` value is the issuance, which the user must check
+/// with [`receive`], otherwise a [`CredentialError`], e.g. if there are the
+/// wrong number of `attributes` for the issuer's [`SystemParameters`].
+pub fn issue<C>(
+    csprng: &mut C,
+    issuer: &Issuer,
+    attributes: Vec<Attribute>,
+) -> Result<CredentialIssuance, CredentialError>
+where
+    C: CryptoRng + RngCore,
+{
+    issuer.issue(CredentialRequest { attributes }, csprng)
+}
+
+/// Receive a credential from an `issuance`, checking its proof that the
+/// credential was issued under the `system_parameters` and
+/// `issuer_parameters`, as in [`CredentialIssuance::verify`].
+///
+/// # Returns
+///
+/// A `Result` whose `Ok` value is the credential, otherwise a
+/// [`CredentialError`] if the proof of issuance doesn't verify.
+pub fn receive(
+    issuance: CredentialIssuance,
+    system_parameters: &SystemParameters,
+    issuer_parameters: &IssuerParameters,
+) -> Result<AnonymousCredential, CredentialError>
+{
+    issuance.verify(system_parameters, issuer_parameters)
+}
+
+/// Present a `credential`, issued under the `system_parameters` and
+/// `issuer_parameters`, as declared by the `policy`, as in
+/// [`AnonymousCredential::show_with_policy`] without a symmetric keypair.
+///
+/// # Returns
+///
+/// A `Result` whose `Ok` value is the presentation, otherwise a
+/// [`CredentialError`], e.g. if the policy would hide a group element
+/// attribute, which requires a symmetric keypair.
+pub fn show<C>(
+    csprng: &mut C,
+    credential: &AnonymousCredential,
+    system_parameters: &SystemParameters,
+    issuer_parameters: &IssuerParameters,
+    policy: &Policy,
+) -> Result<ProofOfValidCredential, CredentialError>
+where
+    C: CryptoRng + RngCore,
+{
+    credential.show_with_policy(system_parameters, issuer_parameters, None, policy, csprng)
+}
+
+/// Verify a `presentation` of a credential issued by the `issuer`, which
+/// must satisfy the `policy`, as in [`Issuer::verify_with_policy`].
+///
+/// Credentials in this scheme are keyed-verification credentials, so only
+/// the holder of the issuer's secret key may verify their presentations.
+///
+/// # Returns
+///
+/// A `Result` whose `Ok` value is empty, otherwise a
+/// [`CredentialError::PolicyViolation`] if the presentation doesn't satisfy
+/// the policy, or another [`CredentialError`] if it doesn't verify.
+pub fn verify(
+    issuer: &Issuer,
+    presentation: &ProofOfValidCredential,
+    policy: &Policy,
+) -> Result<(), CredentialError>
+{
+    issuer.verify_with_policy(presentation, policy)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use curve25519_dalek::scalar::Scalar;
+
+    use rand::thread_rng;
+
+    #[test]
+    fn issue_show_and_verify() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 3).unwrap();
+        let issuer = Issuer::new(&system_parameters, &mut rng);
+        let attributes = vec![Attribute::PublicScalar(Scalar::from(42u64)),
+                              Attribute::PublicScalar(Scalar::random(&mut rng)),
+                              Attribute::expiry(7)];
+
+        let issuance = issue(&mut rng, &issuer, attributes).unwrap();
+        let credential = receive(issuance, &system_parameters, &issuer.issuer_parameters).unwrap();
+        let policy = Policy::new().reveal(0).reveal(2).with_nonce(b"fresh nonce");
+        let presentation = show(&mut rng, &credential, &system_parameters, &issuer.issuer_parameters, &policy).unwrap();
+
+        assert!(verify(&issuer, &presentation, &policy).is_ok());
+
+        // The presentation must reveal exactly what the policy says to reveal...
+        assert_eq!(verify(&issuer, &presentation, &Policy::new().reveal(0).with_nonce(b"fresh nonce")),
+                   Err(CredentialError::PolicyViolation));
+        assert_eq!(verify(&issuer, &presentation, &Policy::new().reveal(0).reveal(1).reveal(2).with_nonce(b"fresh nonce")),
+                   Err(CredentialError::PolicyViolation));
+
+        // ...and be bound to its nonce.
+        assert!(verify(&issuer, &presentation, &Policy::new().reveal(0).reveal(2)).is_err());
+        assert!(verify(&issuer, &presentation, &Policy::new().reveal(0).reveal(2).with_nonce(b"stale nonce")).is_err());
+
+        // Policies may only refer to attributes which exist.
+        assert_eq!(show(&mut rng, &credential, &system_parameters, &issuer.issuer_parameters,
+                        &Policy::new().reveal(3)).err(), Some(CredentialError::WrongNumberOfAttributes));
+    }
+
+    #[test]
+    fn receive_checks_the_issuer() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 2).unwrap();
+        let issuer = Issuer::new(&system_parameters, &mut rng);
+        let other = Issuer::new(&system_parameters, &mut rng);
+        let attributes = vec![Attribute::PublicScalar(Scalar::one()), Attribute::expiry(7)];
+
+        let issuance = issue(&mut rng, &issuer, attributes).unwrap();
+
+        assert!(receive(issuance, &system_parameters, &other.issuer_parameters).is_err());
+    }
+}
@@ -8,14 +8,41 @@
 // Authors:
 // - isis agora lovecruft <isis@patternsinthevoid.net>
 
+#[cfg(all(not(feature = "std"), feature = "alloc", feature = "precompute-tables"))]
+use alloc::boxed::Box;
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::sync::Arc;
 #[cfg(all(not(feature = "std"), feature = "alloc"))]
 use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std", feature = "precompute-tables"))]
+use std::boxed::Box;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::sync::Arc;
 #[cfg(all(not(feature = "alloc"), feature = "std"))]
 use std::vec::Vec;
 
+#[cfg(not(feature = "std"))]
+use core::ops::Deref;
+#[cfg(feature = "std")]
+use std::ops::Deref;
+
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "std")]
+use std::io::Read;
+#[cfg(feature = "std")]
+use std::io::Write;
+
+#[cfg(feature = "arbitrary")]
+use arbitrary::Arbitrary;
+#[cfg(feature = "arbitrary")]
+use arbitrary::Unstructured;
+
 use curve25519_dalek::constants::RISTRETTO_BASEPOINT_COMPRESSED;
 use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
 use curve25519_dalek::ristretto::CompressedRistretto;
+#[cfg(feature = "precompute-tables")]
+use curve25519_dalek::ristretto::RistrettoBasepointTable;
 use curve25519_dalek::ristretto::RistrettoPoint;
 use curve25519_dalek::scalar::Scalar;
 use curve25519_dalek::traits::Identity;
@@ -26,8 +53,27 @@ use serde::de::Visitor;
 use rand_core::CryptoRng;
 use rand_core::RngCore;
 
+use sha2::Digest;
+use sha2::Sha512Trunc256;
+
 use crate::amacs::SecretKey;
 use crate::errors::CredentialError;
+use crate::fixed::DERIVATION_DOMAIN;
+use crate::fixed::derive_generator;
+use crate::nizk::keygen::ProofOfKeyGeneration;
+use crate::rng::CryptoRngCore;
+use crate::wire;
+use crate::wire::Sink;
+use crate::wire::WireType;
+
+/// The largest number of attributes which [`SystemParameters`] may be
+/// generated for, and which a serialised key, credential, or presentation may
+/// claim to have.
+///
+/// Deserialisation checks the embedded number of attributes against this
+/// bound before computing any lengths from it, so that malformed input can
+/// neither overflow those computations nor cause huge allocations.
+pub const MAX_NUMBER_OF_ATTRIBUTES: u32 = u16::MAX as u32;
 
 /// Given the `number_of_attributes`, calculate the size of a serialised
 /// [`SystemParameters`], in bytes.
@@ -73,8 +119,54 @@ pub struct SystemParameters {
     pub(crate) G_a:       RistrettoPoint,
     pub(crate) G_a0:      RistrettoPoint,
     pub(crate) G_a1:      RistrettoPoint,
+    /// Precomputed tables for fixed-base multiplication by `G_w`, `G_x_0`,
+    /// `G_x_1`, and each of the `G_m`, if they have been built with
+    /// [`SystemParameters::precompute_tables`].
+    #[cfg(feature = "precompute-tables")]
+    pub(crate) tables: GeneratorTables,
+}
+
+/// Precomputed [`RistrettoBasepointTable`]s for the generators of some
+/// [`SystemParameters`] which are used in fixed-base multiplications by an
+/// issuer, if they have been built.
+///
+/// These are merely a cache of the generators, thus they are never serialised
+/// and are ignored when comparing `SystemParameters` for equality.
+#[cfg(feature = "precompute-tables")]
+#[derive(Clone)]
+pub(crate) struct GeneratorTables(pub(crate) Option<Box<Tables>>);
+
+/// The tables themselves, one per generator.  Each is roughly 30 KiB, so
+/// they are boxed to keep `SystemParameters` small.
+#[cfg(feature = "precompute-tables")]
+#[derive(Clone)]
+pub(crate) struct Tables {
+    pub(crate) G_w:   RistrettoBasepointTable,
+    pub(crate) G_x_0: RistrettoBasepointTable,
+    pub(crate) G_x_1: RistrettoBasepointTable,
+    pub(crate) G_m:   Vec<RistrettoBasepointTable>,
 }
 
+#[cfg(feature = "precompute-tables")]
+impl ::core::fmt::Debug for GeneratorTables {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+        match &self.0 {
+            Some(tables) => write!(f, "GeneratorTables({} tables)", tables.G_m.len() + 3),
+            None         => write!(f, "GeneratorTables(None)"),
+        }
+    }
+}
+
+#[cfg(feature = "precompute-tables")]
+impl PartialEq for GeneratorTables {
+    fn eq(&self, _other: &GeneratorTables) -> bool {
+        true
+    }
+}
+
+#[cfg(feature = "precompute-tables")]
+impl Eq for GeneratorTables {}
+
 macro_rules! try_deserialise {
     ($name:expr, $bytes:expr) => {
         match CompressedRistretto($bytes).decompress() {
@@ -89,15 +181,70 @@ macro_rules! try_deserialise {
 }
 
 impl SystemParameters {
+    /// Deserialise system parameters from bytes in either the versioned
+    /// [`wire`] format or the unversioned format of
+    /// [`SystemParameters::to_raw_bytes`].
+    ///
+    /// [`wire`]: crate::wire
     pub fn from_bytes(bytes: &[u8]) -> Result<SystemParameters, CredentialError> {
+        match wire::decode(bytes, WireType::SystemParameters)? {
+            (0, body) | (1, body) => SystemParameters::from_raw_bytes(body),
+            _ => Err(CredentialError::UnsupportedEncoding),
+        }
+    }
+
+    /// Serialise these system parameters in the versioned [`wire`] format,
+    /// whose body is as for [`SystemParameters::to_raw_bytes`].
+    ///
+    /// [`wire`]: crate::wire
+    pub fn to_bytes(&self) -> Vec<u8> {
+        wire::encode(WireType::SystemParameters, &self.to_raw_bytes())
+    }
+
+    /// Stream these system parameters to a `writer` in the versioned
+    /// [`wire`] format, as for [`SystemParameters::to_bytes`], without
+    /// serialising them into memory first.
+    ///
+    /// [`wire`]: crate::wire
+    #[cfg(feature = "std")]
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        wire::write(&mut writer, WireType::SystemParameters, sizeof_system_parameters(self.NUMBER_OF_ATTRIBUTES),
+                    |sink| self.write_raw(sink))
+    }
+
+    /// Read system parameters written with [`SystemParameters::write_to`]
+    /// from a `reader`, consuming exactly their encoding.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the system parameters, otherwise an
+    /// `io::Error`, of kind `InvalidData` if they are malformed.
+    #[cfg(feature = "std")]
+    pub fn read_from<R: Read>(mut reader: R) -> io::Result<SystemParameters> {
+        let bytes = wire::read(&mut reader, WireType::SystemParameters)?;
+
+        SystemParameters::from_bytes(&bytes).map_err(wire::invalid_data)
+    }
+
+    /// Deserialise system parameters from bytes without a [`wire`] header.
+    ///
+    /// [`wire`]: crate::wire
+    pub fn from_raw_bytes(bytes: &[u8]) -> Result<SystemParameters, CredentialError> {
         let mut index: usize = 0;
         let mut chunk = [0u8; 32];
 
         let mut tmp = [0u8; 4];
 
+        if bytes.len() < 4 {
+            return Err(CredentialError::NoSystemParameters);
+        }
+
         tmp.copy_from_slice(&bytes[index..index+4]); index += 4;
         let NUMBER_OF_ATTRIBUTES: u32 = u32::from_le_bytes(tmp);
 
+        if NUMBER_OF_ATTRIBUTES > MAX_NUMBER_OF_ATTRIBUTES {
+            return Err(CredentialError::WrongNumberOfAttributes);
+        }
         if bytes.len() != sizeof_system_parameters(NUMBER_OF_ATTRIBUTES) {
             return Err(CredentialError::NoSystemParameters);
         }
@@ -149,18 +296,34 @@ impl SystemParameters {
         chunk.copy_from_slice(&bytes[index..index+32]);
         let G_a1: RistrettoPoint = try_deserialise!("G_a1", chunk);
 
-        Ok(SystemParameters { NUMBER_OF_ATTRIBUTES, G, G_w, G_w_prime, G_x_0, G_x_1, G_y, G_m, G_V, G_a, G_a0, G_a1 })
+        Ok(SystemParameters {
+            NUMBER_OF_ATTRIBUTES, G, G_w, G_w_prime, G_x_0, G_x_1, G_y, G_m, G_V, G_a, G_a0, G_a1,
+            #[cfg(feature = "precompute-tables")]
+            tables: GeneratorTables(None),
+        })
     }
 
-    pub fn to_bytes(&self) -> Vec<u8> {
+    /// Serialise these system parameters without a [`wire`] header, as a
+    /// little-endian `u32` number of attributes followed by the compressed
+    /// generators.
+    ///
+    /// [`wire`]: crate::wire
+    pub fn to_raw_bytes(&self) -> Vec<u8> {
         let mut v: Vec<u8> = Vec::with_capacity(sizeof_system_parameters(self.NUMBER_OF_ATTRIBUTES));
 
-        v.extend(&self.NUMBER_OF_ATTRIBUTES.to_le_bytes());
-        v.extend(self.G.compress().to_bytes().iter());
-        v.extend(self.G_w.compress().to_bytes().iter());
-        v.extend(self.G_w_prime.compress().to_bytes().iter());
-        v.extend(self.G_x_0.compress().to_bytes().iter());
-        v.extend(self.G_x_1.compress().to_bytes().iter());
+        self.write_raw(&mut v);
+        v
+    }
+
+    /// Write these system parameters to a [`Sink`], as for
+    /// [`SystemParameters::to_raw_bytes`].
+    fn write_raw<S: Sink>(&self, v: &mut S) {
+        v.put(&self.NUMBER_OF_ATTRIBUTES.to_le_bytes());
+        v.put(self.G.compress().as_bytes());
+        v.put(self.G_w.compress().as_bytes());
+        v.put(self.G_w_prime.compress().as_bytes());
+        v.put(self.G_x_0.compress().as_bytes());
+        v.put(self.G_x_1.compress().as_bytes());
 
         let mut number_of_G_y = self.NUMBER_OF_ATTRIBUTES;
 
@@ -169,24 +332,60 @@ impl SystemParameters {
         }
 
         for i in 0..number_of_G_y as usize {
-            v.extend(self.G_y[i].compress().to_bytes().iter());
+            v.put(self.G_y[i].compress().as_bytes());
         }
 
         for i in 0..self.NUMBER_OF_ATTRIBUTES as usize {
-            v.extend(self.G_m[i].compress().to_bytes().iter());
+            v.put(self.G_m[i].compress().as_bytes());
         }
 
-        v.extend(self.G_V.compress().to_bytes().iter());
-        v.extend(self.G_a.compress().to_bytes().iter());
-        v.extend(self.G_a0.compress().to_bytes().iter());
-        v.extend(self.G_a1.compress().to_bytes().iter());
-        v
+        v.put(self.G_V.compress().as_bytes());
+        v.put(self.G_a.compress().as_bytes());
+        v.put(self.G_a0.compress().as_bytes());
+        v.put(self.G_a1.compress().as_bytes());
+    }
+
+    /// A 32-byte identifier for these parameters, i.e. a hash of their
+    /// unversioned serialised form, with which a [`SecretKey`] may be bound
+    /// to them, and which is embedded in each [`AnonymousCredential`] issued
+    /// under them.
+    ///
+    /// [`AnonymousCredential`]: crate::credential::AnonymousCredential
+    pub fn identifier(&self) -> [u8; 32] {
+        let mut hash = Sha512Trunc256::default();
+        let mut identifier = [0u8; 32];
+
+        hash.input(b"aeonflux SystemParameters identifier");
+        hash.input(self.to_raw_bytes());
+        identifier.copy_from_slice(&hash.result()[..]);
+        identifier
     }
 }
 
 impl_serde_with_to_bytes_and_from_bytes!(SystemParameters,
                                          "A valid byte sequence representing a SystemParameters");
 
+#[cfg(feature = "schema")]
+impl_json_schema_for_bytes!(SystemParameters, "The serialised bytes of a SystemParameters: a \
+    wire format header, then a little-endian u32 number of attributes followed by compressed \
+    Ristretto generators");
+
+/// Generate arbitrary [`SystemParameters`] for between one and sixteen
+/// attributes, deterministically derived from a seed taken from the fuzzer
+/// input so that the generators are always valid and distinct.
+#[cfg(feature = "arbitrary")]
+impl<'a> Arbitrary<'a> for SystemParameters {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<SystemParameters> {
+        let seed: [u8; 32] = u.arbitrary()?;
+        let number_of_attributes: u32 = u.int_in_range(1..=16)?;
+
+        match SystemParameters::generate_from_seed(&seed, number_of_attributes) {
+            Ok(system_parameters) => Ok(system_parameters),
+            Err(_) => Err(arbitrary::Error::IncorrectFormat),
+        }
+    }
+}
+
 impl SystemParameters {
     /// Generate the [`SystemParameters`] randomly via an RNG.
     ///
@@ -200,6 +399,10 @@ impl SystemParameters {
     where
         R: RngCore + CryptoRng,
     {
+        if number_of_attributes > MAX_NUMBER_OF_ATTRIBUTES {
+            return Err(CredentialError::WrongNumberOfAttributes);
+        }
+
         let mut tmp: [u8; 32] = [0u8; 32];
         let mut G_w: Option<RistrettoPoint> = None;
         let mut G_w_prime: Option<RistrettoPoint> = None;
@@ -322,7 +525,11 @@ impl SystemParameters {
             }
         }
 
-        Ok(SystemParameters { NUMBER_OF_ATTRIBUTES, G, G_w, G_w_prime, G_x_0, G_x_1, G_y, G_m, G_V, G_a, G_a0, G_a1 })
+        Ok(SystemParameters {
+            NUMBER_OF_ATTRIBUTES, G, G_w, G_w_prime, G_x_0, G_x_1, G_y, G_m, G_V, G_a, G_a0, G_a1,
+            #[cfg(feature = "precompute-tables")]
+            tables: GeneratorTables(None),
+        })
     }
 
     /// Generate new system parameters using the
@@ -334,25 +541,442 @@ impl SystemParameters {
     {
         SystemParameters::hash_and_pray(csprng, number_of_attributes)
     }
+
+    /// Generate the [`SystemParameters`], as in [`SystemParameters::generate`],
+    /// with a type-erased `csprng`.
+    pub fn generate_dyn(csprng: &mut dyn CryptoRngCore, number_of_attributes: u32)
+        -> Result<SystemParameters, CredentialError>
+    {
+        SystemParameters::generate(&mut &mut *csprng, number_of_attributes)
+    }
+
+    /// Extend these parameters with fresh, randomly sampled generators for
+    /// `additional_attributes` more attributes, appended after the existing
+    /// ones.
+    ///
+    /// Every existing generator is kept, so that a credential issued under
+    /// these parameters remains valid under the extended parameters once it
+    /// is padded with [`AnonymousCredential::extend`].  Parameters which were
+    /// derived from a seed should instead be extended with
+    /// [`SystemParameters::generate_family`], so that they remain
+    /// reproducible.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the extended parameters, otherwise a
+    /// [`CredentialError::WrongNumberOfAttributes`] if they would have more
+    /// than [`MAX_NUMBER_OF_ATTRIBUTES`] attributes, or a
+    /// [`CredentialError::NoSystemParameters`] if the new generators are not
+    /// distinct from the existing ones.
+    ///
+    /// [`AnonymousCredential::extend`]: crate::credential::AnonymousCredential::extend
+    pub fn extend<R>(&self, csprng: &mut R, additional_attributes: u32)
+        -> Result<SystemParameters, CredentialError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let number_of_attributes = self.NUMBER_OF_ATTRIBUTES.checked_add(additional_attributes)
+            .ok_or(CredentialError::WrongNumberOfAttributes)?;
+
+        if number_of_attributes > MAX_NUMBER_OF_ATTRIBUTES {
+            return Err(CredentialError::WrongNumberOfAttributes);
+        }
+
+        let mut tmp: [u8; 32] = [0u8; 32];
+        let mut random_generator = || loop {
+            csprng.fill_bytes(&mut tmp);
+
+            if let Some(point) = CompressedRistretto(tmp).decompress() {
+                break point;
+            }
+        };
+
+        let mut G_y = self.G_y.clone();
+        let mut G_m = self.G_m.clone();
+
+        // As for hash_and_pray(), G_y always has at least three elements.
+        while G_y.len() < ::core::cmp::max(number_of_attributes as usize, 3) {
+            G_y.push(random_generator());
+        }
+        while G_m.len() < number_of_attributes as usize {
+            G_m.push(random_generator());
+        }
+
+        let system_parameters = SystemParameters {
+            NUMBER_OF_ATTRIBUTES: number_of_attributes,
+            G:         self.G,
+            G_w:       self.G_w,
+            G_w_prime: self.G_w_prime,
+            G_x_0:     self.G_x_0,
+            G_x_1:     self.G_x_1,
+            G_y,
+            G_m,
+            G_V:       self.G_V,
+            G_a:       self.G_a,
+            G_a0:      self.G_a0,
+            G_a1:      self.G_a1,
+            #[cfg(feature = "precompute-tables")]
+            tables: GeneratorTables(None),
+        };
+
+        if !system_parameters.generators_are_unique() {
+            return Err(CredentialError::NoSystemParameters);
+        }
+        Ok(system_parameters)
+    }
+
+    /// Check whether these parameters extend the `smaller` parameters, i.e.
+    /// support at least as many attributes and share all of their
+    /// generators, as for the result of [`SystemParameters::extend`].
+    pub fn extends(&self, smaller: &SystemParameters) -> bool {
+        self.NUMBER_OF_ATTRIBUTES >= smaller.NUMBER_OF_ATTRIBUTES &&
+            self.G == smaller.G &&
+            self.G_w == smaller.G_w &&
+            self.G_w_prime == smaller.G_w_prime &&
+            self.G_x_0 == smaller.G_x_0 &&
+            self.G_x_1 == smaller.G_x_1 &&
+            self.G_V == smaller.G_V &&
+            self.G_a == smaller.G_a &&
+            self.G_a0 == smaller.G_a0 &&
+            self.G_a1 == smaller.G_a1 &&
+            self.G_y.starts_with(&smaller.G_y) &&
+            self.G_m.starts_with(&smaller.G_m)
+    }
+
+    /// Build and cache a [`RistrettoBasepointTable`] for each of `G_w`,
+    /// `G_x_0`, `G_x_1`, and the `G_m` generators, so that an issuer's key
+    /// generation, issuer parameters, and the message term of each MAC are
+    /// subsequently computed with much faster fixed-base multiplications.
+    ///
+    /// Each table occupies roughly 30 KiB, so this is only worthwhile for
+    /// long-lived parameters.  The tables are not serialised, and must be
+    /// rebuilt after deserialisation.
+    #[cfg(feature = "precompute-tables")]
+    pub fn precompute_tables(&mut self) {
+        let tables = Tables {
+            G_w:   RistrettoBasepointTable::create(&self.G_w),
+            G_x_0: RistrettoBasepointTable::create(&self.G_x_0),
+            G_x_1: RistrettoBasepointTable::create(&self.G_x_1),
+            G_m:   self.G_m.iter().map(|G_m_i| RistrettoBasepointTable::create(G_m_i)).collect(),
+        };
+
+        self.tables = GeneratorTables(Some(Box::new(tables)));
+    }
+
+    /// Compute \(( m * G_m_i \)), using a precomputed table for \(( G_m_i \))
+    /// if one has been built.
+    pub(crate) fn mul_G_m(&self, i: usize, m: &Scalar) -> RistrettoPoint {
+        #[cfg(feature = "precompute-tables")]
+        {
+            if let Some(tables) = &self.tables.0 {
+                return &tables.G_m[i] * m;
+            }
+        }
+        m * self.G_m[i]
+    }
+
+    /// Compute \(( w * G_w \)), using a precomputed table for \(( G_w \)) if
+    /// one has been built.
+    pub(crate) fn mul_G_w(&self, w: &Scalar) -> RistrettoPoint {
+        #[cfg(feature = "precompute-tables")]
+        {
+            if let Some(tables) = &self.tables.0 {
+                return &tables.G_w * w;
+            }
+        }
+        w * self.G_w
+    }
+
+    /// Compute \(( x_0 * G_x_0 + x_1 * G_x_1 \)), using precomputed tables for
+    /// \(( G_x_0 \)) and \(( G_x_1 \)) if they have been built.
+    pub(crate) fn mul_G_x(&self, x_0: &Scalar, x_1: &Scalar) -> RistrettoPoint {
+        #[cfg(feature = "precompute-tables")]
+        {
+            if let Some(tables) = &self.tables.0 {
+                return (&tables.G_x_0 * x_0) + (&tables.G_x_1 * x_1);
+            }
+        }
+        (x_0 * self.G_x_0) + (x_1 * self.G_x_1)
+    }
 }
 
-/// DOCDOC
+/// The domain separator of generators derived from a label, by
+/// [`SystemParameters::generate_from_label`].
+const LABEL_DOMAIN: &[u8] = b"aeonflux SystemParameters label";
+
+impl SystemParameters {
+    /// Deterministically derive [`SystemParameters`] for `number_of_attributes`
+    /// from some `seed` bytes.
+    ///
+    /// Every generator is obtained by hashing the `seed`, the generator's
+    /// name, and its index to the group, so its discrete logarithm w.r.t. any
+    /// other generator is unknown.  A generator never depends upon the
+    /// `number_of_attributes`, hence parameters derived from the same `seed`
+    /// for different numbers of attributes are prefix-consistent.  The
+    /// `domain` separates the parameters of different derivations.
+    pub(crate) fn derive(
+        domain: &[u8],
+        seed: &[u8],
+        number_of_attributes: u32,
+    ) -> Result<SystemParameters, CredentialError>
+    {
+        if number_of_attributes > MAX_NUMBER_OF_ATTRIBUTES {
+            return Err(CredentialError::WrongNumberOfAttributes);
+        }
+
+        let mut number_of_G_y: u32 = number_of_attributes;
+
+        // The number of elements in G_y must always be at least three in order
+        // to support encrypted group element attributes.
+        if number_of_G_y < 3 {
+            number_of_G_y = 3;
+        }
+
+        let G_y: Vec<RistrettoPoint> = (0..number_of_G_y)
+            .map(|i| derive_generator(domain, seed, b"G_y", i))
+            .collect();
+        let G_m: Vec<RistrettoPoint> = (0..number_of_attributes)
+            .map(|i| derive_generator(domain, seed, b"G_m", i))
+            .collect();
+
+        let system_parameters = SystemParameters {
+            NUMBER_OF_ATTRIBUTES: number_of_attributes,
+            G:         RISTRETTO_BASEPOINT_POINT,
+            G_w:       derive_generator(domain, seed, b"G_w", 0),
+            G_w_prime: derive_generator(domain, seed, b"G_w_prime", 0),
+            G_x_0:     derive_generator(domain, seed, b"G_x_0", 0),
+            G_x_1:     derive_generator(domain, seed, b"G_x_1", 0),
+            G_y,
+            G_m,
+            G_V:       derive_generator(domain, seed, b"G_V", 0),
+            G_a:       derive_generator(domain, seed, b"G_a", 0),
+            G_a0:      derive_generator(domain, seed, b"G_a0", 0),
+            G_a1:      derive_generator(domain, seed, b"G_a1", 0),
+            #[cfg(feature = "precompute-tables")]
+            tables: GeneratorTables(None),
+        };
+
+        if !system_parameters.generators_are_unique() {
+            return Err(CredentialError::NoSystemParameters);
+        }
+        Ok(system_parameters)
+    }
+
+    /// Safety check: all generators should be generators (i.e. not the
+    /// identity element) and be unique.
+    pub(crate) fn generators_are_unique(&self) -> bool {
+        let mut generators: Vec<CompressedRistretto> = Vec::new();
+
+        generators.push(RistrettoPoint::identity().compress());
+        generators.push(self.G.compress());
+        generators.push(self.G_w.compress());
+        generators.push(self.G_w_prime.compress());
+        generators.push(self.G_x_0.compress());
+        generators.push(self.G_x_1.compress());
+        generators.push(self.G_V.compress());
+        generators.push(self.G_a.compress());
+        generators.push(self.G_a0.compress());
+        generators.push(self.G_a1.compress());
+        generators.extend(self.G_y.iter().map(|G_y_i| G_y_i.compress()));
+        generators.extend(self.G_m.iter().map(|G_m_i| G_m_i.compress()));
+
+        generators.sort_by(|a, b| a.as_bytes().cmp(b.as_bytes()));
+
+        generators.windows(2).all(|pair| pair[0] != pair[1])
+    }
+
+    /// Find the indices of the attribute generators \(( G_m_i \)) which
+    /// differ between `self` and `other`.
+    ///
+    /// The order of the generators is significant to the scheme, so two sets
+    /// of parameters holding the same generators in a different order will
+    /// (correctly) disagree at every reordered index.  If one set has more
+    /// attributes than the other, each index beyond the shorter set is
+    /// reported as differing.
+    ///
+    /// # Returns
+    ///
+    /// A `Vec<usize>` of the differing indices, in ascending order, which is
+    /// empty if all \(( G_m_i \)) agree.
+    pub fn diff(&self, other: &SystemParameters) -> Vec<usize> {
+        let longest = ::core::cmp::max(self.G_m.len(), other.G_m.len());
+
+        (0..longest).filter(|&i| self.G_m.get(i) != other.G_m.get(i)).collect()
+    }
+
+    /// Deterministically generate [`SystemParameters`] for `number_of_attributes`
+    /// from a 32-byte `seed`.
+    ///
+    /// The generators are derived by hashing to the group, as described in
+    /// [`SystemParameters::generate_family`].
+    pub fn generate_from_seed(
+        seed: &[u8; 32],
+        number_of_attributes: u32,
+    ) -> Result<SystemParameters, CredentialError>
+    {
+        SystemParameters::derive(DERIVATION_DOMAIN, &seed[..], number_of_attributes)
+    }
+
+    /// Generate [`SystemParameters`] from a freshly sampled seed, as in
+    /// [`SystemParameters::generate_from_seed`], and return the seed
+    /// alongside them.
+    ///
+    /// The operator may then securely archive the seed, so that the exact
+    /// parameters of a deployment can later be reproduced and audited.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the parameters and the seed from which
+    /// they were derived, otherwise a [`CredentialError`].
+    pub fn generate_with_recorded_seed<R>(
+        csprng: &mut R,
+        number_of_attributes: u32,
+    ) -> Result<(SystemParameters, [u8; 32]), CredentialError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let mut seed = [0u8; 32];
+
+        csprng.fill_bytes(&mut seed);
+
+        SystemParameters::generate_from_seed(&seed, number_of_attributes).map(|params| (params, seed))
+    }
+
+    /// Deterministically generate a family of [`SystemParameters`] from a
+    /// 32-byte `seed`, one for each number of attributes in `counts`.
+    ///
+    /// # Prefix-consistency
+    ///
+    /// Each generator is derived by hashing the `seed`, the generator's name,
+    /// and its index to the group, and never depends upon the number of
+    /// attributes.  Thus, for two members of the family supporting `k` and `n`
+    /// attributes, where `k < n`, the first `k` elements of `G_m` (and `G_y`)
+    /// and all other generators are identical.  This allows a credential
+    /// schema to grow, by switching to a larger member of the family, without
+    /// changing the generators for the existing attributes.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the parameters, in the same order as the
+    /// `counts`, otherwise a [`CredentialError`].
+    pub fn generate_family(
+        seed: &[u8; 32],
+        counts: &[u32],
+    ) -> Result<Vec<SystemParameters>, CredentialError>
+    {
+        counts.iter().map(|count| SystemParameters::generate_from_seed(seed, *count)).collect()
+    }
+
+    /// Generate "nothing-up-my-sleeve" [`SystemParameters`] for
+    /// `number_of_attributes` from a public `domain` string, e.g.
+    /// `b"example.com credentials 2020"`.
+    ///
+    /// Anyone may re-derive the generators from the `domain` in order to
+    /// check that no one knows their discrete logarithms.
+    pub fn generate_nums(
+        domain: &[u8],
+        number_of_attributes: u32,
+    ) -> Result<SystemParameters, CredentialError>
+    {
+        SystemParameters::derive(DERIVATION_DOMAIN, domain, number_of_attributes)
+    }
+
+    /// Deterministically generate [`SystemParameters`] for
+    /// `number_of_attributes` from a public `label`, rather than from a
+    /// `csprng`, so that independent parties may reproduce and audit
+    /// identical parameters.
+    ///
+    /// Each generator other than the basepoint \(( G \)) is the hash to the
+    /// group of a domain separator, the length-prefixed `label`, the
+    /// generator's name, and its index.  The domain separator differs from
+    /// that of [`SystemParameters::generate_nums`], so parameters generated
+    /// by each from the same bytes are unrelated.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the parameters, otherwise a
+    /// [`CredentialError`].
+    pub fn generate_from_label(
+        label: &[u8],
+        number_of_attributes: u32,
+    ) -> Result<SystemParameters, CredentialError>
+    {
+        SystemParameters::derive(LABEL_DOMAIN, label, number_of_attributes)
+    }
+
+    /// Migrate to a fresh basis of generators, derived as in
+    /// [`SystemParameters::generate_nums`] from a `new_domain`, for the same
+    /// number of attributes as these parameters.
+    ///
+    /// See [`SystemParameters::migration_note`] for what else a migration
+    /// entails.
+    pub fn rederive(&self, new_domain: &[u8]) -> Result<SystemParameters, CredentialError> {
+        SystemParameters::generate_nums(new_domain, self.NUMBER_OF_ATTRIBUTES)
+    }
+
+    /// What must be done after migrating to new parameters with
+    /// [`SystemParameters::rederive`].
+    pub fn migration_note() -> &'static str {
+        "Every SecretKey and IssuerParameters depend upon the generators of the \
+         SystemParameters they were created with, and every credential upon \
+         those keys.  After rederiving SystemParameters, all issuer keys must \
+         be regenerated and all credentials must be reissued under them."
+    }
+}
+
+impl SystemParameters {
+    /// Generate new system parameters, as in [`SystemParameters::generate`],
+    /// which may be cheaply shared between many threads.
+    pub fn generate_shared<R>(csprng: &mut R, number_of_attributes: u32)
+        -> Result<SharedSystemParameters, CredentialError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        SystemParameters::generate(csprng, number_of_attributes).map(SharedSystemParameters::from)
+    }
+}
+
+/// An immutable, reference-counted set of [`SystemParameters`].
+///
+/// Cloning a `SharedSystemParameters` only increments a reference count,
+/// rather than copying every generator (including the `G_y` and `G_m`
+/// vectors), so that e.g. each worker thread in a multi-threaded issuer may
+/// hold its own handle to a single copy of the parameters.  It dereferences to
+/// [`SystemParameters`], and thus may be used anywhere those are expected.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SharedSystemParameters(Arc<SystemParameters>);
+
+impl From<SystemParameters> for SharedSystemParameters {
+    fn from(source: SystemParameters) -> SharedSystemParameters {
+        SharedSystemParameters(Arc::new(source))
+    }
+}
+
+impl Deref for SharedSystemParameters {
+    type Target = SystemParameters;
+
+    fn deref(&self) -> &SystemParameters {
+        &self.0
+    }
+}
+
+/// The public parameters of an issuer, i.e. commitments to its
+/// [`SecretKey`], \(( C_W = G_w * w + G_w' * w' \)) and
+/// \(( I = G_V - (G_x_0 * x_0 + G_x_1 * x_1 + \sigma{i=1}{n} G_y_i * y_i) \)).
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct IssuerParameters {
     pub C_W: RistrettoPoint,
     pub I: RistrettoPoint,
 }
 
-/// DOCDOC
 impl IssuerParameters {
-    /// DOCDOC
+    /// Compute the public [`IssuerParameters`] for an issuer's `secret_key`.
     pub fn generate(system_parameters: &SystemParameters, secret_key: &SecretKey) -> IssuerParameters {
-        let C_W: RistrettoPoint = (system_parameters.G_w * secret_key.w) +
+        let C_W: RistrettoPoint = system_parameters.mul_G_w(&secret_key.w) +
                                   (system_parameters.G_w_prime * secret_key.w_prime);
 
         let mut I: RistrettoPoint = system_parameters.G_V -
-                                   (system_parameters.G_x_0 * secret_key.x_0) -
-                                   (system_parameters.G_x_1 * secret_key.x_1);
+                                    system_parameters.mul_G_x(&secret_key.x_0, &secret_key.x_1);
 
         for i in 0..system_parameters.NUMBER_OF_ATTRIBUTES as usize {
             I -= system_parameters.G_y[i] * secret_key.y[i];
@@ -361,23 +985,61 @@ impl IssuerParameters {
         IssuerParameters { C_W, I }
     }
 
-    /// DOCDOC
+    /// Compute the public [`IssuerParameters`] for an issuer's `secret_key`,
+    /// along with a [`ProofOfKeyGeneration`] with which clients may check
+    /// that they are well-formed before requesting credentials.
+    pub fn generate_with_proof(
+        system_parameters: &SystemParameters,
+        secret_key: &SecretKey,
+    ) -> (IssuerParameters, ProofOfKeyGeneration)
+    {
+        let issuer_parameters = IssuerParameters::generate(system_parameters, secret_key);
+        let proof = ProofOfKeyGeneration::prove(system_parameters, secret_key, &issuer_parameters);
+
+        (issuer_parameters, proof)
+    }
+
+    /// Deserialise [`IssuerParameters`] from the 64 bytes of the compressed
+    /// \(( C_W \)) and \(( I \)).
     pub fn from_bytes(bytes: &[u8]) -> Result<IssuerParameters, CredentialError> {
-        unimplemented!();
+        if bytes.len() != 64 {
+            return Err(CredentialError::NoIssuerParameters);
+        }
+
+        let mut chunk: [u8; 32] = [0u8; 32];
+
+        chunk.copy_from_slice(&bytes[0..32]);
+        let C_W: RistrettoPoint = try_deserialise!("C_W", chunk);
+
+        chunk.copy_from_slice(&bytes[32..64]);
+        let I: RistrettoPoint = try_deserialise!("I", chunk);
+
+        Ok(IssuerParameters { C_W, I })
     }
 
-    /// DOCDOC
+    /// Serialise these [`IssuerParameters`] as the compressed \(( C_W \))
+    /// followed by the compressed \(( I \)).
     pub fn to_bytes(&self) -> Vec<u8> {
-        unimplemented!();
+        let mut v: Vec<u8> = Vec::with_capacity(64);
+
+        v.extend(self.C_W.compress().to_bytes().iter());
+        v.extend(self.I.compress().to_bytes().iter());
+        v
     }
 }
 
 impl_serde_with_to_bytes_and_from_bytes!(IssuerParameters, "A valid byte sequence representing IssuerParameters");
 
+#[cfg(feature = "schema")]
+impl_json_schema_for_bytes!(IssuerParameters, "The serialised bytes of an IssuerParameters: \
+    the compressed Ristretto points C_W and I");
+
 #[cfg(test)]
 mod test {
     use super::*;
 
+    use crate::errors::MacError;
+
     use rand::thread_rng;
 
     #[test]
@@ -391,6 +1053,50 @@ mod test {
         assert!(system_parameters == deserialized);
     }
 
+    #[test]
+    fn system_parameters_wire_format() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 2).unwrap();
+        let raw = system_parameters.to_raw_bytes();
+        let mut serialized = system_parameters.to_bytes();
+
+        assert_eq!(&serialized[..4], wire::MAGIC);
+        assert_eq!(&serialized[wire::HEADER_LENGTH..], &raw[..]);
+
+        // Unversioned encodings are still accepted, and may be migrated.
+        assert!(SystemParameters::from_bytes(&raw).unwrap() == system_parameters);
+        assert_eq!(wire::migrate(&raw, WireType::SystemParameters).unwrap(), serialized);
+        assert_eq!(SecretKey::from_bytes(&serialized).unwrap_err(), MacError::UnsupportedEncoding);
+
+        serialized[4] = wire::VERSION + 1;
+
+        assert_eq!(SystemParameters::from_bytes(&serialized).unwrap_err(), CredentialError::UnsupportedEncoding);
+    }
+
+    #[test]
+    fn system_parameters_from_bytes_rejects_malformed_input() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 2).unwrap();
+        let body = system_parameters.to_raw_bytes();
+
+        for bytes in wire::malformations(WireType::SystemParameters, &body, &mut rng) {
+            let _ = SystemParameters::from_bytes(&bytes);
+        }
+        for length in 0..body.len() {
+            assert!(SystemParameters::from_raw_bytes(&body[..length]).is_err());
+        }
+
+        let mut bytes = body.clone();
+
+        bytes[..4].copy_from_slice(&u32::MAX.to_le_bytes());
+
+        assert_eq!(SystemParameters::from_raw_bytes(&bytes).unwrap_err(), CredentialError::WrongNumberOfAttributes);
+        assert_eq!(SystemParameters::generate(&mut rng, MAX_NUMBER_OF_ATTRIBUTES + 1).unwrap_err(),
+                   CredentialError::WrongNumberOfAttributes);
+        assert_eq!(SystemParameters::generate_from_seed(&[0u8; 32], u32::MAX).unwrap_err(),
+                   CredentialError::WrongNumberOfAttributes);
+    }
+
     #[test]
     fn hash_and_pray() {
         let mut rng = thread_rng();
@@ -398,6 +1104,164 @@ mod test {
         SystemParameters::hash_and_pray(&mut rng, 2).unwrap();
     }
 
+    #[test]
+    fn shared_system_parameters_across_threads() {
+        use crate::amacs::Amac;
+        use crate::amacs::Attribute;
+
+        use std::thread;
+
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate_shared(&mut rng, 2).unwrap();
+        let sk = Arc::new(SecretKey::generate(&mut rng, &system_parameters));
+        let mut workers = Vec::new();
+
+        for _ in 0..4 {
+            let system_parameters = system_parameters.clone();
+            let sk = sk.clone();
+
+            workers.push(thread::spawn(move || {
+                let mut rng = thread_rng();
+                let mut attributes = Vec::new();
+
+                attributes.push(Attribute::PublicScalar(Scalar::random(&mut rng)));
+                attributes.push(Attribute::SecretScalar(Scalar::random(&mut rng)));
+
+                let amac = Amac::tag(&mut rng, &system_parameters, &sk, &attributes).unwrap();
+
+                amac.verify(&system_parameters, &sk, &attributes)
+            }));
+        }
+
+        for worker in workers {
+            assert!(worker.join().unwrap().is_ok());
+        }
+    }
+
+    #[test]
+    fn generate_with_recorded_seed_is_reproducible() {
+        let mut rng = thread_rng();
+        let (params, seed) = SystemParameters::generate_with_recorded_seed(&mut rng, 3).unwrap();
+
+        assert_eq!(params, SystemParameters::generate_from_seed(&seed, 3).unwrap());
+    }
+
+    #[test]
+    fn generate_from_seed_is_deterministic() {
+        let seed = [7u8; 32];
+        let a = SystemParameters::generate_from_seed(&seed, 4).unwrap();
+        let b = SystemParameters::generate_from_seed(&seed, 4).unwrap();
+        let c = SystemParameters::generate_from_seed(&[8u8; 32], 4).unwrap();
+
+        assert_eq!(a, b);
+        assert!(a.G_w != c.G_w);
+        assert!(a.generators_are_unique());
+    }
+
+    #[test]
+    fn rederive_changes_every_generator() {
+        let old = SystemParameters::generate_nums(b"aeonflux test domain 1", 5).unwrap();
+        let new = old.rederive(b"aeonflux test domain 2").unwrap();
+
+        assert_eq!(new.NUMBER_OF_ATTRIBUTES, old.NUMBER_OF_ATTRIBUTES);
+        assert_eq!(new.G_m.len(), old.G_m.len());
+        assert!(new.G_w != old.G_w);
+        assert!(new.G_w_prime != old.G_w_prime);
+        assert!(new.G_x_0 != old.G_x_0);
+        assert!(new.G_x_1 != old.G_x_1);
+        assert!(new.G_V != old.G_V);
+        assert!(new.G_a != old.G_a);
+        assert!(new.G_a0 != old.G_a0);
+        assert!(new.G_a1 != old.G_a1);
+
+        for (G_y_new, G_y_old) in new.G_y.iter().zip(old.G_y.iter()) {
+            assert!(G_y_new != G_y_old);
+        }
+        for (G_m_new, G_m_old) in new.G_m.iter().zip(old.G_m.iter()) {
+            assert!(G_m_new != G_m_old);
+        }
+        assert_eq!(new, SystemParameters::generate_nums(b"aeonflux test domain 2", 5).unwrap());
+    }
+
+    #[test]
+    fn generate_from_label_is_reproducible() {
+        let a = SystemParameters::generate_from_label(b"aeonflux test label", 4).unwrap();
+        let b = SystemParameters::generate_from_label(b"aeonflux test label", 4).unwrap();
+        let c = SystemParameters::generate_from_label(b"aeonflux other label", 4).unwrap();
+
+        assert_eq!(a, b);
+        assert_eq!(a.diff(&SystemParameters::generate_nums(b"aeonflux test label", 4).unwrap()), vec![0, 1, 2, 3]);
+        assert_eq!(a.G, c.G);
+        assert_ne!(a.G_w, c.G_w);
+        assert_eq!(a.diff(&c), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn extend_keeps_existing_generators() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 2).unwrap();
+        let extended = system_parameters.extend(&mut rng, 3).unwrap();
+
+        assert_eq!(extended.NUMBER_OF_ATTRIBUTES, 5);
+        assert_eq!(extended.G_y.len(), 5);
+        assert_eq!(extended.G_m.len(), 5);
+        assert!(extended.extends(&system_parameters));
+        assert!(extended.extends(&extended));
+        assert!(!system_parameters.extends(&extended));
+        assert!(!extended.extends(&SystemParameters::generate(&mut rng, 2).unwrap()));
+        assert_eq!(SystemParameters::from_bytes(&extended.to_bytes()).unwrap(), extended);
+    }
+
+    #[test]
+    fn extend_rejects_too_many_attributes() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 2).unwrap();
+
+        assert_eq!(system_parameters.extend(&mut rng, MAX_NUMBER_OF_ATTRIBUTES - 1).unwrap_err(),
+                   CredentialError::WrongNumberOfAttributes);
+        assert_eq!(system_parameters.extend(&mut rng, u32::MAX).unwrap_err(),
+                   CredentialError::WrongNumberOfAttributes);
+    }
+
+    #[test]
+    fn diff_reports_differing_generators() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 4).unwrap();
+        let mut other = system_parameters.clone();
+
+        assert!(system_parameters.diff(&other).is_empty());
+
+        other.G_m[1] = RistrettoPoint::random(&mut rng);
+
+        assert_eq!(system_parameters.diff(&other), vec![1]);
+        assert_eq!(other.diff(&system_parameters), vec![1]);
+    }
+
+    #[test]
+    fn identifier_distinguishes_parameters() {
+        let system_parameters = SystemParameters::generate_from_seed(&[1u8; 32], 3).unwrap();
+        let other = SystemParameters::generate_from_seed(&[2u8; 32], 3).unwrap();
+
+        assert_eq!(system_parameters.identifier(), system_parameters.clone().identifier());
+        assert!(system_parameters.identifier() != other.identifier());
+    }
+
+    #[test]
+    fn generate_family_is_prefix_consistent() {
+        let family = SystemParameters::generate_family(&[42u8; 32], &[2, 8, 32]).unwrap();
+
+        assert_eq!(family.len(), 3);
+        assert_eq!(family[0].NUMBER_OF_ATTRIBUTES, 2);
+        assert_eq!(family[1].NUMBER_OF_ATTRIBUTES, 8);
+        assert_eq!(family[2].NUMBER_OF_ATTRIBUTES, 32);
+
+        assert_eq!(family[0].G_m[..], family[1].G_m[..2]);
+        assert_eq!(family[0].G_y[..], family[1].G_y[..3]);
+        assert_eq!(family[1].G_m[..], family[2].G_m[..8]);
+        assert_eq!(family[0].G_w, family[2].G_w);
+        assert_eq!(family[0].G_V, family[2].G_V);
+    }
+
     #[test]
     fn issuer_parameters_generate() {
         let mut rng = thread_rng();
@@ -407,4 +1271,48 @@ mod test {
 
         assert!(issuer_params.C_W != RistrettoPoint::identity());
     }
+
+    #[test]
+    fn issuer_parameters_serialize_deserialize() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 2).unwrap();
+        let sk = SecretKey::generate(&mut rng, &system_parameters);
+        let issuer_params = IssuerParameters::generate(&system_parameters, &sk);
+        let deserialized = IssuerParameters::from_bytes(&issuer_params.to_bytes()).unwrap();
+
+        assert_eq!(issuer_params, deserialized);
+    }
+
+    #[cfg(feature = "precompute-tables")]
+    #[test]
+    fn precomputed_tables_match_naive_multiplication() {
+        let mut rng = thread_rng();
+        let mut system_parameters = SystemParameters::generate(&mut rng, 4).unwrap();
+        let naive = system_parameters.clone();
+
+        system_parameters.precompute_tables();
+
+        assert_eq!(system_parameters, naive);
+
+        for i in 0..4 {
+            let m = Scalar::random(&mut rng);
+
+            assert_eq!(system_parameters.mul_G_m(i, &m), m * naive.G_m[i]);
+            assert_eq!(naive.mul_G_m(i, &m), m * naive.G_m[i]);
+        }
+
+        let w = Scalar::random(&mut rng);
+        let x_0 = Scalar::random(&mut rng);
+        let x_1 = Scalar::random(&mut rng);
+
+        assert_eq!(system_parameters.mul_G_w(&w), w * naive.G_w);
+        assert_eq!(system_parameters.mul_G_x(&x_0, &x_1), x_0 * naive.G_x_0 + x_1 * naive.G_x_1);
+
+        // Keys and issuer parameters are the same with or without the tables.
+        let secret_key = SecretKey::generate(&mut rng, &system_parameters);
+
+        assert_eq!(secret_key.W, naive.G_w * secret_key.w);
+        assert_eq!(IssuerParameters::generate(&system_parameters, &secret_key),
+                   IssuerParameters::generate(&naive, &secret_key));
+    }
 }
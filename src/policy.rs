@@ -0,0 +1,284 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2020 The Brave Authors
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Disclosure policies agreed upon by the presenter and the verifier.
+//!
+//! A [`Policy`] declares which attributes of a credential must be revealed
+//! upon presentation, which must stay hidden, and which predicates, i.e.
+//! range and set membership statements, must be proven about the hidden
+//! ones.  Disclosure is denied by default: every attribute which the policy
+//! doesn't declare revealed is hidden.
+//!
+//! The user presents their credential according to the policy with
+//! [`AnonymousCredential::show_with_policy`], and the issuer checks the
+//! presentation against the same policy with [`Issuer::verify_with_policy`],
+//! which fails if it reveals or hides any attribute other than as declared,
+//! or lacks any of the required predicates.
+//!
+//! [`AnonymousCredential::show_with_policy`]: crate::credential::AnonymousCredential::show_with_policy
+//! [`Issuer::verify_with_policy`]: crate::issuer::Issuer::verify_with_policy
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use curve25519_dalek::scalar::Scalar;
+
+use crate::amacs::EncryptedAttribute;
+use crate::credential::AnonymousCredential;
+use crate::errors::CredentialError;
+use crate::nizk::membership::SetMembershipStatement;
+use crate::nizk::presentation::ProofOfValidCredential;
+use crate::nizk::range::RangeStatement;
+
+/// Which attributes of a credential are revealed or hidden upon
+/// presentation, which predicates must be proven about the hidden ones, and
+/// the nonce, if any, to which presentations are bound.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Policy {
+    /// The indices of the attributes which must be revealed.  Every other
+    /// attribute is hidden.  Defaults to none.
+    pub revealed: Vec<usize>,
+    /// The indices of the attributes which must stay hidden, and which
+    /// therefore may not also be revealed.  Defaults to none.
+    pub hidden: Vec<usize>,
+    /// Statements that hidden scalar attributes lie within ranges.  A
+    /// presentation may instead prove a narrower range.  Defaults to none.
+    pub ranges: Vec<RangeStatement>,
+    /// Statements that hidden scalar attributes are members of sets.  A
+    /// presentation may instead prove membership of a subset.  Defaults to
+    /// none.
+    pub memberships: Vec<SetMembershipStatement>,
+    /// A nonce chosen by the issuer, to prevent the replay of captured
+    /// presentations.  Defaults to none.
+    pub nonce: Option<Vec<u8>>,
+}
+
+impl Policy {
+    /// Create a policy which hides every attribute and requires no
+    /// predicates.
+    pub fn new() -> Policy {
+        Policy::default()
+    }
+
+    /// Reveal the attribute at `index`.
+    pub fn reveal(mut self, index: usize) -> Policy {
+        self.revealed.push(index);
+        self
+    }
+
+    /// Require the attribute at `index` to stay hidden.
+    pub fn hide(mut self, index: usize) -> Policy {
+        self.hidden.push(index);
+        self
+    }
+
+    /// Require a proof that the hidden scalar attribute at `index` lies
+    /// within the inclusive range `[min, max]`.
+    pub fn require_range(mut self, index: usize, min: u64, max: u64) -> Policy {
+        self.ranges.push(RangeStatement { index, min, max });
+        self
+    }
+
+    /// Require a proof that the hidden scalar attribute at `index` is one of
+    /// the values in the `set`.
+    pub fn require_membership(mut self, index: usize, set: Vec<Scalar>) -> Policy {
+        self.memberships.push(SetMembershipStatement { index, set });
+        self
+    }
+
+    /// Bind presentations to a `nonce`, which should be fresh for every
+    /// presentation.
+    pub fn with_nonce(mut self, nonce: &[u8]) -> Policy {
+        self.nonce = Some(nonce.to_vec());
+        self
+    }
+
+    /// The indices of every attribute which this policy requires to be
+    /// hidden, whether explicitly or by a predicate.
+    fn required_hidden(&self) -> impl Iterator<Item = usize> + '_ {
+        self.hidden.iter().copied()
+            .chain(self.ranges.iter().map(|statement| statement.index))
+            .chain(self.memberships.iter().map(|statement| statement.index))
+    }
+
+    /// Check that this policy is consistent for a credential with some
+    /// `number_of_attributes`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is empty, otherwise a
+    /// [`CredentialError::WrongNumberOfAttributes`] if the policy refers to
+    /// an attribute which doesn't exist, or a
+    /// [`CredentialError::BadAttribute`] if it both reveals an attribute and
+    /// requires it to be hidden.
+    pub fn check(&self, number_of_attributes: usize) -> Result<(), CredentialError> {
+        if self.revealed.iter().copied().chain(self.required_hidden()).any(|index| index >= number_of_attributes) {
+            return Err(CredentialError::WrongNumberOfAttributes);
+        }
+        if self.required_hidden().any(|index| self.revealed.contains(&index)) {
+            return Err(CredentialError::BadAttribute);
+        }
+        Ok(())
+    }
+
+    /// Mark the attributes of a `credential` as revealed or hidden according
+    /// to this policy.
+    pub(crate) fn apply(&self, credential: &mut AnonymousCredential) -> Result<(), CredentialError> {
+        self.check(credential.attributes.len())?;
+
+        for i in 0..credential.attributes.len() {
+            match self.revealed.contains(&i) {
+                true  => credential.reveal_attribute(i),
+                false => credential.hide_attribute(i),
+            }.or(Err(CredentialError::BadAttribute))?;
+        }
+        Ok(())
+    }
+
+    /// Check that a `presentation` reveals and hides exactly the attributes
+    /// declared by this policy, and proves each of its predicates.
+    ///
+    /// This doesn't verify the presentation itself, which must be done
+    /// separately, as by [`Issuer::verify_with_policy`].
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is empty, otherwise a
+    /// [`CredentialError::PolicyViolation`] if the presentation doesn't
+    /// satisfy the policy, or another [`CredentialError`] if the policy
+    /// itself is inconsistent, as for [`Policy::check`].
+    ///
+    /// [`Issuer::verify_with_policy`]: crate::issuer::Issuer::verify_with_policy
+    pub fn check_presentation(&self, presentation: &ProofOfValidCredential) -> Result<(), CredentialError> {
+        self.check(presentation.attributes().len())?;
+
+        for (i, attribute) in presentation.attributes().iter().enumerate() {
+            let revealed = match attribute {
+                EncryptedAttribute::PublicScalar(_) | EncryptedAttribute::PublicPoint(_) => true,
+                EncryptedAttribute::SecretScalar | EncryptedAttribute::SecretPoint => false,
+            };
+
+            if revealed != self.revealed.contains(&i) {
+                return Err(CredentialError::PolicyViolation);
+            }
+        }
+
+        let ranges = presentation.range_statements();
+        let memberships = presentation.membership_statements();
+
+        for required in self.ranges.iter() {
+            if !ranges.iter().any(|proven| proven.index == required.index &&
+                                           proven.min >= required.min && proven.max <= required.max) {
+                return Err(CredentialError::PolicyViolation);
+            }
+        }
+        for required in self.memberships.iter() {
+            if !memberships.iter().any(|proven| proven.index == required.index &&
+                                                proven.set.iter().all(|x| required.set.contains(x))) {
+                return Err(CredentialError::PolicyViolation);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::amacs::Attribute;
+    use crate::issuer::Issuer;
+    use crate::oneshot;
+    use crate::parameters::SystemParameters;
+    use crate::user::CredentialRequestConstructor;
+
+    use curve25519_dalek::ristretto::RistrettoPoint;
+
+    use rand::thread_rng;
+
+    #[test]
+    fn presentations_must_satisfy_the_policy() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 3).unwrap();
+        let issuer = Issuer::new(&system_parameters, &mut rng);
+        let mut request = CredentialRequestConstructor::new(&system_parameters);
+
+        request.append_revealed_scalar(Scalar::from(7u64));
+        request.append_revealed_scalar(Scalar::from(30u64));
+        request.append_revealed_scalar(Scalar::from(2u64));
+
+        let issuance = issuer.issue(request.finish(), &mut rng).unwrap();
+        let credential = issuance.verify(&system_parameters, &issuer.issuer_parameters).unwrap();
+        let policy = Policy::new()
+            .reveal(0)
+            .require_range(1, 18, 200)
+            .require_membership(2, vec![Scalar::from(1u64), Scalar::from(2u64), Scalar::from(3u64)])
+            .with_nonce(b"fresh nonce");
+
+        let presentation = credential.show_with_policy(&system_parameters, &issuer.issuer_parameters, None,
+                                                       &policy, &mut rng).unwrap();
+
+        assert!(issuer.verify_with_policy(&presentation, &policy).is_ok());
+        assert!(matches!(presentation.attributes()[0], EncryptedAttribute::PublicScalar(_)));
+        assert!(matches!(presentation.attributes()[1], EncryptedAttribute::SecretScalar));
+
+        // Attributes which aren't declared revealed are hidden by default,
+        // and must have been.
+        assert_eq!(issuer.verify_with_policy(&presentation, &Policy::new().reveal(0).reveal(1).with_nonce(b"fresh nonce")),
+                   Err(CredentialError::PolicyViolation));
+        assert_eq!(issuer.verify_with_policy(&presentation, &Policy { revealed: vec![], ..policy.clone() }),
+                   Err(CredentialError::PolicyViolation));
+
+        // Nor may a policy reveal an attribute which it requires to be hidden.
+        assert_eq!(issuer.verify_with_policy(&presentation, &policy.clone().reveal(2)),
+                   Err(CredentialError::BadAttribute));
+
+        // Required predicates must be proven, at least as tightly.
+        assert_eq!(issuer.verify_with_policy(&presentation, &policy.clone().require_range(1, 18, 100)),
+                   Err(CredentialError::PolicyViolation));
+        assert_eq!(issuer.verify_with_policy(&presentation, &policy.clone().require_range(2, 0, 10)),
+                   Err(CredentialError::PolicyViolation));
+        assert!(issuer.verify_with_policy(&presentation, &policy.clone().hide(1)).is_ok());
+        assert!(issuer.verify_with_policy(&presentation,
+                                          &Policy { memberships: vec![], ..policy.clone() }).is_ok());
+
+        // The presentation is bound to the policy's nonce.
+        assert!(issuer.verify_with_policy(&presentation, &policy.clone().with_nonce(b"stale nonce")).is_err());
+
+        // Presentations of values outside a required range cannot be made.
+        assert!(credential.show_with_policy(&system_parameters, &issuer.issuer_parameters, None,
+                                            &policy.clone().require_range(1, 0, 10), &mut rng).is_err());
+    }
+
+    #[test]
+    fn inconsistent_policies_are_rejected() {
+        assert!(Policy::new().reveal(0).hide(1).require_range(2, 0, 1).check(3).is_ok());
+        assert_eq!(Policy::new().reveal(3).check(3), Err(CredentialError::WrongNumberOfAttributes));
+        assert_eq!(Policy::new().require_membership(5, vec![]).check(3), Err(CredentialError::WrongNumberOfAttributes));
+        assert_eq!(Policy::new().reveal(1).hide(1).check(3), Err(CredentialError::BadAttribute));
+        assert_eq!(Policy::new().reveal(1).require_range(1, 0, 1).check(3), Err(CredentialError::BadAttribute));
+
+        // Public group element attributes cannot be hidden, so must be revealed.
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 2).unwrap();
+        let issuer = Issuer::new(&system_parameters, &mut rng);
+        let attributes = vec![Attribute::PublicScalar(Scalar::one()),
+                              Attribute::PublicPoint(RistrettoPoint::random(&mut rng))];
+        let issuance = oneshot::issue(&mut rng, &issuer, attributes).unwrap();
+        let credential = oneshot::receive(issuance, &system_parameters, &issuer.issuer_parameters).unwrap();
+
+        assert_eq!(credential.show_with_policy(&system_parameters, &issuer.issuer_parameters, None,
+                                               &Policy::new(), &mut rng).err(),
+                   Some(CredentialError::BadAttribute));
+        assert!(credential.show_with_policy(&system_parameters, &issuer.issuer_parameters, None,
+                                            &Policy::new().reveal(1), &mut rng).is_ok());
+    }
+}
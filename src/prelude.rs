@@ -13,8 +13,15 @@
 pub use curve25519_dalek::ristretto::RistrettoPoint;
 pub use curve25519_dalek::scalar::Scalar;
 
+pub use zkp::Transcript;
+
 pub use crate::amacs::Attribute;
 pub use crate::issuer::Issuer;
+pub use crate::oneshot::issue;
+pub use crate::oneshot::receive;
+pub use crate::oneshot::show;
+pub use crate::oneshot::verify;
 pub use crate::parameters::SystemParameters;
+pub use crate::policy::Policy;
 pub use crate::symmetric::Plaintext;
 pub use crate::symmetric::Keypair;
@@ -0,0 +1,334 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2020 The Brave Authors
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Revocation of credentials with a keyed-verification accumulator.
+//!
+//! Each credential bears a revocation handle \\(( y \\)) as a scalar
+//! attribute, chosen by the issuer and hidden upon presentation.  The issuer
+//! keeps an [`Accumulator`], whose secret key is \\(( \alpha \\)) and whose
+//! public value is \\(( V \\)), and gives each user a [`MembershipWitness`]
+//! \\(( W = V * (\alpha + y)^{-1} \\)) for their handle.  Every handle is
+//! implicitly a member until it is revoked, so issuing a new witness doesn't
+//! change the accumulator.
+//!
+//! Revoking \\(( y' \\)) replaces the value with
+//! \\(( V' = V * (\alpha + y')^{-1} \\)), and publishes a
+//! [`RevocationUpdate`] of \\(( (y', V') \\)), with which every other user
+//! updates their witness as \\(( W' = (W - V') * (y' - y)^{-1} \\)).
+//!
+//! To prove non-revocation, the user randomises their witness as
+//! \\(( \bar{W} = W * r \\)), and proves knowledge of \\(( r \\)) and
+//! \\(( y \\)) such that \\(( \bar{V} = V * r - \bar{W} * y \\)), where
+//! \\(( y \\)) is the handle committed to in their presentation.  The issuer
+//! computes \\(( \bar{V} = \bar{W} * \alpha \\)) itself, which only holds for
+//! a witness of an unrevoked handle.  As with the credentials themselves, no
+//! pairings are needed, since the verifier knows the secret key.
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::IsIdentity;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use zeroize::Zeroize;
+
+use zkp::CompactProof;
+use zkp::Transcript;
+use zkp::toolbox::SchnorrCS;
+use zkp::toolbox::prover::Prover;
+use zkp::toolbox::verifier::Verifier;
+
+use crate::amacs::Attribute;
+use crate::amacs::EncryptedAttribute;
+use crate::credential::AnonymousCredential;
+use crate::errors::CredentialError;
+use crate::nizk::presentation::ProofOfValidCredential;
+use crate::parameters::{IssuerParameters, SystemParameters};
+
+/// The public value of an [`Accumulator`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct AccumulatorValue(pub(crate) RistrettoPoint);
+
+/// An issuer's accumulator of unrevoked revocation handles.
+pub struct Accumulator {
+    secret: Scalar,
+    value: RistrettoPoint,
+}
+
+/// Overwrite the accumulator's secret key when it drops out of scope.
+impl Drop for Accumulator {
+    fn drop(&mut self) {
+        self.secret.zeroize();
+    }
+}
+
+/// The publication of a revoked handle and the accumulator's new value, with
+/// which users update their [`MembershipWitness`]es.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RevocationUpdate {
+    /// The revoked handle.
+    pub handle: Scalar,
+    /// The value of the accumulator after the handle was revoked.
+    pub value: AccumulatorValue,
+}
+
+/// A witness that a revocation handle hasn't been revoked from an
+/// [`Accumulator`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MembershipWitness(pub(crate) RistrettoPoint);
+
+impl Accumulator {
+    /// Create a new accumulator, from which no handles have been revoked.
+    pub fn new<C>(csprng: &mut C) -> Accumulator
+    where
+        C: RngCore + CryptoRng,
+    {
+        Accumulator { secret: Scalar::random(csprng), value: RistrettoPoint::random(csprng) }
+    }
+
+    /// The current public value of this accumulator.
+    pub fn value(&self) -> AccumulatorValue {
+        AccumulatorValue(self.value)
+    }
+
+    /// Create a [`MembershipWitness`] for the revocation `handle` of a newly
+    /// issued credential, against the current value of this accumulator.
+    pub fn witness(&self, handle: &Scalar) -> MembershipWitness {
+        MembershipWitness(self.value * (self.secret + handle).invert())
+    }
+
+    /// Revoke the `handle`.
+    ///
+    /// # Returns
+    ///
+    /// The [`RevocationUpdate`] to publish to users.
+    pub fn revoke(&mut self, handle: &Scalar) -> RevocationUpdate {
+        self.value *= (self.secret + handle).invert();
+
+        RevocationUpdate { handle: *handle, value: self.value() }
+    }
+
+    /// Verify that the revocation handle of a [`NonRevocationPresentation`]
+    /// is a member of this accumulator, i.e. hasn't been revoked.
+    ///
+    /// This does *not* verify the presentation itself, for which see
+    /// [`Issuer::verify_non_revoked`](crate::issuer::Issuer::verify_non_revoked).
+    pub fn verify(
+        &self,
+        system_parameters: &SystemParameters,
+        presentation: &NonRevocationPresentation,
+    ) -> Result<(), CredentialError>
+    {
+        let index = presentation.index as usize;
+
+        match presentation.presentation.attributes().get(index) {
+            Some(EncryptedAttribute::SecretScalar) => (),
+            _ => return Err(CredentialError::BadAttribute),
+        }
+        if index >= presentation.presentation.C_y.len() || index >= system_parameters.G_m.len() {
+            return Err(CredentialError::WrongNumberOfAttributes);
+        }
+        if presentation.W.is_identity() {
+            return Err(CredentialError::Revoked);
+        }
+
+        let mut transcript = Transcript::new(b"2019/1416 anonymous credential");
+        let mut verifier = Verifier::new(b"2019/1416 non-revocation proof", &mut transcript);
+
+        // Feed the domain separators for the Camenisch-Stadler secrets into the protocol transcript.
+        let y = verifier.allocate_scalar(b"y");
+        let z = verifier.allocate_scalar(b"z");
+        let r = verifier.allocate_scalar(b"r");
+
+        // Feed in the domain separators and values for the publics into the transcript.
+        let G_y_i   = verifier.allocate_point(b"G_y", system_parameters.G_y[index].compress())?;
+        let G_m_i   = verifier.allocate_point(b"G_m", system_parameters.G_m[index].compress())?;
+        let C_y_i   = verifier.allocate_point(b"C_y", presentation.presentation.C_y[index].compress())?;
+        let V       = verifier.allocate_point(b"V", self.value.compress())?;
+        let minus_W = verifier.allocate_point(b"-W", (-presentation.W).compress())?;
+        let V_bar   = verifier.allocate_point(b"V'", (presentation.W * self.secret).compress())?;
+
+        // Constraint #1: C_y_i = G_y_i * z + G_m_i * y
+        verifier.constrain(C_y_i, vec![(z, G_y_i), (y, G_m_i)]);
+
+        // Constraint #2: V' = V * r - W' * y
+        verifier.constrain(V_bar, vec![(r, V), (y, minus_W)]);
+
+        verifier.verify_compact(&presentation.proof).or(Err(CredentialError::Revoked))
+    }
+}
+
+impl MembershipWitness {
+    /// Update this witness for the revocation `handle` after an `update`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is empty, otherwise a
+    /// [`CredentialError::Revoked`] if the `handle` was itself revoked.
+    pub fn update(&mut self, handle: &Scalar, update: &RevocationUpdate) -> Result<(), CredentialError> {
+        if update.handle == *handle {
+            return Err(CredentialError::Revoked);
+        }
+
+        self.0 = (self.0 - update.value.0) * (update.handle - handle).invert();
+
+        Ok(())
+    }
+
+    /// Update this witness for the revocation `handle` after each of the
+    /// `updates`, in the order in which they were published.
+    pub fn update_batch(&mut self, handle: &Scalar, updates: &[RevocationUpdate]) -> Result<(), CredentialError> {
+        for update in updates.iter() {
+            self.update(handle, update)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A presentation of a credential, along with a proof that its revocation
+/// handle hasn't been revoked.
+pub struct NonRevocationPresentation {
+    presentation: ProofOfValidCredential,
+    index: u16,
+    W: RistrettoPoint,
+    proof: CompactProof,
+}
+
+impl NonRevocationPresentation {
+    /// Present the `credential`, proving that its revocation handle, which
+    /// is the hidden scalar attribute at `index`, is a member of the
+    /// accumulator whose current value is `value`, with the user's up to
+    /// date `witness`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the `NonRevocationPresentation`,
+    /// otherwise a [`CredentialError`].
+    pub fn new<C>(
+        system_parameters: &SystemParameters,
+        issuer_parameters: &IssuerParameters,
+        credential: &AnonymousCredential,
+        index: usize,
+        witness: &MembershipWitness,
+        value: &AccumulatorValue,
+        csprng: &mut C,
+    ) -> Result<NonRevocationPresentation, CredentialError>
+    where
+        C: RngCore + CryptoRng,
+    {
+        let y_ = match credential.attributes.get(index) {
+            Some(Attribute::SecretScalar(y)) => *y,
+            _ => return Err(CredentialError::BadAttribute),
+        };
+        let (presentation, z_) = ProofOfValidCredential::prove_with_commitment_nonce(system_parameters, issuer_parameters,
+                                                                                     credential, None, csprng)?;
+
+        let r_: Scalar = Scalar::random(csprng);
+        let W_: RistrettoPoint = witness.0 * r_;
+        let V_bar_: RistrettoPoint = (value.0 * r_) - (W_ * y_);
+
+        let mut transcript = Transcript::new(b"2019/1416 anonymous credential");
+        let mut prover = Prover::new(b"2019/1416 non-revocation proof", &mut transcript);
+
+        // Feed the domain separators for the Camenisch-Stadler secrets into the protocol transcript.
+        let y = prover.allocate_scalar(b"y", y_);
+        let z = prover.allocate_scalar(b"z", z_);
+        let r = prover.allocate_scalar(b"r", r_);
+
+        // Feed in the domain separators and values for the publics into the transcript.
+        let (G_y_i, _)   = prover.allocate_point(b"G_y", system_parameters.G_y[index]);
+        let (G_m_i, _)   = prover.allocate_point(b"G_m", system_parameters.G_m[index]);
+        let (C_y_i, _)   = prover.allocate_point(b"C_y", presentation.C_y[index]);
+        let (V, _)       = prover.allocate_point(b"V", value.0);
+        let (minus_W, _) = prover.allocate_point(b"-W", -W_);
+        let (V_bar, _)   = prover.allocate_point(b"V'", V_bar_);
+
+        // Constraint #1: C_y_i = G_y_i * z + G_m_i * y
+        prover.constrain(C_y_i, vec![(z, G_y_i), (y, G_m_i)]);
+
+        // Constraint #2: V' = V * r - W' * y
+        prover.constrain(V_bar, vec![(r, V), (y, minus_W)]);
+
+        let proof = prover.prove_compact();
+
+        Ok(NonRevocationPresentation { presentation, index: index as u16, W: W_, proof })
+    }
+
+    /// The presentation of the credential.
+    pub fn presentation(&self) -> &ProofOfValidCredential {
+        &self.presentation
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[cfg(all(not(feature = "std"), feature = "alloc"))]
+    use alloc::vec::Vec;
+    #[cfg(all(not(feature = "alloc"), feature = "std"))]
+    use std::vec::Vec;
+
+    use crate::issuer::Issuer;
+    use crate::user::CredentialRequestConstructor;
+
+    use rand::thread_rng;
+
+    #[test]
+    fn revoked_credentials_cannot_prove_non_revocation() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 2).unwrap();
+        let issuer = Issuer::new(&system_parameters, &mut rng);
+        let mut accumulator = Accumulator::new(&mut rng);
+        let mut credentials: Vec<(Scalar, AnonymousCredential, MembershipWitness)> = Vec::new();
+
+        for _ in 0..3 {
+            let handle = Scalar::random(&mut rng);
+            let mut request = CredentialRequestConstructor::new(&system_parameters);
+
+            request.append_revealed_scalar(Scalar::random(&mut rng));
+            request.append_revealed_scalar(handle);
+
+            let issuance = issuer.issue(request.finish(), &mut rng).unwrap();
+            let mut credential = issuance.verify(&system_parameters, &issuer.issuer_parameters).unwrap();
+
+            credential.hide_attribute(1).unwrap();
+            credentials.push((handle, credential, accumulator.witness(&handle)));
+        }
+
+        let present = |credential: &AnonymousCredential, witness: &MembershipWitness, value: &AccumulatorValue| {
+            NonRevocationPresentation::new(&system_parameters, &issuer.issuer_parameters, credential, 1,
+                                           witness, value, &mut thread_rng()).unwrap()
+        };
+
+        for (_, credential, witness) in credentials.iter() {
+            let presentation = present(credential, witness, &accumulator.value());
+
+            assert!(issuer.verify_non_revoked(&accumulator, &presentation).is_ok());
+        }
+
+        let stale = accumulator.value();
+        let updates = vec![accumulator.revoke(&credentials[1].0), accumulator.revoke(&credentials[2].0)];
+
+        // Unrevoked users may update their witnesses, and revoked users may not.
+        let (handle, credential, mut witness) = credentials[0].clone();
+
+        assert!(issuer.verify_non_revoked(&accumulator, &present(&credential, &witness, &stale)).is_err());
+        assert!(witness.update_batch(&handle, &updates).is_ok());
+        assert!(issuer.verify_non_revoked(&accumulator, &present(&credential, &witness, &accumulator.value())).is_ok());
+
+        let (handle, credential, mut witness) = credentials[2].clone();
+
+        assert_eq!(witness.update_batch(&handle, &updates), Err(CredentialError::Revoked));
+        assert_eq!(accumulator.verify(&system_parameters, &present(&credential, &witness, &accumulator.value())),
+                   Err(CredentialError::Revoked));
+    }
+}
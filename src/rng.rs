@@ -0,0 +1,48 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2020 The Brave Authors
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Type-erased cryptographically secure random number generators.
+//!
+//! Every function in this crate which requires randomness is generic over
+//! `R: RngCore + CryptoRng`.  For callers which hold a single boxed or
+//! otherwise erased RNG, such as one backed by an HSM, the `*_dyn` variants of
+//! these functions instead accept a `&mut dyn CryptoRngCore`, and are
+//! monomorphised only once.
+
+use curve25519_dalek::scalar::Scalar;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+/// A cryptographically secure RNG which may be used as a trait object.
+///
+/// This is implemented for every `RngCore + CryptoRng`.
+pub trait CryptoRngCore: RngCore + CryptoRng {}
+
+impl<R: RngCore + CryptoRng + ?Sized> CryptoRngCore for R {}
+
+/// Sample a secret scalar which is neither zero nor one, re-sampling until
+/// it is.
+///
+/// A working CSPRNG produces either with negligible probability, but a broken
+/// one (e.g. returning all zeroes) would otherwise silently produce a key for
+/// which the MAC is trivially forgeable.  An RNG which only ever produces
+/// degenerate scalars never yields a key at all.
+pub(crate) fn random_nondegenerate_scalar<R>(csprng: &mut R) -> Scalar
+where
+    R: RngCore + CryptoRng,
+{
+    loop {
+        let x: Scalar = Scalar::random(csprng);
+
+        if x != Scalar::zero() && x != Scalar::one() {
+            return x;
+        }
+    }
+}
@@ -0,0 +1,69 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2020 The Brave Authors
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! JSON Schema descriptions of the serialised forms of this crate's public
+//! types, for generating matching types in clients written in other
+//! languages.
+//!
+//! Every serialisable type in this crate is serialised as a single byte
+//! string, which human-readable formats (e.g. `serde_json`) represent as a
+//! hexadecimal string.  Algebraic MACs and presented attributes
+//! have no serialised form of their own; they are only ever sent within
+//! credentials and proofs.
+
+use std::string::String;
+
+use schemars::gen::SchemaGenerator;
+
+use crate::parameters::IssuerParameters;
+use crate::parameters::SystemParameters;
+
+/// Export the JSON Schema definitions of the [`SystemParameters`] and
+/// [`IssuerParameters`] as a single JSON object, whose `definitions` are
+/// keyed by type name.
+pub fn export_schemas() -> String {
+    let mut generator = SchemaGenerator::default();
+
+    generator.subschema_for::<SystemParameters>();
+    generator.subschema_for::<IssuerParameters>();
+
+    let schemas = serde_json::json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "definitions": generator.take_definitions(),
+    });
+
+    // This can't fail, since the schemas contain no non-string map keys.
+    serde_json::to_string_pretty(&schemas).unwrap()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use serde_json::Value;
+
+    #[test]
+    fn exported_schemas_are_valid_json() {
+        let schemas: Value = serde_json::from_str(&export_schemas()).unwrap();
+        let definitions = &schemas["definitions"];
+
+        assert_eq!(definitions["SystemParameters"]["type"], "string");
+        assert_eq!(definitions["IssuerParameters"]["type"], "string");
+    }
+
+    #[test]
+    fn json_is_hexadecimal() {
+        let system_parameters = SystemParameters::generate_from_seed(&[1u8; 32], 2).unwrap();
+        let json = serde_json::to_string(&system_parameters).unwrap();
+
+        assert_eq!(json, format!("\"{}\"", crate::encoding::Hex(&system_parameters.to_bytes()[..])));
+        assert_eq!(serde_json::from_str::<SystemParameters>(&json).unwrap(), system_parameters);
+        assert!(serde_json::from_str::<SystemParameters>("\"0g\"").is_err());
+    }
+}
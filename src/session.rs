@@ -0,0 +1,155 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2020 The Brave Authors
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Server-side state for the blind issuance exchange.
+//!
+//! An [`IssuerSession`] walks an issuer through the rounds of a blind
+//! issuance:
+//!
+//! 1. The issuer opens a session and sends its fresh
+//!    [`challenge`](IssuerSession::challenge) to the user.
+//! 2. The user creates a [`Request`] bound to the challenge with
+//!    [`Request::new_with_nonce`] and sends it to the issuer.
+//! 3. The issuer answers it with [`IssuerSession::issue`], and the user
+//!    finishes their credential from the [`Response`] as usual.
+//!
+//! Each session answers at most one request, and only one bound to its own
+//! challenge, so that requests captured from one exchange can be replayed
+//! neither within it nor to any other.
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use crate::errors::CredentialError;
+use crate::issuer::Issuer;
+use crate::nizk::issuance::Request;
+use crate::nizk::issuance::Response;
+
+/// The round of an [`IssuerSession`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SessionState {
+    /// The challenge has been created, and the session awaits the user's
+    /// request.
+    AwaitingRequest,
+    /// A credential has been issued, and the session is over.
+    Issued,
+    /// The user's request failed to verify, and the session is over.
+    Aborted,
+}
+
+/// An issuer's state for a single blind issuance exchange with a user.
+#[derive(Debug)]
+pub struct IssuerSession {
+    challenge: [u8; 32],
+    state: SessionState,
+}
+
+impl IssuerSession {
+    /// Open a new session with a fresh challenge.
+    pub fn new<C>(csprng: &mut C) -> IssuerSession
+    where
+        C: RngCore + CryptoRng,
+    {
+        let mut challenge = [0u8; 32];
+
+        csprng.fill_bytes(&mut challenge);
+
+        IssuerSession { challenge, state: SessionState::AwaitingRequest }
+    }
+
+    /// The challenge to send to the user, to which their [`Request`] must
+    /// be bound.
+    pub fn challenge(&self) -> &[u8; 32] {
+        &self.challenge
+    }
+
+    /// The current round of this session.
+    pub fn state(&self) -> SessionState {
+        self.state
+    }
+
+    /// Blindly issue a credential, as in [`Issuer::issue_blind`], for the
+    /// user's `request`, which must be bound to this session's challenge.
+    ///
+    /// Whether or not the request verifies, the session is then over.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the [`Response`] to send to the user,
+    /// otherwise a [`CredentialError::UnexpectedMessage`] if the session has
+    /// already answered a request, or a [`CredentialError`] if the request
+    /// doesn't verify, e.g. because it was bound to another challenge.
+    pub fn issue<C>(
+        &mut self,
+        issuer: &Issuer,
+        request: &Request,
+        csprng: &mut C,
+    ) -> Result<Response, CredentialError>
+    where
+        C: RngCore + CryptoRng,
+    {
+        if self.state != SessionState::AwaitingRequest {
+            return Err(CredentialError::UnexpectedMessage);
+        }
+
+        let response = Response::prove_with_nonce(issuer, request, &self.challenge, csprng);
+
+        self.state = match response {
+            Ok(_)  => SessionState::Issued,
+            Err(_) => SessionState::Aborted,
+        };
+        response
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::amacs::Attribute;
+    use crate::parameters::SystemParameters;
+
+    use curve25519_dalek::scalar::Scalar;
+
+    use rand::thread_rng;
+
+    #[test]
+    fn sessions_reject_replayed_requests() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 2).unwrap();
+        let issuer = Issuer::new(&system_parameters, &mut rng);
+        let attributes = vec![Attribute::SecretScalar(Scalar::random(&mut rng)),
+                              Attribute::PublicScalar(Scalar::random(&mut rng))];
+
+        let mut session = IssuerSession::new(&mut rng);
+        let (request, state) = Request::new_with_nonce(&system_parameters, attributes.clone(),
+                                                       session.challenge(), &mut rng).unwrap();
+        let response = session.issue(&issuer, &request, &mut rng).unwrap();
+
+        assert_eq!(session.state(), SessionState::Issued);
+        assert!(state.finish(&system_parameters, &issuer.issuer_parameters, &request, &response).is_ok());
+
+        // The request cannot be answered twice by the same session...
+        assert_eq!(session.issue(&issuer, &request, &mut rng).err(), Some(CredentialError::UnexpectedMessage));
+
+        // ...nor by another, whose challenge differs.
+        let mut other = IssuerSession::new(&mut rng);
+
+        assert_eq!(other.issue(&issuer, &request, &mut rng).err(), Some(CredentialError::VerificationFailure));
+        assert_eq!(other.state(), SessionState::Aborted);
+        assert_eq!(other.issue(&issuer, &request, &mut rng).err(), Some(CredentialError::UnexpectedMessage));
+
+        // Requests which aren't bound to any challenge are rejected.
+        let mut session = IssuerSession::new(&mut rng);
+        let (request, _) = Request::new(&system_parameters, attributes, &mut rng).unwrap();
+
+        assert!(session.issue(&issuer, &request, &mut rng).is_err());
+        assert!(issuer.issue_blind(&request, &mut rng).is_ok());
+    }
+}
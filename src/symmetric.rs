@@ -21,6 +21,18 @@
 //!
 //! * is correct under adversarially chosen keys, meaning that it is hard to
 //!   find a key and a message that cause decryption to fail.
+//!
+//! Since ciphertexts are unique, decryption recomputes \\(( E_1 \\)) from
+//! the decrypted message and rejects any ciphertext which was tampered with.
+//! A [`Plaintext`] may further be bound to some associated data, e.g. an
+//! issuer id or an epoch, with [`Plaintext::with_associated_data`], in which
+//! case its ciphertext only decrypts with
+//! [`Keypair::decrypt_with_associated_data`] given the same associated data.
+//! The binding is made through the hashes \\(( M_2 \\)) and \\(( m_3 \\))
+//! of the message, so such ciphertexts remain verifiable with a
+//! [`ProofOfEncryption`].
+//!
+//! [`ProofOfEncryption`]: crate::nizk::encryption::ProofOfEncryption
 
 #[cfg(all(not(feature = "std"), feature = "alloc"))]
 use alloc::vec::Vec;
@@ -34,6 +46,7 @@ use curve25519_dalek::traits::Identity;
 use rand_core::CryptoRng;
 use rand_core::RngCore;
 
+use sha2::Digest;
 use sha2::Sha512;
 
 use subtle::Choice;
@@ -114,7 +127,39 @@ impl Drop for Plaintext {
     }
 }
 
+/// Hash a `message` along with its length-prefixed `associated_data`,
+/// under the domain separation `label`.
+fn hash_with_associated_data(label: &[u8], associated_data: &[u8], message: &[u8]) -> Sha512 {
+    Sha512::default()
+        .chain(label)
+        .chain((associated_data.len() as u64).to_le_bytes())
+        .chain(associated_data)
+        .chain(message)
+}
+
 impl Plaintext {
+    /// Encode thirty bytes of `source` into a [`Plaintext`] whose encryption
+    /// is bound to some `associated_data`, e.g. an issuer id or an epoch.
+    ///
+    /// The message \\(( M_1 \\)) is encoded as usual, but its hashes
+    /// \\(( M_2 \\)) and \\(( m_3 \\)) also cover the associated data,
+    /// so that the ciphertext only decrypts with
+    /// [`Keypair::decrypt_with_associated_data`] given the same associated
+    /// data.
+    pub fn with_associated_data(source: &[u8; 30], associated_data: &[u8]) -> Plaintext {
+        let (M1, _) = encode_to_group(source);
+
+        Plaintext::bind(M1, source, associated_data)
+    }
+
+    /// Hash the message `m`, encoded as `M1`, along with some `associated_data`.
+    fn bind(M1: RistrettoPoint, m: &[u8; 30], associated_data: &[u8]) -> Plaintext {
+        let M2 = RistrettoPoint::from_hash(hash_with_associated_data(b"aeonflux Plaintext M2", associated_data, m));
+        let m3 = Scalar::from_hash(hash_with_associated_data(b"aeonflux Plaintext m3", associated_data, m));
+
+        Plaintext { M1, M2, m3 }
+    }
+
     pub(crate) fn from_slice(slice: &[u8]) -> Vec<Plaintext> {
         let mut plaintexts: Vec<Plaintext> = Vec::new();
 
@@ -130,6 +175,66 @@ impl Plaintext {
 
         plaintexts
     }
+
+    /// Encode an arbitrary-length `message`, such as an email address or a
+    /// handle, into as many [`Plaintext`]s as are needed to hold it, e.g. as
+    /// consecutive [`Attribute::EitherPoint`] or [`Attribute::SecretPoint`]
+    /// attributes.
+    ///
+    /// The message is canonically padded with a single `0x80` byte followed
+    /// by as many zeroes as are needed to fill the last chunk, so that every
+    /// message has exactly one encoding, and messages which differ only in
+    /// trailing zeroes are encoded differently.
+    ///
+    /// [`Attribute::EitherPoint`]: crate::amacs::Attribute::EitherPoint
+    /// [`Attribute::SecretPoint`]: crate::amacs::Attribute::SecretPoint
+    pub fn encode_chunks(message: &[u8]) -> Vec<Plaintext> {
+        let mut bytes: Vec<u8> = Vec::with_capacity(sizeof_chunks(message.len()) * 30);
+
+        bytes.extend(message);
+        bytes.push(0x80);
+
+        let plaintexts = Plaintext::from_slice(&bytes[..]);
+
+        bytes.zeroize();
+        plaintexts
+    }
+
+    /// Decode a message from the `plaintexts` produced by
+    /// [`Plaintext::encode_chunks`].
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the message, otherwise a
+    /// [`CredentialError::UnsupportedEncoding`] if the padding isn't
+    /// canonical.
+    pub fn decode_chunks(plaintexts: &[Plaintext]) -> Result<Vec<u8>, CredentialError> {
+        let mut bytes: Vec<u8> = Vec::with_capacity(30 * plaintexts.len());
+
+        for plaintext in plaintexts.iter() {
+            bytes.extend(&<[u8; 30]>::from(plaintext));
+        }
+
+        // The padding must be a 0x80 byte and fewer than thirty zeroes.
+        let length = bytes.iter().rposition(|byte| *byte != 0);
+
+        match length {
+            Some(length) if bytes[length] == 0x80 && bytes.len() - length <= 30 => {
+                bytes.truncate(length);
+                Ok(bytes)
+            },
+            _ => {
+                bytes.zeroize();
+                Err(CredentialError::UnsupportedEncoding)
+            },
+        }
+    }
+}
+
+/// The number of [`Plaintext`]s needed to encode a message of `length`
+/// bytes with [`Plaintext::encode_chunks`].
+pub fn sizeof_chunks(length: usize) -> usize {
+    (length + 1 + 29) / 30
 }
 
 impl From<&[u8; 30]> for Plaintext {
@@ -200,6 +305,43 @@ impl Keypair {
     ) -> Keypair
     {
         let a: Scalar = Scalar::hash_from_bytes::<Sha512>(&master_secret[..]);
+
+        Keypair::from_secret_scalar(a, system_parameters)
+    }
+
+    /// Derive a [`Keypair`] for a single credential from a master secret.
+    ///
+    /// Each credential's hidden group element attributes are then encrypted
+    /// under a different key, so that compromising the key of one credential
+    /// doesn't expose the hidden attributes of any other, and so that the
+    /// public keys revealed upon presentation of different credentials are
+    /// unlinkable.
+    ///
+    /// # Inputs
+    ///
+    /// * A [`MasterSecret`],
+    /// * the `credential_id`, e.g. an [`AnonymousCredential::id`], and
+    /// * some [`SystemParameters`].
+    ///
+    /// # Returns
+    ///
+    /// A `Keypair`.
+    ///
+    /// [`AnonymousCredential::id`]: crate::credential::AnonymousCredential::id
+    pub fn derive_for_credential(
+        master_secret: &MasterSecret,
+        credential_id: &[u8],
+        system_parameters: &SystemParameters,
+    ) -> Keypair
+    {
+        let a: Scalar = Scalar::from_hash(hash_with_associated_data(b"aeonflux credential Keypair",
+                                                                    credential_id, &master_secret[..]));
+
+        Keypair::from_secret_scalar(a, system_parameters)
+    }
+
+    /// Expand the secret scalar `a` into a [`Keypair`].
+    fn from_secret_scalar(a: Scalar, system_parameters: &SystemParameters) -> Keypair {
         let a0: Scalar = Scalar::hash_from_bytes::<Sha512>(a.as_bytes());
         let a1: Scalar = Scalar::hash_from_bytes::<Sha512>(a0.as_bytes());
 
@@ -268,22 +410,61 @@ impl Keypair {
     ///
     /// # Returns
     ///
-    /// A `Result` whose `Ok` value is the [`Plaintext`], otherwise a [`CredentialError`].
+    /// A `Result` whose `Ok` value is the [`Plaintext`], otherwise a
+    /// [`CredentialError::UndecryptableAttribute`] if the ciphertext was
+    /// tampered with, or is of a plaintext bound to associated data.
     // XXX TODO return the counter
     pub fn decrypt(
         &self,
         ciphertext: &Ciphertext,
     ) -> Result<Plaintext, CredentialError>
+    {
+        self.decrypt_inner(ciphertext, None)
+    }
+
+    /// Use this key to decrypt a ciphertext of a [`Plaintext`] created with
+    /// [`Plaintext::with_associated_data`].
+    ///
+    /// # Inputs
+    ///
+    /// * The [`Ciphertext`] to be decrypted, and
+    /// * the associated data to which its plaintext is bound.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the [`Plaintext`], otherwise a
+    /// [`CredentialError::UndecryptableAttribute`] if the ciphertext was
+    /// tampered with, or its plaintext is bound to different associated data.
+    pub fn decrypt_with_associated_data(
+        &self,
+        ciphertext: &Ciphertext,
+        associated_data: &[u8],
+    ) -> Result<Plaintext, CredentialError>
+    {
+        self.decrypt_inner(ciphertext, Some(associated_data))
+    }
+
+    fn decrypt_inner(
+        &self,
+        ciphertext: &Ciphertext,
+        associated_data: Option<&[u8]>,
+    ) -> Result<Plaintext, CredentialError>
     {
         let M1_prime = ciphertext.E2 - (ciphertext.E1 * self.secret.a);
         let (m_prime, _) = decode_from_group(&M1_prime);
-        let m3_prime = Scalar::hash_from_bytes::<Sha512>(&m_prime);
 
-        let M2_prime = RistrettoPoint::hash_from_bytes::<Sha512>(&m_prime);
-        let E1_prime = M2_prime * (self.secret.a0 + self.secret.a1 * m3_prime);
+        let plaintext = match associated_data {
+            Some(associated_data) => Plaintext::bind(M1_prime, &m_prime, associated_data),
+            None => Plaintext {
+                M1: M1_prime,
+                M2: RistrettoPoint::hash_from_bytes::<Sha512>(&m_prime),
+                m3: Scalar::hash_from_bytes::<Sha512>(&m_prime),
+            },
+        };
+        let E1_prime = plaintext.M2 * (self.secret.a0 + self.secret.a1 * plaintext.m3);
 
         match ciphertext.E1 == E1_prime {
-            true => Ok(Plaintext { M1: M1_prime, M2: M2_prime, m3: m3_prime }),
+            true => Ok(plaintext),
             false => Err(CredentialError::UndecryptableAttribute),
         }
     }
@@ -308,4 +489,76 @@ mod test {
         assert!(decrypted.is_ok());
         assert_eq!(plaintext, decrypted.unwrap());
     }
+
+    #[test]
+    fn associated_data_is_bound() {
+        let mut csprng = thread_rng();
+        let system_parameters = SystemParameters::hash_and_pray(&mut csprng, 2).unwrap();
+        let (keypair, _) = Keypair::generate(&system_parameters, &mut csprng);
+        let message = b"This is a tsunami alert test..";
+        let plaintext = Plaintext::with_associated_data(message, b"issuer 1, epoch 7");
+        let ciphertext = keypair.encrypt(&plaintext);
+        let decrypted = keypair.decrypt_with_associated_data(&ciphertext, b"issuer 1, epoch 7").unwrap();
+
+        assert_eq!(plaintext, decrypted);
+        assert_eq!(&<[u8; 30]>::from(&decrypted), message);
+
+        // Other associated data, or none at all, is rejected.
+        assert!(keypair.decrypt_with_associated_data(&ciphertext, b"issuer 1, epoch 8").is_err());
+        assert!(keypair.decrypt(&ciphertext).is_err());
+        assert!(keypair.decrypt_with_associated_data(&keypair.encrypt(&message.into()), b"").is_err());
+
+        // As are tampered ciphertexts.
+        let tampered = Ciphertext { E1: ciphertext.E1, E2: ciphertext.E2 + system_parameters.G_a };
+
+        assert!(keypair.decrypt_with_associated_data(&tampered, b"issuer 1, epoch 7").is_err());
+    }
+
+    #[test]
+    fn chunks_roundtrip_with_canonical_padding() {
+        for length in &[0usize, 1, 29, 30, 31, 59, 60, 100] {
+            let message: Vec<u8> = (0..*length).map(|i| i as u8 | 1).collect();
+            let plaintexts = Plaintext::encode_chunks(&message);
+
+            assert_eq!(plaintexts.len(), sizeof_chunks(*length));
+            assert_eq!(Plaintext::decode_chunks(&plaintexts).unwrap(), message);
+        }
+
+        // Trailing zeroes are kept.
+        let plaintexts = Plaintext::encode_chunks(b"alice\0\0");
+
+        assert_eq!(Plaintext::decode_chunks(&plaintexts).unwrap(), b"alice\0\0".to_vec());
+        assert_ne!(plaintexts, Plaintext::encode_chunks(b"alice"));
+
+        // Unpadded, or overly padded, chunks are rejected.
+        assert!(Plaintext::decode_chunks(&[Plaintext::from(&[1u8; 30])]).is_err());
+        assert!(Plaintext::decode_chunks(&[Plaintext::from(&[0u8; 30])]).is_err());
+        assert!(Plaintext::decode_chunks(&[]).is_err());
+
+        let mut padded = Plaintext::encode_chunks(&[7u8; 30]);
+
+        padded.push(Plaintext::from(&[0u8; 30]));
+
+        assert!(Plaintext::decode_chunks(&padded).is_err());
+    }
+
+    #[test]
+    fn credential_keypairs_are_independent() {
+        let mut csprng = thread_rng();
+        let system_parameters = SystemParameters::hash_and_pray(&mut csprng, 2).unwrap();
+        let (keypair, master_secret) = Keypair::generate(&system_parameters, &mut csprng);
+        let first = Keypair::derive_for_credential(&master_secret, b"first", &system_parameters);
+        let second = Keypair::derive_for_credential(&master_secret, b"second", &system_parameters);
+        let again = Keypair::derive_for_credential(&master_secret, b"first", &system_parameters);
+
+        assert_eq!(first.public.pk, again.public.pk);
+        assert!(first.public.pk != second.public.pk);
+        assert!(first.public.pk != keypair.public.pk);
+
+        // A ciphertext under one credential's key doesn't decrypt under another's.
+        let ciphertext = first.encrypt(&(&[7u8; 30]).into());
+
+        assert!(again.decrypt(&ciphertext).is_ok());
+        assert!(second.decrypt(&ciphertext).is_err());
+    }
 }
@@ -0,0 +1,87 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2020 The Brave Authors
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Helpers for writing tests against this crate, enabled with the
+//! `test-util` feature.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+
+use rand_core::CryptoRng;
+use rand_core::RngCore;
+
+use crate::amacs::Attribute;
+use crate::symmetric::Plaintext;
+
+/// Generate a random 30-byte plaintext for a group element attribute.
+fn random_plaintext<R>(csprng: &mut R) -> Plaintext
+where
+    R: RngCore + CryptoRng,
+{
+    let mut bytes = [0u8; 30];
+
+    csprng.fill_bytes(&mut bytes);
+
+    (&bytes).into()
+}
+
+/// Generate `count` random attributes, cycling through every variant of
+/// [`Attribute`] in the order `PublicScalar`, `SecretScalar`, `PublicPoint`,
+/// `EitherPoint`, and `SecretPoint`, so that any `count` of at least five
+/// exercises all of them.
+pub fn all_variant_attributes<R>(csprng: &mut R, count: usize) -> Vec<Attribute>
+where
+    R: RngCore + CryptoRng,
+{
+    let mut attributes: Vec<Attribute> = Vec::with_capacity(count);
+
+    for i in 0..count {
+        attributes.push(match i % 5 {
+            0 => Attribute::PublicScalar(Scalar::random(csprng)),
+            1 => Attribute::SecretScalar(Scalar::random(csprng)),
+            2 => Attribute::PublicPoint(RistrettoPoint::random(csprng)),
+            3 => Attribute::EitherPoint(random_plaintext(csprng)),
+            _ => Attribute::SecretPoint(random_plaintext(csprng)),
+        });
+    }
+    attributes
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rand::thread_rng;
+
+    use crate::amacs::Amac;
+    use crate::amacs::SecretKey;
+    use crate::parameters::SystemParameters;
+
+    #[test]
+    fn all_variant_attributes_tag_and_verify() {
+        let mut rng = thread_rng();
+
+        for count in 1..=8 {
+            let params = SystemParameters::generate(&mut rng, count as u32).unwrap();
+            let sk = SecretKey::generate(&mut rng, &params);
+            let attributes = all_variant_attributes(&mut rng, count);
+
+            assert_eq!(attributes.len(), count);
+
+            let amac = Amac::tag(&mut rng, &params, &sk, &attributes).unwrap();
+
+            assert!(amac.verify(&params, &sk, &attributes).is_ok());
+        }
+    }
+}
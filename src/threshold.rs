@@ -0,0 +1,262 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2020 The Brave Authors
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Threshold issuance of anonymous credentials.
+//!
+//! An issuer's [`SecretKey`] is Shamir secret-shared with
+//! [`SecretKey::split`] amongst \\(( n \\)) issuer nodes, any \\(( t \\)) of
+//! which can jointly issue a credential without the whole key ever being
+//! recombined.  Since \\(( V \\)) of an algebraic MAC, like the
+//! [`IssuerParameters`], is linear in the key for fixed nonces
+//! \\(( (t, U) \\)), each node computes a [`PartialTag`] with its share, and
+//! the user interpolates \\(( t \\)) of them into the MAC.
+//!
+//! All nodes must use the same nonces, so these are derived from a session
+//! identifier, which should be unique to each issuance, and the attributes.
+//!
+//! # Warning
+//!
+//! Partial tags do not yet carry proofs of their correctness, so a faulty
+//! node goes undetected until the resulting credential fails to verify.
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+
+use sha2::Digest;
+use sha2::Sha512;
+
+use crate::amacs::attributes_to_bytes;
+use crate::amacs::lagrange_coefficient;
+use crate::amacs::Amac;
+use crate::amacs::Attribute;
+use crate::amacs::KeyShare;
+use crate::amacs::SecretKey;
+use crate::credential::AnonymousCredential;
+use crate::errors::MacError;
+use crate::parameters::IssuerParameters;
+use crate::parameters::SystemParameters;
+
+/// An issuer node holding one [`KeyShare`] of a threshold issuer's key.
+pub struct IssuerNode {
+    system_parameters: SystemParameters,
+    index: u8,
+    threshold: u8,
+    key: SecretKey,
+}
+
+/// One issuer node's share of an algebraic MAC.
+#[derive(Clone, Debug)]
+pub struct PartialTag {
+    index: u8,
+    threshold: u8,
+    t: Scalar,
+    U: RistrettoPoint,
+    V: RistrettoPoint,
+}
+
+/// One issuer node's share of the threshold issuer's [`IssuerParameters`].
+#[derive(Clone, Debug)]
+pub struct PartialParameters {
+    index: u8,
+    threshold: u8,
+    parameters: IssuerParameters,
+}
+
+/// Derive the nonces \\(( (t, U) \\)) for an issuance `session` upon some
+/// `attributes`.
+fn derive_nonces(session: &[u8], attributes: &[Attribute]) -> (Scalar, RistrettoPoint) {
+    let attributes = attributes_to_bytes(attributes);
+    let hasher = |label: &[u8]| {
+        let mut hasher = Sha512::default();
+
+        hasher.input(b"aeonflux threshold issuance");
+        hasher.input(label);
+        hasher.input((session.len() as u64).to_le_bytes());
+        hasher.input(session);
+        hasher.input(&attributes[..]);
+        hasher
+    };
+
+    (Scalar::from_hash(hasher(b"t")), RistrettoPoint::from_hash(hasher(b"U")))
+}
+
+/// Check that some shares, given as `(index, threshold)` pairs, are at least
+/// a threshold of distinct shares of the same key.
+fn check_shares(shares: &[(u8, u8)]) -> Result<Vec<u8>, MacError> {
+    if shares.is_empty() || shares.len() < shares[0].1 as usize {
+        return Err(MacError::KeyShareError);
+    }
+
+    for (i, (index, threshold)) in shares.iter().enumerate() {
+        if *index == 0 || *threshold != shares[0].1 || shares[..i].iter().any(|other| other.0 == *index) {
+            return Err(MacError::KeyShareError);
+        }
+    }
+
+    Ok(shares.iter().map(|(index, _)| *index).collect())
+}
+
+impl IssuerNode {
+    /// Create an issuer node from its `share` of the issuer's key.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the node, otherwise a
+    /// [`MacError::KeyShareError`] if the share is inconsistent with the
+    /// `system_parameters`.
+    pub fn new(system_parameters: &SystemParameters, share: &KeyShare) -> Result<IssuerNode, MacError> {
+        if share.index == 0 || share.y.len() != system_parameters.NUMBER_OF_ATTRIBUTES as usize {
+            return Err(MacError::KeyShareError);
+        }
+
+        Ok(IssuerNode {
+            system_parameters: system_parameters.clone(),
+            index: share.index,
+            threshold: share.threshold,
+            key: share.to_secret_key(system_parameters),
+        })
+    }
+
+    /// Compute this node's [`PartialTag`] upon some `attributes` for an
+    /// issuance `session`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the partial tag, otherwise a
+    /// [`MacError::MessageLengthError`] if there are the wrong number of
+    /// `attributes`.
+    pub fn partial_tag(&self, session: &[u8], attributes: &[Attribute]) -> Result<PartialTag, MacError> {
+        if attributes.len() != self.system_parameters.NUMBER_OF_ATTRIBUTES as usize {
+            return Err(MacError::MessageLengthError{length: self.system_parameters.NUMBER_OF_ATTRIBUTES as usize});
+        }
+
+        let (t, U) = derive_nonces(session, attributes);
+        let V = Amac::compute_V(&self.system_parameters, &self.key, attributes, &t, &U);
+
+        Ok(PartialTag { index: self.index, threshold: self.threshold, t, U, V })
+    }
+
+    /// Compute this node's share of the issuer's public parameters.
+    pub fn partial_parameters(&self) -> PartialParameters {
+        PartialParameters {
+            index: self.index,
+            threshold: self.threshold,
+            parameters: IssuerParameters::generate(&self.system_parameters, &self.key),
+        }
+    }
+}
+
+/// Aggregate at least a threshold of [`PartialTag`]s, from distinct issuer
+/// nodes, upon some `attributes` into an [`AnonymousCredential`] issued
+/// under the `system_parameters`.
+///
+/// # Returns
+///
+/// A `Result` whose `Ok` value is the credential, otherwise a
+/// [`MacError::KeyShareError`] if there are too few partial tags, or they
+/// are duplicated or were computed for different sessions or attributes.
+pub fn aggregate_tags(
+    system_parameters: &SystemParameters,
+    partials: &[PartialTag],
+    attributes: Vec<Attribute>,
+) -> Result<AnonymousCredential, MacError>
+{
+    let shares: Vec<(u8, u8)> = partials.iter().map(|partial| (partial.index, partial.threshold)).collect();
+    let indices = check_shares(&shares)?;
+
+    if partials.iter().any(|partial| partial.t != partials[0].t || partial.U != partials[0].U) {
+        return Err(MacError::KeyShareError);
+    }
+
+    let V = partials.iter().fold(RistrettoPoint::identity(), |V, partial| {
+        V + partial.V * lagrange_coefficient(partial.index, &indices)
+    });
+    let amac = Amac { t: partials[0].t, U: partials[0].U, V };
+
+    Ok(AnonymousCredential { amac, attributes, parameters_fingerprint: system_parameters.identifier() })
+}
+
+/// Aggregate at least a threshold of [`PartialParameters`], from distinct
+/// issuer nodes, into the threshold issuer's [`IssuerParameters`].
+///
+/// # Returns
+///
+/// A `Result` whose `Ok` value is the issuer parameters, otherwise a
+/// [`MacError::KeyShareError`] if there are too few partial parameters, or
+/// they are duplicated.
+pub fn aggregate_parameters(partials: &[PartialParameters]) -> Result<IssuerParameters, MacError> {
+    let shares: Vec<(u8, u8)> = partials.iter().map(|partial| (partial.index, partial.threshold)).collect();
+    let indices = check_shares(&shares)?;
+
+    let mut C_W = RistrettoPoint::identity();
+    let mut I = RistrettoPoint::identity();
+
+    for partial in partials.iter() {
+        let lambda = lagrange_coefficient(partial.index, &indices);
+
+        C_W += partial.parameters.C_W * lambda;
+        I += partial.parameters.I * lambda;
+    }
+
+    Ok(IssuerParameters { C_W, I })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::issuer::Issuer;
+
+    use rand::thread_rng;
+
+    #[test]
+    fn threshold_issuance_2_of_3() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 2).unwrap();
+        let secret_key = SecretKey::generate(&mut rng, &system_parameters);
+        let shares = secret_key.split(2, 3, &mut rng).unwrap();
+        let nodes: Vec<IssuerNode> = shares.iter().map(|share| IssuerNode::new(&system_parameters, share).unwrap()).collect();
+        let attributes = vec![Attribute::PublicScalar(Scalar::random(&mut rng)),
+                              Attribute::SecretScalar(Scalar::random(&mut rng))];
+
+        let partials = vec![nodes[0].partial_tag(b"session 1", &attributes).unwrap(),
+                            nodes[2].partial_tag(b"session 1", &attributes).unwrap()];
+        let credential = aggregate_tags(&system_parameters, &partials, attributes.clone()).unwrap();
+
+        assert!(credential.amac.verify(&system_parameters, &secret_key, &attributes).is_ok());
+
+        let parameters = aggregate_parameters(&[nodes[1].partial_parameters(), nodes[2].partial_parameters()]).unwrap();
+
+        assert_eq!(parameters, IssuerParameters::generate(&system_parameters, &secret_key));
+
+        let issuer = Issuer { system_parameters: system_parameters.clone(), issuer_parameters: parameters, amacs_key: secret_key };
+        let presentation = credential.show(&system_parameters, &issuer.issuer_parameters, None, &mut rng).unwrap();
+
+        assert!(issuer.verify(&presentation).is_ok());
+
+        // Too few, duplicated, or mismatched partial tags are rejected.
+        let mismatched = vec![nodes[0].partial_tag(b"session 1", &attributes).unwrap(),
+                              nodes[1].partial_tag(b"session 2", &attributes).unwrap()];
+
+        let duplicated = vec![partials[0].clone(), partials[0].clone()];
+
+        assert_eq!(aggregate_tags(&system_parameters, &partials[..1], attributes.clone()).unwrap_err(),
+                   MacError::KeyShareError);
+        assert_eq!(aggregate_tags(&system_parameters, &duplicated, attributes.clone()).unwrap_err(),
+                   MacError::KeyShareError);
+        assert_eq!(aggregate_tags(&system_parameters, &mismatched, attributes).unwrap_err(),
+                   MacError::KeyShareError);
+    }
+}
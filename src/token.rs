@@ -0,0 +1,138 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2020 The Brave Authors
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Rate-limiting tokens, i.e. credentials which may be shown at most \\(( k \\))
+//! times within a scope.
+//!
+//! A token credential bears a random seed \\(( s \\)) as a hidden scalar
+//! attribute, which should be blindly issued with
+//! [`Request`](crate::nizk::issuance::Request) so that the issuer never
+//! learns it, and a limit \\(( k \\)) as a scalar attribute encoded with
+//! [`encode_u64`](crate::attributes::encode::encode_u64), which the issuer
+//! sets.  Each show within a scope consumes one of the counters
+//! \\(( 0 \leq j < k \\)), and carries:
+//!
+//! * a [`Nullifier`] derived from \\(( s \\)) within a context of the scope and
+//!   \\(( j \\)), which is the token's serial number for that counter; and
+//! * a proof that the hidden limit is at least \\(( j + 1 \\)).
+//!
+//! Since a serial number is deterministic, a verifier detects a counter being
+//! spent twice, and so accepts at most \\(( k \\)) shows of a token per scope,
+//! while serial numbers are otherwise unlinkable to each other and to the
+//! token's issuance.
+//!
+//! # Warning
+//!
+//! The counter \\(( j \\)) is revealed to the verifier.  Users should spend
+//! their unused counters in a uniformly random order, rather than
+//! sequentially, lest the counter reveal how many times they have already
+//! shown their token.  The limit must be hidden upon presentation, with
+//! [`AnonymousCredential::hide_attribute`], lest it partition users.
+//!
+//! Verifiers obtain the counter and serial number of a show with
+//! [`ProofOfValidCredential::token_serial`], and must check the presentation
+//! with [`Issuer::verify`] and that the serial number is unspent.
+//!
+//! [`AnonymousCredential::hide_attribute`]: crate::credential::AnonymousCredential::hide_attribute
+//! [`Issuer::verify`]: crate::issuer::Issuer::verify
+//! [`Nullifier`]: crate::nizk::nullifier::Nullifier
+//! [`ProofOfValidCredential::token_serial`]: crate::nizk::presentation::ProofOfValidCredential::token_serial
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+/// The prefix of the context of a token's serial number, which keeps it
+/// apart from the contexts of other nullifiers.
+const SERIAL_CONTEXT: &[u8] = b"aeonflux k-show token";
+
+/// The context of the serial number of the `counter`th show of a token
+/// within a `scope`.
+pub(crate) fn serial_context(scope: &[u8], counter: u64) -> Vec<u8> {
+    let mut context = Vec::with_capacity(SERIAL_CONTEXT.len() + 8 + scope.len());
+
+    context.extend_from_slice(SERIAL_CONTEXT);
+    context.extend_from_slice(&counter.to_le_bytes());
+    context.extend_from_slice(scope);
+    context
+}
+
+/// Parse the context of a token's serial number into its counter and scope.
+pub(crate) fn parse_serial_context(context: &[u8]) -> Option<(u64, &[u8])> {
+    if context.len() < SERIAL_CONTEXT.len() + 8 || !context.starts_with(SERIAL_CONTEXT) {
+        return None;
+    }
+
+    let mut counter = [0u8; 8];
+
+    counter.copy_from_slice(&context[SERIAL_CONTEXT.len()..SERIAL_CONTEXT.len() + 8]);
+
+    Some((u64::from_le_bytes(counter), &context[SERIAL_CONTEXT.len() + 8..]))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::amacs::Attribute;
+    use crate::attributes::encode::encode_u64;
+    use crate::credential::PresentationOptions;
+    use crate::issuer::Issuer;
+    use crate::nizk::issuance::Request;
+    use crate::parameters::SystemParameters;
+
+    use curve25519_dalek::scalar::Scalar;
+
+    use rand::thread_rng;
+
+    #[test]
+    fn token_shows_are_limited_per_scope() {
+        let mut rng = thread_rng();
+        let system_parameters = SystemParameters::generate(&mut rng, 2).unwrap();
+        let issuer = Issuer::new(&system_parameters, &mut rng);
+        let attributes = vec![Attribute::SecretScalar(Scalar::random(&mut rng)), Attribute::PublicScalar(encode_u64(3))];
+
+        let (request, state) = Request::new(&system_parameters, attributes, &mut rng).unwrap();
+        let response = issuer.issue_blind(&request, &mut rng).unwrap();
+        let mut token = state.finish(&system_parameters, &issuer.issuer_parameters, &request, &response).unwrap();
+
+        token.hide_attribute(1).unwrap();
+
+        let show = |counter: u64, scope: &[u8]| {
+            let options = PresentationOptions::default().with_token(0, 1, scope, counter);
+
+            token.show_with_options(&system_parameters, &issuer.issuer_parameters, None, &options, thread_rng())
+        };
+
+        let first = show(0, b"example.com").unwrap();
+        let again = show(0, b"example.com").unwrap();
+        let second = show(1, b"example.com").unwrap();
+        let elsewhere = show(0, b"example.org").unwrap();
+
+        for presentation in [&first, &again, &second, &elsewhere].iter() {
+            assert!(issuer.verify(presentation).is_ok());
+        }
+
+        let (counter, serial) = first.token_serial(0, 1, b"example.com").unwrap();
+
+        assert_eq!(counter, 0);
+        assert_eq!(again.token_serial(0, 1, b"example.com"), Some((0, serial)));
+        assert_ne!(second.token_serial(0, 1, b"example.com").unwrap().1, serial);
+        assert_ne!(elsewhere.token_serial(0, 1, b"example.org").unwrap().1, serial);
+        assert!(first.token_serial(0, 1, b"example.org").is_none());
+
+        // The limit may not be exceeded.
+        assert!(show(2, b"example.com").is_ok());
+        assert!(show(3, b"example.com").is_err());
+
+        assert_eq!(parse_serial_context(&serial_context(b"scope", 7)), Some((7, &b"scope"[..])));
+        assert_eq!(parse_serial_context(b"poll #1"), None);
+    }
+}
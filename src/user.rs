@@ -18,9 +18,14 @@ use curve25519_dalek::scalar::Scalar;
 use curve25519_dalek::ristretto::RistrettoPoint;
 
 use crate::symmetric;
+use crate::amacs::attributes_from_bytes;
+use crate::amacs::attributes_to_bytes;
 use crate::amacs::Attribute;
+use crate::errors::CredentialError;
 use crate::parameters::SystemParameters;
 use crate::symmetric::Plaintext;
+use crate::wire;
+use crate::wire::WireType;
 
 /// A constructor for creating a request for a new credential.
 pub struct CredentialRequestConstructor {
@@ -50,6 +55,16 @@ impl CredentialRequestConstructor {
         self.attributes.push(Attribute::PublicPoint(point));
     }
 
+    /// Append an expiry, as the last epoch in which the eventual
+    /// `AnonymousCredential` is valid, as an attribute.
+    ///
+    /// Issuers which enforce their own expiries will overwrite this with
+    /// [`Issuer::issue_with_expiry`](crate::issuer::Issuer::issue_with_expiry).
+    // XXX check if we're overflowing the allotted attributes and return Result
+    pub fn append_expiry(&mut self, epoch: u64) {
+        self.attributes.push(Attribute::expiry(epoch));
+    }
+
     /// Append a message to be encoded as revealed plaintext into the
     /// `AnonymousCredential` attributes.
     ///
@@ -76,6 +91,27 @@ impl CredentialRequestConstructor {
         plaintexts
     }
 
+    /// Append an arbitrary-length byte `string`, such as an email address,
+    /// to the eventual `AnonymousCredential` attributes, as with
+    /// [`Attribute::public_string`].
+    ///
+    /// Unlike [`CredentialRequestConstructor::append_plaintext`], the
+    /// string's length is encoded along with it, so that it may later be
+    /// recovered exactly with [`Attribute::string_from_attributes`].
+    ///
+    /// # Returns
+    ///
+    /// The number of attribute slots consumed by the string.
+    // XXX check if we're overflowing the allotted attributes and return Result
+    pub fn append_string(&mut self, string: &[u8]) -> usize {
+        let attributes = Attribute::public_string(string);
+        let slots = attributes.len();
+
+        self.attributes.extend(attributes);
+
+        slots
+    }
+
     /// Append a hidden-at-issuance scalar to the eventual `AnonymousCredential`
     /// attributes.
     ///
@@ -137,3 +173,26 @@ impl CredentialRequestConstructor {
 pub struct CredentialRequest {
     pub(crate) attributes: Vec<Attribute>,
 }
+
+impl CredentialRequest {
+    /// Serialise this request, to send it to the issuer.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        wire::encode(WireType::CredentialRequest, &attributes_to_bytes(&self.attributes))
+    }
+
+    /// Deserialise a request previously serialised with
+    /// [`CredentialRequest::to_bytes`].
+    ///
+    /// # Returns
+    ///
+    /// A `Result` whose `Ok` value is the request, otherwise a
+    /// [`CredentialError`] if the `bytes` are malformed.
+    pub fn from_bytes(bytes: &[u8]) -> Result<CredentialRequest, CredentialError> {
+        let body = match wire::decode(bytes, WireType::CredentialRequest)? {
+            (0, body) | (1, body) => body,
+            _ => return Err(CredentialError::UnsupportedEncoding),
+        };
+
+        Ok(CredentialRequest { attributes: attributes_from_bytes(body)? })
+    }
+}
@@ -0,0 +1,161 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2020 The Brave Authors
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! WebAssembly bindings for the user's side of the protocol.
+//!
+//! These wrap requesting, receiving, and presenting a credential for
+//! JavaScript, e.g. in a browser extension, with every object passed across
+//! the boundary as its [`wire`](crate::wire) encoding in a `Uint8Array`, so
+//! that the serialisation need not be reimplemented.  Errors are thrown as
+//! strings.
+//!
+//! Randomness is taken from the browser's `crypto.getRandomValues()`.
+//!
+//! # Warning
+//!
+//! Since symmetric keypairs cannot yet be serialised, credentials whose
+//! hidden group element attributes would be encrypted cannot be presented
+//! through these bindings.
+
+use std::string::ToString;
+use std::vec::Vec;
+
+use core::fmt::Display;
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use curve25519_dalek::scalar::Scalar;
+
+use rand_core::OsRng;
+
+use wasm_bindgen::prelude::*;
+
+use crate::credential::AnonymousCredential;
+use crate::issuer::CredentialIssuance;
+use crate::parameters::IssuerParameters;
+use crate::parameters::SystemParameters;
+use crate::user::CredentialRequestConstructor;
+
+/// Convert an error into a string to be thrown to JavaScript.
+fn to_js<E: Display>(error: E) -> JsValue {
+    JsValue::from_str(&error.to_string())
+}
+
+/// A builder of a [`CredentialRequest`](crate::user::CredentialRequest), as
+/// a [`CredentialRequestConstructor`].
+#[wasm_bindgen]
+pub struct CredentialRequestBuilder {
+    constructor: CredentialRequestConstructor,
+}
+
+#[wasm_bindgen]
+impl CredentialRequestBuilder {
+    /// Begin a new request under some serialised `system_parameters`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(system_parameters: &[u8]) -> Result<CredentialRequestBuilder, JsValue> {
+        let system_parameters = SystemParameters::from_bytes(system_parameters).map_err(to_js)?;
+
+        Ok(CredentialRequestBuilder { constructor: CredentialRequestConstructor::new(&system_parameters) })
+    }
+
+    /// Append a revealed-at-issuance scalar, given as 32 canonical bytes.
+    pub fn append_revealed_scalar(&mut self, scalar: &[u8]) -> Result<(), JsValue> {
+        let mut bytes = [0u8; 32];
+
+        if scalar.len() != 32 {
+            return Err(JsValue::from_str("A scalar must be 32 bytes"));
+        }
+        bytes.copy_from_slice(scalar);
+
+        match Scalar::from_canonical_bytes(bytes) {
+            Some(scalar) => Ok(self.constructor.append_revealed_scalar(scalar)),
+            None         => Err(JsValue::from_str("Could not decode scalar")),
+        }
+    }
+
+    /// Append a revealed-at-issuance group element, given as 32 compressed
+    /// bytes.
+    pub fn append_revealed_point(&mut self, point: &[u8]) -> Result<(), JsValue> {
+        if point.len() != 32 {
+            return Err(JsValue::from_str("A group element must be 32 bytes"));
+        }
+
+        match CompressedRistretto::from_slice(point).decompress() {
+            Some(point) => Ok(self.constructor.append_revealed_point(point)),
+            None        => Err(JsValue::from_str("Could not decompress group element")),
+        }
+    }
+
+    /// Append an expiry, as the last epoch in which the credential is valid.
+    pub fn append_expiry(&mut self, epoch: u64) {
+        self.constructor.append_expiry(epoch);
+    }
+
+    /// Append a `message` to be hidden upon presentation, returning the
+    /// number of attributes it consumed.
+    pub fn append_plaintext(&mut self, message: &[u8]) -> usize {
+        self.constructor.append_plaintext(&message.to_vec()).len()
+    }
+
+    /// Append a byte `string`, returning the number of attributes it
+    /// consumed.
+    pub fn append_string(&mut self, string: &[u8]) -> usize {
+        self.constructor.append_string(string)
+    }
+
+    /// Finish the request, returning it serialised to send to the issuer.
+    pub fn finish(self) -> Vec<u8> {
+        self.constructor.finish().to_bytes()
+    }
+}
+
+/// Verify a serialised [`CredentialIssuance`] received from the issuer.
+///
+/// # Returns
+///
+/// The serialised [`AnonymousCredential`], otherwise throws if the issuance
+/// is malformed or its proof of issuance does not verify.
+#[wasm_bindgen]
+pub fn verify_issuance(
+    system_parameters: &[u8],
+    issuer_parameters: &[u8],
+    issuance: &[u8],
+) -> Result<Vec<u8>, JsValue>
+{
+    let system_parameters = SystemParameters::from_bytes(system_parameters).map_err(to_js)?;
+    let issuer_parameters = IssuerParameters::from_bytes(issuer_parameters).map_err(to_js)?;
+    let issuance = CredentialIssuance::from_bytes(issuance).map_err(to_js)?;
+    let credential = issuance.verify(&system_parameters, &issuer_parameters).map_err(to_js)?;
+
+    Ok(credential.to_bytes())
+}
+
+/// Present a serialised [`AnonymousCredential`], revealing the attributes
+/// whose entries in the `disclosure_mask` are non-zero and hiding the rest,
+/// as in [`AnonymousCredential::show_disclosing`].
+///
+/// # Returns
+///
+/// The serialised presentation to send to the verifier, otherwise throws.
+#[wasm_bindgen]
+pub fn show(
+    system_parameters: &[u8],
+    issuer_parameters: &[u8],
+    credential: &[u8],
+    disclosure_mask: &[u8],
+) -> Result<Vec<u8>, JsValue>
+{
+    let system_parameters = SystemParameters::from_bytes(system_parameters).map_err(to_js)?;
+    let issuer_parameters = IssuerParameters::from_bytes(issuer_parameters).map_err(to_js)?;
+    let credential = AnonymousCredential::from_bytes(credential).map_err(to_js)?;
+    let disclosure_mask: Vec<bool> = disclosure_mask.iter().map(|disclose| *disclose != 0).collect();
+    let presentation = credential.show_disclosing(&system_parameters, &issuer_parameters, None,
+                                                  &disclosure_mask, OsRng).map_err(to_js)?;
+
+    presentation.to_bytes().map_err(to_js)
+}
@@ -0,0 +1,351 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2020 The Brave Authors
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! The versioned wire format of this crate's serialised types.
+//!
+//! The serialised forms of [`SystemParameters`], [`SecretKey`],
+//! [`AnonymousCredential`], [`ProofOfValidCredential`], [`CredentialRequest`],
+//! [`CredentialIssuance`], [`Tag`], and [`TaggedCredential`] begin with a header of the magic bytes `AFLX`, a
+//! one byte format version, a one byte [`WireType`] tag, and the
+//! little-endian `u32` length of the body which follows.  Deserialisation
+//! rejects the wrong type, an unknown version, or a body of the wrong length,
+//! before the body is parsed.
+//!
+//! The body of a version 1 encoding is exactly the unversioned encoding of
+//! earlier releases, which the `from_bytes` methods still accept as version 0,
+//! except for types introduced since, which have no unversioned encoding.
+//! Credentials and issuances are also excepted: their version 1 bodies end
+//! with the [`SystemParameters::identifier`] they were issued under, which
+//! unversioned encodings lack, and so those are no longer accepted.
+//! (No unversioned encoding can begin with the magic bytes, since as a
+//! little-endian number of attributes they exceed a billion.)  Stored
+//! unversioned encodings may be upgraded in place with [`migrate`].  When a
+//! body format changes, `from_bytes` will dispatch upon the version, so that
+//! encodings of every released version remain readable.
+//!
+//! With the `std` feature, system parameters, secret keys, credentials, and
+//! presentations may also be written to a [`std::io::Write`] with their
+//! `write_to` methods, which stream the body rather than building it in
+//! memory, and read back from a [`std::io::Read`] with their `read_from`
+//! methods, which consume exactly one encoding from the stream.  Only
+//! versioned encodings may be read from a stream.
+//!
+//! [`SystemParameters`]: crate::parameters::SystemParameters
+//! [`SystemParameters::identifier`]: crate::parameters::SystemParameters::identifier
+//! [`SecretKey`]: crate::amacs::SecretKey
+//! [`AnonymousCredential`]: crate::credential::AnonymousCredential
+//! [`ProofOfValidCredential`]: crate::nizk::presentation::ProofOfValidCredential
+//! [`CredentialRequest`]: crate::user::CredentialRequest
+//! [`CredentialIssuance`]: crate::issuer::CredentialIssuance
+//! [`Tag`]: crate::amacs::Tag
+//! [`TaggedCredential`]: crate::amacs::TaggedCredential
+
+#[cfg(all(not(feature = "std"), feature = "alloc"))]
+use alloc::vec::Vec;
+#[cfg(all(not(feature = "alloc"), feature = "std"))]
+use std::vec::Vec;
+
+#[cfg(feature = "std")]
+use std::boxed::Box;
+#[cfg(feature = "std")]
+use std::error::Error;
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "std")]
+use std::io::Read;
+#[cfg(feature = "std")]
+use std::io::Write;
+
+use crate::errors::MacError;
+
+/// The magic bytes at the start of every versioned encoding.
+pub const MAGIC: &[u8; 4] = b"AFLX";
+
+/// The current version of the wire format.
+pub const VERSION: u8 = 1;
+
+/// The length, in bytes, of the header of a versioned encoding: the magic
+/// bytes, the version, the type tag, and the body length.
+pub const HEADER_LENGTH: usize = 4 + 1 + 1 + 4;
+
+/// The type of a serialised object, so that an encoding of one type is never
+/// mistaken for another.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum WireType {
+    SystemParameters = 1,
+    SecretKey = 2,
+    Credential = 3,
+    Presentation = 4,
+    CredentialRequest = 5,
+    CredentialIssuance = 6,
+    Tag = 7,
+    TaggedCredential = 8,
+}
+
+/// A destination for the body of an encoding, so that the same code may
+/// serialise either into a buffer or, with the `std` feature, straight into
+/// a writer.
+pub(crate) trait Sink {
+    /// Append some `bytes` to the body.
+    fn put(&mut self, bytes: &[u8]);
+}
+
+impl Sink for Vec<u8> {
+    fn put(&mut self, bytes: &[u8]) {
+        self.extend_from_slice(bytes);
+    }
+}
+
+/// A [`Sink`] which writes to a `writer`, keeping the first error.
+#[cfg(feature = "std")]
+pub(crate) struct WriteSink<'a, W: Write> {
+    writer: &'a mut W,
+    written: usize,
+    result: io::Result<()>,
+}
+
+#[cfg(feature = "std")]
+impl<'a, W: Write> Sink for WriteSink<'a, W> {
+    fn put(&mut self, bytes: &[u8]) {
+        if self.result.is_ok() {
+            self.result = self.writer.write_all(bytes);
+            self.written += bytes.len();
+        }
+    }
+}
+
+/// Prefix a `body` in the current version's format with its header.
+pub(crate) fn encode(wire_type: WireType, body: &[u8]) -> Vec<u8> {
+    let mut bytes: Vec<u8> = Vec::with_capacity(HEADER_LENGTH + body.len());
+
+    bytes.extend(MAGIC);
+    bytes.push(VERSION);
+    bytes.push(wire_type as u8);
+    bytes.extend(&(body.len() as u32).to_le_bytes());
+    bytes.extend(body);
+    bytes
+}
+
+/// Split an encoding of a `wire_type` into its version and body.
+///
+/// # Returns
+///
+/// A `Result` whose `Ok` value is the version and body, where bytes without
+/// the magic bytes are an unversioned encoding, i.e. version 0, otherwise a
+/// [`MacError::UnsupportedEncoding`] if the header is truncated, the version
+/// is unknown, the type differs, or the body has the wrong length.
+pub(crate) fn decode(bytes: &[u8], wire_type: WireType) -> Result<(u8, &[u8]), MacError> {
+    if !bytes.starts_with(MAGIC) {
+        return Ok((0, bytes));
+    }
+    if bytes.len() < HEADER_LENGTH {
+        return Err(MacError::UnsupportedEncoding);
+    }
+
+    let version = bytes[4];
+    let mut tmp = [0u8; 4];
+
+    tmp.copy_from_slice(&bytes[6..10]);
+
+    if version == 0 || version > VERSION || bytes[5] != wire_type as u8 {
+        return Err(MacError::UnsupportedEncoding);
+    }
+    if bytes.len() - HEADER_LENGTH != u32::from_le_bytes(tmp) as usize {
+        return Err(MacError::UnsupportedEncoding);
+    }
+    Ok((version, &bytes[HEADER_LENGTH..]))
+}
+
+/// Write an encoding of a `wire_type` in the current version's format to a
+/// `writer`, where `body` writes the `length` bytes of the body to a
+/// [`Sink`].
+#[cfg(feature = "std")]
+pub(crate) fn write<W, F>(writer: &mut W, wire_type: WireType, length: usize, body: F) -> io::Result<()>
+where
+    W: Write,
+    F: FnOnce(&mut WriteSink<W>),
+{
+    writer.write_all(MAGIC)?;
+    writer.write_all(&[VERSION, wire_type as u8])?;
+    writer.write_all(&(length as u32).to_le_bytes())?;
+
+    let mut sink = WriteSink { writer, written: 0, result: Ok(()) };
+
+    body(&mut sink);
+
+    debug_assert!(sink.result.is_err() || sink.written == length);
+
+    sink.result
+}
+
+/// Read one encoding of a `wire_type` from a `reader`, leaving anything after
+/// it unread.
+///
+/// # Returns
+///
+/// A `Result` whose `Ok` value is the whole encoding, including its header,
+/// otherwise an `io::Error` of kind `InvalidData` if the header is not that
+/// of a versioned encoding of the `wire_type`, or of kind `UnexpectedEof` if
+/// the stream ends early.
+#[cfg(feature = "std")]
+pub(crate) fn read<R: Read>(reader: &mut R, wire_type: WireType) -> io::Result<Vec<u8>> {
+    let mut bytes: Vec<u8> = vec![0u8; HEADER_LENGTH];
+
+    reader.read_exact(&mut bytes)?;
+
+    if !bytes.starts_with(MAGIC) || bytes[4] == 0 || bytes[4] > VERSION || bytes[5] != wire_type as u8 {
+        return Err(invalid_data(MacError::UnsupportedEncoding));
+    }
+
+    let mut tmp = [0u8; 4];
+
+    tmp.copy_from_slice(&bytes[6..10]);
+
+    // The body is read incrementally, so a bogus length cannot make us
+    // allocate more than the stream actually contains.
+    let length = u32::from_le_bytes(tmp) as usize;
+
+    reader.take(length as u64).read_to_end(&mut bytes)?;
+
+    if bytes.len() != HEADER_LENGTH + length {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(bytes)
+}
+
+/// Wrap an `error` in deserialising something read from a stream as an
+/// `io::Error`.
+#[cfg(feature = "std")]
+pub(crate) fn invalid_data<E>(error: E) -> io::Error
+where
+    E: Into<Box<dyn Error + Send + Sync>>,
+{
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+/// Upgrade an encoding of a `wire_type`, of any supported version including
+/// the unversioned encoding, to the current version.
+///
+/// Unversioned credentials and issuances cannot be upgraded, since they
+/// don't record the parameters they were issued under.
+///
+/// Only the header is checked, so the body must still be validated by
+/// deserialising it.
+///
+/// # Returns
+///
+/// A `Result` whose `Ok` value is the upgraded encoding, otherwise a
+/// [`MacError::UnsupportedEncoding`] as for deserialisation.
+pub fn migrate(bytes: &[u8], wire_type: WireType) -> Result<Vec<u8>, MacError> {
+    match decode(bytes, wire_type)? {
+        (0, _) if matches!(wire_type, WireType::Credential | WireType::CredentialIssuance) => {
+            Err(MacError::UnsupportedEncoding)
+        },
+        (0, body) | (1, body) => Ok(encode(wire_type, body)),
+        _ => Err(MacError::UnsupportedEncoding),
+    }
+}
+
+/// Derive malformed encodings of a `wire_type` from a valid `body`, for
+/// checking that deserialisation rejects them without panicking.
+///
+/// These are every truncation of the body, both with and without a header,
+/// the body with its leading little-endian `u32` replaced by huge values,
+/// and copies of it with random bytes overwritten by the `csprng`.
+#[cfg(test)]
+pub(crate) fn malformations<R: rand_core::RngCore>(
+    wire_type: WireType,
+    body: &[u8],
+    csprng: &mut R,
+) -> Vec<Vec<u8>> {
+    let mut malformations: Vec<Vec<u8>> = Vec::new();
+
+    for length in 0..body.len() {
+        malformations.push(body[..length].to_vec());
+        malformations.push(encode(wire_type, &body[..length]));
+    }
+    if body.len() >= 4 {
+        for count in [u16::MAX as u32 + 1, u32::MAX - 4, u32::MAX].iter() {
+            let mut bytes = body.to_vec();
+
+            bytes[..4].copy_from_slice(&count.to_le_bytes());
+            malformations.push(encode(wire_type, &bytes));
+            malformations.push(bytes);
+        }
+    }
+    for _ in 0..64 {
+        let mut bytes = body.to_vec();
+
+        for _ in 0..1 + csprng.next_u32() % 8 {
+            let index = csprng.next_u32() as usize % bytes.len().max(1);
+
+            if let Some(byte) = bytes.get_mut(index) {
+                *byte = csprng.next_u32() as u8;
+            }
+        }
+        malformations.push(encode(wire_type, &bytes));
+        malformations.push(bytes);
+    }
+    malformations
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn header_roundtrip() {
+        let body = [1u8, 2, 3];
+        let bytes = encode(WireType::Credential, &body);
+
+        assert_eq!(&bytes[..6], b"AFLX\x01\x03");
+        assert_eq!(decode(&bytes, WireType::Credential).unwrap(), (1, &body[..]));
+        assert_eq!(decode(&body, WireType::Credential).unwrap(), (0, &body[..]));
+        assert_eq!(migrate(&bytes, WireType::Credential).unwrap(), bytes);
+        assert_eq!(migrate(&body, WireType::SystemParameters).unwrap(), encode(WireType::SystemParameters, &body));
+
+        // Unversioned credentials lack the identifier of their parameters.
+        assert_eq!(migrate(&body, WireType::Credential).unwrap_err(), MacError::UnsupportedEncoding);
+
+        // The wrong type, an unknown version, or the wrong length are rejected.
+        let mut future = bytes.clone();
+
+        future[4] = VERSION + 1;
+
+        assert_eq!(decode(&bytes, WireType::Presentation).unwrap_err(), MacError::UnsupportedEncoding);
+        assert_eq!(decode(&future, WireType::Credential).unwrap_err(), MacError::UnsupportedEncoding);
+        assert_eq!(decode(&bytes[..bytes.len() - 1], WireType::Credential).unwrap_err(),
+                   MacError::UnsupportedEncoding);
+        assert_eq!(decode(&bytes[..7], WireType::Credential).unwrap_err(), MacError::UnsupportedEncoding);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn stream_roundtrip() {
+        let body = [1u8, 2, 3];
+        let mut stream: Vec<u8> = Vec::new();
+
+        write(&mut stream, WireType::Credential, body.len(), |sink| sink.put(&body)).unwrap();
+        write(&mut stream, WireType::Tag, 1, |sink| sink.put(&[4])).unwrap();
+
+        assert_eq!(&stream[..HEADER_LENGTH + 3], &encode(WireType::Credential, &body)[..]);
+
+        // Each encoding is read in turn, leaving the rest of the stream.
+        let mut reader = &stream[..];
+
+        assert_eq!(read(&mut reader, WireType::Credential).unwrap(), encode(WireType::Credential, &body));
+        assert_eq!(read(&mut reader, WireType::Credential).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        assert_eq!(reader.len(), 1);
+
+        // Unversioned and truncated encodings cannot be read.
+        assert_eq!(read(&mut &body[..], WireType::Credential).unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+        assert_eq!(read(&mut &stream[..HEADER_LENGTH + 2], WireType::Credential).unwrap_err().kind(),
+                   io::ErrorKind::UnexpectedEof);
+    }
+}
@@ -0,0 +1,21 @@
+// -*- mode: rust; -*-
+//
+// This file is part of aeonflux.
+// Copyright (c) 2020 The Brave Authors
+// See LICENSE for licensing information.
+//
+// Authors:
+// - isis agora lovecruft <isis@patternsinthevoid.net>
+
+//! Compile-time checks for the `attribute_schema!` macro.
+
+extern crate trybuild;
+
+#[test]
+fn attribute_schema() {
+    let t = trybuild::TestCases::new();
+
+    t.pass("tests/ui/schema-roundtrip.rs");
+    t.pass("tests/ui/schema-hygiene.rs");
+    t.compile_fail("tests/ui/schema-unknown-kind.rs");
+}
@@ -0,0 +1,23 @@
+#![no_implicit_prelude]
+
+extern crate aeonflux;
+extern crate std;
+
+// Items in the invoking crate which shadow those the macro expands to.
+#[allow(dead_code)]
+struct Vec;
+#[allow(dead_code)]
+type Result = ();
+
+aeonflux::attribute_schema! {
+    pub struct Profile {
+        age: public_scalar,
+        email: either_point,
+    }
+}
+
+fn main() {
+    let schema = Profile::schema();
+
+    std::assert_eq!(schema.0[1], aeonflux::amacs::AttributeKind::EitherPoint);
+}
@@ -0,0 +1,35 @@
+#[macro_use]
+extern crate aeonflux;
+extern crate curve25519_dalek;
+extern crate rand;
+
+use aeonflux::errors::MacError;
+use aeonflux::parameters::SystemParameters;
+use aeonflux::symmetric::Plaintext;
+
+use curve25519_dalek::scalar::Scalar;
+
+attribute_schema! {
+    pub struct Profile {
+        age: public_scalar,
+        avatar: secret_point,
+    }
+}
+
+fn main() {
+    let mut rng = rand::thread_rng();
+    let profile = Profile { age: Scalar::from(42u64), avatar: Plaintext::from(b"This is a tsunami alert test..") };
+
+    // A schema with the wrong number of fields for the parameters fails gracefully.
+    let wrong = SystemParameters::generate(&mut rng, 3).unwrap();
+
+    assert_eq!(profile.to_attributes(&wrong).unwrap_err(), MacError::MessageLengthError{ length: 3 });
+
+    // And a correct one round-trips.
+    let right = SystemParameters::generate(&mut rng, Profile::NUMBER_OF_ATTRIBUTES).unwrap();
+    let attributes = profile.to_attributes(&right).unwrap();
+    let parsed = Profile::from_attributes(&attributes).unwrap();
+
+    assert_eq!(parsed.age, profile.age);
+    assert_eq!(parsed.avatar, profile.avatar);
+}
@@ -0,0 +1,10 @@
+#[macro_use]
+extern crate aeonflux;
+
+attribute_schema! {
+    pub struct Profile {
+        age: public_integer,
+    }
+}
+
+fn main() {}